@@ -275,6 +275,19 @@ const supportedRtpCapabilities: RtpCapabilities =
 				{ type: 'goog-remb' },
 				{ type: 'transport-cc' }
 			]
+		},
+		{
+			kind         : 'video',
+			mimeType     : 'video/AV1',
+			clockRate    : 90000,
+			rtcpFeedback :
+			[
+				{ type: 'nack' },
+				{ type: 'nack', parameter: 'pli' },
+				{ type: 'ccm', parameter: 'fir' },
+				{ type: 'goog-remb' },
+				{ type: 'transport-cc' }
+			]
 		}
 	],
 	headerExtensions :
@@ -13,6 +13,7 @@ use std::fmt;
 use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use thiserror::Error;
 
 /// Container for arbitrary data attached to mediasoup entities.
 #[derive(Debug, Clone)]
@@ -741,7 +742,7 @@ impl<'de> Deserialize<'de> for DtlsFingerprint {
 }
 
 impl DtlsFingerprint {
-    fn value_string(&self) -> String {
+    pub(crate) fn value_string(&self) -> String {
         match self {
             DtlsFingerprint::Sha1 { value } => {
                 format!(
@@ -979,7 +980,7 @@ impl DtlsFingerprint {
         }
     }
 
-    fn algorithm_str(&self) -> &'static str {
+    pub(crate) fn algorithm_str(&self) -> &'static str {
         match self {
             DtlsFingerprint::Sha1 { .. } => "sha-1",
             DtlsFingerprint::Sha224 { .. } => "sha-224",
@@ -1173,6 +1174,34 @@ impl RtpPacketTraceInfo {
     }
 }
 
+/// Error that happened while decoding trace event received from the worker.
+///
+/// Malformed trace notifications are reported through `on_trace_error` callbacks of the entity
+/// that has trace events enabled instead of regular trace callbacks.
+#[derive(Debug, Clone, Error, Eq, PartialEq)]
+pub enum TraceEventError {
+    /// Notification body is missing or is not a trace notification.
+    #[error("Notification body is not a trace notification")]
+    UnexpectedBody,
+    /// Trace notification could not be decoded.
+    #[error("Failed to decode trace notification: {0}")]
+    Decode(String),
+    /// Trace event info is missing or doesn't correspond to the event type.
+    #[error("Missing or unexpected info for trace event of type {event_type}")]
+    UnexpectedInfo {
+        /// Type of the trace event.
+        event_type: String,
+    },
+}
+
+impl TraceEventError {
+    pub(crate) fn unexpected_info(event_type: impl fmt::Debug) -> Self {
+        Self::UnexpectedInfo {
+            event_type: format!("{event_type:?}"),
+        }
+    }
+}
+
 /// SSRC info in trace event.
 #[derive(Debug, Copy, Clone, Deserialize, Serialize)]
 pub struct SsrcTraceInfo {
@@ -1203,23 +1232,24 @@ impl BweType {
 /// BWE info in trace event.
 #[derive(Debug, Copy, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct BweTraceInfo {
     /// Bandwidth estimation type.
-    r#type: BweType,
+    pub r#type: BweType,
     /// Desired bitrate
-    desired_bitrate: u32,
+    pub desired_bitrate: u32,
     /// Effective desired bitrate.
-    effective_desired_bitrate: u32,
+    pub effective_desired_bitrate: u32,
     /// Min bitrate.
-    min_bitrate: u32,
+    pub min_bitrate: u32,
     /// Max bitrate.
-    max_bitrate: u32,
+    pub max_bitrate: u32,
     /// Start bitrate.
-    start_bitrate: u32,
+    pub start_bitrate: u32,
     /// Max padding bitrate.
-    max_padding_bitrate: u32,
+    pub max_padding_bitrate: u32,
     /// Available bitrate.
-    available_bitrate: u32,
+    pub available_bitrate: u32,
 }
 
 impl BweTraceInfo {
@@ -0,0 +1,181 @@
+//! Stream-based alternative to `on_*` callbacks of entities.
+//!
+//! `events()` methods of [`Worker`](crate::worker::Worker), [`Router`](crate::router::Router),
+//! [`WebRtcTransport`](crate::webrtc_transport::WebRtcTransport),
+//! [`Producer`](crate::producer::Producer), [`Consumer`](crate::consumer::Consumer) and
+//! [`DataConsumer`](crate::data_consumer::DataConsumer) return [`EventStream`] of entity-specific
+//! event enum, which is more convenient to consume from async code than callbacks.
+//!
+//! Each stream buffers up to [`EVENT_STREAM_CAPACITY`] events. If the stream is not polled fast
+//! enough, the oldest buffered events are dropped and the next item yielded by the stream is
+//! [`EventsLagged`] with the number of dropped events. The stream ends after the event that
+//! corresponds to entity closing.
+
+#[cfg(test)]
+mod tests;
+
+use event_listener_primitives::HandlerId;
+use futures_lite::Stream;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use thiserror::Error;
+
+/// Maximum number of events buffered by [`EventStream`] before the oldest events are dropped.
+pub const EVENT_STREAM_CAPACITY: usize = 256;
+
+/// Events were dropped because [`EventStream`] was not polled fast enough.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+#[error("Event stream lagged behind, {missed} events were dropped")]
+pub struct EventsLagged {
+    /// Number of dropped events.
+    pub missed: u64,
+}
+
+struct State<E> {
+    capacity: usize,
+    buffer: VecDeque<E>,
+    missed: u64,
+    ended: bool,
+    waker: Option<Waker>,
+}
+
+/// Sending side of [`EventStream`], used by entity callbacks.
+pub(crate) struct EventSender<E> {
+    state: Arc<Mutex<State<E>>>,
+}
+
+impl<E> Clone for EventSender<E> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<E: Send + 'static> EventSender<E> {
+    pub(crate) fn send(&self, event: E) {
+        let waker = {
+            let mut state = self.state.lock();
+            if state.ended {
+                return;
+            }
+            if state.buffer.len() == state.capacity {
+                state.buffer.pop_front();
+                state.missed += 1;
+            }
+            state.buffer.push_back(event);
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Send the last event, stream ends once it is consumed.
+    pub(crate) fn send_last(&self, event: E) {
+        self.send(event);
+
+        let waker = {
+            let mut state = self.state.lock();
+            state.ended = true;
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Callback for `on_*` methods that sends the same event every time.
+    pub(crate) fn emitter(&self, event: E) -> impl Fn() + Send + Sync + 'static
+    where
+        E: Clone + Sync,
+    {
+        let sender = self.clone();
+
+        move || sender.send(event.clone())
+    }
+
+    /// Callback for `on_*close` methods that sends the last event.
+    pub(crate) fn last_emitter(&self, event: E) -> impl FnOnce() + Send + 'static {
+        let sender = self.clone();
+
+        move || sender.send_last(event)
+    }
+}
+
+/// Stream of entity events, see [module-level documentation](self) for details.
+///
+/// Callbacks that feed the stream are removed when the stream is dropped.
+#[must_use = "Stream does nothing unless polled"]
+pub struct EventStream<E> {
+    state: Arc<Mutex<State<E>>>,
+    _handlers: Vec<HandlerId>,
+}
+
+impl<E> fmt::Debug for EventStream<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("EventStream")
+            .field("buffered", &state.buffer.len())
+            .field("missed", &state.missed)
+            .field("ended", &state.ended)
+            .finish()
+    }
+}
+
+impl<E: Send + 'static> EventStream<E> {
+    /// Create stream, `register` is expected to register callbacks that feed events into the
+    /// stream.
+    pub(crate) fn new<R>(capacity: usize, register: R) -> Self
+    where
+        R: FnOnce(&EventSender<E>) -> Vec<HandlerId>,
+    {
+        let state = Arc::new(Mutex::new(State {
+            capacity,
+            buffer: VecDeque::new(),
+            missed: 0,
+            ended: false,
+            waker: None,
+        }));
+
+        let handlers = register(&EventSender {
+            state: Arc::clone(&state),
+        });
+
+        Self {
+            state,
+            _handlers: handlers,
+        }
+    }
+}
+
+impl<E> Stream for EventStream<E> {
+    type Item = Result<E, EventsLagged>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.lock();
+
+        if state.missed > 0 {
+            let missed = std::mem::take(&mut state.missed);
+            return Poll::Ready(Some(Err(EventsLagged { missed })));
+        }
+
+        if let Some(event) = state.buffer.pop_front() {
+            return Poll::Ready(Some(Ok(event)));
+        }
+
+        if state.ended {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,49 @@
+use super::*;
+use futures_lite::{future, StreamExt};
+
+#[test]
+fn stream_buffers_and_ends() {
+    let mut sender = None;
+    let mut stream = EventStream::<u32>::new(4, |s| {
+        sender.replace(s.clone());
+        vec![]
+    });
+    let sender = sender.unwrap();
+
+    sender.send(1);
+    sender.emitter(2)();
+    sender.last_emitter(3)();
+    // Ignored after the last event
+    sender.send(4);
+
+    future::block_on(async move {
+        assert_eq!(stream.next().await, Some(Ok(1)));
+        assert_eq!(stream.next().await, Some(Ok(2)));
+        assert_eq!(stream.next().await, Some(Ok(3)));
+        assert_eq!(stream.next().await, None);
+    });
+}
+
+#[test]
+fn stream_reports_lag() {
+    let mut sender = None;
+    let mut stream = EventStream::<u32>::new(2, |s| {
+        sender.replace(s.clone());
+        vec![]
+    });
+    let sender = sender.unwrap();
+
+    for event in 0..5 {
+        sender.send(event);
+    }
+
+    future::block_on(async move {
+        assert_eq!(stream.next().await, Some(Err(EventsLagged { missed: 3 })));
+        assert_eq!(stream.next().await, Some(Ok(3)));
+        assert_eq!(stream.next().await, Some(Ok(4)));
+
+        // Wakes up pending stream
+        std::thread::spawn(move || sender.send(5));
+        assert_eq!(stream.next().await, Some(Ok(5)));
+    });
+}
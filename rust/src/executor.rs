@@ -0,0 +1,54 @@
+//! Abstraction over async runtime that drives internal background tasks.
+//!
+//! By default [`WorkerManager::new()`](crate::worker_manager::WorkerManager::new) creates a
+//! dedicated thread with [`async_executor::Executor`] running on it. Applications that already
+//! have a runtime can pass it to
+//! [`WorkerManager::with_executor()`](crate::worker_manager::WorkerManager::with_executor) instead,
+//! so that tasks spawned by workers, routers, transports, producers, consumers, etc. run on it.
+//! Blocking work that lasts for the whole lifetime of a worker (running the worker itself or doing
+//! I/O with worker process) goes through [`Executor::spawn_blocking()`].
+//!
+//! [`Executor`] is implemented for [`async_executor::Executor`] and, with `tokio` feature enabled,
+//! for [`tokio::runtime::Handle`], both with the default [`Executor::spawn_blocking()`] since a
+//! blocking pool thread would be held for the whole lifetime of the worker anyway.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::{io, thread};
+
+/// Runtime that spawns background tasks on behalf of mediasoup.
+///
+/// Tasks are fire-and-forget: mediasoup never awaits or cancels them, they only need to be polled
+/// to completion eventually.
+pub trait Executor: Send + Sync + 'static {
+    /// Spawn a future that runs in the background.
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send + 'static>>);
+
+    /// Spawn a closure that blocks for as long as the worker is alive (running worker itself or
+    /// doing I/O with worker process), `name` describes the task and can be used as thread name.
+    ///
+    /// By default closure runs on a new dedicated thread.
+    fn spawn_blocking(
+        &self,
+        name: String,
+        f: Box<dyn FnOnce() + Send + 'static>,
+    ) -> io::Result<()> {
+        thread::Builder::new().name(name).spawn(f)?;
+
+        Ok(())
+    }
+}
+
+impl Executor for async_executor::Executor<'static> {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send + 'static>>) {
+        async_executor::Executor::spawn(self, future).detach();
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Executor for tokio::runtime::Handle {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send + 'static>>) {
+        // Dropping `JoinHandle` detaches the task
+        drop(tokio::runtime::Handle::spawn(self, future));
+    }
+}
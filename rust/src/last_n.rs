@@ -0,0 +1,573 @@
+//! Last-N video forwarding policy driven by speaker activity.
+//!
+//! [`LastN`] tracks speaking activity reported by
+//! [`ActiveSpeakerObserver`] (dominant speaker) and [`AudioLevelObserver`] (volumes), and for
+//! every subscriber forwards video of the N most recent speakers with high layers and priority,
+//! video of the next few speakers as thumbnails and pauses the rest, see [`VideoTier`].
+//!
+//! Speakers are identified by their audio producers, their video producers are linked with
+//! [`LastN::add_video()`] and consumers of those video producers are managed once added with
+//! [`LastN::add_consumer()`].
+//!
+//! To avoid flapping, a speaker that got into the last N of a subscriber stays there for at least
+//! [`LastNOptions::hold`], even if others speak in the meantime.
+//!
+//! # Example
+//! ```no_run
+//! # use mediasoup::active_speaker_observer::ActiveSpeakerObserver;
+//! # use mediasoup::consumer::Consumer;
+//! # use mediasoup::last_n::{LastN, LastNOptions};
+//! # use mediasoup::producer::Producer;
+//! # fn f(
+//! #     observer: ActiveSpeakerObserver,
+//! #     audio_producer: Producer,
+//! #     video_producer: Producer,
+//! #     video_consumer: Consumer,
+//! #     subscriber_audio_producer: Producer,
+//! # ) {
+//! let last_n = LastN::new(LastNOptions::new(4));
+//! last_n.observe_active_speaker_observer(&observer).detach();
+//!
+//! last_n.add_video(&audio_producer, &video_producer);
+//! // Subscriber's own speaker is never counted among the last N
+//! last_n.add_consumer(&video_consumer, Some(subscriber_audio_producer.id()));
+//! # }
+//! ```
+//!
+//! # Notes on usage
+//! Consumers are only paused and resumed by the policy itself: consumers that are paused by the
+//! application are not resumed, but the policy may pause consumers that the application resumed.
+
+#[cfg(test)]
+mod tests;
+
+use crate::active_speaker_observer::ActiveSpeakerObserver;
+use crate::audio_level_observer::{AudioLevelObserver, AudioLevelObserverVolume};
+use crate::consumer::{Consumer, ConsumerId, ConsumerLayers, WeakConsumer};
+use crate::producer::{Producer, ProducerId};
+use crate::rtp_parameters::MediaKind;
+use async_lock::Mutex as AsyncMutex;
+use event_listener_primitives::HandlerId;
+use hash_hasher::HashedMap;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How video of a speaker is forwarded to a subscriber.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VideoTier {
+    /// One of the last N speakers, forwarded with [`LastNOptions::high_layers`].
+    High,
+    /// One of the next [`LastNOptions::thumbnails`] speakers, forwarded with
+    /// [`LastNOptions::thumbnail_layers`].
+    Thumbnail,
+    /// Consumer is paused.
+    Off,
+}
+
+/// [`LastN`] options.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LastNOptions {
+    /// Number of the most recent speakers forwarded with high layers.
+    pub last_n: usize,
+    /// Number of speakers after the last N that are forwarded as thumbnails, others are paused.
+    ///
+    /// Default 0.
+    pub thumbnails: usize,
+    /// Preferred layers of the last N speakers.
+    ///
+    /// Default is spatial layer 2 (the highest layer of typical 3 simulcast streams) and the
+    /// highest temporal layer.
+    pub high_layers: ConsumerLayers,
+    /// Preferred layers of thumbnails.
+    ///
+    /// Default is the lowest spatial layer and the highest temporal layer.
+    pub thumbnail_layers: ConsumerLayers,
+    /// Priority of the last N speakers (see [`Consumer::set_priority()`]).
+    ///
+    /// Default 10.
+    pub high_priority: u8,
+    /// Priority of thumbnails.
+    ///
+    /// Default 1.
+    pub thumbnail_priority: u8,
+    /// Minimum time a speaker stays among the last N before less recent speakers can replace it.
+    ///
+    /// Default 2 seconds.
+    pub hold: Duration,
+}
+
+impl LastNOptions {
+    /// Create options that forward the specified number of the most recent speakers.
+    #[must_use]
+    pub fn new(last_n: usize) -> Self {
+        Self {
+            last_n,
+            thumbnails: 0,
+            high_layers: ConsumerLayers {
+                spatial_layer: 2,
+                temporal_layer: None,
+            },
+            thumbnail_layers: ConsumerLayers {
+                spatial_layer: 0,
+                temporal_layer: None,
+            },
+            high_priority: 10,
+            thumbnail_priority: 1,
+            hold: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Speaking activity, ties in time are broken by volume.
+type Activity = Option<(Instant, i8)>;
+
+struct Speaker {
+    videos: Vec<ProducerId>,
+    activity: Activity,
+    /// Order in which speakers were added, earlier speakers go first until they speak.
+    sequence: u64,
+}
+
+/// Speakers with video ordered from the most recently active, excluding subscriber's own speaker.
+fn rank(speakers: &HashedMap<ProducerId, Speaker>, own: Option<ProducerId>) -> Vec<ProducerId> {
+    let mut ranking = speakers
+        .iter()
+        .filter(|(speaker_id, speaker)| Some(**speaker_id) != own && !speaker.videos.is_empty())
+        .map(|(speaker_id, speaker)| (*speaker_id, speaker.activity, speaker.sequence))
+        .collect::<Vec<_>>();
+    ranking.sort_by_key(|&(_, activity, sequence)| (Reverse(activity), sequence));
+    ranking
+        .into_iter()
+        .map(|(speaker_id, _, _)| speaker_id)
+        .collect()
+}
+
+/// Last N speakers of a subscriber along with the time they were selected at.
+#[derive(Debug, Default)]
+struct Selection {
+    selected: Vec<(ProducerId, Instant)>,
+}
+
+impl Selection {
+    fn contains(&self, speaker_id: &ProducerId) -> bool {
+        self.selected.iter().any(|(id, _)| id == speaker_id)
+    }
+
+    /// Update selection with given ranking, a selected speaker is only replaced by more recently
+    /// active one once it was held for long enough.
+    fn update(
+        &mut self,
+        ranking: &[ProducerId],
+        activity: impl Fn(&ProducerId) -> Activity,
+        last_n: usize,
+        hold: Duration,
+        now: Instant,
+    ) {
+        self.selected
+            .retain(|(speaker_id, _)| ranking.contains(speaker_id));
+        self.selected.truncate(last_n);
+
+        for candidate in ranking {
+            if self.contains(candidate) {
+                continue;
+            }
+            if self.selected.len() < last_n {
+                self.selected.push((*candidate, now));
+                continue;
+            }
+            let Some((index, (weakest, selected_at))) = self
+                .selected
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (speaker_id, _))| activity(speaker_id))
+            else {
+                break;
+            };
+            // Ranking is ordered by activity, so the rest of candidates will not replace anyone
+            // either
+            if activity(candidate) <= activity(weakest)
+                || now.saturating_duration_since(*selected_at) < hold
+            {
+                break;
+            }
+            self.selected[index] = (*candidate, now);
+        }
+    }
+
+    fn tier(
+        &self,
+        ranking: &[ProducerId],
+        speaker_id: &ProducerId,
+        thumbnails: usize,
+    ) -> VideoTier {
+        if self.contains(speaker_id) {
+            return VideoTier::High;
+        }
+        let thumbnail = ranking
+            .iter()
+            .filter(|id| !self.contains(id))
+            .take(thumbnails)
+            .any(|id| id == speaker_id);
+        if thumbnail {
+            VideoTier::Thumbnail
+        } else {
+            VideoTier::Off
+        }
+    }
+}
+
+struct ManagedConsumer {
+    consumer: WeakConsumer,
+    /// Audio producer of the subscriber.
+    subscriber: Option<ProducerId>,
+    /// Desired tier, `None` until producer is linked to a speaker.
+    tier: Option<VideoTier>,
+    /// Tier applied to the consumer, locked while it is being applied.
+    applied: Arc<AsyncMutex<Option<VideoTier>>>,
+}
+
+#[derive(Default)]
+struct State {
+    speakers: HashedMap<ProducerId, Speaker>,
+    /// Speakers by their video producers.
+    video_speakers: HashedMap<ProducerId, ProducerId>,
+    selections: HashMap<Option<ProducerId>, Selection>,
+    consumers: HashedMap<ConsumerId, ManagedConsumer>,
+    next_sequence: u64,
+}
+
+struct Inner {
+    options: LastNOptions,
+    state: Mutex<State>,
+}
+
+impl Inner {
+    /// Recalculate tiers of all consumers and apply those that changed.
+    fn update(self: &Arc<Self>, now: Instant) {
+        let mut changed = Vec::new();
+        {
+            let mut state = self.state.lock();
+            let State {
+                speakers,
+                video_speakers,
+                selections,
+                consumers,
+                ..
+            } = &mut *state;
+
+            for managed in consumers.values() {
+                selections.entry(managed.subscriber).or_default();
+            }
+            selections.retain(|subscriber, _| {
+                consumers
+                    .values()
+                    .any(|managed| managed.subscriber == *subscriber)
+            });
+
+            for (subscriber, selection) in selections.iter_mut() {
+                let ranking = rank(speakers, *subscriber);
+                selection.update(
+                    &ranking,
+                    |speaker_id| {
+                        speakers
+                            .get(speaker_id)
+                            .and_then(|speaker| speaker.activity)
+                    },
+                    self.options.last_n,
+                    self.options.hold,
+                    now,
+                );
+
+                for (consumer_id, managed) in consumers.iter_mut() {
+                    if managed.subscriber != *subscriber {
+                        continue;
+                    }
+                    let Some(consumer) = managed.consumer.upgrade() else {
+                        continue;
+                    };
+                    let tier = video_speakers
+                        .get(&consumer.producer_id())
+                        .map(|speaker_id| {
+                            selection.tier(&ranking, speaker_id, self.options.thumbnails)
+                        });
+                    if tier != managed.tier {
+                        debug!("consumer {} tier changed to {:?}", consumer_id, tier);
+                        managed.tier = tier;
+                        changed.push((consumer, Arc::clone(&managed.applied)));
+                    }
+                }
+            }
+        }
+
+        for (consumer, applied) in changed {
+            let inner_weak = Arc::downgrade(self);
+            let executor = Arc::clone(consumer.executor());
+            executor.spawn(Box::pin(async move {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.apply(&consumer, &applied).await;
+                }
+            }));
+        }
+    }
+
+    /// Bring consumer to its current desired tier.
+    async fn apply(&self, consumer: &Consumer, applied: &AsyncMutex<Option<VideoTier>>) {
+        let mut applied = applied.lock().await;
+        let Some(tier) = self
+            .state
+            .lock()
+            .consumers
+            .get(&consumer.id())
+            .and_then(|managed| managed.tier)
+        else {
+            return;
+        };
+        if *applied == Some(tier) {
+            return;
+        }
+
+        let (layers, priority) = match tier {
+            VideoTier::High => (self.options.high_layers, self.options.high_priority),
+            VideoTier::Thumbnail => (
+                self.options.thumbnail_layers,
+                self.options.thumbnail_priority,
+            ),
+            VideoTier::Off => {
+                if let Err(error) = consumer.pause().await {
+                    warn!("failed to pause consumer {}: {}", consumer.id(), error);
+                    return;
+                }
+                applied.replace(tier);
+                return;
+            }
+        };
+
+        let result = async {
+            consumer.set_preferred_layers(layers).await?;
+            consumer.set_priority(priority).await?;
+            if *applied == Some(VideoTier::Off) {
+                consumer.resume().await?;
+            }
+            Ok::<_, crate::worker::RequestError>(())
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                applied.replace(tier);
+            }
+            Err(error) => {
+                warn!(
+                    "failed to apply {:?} tier to consumer {}: {}",
+                    tier,
+                    consumer.id(),
+                    error
+                );
+            }
+        }
+    }
+
+    fn speaker_activity(self: &Arc<Self>, activities: impl IntoIterator<Item = (ProducerId, i8)>) {
+        let now = Instant::now();
+        {
+            let mut state = self.state.lock();
+            for (speaker_id, volume) in activities {
+                if let Some(speaker) = state.speakers.get_mut(&speaker_id) {
+                    speaker.activity = Some((now, volume));
+                }
+            }
+        }
+        self.update(now);
+    }
+
+    fn remove_producer(self: &Arc<Self>, producer_id: &ProducerId) {
+        {
+            let mut state = self.state.lock();
+            if let Some(speaker) = state.speakers.remove(producer_id) {
+                for video_producer_id in speaker.videos {
+                    state.video_speakers.remove(&video_producer_id);
+                }
+            }
+            if let Some(speaker_id) = state.video_speakers.remove(producer_id) {
+                if let Some(speaker) = state.speakers.get_mut(&speaker_id) {
+                    speaker.videos.retain(|id| id != producer_id);
+                }
+            }
+        }
+        self.update(Instant::now());
+    }
+}
+
+/// Last-N video forwarding policy, see [module-level documentation](self).
+#[derive(Clone)]
+pub struct LastN {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for LastN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("LastN")
+            .field("options", &self.inner.options)
+            .field("speakers", &state.speakers.len())
+            .field("consumers", &state.consumers.len())
+            .finish()
+    }
+}
+
+impl LastN {
+    /// Create a new policy.
+    #[must_use]
+    pub fn new(options: LastNOptions) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                options,
+                state: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Link video producer to the speaker with given audio producer, both are forgotten once
+    /// closed.
+    pub fn add_video(&self, audio_producer: &Producer, video_producer: &Producer) {
+        let speaker_id = audio_producer.id();
+        let video_producer_id = video_producer.id();
+        let new_speaker;
+        let new_video;
+        {
+            let mut state = self.inner.state.lock();
+            let sequence = state.next_sequence;
+            let speaker = state.speakers.entry(speaker_id).or_insert_with(|| Speaker {
+                videos: Vec::new(),
+                activity: None,
+                sequence,
+            });
+            new_speaker = speaker.sequence == sequence;
+            if !speaker.videos.contains(&video_producer_id) {
+                speaker.videos.push(video_producer_id);
+            }
+            if new_speaker {
+                state.next_sequence += 1;
+            }
+            let previous_speaker_id = state.video_speakers.insert(video_producer_id, speaker_id);
+            new_video = previous_speaker_id.is_none();
+            if let Some(previous_speaker_id) = previous_speaker_id.filter(|id| *id != speaker_id) {
+                // Video was linked to another speaker before
+                if let Some(previous_speaker) = state.speakers.get_mut(&previous_speaker_id) {
+                    previous_speaker
+                        .videos
+                        .retain(|id| *id != video_producer_id);
+                }
+            }
+        }
+
+        for (producer, is_new) in [(audio_producer, new_speaker), (video_producer, new_video)] {
+            if !is_new {
+                continue;
+            }
+            let producer_id = producer.id();
+            let inner_weak = Arc::downgrade(&self.inner);
+            producer
+                .on_close(move || {
+                    if let Some(inner) = inner_weak.upgrade() {
+                        inner.remove_producer(&producer_id);
+                    }
+                })
+                .detach();
+        }
+
+        self.inner.update(Instant::now());
+    }
+
+    /// Manage video consumer of a subscriber with given audio producer (`None` for subscribers that
+    /// don't send audio), consumer is forgotten once closed.
+    ///
+    /// Audio consumers are ignored.
+    pub fn add_consumer(
+        &self,
+        consumer: &Consumer,
+        subscriber_audio_producer_id: Option<ProducerId>,
+    ) {
+        if consumer.kind() != MediaKind::Video {
+            return;
+        }
+
+        self.inner.state.lock().consumers.insert(
+            consumer.id(),
+            ManagedConsumer {
+                consumer: consumer.downgrade(),
+                subscriber: subscriber_audio_producer_id,
+                tier: None,
+                applied: Arc::default(),
+            },
+        );
+
+        let consumer_id = consumer.id();
+        let inner_weak = Arc::downgrade(&self.inner);
+        consumer
+            .on_close(move || {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.state.lock().consumers.remove(&consumer_id);
+                }
+            })
+            .detach();
+
+        self.inner.update(Instant::now());
+    }
+
+    /// Current tier of a managed consumer, `None` if consumer is not managed or its producer is
+    /// not linked to a speaker.
+    #[must_use]
+    pub fn tier(&self, consumer_id: &ConsumerId) -> Option<VideoTier> {
+        self.inner
+            .state
+            .lock()
+            .consumers
+            .get(consumer_id)
+            .and_then(|managed| managed.tier)
+    }
+
+    /// Mark speaker with given audio producer as the dominant speaker.
+    pub fn set_dominant_speaker(&self, audio_producer_id: ProducerId) {
+        // Dominant speaker is considered to be the loudest
+        self.inner.speaker_activity([(audio_producer_id, 0)]);
+    }
+
+    /// Mark speakers with given volumes as active.
+    pub fn set_volumes(&self, volumes: &[AudioLevelObserverVolume]) {
+        self.inner.speaker_activity(
+            volumes
+                .iter()
+                .map(|volume| (volume.producer.id(), volume.volume)),
+        );
+    }
+
+    /// Follow dominant speaker changes of given observer.
+    pub fn observe_active_speaker_observer(&self, observer: &ActiveSpeakerObserver) -> HandlerId {
+        let inner_weak = Arc::downgrade(&self.inner);
+        observer.on_dominant_speaker(move |dominant_speaker| {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.speaker_activity([(dominant_speaker.producer.id(), 0)]);
+            }
+        })
+    }
+
+    /// Follow volumes reported by given observer.
+    pub fn observe_audio_level_observer(&self, observer: &AudioLevelObserver) -> HandlerId {
+        let inner_weak = Arc::downgrade(&self.inner);
+        observer.on_volumes(move |volumes| {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.speaker_activity(
+                    volumes
+                        .iter()
+                        .map(|volume| (volume.producer.id(), volume.volume)),
+                );
+            }
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+use uuid::Uuid;
+
+fn producer_id() -> ProducerId {
+    Uuid::new_v4().to_string().parse().unwrap()
+}
+
+fn speakers(count: usize) -> (Vec<ProducerId>, HashedMap<ProducerId, Speaker>) {
+    let ids = (0..count).map(|_| producer_id()).collect::<Vec<_>>();
+    let speakers = ids
+        .iter()
+        .enumerate()
+        .map(|(sequence, id)| {
+            (
+                *id,
+                Speaker {
+                    videos: vec![producer_id()],
+                    activity: None,
+                    sequence: sequence as u64,
+                },
+            )
+        })
+        .collect();
+
+    (ids, speakers)
+}
+
+#[test]
+fn rank_speakers() {
+    let (ids, mut speakers) = speakers(4);
+    let now = Instant::now();
+
+    // Speakers that never spoke are ordered by the time they were added
+    assert_eq!(rank(&speakers, None), ids);
+    assert_eq!(rank(&speakers, Some(ids[0])), ids[1..]);
+
+    speakers.get_mut(&ids[2]).unwrap().activity = Some((now, -40));
+    speakers.get_mut(&ids[3]).unwrap().activity = Some((now, -20));
+    speakers.get_mut(&ids[1]).unwrap().videos.clear();
+    // Louder speaker goes first, speakers without video are not ranked
+    assert_eq!(rank(&speakers, None), vec![ids[3], ids[2], ids[0]]);
+
+    speakers.get_mut(&ids[2]).unwrap().activity = Some((now + Duration::from_secs(1), -60));
+    assert_eq!(rank(&speakers, None), vec![ids[2], ids[3], ids[0]]);
+}
+
+#[test]
+fn selection_hysteresis() {
+    let (ids, mut speakers) = speakers(3);
+    let hold = Duration::from_secs(2);
+    let start = Instant::now();
+    let mut selection = Selection::default();
+
+    let update = |selection: &mut Selection,
+                  speakers: &mut HashedMap<ProducerId, Speaker>,
+                  speaker: usize,
+                  at: Duration| {
+        speakers.get_mut(&ids[speaker]).unwrap().activity = Some((start + at, 0));
+        let ranking = rank(speakers, None);
+        selection.update(
+            &ranking,
+            |id| speakers.get(id).and_then(|speaker| speaker.activity),
+            2,
+            hold,
+            start + at,
+        );
+        ranking
+    };
+
+    update(&mut selection, &mut speakers, 0, Duration::ZERO);
+    assert!(selection.contains(&ids[0]) && selection.contains(&ids[1]));
+
+    // Speaker 1 was selected less than 2 seconds ago
+    let ranking = update(&mut selection, &mut speakers, 2, Duration::from_secs(1));
+    assert!(!selection.contains(&ids[2]));
+    assert_eq!(selection.tier(&ranking, &ids[2], 0), VideoTier::Off);
+    assert_eq!(selection.tier(&ranking, &ids[2], 1), VideoTier::Thumbnail);
+
+    // Speaker 1 is replaced as the least recently active one
+    let ranking = update(&mut selection, &mut speakers, 2, Duration::from_secs(3));
+    assert!(selection.contains(&ids[0]) && selection.contains(&ids[2]));
+    assert_eq!(selection.tier(&ranking, &ids[2], 0), VideoTier::High);
+    assert_eq!(selection.tier(&ranking, &ids[1], 1), VideoTier::Thumbnail);
+
+    // Speaker 0 was selected long ago, but speaker 2 was just selected and is held
+    let ranking = update(&mut selection, &mut speakers, 1, Duration::from_secs(4));
+    assert!(selection.contains(&ids[1]) && selection.contains(&ids[2]));
+    assert_eq!(selection.tier(&ranking, &ids[0], 1), VideoTier::Thumbnail);
+}
@@ -0,0 +1,462 @@
+//! Bandwidth-aware spatial layer allocation across video consumers of a WebRTC transport.
+//!
+//! The worker distributes available outgoing bitrate of a transport between its consumers using
+//! their priorities (see [`Consumer::set_priority()`]). [`LayerAllocator`] moves this decision to
+//! the application: every time bandwidth estimation reports new available bitrate (see
+//! [`WebRtcTransport::on_available_bitrate()`]) it splits the bitrate between video consumers of
+//! the transport according to their importance and the size of the tiles they are rendered in,
+//! and applies the result with [`Consumer::set_preferred_layers()`].
+//!
+//! Bitrate and resolution of each spatial layer are not known in advance, they are taken from
+//! [`LayerAllocatorOptions::layers`] ladder. Consumers with fewer spatial layers use the lowest
+//! entries of the ladder.
+//!
+//! # Example
+//! ```no_run
+//! # use mediasoup::consumer::Consumer;
+//! # use mediasoup::layer_allocator::{LayerAllocator, LayerAllocatorOptions, TileSize};
+//! # use mediasoup::prelude::*;
+//! # use mediasoup::transport::TransportTraceEventType;
+//! # use mediasoup::webrtc_transport::WebRtcTransport;
+//! # async fn f(transport: WebRtcTransport, consumer: Consumer) {
+//! // Bandwidth estimations are only sent by the worker when enabled
+//! transport
+//!     .enable_trace_event(vec![TransportTraceEventType::Bwe])
+//!     .await
+//!     .unwrap();
+//!
+//! let allocator = LayerAllocator::new(&transport, LayerAllocatorOptions::default());
+//! // Video consumers created on the transport after this point are managed automatically
+//! allocator.add_consumer(&consumer);
+//!
+//! // Active speaker is rendered large and is preferred when bitrate is scarce
+//! allocator.set_importance(&consumer.id(), 4.0);
+//! allocator.set_tile_size(
+//!     &consumer.id(),
+//!     Some(TileSize {
+//!         width: 1280,
+//!         height: 720,
+//!     }),
+//! );
+//! # }
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::{Consumer, ConsumerId, ConsumerLayers, WeakConsumer};
+use crate::executor::Executor;
+use crate::rtp_parameters::MediaKind;
+use crate::transport::Transport;
+use crate::webrtc_transport::WebRtcTransport;
+use async_lock::Mutex as AsyncMutex;
+use event_listener_primitives::HandlerId;
+use hash_hasher::HashedMap;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+
+/// Resolution and expected bitrate of a spatial layer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpatialLayerSpec {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Expected bitrate in bps.
+    pub bitrate: u32,
+}
+
+/// [`LayerAllocator`] options.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LayerAllocatorOptions {
+    /// Spatial layers from the lowest to the highest.
+    ///
+    /// Default is 320x180 at 150 kbps, 640x360 at 500 kbps and 1280x720 at 1.5 Mbps, which
+    /// matches typical 3 simulcast streams.
+    pub layers: Vec<SpatialLayerSpec>,
+}
+
+impl Default for LayerAllocatorOptions {
+    fn default() -> Self {
+        Self {
+            layers: vec![
+                SpatialLayerSpec {
+                    width: 320,
+                    height: 180,
+                    bitrate: 150_000,
+                },
+                SpatialLayerSpec {
+                    width: 640,
+                    height: 360,
+                    bitrate: 500_000,
+                },
+                SpatialLayerSpec {
+                    width: 1280,
+                    height: 720,
+                    bitrate: 1_500_000,
+                },
+            ],
+        }
+    }
+}
+
+/// Size of the UI tile consumer is rendered in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TileSize {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+/// What a single consumer asks for.
+#[derive(Debug, Clone, PartialEq)]
+struct Demand {
+    importance: f64,
+    /// Bitrates of spatial layers the consumer may use, from the lowest.
+    bitrates: Vec<u32>,
+}
+
+impl Demand {
+    fn new(
+        ladder: &[SpatialLayerSpec],
+        spatial_layers: u8,
+        importance: f64,
+        tile_size: Option<TileSize>,
+    ) -> Self {
+        let specs = (0..usize::from(spatial_layers.max(1)))
+            .filter_map(|index| ladder.get(index).or_else(|| ladder.last()))
+            .collect::<Vec<_>>();
+        // No point in sending resolution higher than the smallest one covering the tile
+        let max_layer = tile_size
+            .and_then(|tile_size| {
+                specs.iter().position(|spec| {
+                    spec.width >= tile_size.width && spec.height >= tile_size.height
+                })
+            })
+            .unwrap_or(specs.len().saturating_sub(1));
+        let mut bitrates = specs
+            .iter()
+            .take(max_layer + 1)
+            .map(|spec| spec.bitrate)
+            .collect::<Vec<_>>();
+        if bitrates.is_empty() {
+            bitrates.push(0);
+        }
+
+        Self {
+            importance,
+            bitrates,
+        }
+    }
+}
+
+/// Select spatial layer for every demand.
+///
+/// Everyone gets the lowest layer, then remaining bitrate is spent greedily on upgrades with the
+/// best importance per extra bitrate.
+fn allocate(demands: &[Demand], available_bitrate: u32) -> Vec<usize> {
+    let mut selected = vec![0_usize; demands.len()];
+    let mut remaining = u64::from(available_bitrate).saturating_sub(
+        demands
+            .iter()
+            .map(|demand| u64::from(demand.bitrates[0]))
+            .sum(),
+    );
+
+    loop {
+        let best = demands
+            .iter()
+            .enumerate()
+            .filter(|(_, demand)| demand.importance > 0.0)
+            .filter_map(|(index, demand)| {
+                let current = demand.bitrates[selected[index]];
+                let next = *demand.bitrates.get(selected[index] + 1)?;
+                let cost = u64::from(next.saturating_sub(current));
+                let value = demand.importance / cost.max(1) as f64;
+                (cost <= remaining).then_some((index, cost, value))
+            })
+            // On ties the earliest demand wins
+            .reduce(|best, candidate| {
+                if candidate.2 > best.2 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+
+        let Some((index, cost, _)) = best else {
+            break;
+        };
+        selected[index] += 1;
+        remaining -= cost;
+    }
+
+    selected
+}
+
+struct ManagedConsumer {
+    consumer: WeakConsumer,
+    spatial_layers: u8,
+    importance: f64,
+    tile_size: Option<TileSize>,
+}
+
+struct State {
+    consumers: HashedMap<ConsumerId, ManagedConsumer>,
+    available_bitrate: Option<u32>,
+}
+
+struct Inner {
+    options: LayerAllocatorOptions,
+    state: Mutex<State>,
+    /// Serializes application of allocations so that older one doesn't override newer one.
+    apply_lock: AsyncMutex<()>,
+    executor: Arc<dyn Executor>,
+    handlers: Mutex<Vec<HandlerId>>,
+}
+
+impl Inner {
+    fn allocation(&self, available_bitrate: u32) -> Vec<(Consumer, ConsumerLayers)> {
+        let state = self.state.lock();
+        let (consumers, demands): (Vec<_>, Vec<_>) = state
+            .consumers
+            .values()
+            .filter_map(|managed| {
+                let consumer = managed.consumer.upgrade()?;
+                let demand = Demand::new(
+                    &self.options.layers,
+                    managed.spatial_layers,
+                    managed.importance,
+                    managed.tile_size,
+                );
+
+                Some((consumer, demand))
+            })
+            .unzip();
+
+        consumers
+            .into_iter()
+            .zip(allocate(&demands, available_bitrate))
+            .map(|(consumer, spatial_layer)| {
+                let layers = ConsumerLayers {
+                    spatial_layer: u8::try_from(spatial_layer).unwrap_or(u8::MAX),
+                    temporal_layer: None,
+                };
+
+                (consumer, layers)
+            })
+            .collect()
+    }
+
+    /// Allocate last known available bitrate and apply preferred layers that changed.
+    async fn apply(&self) {
+        let _apply_lock = self.apply_lock.lock().await;
+        let Some(available_bitrate) = self.state.lock().available_bitrate else {
+            return;
+        };
+
+        for (consumer, layers) in self.allocation(available_bitrate) {
+            if consumer.preferred_layers() == Some(layers) {
+                continue;
+            }
+            debug!(
+                "setting preferred layers of consumer {} to {:?}",
+                consumer.id(),
+                layers
+            );
+            if let Err(error) = consumer.set_preferred_layers(layers).await {
+                warn!(
+                    "failed to set preferred layers of consumer {}: {}",
+                    consumer.id(),
+                    error
+                );
+            }
+        }
+    }
+
+    fn spawn_apply(self: &Arc<Self>) {
+        let inner_weak = Arc::downgrade(self);
+        self.executor.spawn(Box::pin(async move {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.apply().await;
+            }
+        }));
+    }
+
+    fn add_consumer(self: &Arc<Self>, consumer: &Consumer) {
+        if consumer.kind() != MediaKind::Video {
+            return;
+        }
+
+        let spatial_layers = consumer
+            .rtp_parameters()
+            .encodings
+            .first()
+            .map_or(1, |encoding| {
+                encoding.scalability_mode.spatial_layers().get()
+            });
+        self.state
+            .lock()
+            .consumers
+            .entry(consumer.id())
+            .or_insert_with(|| ManagedConsumer {
+                consumer: consumer.downgrade(),
+                spatial_layers,
+                importance: 1.0,
+                tile_size: None,
+            });
+
+        let consumer_id = consumer.id();
+        let inner_weak = Arc::downgrade(self);
+        consumer
+            .on_close(move || {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.state.lock().consumers.remove(&consumer_id);
+                    inner.spawn_apply();
+                }
+            })
+            .detach();
+
+        self.spawn_apply();
+    }
+}
+
+/// Bandwidth-aware layer allocator, see [module-level documentation](self).
+///
+/// Allocator stops following the transport once the last instance is dropped.
+#[derive(Clone)]
+pub struct LayerAllocator {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for LayerAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("LayerAllocator")
+            .field("options", &self.inner.options)
+            .field("consumers", &state.consumers.len())
+            .field("available_bitrate", &state.available_bitrate)
+            .finish()
+    }
+}
+
+impl LayerAllocator {
+    /// Create a new allocator for video consumers of given transport.
+    ///
+    /// Consumers created on the transport from now on are managed automatically, existing ones
+    /// need to be added with [`LayerAllocator::add_consumer()`].
+    ///
+    /// NOTE: allocation only happens once available bitrate is known, either reported by
+    /// bandwidth estimation (requires [`TransportTraceEventType::Bwe`] trace event to be enabled)
+    /// or set explicitly with [`LayerAllocator::allocate()`].
+    ///
+    /// [`TransportTraceEventType::Bwe`]: crate::transport::TransportTraceEventType::Bwe
+    #[must_use]
+    pub fn new(transport: &WebRtcTransport, options: LayerAllocatorOptions) -> Self {
+        let inner = Arc::new(Inner {
+            options,
+            state: Mutex::new(State {
+                consumers: HashedMap::default(),
+                available_bitrate: transport.available_bitrate(),
+            }),
+            apply_lock: AsyncMutex::new(()),
+            executor: Arc::clone(transport.router().executor()),
+            handlers: Mutex::default(),
+        });
+
+        let available_bitrate_handler = transport.on_available_bitrate({
+            let inner_weak = Arc::downgrade(&inner);
+
+            move |available_bitrate| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner
+                        .state
+                        .lock()
+                        .available_bitrate
+                        .replace(available_bitrate);
+                    inner.spawn_apply();
+                }
+            }
+        });
+        let new_consumer_handler = transport.on_new_consumer(Arc::new({
+            let inner_weak = Arc::downgrade(&inner);
+
+            move |consumer| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.add_consumer(consumer);
+                }
+            }
+        }));
+        inner
+            .handlers
+            .lock()
+            .extend([available_bitrate_handler, new_consumer_handler]);
+
+        Self { inner }
+    }
+
+    /// Manage video consumer, consumer is forgotten once closed.
+    ///
+    /// Audio consumers are ignored.
+    pub fn add_consumer(&self, consumer: &Consumer) {
+        self.inner.add_consumer(consumer);
+    }
+
+    /// Set importance of a managed consumer, higher importance gets higher layers when bitrate is
+    /// scarce. Consumers with importance `0` (or less) always get the lowest layer.
+    ///
+    /// Default 1.
+    pub fn set_importance(&self, consumer_id: &ConsumerId, importance: f64) {
+        if let Some(managed) = self.inner.state.lock().consumers.get_mut(consumer_id) {
+            managed.importance = importance;
+        }
+        self.inner.spawn_apply();
+    }
+
+    /// Set size of the tile a managed consumer is rendered in, consumer never gets layer of higher
+    /// resolution than necessary to cover the tile. `None` means size is unknown and any layer can
+    /// be used.
+    ///
+    /// Default `None`.
+    pub fn set_tile_size(&self, consumer_id: &ConsumerId, tile_size: Option<TileSize>) {
+        if let Some(managed) = self.inner.state.lock().consumers.get_mut(consumer_id) {
+            managed.tile_size = tile_size;
+        }
+        self.inner.spawn_apply();
+    }
+
+    /// Last available bitrate (in bps) allocation was made for.
+    #[must_use]
+    pub fn available_bitrate(&self) -> Option<u32> {
+        self.inner.state.lock().available_bitrate
+    }
+
+    /// Preferred layers managed consumers would get with given available bitrate (in bps), nothing
+    /// is applied.
+    #[must_use]
+    pub fn allocation(&self, available_bitrate: u32) -> HashedMap<ConsumerId, ConsumerLayers> {
+        self.inner
+            .allocation(available_bitrate)
+            .into_iter()
+            .map(|(consumer, layers)| (consumer.id(), layers))
+            .collect()
+    }
+
+    /// Allocate given available bitrate (in bps) and apply resulting preferred layers to managed
+    /// consumers.
+    ///
+    /// Available bitrate is remembered and used for following allocations until bandwidth
+    /// estimation reports a new one.
+    pub async fn allocate(&self, available_bitrate: u32) {
+        self.inner
+            .state
+            .lock()
+            .available_bitrate
+            .replace(available_bitrate);
+        self.inner.apply().await;
+    }
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+fn ladder() -> Vec<SpatialLayerSpec> {
+    LayerAllocatorOptions::default().layers
+}
+
+#[test]
+fn demand_follows_tile_size() {
+    let ladder = ladder();
+
+    assert_eq!(
+        Demand::new(&ladder, 3, 1.0, None).bitrates,
+        vec![150_000, 500_000, 1_500_000],
+    );
+    // Smallest layer covering the tile is the highest one used
+    assert_eq!(
+        Demand::new(
+            &ladder,
+            3,
+            1.0,
+            Some(TileSize {
+                width: 400,
+                height: 200,
+            }),
+        )
+        .bitrates,
+        vec![150_000, 500_000],
+    );
+    // Tile larger than any layer can use all of them
+    assert_eq!(
+        Demand::new(
+            &ladder,
+            3,
+            1.0,
+            Some(TileSize {
+                width: 1920,
+                height: 1080,
+            }),
+        )
+        .bitrates,
+        vec![150_000, 500_000, 1_500_000],
+    );
+    // Consumers with fewer layers use the lowest entries of the ladder
+    assert_eq!(
+        Demand::new(&ladder, 2, 1.0, None).bitrates,
+        vec![150_000, 500_000],
+    );
+    assert_eq!(Demand::new(&[], 3, 1.0, None).bitrates, vec![0]);
+}
+
+#[test]
+fn allocate_by_importance() {
+    let ladder = ladder();
+    let demands = [
+        Demand::new(&ladder, 3, 1.0, None),
+        Demand::new(&ladder, 3, 4.0, None),
+        Demand::new(&ladder, 3, 1.0, None),
+    ];
+
+    // Not enough bitrate for anything but the lowest layers
+    assert_eq!(allocate(&demands, 0), vec![0, 0, 0]);
+    assert_eq!(allocate(&demands, 500_000), vec![0, 0, 0]);
+    // The most important consumer is upgraded first
+    assert_eq!(allocate(&demands, 800_000), vec![0, 1, 0]);
+    assert_eq!(allocate(&demands, 1_850_000), vec![0, 2, 0]);
+    // Cheaper upgrades of others go before expensive upgrade of the most important one
+    assert_eq!(allocate(&demands, 1_550_000), vec![1, 1, 1]);
+    assert_eq!(allocate(&demands, 5_000_000), vec![2, 2, 2]);
+}
+
+#[test]
+fn allocate_respects_caps() {
+    let ladder = ladder();
+    let demands = [
+        Demand::new(
+            &ladder,
+            3,
+            10.0,
+            Some(TileSize {
+                width: 320,
+                height: 180,
+            }),
+        ),
+        Demand::new(&ladder, 1, 10.0, None),
+        Demand::new(&ladder, 3, 0.0, None),
+        Demand::new(&ladder, 3, 1.0, None),
+    ];
+
+    // Bitrate not used by capped and unimportant consumers goes to the rest
+    assert_eq!(allocate(&demands, 3_000_000), vec![0, 0, 0, 2]);
+    assert!(allocate(&[], 3_000_000).is_empty());
+}
@@ -49,18 +49,32 @@
 //! and are a good place to start until we have demo apps built in Rust).
 
 pub mod data_structures;
+pub mod events;
+pub mod executor;
+pub mod last_n;
+pub mod layer_allocator;
 mod macros;
 mod messages;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod negotiation;
 #[doc(hidden)]
 pub mod ortc;
+pub mod playback;
 pub mod prelude;
+pub mod recorder;
+pub mod room;
 pub mod router;
+pub mod rtp;
 pub mod rtp_parameters;
 pub mod scalability_modes;
 pub mod sctp_parameters;
+pub mod sdp;
 pub mod srtp_parameters;
+pub mod supervisor;
 pub mod supported_rtp_capabilities;
 pub mod webrtc_server;
+pub mod whip;
 pub mod worker;
 pub mod worker_manager;
 
@@ -179,6 +193,53 @@ pub mod pipe_transport {
     pub use crate::router::pipe_transport::*;
 }
 
+pub mod remote_pipe {
+    //! Piping of producers into routers on other hosts.
+    //!
+    //! In contrast to [`Router::pipe_producer_to_router()`](router::Router::pipe_producer_to_router)
+    //! both routers are not available in the same process, so pipe transports are connected using
+    //! an offer/answer exchange and events of the piped producer are propagated as
+    //! [`RemotePipeMessage`]s, both over signaling channel of application's choice.
+    //!
+    //! # Example
+    //! ```no_run
+    //! use mediasoup::prelude::*;
+    //! use mediasoup::remote_pipe::{RemotePipeMessage, RemotePipeOptions};
+    //!
+    //! # async fn f(
+    //! #     router1: Router,
+    //! #     router2: Router,
+    //! #     producer: Producer,
+    //! # ) -> Result<(), Box<dyn std::error::Error>> {
+    //! // On the host with producer
+    //! let pending_pipe = router1
+    //!     .create_remote_pipe_offer(producer.id(), RemotePipeOptions::default())
+    //!     .await?;
+    //! // Send `pending_pipe.offer()` to the other host, there
+    //! let (answer, pipe_producer) = router2
+    //!     .accept_remote_pipe_offer(
+    //!         pending_pipe.offer().clone(),
+    //!         RemotePipeOptions::default(),
+    //!         |message: RemotePipeMessage| {
+    //!             // Send message to the first host and call `RemotePipeConsumer::handle_message()`
+    //!         },
+    //!     )
+    //!     .await?;
+    //! // Send answer back to the first host, there
+    //! let pipe_consumer = pending_pipe
+    //!     .connect(answer, |message: RemotePipeMessage| {
+    //!         // Send message to the second host and call `RemotePipeProducer::handle_message()`
+    //!     })
+    //!     .await?;
+    //! # Ok(())
+    //! # }
+    //! ```
+
+    #[cfg(doc)]
+    use super::*;
+    pub use crate::router::remote_pipe::*;
+}
+
 pub mod plain_transport {
     //! A plain transport represents a network path through which RTP, RTCP (optionally secured with
     //! SRTP) and SCTP (DataChannel) is transmitted.
@@ -197,6 +258,36 @@ pub mod rtp_observer {
     pub use crate::router::rtp_observer::*;
 }
 
+pub mod snapshot {
+    //! Serializable snapshots of router topology.
+    //!
+    //! [`Router::snapshot()`](router::Router::snapshot) captures transports, producers, consumers,
+    //! data producers, data consumers and RTP observers of a router along with options they were
+    //! created with, [`Worker::restore_router()`](worker::Worker::restore_router) re-creates the same
+    //! topology on any worker (with new ids). Snapshots are versioned, see
+    //! [`ROUTER_SNAPSHOT_VERSION`].
+    //!
+    //! Only the media plane is restored, endpoints need to connect to restored transports again.
+    //!
+    //! # Example
+    //! ```no_run
+    //! use mediasoup::prelude::*;
+    //! use mediasoup::snapshot::RouterSnapshot;
+    //!
+    //! # async fn f(router: Router, worker: Worker) -> Result<(), Box<dyn std::error::Error>> {
+    //! let serialized = serde_json::to_string(&router.snapshot())?;
+    //! // Store it somewhere, then after the worker has died and new one was created
+    //! let snapshot = serde_json::from_str::<RouterSnapshot>(&serialized)?;
+    //! let restored = worker.restore_router(snapshot).await?;
+    //! # Ok(())
+    //! # }
+    //! ```
+
+    #[cfg(doc)]
+    use super::*;
+    pub use crate::router::snapshot::*;
+}
+
 pub mod webrtc_transport {
     //! A WebRTC transport represents a network path negotiated by both, a WebRTC endpoint and
     //! mediasoup, via ICE and DTLS procedures. A WebRTC transport may be used to receive media, to
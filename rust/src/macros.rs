@@ -53,3 +53,19 @@ macro_rules! uuid_based_wrapper_type {
         }
     };
 }
+
+/// Wraps future into a span when `tracing` feature is enabled, returns the future as is otherwise.
+///
+/// Span is typically created right before under `#[cfg(feature = "tracing")]`, tokens of span
+/// expression are dropped without `tracing` feature, so it doesn't need to exist in that case.
+macro_rules! instrument {
+    ($future:expr, $span:expr $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument($future, $span);
+        #[cfg(not(feature = "tracing"))]
+        let future = $future;
+        future
+    }};
+}
+
+pub(crate) use instrument;
@@ -10,7 +10,7 @@ use crate::data_structures::{
     ListenInfo, SctpState, TransportTuple,
 };
 use crate::direct_transport::DirectTransportOptions;
-use crate::ortc::RtpMapping;
+use crate::ortc::{RtpMapping, RtpMappingCodec};
 use crate::pipe_transport::PipeTransportOptions;
 use crate::plain_transport::PlainTransportOptions;
 use crate::producer::{ProducerId, ProducerTraceEventType, ProducerType};
@@ -64,7 +64,7 @@ where
     /// Convert generic response into specific type of this request.
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>>;
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>>;
 }
 
 pub(crate) trait Notification: Debug {
@@ -102,7 +102,7 @@ impl Request for WorkerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -133,7 +133,7 @@ impl Request for WorkerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::WorkerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -210,7 +210,7 @@ impl Request for WorkerUpdateSettingsRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -250,7 +250,7 @@ impl Request for WorkerCreateWebRtcServerRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -289,7 +289,7 @@ impl Request for WebRtcServerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -320,7 +320,7 @@ impl Request for WebRtcServerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::WebRtcServerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -402,7 +402,7 @@ impl Request for WorkerCreateRouterRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -437,7 +437,7 @@ impl Request for RouterCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -468,7 +468,7 @@ impl Request for RouterDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::RouterDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -481,12 +481,12 @@ impl Request for RouterDumpRequest {
                 .map_consumer_id_producer_id
                 .into_iter()
                 .map(|key_value| Ok((key_value.key.parse()?, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_data_consumer_id_data_producer_id: data
                 .map_data_consumer_id_data_producer_id
                 .into_iter()
                 .map(|key_value| Ok((key_value.key.parse()?, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_data_producer_id_data_consumer_ids: data
                 .map_data_producer_id_data_consumer_ids
                 .into_iter()
@@ -500,7 +500,7 @@ impl Request for RouterDumpRequest {
                             .collect::<Result<_, _>>()?,
                     ))
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_producer_id_consumer_ids: data
                 .map_producer_id_consumer_ids
                 .into_iter()
@@ -514,7 +514,7 @@ impl Request for RouterDumpRequest {
                             .collect::<Result<_, _>>()?,
                     ))
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_producer_id_observer_ids: data
                 .map_producer_id_observer_ids
                 .into_iter()
@@ -528,7 +528,7 @@ impl Request for RouterDumpRequest {
                             .collect::<Result<_, _>>()?,
                     ))
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             rtp_observer_ids: data
                 .rtp_observer_ids
                 .into_iter()
@@ -613,7 +613,7 @@ impl Request for RouterCreateDirectTransportRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -738,6 +738,8 @@ pub(crate) struct WebRtcTransportData {
     pub(crate) dtls_remote_cert: Mutex<Option<String>>,
     pub(crate) sctp_parameters: Option<SctpParameters>,
     pub(crate) sctp_state: Mutex<Option<SctpState>>,
+    #[serde(skip)]
+    pub(crate) available_bitrate: Mutex<Option<u32>>,
 }
 
 #[derive(Debug)]
@@ -779,7 +781,7 @@ impl Request for RouterCreateWebRtcTransportRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::WebRtcTransportDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -811,6 +813,7 @@ impl Request for RouterCreateWebRtcTransportRequest {
                     .sctp_state
                     .map(|state| SctpState::from_fbs(&state)),
             ),
+            available_bitrate: Mutex::new(None),
         })
     }
 }
@@ -856,7 +859,7 @@ impl Request for RouterCreateWebRtcTransportWithServerRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::WebRtcTransportDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -888,6 +891,7 @@ impl Request for RouterCreateWebRtcTransportWithServerRequest {
                     .sctp_state
                     .map(|state| SctpState::from_fbs(&state)),
             ),
+            available_bitrate: Mutex::new(None),
         })
     }
 }
@@ -988,7 +992,7 @@ impl Request for RouterCreatePlainTransportRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::PlainTransportDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1114,7 +1118,7 @@ impl Request for RouterCreatePipeTransportRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::PipeTransportDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1215,7 +1219,7 @@ impl Request for RouterCreateAudioLevelObserverRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1280,7 +1284,7 @@ impl Request for RouterCreateActiveSpeakerObserverRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1311,7 +1315,7 @@ impl Request for TransportDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -1346,7 +1350,7 @@ impl Request for TransportGetStatsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -1387,7 +1391,7 @@ impl Request for TransportCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1428,7 +1432,7 @@ impl Request for WebRtcTransportConnectRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::WebRtcTransportConnectResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1482,7 +1486,7 @@ impl Request for PipeTransportConnectRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::PipeTransportConnectResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1541,7 +1545,7 @@ impl Request for TransportConnectPlainRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::PlainTransportConnectResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1591,7 +1595,7 @@ impl Request for TransportSetMaxIncomingBitrateRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1627,7 +1631,7 @@ impl Request for TransportSetMaxOutgoingBitrateRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1663,7 +1667,7 @@ impl Request for TransportSetMinOutgoingBitrateRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -1693,7 +1697,7 @@ impl Request for TransportRestartIceRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::TransportRestartIceResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1755,7 +1759,7 @@ impl Request for TransportProduceRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::TransportProduceResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1779,6 +1783,7 @@ pub(crate) struct TransportConsumeRequest {
     pub(crate) paused: bool,
     pub(crate) preferred_layers: Option<ConsumerLayers>,
     pub(crate) ignore_dtx: bool,
+    pub(crate) payload_type_mapping: Vec<RtpMappingCodec>,
 }
 
 #[derive(Debug)]
@@ -1810,6 +1815,10 @@ impl Request for TransportConsumeRequest {
             self.paused,
             self.preferred_layers.map(ConsumerLayers::to_fbs),
             self.ignore_dtx,
+            self.payload_type_mapping
+                .into_iter()
+                .map(RtpMappingCodec::to_fbs)
+                .collect::<Vec<_>>(),
         );
         let request_body = request::Body::create_transport_consume_request(&mut builder, data);
         let request = request::Request::create(
@@ -1827,7 +1836,7 @@ impl Request for TransportConsumeRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::TransportConsumeResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -1909,7 +1918,7 @@ impl Request for TransportProduceDataRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::DataProducerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2000,7 +2009,7 @@ impl Request for TransportConsumeDataRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::DataConsumerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2061,7 +2070,7 @@ impl Request for TransportEnableTraceEventRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -2132,7 +2141,7 @@ impl Request for ProducerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2163,7 +2172,7 @@ impl Request for ProducerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::ProducerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2198,7 +2207,7 @@ impl Request for ProducerGetStatsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -2234,7 +2243,7 @@ impl Request for ProducerPauseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2265,7 +2274,7 @@ impl Request for ProducerResumeRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2307,7 +2316,7 @@ impl Request for ProducerEnableTraceEventRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -2378,7 +2387,7 @@ impl Request for ConsumerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2409,7 +2418,7 @@ impl Request for ConsumerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::ConsumerDumpResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2444,7 +2453,7 @@ impl Request for ConsumerGetStatsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -2480,7 +2489,7 @@ impl Request for ConsumerPauseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2511,7 +2520,7 @@ impl Request for ConsumerResumeRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2551,7 +2560,7 @@ impl Request for ConsumerSetPreferredLayersRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::ConsumerSetPreferredLayersResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2601,7 +2610,7 @@ impl Request for ConsumerSetPriorityRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::ConsumerSetPriorityResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -2639,7 +2648,7 @@ impl Request for ConsumerRequestKeyFrameRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2681,7 +2690,7 @@ impl Request for ConsumerEnableTraceEventRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -2724,7 +2733,7 @@ impl Request for DataProducerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2755,7 +2764,7 @@ impl Request for DataProducerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -2791,7 +2800,7 @@ impl Request for DataProducerGetStatsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -2827,7 +2836,7 @@ impl Request for DataProducerPauseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2858,7 +2867,7 @@ impl Request for DataProducerResumeRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2935,7 +2944,7 @@ impl Request for DataConsumerCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -2966,7 +2975,7 @@ impl Request for DataConsumerDumpRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -3002,7 +3011,7 @@ impl Request for DataConsumerGetStatsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         match response {
             Some(data) => Ok(data.try_into().unwrap()),
             _ => {
@@ -3038,7 +3047,7 @@ impl Request for DataConsumerPauseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3069,7 +3078,7 @@ impl Request for DataConsumerResumeRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3105,7 +3114,7 @@ impl Request for DataConsumerGetBufferedAmountRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::DataConsumerGetBufferedAmountResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -3156,7 +3165,7 @@ impl Request for DataConsumerSetBufferedAmountLowThresholdRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3194,7 +3203,7 @@ impl Request for DataConsumerSendRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3242,7 +3251,7 @@ impl Request for DataConsumerSetSubchannelsRequest {
 
     fn convert_response(
         response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         let Some(response::BodyRef::DataConsumerSetSubchannelsResponse(data)) = response else {
             panic!("Wrong message from worker: {response:?}");
         };
@@ -3287,7 +3296,7 @@ impl Request for RtpObserverCloseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3317,7 +3326,7 @@ impl Request for RtpObserverPauseRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3347,7 +3356,7 @@ impl Request for RtpObserverResumeRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3386,7 +3395,7 @@ impl Request for RtpObserverAddProducerRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -3425,7 +3434,7 @@ impl Request for RtpObserverRemoveProducerRequest {
 
     fn convert_response(
         _response: Option<response::BodyRef<'_>>,
-    ) -> Result<Self::Response, Box<dyn Error>> {
+    ) -> Result<Self::Response, Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
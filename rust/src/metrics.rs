@@ -0,0 +1,920 @@
+//! Prometheus/OpenMetrics exporter for worker, transport, producer and consumer statistics.
+//!
+//! [`MetricsCollector`] follows workers created by [`WorkerManager`] together with their routers,
+//! transports, producers and consumers, gathers statistics of all of them (either on demand with
+//! [`MetricsCollector::collect()`] or periodically after [`MetricsCollector::start()`]) and renders
+//! the last gathered snapshot in
+//! [OpenMetrics text format](https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md)
+//! with [`MetricsCollector::render()`]. Serving rendered metrics over HTTP is up to the
+//! application.
+//!
+//! Every sample carries `worker_id`, `router_id` and `transport_id` labels of the entity it belongs
+//! to, RTP stream samples additionally carry `kind`, `mime_type` and (for producers) `rid` labels.
+//!
+//! # Example
+//! ```rust
+//! use mediasoup::metrics::MetricsCollector;
+//! use mediasoup::worker_manager::WorkerManager;
+//! use std::time::Duration;
+//!
+//! let worker_manager = WorkerManager::new();
+//! // Create collector before workers, so that all entities are tracked from the very beginning
+//! let metrics_collector = MetricsCollector::new(&worker_manager);
+//! metrics_collector.start(Duration::from_secs(15));
+//!
+//! // Later, in HTTP handler of `/metrics` endpoint
+//! let body = metrics_collector.render();
+//! assert!(body.ends_with("# EOF\n"));
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::{ConsumerId, ConsumerStat, WeakConsumer};
+use crate::direct_transport::{DirectTransportStat, WeakDirectTransport};
+use crate::executor::Executor;
+use crate::pipe_transport::{PipeTransportStat, WeakPipeTransport};
+use crate::plain_transport::{PlainTransportStat, WeakPlainTransport};
+use crate::producer::{ProducerId, ProducerStat, WeakProducer};
+use crate::router::{NewTransport, RouterId};
+use crate::rtp_parameters::MediaKind;
+use crate::transport::{TransportGeneric, TransportId};
+use crate::webrtc_transport::{WeakWebRtcTransport, WebRtcTransportStat};
+use crate::worker::{RequestError, WeakWorker, Worker, WorkerId};
+use crate::worker_manager::WorkerManager;
+use async_io::Timer;
+use event_listener_primitives::HandlerId;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// OpenMetrics metric type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+        }
+    }
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug)]
+struct Sample {
+    labels: Labels,
+    value: f64,
+}
+
+#[derive(Debug)]
+struct MetricFamily {
+    name: &'static str,
+    help: &'static str,
+    metric_type: MetricType,
+    samples: Vec<Sample>,
+}
+
+/// Describes how to extract metric from statistics of type `S`, `None` means value is not
+/// available and sample is skipped.
+struct MetricDefinition<S> {
+    name: &'static str,
+    help: &'static str,
+    metric_type: MetricType,
+    value: fn(&S) -> Option<f64>,
+}
+
+/// Creates one family for each definition with a sample for each entry in `stats`.
+fn metric_families<S>(
+    definitions: &[MetricDefinition<S>],
+    stats: &[(Labels, S)],
+) -> Vec<MetricFamily> {
+    definitions
+        .iter()
+        .map(|definition| MetricFamily {
+            name: definition.name,
+            help: definition.help,
+            metric_type: definition.metric_type,
+            samples: stats
+                .iter()
+                .filter_map(|(labels, stat)| {
+                    Some(Sample {
+                        labels: labels.clone(),
+                        value: (definition.value)(stat)?,
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn write_label_value(output: &mut String, value: &str) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '\\' => output.write_str("\\\\")?,
+            '"' => output.write_str("\\\"")?,
+            '\n' => output.write_str("\\n")?,
+            c => output.write_char(c)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders metric families in OpenMetrics text format, families without samples are omitted.
+fn render_families(families: &[MetricFamily]) -> Result<String, fmt::Error> {
+    let mut output = String::new();
+
+    for family in families {
+        if family.samples.is_empty() {
+            continue;
+        }
+
+        writeln!(
+            output,
+            "# TYPE {} {}",
+            family.name,
+            family.metric_type.as_str()
+        )?;
+        writeln!(output, "# HELP {} {}", family.name, family.help)?;
+
+        for sample in &family.samples {
+            output.write_str(family.name)?;
+            if family.metric_type == MetricType::Counter {
+                output.write_str("_total")?;
+            }
+            if !sample.labels.is_empty() {
+                output.write_char('{')?;
+                for (index, (name, value)) in sample.labels.iter().enumerate() {
+                    if index > 0 {
+                        output.write_char(',')?;
+                    }
+                    write!(output, "{name}=\"")?;
+                    write_label_value(&mut output, value)?;
+                    output.write_char('"')?;
+                }
+                output.write_char('}')?;
+            }
+            writeln!(output, " {}", sample.value)?;
+        }
+    }
+
+    output.write_str("# EOF\n")?;
+
+    Ok(output)
+}
+
+fn media_kind_label(kind: MediaKind) -> &'static str {
+    match kind {
+        MediaKind::Audio => "audio",
+        MediaKind::Video => "video",
+    }
+}
+
+struct WorkerStat {
+    outstanding_requests: usize,
+    routers: usize,
+}
+
+const WORKER_METRICS: &[MetricDefinition<WorkerStat>] = &[
+    MetricDefinition {
+        name: "mediasoup_worker_routers",
+        help: "Number of routers on the worker.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(stat.routers as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_worker_outstanding_requests",
+        help: "Number of requests sent to the worker that are waiting for response.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(stat.outstanding_requests as f64),
+    },
+];
+
+/// Subset of statistics common to all transport types.
+struct TransportStat {
+    bytes_received: u64,
+    bytes_sent: u64,
+    rtp_bytes_received: u64,
+    rtp_bytes_sent: u64,
+    rtx_bytes_sent: u64,
+    probation_bytes_sent: u64,
+    recv_bitrate: u32,
+    send_bitrate: u32,
+    available_outgoing_bitrate: Option<u32>,
+    available_incoming_bitrate: Option<u32>,
+}
+
+macro_rules! impl_from_transport_stat {
+    ($($stat:ty),+) => {
+        $(
+            impl From<&$stat> for TransportStat {
+                fn from(stat: &$stat) -> Self {
+                    Self {
+                        bytes_received: stat.bytes_received,
+                        bytes_sent: stat.bytes_sent,
+                        rtp_bytes_received: stat.rtp_bytes_received,
+                        rtp_bytes_sent: stat.rtp_bytes_sent,
+                        rtx_bytes_sent: stat.rtx_bytes_sent,
+                        probation_bytes_sent: stat.probation_bytes_sent,
+                        recv_bitrate: stat.recv_bitrate,
+                        send_bitrate: stat.send_bitrate,
+                        available_outgoing_bitrate: stat.available_outgoing_bitrate,
+                        available_incoming_bitrate: stat.available_incoming_bitrate,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_from_transport_stat!(
+    DirectTransportStat,
+    PipeTransportStat,
+    PlainTransportStat,
+    WebRtcTransportStat
+);
+
+const TRANSPORT_METRICS: &[MetricDefinition<TransportStat>] = &[
+    MetricDefinition {
+        name: "mediasoup_transport_received_bytes",
+        help: "Bytes received by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.bytes_received as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_sent_bytes",
+        help: "Bytes sent by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.bytes_sent as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_rtp_received_bytes",
+        help: "RTP bytes received by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.rtp_bytes_received as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_rtp_sent_bytes",
+        help: "RTP bytes sent by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.rtp_bytes_sent as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_rtx_sent_bytes",
+        help: "RTX bytes sent by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.rtx_bytes_sent as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_probation_sent_bytes",
+        help: "Probation bytes sent by the transport.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.probation_bytes_sent as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_recv_bitrate",
+        help: "Current receive bitrate of the transport in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.recv_bitrate)),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_send_bitrate",
+        help: "Current send bitrate of the transport in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.send_bitrate)),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_available_outgoing_bitrate",
+        help: "Outgoing bitrate estimated by bandwidth estimation in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| stat.available_outgoing_bitrate.map(f64::from),
+    },
+    MetricDefinition {
+        name: "mediasoup_transport_available_incoming_bitrate",
+        help: "Incoming bitrate estimated by bandwidth estimation in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| stat.available_incoming_bitrate.map(f64::from),
+    },
+];
+
+const PRODUCER_METRICS: &[MetricDefinition<ProducerStat>] = &[
+    MetricDefinition {
+        name: "mediasoup_producer_bytes",
+        help: "Bytes received for the producer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.byte_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_packets",
+        help: "Packets received for the producer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.packet_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_packets_lost",
+        help: "Packets of the producer RTP stream that were lost.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.packets_lost as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_nacks",
+        help: "NACK packets sent for the producer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.nack_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_plis",
+        help: "PLI packets sent for the producer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.pli_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_firs",
+        help: "FIR packets sent for the producer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.fir_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_bitrate",
+        help: "Current bitrate of the producer RTP stream in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.bitrate)),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_score",
+        help: "Score of the producer RTP stream, from 0 to 10.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.score)),
+    },
+    MetricDefinition {
+        name: "mediasoup_producer_jitter",
+        help: "Jitter of the producer RTP stream in RTP timestamp units.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.jitter)),
+    },
+];
+
+const CONSUMER_METRICS: &[MetricDefinition<ConsumerStat>] = &[
+    MetricDefinition {
+        name: "mediasoup_consumer_bytes",
+        help: "Bytes sent for the consumer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.byte_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_packets",
+        help: "Packets sent for the consumer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.packet_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_packets_lost",
+        help: "Packets of the consumer RTP stream reported lost by the remote endpoint.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.packets_lost as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_packets_retransmitted",
+        help: "Packets of the consumer RTP stream that were retransmitted.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.packets_retransmitted as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_nacks",
+        help: "NACK packets received for the consumer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.nack_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_plis",
+        help: "PLI packets received for the consumer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.pli_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_firs",
+        help: "FIR packets received for the consumer RTP stream.",
+        metric_type: MetricType::Counter,
+        value: |stat| Some(stat.fir_count as f64),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_bitrate",
+        help: "Current bitrate of the consumer RTP stream in bits per second.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.bitrate)),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_score",
+        help: "Score of the consumer RTP stream, from 0 to 10.",
+        metric_type: MetricType::Gauge,
+        value: |stat| Some(f64::from(stat.score)),
+    },
+    MetricDefinition {
+        name: "mediasoup_consumer_round_trip_time",
+        help: "Round trip time of the consumer RTP stream in milliseconds.",
+        metric_type: MetricType::Gauge,
+        value: |stat| stat.round_trip_time.map(f64::from),
+    },
+];
+
+#[derive(Clone)]
+enum WeakAnyTransport {
+    Direct(WeakDirectTransport),
+    Pipe(WeakPipeTransport),
+    Plain(WeakPlainTransport),
+    WebRtc(WeakWebRtcTransport),
+}
+
+impl WeakAnyTransport {
+    fn new(transport: &NewTransport<'_>) -> Self {
+        match transport {
+            NewTransport::Direct(transport) => Self::Direct(transport.downgrade()),
+            NewTransport::Pipe(transport) => Self::Pipe(transport.downgrade()),
+            NewTransport::Plain(transport) => Self::Plain(transport.downgrade()),
+            NewTransport::WebRtc(transport) => Self::WebRtc(transport.downgrade()),
+        }
+    }
+
+    fn type_label(&self) -> &'static str {
+        match self {
+            Self::Direct(_) => "direct",
+            Self::Pipe(_) => "pipe",
+            Self::Plain(_) => "plain",
+            Self::WebRtc(_) => "webrtc",
+        }
+    }
+
+    /// Returns `None` if transport was already dropped.
+    async fn get_stats(&self) -> Option<Result<Vec<TransportStat>, RequestError>> {
+        async fn get_stats<T>(
+            transport: Option<T>,
+        ) -> Option<Result<Vec<TransportStat>, RequestError>>
+        where
+            T: TransportGeneric,
+            for<'a> &'a T::Stat: Into<TransportStat>,
+        {
+            let stats = transport?.get_stats().await;
+
+            Some(stats.map(|stats| stats.iter().map(Into::into).collect()))
+        }
+
+        match self {
+            Self::Direct(transport) => get_stats(transport.upgrade()).await,
+            Self::Pipe(transport) => get_stats(transport.upgrade()).await,
+            Self::Plain(transport) => get_stats(transport.upgrade()).await,
+            Self::WebRtc(transport) => get_stats(transport.upgrade()).await,
+        }
+    }
+}
+
+/// Ids of the entities transport belongs to.
+#[derive(Clone)]
+struct TransportLabels {
+    worker_id: WorkerId,
+    router_id: RouterId,
+    transport_id: TransportId,
+}
+
+impl TransportLabels {
+    fn to_labels(&self) -> Labels {
+        vec![
+            ("worker_id", self.worker_id.to_string()),
+            ("router_id", self.router_id.to_string()),
+            ("transport_id", self.transport_id.to_string()),
+        ]
+    }
+}
+
+struct TrackedWorker {
+    worker_id: WorkerId,
+    worker: WeakWorker,
+}
+
+struct TrackedRouter {
+    worker_id: WorkerId,
+    router_id: RouterId,
+}
+
+struct TrackedTransport {
+    labels: TransportLabels,
+    transport: WeakAnyTransport,
+}
+
+struct TrackedProducer {
+    labels: TransportLabels,
+    producer_id: ProducerId,
+    producer: WeakProducer,
+}
+
+struct TrackedConsumer {
+    labels: TransportLabels,
+    consumer_id: ConsumerId,
+    producer_id: ProducerId,
+    consumer: WeakConsumer,
+}
+
+#[derive(Default)]
+struct Tracked {
+    workers: Vec<TrackedWorker>,
+    routers: Vec<TrackedRouter>,
+    transports: Vec<TrackedTransport>,
+    producers: Vec<TrackedProducer>,
+    consumers: Vec<TrackedConsumer>,
+}
+
+struct Inner {
+    executor: Arc<dyn Executor>,
+    tracked: Mutex<Tracked>,
+    families: Mutex<Vec<MetricFamily>>,
+    handlers: Mutex<Vec<HandlerId>>,
+    /// Incremented on every [`MetricsCollector::start()`] call to stop previous background loop
+    collection_generation: AtomicUsize,
+}
+
+impl Inner {
+    fn track_worker(self: &Arc<Self>, worker: &Worker) {
+        let worker_id = worker.id();
+
+        self.tracked.lock().workers.push(TrackedWorker {
+            worker_id,
+            worker: worker.downgrade(),
+        });
+
+        let inner_weak = Arc::downgrade(self);
+        let new_router_handler = worker.on_new_router(move |router| {
+            let Some(inner) = inner_weak.upgrade() else {
+                return;
+            };
+            let router_id = router.id();
+
+            inner.tracked.lock().routers.push(TrackedRouter {
+                worker_id,
+                router_id,
+            });
+
+            router
+                .on_close(untrack(&inner, move |tracked| {
+                    tracked
+                        .routers
+                        .retain(|router| router.router_id != router_id);
+                }))
+                .detach();
+
+            let inner_weak = Arc::downgrade(&inner);
+            router
+                .on_new_transport(move |transport| {
+                    if let Some(inner) = inner_weak.upgrade() {
+                        inner.track_transport(
+                            TransportLabels {
+                                worker_id,
+                                router_id,
+                                transport_id: transport.id(),
+                            },
+                            &transport,
+                        );
+                    }
+                })
+                .detach();
+        });
+
+        self.handlers.lock().push(new_router_handler);
+
+        worker
+            .on_close(untrack(self, move |tracked| {
+                tracked
+                    .workers
+                    .retain(|worker| worker.worker_id != worker_id);
+            }))
+            .detach();
+    }
+
+    fn track_transport(self: &Arc<Self>, labels: TransportLabels, transport: &NewTransport<'_>) {
+        let transport_id = labels.transport_id;
+
+        self.tracked.lock().transports.push(TrackedTransport {
+            labels: labels.clone(),
+            transport: WeakAnyTransport::new(transport),
+        });
+
+        transport
+            .on_close(Box::new(untrack(self, move |tracked| {
+                tracked
+                    .transports
+                    .retain(|transport| transport.labels.transport_id != transport_id);
+            })))
+            .detach();
+
+        transport
+            .on_new_producer(Arc::new({
+                let inner_weak = Arc::downgrade(self);
+                let labels = labels.clone();
+
+                move |producer| {
+                    let Some(inner) = inner_weak.upgrade() else {
+                        return;
+                    };
+                    let producer_id = producer.id();
+
+                    inner.tracked.lock().producers.push(TrackedProducer {
+                        labels: labels.clone(),
+                        producer_id,
+                        producer: producer.downgrade(),
+                    });
+
+                    producer
+                        .on_close(untrack(&inner, move |tracked| {
+                            tracked
+                                .producers
+                                .retain(|producer| producer.producer_id != producer_id);
+                        }))
+                        .detach();
+                }
+            }))
+            .detach();
+
+        transport
+            .on_new_consumer(Arc::new({
+                let inner_weak = Arc::downgrade(self);
+
+                move |consumer| {
+                    let Some(inner) = inner_weak.upgrade() else {
+                        return;
+                    };
+                    let consumer_id = consumer.id();
+
+                    inner.tracked.lock().consumers.push(TrackedConsumer {
+                        labels: labels.clone(),
+                        consumer_id,
+                        producer_id: consumer.producer_id(),
+                        consumer: consumer.downgrade(),
+                    });
+
+                    consumer
+                        .on_close(untrack(&inner, move |tracked| {
+                            tracked
+                                .consumers
+                                .retain(|consumer| consumer.consumer_id != consumer_id);
+                        }))
+                        .detach();
+                }
+            }))
+            .detach();
+    }
+
+    async fn collect(&self) {
+        debug!("collect()");
+
+        // Upgrade everything upfront so that the lock is not held across requests
+        let (workers, routers, transports, producers, consumers) = {
+            let tracked = self.tracked.lock();
+
+            (
+                tracked
+                    .workers
+                    .iter()
+                    .filter_map(|worker| worker.worker.upgrade())
+                    .collect::<Vec<_>>(),
+                tracked
+                    .routers
+                    .iter()
+                    .map(|router| router.worker_id)
+                    .collect::<Vec<_>>(),
+                tracked
+                    .transports
+                    .iter()
+                    .map(|transport| (transport.labels.clone(), transport.transport.clone()))
+                    .collect::<Vec<_>>(),
+                tracked
+                    .producers
+                    .iter()
+                    .filter_map(|producer| {
+                        Some((
+                            producer.labels.clone(),
+                            producer.producer_id,
+                            producer.producer.upgrade()?,
+                        ))
+                    })
+                    .collect::<Vec<_>>(),
+                tracked
+                    .consumers
+                    .iter()
+                    .filter_map(|consumer| {
+                        Some((
+                            consumer.labels.clone(),
+                            consumer.consumer_id,
+                            consumer.producer_id,
+                            consumer.consumer.upgrade()?,
+                        ))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let worker_stats = workers
+            .iter()
+            .map(|worker| {
+                let worker_id = worker.id();
+                (
+                    vec![("worker_id", worker_id.to_string())],
+                    WorkerStat {
+                        outstanding_requests: worker.outstanding_requests(),
+                        routers: routers.iter().filter(|id| **id == worker_id).count(),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut transport_stats = Vec::new();
+        for (labels, transport) in transports {
+            match transport.get_stats().await {
+                Some(Ok(stats)) => {
+                    let mut labels = labels.to_labels();
+                    labels.push(("type", transport.type_label().to_string()));
+                    for stat in stats {
+                        transport_stats.push((labels.clone(), stat));
+                    }
+                }
+                Some(Err(error)) => {
+                    warn!(
+                        "failed to get transport stats [transport_id:{}]: {}",
+                        labels.transport_id, error,
+                    );
+                }
+                None => {}
+            }
+        }
+
+        let mut producer_stats = Vec::new();
+        for (labels, producer_id, producer) in producers {
+            match producer.get_stats().await {
+                Ok(stats) => {
+                    for stat in stats {
+                        let mut labels = labels.to_labels();
+                        labels.extend([
+                            ("producer_id", producer_id.to_string()),
+                            ("kind", media_kind_label(stat.kind).to_string()),
+                            ("mime_type", stat.mime_type.as_str().to_string()),
+                            ("rid", stat.rid.clone().unwrap_or_default()),
+                        ]);
+                        producer_stats.push((labels, stat));
+                    }
+                }
+                Err(error) => {
+                    warn!(
+                        "failed to get producer stats [producer_id:{}]: {}",
+                        producer_id, error,
+                    );
+                }
+            }
+        }
+
+        let mut consumer_stats = Vec::new();
+        for (labels, consumer_id, producer_id, consumer) in consumers {
+            match consumer.get_stats().await {
+                Ok(stats) => {
+                    let stat = stats.consumer_stats().clone();
+                    let mut labels = labels.to_labels();
+                    labels.extend([
+                        ("consumer_id", consumer_id.to_string()),
+                        ("producer_id", producer_id.to_string()),
+                        ("kind", media_kind_label(stat.kind).to_string()),
+                        ("mime_type", stat.mime_type.as_str().to_string()),
+                    ]);
+                    consumer_stats.push((labels, stat));
+                }
+                Err(error) => {
+                    warn!(
+                        "failed to get consumer stats [consumer_id:{}]: {}",
+                        consumer_id, error,
+                    );
+                }
+            }
+        }
+
+        let mut families = metric_families(WORKER_METRICS, &worker_stats);
+        families.extend(metric_families(TRANSPORT_METRICS, &transport_stats));
+        families.extend(metric_families(PRODUCER_METRICS, &producer_stats));
+        families.extend(metric_families(CONSUMER_METRICS, &consumer_stats));
+
+        *self.families.lock() = families;
+    }
+}
+
+/// Creates close callback that removes entity from tracked entities.
+fn untrack<F>(inner: &Arc<Inner>, remove: F) -> impl FnOnce() + Send + 'static
+where
+    F: FnOnce(&mut Tracked) + Send + 'static,
+{
+    let inner_weak: Weak<Inner> = Arc::downgrade(inner);
+
+    move || {
+        if let Some(inner) = inner_weak.upgrade() {
+            remove(&mut inner.tracked.lock());
+        }
+    }
+}
+
+/// Collects statistics of workers and entities on them and renders them in OpenMetrics text
+/// format.
+///
+/// See [module-level documentation](self) for details.
+#[derive(Clone)]
+pub struct MetricsCollector {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for MetricsCollector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsCollector").finish()
+    }
+}
+
+impl MetricsCollector {
+    /// Create collector that tracks all workers created by `worker_manager` from now on.
+    ///
+    /// Workers that already exist can be added with [`MetricsCollector::track_worker()`].
+    #[must_use]
+    pub fn new(worker_manager: &WorkerManager) -> Self {
+        debug!("new()");
+
+        let inner = Arc::new(Inner {
+            executor: Arc::clone(worker_manager.executor()),
+            tracked: Mutex::default(),
+            families: Mutex::default(),
+            handlers: Mutex::default(),
+            collection_generation: AtomicUsize::default(),
+        });
+
+        let new_worker_handler = worker_manager.on_new_worker({
+            let inner_weak = Arc::downgrade(&inner);
+
+            move |worker| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.track_worker(worker);
+                }
+            }
+        });
+        inner.handlers.lock().push(new_worker_handler);
+
+        Self { inner }
+    }
+
+    /// Start tracking worker that was created before this collector.
+    ///
+    /// NOTE: Only routers (and entities on them) created after this call will be tracked.
+    pub fn track_worker(&self, worker: &Worker) {
+        self.inner.track_worker(worker);
+    }
+
+    /// Gather statistics of all tracked entities, replacing snapshot that is returned by
+    /// [`MetricsCollector::render()`].
+    ///
+    /// Entities for which statistics request failed are skipped.
+    pub async fn collect(&self) {
+        self.inner.collect().await;
+    }
+
+    /// Start gathering statistics every `interval` in the background, replacing previously started
+    /// background collection (if any).
+    ///
+    /// Background collection stops at the next tick after the last instance of collector is dropped.
+    pub fn start(&self, interval: Duration) {
+        debug!("start()");
+
+        let generation = self
+            .inner
+            .collection_generation
+            .fetch_add(1, Ordering::SeqCst)
+            + 1;
+        let inner_weak = Arc::downgrade(&self.inner);
+        self.inner.executor.spawn(Box::pin(async move {
+            loop {
+                Timer::after(interval).await;
+
+                let Some(inner) = inner_weak.upgrade() else {
+                    break;
+                };
+                if inner.collection_generation.load(Ordering::SeqCst) != generation {
+                    break;
+                }
+
+                inner.collect().await;
+            }
+        }));
+    }
+
+    /// Render statistics gathered during the last collection in OpenMetrics text format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        render_families(&self.inner.families.lock()).expect("Writing to string never fails; qed")
+    }
+}
@@ -0,0 +1,176 @@
+use super::*;
+use crate::data_structures::{ListenInfo, Protocol};
+use crate::producer::ProducerOptions;
+use crate::router::RouterOptions;
+use crate::rtp_parameters::{
+    MimeTypeAudio, RtpCodecCapability, RtpCodecParameters, RtpCodecParametersParameters,
+    RtpParameters,
+};
+use crate::transport::Transport;
+use crate::webrtc_transport::{WebRtcTransportListenInfos, WebRtcTransportOptions};
+use crate::worker::WorkerSettings;
+use futures_lite::future;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+
+fn init() {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+}
+
+#[test]
+fn render_open_metrics() {
+    let families = vec![
+        MetricFamily {
+            name: "mediasoup_producer_bytes",
+            help: "Bytes received for the producer RTP stream.",
+            metric_type: MetricType::Counter,
+            samples: vec![
+                Sample {
+                    labels: vec![("producer_id", "p1".to_string()), ("rid", "h".to_string())],
+                    value: 1200.0,
+                },
+                Sample {
+                    labels: vec![
+                        ("producer_id", "p2".to_string()),
+                        ("rid", "a\"b\\c\nd".to_string()),
+                    ],
+                    value: 0.0,
+                },
+            ],
+        },
+        MetricFamily {
+            name: "mediasoup_worker_routers",
+            help: "Number of routers on the worker.",
+            metric_type: MetricType::Gauge,
+            samples: vec![],
+        },
+        MetricFamily {
+            name: "mediasoup_consumer_round_trip_time",
+            help: "Round trip time of the consumer RTP stream in milliseconds.",
+            metric_type: MetricType::Gauge,
+            samples: vec![Sample {
+                labels: vec![],
+                value: 12.5,
+            }],
+        },
+    ];
+
+    assert_eq!(
+        render_families(&families).unwrap(),
+        "# TYPE mediasoup_producer_bytes counter\n\
+        # HELP mediasoup_producer_bytes Bytes received for the producer RTP stream.\n\
+        mediasoup_producer_bytes_total{producer_id=\"p1\",rid=\"h\"} 1200\n\
+        mediasoup_producer_bytes_total{producer_id=\"p2\",rid=\"a\\\"b\\\\c\\nd\"} 0\n\
+        # TYPE mediasoup_consumer_round_trip_time gauge\n\
+        # HELP mediasoup_consumer_round_trip_time Round trip time of the consumer RTP stream in milliseconds.\n\
+        mediasoup_consumer_round_trip_time 12.5\n\
+        # EOF\n"
+    );
+
+    assert_eq!(render_families(&[]).unwrap(), "# EOF\n");
+}
+
+#[test]
+fn collect_tracks_entities() {
+    init();
+
+    let worker_manager = WorkerManager::new();
+    let metrics_collector = MetricsCollector::new(&worker_manager);
+
+    future::block_on(async move {
+        let worker = worker_manager
+            .create_worker(WorkerSettings::default())
+            .await
+            .expect("Failed to create worker");
+
+        let router = worker
+            .create_router(RouterOptions::new(vec![RtpCodecCapability::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                preferred_payload_type: None,
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }]))
+            .await
+            .expect("Failed to create router");
+
+        let transport = router
+            .create_webrtc_transport(WebRtcTransportOptions::new(
+                WebRtcTransportListenInfos::new(ListenInfo {
+                    protocol: Protocol::Udp,
+                    ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    announced_ip: None,
+                    port: None,
+                    send_buffer_size: None,
+                    recv_buffer_size: None,
+                }),
+            ))
+            .await
+            .expect("Failed to create transport");
+
+        let producer = transport
+            .produce(ProducerOptions::new(
+                MediaKind::Audio,
+                RtpParameters {
+                    mid: Some("AUDIO".to_string()),
+                    codecs: vec![RtpCodecParameters::Audio {
+                        mime_type: MimeTypeAudio::Opus,
+                        payload_type: 111,
+                        clock_rate: NonZeroU32::new(48000).unwrap(),
+                        channels: NonZeroU8::new(2).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![],
+                    }],
+                    ..RtpParameters::default()
+                },
+            ))
+            .await
+            .expect("Failed to produce");
+
+        assert_eq!(metrics_collector.render(), "# EOF\n");
+
+        metrics_collector.collect().await;
+
+        let output = metrics_collector.render();
+        assert!(output.contains(&format!(
+            "mediasoup_worker_routers{{worker_id=\"{}\"}} 1\n",
+            worker.id(),
+        )));
+        assert!(output.contains(&format!(
+            "mediasoup_transport_received_bytes_total{{worker_id=\"{}\",router_id=\"{}\",transport_id=\"{}\",type=\"webrtc\"}} 0\n",
+            worker.id(),
+            router.id(),
+            transport.id(),
+        )));
+        assert!(output.ends_with("# EOF\n"));
+
+        {
+            let tracked = metrics_collector.inner.tracked.lock();
+            assert_eq!(tracked.transports.len(), 1);
+            assert_eq!(tracked.producers.len(), 1);
+        }
+
+        drop(producer);
+        drop(transport);
+
+        {
+            let tracked = metrics_collector.inner.tracked.lock();
+            assert!(tracked.transports.is_empty());
+            assert!(tracked.producers.is_empty());
+        }
+
+        metrics_collector.collect().await;
+
+        assert!(!metrics_collector
+            .render()
+            .contains("mediasoup_transport_received_bytes_total"));
+    });
+}
@@ -0,0 +1,459 @@
+//! Negotiation of RTP capabilities between a router and a remote endpoint.
+//!
+//! RTP capabilities received from a remote endpoint can be given to
+//! [`ConsumerOptions`](crate::consumer::ConsumerOptions) directly, but then it is hard to tell why
+//! some codec or header extension ended up not being used. [`negotiate()`] (also available as
+//! [`Router::negotiate_rtp_capabilities()`](crate::router::Router::negotiate_rtp_capabilities))
+//! computes the intersection of router and remote RTP capabilities upfront and explains every
+//! codec and header extension that was rejected.
+//!
+//! When a particular producer can't be consumed,
+//! [`Router::check_consume()`](crate::router::Router::check_consume) explains why with
+//! [`ConsumeIncompatibility`].
+//!
+//! # Example
+//! ```no_run
+//! # use mediasoup::router::Router;
+//! # use mediasoup::rtp_parameters::RtpCapabilities;
+//! # fn f(router: Router, remote_rtp_capabilities: RtpCapabilities) {
+//! let negotiation = router
+//!     .negotiate_rtp_capabilities(&remote_rtp_capabilities)
+//!     .unwrap();
+//!
+//! for rejected_codec in &negotiation.rejected_codecs {
+//!     println!(
+//!         "Codec {:?} rejected: {}",
+//!         rejected_codec.mime_type, rejected_codec.reason,
+//!     );
+//! }
+//!
+//! // Use `negotiation.rtp_capabilities` in `ConsumerOptions`
+//! # }
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::ortc::{self, RtpCapabilitiesError};
+use crate::producer::ProducerId;
+use crate::rtp_parameters::{
+    MediaKind, MimeType, MimeTypeAudio, MimeTypeVideo, RtpCapabilities, RtpCapabilitiesFinalized,
+    RtpCodecCapability, RtpCodecCapabilityFinalized, RtpCodecParametersParametersValue,
+    RtpHeaderExtension, RtpHeaderExtensionDirection, RtpHeaderExtensionUri,
+};
+use std::fmt;
+use thiserror::Error;
+
+/// Reason two codecs don't match.
+#[derive(Debug, Copy, Clone, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CodecMismatch {
+    /// Different MIME types.
+    #[error("Different MIME types")]
+    MimeType,
+    /// Different number of channels.
+    #[error("Different number of channels")]
+    Channels,
+    /// Different clock rates.
+    #[error("Different clock rates")]
+    ClockRate,
+    /// Different `num_streams` of multi-channel Opus.
+    #[error("Different number of multi-channel Opus streams")]
+    NumStreams,
+    /// Different `coupled_streams` of multi-channel Opus.
+    #[error("Different number of coupled multi-channel Opus streams")]
+    CoupledStreams,
+    /// Different H264 `packetization-mode`.
+    #[error("Different H264 packetization mode")]
+    PacketizationMode,
+    /// Different H264 profile (from `profile-level-id`), VP9 `profile-id` or AV1 `profile`.
+    #[error("Different profile")]
+    Profile,
+    /// No H264 `profile-level-id` can be selected for the answer.
+    #[error("Incompatible H264 profile level")]
+    ProfileLevelId,
+}
+
+/// Reason codec was rejected during negotiation.
+#[derive(Debug, Copy, Clone, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CodecRejection {
+    /// Router codec is not supported by the remote endpoint.
+    #[error("Not supported by remote endpoint")]
+    NotSupportedByRemote,
+    /// Remote endpoint supports codec with the same MIME type as router codec, but it doesn't
+    /// match.
+    #[error("Doesn't match codec of remote endpoint: {0}")]
+    Mismatch(CodecMismatch),
+    /// Router RTX codec is associated with media codec that was rejected.
+    #[error("Associated media codec was rejected")]
+    MediaCodecRejected,
+    /// Remote codec doesn't match any of router codecs.
+    #[error("Not supported by router")]
+    NotSupportedByRouter,
+}
+
+/// Codec rejected during negotiation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RejectedCodec {
+    /// Codec MIME type.
+    pub mime_type: MimeType,
+    /// Preferred payload type of the codec, in router capabilities or remote capabilities
+    /// depending on where the codec comes from (see [`CodecRejection`]).
+    pub preferred_payload_type: Option<u8>,
+    /// Why codec was rejected.
+    pub reason: CodecRejection,
+}
+
+/// Reason RTP header extension was rejected during negotiation.
+#[derive(Debug, Copy, Clone, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HeaderExtensionRejection {
+    /// Router header extension is not supported by the remote endpoint.
+    #[error("Not supported by remote endpoint")]
+    NotSupportedByRemote,
+    /// Router header extension can't be sent by router to consumers.
+    #[error("Not sent by router")]
+    NotSentByRouter,
+    /// Remote endpoint expects the header extension with a different id.
+    #[error("Remote endpoint expects id {remote_preferred_id}")]
+    IdMismatch {
+        /// Preferred id in remote capabilities.
+        remote_preferred_id: u16,
+    },
+    /// Remote header extension is not supported by router.
+    #[error("Not supported by router")]
+    NotSupportedByRouter,
+}
+
+/// RTP header extension rejected during negotiation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RejectedHeaderExtension {
+    /// Media kind.
+    pub kind: MediaKind,
+    /// Header extension URI.
+    pub uri: RtpHeaderExtensionUri,
+    /// Preferred id of the header extension, in router capabilities or remote capabilities
+    /// depending on where the header extension comes from (see [`HeaderExtensionRejection`]).
+    pub preferred_id: u16,
+    /// Why header extension was rejected.
+    pub reason: HeaderExtensionRejection,
+}
+
+/// Result of [`negotiate()`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RtpCapabilitiesNegotiation {
+    /// Capabilities supported by both router and remote endpoint, with router payload types and
+    /// header extension ids, ready to be used in
+    /// [`ConsumerOptions`](crate::consumer::ConsumerOptions).
+    pub rtp_capabilities: RtpCapabilities,
+    /// Codecs of either side that were rejected.
+    pub rejected_codecs: Vec<RejectedCodec>,
+    /// Header extensions of either side that were rejected.
+    pub rejected_header_extensions: Vec<RejectedHeaderExtension>,
+}
+
+/// Details of codec mismatch between producer codec and codec in RTP capabilities.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CodecMismatchDetails {
+    /// Preferred payload type of the codec in RTP capabilities.
+    pub preferred_payload_type: Option<u8>,
+    /// What doesn't match.
+    pub mismatch: CodecMismatch,
+    /// Mismatched value (parameter, number of channels or clock rate) of the producer codec,
+    /// `None` if parameter is missing.
+    pub producer_value: Option<String>,
+    /// Mismatched value of the codec in RTP capabilities, `None` if parameter is missing.
+    pub capability_value: Option<String>,
+}
+
+impl fmt::Display for CodecMismatchDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(preferred_payload_type) = self.preferred_payload_type {
+            write!(f, "[PT {preferred_payload_type}] ")?;
+        }
+        write!(
+            f,
+            "{} (producer: {}, capabilities: {})",
+            self.mismatch,
+            self.producer_value.as_deref().unwrap_or("none"),
+            self.capability_value.as_deref().unwrap_or("none"),
+        )
+    }
+}
+
+/// Producer codec that doesn't match any codec in RTP capabilities.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CodecIncompatibility {
+    /// MIME type of the producer codec.
+    pub mime_type: MimeType,
+    /// Payload type of the producer codec as consumed.
+    pub payload_type: u8,
+    /// Mismatches with codecs of the same MIME type in RTP capabilities, empty if RTP capabilities
+    /// don't have any.
+    pub mismatches: Vec<CodecMismatchDetails>,
+}
+
+impl fmt::Display for CodecIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (PT {}): ",
+            self.mime_type.as_str(),
+            self.payload_type
+        )?;
+        if self.mismatches.is_empty() {
+            return write!(f, "not in RTP capabilities");
+        }
+        for (index, mismatch) in self.mismatches.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Reason RTP capabilities can't consume a producer, see
+/// [`Router::check_consume()`](crate::router::Router::check_consume).
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum ConsumeIncompatibility {
+    /// Producer with given id not found.
+    #[error("Producer with id \"{0}\" not found")]
+    ProducerNotFound(ProducerId),
+    /// Invalid RTP capabilities.
+    #[error("Invalid RTP capabilities: {0}")]
+    InvalidCapabilities(RtpCapabilitiesError),
+    /// None of producer media codecs matches RTP capabilities.
+    #[error("No compatible media codecs: {}", join(codecs))]
+    NoCompatibleMediaCodecs {
+        /// Producer media codecs and why they don't match.
+        codecs: Vec<CodecIncompatibility>,
+        /// Producer header extensions that would not be used by the consumer either.
+        header_extensions: Vec<RejectedHeaderExtension>,
+    },
+}
+
+/// Router codec as remote codec capability, with RTCP feedback supported by both sides.
+fn negotiated_codec(
+    router_codec: &RtpCodecCapabilityFinalized,
+    remote_codec: &RtpCodecCapability,
+    profile_level_id: Option<String>,
+) -> RtpCodecCapability {
+    let mut parameters = router_codec.parameters().clone();
+    if let Some(profile_level_id) = profile_level_id {
+        parameters.insert("profile-level-id", profile_level_id);
+    }
+    let rtcp_feedback = router_codec
+        .rtcp_feedback()
+        .iter()
+        .filter(|fb| remote_codec.rtcp_feedback().contains(fb))
+        .copied()
+        .collect();
+
+    match router_codec {
+        RtpCodecCapabilityFinalized::Audio {
+            mime_type,
+            preferred_payload_type,
+            clock_rate,
+            channels,
+            ..
+        } => RtpCodecCapability::Audio {
+            mime_type: *mime_type,
+            preferred_payload_type: Some(*preferred_payload_type),
+            clock_rate: *clock_rate,
+            channels: *channels,
+            parameters,
+            rtcp_feedback,
+        },
+        RtpCodecCapabilityFinalized::Video {
+            mime_type,
+            preferred_payload_type,
+            clock_rate,
+            ..
+        } => RtpCodecCapability::Video {
+            mime_type: *mime_type,
+            preferred_payload_type: Some(*preferred_payload_type),
+            clock_rate: *clock_rate,
+            parameters,
+            rtcp_feedback,
+        },
+    }
+}
+
+/// Compute intersection of router and remote RTP capabilities for consuming, explaining rejected
+/// codecs and header extensions.
+///
+/// Codecs keep the order of router capabilities.
+pub fn negotiate(
+    router_rtp_capabilities: &RtpCapabilitiesFinalized,
+    remote_rtp_capabilities: &RtpCapabilities,
+) -> Result<RtpCapabilitiesNegotiation, RtpCapabilitiesError> {
+    ortc::validate_rtp_capabilities(remote_rtp_capabilities)?;
+
+    let mut negotiation = RtpCapabilitiesNegotiation::default();
+    let mut accepted_payload_types = Vec::<u8>::new();
+
+    for router_codec in &router_rtp_capabilities.codecs {
+        let preferred_payload_type = router_codec.preferred_payload_type();
+
+        if router_codec.is_rtx() {
+            let media_codec_accepted = matches!(
+                router_codec.parameters().get("apt"),
+                Some(RtpCodecParametersParametersValue::Number(apt))
+                    if accepted_payload_types.iter().any(|&pt| u32::from(pt) == *apt)
+            );
+            if !media_codec_accepted {
+                negotiation.rejected_codecs.push(RejectedCodec {
+                    mime_type: router_codec.mime_type(),
+                    preferred_payload_type: Some(preferred_payload_type),
+                    reason: CodecRejection::MediaCodecRejected,
+                });
+                continue;
+            }
+        }
+
+        let mut mismatch = None;
+        let matched = remote_rtp_capabilities
+            .codecs
+            .iter()
+            .find_map(|remote_codec| {
+                match ortc::match_codecs(remote_codec.into(), router_codec.into(), true) {
+                    Ok(profile_level_id) => Some((remote_codec, profile_level_id)),
+                    Err(CodecMismatch::MimeType) => None,
+                    Err(error) => {
+                        mismatch.get_or_insert(error);
+                        None
+                    }
+                }
+            });
+
+        match matched {
+            Some((remote_codec, profile_level_id)) => {
+                accepted_payload_types.push(preferred_payload_type);
+                negotiation.rtp_capabilities.codecs.push(negotiated_codec(
+                    router_codec,
+                    remote_codec,
+                    profile_level_id,
+                ));
+            }
+            None => {
+                negotiation.rejected_codecs.push(RejectedCodec {
+                    mime_type: router_codec.mime_type(),
+                    preferred_payload_type: Some(preferred_payload_type),
+                    reason: mismatch.map_or(CodecRejection::NotSupportedByRemote, |mismatch| {
+                        CodecRejection::Mismatch(mismatch)
+                    }),
+                });
+            }
+        }
+    }
+
+    for remote_codec in &remote_rtp_capabilities.codecs {
+        let mime_type = remote_codec.mime_type();
+        // RTX codecs are only meaningful along with their media codecs
+        if is_rtx(mime_type) {
+            continue;
+        }
+        let supported = router_rtp_capabilities.codecs.iter().any(|router_codec| {
+            ortc::match_codecs(remote_codec.into(), router_codec.into(), true).is_ok()
+        });
+        if !supported {
+            negotiation.rejected_codecs.push(RejectedCodec {
+                mime_type,
+                preferred_payload_type: remote_codec.preferred_payload_type(),
+                reason: CodecRejection::NotSupportedByRouter,
+            });
+        }
+    }
+
+    for router_ext in &router_rtp_capabilities.header_extensions {
+        let reason = if matches!(
+            router_ext.direction,
+            RtpHeaderExtensionDirection::RecvOnly | RtpHeaderExtensionDirection::Inactive
+        ) {
+            Some(HeaderExtensionRejection::NotSentByRouter)
+        } else {
+            match find_header_extension(remote_rtp_capabilities, router_ext) {
+                None => Some(HeaderExtensionRejection::NotSupportedByRemote),
+                Some(remote_ext) if remote_ext.preferred_id != router_ext.preferred_id => {
+                    Some(HeaderExtensionRejection::IdMismatch {
+                        remote_preferred_id: remote_ext.preferred_id,
+                    })
+                }
+                Some(_) => None,
+            }
+        };
+
+        match reason {
+            Some(reason) => {
+                negotiation
+                    .rejected_header_extensions
+                    .push(RejectedHeaderExtension {
+                        kind: router_ext.kind,
+                        uri: router_ext.uri,
+                        preferred_id: router_ext.preferred_id,
+                        reason,
+                    });
+            }
+            None => {
+                negotiation
+                    .rtp_capabilities
+                    .header_extensions
+                    .push(*router_ext);
+            }
+        }
+    }
+
+    for remote_ext in &remote_rtp_capabilities.header_extensions {
+        let supported = router_rtp_capabilities
+            .header_extensions
+            .iter()
+            .any(|router_ext| {
+                router_ext.kind == remote_ext.kind && router_ext.uri == remote_ext.uri
+            });
+        if !supported {
+            negotiation
+                .rejected_header_extensions
+                .push(RejectedHeaderExtension {
+                    kind: remote_ext.kind,
+                    uri: remote_ext.uri,
+                    preferred_id: remote_ext.preferred_id,
+                    reason: HeaderExtensionRejection::NotSupportedByRouter,
+                });
+        }
+    }
+
+    Ok(negotiation)
+}
+
+fn is_rtx(mime_type: MimeType) -> bool {
+    matches!(
+        mime_type,
+        MimeType::Audio(MimeTypeAudio::Rtx) | MimeType::Video(MimeTypeVideo::Rtx)
+    )
+}
+
+fn find_header_extension<'a>(
+    rtp_capabilities: &'a RtpCapabilities,
+    ext: &RtpHeaderExtension,
+) -> Option<&'a RtpHeaderExtension> {
+    rtp_capabilities
+        .header_extensions
+        .iter()
+        .find(|remote_ext| remote_ext.kind == ext.kind && remote_ext.uri == ext.uri)
+}
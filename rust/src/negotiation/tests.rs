@@ -0,0 +1,243 @@
+use super::*;
+use crate::rtp_parameters::{RtcpFeedback, RtpCodecParametersParameters};
+use std::num::{NonZeroU32, NonZeroU8};
+
+fn router_rtp_capabilities() -> RtpCapabilitiesFinalized {
+    ortc::generate_router_rtp_capabilities(vec![
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::H264,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::from([
+                ("packetization-mode", 1_u32.into()),
+                ("profile-level-id", "42e01f".into()),
+            ]),
+            rtcp_feedback: vec![],
+        },
+    ])
+    .expect("Failed to generate router RTP capabilities")
+}
+
+fn header_extension(kind: MediaKind, uri: RtpHeaderExtensionUri, id: u16) -> RtpHeaderExtension {
+    RtpHeaderExtension {
+        kind,
+        uri,
+        preferred_id: id,
+        preferred_encrypt: false,
+        direction: RtpHeaderExtensionDirection::SendRecv,
+    }
+}
+
+fn remote_rtp_capabilities() -> RtpCapabilities {
+    RtpCapabilities {
+        codecs: vec![
+            RtpCodecCapability::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                preferred_payload_type: Some(111),
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![RtcpFeedback::TransportCc],
+            },
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                preferred_payload_type: Some(96),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+            },
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Rtx,
+                preferred_payload_type: Some(97),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::from([("apt", 96_u32.into())]),
+                rtcp_feedback: vec![],
+            },
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::H264,
+                preferred_payload_type: Some(102),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::from([
+                    ("packetization-mode", 0_u32.into()),
+                    ("profile-level-id", "42e01f".into()),
+                ]),
+                rtcp_feedback: vec![],
+            },
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Vp9,
+                preferred_payload_type: Some(98),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            },
+        ],
+        header_extensions: vec![
+            header_extension(MediaKind::Video, RtpHeaderExtensionUri::Mid, 1),
+            header_extension(MediaKind::Video, RtpHeaderExtensionUri::AbsSendTime, 14),
+            header_extension(MediaKind::Video, RtpHeaderExtensionUri::RtpStreamId, 2),
+            header_extension(
+                MediaKind::Audio,
+                RtpHeaderExtensionUri::VideoOrientation,
+                13,
+            ),
+        ],
+    }
+}
+
+fn codec_rejection(
+    negotiation: &RtpCapabilitiesNegotiation,
+    mime_type: MimeType,
+) -> Vec<CodecRejection> {
+    negotiation
+        .rejected_codecs
+        .iter()
+        .filter(|rejected_codec| rejected_codec.mime_type == mime_type)
+        .map(|rejected_codec| rejected_codec.reason)
+        .collect()
+}
+
+fn header_extension_rejection(
+    negotiation: &RtpCapabilitiesNegotiation,
+    kind: MediaKind,
+    uri: RtpHeaderExtensionUri,
+) -> Option<HeaderExtensionRejection> {
+    negotiation
+        .rejected_header_extensions
+        .iter()
+        .find(|rejected_ext| rejected_ext.kind == kind && rejected_ext.uri == uri)
+        .map(|rejected_ext| rejected_ext.reason)
+}
+
+#[test]
+fn negotiate_codecs() {
+    let router_rtp_capabilities = router_rtp_capabilities();
+    let negotiation = negotiate(&router_rtp_capabilities, &remote_rtp_capabilities())
+        .expect("Failed to negotiate");
+
+    assert_eq!(
+        negotiation
+            .rtp_capabilities
+            .codecs
+            .iter()
+            .map(RtpCodecCapability::mime_type)
+            .collect::<Vec<_>>(),
+        vec![
+            MimeType::Audio(MimeTypeAudio::Opus),
+            MimeType::Video(MimeTypeVideo::Vp8),
+            MimeType::Video(MimeTypeVideo::Rtx),
+        ],
+    );
+    // Router payload types are used
+    assert_eq!(
+        negotiation.rtp_capabilities.codecs[1].preferred_payload_type(),
+        Some(router_rtp_capabilities.codecs[1].preferred_payload_type()),
+    );
+    // Only RTCP feedback supported by both sides is kept
+    assert_eq!(
+        negotiation.rtp_capabilities.codecs[0].rtcp_feedback(),
+        &vec![RtcpFeedback::TransportCc],
+    );
+    assert_eq!(
+        negotiation.rtp_capabilities.codecs[1].rtcp_feedback(),
+        &vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+    );
+
+    assert_eq!(
+        codec_rejection(&negotiation, MimeType::Video(MimeTypeVideo::H264)),
+        vec![
+            CodecRejection::Mismatch(CodecMismatch::PacketizationMode),
+            CodecRejection::NotSupportedByRouter,
+        ],
+    );
+    // RTX of H264
+    assert_eq!(
+        codec_rejection(&negotiation, MimeType::Video(MimeTypeVideo::Rtx)),
+        vec![CodecRejection::MediaCodecRejected],
+    );
+    assert_eq!(
+        codec_rejection(&negotiation, MimeType::Video(MimeTypeVideo::Vp9)),
+        vec![CodecRejection::NotSupportedByRouter],
+    );
+    assert_eq!(
+        negotiation
+            .rejected_codecs
+            .iter()
+            .find(|rejected_codec| rejected_codec.reason == CodecRejection::NotSupportedByRouter)
+            .and_then(|rejected_codec| rejected_codec.preferred_payload_type),
+        Some(102),
+    );
+}
+
+#[test]
+fn negotiate_header_extensions() {
+    let negotiation = negotiate(&router_rtp_capabilities(), &remote_rtp_capabilities())
+        .expect("Failed to negotiate");
+
+    assert_eq!(
+        negotiation
+            .rtp_capabilities
+            .header_extensions
+            .iter()
+            .map(|ext| (ext.kind, ext.uri))
+            .collect::<Vec<_>>(),
+        vec![(MediaKind::Video, RtpHeaderExtensionUri::Mid)],
+    );
+    assert_eq!(
+        header_extension_rejection(
+            &negotiation,
+            MediaKind::Video,
+            RtpHeaderExtensionUri::AbsSendTime,
+        ),
+        Some(HeaderExtensionRejection::IdMismatch {
+            remote_preferred_id: 14,
+        }),
+    );
+    assert_eq!(
+        header_extension_rejection(
+            &negotiation,
+            MediaKind::Video,
+            RtpHeaderExtensionUri::RtpStreamId,
+        ),
+        Some(HeaderExtensionRejection::NotSentByRouter),
+    );
+    assert_eq!(
+        header_extension_rejection(
+            &negotiation,
+            MediaKind::Audio,
+            RtpHeaderExtensionUri::VideoOrientation,
+        ),
+        Some(HeaderExtensionRejection::NotSupportedByRouter),
+    );
+    assert_eq!(
+        header_extension_rejection(&negotiation, MediaKind::Audio, RtpHeaderExtensionUri::Mid),
+        Some(HeaderExtensionRejection::NotSupportedByRemote),
+    );
+}
+
+#[test]
+fn negotiate_invalid_capabilities() {
+    let mut remote_rtp_capabilities = remote_rtp_capabilities();
+    remote_rtp_capabilities.codecs[2]
+        .parameters_mut()
+        .insert("apt", "96");
+
+    assert_eq!(
+        negotiate(&router_rtp_capabilities(), &remote_rtp_capabilities),
+        Err(RtpCapabilitiesError::InvalidAptParameter("96".into())),
+    );
+}
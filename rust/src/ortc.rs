@@ -1,3 +1,7 @@
+use crate::negotiation::{
+    CodecIncompatibility, CodecMismatch, CodecMismatchDetails, ConsumeIncompatibility,
+    HeaderExtensionRejection, RejectedHeaderExtension,
+};
 use crate::rtp_parameters::{
     MediaKind, MimeType, MimeTypeAudio, MimeTypeVideo, RtcpFeedback, RtcpParameters,
     RtpCapabilities, RtpCapabilitiesFinalized, RtpCodecCapability, RtpCodecCapabilityFinalized,
@@ -34,6 +38,15 @@ pub struct RtpMappingCodec {
     pub mapped_payload_type: u8,
 }
 
+impl RtpMappingCodec {
+    pub(crate) fn to_fbs(self) -> rtp_parameters::CodecMapping {
+        rtp_parameters::CodecMapping {
+            payload_type: self.payload_type,
+            mapped_payload_type: self.mapped_payload_type,
+        }
+    }
+}
+
 #[doc(hidden)]
 #[derive(Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -60,10 +73,8 @@ impl RtpMapping {
             codecs: self
                 .codecs
                 .iter()
-                .map(|mapping| rtp_parameters::CodecMapping {
-                    payload_type: mapping.payload_type,
-                    mapped_payload_type: mapping.mapped_payload_type,
-                })
+                .copied()
+                .map(RtpMappingCodec::to_fbs)
                 .collect(),
             encodings: self
                 .encodings
@@ -80,7 +91,7 @@ impl RtpMapping {
 
     pub(crate) fn from_fbs_ref(
         mapping: rtp_parameters::RtpMappingRef<'_>,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             codecs: mapping
                 .codecs()?
@@ -91,7 +102,7 @@ impl RtpMapping {
                         mapped_payload_type: mapping?.mapped_payload_type()?,
                     })
                 })
-                .collect::<Result<Vec<_>, Box<dyn Error>>>()?,
+                .collect::<Result<Vec<_>, Box<dyn Error + Send + Sync>>>()?,
             encodings: mapping
                 .encodings()?
                 .iter()
@@ -107,7 +118,7 @@ impl RtpMapping {
                         mapped_ssrc: mapping?.mapped_ssrc()?,
                     })
                 })
-                .collect::<Result<Vec<_>, Box<dyn Error>>>()?,
+                .collect::<Result<Vec<_>, Box<dyn Error + Send + Sync>>>()?,
         })
     }
 }
@@ -163,6 +174,14 @@ pub enum RtpParametersMappingError {
         /// Payload type.
         payload_type: u8,
     },
+    /// Simulcast is not supported for codec.
+    #[error("Simulcast is not supported for codec [mime_type:{mime_type:?}, payloadType:{payload_type}]")]
+    UnsupportedSimulcast {
+        /// Mime type.
+        mime_type: MimeType,
+        /// Payload type.
+        payload_type: u8,
+    },
 }
 
 /// Error caused by bad consumer RTP parameters.
@@ -174,6 +193,9 @@ pub enum ConsumerRtpParametersError {
     /// No compatible media codecs
     #[error("No compatible media codecs")]
     NoCompatibleMediaCodecs,
+    /// None of compatible media codecs is among codec preferences
+    #[error("None of compatible media codecs is among codec preferences")]
+    NoPreferredMediaCodecs,
 }
 
 fn generate_ssrc() -> u32 {
@@ -422,6 +444,29 @@ pub(crate) fn get_producer_rtp_parameters_mapping(
         }
     }
 
+    // Worker can't switch between simulcast streams of AV1 (it doesn't parse AV1 payloads), while
+    // SVC layers of a single AV1 stream are forwarded as is.
+    if rtp_parameters.encodings.len() > 1 {
+        for encoding in &rtp_parameters.encodings {
+            let codec = match encoding.codec_payload_type {
+                Some(payload_type) => rtp_parameters
+                    .codecs
+                    .iter()
+                    .find(|codec| codec.payload_type() == payload_type),
+                None => rtp_parameters.codecs.iter().find(|codec| !codec.is_rtx()),
+            };
+
+            if let Some(codec) = codec {
+                if codec.mime_type() == MimeType::Video(MimeTypeVideo::Av1) {
+                    return Err(RtpParametersMappingError::UnsupportedSimulcast {
+                        mime_type: codec.mime_type(),
+                        payload_type: codec.payload_type(),
+                    });
+                }
+            }
+        }
+    }
+
     // Match parameters RTX codecs to capabilities RTX codecs.
     for codec in &rtp_parameters.codecs {
         if !codec.is_rtx() {
@@ -673,37 +718,133 @@ pub(crate) fn can_consume(
     consumable_params: &RtpParameters,
     caps: &RtpCapabilities,
 ) -> Result<bool, RtpCapabilitiesError> {
-    validate_rtp_capabilities(caps)?;
+    match check_consume(consumable_params, caps) {
+        Ok(()) => Ok(true),
+        Err(ConsumeIncompatibility::InvalidCapabilities(error)) => Err(error),
+        Err(_) => Ok(false),
+    }
+}
 
-    let mut matching_codecs = Vec::<&RtpCodecParameters>::new();
+/// Check whether the given RTP capabilities can consume the given Producer, explaining why not
+/// otherwise.
+pub(crate) fn check_consume(
+    consumable_params: &RtpParameters,
+    caps: &RtpCapabilities,
+) -> Result<(), ConsumeIncompatibility> {
+    validate_rtp_capabilities(caps).map_err(ConsumeIncompatibility::InvalidCapabilities)?;
 
-    for codec in &consumable_params.codecs {
-        if caps
-            .codecs
-            .iter()
-            .any(|cap_codec| match_codecs(cap_codec.deref().into(), codec.into(), true).is_ok())
-        {
-            matching_codecs.push(codec);
+    let mut codecs = Vec::new();
+
+    // There must be at least one matching media codec.
+    for codec in consumable_params
+        .codecs
+        .iter()
+        .filter(|codec| !codec.is_rtx())
+    {
+        let mut mismatches = Vec::new();
+
+        for cap_codec in &caps.codecs {
+            let cap_codec_to_match = CodecToMatch::from(cap_codec.deref());
+            let codec_to_match = CodecToMatch::from(codec);
+
+            match match_codecs(cap_codec_to_match, codec_to_match, true) {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(CodecMismatch::MimeType) => {}
+                Err(mismatch) => {
+                    let (producer_value, capability_value) =
+                        mismatch_values(codec_to_match, cap_codec_to_match, mismatch);
+
+                    mismatches.push(CodecMismatchDetails {
+                        preferred_payload_type: cap_codec.preferred_payload_type(),
+                        mismatch,
+                        producer_value,
+                        capability_value,
+                    });
+                }
+            }
         }
+
+        codecs.push(CodecIncompatibility {
+            mime_type: codec.mime_type(),
+            payload_type: codec.payload_type(),
+            mismatches,
+        });
     }
 
-    // Ensure there is at least one media codec.
-    Ok(matching_codecs
-        .get(0)
-        .map(|codec| !codec.is_rtx())
-        .unwrap_or_default())
+    let kind = match consumable_params
+        .codecs
+        .first()
+        .map(RtpCodecParameters::mime_type)
+    {
+        Some(MimeType::Video(_)) => MediaKind::Video,
+        _ => MediaKind::Audio,
+    };
+    let header_extensions = consumable_params
+        .header_extensions
+        .iter()
+        .filter_map(|ext| {
+            let reason = match caps
+                .header_extensions
+                .iter()
+                .find(|cap_ext| cap_ext.kind == kind && cap_ext.uri == ext.uri)
+            {
+                None => HeaderExtensionRejection::NotSupportedByRemote,
+                Some(cap_ext) if cap_ext.preferred_id != ext.id => {
+                    HeaderExtensionRejection::IdMismatch {
+                        remote_preferred_id: cap_ext.preferred_id,
+                    }
+                }
+                Some(_) => {
+                    return None;
+                }
+            };
+
+            Some(RejectedHeaderExtension {
+                kind,
+                uri: ext.uri,
+                preferred_id: ext.id,
+                reason,
+            })
+        })
+        .collect();
+
+    Err(ConsumeIncompatibility::NoCompatibleMediaCodecs {
+        codecs,
+        header_extensions,
+    })
+}
+
+/// Find Router capability codec that given codec parameters (strictly) match, if any.
+pub(crate) fn find_matching_codec_capability<'a>(
+    codec: &RtpCodecParameters,
+    caps: &'a RtpCapabilitiesFinalized,
+) -> Option<&'a RtpCodecCapabilityFinalized> {
+    caps.codecs
+        .iter()
+        .find(|cap_codec| match_codecs(codec.into(), (*cap_codec).into(), true).is_ok())
 }
 
 /// Generate RTP parameters for a specific Consumer.
 ///
 /// It reduces encodings to just one and takes into account given RTP capabilities to reduce codecs,
 /// codecs' RTCP feedback and header extensions, and also enables or disabled RTX.
+///
+/// If codec preferences are given, only media codecs with listed MIME types are kept, ordered by
+/// preference.
+///
+/// If `remote_ids` is true, codecs and header extensions take payload types and ids preferred in
+/// given RTP capabilities instead of those of the Router, RTX codecs without a remote counterpart
+/// and header extensions whose id doesn't fit into One-Byte header are dropped.
 #[allow(clippy::suspicious_operation_groupings)]
 pub(crate) fn get_consumer_rtp_parameters(
     consumable_rtp_parameters: &RtpParameters,
     remote_rtp_capabilities: &RtpCapabilities,
     pipe: bool,
     enable_rtx: bool,
+    codec_preferences: &[MimeType],
+    remote_ids: bool,
 ) -> Result<RtpParameters, ConsumerRtpParametersError> {
     let mut consumer_params = RtpParameters {
         rtcp: consumable_rtp_parameters.rtcp.clone(),
@@ -716,6 +857,8 @@ pub(crate) fn get_consumer_rtp_parameters(
     }
 
     let mut rtx_supported = false;
+    // Router and remote payload types of media codecs, used if `remote_ids` is true.
+    let mut remote_payload_types = Vec::<(u8, u8)>::new();
 
     for mut codec in consumable_rtp_parameters.codecs.clone() {
         if !enable_rtx && codec.is_rtx() {
@@ -734,9 +877,96 @@ pub(crate) fn get_consumer_rtp_parameters(
                 .copied()
                 .collect();
 
+            if remote_ids && !codec.is_rtx() {
+                let payload_type = matched_cap_codec
+                    .preferred_payload_type()
+                    .unwrap_or_else(|| codec.payload_type());
+                // Another Router codec already took this payload type.
+                if remote_payload_types
+                    .iter()
+                    .any(|&(_, remote_payload_type)| remote_payload_type == payload_type)
+                {
+                    continue;
+                }
+                remote_payload_types.push((codec.payload_type(), payload_type));
+                *codec.payload_type_mut() = payload_type;
+            }
+
             consumer_params.codecs.push(codec);
         }
     }
+    if remote_ids {
+        let mut remote_rtx_payload_types = Vec::new();
+
+        consumer_params.codecs.retain_mut(|codec| {
+            if !codec.is_rtx() {
+                return true;
+            }
+            let remote_apt = match codec.parameters().get("apt") {
+                Some(RtpCodecParametersParametersValue::Number(apt)) => remote_payload_types
+                    .iter()
+                    .find(|&&(payload_type, _)| u32::from(payload_type) == *apt)
+                    .map(|&(_, remote_payload_type)| u32::from(remote_payload_type)),
+                _ => None,
+            };
+            let remote_payload_type = remote_apt.and_then(|remote_apt| {
+                remote_rtp_capabilities
+                    .codecs
+                    .iter()
+                    .filter(|cap_codec| cap_codec.mime_type() == codec.mime_type())
+                    .find(|cap_codec| {
+                        cap_codec.parameters().get("apt")
+                            == Some(&RtpCodecParametersParametersValue::Number(remote_apt))
+                    })
+                    .and_then(RtpCodecCapability::preferred_payload_type)
+                    .filter(|payload_type| {
+                        !remote_rtx_payload_types.contains(payload_type)
+                            && remote_payload_types
+                                .iter()
+                                .all(|&(_, remote_payload_type)| {
+                                    remote_payload_type != *payload_type
+                                })
+                    })
+            });
+
+            // RTX codec the remote endpoint didn't offer for this media codec is useless.
+            let (Some(remote_apt), Some(payload_type)) = (remote_apt, remote_payload_type) else {
+                return false;
+            };
+            remote_rtx_payload_types.push(payload_type);
+            *codec.payload_type_mut() = payload_type;
+            codec.parameters_mut().insert("apt", remote_apt);
+            true
+        });
+    }
+    if !codec_preferences.is_empty() {
+        let (rtx_codecs, media_codecs): (Vec<_>, Vec<_>) = mem::take(&mut consumer_params.codecs)
+            .into_iter()
+            .partition(RtpCodecParameters::is_rtx);
+        if media_codecs.is_empty() {
+            return Err(ConsumerRtpParametersError::NoCompatibleMediaCodecs);
+        }
+        for mime_type in codec_preferences {
+            if consumer_params
+                .codecs
+                .iter()
+                .any(|codec| codec.mime_type() == *mime_type)
+            {
+                continue;
+            }
+            consumer_params.codecs.extend(
+                media_codecs
+                    .iter()
+                    .filter(|codec| codec.mime_type() == *mime_type)
+                    .cloned(),
+            );
+        }
+        if consumer_params.codecs.is_empty() {
+            return Err(ConsumerRtpParametersError::NoPreferredMediaCodecs);
+        }
+        // RTX codecs of removed media codecs are removed below
+        consumer_params.codecs.extend(rtx_codecs);
+    }
     // Must sanitize the list of matched codecs by removing useless RTX codecs.
     let mut remove_codecs = Vec::new();
     for (idx, codec) in consumer_params.codecs.iter().enumerate() {
@@ -767,17 +997,43 @@ pub(crate) fn get_consumer_rtp_parameters(
         return Err(ConsumerRtpParametersError::NoCompatibleMediaCodecs);
     }
 
-    consumer_params.header_extensions = consumable_rtp_parameters
-        .header_extensions
-        .iter()
-        .filter(|ext| {
-            remote_rtp_capabilities
-                .header_extensions
-                .iter()
-                .any(|cap_ext| cap_ext.preferred_id == ext.id && cap_ext.uri == ext.uri)
-        })
-        .cloned()
-        .collect();
+    consumer_params.header_extensions = if remote_ids {
+        let kind = match consumer_params.codecs[0].mime_type() {
+            MimeType::Audio(_) => MediaKind::Audio,
+            MimeType::Video(_) => MediaKind::Video,
+        };
+
+        consumable_rtp_parameters
+            .header_extensions
+            .iter()
+            .filter_map(|ext| {
+                let cap_ext = remote_rtp_capabilities
+                    .header_extensions
+                    .iter()
+                    .find(|cap_ext| cap_ext.kind == kind && cap_ext.uri == ext.uri)?;
+
+                // Worker rewrites ids in One-Byte header only.
+                (1..=14)
+                    .contains(&cap_ext.preferred_id)
+                    .then(|| RtpHeaderExtensionParameters {
+                        id: cap_ext.preferred_id,
+                        ..ext.clone()
+                    })
+            })
+            .collect()
+    } else {
+        consumable_rtp_parameters
+            .header_extensions
+            .iter()
+            .filter(|ext| {
+                remote_rtp_capabilities
+                    .header_extensions
+                    .iter()
+                    .any(|cap_ext| cap_ext.preferred_id == ext.id && cap_ext.uri == ext.uri)
+            })
+            .cloned()
+            .collect()
+    };
 
     // Reduce codecs' RTCP feedback. Use Transport-CC if available, REMB otherwise.
     if consumer_params
@@ -873,6 +1129,32 @@ pub(crate) fn get_consumer_rtp_parameters(
     Ok(consumer_params)
 }
 
+/// Payload types of media codecs that given Consumer RTP parameters use instead of those of the
+/// consumable RTP parameters (which are the Router ones).
+pub(crate) fn get_consumer_payload_type_mapping(
+    consumable_rtp_parameters: &RtpParameters,
+    consumer_codecs: &[RtpCodecParameters],
+) -> Vec<RtpMappingCodec> {
+    consumer_codecs
+        .iter()
+        .filter(|codec| !codec.is_rtx())
+        .filter_map(|codec| {
+            let consumable_codec =
+                consumable_rtp_parameters
+                    .codecs
+                    .iter()
+                    .find(|consumable_codec| {
+                        match_codecs((*consumable_codec).into(), codec.into(), true).is_ok()
+                    })?;
+
+            (consumable_codec.payload_type() != codec.payload_type()).then(|| RtpMappingCodec {
+                payload_type: consumable_codec.payload_type(),
+                mapped_payload_type: codec.payload_type(),
+            })
+        })
+        .collect()
+}
+
 /// Generate RTP parameters for a pipe Consumer.
 ///
 /// It keeps all original consumable encodings and removes support for BWE. If
@@ -939,7 +1221,8 @@ pub(crate) fn get_pipe_consumer_rtp_parameters(
     consumer_params
 }
 
-struct CodecToMatch<'a> {
+#[derive(Copy, Clone)]
+pub(crate) struct CodecToMatch<'a> {
     channels: Option<NonZeroU8>,
     clock_rate: NonZeroU32,
     mime_type: MimeType,
@@ -1037,21 +1320,21 @@ impl<'a> From<&'a RtpCodecParameters> for CodecToMatch<'a> {
 }
 
 /// Returns selected `Ok(Some(profile-level-id))` for H264 codec and `Ok(None)` for others
-fn match_codecs(
+pub(crate) fn match_codecs(
     codec_a: CodecToMatch<'_>,
     codec_b: CodecToMatch<'_>,
     strict: bool,
-) -> Result<Option<String>, ()> {
+) -> Result<Option<String>, CodecMismatch> {
     if codec_a.mime_type != codec_b.mime_type {
-        return Err(());
+        return Err(CodecMismatch::MimeType);
     }
 
     if codec_a.channels != codec_b.channels {
-        return Err(());
+        return Err(CodecMismatch::Channels);
     }
 
     if codec_a.clock_rate != codec_b.clock_rate {
-        return Err(());
+        return Err(CodecMismatch::ClockRate);
     }
     // Per codec special checks.
     match codec_a.mime_type {
@@ -1060,14 +1343,14 @@ fn match_codecs(
             let num_streams_b = codec_b.parameters.get("num_streams");
 
             if num_streams_a != num_streams_b {
-                return Err(());
+                return Err(CodecMismatch::NumStreams);
             }
 
             let coupled_streams_a = codec_a.parameters.get("coupled_streams");
             let coupled_streams_b = codec_b.parameters.get("coupled_streams");
 
             if coupled_streams_a != coupled_streams_b {
-                return Err(());
+                return Err(CodecMismatch::CoupledStreams);
             }
         }
         MimeType::Video(MimeTypeVideo::H264 | MimeTypeVideo::H264Svc) => {
@@ -1082,7 +1365,7 @@ fn match_codecs(
                     .unwrap_or(&RtpCodecParametersParametersValue::Number(0));
 
                 if packetization_mode_a != packetization_mode_b {
-                    return Err(());
+                    return Err(CodecMismatch::PacketizationMode);
                 }
 
                 let profile_level_id_a =
@@ -1111,7 +1394,7 @@ fn match_codecs(
                             (profile_level_id_a, profile_level_id_b)
                         }
                         None => {
-                            return Err(());
+                            return Err(CodecMismatch::Profile);
                         }
                     };
 
@@ -1135,7 +1418,7 @@ fn match_codecs(
                     Ok(selected_profile_level_id) => {
                         Ok(Some(selected_profile_level_id.to_string()))
                     }
-                    Err(_) => Err(()),
+                    Err(_) => Err(CodecMismatch::ProfileLevelId),
                 };
             }
         }
@@ -1152,7 +1435,24 @@ fn match_codecs(
                     .unwrap_or(&RtpCodecParametersParametersValue::Number(0));
 
                 if profile_id_a != profile_id_b {
-                    return Err(());
+                    return Err(CodecMismatch::Profile);
+                }
+            }
+        }
+        MimeType::Video(MimeTypeVideo::Av1) => {
+            // If strict matching check profile.
+            if strict {
+                let profile_a = codec_a
+                    .parameters
+                    .get("profile")
+                    .unwrap_or(&RtpCodecParametersParametersValue::Number(0));
+                let profile_b = codec_b
+                    .parameters
+                    .get("profile")
+                    .unwrap_or(&RtpCodecParametersParametersValue::Number(0));
+
+                if profile_a != profile_b {
+                    return Err(CodecMismatch::Profile);
                 }
             }
         }
@@ -1162,3 +1462,73 @@ fn match_codecs(
 
     Ok(None)
 }
+
+/// Values of given codecs that caused the mismatch, in human-readable form.
+fn mismatch_values(
+    codec_a: CodecToMatch<'_>,
+    codec_b: CodecToMatch<'_>,
+    mismatch: CodecMismatch,
+) -> (Option<String>, Option<String>) {
+    let parameter = |codec: CodecToMatch<'_>, name: &str| {
+        codec.parameters.get(name).map(|value| match value {
+            RtpCodecParametersParametersValue::String(string) => string.to_string(),
+            RtpCodecParametersParametersValue::Number(number) => number.to_string(),
+        })
+    };
+
+    match mismatch {
+        CodecMismatch::MimeType => (
+            Some(codec_a.mime_type.as_str().to_string()),
+            Some(codec_b.mime_type.as_str().to_string()),
+        ),
+        CodecMismatch::Channels => (
+            codec_a.channels.map(|channels| channels.to_string()),
+            codec_b.channels.map(|channels| channels.to_string()),
+        ),
+        CodecMismatch::ClockRate => (
+            Some(codec_a.clock_rate.to_string()),
+            Some(codec_b.clock_rate.to_string()),
+        ),
+        CodecMismatch::NumStreams => (
+            parameter(codec_a, "num_streams"),
+            parameter(codec_b, "num_streams"),
+        ),
+        CodecMismatch::CoupledStreams => (
+            parameter(codec_a, "coupled_streams"),
+            parameter(codec_b, "coupled_streams"),
+        ),
+        CodecMismatch::PacketizationMode => (
+            parameter(codec_a, "packetization-mode"),
+            parameter(codec_b, "packetization-mode"),
+        ),
+        CodecMismatch::Profile | CodecMismatch::ProfileLevelId => {
+            if matches!(
+                codec_a.mime_type,
+                MimeType::Video(MimeTypeVideo::H264 | MimeTypeVideo::H264Svc)
+            ) {
+                // Include parsed profile and level, which are not obvious from the hex string
+                let profile_level_id = |codec| {
+                    parameter(codec, "profile-level-id").map(|profile_level_id| {
+                        match profile_level_id.parse::<h264_profile_level_id::ProfileLevelId>() {
+                            Ok(parsed) => format!(
+                                "{profile_level_id} ({:?}, {:?})",
+                                parsed.profile(),
+                                parsed.level(),
+                            ),
+                            Err(error) => format!("{profile_level_id} ({error})"),
+                        }
+                    })
+                };
+
+                (profile_level_id(codec_a), profile_level_id(codec_b))
+            } else if codec_a.mime_type == MimeType::Video(MimeTypeVideo::Av1) {
+                (parameter(codec_a, "profile"), parameter(codec_b, "profile"))
+            } else {
+                (
+                    parameter(codec_a, "profile-id"),
+                    parameter(codec_b, "profile-id"),
+                )
+            }
+        }
+    }
+}
@@ -455,6 +455,8 @@ fn get_producer_rtp_parameters_mapping_get_consumable_rtp_parameters_get_consume
         &remote_rtp_capabilities,
         false,
         true,
+        &[],
+        false,
     )
     .expect("Failed to get consumer RTP parameters");
 
@@ -703,3 +705,525 @@ fn get_producer_rtp_parameters_mapping_unsupported() {
         Err(RtpParametersMappingError::UnsupportedCodec { .. }),
     ));
 }
+
+#[test]
+fn get_consumer_rtp_parameters_codec_preferences() {
+    let video_codec = |mime_type, payload_type| RtpCodecParameters::Video {
+        mime_type,
+        payload_type,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    };
+    let rtx_codec = |payload_type, apt: u8| RtpCodecParameters::Video {
+        mime_type: MimeTypeVideo::Rtx,
+        payload_type,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::from([("apt", u32::from(apt).into())]),
+        rtcp_feedback: vec![],
+    };
+    let video_capability = |mime_type| RtpCodecCapability::Video {
+        mime_type,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    };
+
+    let consumable_rtp_parameters = RtpParameters {
+        codecs: vec![
+            video_codec(MimeTypeVideo::Vp8, 101),
+            rtx_codec(102, 101),
+            video_codec(MimeTypeVideo::Vp9, 103),
+            rtx_codec(104, 103),
+        ],
+        encodings: vec![RtpEncodingParameters {
+            ssrc: Some(11111111),
+            ..RtpEncodingParameters::default()
+        }],
+        ..RtpParameters::default()
+    };
+    let remote_rtp_capabilities = RtpCapabilities {
+        codecs: vec![
+            video_capability(MimeTypeVideo::Vp8),
+            video_capability(MimeTypeVideo::Vp9),
+            video_capability(MimeTypeVideo::Rtx),
+        ],
+        header_extensions: vec![],
+    };
+    let payload_types = |codec_preferences: &[MimeType]| {
+        get_consumer_rtp_parameters(
+            &consumable_rtp_parameters,
+            &remote_rtp_capabilities,
+            false,
+            true,
+            codec_preferences,
+            false,
+        )
+        .map(|rtp_parameters| {
+            rtp_parameters
+                .codecs
+                .iter()
+                .map(RtpCodecParameters::payload_type)
+                .collect::<Vec<_>>()
+        })
+    };
+
+    assert_eq!(payload_types(&[]), Ok(vec![101, 102, 103, 104]));
+    assert_eq!(
+        payload_types(&[
+            MimeType::Video(MimeTypeVideo::H264),
+            MimeType::Video(MimeTypeVideo::Vp9),
+            MimeType::Video(MimeTypeVideo::Vp8),
+        ]),
+        Ok(vec![103, 101, 102, 104]),
+    );
+    // RTX of the media codec that is not preferred is removed too
+    assert_eq!(
+        payload_types(&[MimeType::Video(MimeTypeVideo::Vp9)]),
+        Ok(vec![103, 104]),
+    );
+    assert_eq!(
+        payload_types(&[MimeType::Video(MimeTypeVideo::H264Svc)]),
+        Err(ConsumerRtpParametersError::NoPreferredMediaCodecs),
+    );
+}
+
+#[test]
+fn get_consumer_rtp_parameters_remote_ids() {
+    let video_codec = |mime_type, payload_type| RtpCodecParameters::Video {
+        mime_type,
+        payload_type,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    };
+    let rtx_codec = |payload_type, apt: u8| RtpCodecParameters::Video {
+        mime_type: MimeTypeVideo::Rtx,
+        payload_type,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::from([("apt", u32::from(apt).into())]),
+        rtcp_feedback: vec![],
+    };
+    let video_capability =
+        |mime_type, preferred_payload_type, parameters| RtpCodecCapability::Video {
+            mime_type,
+            preferred_payload_type: Some(preferred_payload_type),
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters,
+            rtcp_feedback: vec![],
+        };
+    let header_extension = |uri, id| RtpHeaderExtensionParameters {
+        uri,
+        id,
+        encrypt: false,
+    };
+    let header_extension_capability = |uri, preferred_id| RtpHeaderExtension {
+        kind: MediaKind::Video,
+        uri,
+        preferred_id,
+        preferred_encrypt: false,
+        direction: RtpHeaderExtensionDirection::SendRecv,
+    };
+
+    let consumable_rtp_parameters = RtpParameters {
+        codecs: vec![
+            video_codec(MimeTypeVideo::Vp8, 101),
+            rtx_codec(102, 101),
+            video_codec(MimeTypeVideo::Vp9, 103),
+            rtx_codec(104, 103),
+        ],
+        header_extensions: vec![
+            header_extension(RtpHeaderExtensionUri::Mid, 1),
+            header_extension(RtpHeaderExtensionUri::AbsSendTime, 4),
+            header_extension(RtpHeaderExtensionUri::TransportWideCcDraft01, 5),
+        ],
+        encodings: vec![RtpEncodingParameters {
+            ssrc: Some(11111111),
+            ..RtpEncodingParameters::default()
+        }],
+        ..RtpParameters::default()
+    };
+    // Payload types and ids of an SDP offer that differ from the Router ones, VP9 without RTX.
+    let remote_rtp_capabilities = RtpCapabilities {
+        codecs: vec![
+            video_capability(
+                MimeTypeVideo::Vp8,
+                96,
+                RtpCodecParametersParameters::default(),
+            ),
+            video_capability(
+                MimeTypeVideo::Rtx,
+                97,
+                RtpCodecParametersParameters::from([("apt", 96_u32.into())]),
+            ),
+            video_capability(
+                MimeTypeVideo::Vp9,
+                98,
+                RtpCodecParametersParameters::default(),
+            ),
+        ],
+        header_extensions: vec![
+            header_extension_capability(RtpHeaderExtensionUri::Mid, 9),
+            header_extension_capability(RtpHeaderExtensionUri::AbsSendTime, 3),
+            // Doesn't fit into One-Byte header.
+            header_extension_capability(RtpHeaderExtensionUri::TransportWideCcDraft01, 20),
+        ],
+    };
+
+    let consumer_rtp_parameters = get_consumer_rtp_parameters(
+        &consumable_rtp_parameters,
+        &remote_rtp_capabilities,
+        false,
+        true,
+        &[],
+        true,
+    )
+    .expect("Failed to get consumer RTP parameters");
+
+    assert_eq!(
+        consumer_rtp_parameters.codecs,
+        vec![
+            video_codec(MimeTypeVideo::Vp8, 96),
+            rtx_codec(97, 96),
+            video_codec(MimeTypeVideo::Vp9, 98),
+        ]
+    );
+    assert_eq!(
+        consumer_rtp_parameters.header_extensions,
+        vec![
+            header_extension(RtpHeaderExtensionUri::Mid, 9),
+            header_extension(RtpHeaderExtensionUri::AbsSendTime, 3),
+        ]
+    );
+    assert_eq!(
+        consumer_rtp_parameters.encodings[0].rtx,
+        Some(RtpEncodingParametersRtx {
+            ssrc: consumer_rtp_parameters.encodings[0].ssrc.unwrap() + 1
+        })
+    );
+    assert_eq!(
+        get_consumer_payload_type_mapping(
+            &consumable_rtp_parameters,
+            &consumer_rtp_parameters.codecs,
+        ),
+        vec![
+            RtpMappingCodec {
+                payload_type: 101,
+                mapped_payload_type: 96,
+            },
+            RtpMappingCodec {
+                payload_type: 103,
+                mapped_payload_type: 98,
+            },
+        ]
+    );
+
+    // Router payload types and ids are kept otherwise.
+    let consumer_rtp_parameters = get_consumer_rtp_parameters(
+        &consumable_rtp_parameters,
+        &remote_rtp_capabilities,
+        false,
+        true,
+        &[],
+        false,
+    )
+    .expect("Failed to get consumer RTP parameters");
+
+    assert_eq!(
+        get_consumer_payload_type_mapping(
+            &consumable_rtp_parameters,
+            &consumer_rtp_parameters.codecs,
+        ),
+        vec![]
+    );
+    assert_eq!(consumer_rtp_parameters.header_extensions, vec![]);
+}
+
+#[test]
+fn check_consume_explains_mismatches() {
+    let h264_parameters = |packetization_mode: u32, profile_level_id: &'static str| {
+        RtpCodecParametersParameters::from([
+            ("packetization-mode", packetization_mode.into()),
+            ("profile-level-id", profile_level_id.into()),
+        ])
+    };
+    let h264_capability = |preferred_payload_type, parameters| RtpCodecCapability::Video {
+        mime_type: MimeTypeVideo::H264,
+        preferred_payload_type: Some(preferred_payload_type),
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters,
+        rtcp_feedback: vec![],
+    };
+
+    let consumable_rtp_parameters = RtpParameters {
+        codecs: vec![RtpCodecParameters::Video {
+            mime_type: MimeTypeVideo::H264,
+            payload_type: 125,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: h264_parameters(1, "640032"),
+            rtcp_feedback: vec![],
+        }],
+        header_extensions: vec![
+            RtpHeaderExtensionParameters {
+                uri: RtpHeaderExtensionUri::Mid,
+                id: 1,
+                encrypt: false,
+            },
+            RtpHeaderExtensionParameters {
+                uri: RtpHeaderExtensionUri::AbsSendTime,
+                id: 4,
+                encrypt: false,
+            },
+        ],
+        ..RtpParameters::default()
+    };
+    let header_extension = |uri, preferred_id| RtpHeaderExtension {
+        kind: MediaKind::Video,
+        uri,
+        preferred_id,
+        preferred_encrypt: false,
+        direction: RtpHeaderExtensionDirection::SendRecv,
+    };
+    let mut rtp_capabilities = RtpCapabilities {
+        codecs: vec![
+            RtpCodecCapability::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                preferred_payload_type: Some(100),
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            },
+            h264_capability(102, h264_parameters(1, "42e01f")),
+            h264_capability(104, h264_parameters(0, "640032")),
+        ],
+        header_extensions: vec![
+            header_extension(RtpHeaderExtensionUri::Mid, 1),
+            header_extension(RtpHeaderExtensionUri::AbsSendTime, 14),
+        ],
+    };
+
+    let incompatibility = check_consume(&consumable_rtp_parameters, &rtp_capabilities)
+        .expect_err("Must not be able to consume");
+    assert_eq!(
+        incompatibility,
+        ConsumeIncompatibility::NoCompatibleMediaCodecs {
+            codecs: vec![CodecIncompatibility {
+                mime_type: MimeType::Video(MimeTypeVideo::H264),
+                payload_type: 125,
+                mismatches: vec![
+                    CodecMismatchDetails {
+                        preferred_payload_type: Some(102),
+                        mismatch: CodecMismatch::Profile,
+                        producer_value: Some("640032 (High, Level5)".to_string()),
+                        capability_value: Some("42e01f (ConstrainedBaseline, Level31)".to_string()),
+                    },
+                    CodecMismatchDetails {
+                        preferred_payload_type: Some(104),
+                        mismatch: CodecMismatch::PacketizationMode,
+                        producer_value: Some("1".to_string()),
+                        capability_value: Some("0".to_string()),
+                    },
+                ],
+            }],
+            header_extensions: vec![RejectedHeaderExtension {
+                kind: MediaKind::Video,
+                uri: RtpHeaderExtensionUri::AbsSendTime,
+                preferred_id: 4,
+                reason: HeaderExtensionRejection::IdMismatch {
+                    remote_preferred_id: 14,
+                },
+            }],
+        },
+    );
+    assert_eq!(
+        incompatibility.to_string(),
+        "No compatible media codecs: video/H264 (PT 125): [PT 102] Different profile \
+        (producer: 640032 (High, Level5), capabilities: 42e01f (ConstrainedBaseline, Level31)); \
+        [PT 104] Different H264 packetization mode (producer: 1, capabilities: 0)",
+    );
+    assert_eq!(
+        can_consume(&consumable_rtp_parameters, &rtp_capabilities),
+        Ok(false),
+    );
+
+    rtp_capabilities
+        .codecs
+        .push(h264_capability(106, h264_parameters(1, "640028")));
+    assert_eq!(
+        check_consume(&consumable_rtp_parameters, &rtp_capabilities),
+        Ok(()),
+    );
+    assert_eq!(
+        can_consume(&consumable_rtp_parameters, &rtp_capabilities),
+        Ok(true),
+    );
+}
+
+#[test]
+fn av1_profile_and_scalability_mode() {
+    let av1_parameters =
+        |profile: u32| RtpCodecParametersParameters::from([("profile", profile.into())]);
+
+    let router_rtp_capabilities =
+        generate_router_rtp_capabilities(vec![RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Av1,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        }])
+        .expect("Failed to generate router RTP capabilities");
+
+    assert_eq!(
+        router_rtp_capabilities.codecs[0].mime_type(),
+        MimeType::Video(MimeTypeVideo::Av1),
+    );
+    // Dependency descriptor is not advertised until worker understands it
+    assert!(router_rtp_capabilities
+        .header_extensions
+        .iter()
+        .all(|ext| ext.uri != RtpHeaderExtensionUri::DependencyDescriptor));
+
+    let mut rtp_parameters = RtpParameters {
+        mid: None,
+        codecs: vec![RtpCodecParameters::Video {
+            mime_type: MimeTypeVideo::Av1,
+            payload_type: 45,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: av1_parameters(1),
+            rtcp_feedback: vec![],
+        }],
+        header_extensions: vec![
+            RtpHeaderExtensionParameters {
+                uri: RtpHeaderExtensionUri::Mid,
+                id: 1,
+                encrypt: false,
+            },
+            RtpHeaderExtensionParameters {
+                uri: RtpHeaderExtensionUri::DependencyDescriptor,
+                id: 8,
+                encrypt: false,
+            },
+        ],
+        encodings: vec![RtpEncodingParameters {
+            ssrc: Some(11111111),
+            scalability_mode: "L3T3_KEY".parse().unwrap(),
+            ..RtpEncodingParameters::default()
+        }],
+        rtcp: RtcpParameters::default(),
+    };
+
+    // Profile 1 doesn't match the default profile 0 of the router
+    assert!(matches!(
+        get_producer_rtp_parameters_mapping(&rtp_parameters, &router_rtp_capabilities),
+        Err(RtpParametersMappingError::UnsupportedCodec { .. }),
+    ));
+
+    if let RtpCodecParameters::Video { parameters, .. } = &mut rtp_parameters.codecs[0] {
+        *parameters = av1_parameters(0);
+    }
+
+    let rtp_mapping =
+        get_producer_rtp_parameters_mapping(&rtp_parameters, &router_rtp_capabilities)
+            .expect("Failed to get producer RTP parameters mapping");
+
+    // Worker doesn't know the dependency descriptor, so it is never sent there
+    assert_eq!(
+        rtp_parameters
+            .clone()
+            .into_fbs()
+            .header_extensions
+            .iter()
+            .map(|ext| ext.uri)
+            .collect::<Vec<_>>(),
+        vec![rtp_parameters::RtpHeaderExtensionUri::Mid],
+    );
+
+    let consumable_rtp_parameters = get_consumable_rtp_parameters(
+        MediaKind::Video,
+        &rtp_parameters,
+        &router_rtp_capabilities,
+        &rtp_mapping,
+    );
+
+    assert_eq!(
+        consumable_rtp_parameters.encodings[0].scalability_mode,
+        ScalabilityMode::L3T3Key,
+    );
+    assert!(consumable_rtp_parameters
+        .header_extensions
+        .iter()
+        .all(|ext| ext.uri != RtpHeaderExtensionUri::DependencyDescriptor));
+
+    let av1_capability = |profile| RtpCodecCapability::Video {
+        mime_type: MimeTypeVideo::Av1,
+        preferred_payload_type: Some(45),
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: av1_parameters(profile),
+        rtcp_feedback: vec![],
+    };
+    let mut remote_rtp_capabilities = RtpCapabilities {
+        codecs: vec![av1_capability(1)],
+        header_extensions: vec![],
+    };
+
+    let incompatibility = check_consume(&consumable_rtp_parameters, &remote_rtp_capabilities)
+        .expect_err("Must not be able to consume");
+    let ConsumeIncompatibility::NoCompatibleMediaCodecs { codecs, .. } = incompatibility else {
+        panic!("Unexpected incompatibility: {incompatibility:?}");
+    };
+    assert_eq!(
+        codecs,
+        vec![CodecIncompatibility {
+            mime_type: MimeType::Video(MimeTypeVideo::Av1),
+            payload_type: consumable_rtp_parameters.codecs[0].payload_type(),
+            mismatches: vec![CodecMismatchDetails {
+                preferred_payload_type: Some(45),
+                mismatch: CodecMismatch::Profile,
+                producer_value: Some("0".to_string()),
+                capability_value: Some("1".to_string()),
+            }],
+        }],
+    );
+
+    remote_rtp_capabilities.codecs = vec![av1_capability(0)];
+
+    let consumer_rtp_parameters = get_consumer_rtp_parameters(
+        &consumable_rtp_parameters,
+        &remote_rtp_capabilities,
+        false,
+        true,
+        &[],
+        false,
+    )
+    .expect("Failed to get consumer RTP parameters");
+
+    assert_eq!(
+        consumer_rtp_parameters.codecs[0].mime_type(),
+        MimeType::Video(MimeTypeVideo::Av1),
+    );
+    // SVC layers of a single AV1 stream are kept as is
+    assert_eq!(consumer_rtp_parameters.encodings.len(), 1);
+    assert_eq!(
+        consumer_rtp_parameters.encodings[0].scalability_mode,
+        ScalabilityMode::L3T3Key,
+    );
+
+    // Worker can't switch between simulcast streams of AV1
+    rtp_parameters.encodings = (0..3)
+        .map(|index| RtpEncodingParameters {
+            ssrc: Some(22222222 + index),
+            scalability_mode: ScalabilityMode::L1T3,
+            ..RtpEncodingParameters::default()
+        })
+        .collect();
+    assert_eq!(
+        get_producer_rtp_parameters_mapping(&rtp_parameters, &router_rtp_capabilities),
+        Err(RtpParametersMappingError::UnsupportedSimulcast {
+            mime_type: MimeType::Video(MimeTypeVideo::Av1),
+            payload_type: 45,
+        }),
+    );
+}
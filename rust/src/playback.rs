@@ -0,0 +1,550 @@
+//! Playback of media files into a router.
+//!
+//! [`Playback`] reads encoded frames from a file, creates a
+//! [`DirectProducer`](crate::producer::DirectProducer) on given
+//! [`DirectTransport`](crate::direct_transport::DirectTransport) and sends frames through it at
+//! real-time pace with [`Packetizer`], which covers hold music, announcements and test patterns
+//! without an external FFmpeg/GStreamer process.
+//!
+//! Supported formats (detected by file signature) are:
+//! * Ogg with Opus.
+//! * IVF with VP8 or VP9.
+//! * WebM/Matroska, the first track with Opus, VP8 or VP9 is played.
+//!
+//! Router must have a codec matching the file, payload type and SSRC are chosen automatically.
+//!
+//! # Notes on usage
+//! Playback can loop and seek. Video playback after seeking starts with the first key frame at or
+//! after target position, since there is no way to generate key frames on demand, key frame
+//! requests of consumers are ignored. It is recommended to encode video files with short key
+//! frame interval.
+//!
+//! # Examples
+//! ```no_run
+//! use mediasoup::direct_transport::DirectTransport;
+//! use mediasoup::playback::{Playback, PlaybackOptions};
+//!
+//! # async fn play(transport: DirectTransport) {
+//! let mut options = PlaybackOptions::default();
+//! options.looped = true;
+//! let playback = Playback::create(&transport, "hold-music.ogg", options)
+//!     .await
+//!     .unwrap();
+//!
+//! // Producer can be consumed as usual
+//! let producer_id = playback.producer().id();
+//! # }
+//! ```
+
+mod ivf;
+mod ogg;
+#[cfg(test)]
+mod tests;
+mod webm;
+
+use crate::data_structures::AppData;
+use crate::direct_transport::DirectTransport;
+use crate::producer::{DirectProducer, Producer, ProducerOptions};
+use crate::rtp::packetizer::{
+    is_key_frame, PacketizeError, Packetizer, PacketizerCodec, PacketizerError, PacketizerOptions,
+};
+use crate::rtp_parameters::{
+    MediaKind, MimeTypeAudio, MimeTypeVideo, RtpCodecParameters, RtpCodecParametersParameters,
+    RtpEncodingParameters, RtpParameters,
+};
+use crate::transport::{ProduceError, Transport};
+use crate::worker::RequestError;
+use event_listener_primitives::{BagOnce, HandlerId};
+use ivf::IvfReader;
+use log::{debug, error};
+use ogg::OggOpusReader;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::num::{NonZeroU32, NonZeroU8};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use webm::WebmReader;
+
+const AUDIO_PAYLOAD_TYPE: u8 = 100;
+const VIDEO_PAYLOAD_TYPE: u8 = 101;
+
+/// Frame duration assumed when file has only one frame and playback is looped.
+const DEFAULT_FRAME_DURATION: Duration = Duration::from_millis(20);
+
+/// [`Playback`] options.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct PlaybackOptions {
+    /// Start over once the end of file is reached.
+    ///
+    /// Default false.
+    pub looped: bool,
+    /// Position in the file to start playback from.
+    ///
+    /// Default zero.
+    pub start_position: Duration,
+    /// Create playback paused (along with its producer), so that consumers can be created before
+    /// the first frame is sent, see [`Playback::resume()`].
+    ///
+    /// Default false.
+    pub paused: bool,
+    /// Options of packetizer that produces RTP packets.
+    pub packetizer: PacketizerOptions,
+    /// Custom application data of the producer.
+    pub app_data: AppData,
+}
+
+/// Error that caused [`Playback`] creation to fail.
+#[derive(Debug, Error)]
+pub enum PlaybackError {
+    /// File format was not recognized.
+    #[error("Unsupported file format")]
+    UnsupportedFormat,
+    /// File has no track with supported codec.
+    #[error("Unsupported codec {0}")]
+    UnsupportedCodec(String),
+    /// File is malformed.
+    #[error("Invalid file: {0}")]
+    InvalidFile(&'static str),
+    /// Failed to read the file.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to create producer.
+    #[error("Failed to create producer: {0}")]
+    Produce(#[from] ProduceError),
+    /// Failed to create packetizer.
+    #[error("Failed to create packetizer: {0}")]
+    Packetizer(#[from] PacketizerError),
+}
+
+/// Encoded frame read from a file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct SourceFrame {
+    /// Presentation time since the beginning of the file.
+    position: Duration,
+    data: Vec<u8>,
+}
+
+/// Reader of encoded frames of a single track.
+trait Source: Send {
+    fn codec(&self) -> PacketizerCodec;
+
+    /// Next frame in presentation order, `None` at the end of file.
+    fn next_frame(&mut self) -> Result<Option<SourceFrame>, PlaybackError>;
+
+    /// Go back to the first frame.
+    fn rewind(&mut self) -> Result<(), PlaybackError>;
+}
+
+/// Detect file format by its signature and create corresponding source.
+fn open_source<R: Read + Seek + Send + 'static>(
+    mut reader: R,
+) -> Result<Box<dyn Source>, PlaybackError> {
+    let mut signature = [0; 4];
+    reader.read_exact(&mut signature)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    Ok(match &signature {
+        b"OggS" => Box::new(OggOpusReader::new(reader)?),
+        b"DKIF" => Box::new(IvfReader::new(reader)?),
+        [0x1A, 0x45, 0xDF, 0xA3] => Box::new(WebmReader::new(reader)?),
+        _ => {
+            return Err(PlaybackError::UnsupportedFormat);
+        }
+    })
+}
+
+fn rtp_parameters(codec: PacketizerCodec) -> Result<(MediaKind, RtpParameters), PlaybackError> {
+    let (kind, codec) = match codec {
+        PacketizerCodec::Opus => (
+            MediaKind::Audio,
+            RtpCodecParameters::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                payload_type: AUDIO_PAYLOAD_TYPE,
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                // Opus in RTP is always signaled with 2 channels
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            },
+        ),
+        PacketizerCodec::Vp8 | PacketizerCodec::Vp9 | PacketizerCodec::Av1 => (
+            MediaKind::Video,
+            RtpCodecParameters::Video {
+                mime_type: match codec {
+                    PacketizerCodec::Vp8 => MimeTypeVideo::Vp8,
+                    PacketizerCodec::Vp9 => MimeTypeVideo::Vp9,
+                    _ => MimeTypeVideo::Av1,
+                },
+                payload_type: VIDEO_PAYLOAD_TYPE,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            },
+        ),
+        PacketizerCodec::H264 => {
+            return Err(PlaybackError::UnsupportedCodec(format!("{codec:?}")));
+        }
+    };
+
+    Ok((
+        kind,
+        RtpParameters {
+            codecs: vec![codec],
+            encodings: vec![RtpEncodingParameters {
+                ssrc: Some(fastrand::u32(..)),
+                ..RtpEncodingParameters::default()
+            }],
+            ..RtpParameters::default()
+        },
+    ))
+}
+
+enum Message {
+    Pause,
+    Resume,
+    Seek(Duration),
+    Stop,
+}
+
+#[derive(Default)]
+struct Handlers {
+    ended: BagOnce<Box<dyn FnOnce() + Send>>,
+}
+
+/// Frames of a source in playback order, with looping and seeking applied.
+struct Timeline {
+    source: Box<dyn Source>,
+    looped: bool,
+    /// Added to positions of frames when playback is looped.
+    loop_offset: Duration,
+    /// Positions of the last two frames read from the file (without loop offset).
+    last_positions: (Option<Duration>, Option<Duration>),
+}
+
+impl Timeline {
+    fn new(source: Box<dyn Source>, looped: bool) -> Self {
+        Self {
+            source,
+            looped,
+            loop_offset: Duration::ZERO,
+            last_positions: (None, None),
+        }
+    }
+
+    fn remember_position(&mut self, position: Duration) {
+        if self.last_positions.0 != Some(position) {
+            self.last_positions = (Some(position), self.last_positions.0);
+        }
+    }
+
+    /// Next frame with loop offset applied, starts over at the end of file if looped.
+    fn next_frame(&mut self) -> Result<Option<SourceFrame>, PlaybackError> {
+        let mut frame = match self.source.next_frame()? {
+            Some(frame) => frame,
+            None if self.looped => {
+                let (Some(last), previous) = self.last_positions else {
+                    // Empty file
+                    return Ok(None);
+                };
+                let frame_duration = previous
+                    .map(|previous| last.saturating_sub(previous))
+                    .filter(|duration| !duration.is_zero())
+                    .unwrap_or(DEFAULT_FRAME_DURATION);
+                self.loop_offset += last + frame_duration;
+                self.last_positions = (None, None);
+                self.source.rewind()?;
+
+                let Some(frame) = self.source.next_frame()? else {
+                    return Ok(None);
+                };
+                frame
+            }
+            None => {
+                return Ok(None);
+            }
+        };
+
+        self.remember_position(frame.position);
+        frame.position += self.loop_offset;
+
+        Ok(Some(frame))
+    }
+
+    /// Move to the first frame at or after given position (first key frame for video), positions
+    /// past the end of file end playback or start it over if looped.
+    fn seek(&mut self, position: Duration) -> Result<Option<SourceFrame>, PlaybackError> {
+        debug!("seek() [position:{:?}]", position);
+
+        self.source.rewind()?;
+        self.loop_offset = Duration::ZERO;
+        self.last_positions = (None, None);
+
+        let codec = self.source.codec();
+        while let Some(frame) = self.source.next_frame()? {
+            self.remember_position(frame.position);
+            if frame.position >= position && is_key_frame(codec, &frame.data) {
+                return Ok(Some(frame));
+            }
+        }
+
+        if self.looped && !position.is_zero() {
+            return self.seek(Duration::ZERO);
+        }
+
+        Ok(None)
+    }
+}
+
+struct Session {
+    timeline: Timeline,
+    packetizer: Packetizer,
+    producer: DirectProducer,
+    handlers: Arc<Handlers>,
+}
+
+impl Session {
+    fn run(
+        mut self,
+        start_position: Duration,
+        mut paused: bool,
+        receiver: mpsc::Receiver<Message>,
+    ) -> Result<(), PlaybackError> {
+        let started_at = Instant::now();
+        let mut next_frame = self.timeline.seek(start_position)?;
+        // Wall clock time that corresponds to a position in the file
+        let mut anchor = None;
+
+        loop {
+            if paused {
+                match receiver.recv() {
+                    Ok(Message::Pause) => {}
+                    Ok(Message::Resume) => {
+                        paused = false;
+                        anchor.take();
+                    }
+                    Ok(Message::Seek(position)) => {
+                        next_frame = self.timeline.seek(position)?;
+                    }
+                    Ok(Message::Stop) | Err(_) => {
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let Some(frame) = next_frame.take() else {
+                debug!("playback ended");
+                self.handlers.ended.call_simple();
+                break;
+            };
+            let (anchor_instant, anchor_position) =
+                *anchor.get_or_insert((Instant::now(), frame.position));
+            let deadline = anchor_instant + frame.position.saturating_sub(anchor_position);
+
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(Message::Pause) => {
+                    paused = true;
+                    next_frame.replace(frame);
+                }
+                Ok(Message::Resume) => {
+                    next_frame.replace(frame);
+                }
+                Ok(Message::Seek(position)) => {
+                    next_frame = self.timeline.seek(position)?;
+                    anchor.take();
+                }
+                Ok(Message::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    break;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    // RTP timestamps follow wall clock, so they keep increasing across pauses,
+                    // seeks and loops
+                    let presentation_time = deadline.saturating_duration_since(started_at);
+                    match self
+                        .packetizer
+                        .send(&self.producer, &frame.data, presentation_time, None)
+                    {
+                        Ok(()) => {}
+                        Err(PacketizeError::ChannelClosed) => {
+                            break;
+                        }
+                        Err(error) => {
+                            debug!("skipping frame that can't be sent: {}", error);
+                        }
+                    }
+                    next_frame = self.timeline.next_frame()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays media file through a direct producer, see [module-level documentation](self).
+///
+/// Playback stops when [`Playback::stop()`] is called, when it is dropped or when producer is
+/// closed. Producer stays open after the end of file is reached.
+#[must_use = "Playback stops when it is dropped"]
+pub struct Playback {
+    producer: Producer,
+    paused: AtomicBool,
+    sender: mpsc::Sender<Message>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+    handlers: Arc<Handlers>,
+}
+
+impl fmt::Debug for Playback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Playback")
+            .field("producer", &self.producer)
+            .field("paused", &self.paused)
+            .field(
+                "finished",
+                &self.thread.as_ref().map(JoinHandle::is_finished),
+            )
+            .finish()
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Stop);
+    }
+}
+
+impl Playback {
+    /// Start playback of a file at specified path through a new producer on given transport.
+    pub async fn create<P: AsRef<Path>>(
+        transport: &DirectTransport,
+        path: P,
+        options: PlaybackOptions,
+    ) -> Result<Self, PlaybackError> {
+        let file = File::open(path)?;
+
+        Self::new(transport, BufReader::new(file), options).await
+    }
+
+    /// Start playback of given reader through a new producer on given transport.
+    pub async fn new<R: Read + Seek + Send + 'static>(
+        transport: &DirectTransport,
+        reader: R,
+        options: PlaybackOptions,
+    ) -> Result<Self, PlaybackError> {
+        debug!("new()");
+
+        let source = open_source(reader)?;
+        let (kind, rtp_parameters) = rtp_parameters(source.codec())?;
+        let packetizer = Packetizer::new(&rtp_parameters, options.packetizer)?;
+
+        let mut producer_options = ProducerOptions::new(kind, rtp_parameters);
+        producer_options.paused = options.paused;
+        producer_options.app_data = options.app_data;
+        let producer = transport.produce(producer_options).await?;
+        let Producer::Direct(direct_producer) = &producer else {
+            unreachable!("Producer created on direct transport is always direct");
+        };
+
+        let handlers = Arc::<Handlers>::default();
+        let session = Session {
+            timeline: Timeline::new(source, options.looped),
+            packetizer,
+            producer: direct_producer.clone(),
+            handlers: Arc::clone(&handlers),
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let PlaybackOptions {
+            start_position,
+            paused,
+            ..
+        } = options;
+        let thread = thread::Builder::new()
+            .name("mediasoup-playback".to_string())
+            .spawn(move || {
+                session
+                    .run(start_position, paused, receiver)
+                    .map_err(|error| {
+                        error!("playback failed: {}", error);
+                        match error {
+                            PlaybackError::Io(error) => error,
+                            error => io::Error::new(io::ErrorKind::InvalidData, error.to_string()),
+                        }
+                    })
+            })?;
+
+        Ok(Self {
+            producer,
+            paused: AtomicBool::new(paused),
+            sender,
+            thread: Some(thread),
+            handlers,
+        })
+    }
+
+    /// Producer that frames are sent through.
+    pub fn producer(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Whether playback is paused.
+    #[must_use]
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Pause playback and the producer (see [`Producer::pause()`]).
+    pub async fn pause(&self) -> Result<(), RequestError> {
+        debug!("pause()");
+
+        self.producer.pause().await?;
+        self.paused.store(true, Ordering::SeqCst);
+        let _ = self.sender.send(Message::Pause);
+
+        Ok(())
+    }
+
+    /// Resume playback and the producer (see [`Producer::resume()`]).
+    pub async fn resume(&self) -> Result<(), RequestError> {
+        debug!("resume()");
+
+        self.producer.resume().await?;
+        self.paused.store(false, Ordering::SeqCst);
+        let _ = self.sender.send(Message::Resume);
+
+        Ok(())
+    }
+
+    /// Continue playback from specified position in the file.
+    pub fn seek(&self, position: Duration) {
+        debug!("seek()");
+
+        let _ = self.sender.send(Message::Seek(position));
+    }
+
+    /// Callback is called once the end of file is reached (never if playback is looped).
+    pub fn on_ended<F: FnOnce() + Send + 'static>(&self, callback: F) -> HandlerId {
+        self.handlers.ended.add(Box::new(callback))
+    }
+
+    /// Stop playback and wait for it to finish, producer is closed once dropped.
+    pub fn stop(mut self) -> Result<(), PlaybackError> {
+        debug!("stop()");
+
+        let _ = self.sender.send(Message::Stop);
+
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => Ok(result?),
+            Some(Err(_)) => {
+                Err(io::Error::new(io::ErrorKind::Other, "Playback thread panicked").into())
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! IVF container of VP8, VP9 and AV1 frames.
+
+use super::{PlaybackError, Source, SourceFrame};
+use crate::rtp::packetizer::PacketizerCodec;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+const FILE_HEADER_SIZE: usize = 32;
+const FRAME_HEADER_SIZE: usize = 12;
+
+/// Reader of IVF file with a single video stream.
+pub(super) struct IvfReader<R: Read + Seek> {
+    reader: R,
+    codec: PacketizerCodec,
+    header_size: u64,
+    /// Time base as numerator and denominator (seconds per timestamp unit).
+    time_base: (u64, u64),
+}
+
+impl<R: Read + Seek + Send> IvfReader<R> {
+    pub(super) fn new(mut reader: R) -> Result<Self, PlaybackError> {
+        let mut header = [0; FILE_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != b"DKIF" {
+            return Err(PlaybackError::InvalidFile("missing IVF signature"));
+        }
+
+        let header_size = u16::from_le_bytes([header[6], header[7]]);
+        let codec = match &header[8..12] {
+            b"VP80" => PacketizerCodec::Vp8,
+            b"VP90" => PacketizerCodec::Vp9,
+            b"AV01" => PacketizerCodec::Av1,
+            fourcc => {
+                return Err(PlaybackError::UnsupportedCodec(
+                    String::from_utf8_lossy(fourcc).into_owned(),
+                ));
+            }
+        };
+        let denominator = u32::from_le_bytes(header[16..20].try_into().unwrap());
+        let numerator = u32::from_le_bytes(header[20..24].try_into().unwrap());
+        if denominator == 0 || numerator == 0 {
+            return Err(PlaybackError::InvalidFile("invalid IVF time base"));
+        }
+
+        let mut ivf_reader = Self {
+            reader,
+            codec,
+            header_size: u64::from(header_size).max(FILE_HEADER_SIZE as u64),
+            time_base: (u64::from(numerator), u64::from(denominator)),
+        };
+        ivf_reader.rewind()?;
+
+        Ok(ivf_reader)
+    }
+}
+
+impl<R: Read + Seek + Send> Source for IvfReader<R> {
+    fn codec(&self) -> PacketizerCodec {
+        self.codec
+    }
+
+    fn next_frame(&mut self) -> Result<Option<SourceFrame>, PlaybackError> {
+        let mut header = [0; FRAME_HEADER_SIZE];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(None);
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        }
+
+        let size = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let timestamp = u64::from_le_bytes(header[4..].try_into().unwrap());
+        let mut data = vec![0; size as usize];
+        self.reader.read_exact(&mut data)?;
+
+        let (numerator, denominator) = self.time_base;
+        let position = Duration::from_nanos(
+            (u128::from(timestamp) * u128::from(numerator) * 1_000_000_000
+                / u128::from(denominator)) as u64,
+        );
+
+        Ok(Some(SourceFrame { position, data }))
+    }
+
+    fn rewind(&mut self) -> Result<(), PlaybackError> {
+        self.reader.seek(SeekFrom::Start(self.header_size))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Ogg encapsulation of Opus as defined in [RFC 7845](https://tools.ietf.org/html/rfc7845).
+
+use super::{PlaybackError, Source, SourceFrame};
+use crate::recorder::ogg::opus_packet_samples;
+use crate::rtp::packetizer::PacketizerCodec;
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+const OPUS_CLOCK_RATE: u64 = 48000;
+const PAGE_HEADER_SIZE: usize = 27;
+/// Identification and comment headers.
+const HEADER_PACKETS: usize = 2;
+/// Granule position of pages without completed packets.
+const NO_GRANULE_POSITION: u64 = u64::MAX;
+
+/// Reader of the first Opus logical bitstream of Ogg file.
+pub(super) struct OggOpusReader<R: Read + Seek> {
+    reader: R,
+    serial: u32,
+    pre_skip: u64,
+    /// Packet that continues on the next page.
+    partial_packet: Vec<u8>,
+    packets: VecDeque<SourceFrame>,
+    header_packets_left: usize,
+    /// Granule position right after the last packet read so far.
+    granule_position: u64,
+}
+
+impl<R: Read + Seek> OggOpusReader<R> {
+    pub(super) fn new(mut reader: R) -> Result<Self, PlaybackError> {
+        let mut header = [0; PAGE_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let serial = u32::from_le_bytes(header[14..18].try_into().unwrap());
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut ogg_reader = Self {
+            reader,
+            serial,
+            pre_skip: 0,
+            partial_packet: Vec::new(),
+            packets: VecDeque::new(),
+            header_packets_left: HEADER_PACKETS,
+            granule_position: 0,
+        };
+        ogg_reader.read_headers()?;
+
+        Ok(ogg_reader)
+    }
+
+    fn read_headers(&mut self) -> Result<(), PlaybackError> {
+        while self.header_packets_left > 0 {
+            if !self.read_page()? {
+                return Err(PlaybackError::InvalidFile("missing Opus headers"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `false` at the end of file.
+    fn read_page(&mut self) -> Result<bool, PlaybackError> {
+        let mut header = [0; PAGE_HEADER_SIZE];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(false);
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        }
+        if &header[..4] != b"OggS" {
+            return Err(PlaybackError::InvalidFile("missing Ogg page signature"));
+        }
+
+        let granule_position = u64::from_le_bytes(header[6..14].try_into().unwrap());
+        let serial = u32::from_le_bytes(header[14..18].try_into().unwrap());
+        let mut lacing_values = vec![0; usize::from(header[26])];
+        self.reader.read_exact(&mut lacing_values)?;
+        let mut data = vec![0; lacing_values.iter().map(|&value| usize::from(value)).sum()];
+        self.reader.read_exact(&mut data)?;
+
+        if serial != self.serial {
+            // Other logical bitstream
+            return Ok(true);
+        }
+
+        let mut offset = 0;
+        let mut completed = Vec::new();
+        for &lacing_value in &lacing_values {
+            let size = usize::from(lacing_value);
+            self.partial_packet
+                .extend_from_slice(&data[offset..offset + size]);
+            offset += size;
+            if lacing_value < 255 {
+                let packet = std::mem::take(&mut self.partial_packet);
+                if self.header_packets_left > 0 {
+                    self.header_packets_left -= 1;
+                    if self.header_packets_left == HEADER_PACKETS - 1 {
+                        self.parse_opus_head(&packet)?;
+                    }
+                } else {
+                    completed.push(packet);
+                }
+            }
+        }
+
+        // Granule position is the end of the last completed packet, which accounts for gaps
+        // (for instance due to discontinuous transmission)
+        let mut end = if granule_position == NO_GRANULE_POSITION {
+            self.granule_position
+                + completed
+                    .iter()
+                    .map(|p| opus_packet_samples(p))
+                    .sum::<u64>()
+        } else {
+            granule_position
+        };
+        self.granule_position = end;
+        let first_new = self.packets.len();
+        for packet in completed.into_iter().rev() {
+            let start = end.saturating_sub(opus_packet_samples(&packet));
+            self.packets.insert(
+                first_new,
+                SourceFrame {
+                    position: Duration::from_nanos(
+                        start.saturating_sub(self.pre_skip) * 1_000_000_000 / OPUS_CLOCK_RATE,
+                    ),
+                    data: packet,
+                },
+            );
+            end = start;
+        }
+
+        Ok(true)
+    }
+
+    fn parse_opus_head(&mut self, packet: &[u8]) -> Result<(), PlaybackError> {
+        if packet.len() < 19 || &packet[..8] != b"OpusHead" {
+            return Err(PlaybackError::UnsupportedCodec(
+                "non-Opus Ogg stream".to_string(),
+            ));
+        }
+        self.pre_skip = u64::from(u16::from_le_bytes([packet[10], packet[11]]));
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek + Send> Source for OggOpusReader<R> {
+    fn codec(&self) -> PacketizerCodec {
+        PacketizerCodec::Opus
+    }
+
+    fn next_frame(&mut self) -> Result<Option<SourceFrame>, PlaybackError> {
+        loop {
+            if let Some(frame) = self.packets.pop_front() {
+                return Ok(Some(frame));
+            }
+            if !self.read_page()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn rewind(&mut self) -> Result<(), PlaybackError> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        self.partial_packet.clear();
+        self.packets.clear();
+        self.header_packets_left = HEADER_PACKETS;
+        self.granule_position = 0;
+
+        self.read_headers()
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+use crate::recorder::ogg::OggOpusWriter;
+use crate::recorder::webm::{WebmTrack, WebmWriter};
+use crate::rtp::depacketizer::DepacketizerCodec;
+use std::io::Cursor;
+
+const VP8_KEY_FRAME: &[u8] = &[0x10, 0x02, 0x00, 0x9D, 0x01, 0x2A, 0x80, 0x02, 0xE0, 0x01];
+const VP8_INTER_FRAME: &[u8] = &[0x11, 0x02, 0x00];
+
+/// IVF file with time base of 1ms.
+fn ivf(fourcc: &[u8; 4], frames: &[(u64, &[u8])]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"DKIF");
+    // Version, header size
+    data.extend_from_slice(&[0, 0, 32, 0]);
+    data.extend_from_slice(fourcc);
+    // 640x480
+    data.extend_from_slice(&[0x80, 0x02, 0xE0, 0x01]);
+    data.extend_from_slice(&1000_u32.to_le_bytes());
+    data.extend_from_slice(&1_u32.to_le_bytes());
+    data.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    for (timestamp, frame) in frames {
+        data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        data.extend_from_slice(frame);
+    }
+    data
+}
+
+fn vp8_ivf() -> Vec<u8> {
+    ivf(
+        b"VP80",
+        &[
+            (0, VP8_KEY_FRAME),
+            (40, VP8_INTER_FRAME),
+            (80, VP8_KEY_FRAME),
+            (120, VP8_INTER_FRAME),
+        ],
+    )
+}
+
+fn read_all(source: &mut dyn Source) -> Vec<SourceFrame> {
+    std::iter::from_fn(|| source.next_frame().unwrap()).collect()
+}
+
+fn positions(frames: &[SourceFrame]) -> Vec<u64> {
+    frames
+        .iter()
+        .map(|frame| frame.position.as_millis() as u64)
+        .collect()
+}
+
+#[test]
+fn ivf_source() {
+    let mut source = open_source(Cursor::new(vp8_ivf())).unwrap();
+    assert_eq!(source.codec(), PacketizerCodec::Vp8);
+
+    let frames = read_all(source.as_mut());
+    assert_eq!(positions(&frames), vec![0, 40, 80, 120]);
+    assert_eq!(frames[1].data, VP8_INTER_FRAME);
+
+    source.rewind().unwrap();
+    assert_eq!(read_all(source.as_mut()), frames);
+
+    let source = open_source(Cursor::new(ivf(b"AV01", &[(0, &[0x12, 0x00])]))).unwrap();
+    assert_eq!(source.codec(), PacketizerCodec::Av1);
+    let (kind, rtp_parameters) = rtp_parameters(source.codec()).unwrap();
+    assert_eq!(kind, MediaKind::Video);
+    assert!(matches!(
+        rtp_parameters.codecs[0],
+        RtpCodecParameters::Video {
+            mime_type: MimeTypeVideo::Av1,
+            ..
+        },
+    ));
+
+    assert!(matches!(
+        open_source(Cursor::new(ivf(b"H264", &[]))),
+        Err(PlaybackError::UnsupportedCodec(fourcc)) if fourcc == "H264",
+    ));
+    assert!(matches!(
+        open_source(Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec())),
+        Err(PlaybackError::UnsupportedFormat),
+    ));
+}
+
+#[test]
+fn ogg_source() {
+    let mut ogg_writer = OggOpusWriter::new(Vec::new(), 2).unwrap();
+    // 20ms CELT frames, the second page starts after a gap of 1 second
+    for index in 0..60_u64 {
+        let gap = if index < 50 { 0 } else { 1000 };
+        ogg_writer
+            .write_frame(
+                Duration::from_millis(index * 20 + gap),
+                &[0xFC, index as u8],
+            )
+            .unwrap();
+    }
+    let data = ogg_writer.finish().unwrap();
+
+    let mut source = open_source(Cursor::new(data)).unwrap();
+    assert_eq!(source.codec(), PacketizerCodec::Opus);
+
+    let frames = read_all(source.as_mut());
+    assert_eq!(frames.len(), 60);
+    assert_eq!(frames[0].data, vec![0xFC, 0]);
+    assert_eq!(positions(&frames[48..52]), vec![960, 980, 2000, 2020]);
+    assert_eq!(frames[59].data, vec![0xFC, 59]);
+
+    source.rewind().unwrap();
+    assert_eq!(read_all(source.as_mut()), frames);
+}
+
+#[test]
+fn webm_source() {
+    let mut webm_writer = WebmWriter::new(
+        Vec::new(),
+        vec![WebmTrack {
+            codec: DepacketizerCodec::Vp8,
+            clock_rate: 90000,
+            channels: 0,
+        }],
+    );
+    // Clusters are split at key frames after 5 seconds
+    for (time, key_frame) in [(0, true), (40, false), (5000, true), (5040, false)] {
+        let frame = if key_frame {
+            VP8_KEY_FRAME
+        } else {
+            VP8_INTER_FRAME
+        };
+        webm_writer
+            .write_frame(0, Duration::from_millis(time), key_frame, frame)
+            .unwrap();
+    }
+    let data = webm_writer.finish().unwrap();
+
+    let mut source = open_source(Cursor::new(data)).unwrap();
+    assert_eq!(source.codec(), PacketizerCodec::Vp8);
+
+    let frames = read_all(source.as_mut());
+    assert_eq!(positions(&frames), vec![0, 40, 5000, 5040]);
+    assert_eq!(frames[0].data, VP8_KEY_FRAME);
+    assert_eq!(frames[1].data, VP8_INTER_FRAME);
+
+    source.rewind().unwrap();
+    assert_eq!(read_all(source.as_mut()), frames);
+
+    // The first supported track is played
+    let mut webm_writer = WebmWriter::new(
+        Vec::new(),
+        vec![
+            WebmTrack {
+                codec: DepacketizerCodec::Opus,
+                clock_rate: 48000,
+                channels: 2,
+            },
+            WebmTrack {
+                codec: DepacketizerCodec::Vp8,
+                clock_rate: 90000,
+                channels: 0,
+            },
+        ],
+    );
+    webm_writer
+        .write_frame(1, Duration::ZERO, true, VP8_KEY_FRAME)
+        .unwrap();
+    webm_writer
+        .write_frame(0, Duration::from_millis(10), true, &[0xFC])
+        .unwrap();
+    let data = webm_writer.finish().unwrap();
+
+    let mut source = open_source(Cursor::new(data)).unwrap();
+    assert_eq!(source.codec(), PacketizerCodec::Opus);
+    let frames = read_all(source.as_mut());
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].data, vec![0xFC]);
+}
+
+#[test]
+fn timeline_loop() {
+    let source = open_source(Cursor::new(vp8_ivf())).unwrap();
+    let mut timeline = Timeline::new(source, true);
+
+    let frames = std::iter::from_fn(|| timeline.next_frame().unwrap())
+        .take(6)
+        .collect::<Vec<_>>();
+    // Loop starts one frame duration after the last frame
+    assert_eq!(positions(&frames), vec![0, 40, 80, 120, 160, 200]);
+    assert_eq!(frames[4].data, VP8_KEY_FRAME);
+}
+
+#[test]
+fn timeline_seek() {
+    let source = open_source(Cursor::new(vp8_ivf())).unwrap();
+    let mut timeline = Timeline::new(source, false);
+
+    // Video starts with the first key frame at or after position
+    let frame = timeline.seek(Duration::from_millis(30)).unwrap().unwrap();
+    assert_eq!(frame.position, Duration::from_millis(80));
+    let frame = timeline.next_frame().unwrap().unwrap();
+    assert_eq!(frame.position, Duration::from_millis(120));
+    assert_eq!(timeline.next_frame().unwrap(), None);
+
+    assert_eq!(timeline.seek(Duration::from_millis(100)).unwrap(), None);
+
+    // Looped playback starts over
+    timeline.looped = true;
+    let frame = timeline.seek(Duration::from_millis(100)).unwrap().unwrap();
+    assert_eq!(frame.position, Duration::ZERO);
+}
@@ -0,0 +1,280 @@
+//! Reading of WebM/Matroska files (EBML), only the subset needed for playback of a single track.
+//!
+//! Elements are read sequentially without loading the whole file, master elements that contain
+//! frames (Segment, Cluster, BlockGroup) are entered rather than parsed as a whole, so that
+//! elements with unknown size (written by live muxers) are supported too.
+
+use super::{PlaybackError, Source, SourceFrame};
+use crate::rtp::packetizer::PacketizerCodec;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+const EBML: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const CODEC_ID: u32 = 0x86;
+const CLUSTER: u32 = 0x1F43_B675;
+const TIMESTAMP: u32 = 0xE7;
+const BLOCK_GROUP: u32 = 0xA0;
+const BLOCK: u32 = 0xA1;
+const SIMPLE_BLOCK: u32 = 0xA3;
+
+const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+/// Elements larger than this are not read into memory.
+const MAX_ELEMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Reader of the first supported track of WebM/Matroska file.
+pub(super) struct WebmReader<R: Read + Seek> {
+    reader: R,
+    codec: PacketizerCodec,
+    track_number: u64,
+    /// Nanoseconds per timestamp unit.
+    timestamp_scale: u64,
+    cluster_timestamp: u64,
+    /// Offset of the first cluster.
+    clusters_start: u64,
+}
+
+impl<R: Read + Seek + Send> WebmReader<R> {
+    pub(super) fn new(mut reader: R) -> Result<Self, PlaybackError> {
+        let (id, size) =
+            read_element_header(&mut reader)?.ok_or(PlaybackError::InvalidFile("empty file"))?;
+        if id != EBML {
+            return Err(PlaybackError::InvalidFile("missing EBML header"));
+        }
+        skip(&mut reader, size)?;
+
+        let (id, _size) = read_element_header(&mut reader)?
+            .ok_or(PlaybackError::InvalidFile("missing segment"))?;
+        if id != SEGMENT {
+            return Err(PlaybackError::InvalidFile("missing segment"));
+        }
+
+        let mut timestamp_scale = DEFAULT_TIMESTAMP_SCALE;
+        let mut tracks = Vec::new();
+        let clusters_start = loop {
+            let position = reader.stream_position()?;
+            let Some((id, size)) = read_element_header(&mut reader)? else {
+                return Err(PlaybackError::InvalidFile("no clusters"));
+            };
+            match id {
+                INFO => {
+                    for (id, value) in read_children(&mut reader, size)? {
+                        if id == TIMESTAMP_SCALE {
+                            timestamp_scale = read_uint(&value);
+                        }
+                    }
+                }
+                TRACKS => {
+                    for (id, value) in read_children(&mut reader, size)? {
+                        if id == TRACK_ENTRY {
+                            let size = Some(value.len() as u64);
+                            tracks.push(read_children(&mut value.as_slice(), size)?);
+                        }
+                    }
+                }
+                CLUSTER => {
+                    break position;
+                }
+                _ => {
+                    skip(&mut reader, size)?;
+                }
+            }
+        };
+
+        let mut first_codec_id = None;
+        let track = tracks.iter().find_map(|track| {
+            let track_number = track
+                .iter()
+                .find_map(|(id, value)| (*id == TRACK_NUMBER).then(|| read_uint(value)))?;
+            let codec_id = track
+                .iter()
+                .find_map(|(id, value)| (*id == CODEC_ID).then_some(value.as_slice()))?;
+            let codec = match codec_id {
+                b"A_OPUS" => PacketizerCodec::Opus,
+                b"V_VP8" => PacketizerCodec::Vp8,
+                b"V_VP9" => PacketizerCodec::Vp9,
+                b"V_AV1" => PacketizerCodec::Av1,
+                _ => {
+                    first_codec_id
+                        .get_or_insert_with(|| String::from_utf8_lossy(codec_id).into_owned());
+                    return None;
+                }
+            };
+            Some((track_number, codec))
+        });
+        let Some((track_number, codec)) = track else {
+            return Err(match first_codec_id {
+                Some(codec_id) => PlaybackError::UnsupportedCodec(codec_id),
+                None => PlaybackError::InvalidFile("no tracks"),
+            });
+        };
+
+        let mut webm_reader = Self {
+            reader,
+            codec,
+            track_number,
+            timestamp_scale,
+            cluster_timestamp: 0,
+            clusters_start,
+        };
+        webm_reader.rewind()?;
+
+        Ok(webm_reader)
+    }
+}
+
+impl<R: Read + Seek + Send> Source for WebmReader<R> {
+    fn codec(&self) -> PacketizerCodec {
+        self.codec
+    }
+
+    fn next_frame(&mut self) -> Result<Option<SourceFrame>, PlaybackError> {
+        while let Some((id, size)) = read_element_header(&mut self.reader)? {
+            match id {
+                // Enter master elements that contain frames
+                CLUSTER | BLOCK_GROUP => {}
+                TIMESTAMP => {
+                    self.cluster_timestamp = read_uint(&read_value(&mut self.reader, size)?);
+                }
+                SIMPLE_BLOCK | BLOCK => {
+                    let block = read_value(&mut self.reader, size)?;
+                    if let Some(frame) = self.parse_block(&block)? {
+                        return Ok(Some(frame));
+                    }
+                }
+                _ => {
+                    skip(&mut self.reader, size)?;
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn rewind(&mut self) -> Result<(), PlaybackError> {
+        self.reader.seek(SeekFrom::Start(self.clusters_start))?;
+        self.cluster_timestamp = 0;
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> WebmReader<R> {
+    fn parse_block(&self, block: &[u8]) -> Result<Option<SourceFrame>, PlaybackError> {
+        let mut rest = block;
+        let track_number =
+            read_vint(&mut rest, true)?.ok_or(PlaybackError::InvalidFile("truncated block"))?;
+        if rest.len() < 3 {
+            return Err(PlaybackError::InvalidFile("truncated block"));
+        }
+        let (header, data) = rest.split_at(3);
+        if track_number != Some(self.track_number) {
+            return Ok(None);
+        }
+        // Lacing is only used for audio by some muxers and is not supported
+        if header[2] & 0x06 != 0 {
+            return Err(PlaybackError::InvalidFile("laced blocks are not supported"));
+        }
+
+        let relative_timestamp = i64::from(i16::from_be_bytes([header[0], header[1]]));
+        let timestamp = (self.cluster_timestamp as i64 + relative_timestamp).max(0) as u64;
+
+        Ok(Some(SourceFrame {
+            position: Duration::from_nanos(timestamp * self.timestamp_scale),
+            data: data.to_vec(),
+        }))
+    }
+}
+
+/// Read variable size integer, `None` is returned for reserved "unknown" value (all ones).
+///
+/// Marker bit is kept for element ids (`strip_marker == false`).
+fn read_vint<R: Read>(reader: &mut R, strip_marker: bool) -> io::Result<Option<Option<u64>>> {
+    let mut first = [0];
+    if reader.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid variable size integer",
+        ));
+    }
+
+    let mut rest = [0; 7];
+    reader.read_exact(&mut rest[..length - 1])?;
+    let marker = 1_u64 << (7 * length);
+    let mut value = u64::from(first[0]);
+    for &byte in &rest[..length - 1] {
+        value = value << 8 | u64::from(byte);
+    }
+    // Value without marker bit
+    let data = value & (marker - 1);
+
+    Ok(Some(if strip_marker {
+        (data != marker - 1).then_some(data)
+    } else {
+        Some(value)
+    }))
+}
+
+/// Returns element id and size (`None` if unknown), or `None` at the end of file.
+fn read_element_header<R: Read>(reader: &mut R) -> io::Result<Option<(u32, Option<u64>)>> {
+    let Some(id) = read_vint(reader, false)? else {
+        return Ok(None);
+    };
+    let id = id.unwrap_or_default() as u32;
+    let size =
+        read_vint(reader, true)?.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    Ok(Some((id, size)))
+}
+
+fn read_value<R: Read>(reader: &mut R, size: Option<u64>) -> Result<Vec<u8>, PlaybackError> {
+    let size = size
+        .filter(|&size| size <= MAX_ELEMENT_SIZE)
+        .ok_or(PlaybackError::InvalidFile("invalid element size"))?;
+    let mut value = vec![0; size as usize];
+    reader.read_exact(&mut value)?;
+
+    Ok(value)
+}
+
+/// Read children of master element as pairs of id and value.
+fn read_children<R: Read>(
+    reader: &mut R,
+    size: Option<u64>,
+) -> Result<Vec<(u32, Vec<u8>)>, PlaybackError> {
+    let data = read_value(reader, size)?;
+    let mut rest = data.as_slice();
+    let mut children = Vec::new();
+    while let Some((id, size)) = read_element_header(&mut rest)? {
+        children.push((id, read_value(&mut rest, size)?));
+    }
+
+    Ok(children)
+}
+
+fn skip<R: Read + Seek>(reader: &mut R, size: Option<u64>) -> Result<(), PlaybackError> {
+    let size = size.ok_or(PlaybackError::InvalidFile(
+        "unknown size of skipped element",
+    ))?;
+    reader.seek(SeekFrom::Current(
+        i64::try_from(size).map_err(|_| PlaybackError::InvalidFile("invalid element size"))?,
+    ))?;
+
+    Ok(())
+}
+
+fn read_uint(value: &[u8]) -> u64 {
+    value
+        .iter()
+        .take(8)
+        .fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
@@ -12,11 +12,11 @@
 //! ```
 pub use crate::worker_manager::WorkerManager;
 
-pub use crate::worker::{Worker, WorkerSettings};
+pub use crate::worker::{WeakWorker, Worker, WorkerSettings};
 
 pub use crate::router::{
     PipeDataProducerToRouterError, PipeDataProducerToRouterPair, PipeProducerToRouterError,
-    PipeProducerToRouterPair, PipeToRouterOptions, Router, RouterOptions,
+    PipeProducerToRouterPair, PipeToRouterOptions, Router, RouterOptions, WeakRouter,
 };
 
 pub use crate::webrtc_server::{
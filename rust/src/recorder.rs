@@ -0,0 +1,498 @@
+//! In-process recording of consumers into WebM/Matroska or Ogg files.
+//!
+//! [`Recorder`] attaches to consumers created on a
+//! [`DirectTransport`](crate::direct_transport::DirectTransport), restores packet order,
+//! reassembles frames with [`Depacketizer`] and muxes them into a file on a dedicated thread, so
+//! that no external FFmpeg/GStreamer process or SDP is needed.
+//!
+//! Supported combinations are:
+//! * [`RecordingFormat::WebM`]: up to one Opus consumer and up to one VP8, VP9 or H264 consumer.
+//!   Files with H264 are written as Matroska since WebM doesn't allow H264.
+//! * [`RecordingFormat::Ogg`]: exactly one Opus consumer.
+//!
+//! # Notes on usage
+//! Video recording starts with the first key frame, key frames are requested from the producer
+//! automatically at the start and whenever packets are lost. Audio and video tracks are aligned
+//! by the time their first frames arrive at the recorder.
+//!
+//! # Examples
+//! ```no_run
+//! use mediasoup::consumer::Consumer;
+//! use mediasoup::recorder::{Recorder, RecorderOptions, RecordingFormat};
+//!
+//! # fn record(audio_consumer: Consumer, video_consumer: Consumer) {
+//! let recorder = Recorder::create(
+//!     "recording.webm",
+//!     vec![audio_consumer, video_consumer],
+//!     RecorderOptions::new(RecordingFormat::WebM),
+//! )
+//! .unwrap();
+//!
+//! // Later
+//! recorder.stop().unwrap();
+//! # }
+//! ```
+
+pub(crate) mod ogg;
+#[cfg(test)]
+mod tests;
+pub(crate) mod webm;
+
+use crate::consumer::Consumer;
+use crate::rtp::depacketizer::{Depacketizer, DepacketizerCodec};
+use crate::rtp::jitter_buffer::{JitterBuffer, JitterBufferOptions, JitterBufferOutput};
+use crate::rtp::RtpPacket;
+use crate::rtp_parameters::{MimeType, RtpCodecParameters};
+use event_listener_primitives::HandlerId;
+use log::{debug, error, warn};
+use ogg::OggOpusWriter;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use webm::{WebmTrack, WebmWriter};
+
+/// Maximum number of messages queued for the recording thread, RTP packets that don't fit are
+/// dropped (and recovered from as lost packets) instead of growing memory usage unboundedly.
+const MESSAGE_QUEUE_CAPACITY: usize = 1000;
+
+/// Container format of the recording.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RecordingFormat {
+    /// WebM (or Matroska for H264) with audio and/or video track.
+    WebM,
+    /// Ogg with a single Opus track.
+    Ogg,
+}
+
+/// [`Recorder`] options.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RecorderOptions {
+    /// Container format.
+    pub format: RecordingFormat,
+    /// Options of jitter buffer that restores packet order.
+    pub jitter_buffer: JitterBufferOptions,
+    /// Minimum interval between key frame requests.
+    ///
+    /// Default 1 second.
+    pub key_frame_request_interval: Duration,
+}
+
+impl RecorderOptions {
+    /// Create recorder options with given format.
+    #[must_use]
+    pub fn new(format: RecordingFormat) -> Self {
+        Self {
+            format,
+            jitter_buffer: JitterBufferOptions::default(),
+            key_frame_request_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Error that caused [`Recorder`] to fail.
+#[derive(Debug, Error)]
+pub enum RecorderError {
+    /// No consumers were given.
+    #[error("No consumers to record")]
+    NoConsumers,
+    /// Codec of one of the consumers can't be recorded.
+    #[error("Unsupported codec {}", .0.as_str())]
+    UnsupportedCodec(MimeType),
+    /// Combination of consumers is not supported by recording format.
+    #[error("Unsupported combination of consumers for recording format")]
+    UnsupportedTracks,
+    /// Failed to write the file.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+enum Message {
+    Rtp {
+        track: usize,
+        packet: Vec<u8>,
+        received_at: Instant,
+    },
+    Closed {
+        track: usize,
+    },
+    Stop,
+}
+
+enum Muxer<W: Write> {
+    WebM(WebmWriter<W>),
+    Ogg(OggOpusWriter<W>),
+}
+
+impl<W: Write> Muxer<W> {
+    fn write_frame(
+        &mut self,
+        track: usize,
+        time: Duration,
+        key_frame: bool,
+        data: &[u8],
+    ) -> io::Result<()> {
+        match self {
+            Self::WebM(webm_writer) => webm_writer.write_frame(track, time, key_frame, data),
+            Self::Ogg(ogg_writer) => ogg_writer.write_frame(time, data),
+        }
+    }
+
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Self::WebM(webm_writer) => webm_writer.finish(),
+            Self::Ogg(ogg_writer) => ogg_writer.finish(),
+        }
+    }
+}
+
+struct Track {
+    consumer: Consumer,
+    payload_type: u8,
+    clock_rate: u32,
+    jitter_buffer: JitterBuffer,
+    depacketizer: Depacketizer,
+    waiting_for_key_frame: bool,
+    last_key_frame_request: Option<Instant>,
+    /// First extended RTP timestamp and time since the beginning of the recording it was received.
+    first_frame: Option<(u64, Duration)>,
+    highest_timestamp: Option<u64>,
+    closed: bool,
+}
+
+impl Track {
+    fn is_video(&self) -> bool {
+        self.depacketizer.codec() != DepacketizerCodec::Opus
+    }
+
+    fn request_key_frame(&mut self, interval: Duration) {
+        if self
+            .last_key_frame_request
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_key_frame_request.replace(Instant::now());
+
+        let consumer = self.consumer.clone();
+
+        self.consumer.executor().spawn(Box::pin(async move {
+            debug!("requesting key frame [consumer_id:{}]", consumer.id());
+
+            if let Err(error) = consumer.request_key_frame().await {
+                warn!(
+                    "failed to request key frame [consumer_id:{}]: {}",
+                    consumer.id(),
+                    error,
+                );
+            }
+        }));
+    }
+
+    fn extend_timestamp(&mut self, timestamp: u32) -> u64 {
+        let extended = match self.highest_timestamp {
+            Some(highest) => {
+                let diff = i64::from(timestamp.wrapping_sub(highest as u32) as i32);
+                (highest as i64 + diff).max(0) as u64
+            }
+            None => (1 << 32) + u64::from(timestamp),
+        };
+        self.highest_timestamp = Some(
+            self.highest_timestamp
+                .map_or(extended, |highest| highest.max(extended)),
+        );
+
+        extended
+    }
+}
+
+/// Convert difference of RTP timestamps into duration without overflowing intermediate values.
+fn rtp_duration(timestamp_diff: u64, clock_rate: u32) -> Duration {
+    let clock_rate = u64::from(clock_rate);
+
+    Duration::from_secs(timestamp_diff / clock_rate)
+        + Duration::from_nanos(timestamp_diff % clock_rate * 1_000_000_000 / clock_rate)
+}
+
+struct Session<W: Write> {
+    tracks: Vec<Track>,
+    muxer: Muxer<W>,
+    started_at: Instant,
+    key_frame_request_interval: Duration,
+}
+
+impl<W: Write> Session<W> {
+    fn run(mut self, receiver: mpsc::Receiver<Message>) -> io::Result<()> {
+        for track in &mut self.tracks {
+            if track.is_video() {
+                track.request_key_frame(self.key_frame_request_interval);
+            }
+        }
+
+        while let Ok(message) = receiver.recv() {
+            match message {
+                Message::Rtp {
+                    track,
+                    packet,
+                    received_at,
+                } => {
+                    self.process_packet(track, packet, received_at)?;
+                }
+                Message::Closed { track } => {
+                    self.tracks[track].closed = true;
+                    if self.tracks.iter().all(|track| track.closed) {
+                        break;
+                    }
+                }
+                Message::Stop => {
+                    break;
+                }
+            }
+        }
+
+        self.muxer.finish()?;
+
+        Ok(())
+    }
+
+    fn process_packet(
+        &mut self,
+        track_index: usize,
+        packet: Vec<u8>,
+        received_at: Instant,
+    ) -> io::Result<()> {
+        let track = &mut self.tracks[track_index];
+        let output = match RtpPacket::parse(&packet) {
+            Ok(rtp_packet) if rtp_packet.payload_type() == track.payload_type => track
+                .jitter_buffer
+                .push(packet, received_at)
+                .expect("Packet was parsed before; qed"),
+            Ok(_) => {
+                return Ok(());
+            }
+            Err(error) => {
+                debug!("ignoring invalid RTP packet: {}", error);
+                return Ok(());
+            }
+        };
+
+        for output in output {
+            let packet = match output {
+                JitterBufferOutput::Packet(packet) => packet,
+                JitterBufferOutput::Gap { .. } => {
+                    track.depacketizer.reset();
+                    if track.is_video() {
+                        track.waiting_for_key_frame = true;
+                        track.request_key_frame(self.key_frame_request_interval);
+                    }
+                    continue;
+                }
+            };
+            let rtp_packet = RtpPacket::parse(&packet).expect("Packet was parsed before; qed");
+            let Some(frame) = track.depacketizer.push(&rtp_packet) else {
+                continue;
+            };
+
+            if track.waiting_for_key_frame {
+                if !frame.key_frame {
+                    track.request_key_frame(self.key_frame_request_interval);
+                    continue;
+                }
+                track.waiting_for_key_frame = false;
+            }
+
+            let timestamp = track.extend_timestamp(frame.timestamp);
+            let (first_timestamp, first_time) = *track
+                .first_frame
+                .get_or_insert_with(|| (timestamp, received_at - self.started_at));
+            let time = first_time
+                + rtp_duration(timestamp.saturating_sub(first_timestamp), track.clock_rate);
+
+            self.muxer
+                .write_frame(track_index, time, frame.key_frame, &frame.data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Records consumers into a file, see [module-level documentation](self).
+///
+/// Recording is finalized by [`Recorder::stop()`], when the recorder is dropped or when all
+/// consumers are closed. Recorder keeps consumers alive until the recording is finalized.
+#[must_use = "Recording stops when recorder is dropped"]
+pub struct Recorder {
+    sender: mpsc::SyncSender<Message>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+    handlers: Vec<HandlerId>,
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field(
+                "finished",
+                &self.thread.as_ref().map(JoinHandle::is_finished),
+            )
+            .finish()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.handlers.clear();
+        let _ = self.sender.send(Message::Stop);
+    }
+}
+
+impl Recorder {
+    /// Start recording consumers into a new file at specified path.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        consumers: Vec<Consumer>,
+        options: RecorderOptions,
+    ) -> Result<Self, RecorderError> {
+        let file = File::create(path)?;
+
+        Self::new(BufWriter::new(file), consumers, options)
+    }
+
+    /// Start recording consumers into given writer.
+    ///
+    /// Consumers must belong to a [`DirectTransport`](crate::direct_transport::DirectTransport),
+    /// since RTP packets are received with [`Consumer::on_rtp()`].
+    pub fn new<W: Write + Send + 'static>(
+        writer: W,
+        consumers: Vec<Consumer>,
+        options: RecorderOptions,
+    ) -> Result<Self, RecorderError> {
+        debug!("new()");
+
+        if consumers.is_empty() {
+            return Err(RecorderError::NoConsumers);
+        }
+
+        let mut tracks = Vec::with_capacity(consumers.len());
+        let mut webm_tracks = Vec::with_capacity(consumers.len());
+        for consumer in consumers {
+            let codec = consumer
+                .rtp_parameters()
+                .codecs
+                .first()
+                .ok_or(RecorderError::NoConsumers)?
+                .clone();
+            let mime_type = codec.mime_type();
+            let depacketizer_codec = DepacketizerCodec::from_mime_type(mime_type)
+                .ok_or(RecorderError::UnsupportedCodec(mime_type))?;
+            let channels = match &codec {
+                RtpCodecParameters::Audio { channels, .. } => channels.get(),
+                RtpCodecParameters::Video { .. } => 0,
+            };
+
+            webm_tracks.push(WebmTrack {
+                codec: depacketizer_codec,
+                clock_rate: codec.clock_rate().get(),
+                channels,
+            });
+            tracks.push(Track {
+                consumer,
+                payload_type: codec.payload_type(),
+                clock_rate: codec.clock_rate().get(),
+                jitter_buffer: JitterBuffer::new(options.jitter_buffer),
+                depacketizer: Depacketizer::new(depacketizer_codec),
+                waiting_for_key_frame: depacketizer_codec != DepacketizerCodec::Opus,
+                last_key_frame_request: None,
+                first_frame: None,
+                highest_timestamp: None,
+                closed: false,
+            });
+        }
+
+        let audio_tracks = tracks.iter().filter(|track| !track.is_video()).count();
+        let video_tracks = tracks.len() - audio_tracks;
+        let muxer = match options.format {
+            RecordingFormat::WebM => {
+                if audio_tracks > 1 || video_tracks > 1 {
+                    return Err(RecorderError::UnsupportedTracks);
+                }
+                Muxer::WebM(WebmWriter::new(writer, webm_tracks))
+            }
+            RecordingFormat::Ogg => {
+                if audio_tracks != 1 || video_tracks != 0 {
+                    return Err(RecorderError::UnsupportedTracks);
+                }
+                Muxer::Ogg(OggOpusWriter::new(writer, webm_tracks[0].channels)?)
+            }
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(MESSAGE_QUEUE_CAPACITY);
+
+        let mut handlers = Vec::with_capacity(tracks.len() * 2);
+        for (index, track) in tracks.iter().enumerate() {
+            handlers.push(track.consumer.on_rtp({
+                let sender = sender.clone();
+
+                move |packet| {
+                    let result = sender.try_send(Message::Rtp {
+                        track: index,
+                        packet: packet.to_vec(),
+                        received_at: Instant::now(),
+                    });
+                    if let Err(mpsc::TrySendError::Full(_)) = result {
+                        warn!("recording thread is too slow, dropping RTP packet");
+                    }
+                }
+            }));
+            handlers.push(track.consumer.on_close({
+                let sender = sender.clone();
+
+                move || {
+                    let _ = sender.send(Message::Closed { track: index });
+                }
+            }));
+        }
+
+        let session = Session {
+            tracks,
+            muxer,
+            started_at: Instant::now(),
+            key_frame_request_interval: options.key_frame_request_interval,
+        };
+        let thread = thread::Builder::new()
+            .name("mediasoup-recorder".to_string())
+            .spawn(move || {
+                let result = session.run(receiver);
+                if let Err(error) = &result {
+                    error!("recording failed: {}", error);
+                }
+                result
+            })?;
+
+        Ok(Self {
+            sender,
+            thread: Some(thread),
+            handlers,
+        })
+    }
+
+    /// Stop recording and wait for the file to be finalized.
+    pub fn stop(mut self) -> Result<(), RecorderError> {
+        debug!("stop()");
+
+        self.handlers.clear();
+        let _ = self.sender.send(Message::Stop);
+
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => Ok(result?),
+            Some(Err(_)) => {
+                Err(io::Error::new(io::ErrorKind::Other, "Recorder thread panicked").into())
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Ogg encapsulation of Opus as defined in [RFC 7845](https://tools.ietf.org/html/rfc7845).
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+const OPUS_CLOCK_RATE: u64 = 48000;
+/// Flush page once it covers about a second of audio with 20ms frames.
+const MAX_PACKETS_PER_PAGE: usize = 50;
+const MAX_LACING_VALUES: usize = 255;
+
+const HEADER_TYPE_BOS: u8 = 0x02;
+const HEADER_TYPE_EOS: u8 = 0x04;
+
+/// `OpusHead` identification header, also used as Matroska `CodecPrivate`.
+pub(super) fn opus_head(channels: u8) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    // Version
+    head.push(1);
+    head.push(channels);
+    // Pre-skip
+    head.extend_from_slice(&0_u16.to_le_bytes());
+    // Input sample rate
+    head.extend_from_slice(&48000_u32.to_le_bytes());
+    // Output gain
+    head.extend_from_slice(&0_u16.to_le_bytes());
+    // Channel mapping family
+    head.push(0);
+    head
+}
+
+/// Number of 48kHz samples in Opus packet according to its TOC byte.
+pub(crate) fn opus_packet_samples(packet: &[u8]) -> u64 {
+    let Some(&toc) = packet.first() else {
+        return 0;
+    };
+    let config = toc >> 3;
+    let frame_samples = match config {
+        // SILK: 10, 20, 40, 60ms
+        0..=11 => [480, 960, 1920, 2880][usize::from(config % 4)],
+        // Hybrid: 10, 20ms
+        12..=15 => [480, 960][usize::from(config % 2)],
+        // CELT: 2.5, 5, 10, 20ms
+        _ => [120, 240, 480, 960][usize::from(config % 4)],
+    };
+    let frames = match toc & 0x03 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => packet.get(1).map_or(0, |count| u64::from(count & 0x3F)),
+    };
+
+    frame_samples * frames
+}
+
+/// CRC-32 used by Ogg (polynomial `0x04C11DB7`, no reflection, zero initial value).
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0, |mut crc, &byte| {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                crc << 1 ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Writer of Ogg/Opus stream with a single logical bitstream.
+pub(crate) struct OggOpusWriter<W: Write> {
+    writer: W,
+    serial: u32,
+    page_sequence: u32,
+    lacing_values: Vec<u8>,
+    data: Vec<u8>,
+    packets: usize,
+    start_time: Option<Duration>,
+    granule_position: u64,
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    /// Create writer, identification and comment headers are written immediately.
+    pub(crate) fn new(writer: W, channels: u8) -> io::Result<Self> {
+        let mut ogg_writer = Self {
+            writer,
+            serial: fastrand::u32(..),
+            page_sequence: 0,
+            lacing_values: Vec::new(),
+            data: Vec::new(),
+            packets: 0,
+            start_time: None,
+            granule_position: 0,
+        };
+
+        ogg_writer.add_packet(&opus_head(channels));
+        ogg_writer.flush_page(HEADER_TYPE_BOS)?;
+
+        let vendor = concat!("mediasoup-rust ", env!("CARGO_PKG_VERSION"));
+        let mut tags = Vec::new();
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        // No user comments
+        tags.extend_from_slice(&0_u32.to_le_bytes());
+        ogg_writer.add_packet(&tags);
+        ogg_writer.flush_page(0)?;
+
+        Ok(ogg_writer)
+    }
+
+    /// Write Opus packet captured at specified time since the beginning of the recording.
+    pub(crate) fn write_frame(&mut self, time: Duration, data: &[u8]) -> io::Result<()> {
+        let start_time = *self.start_time.get_or_insert(time);
+        let offset = time.saturating_sub(start_time);
+        let position = offset.as_nanos() as u64 * OPUS_CLOCK_RATE / 1_000_000_000;
+
+        let lacing_values = data.len() / 255 + 1;
+        if self.packets == MAX_PACKETS_PER_PAGE
+            || self.lacing_values.len() + lacing_values > MAX_LACING_VALUES
+        {
+            self.flush_page(0)?;
+        }
+
+        self.add_packet(data);
+        self.granule_position = self
+            .granule_position
+            .max(position + opus_packet_samples(data));
+
+        Ok(())
+    }
+
+    /// Write the last page and flush underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.flush_page(HEADER_TYPE_EOS)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn add_packet(&mut self, data: &[u8]) {
+        self.lacing_values
+            .extend(std::iter::repeat(255).take(data.len() / 255));
+        self.lacing_values.push((data.len() % 255) as u8);
+        self.data.extend_from_slice(data);
+        self.packets += 1;
+    }
+
+    fn flush_page(&mut self, header_type: u8) -> io::Result<()> {
+        let mut page = Vec::with_capacity(27 + self.lacing_values.len() + self.data.len());
+        page.extend_from_slice(b"OggS");
+        // Version
+        page.push(0);
+        page.push(header_type);
+        page.extend_from_slice(&self.granule_position.to_le_bytes());
+        page.extend_from_slice(&self.serial.to_le_bytes());
+        page.extend_from_slice(&self.page_sequence.to_le_bytes());
+        // CRC is calculated with zeroes in its place
+        page.extend_from_slice(&[0; 4]);
+        page.push(self.lacing_values.len() as u8);
+        page.extend_from_slice(&self.lacing_values);
+        page.extend_from_slice(&self.data);
+
+        let crc = crc32(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+
+        self.writer.write_all(&page)?;
+
+        self.page_sequence += 1;
+        self.lacing_values.clear();
+        self.data.clear();
+        self.packets = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use super::ogg::{crc32, opus_packet_samples, OggOpusWriter};
+use super::rtp_duration;
+use super::webm::{VideoConfig, WebmTrack, WebmWriter};
+use crate::rtp::depacketizer::DepacketizerCodec;
+use std::time::Duration;
+
+/// Split Ogg stream into pages of header type, granule position and packets.
+fn parse_ogg(mut data: &[u8]) -> Vec<(u8, u64, Vec<Vec<u8>>)> {
+    let mut pages = Vec::new();
+    while !data.is_empty() {
+        assert_eq!(&data[..4], b"OggS");
+        let header_type = data[5];
+        let granule_position = u64::from_le_bytes(data[6..14].try_into().unwrap());
+        let segments = usize::from(data[26]);
+        let lacing_values = &data[27..27 + segments];
+        let mut offset = 27 + segments;
+        let mut packets = Vec::new();
+        let mut packet = Vec::new();
+        for &lacing_value in lacing_values {
+            packet.extend_from_slice(&data[offset..offset + usize::from(lacing_value)]);
+            offset += usize::from(lacing_value);
+            if lacing_value < 255 {
+                packets.push(std::mem::take(&mut packet));
+            }
+        }
+        pages.push((header_type, granule_position, packets));
+        data = &data[offset..];
+    }
+    pages
+}
+
+#[test]
+fn ogg_opus() {
+    let mut ogg_writer = OggOpusWriter::new(Vec::new(), 2).unwrap();
+    // 20ms CELT frames
+    for index in 0..60_u64 {
+        ogg_writer
+            .write_frame(
+                Duration::from_millis(1000 + index * 20),
+                &[0xFC, index as u8],
+            )
+            .unwrap();
+    }
+    let data = ogg_writer.finish().unwrap();
+
+    let pages = parse_ogg(&data);
+    assert_eq!(pages.len(), 4);
+    assert_eq!(pages[0].0, 0x02);
+    assert_eq!(&pages[0].2[0][..8], b"OpusHead");
+    assert_eq!(pages[0].2[0][9], 2);
+    assert_eq!(&pages[1].2[0][..8], b"OpusTags");
+    assert_eq!(pages[2].1, 50 * 960);
+    assert_eq!(pages[2].2.len(), 50);
+    assert_eq!(pages[3].0, 0x04);
+    assert_eq!(pages[3].1, 60 * 960);
+    assert_eq!(pages[3].2[9], vec![0xFC, 59]);
+}
+
+#[test]
+fn ogg_crc() {
+    assert_eq!(crc32(b"123456789"), 0x89A1_897F);
+}
+
+#[test]
+fn opus_samples() {
+    // SILK 60ms
+    assert_eq!(opus_packet_samples(&[0x18]), 2880);
+    // Hybrid 10ms, two frames
+    assert_eq!(opus_packet_samples(&[0x61]), 960);
+    // CELT 2.5ms, arbitrary number of frames
+    assert_eq!(opus_packet_samples(&[0x83, 0x05]), 600);
+    assert_eq!(opus_packet_samples(&[]), 0);
+}
+
+#[test]
+fn video_config() {
+    // VP8 key frame 640x480
+    assert_eq!(
+        VideoConfig::from_key_frame(
+            DepacketizerCodec::Vp8,
+            &[0x10, 0x02, 0x00, 0x9D, 0x01, 0x2A, 0x80, 0x02, 0xE0, 0x01],
+        ),
+        Some(VideoConfig {
+            width: 640,
+            height: 480,
+            codec_private: None,
+        }),
+    );
+
+    // VP9 profile 0 key frame 1280x720
+    assert_eq!(
+        VideoConfig::from_key_frame(
+            DepacketizerCodec::Vp9,
+            &[0x82, 0x49, 0x83, 0x42, 0x00, 0x4F, 0xF0, 0x2C, 0xF0],
+        ),
+        Some(VideoConfig {
+            width: 1280,
+            height: 720,
+            codec_private: None,
+        }),
+    );
+
+    // H264 constrained baseline 320x240
+    let sps = [0x67, 0x42, 0xC0, 0x0D, 0xDA, 0x05, 0x07, 0xC4];
+    let pps = [0x68, 0xCE, 0x3C, 0x80];
+    let mut frame = vec![0, 0, 0, 1];
+    frame.extend_from_slice(&sps);
+    frame.extend_from_slice(&[0, 0, 0, 1]);
+    frame.extend_from_slice(&pps);
+    frame.extend_from_slice(&[0, 0, 0, 1, 0x65, 0x88]);
+    let video_config = VideoConfig::from_key_frame(DepacketizerCodec::H264, &frame).unwrap();
+    assert_eq!((video_config.width, video_config.height), (320, 240));
+    let avcc = video_config.codec_private.unwrap();
+    assert_eq!(&avcc[..6], &[1, 0x42, 0xC0, 0x0D, 0xFF, 0xE1]);
+    assert_eq!(&avcc[8..16], &sps);
+    assert_eq!(&avcc[19..], &pps);
+
+    // AV1 profile 0 level 4.0 640x480, sequence header followed by frame OBU
+    let sequence_header = [
+        0x0A, 0x0B, 0x00, 0x00, 0x00, 0x42, 0x62, 0x7F, 0xEF, 0x98, 0x4F, 0x30, 0x08,
+    ];
+    let frame = [&sequence_header[..], &[0x32, 0x02, 0x10, 0x00]].concat();
+    assert_eq!(
+        VideoConfig::from_key_frame(DepacketizerCodec::Av1, &frame),
+        Some(VideoConfig {
+            width: 640,
+            height: 480,
+            codec_private: Some([&[0x81, 0x08, 0x0C, 0x00][..], &sequence_header].concat()),
+        }),
+    );
+    assert_eq!(
+        VideoConfig::from_key_frame(DepacketizerCodec::Av1, &frame[sequence_header.len()..]),
+        None,
+    );
+
+    assert_eq!(
+        VideoConfig::from_key_frame(DepacketizerCodec::Vp8, &[0x10, 0x02]),
+        None,
+    );
+}
+
+#[test]
+fn webm() {
+    let mut webm_writer = WebmWriter::new(
+        Vec::new(),
+        vec![
+            WebmTrack {
+                codec: DepacketizerCodec::Opus,
+                clock_rate: 48000,
+                channels: 2,
+            },
+            WebmTrack {
+                codec: DepacketizerCodec::Vp8,
+                clock_rate: 90000,
+                channels: 0,
+            },
+        ],
+    );
+    let key_frame = [0x10, 0x02, 0x00, 0x9D, 0x01, 0x2A, 0x80, 0x02, 0xE0, 0x01];
+
+    // Nothing is written before the first video key frame
+    webm_writer
+        .write_frame(0, Duration::from_millis(10), true, &[0xFC])
+        .unwrap();
+    webm_writer
+        .write_frame(1, Duration::from_millis(20), false, &[0x11])
+        .unwrap();
+    webm_writer
+        .write_frame(1, Duration::from_millis(100), true, &key_frame)
+        .unwrap();
+    webm_writer
+        .write_frame(0, Duration::from_millis(90), true, &[0xFC, 1])
+        .unwrap();
+    webm_writer
+        .write_frame(0, Duration::from_millis(120), true, &[0xFC, 2])
+        .unwrap();
+    webm_writer
+        .write_frame(1, Duration::from_millis(6200), false, &[0x11])
+        .unwrap();
+    let data = webm_writer.finish().unwrap();
+
+    // EBML header with "webm" doc type
+    assert_eq!(&data[..4], &[0x1A, 0x45, 0xDF, 0xA3]);
+    assert!(data.windows(7).any(|window| window == b"\x42\x82\x84webm"));
+    assert!(data.windows(6).any(|window| window == b"A_OPUS"));
+    assert!(data.windows(5).any(|window| window == b"V_VP8"));
+    // PixelWidth 640, PixelHeight 480
+    assert!(data
+        .windows(4)
+        .any(|window| window == [0xB0, 0x82, 0x02, 0x80]));
+    assert!(data
+        .windows(4)
+        .any(|window| window == [0xBA, 0x82, 0x01, 0xE0]));
+
+    let cluster_id = [0x1F, 0x43, 0xB6, 0x75];
+    let clusters = data
+        .windows(4)
+        .filter(|window| *window == cluster_id)
+        .count();
+    assert_eq!(clusters, 2);
+
+    // Second cluster starts after 5 seconds because there are no more key frames
+    let second_cluster = data
+        .windows(4)
+        .rposition(|window| window == cluster_id)
+        .unwrap();
+    // Size, Timestamp element with 6100ms
+    assert_eq!(
+        &data[second_cluster + 4..second_cluster + 9],
+        &[0x8B, 0xE7, 0x82, 0x17, 0xD4],
+    );
+    // SimpleBlock of track 2, relative timestamp 0, no flags
+    assert_eq!(
+        &data[second_cluster + 9..],
+        &[0xA3, 0x85, 0x82, 0x00, 0x00, 0x00, 0x11],
+    );
+}
+
+#[test]
+fn rtp_timestamp_duration() {
+    assert_eq!(rtp_duration(0, 90_000), Duration::ZERO);
+    assert_eq!(rtp_duration(135_000, 90_000), Duration::from_millis(1500));
+    assert_eq!(rtp_duration(1, 48_000), Duration::from_nanos(20_833));
+    // Would overflow if multiplied by 10^9 first (more than ~2.4 days of 90 kHz video)
+    assert_eq!(
+        rtp_duration(90_000 * 86_400 * 3, 90_000),
+        Duration::from_secs(86_400 * 3)
+    );
+}
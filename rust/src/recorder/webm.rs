@@ -0,0 +1,784 @@
+//! Minimal [Matroska](https://www.matroska.org/technical/elements.html) writer that produces WebM
+//! files (or Matroska files when H264 is recorded).
+//!
+//! Segment is written with unknown size so that the file is playable even if recording was
+//! interrupted, clusters are buffered in memory and written with known size.
+
+use super::ogg::opus_head;
+use crate::rtp::depacketizer::{
+    annex_b_nal_units, read_leb128, DepacketizerCodec, AV1_OBU_SEQUENCE_HEADER,
+};
+use std::io::{self, Write};
+use std::time::Duration;
+
+const EBML: u32 = 0x1A45_DFA3;
+const EBML_VERSION: u32 = 0x4286;
+const EBML_READ_VERSION: u32 = 0x42F7;
+const EBML_MAX_ID_LENGTH: u32 = 0x42F2;
+const EBML_MAX_SIZE_LENGTH: u32 = 0x42F3;
+const DOC_TYPE: u32 = 0x4282;
+const DOC_TYPE_VERSION: u32 = 0x4287;
+const DOC_TYPE_READ_VERSION: u32 = 0x4285;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const SEEK_PRE_ROLL: u32 = 0x56BB;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+const TIMESTAMP: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+
+const UNKNOWN_SIZE: &[u8] = &[0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+const TRACK_TYPE_VIDEO: u64 = 1;
+const TRACK_TYPE_AUDIO: u64 = 2;
+
+/// Start a new cluster at least this often even without video key frames.
+const MAX_CLUSTER_DURATION: Duration = Duration::from_secs(5);
+
+/// Track to be written into the file.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WebmTrack {
+    pub(crate) codec: DepacketizerCodec,
+    pub(crate) clock_rate: u32,
+    pub(crate) channels: u8,
+}
+
+impl WebmTrack {
+    fn is_video(&self) -> bool {
+        self.codec != DepacketizerCodec::Opus
+    }
+}
+
+fn write_size(buffer: &mut Vec<u8>, size: u64) {
+    let mut length = 1;
+    // All ones value is reserved for unknown size
+    while length < 8 && size >= (1 << (7 * length)) - 1 {
+        length += 1;
+    }
+    let marked = size | 1 << (7 * length);
+    buffer.extend_from_slice(&marked.to_be_bytes()[8 - length..]);
+}
+
+fn write_id(buffer: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&byte| byte == 0).count();
+    buffer.extend_from_slice(&bytes[skip..]);
+}
+
+fn write_element(buffer: &mut Vec<u8>, id: u32, data: &[u8]) {
+    write_id(buffer, id);
+    write_size(buffer, data.len() as u64);
+    buffer.extend_from_slice(data);
+}
+
+fn write_uint(buffer: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&byte| byte == 0).count().min(7);
+    write_element(buffer, id, &bytes[skip..]);
+}
+
+fn write_float(buffer: &mut Vec<u8>, id: u32, value: f64) {
+    write_element(buffer, id, &value.to_be_bytes());
+}
+
+fn write_master<F: FnOnce(&mut Vec<u8>)>(buffer: &mut Vec<u8>, id: u32, write_children: F) {
+    let mut children = Vec::new();
+    write_children(&mut children);
+    write_element(buffer, id, &children);
+}
+
+struct Cluster {
+    timestamp: u64,
+    blocks: Vec<u8>,
+}
+
+/// Writer of WebM/Matroska file, see [module-level documentation](self).
+pub(crate) struct WebmWriter<W: Write> {
+    writer: W,
+    tracks: Vec<WebmTrack>,
+    /// Codec private data and dimensions of video track, known after its first key frame.
+    video_config: Option<VideoConfig>,
+    start_time: Option<Duration>,
+    cluster: Option<Cluster>,
+}
+
+impl<W: Write> WebmWriter<W> {
+    pub(crate) fn new(writer: W, tracks: Vec<WebmTrack>) -> Self {
+        Self {
+            writer,
+            tracks,
+            video_config: None,
+            start_time: None,
+            cluster: None,
+        }
+    }
+
+    /// Write frame of specified track captured at specified time since the beginning of the
+    /// recording.
+    ///
+    /// If there is a video track, nothing is written until the first video key frame is
+    /// received, since video codec configuration is taken from it.
+    pub(crate) fn write_frame(
+        &mut self,
+        track: usize,
+        time: Duration,
+        key_frame: bool,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let webm_track = self.tracks[track];
+
+        let start_time = match self.start_time {
+            Some(start_time) => start_time,
+            None => {
+                if self.tracks.iter().any(WebmTrack::is_video) {
+                    if !webm_track.is_video() || !key_frame {
+                        return Ok(());
+                    }
+                    let Some(video_config) = VideoConfig::from_key_frame(webm_track.codec, data)
+                    else {
+                        return Ok(());
+                    };
+                    self.video_config.replace(video_config);
+                }
+                self.write_header()?;
+                *self.start_time.insert(time)
+            }
+        };
+
+        let timestamp = time.saturating_sub(start_time).as_millis() as u64;
+
+        let start_cluster = match &self.cluster {
+            Some(cluster) => {
+                (webm_track.is_video() && key_frame)
+                    || timestamp >= cluster.timestamp + MAX_CLUSTER_DURATION.as_millis() as u64
+            }
+            None => true,
+        };
+        if start_cluster {
+            self.flush_cluster()?;
+            self.cluster.replace(Cluster {
+                timestamp,
+                blocks: Vec::new(),
+            });
+        }
+        let cluster = self
+            .cluster
+            .as_mut()
+            .expect("Cluster was just started; qed");
+
+        let data = if webm_track.codec == DepacketizerCodec::H264 {
+            // Matroska stores length-prefixed NAL units
+            let mut converted = Vec::with_capacity(data.len() + 16);
+            for nal_unit in annex_b_nal_units(data) {
+                converted.extend_from_slice(&(nal_unit.len() as u32).to_be_bytes());
+                converted.extend_from_slice(nal_unit);
+            }
+            converted
+        } else {
+            data.to_vec()
+        };
+
+        let relative_timestamp = (timestamp as i64 - cluster.timestamp as i64)
+            .clamp(i64::from(i16::MIN), i64::from(i16::MAX))
+            as i16;
+
+        let mut block = Vec::with_capacity(4 + data.len());
+        // Track number as EBML variable size integer
+        write_size(&mut block, track as u64 + 1);
+        block.extend_from_slice(&relative_timestamp.to_be_bytes());
+        block.push(if key_frame { 0x80 } else { 0 });
+        block.extend_from_slice(&data);
+        write_element(&mut cluster.blocks, SIMPLE_BLOCK, &block);
+
+        Ok(())
+    }
+
+    /// Write the last cluster and flush underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.flush_cluster()?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let doc_type = if self
+            .tracks
+            .iter()
+            .any(|track| track.codec == DepacketizerCodec::H264)
+        {
+            "matroska"
+        } else {
+            "webm"
+        };
+
+        let mut header = Vec::new();
+        write_master(&mut header, EBML, |ebml| {
+            write_uint(ebml, EBML_VERSION, 1);
+            write_uint(ebml, EBML_READ_VERSION, 1);
+            write_uint(ebml, EBML_MAX_ID_LENGTH, 4);
+            write_uint(ebml, EBML_MAX_SIZE_LENGTH, 8);
+            write_element(ebml, DOC_TYPE, doc_type.as_bytes());
+            write_uint(ebml, DOC_TYPE_VERSION, 4);
+            write_uint(ebml, DOC_TYPE_READ_VERSION, 2);
+        });
+
+        write_id(&mut header, SEGMENT);
+        header.extend_from_slice(UNKNOWN_SIZE);
+
+        let app = concat!("mediasoup-rust ", env!("CARGO_PKG_VERSION"));
+        write_master(&mut header, INFO, |info| {
+            // Milliseconds
+            write_uint(info, TIMESTAMP_SCALE, 1_000_000);
+            write_element(info, MUXING_APP, app.as_bytes());
+            write_element(info, WRITING_APP, app.as_bytes());
+        });
+
+        write_master(&mut header, TRACKS, |tracks| {
+            for (index, track) in self.tracks.iter().enumerate() {
+                write_master(tracks, TRACK_ENTRY, |entry| {
+                    write_uint(entry, TRACK_NUMBER, index as u64 + 1);
+                    write_uint(entry, TRACK_UID, index as u64 + 1);
+                    match track.codec {
+                        DepacketizerCodec::Opus => {
+                            write_uint(entry, TRACK_TYPE, TRACK_TYPE_AUDIO);
+                            write_element(entry, CODEC_ID, b"A_OPUS");
+                            write_element(entry, CODEC_PRIVATE, &opus_head(track.channels));
+                            // 80ms, recommended by Opus in Matroska specification
+                            write_uint(entry, SEEK_PRE_ROLL, 80_000_000);
+                            write_master(entry, AUDIO, |audio| {
+                                write_float(audio, SAMPLING_FREQUENCY, f64::from(track.clock_rate));
+                                write_uint(audio, CHANNELS, u64::from(track.channels));
+                            });
+                        }
+                        codec => {
+                            let codec_id: &[u8] = match codec {
+                                DepacketizerCodec::Vp8 => b"V_VP8",
+                                DepacketizerCodec::Vp9 => b"V_VP9",
+                                DepacketizerCodec::Av1 => b"V_AV1",
+                                _ => b"V_MPEG4/ISO/AVC",
+                            };
+                            write_uint(entry, TRACK_TYPE, TRACK_TYPE_VIDEO);
+                            write_element(entry, CODEC_ID, codec_id);
+                            if let Some(video_config) = &self.video_config {
+                                if let Some(codec_private) = &video_config.codec_private {
+                                    write_element(entry, CODEC_PRIVATE, codec_private);
+                                }
+                                write_master(entry, VIDEO, |video| {
+                                    write_uint(video, PIXEL_WIDTH, u64::from(video_config.width));
+                                    write_uint(video, PIXEL_HEIGHT, u64::from(video_config.height));
+                                });
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        self.writer.write_all(&header)
+    }
+
+    fn flush_cluster(&mut self) -> io::Result<()> {
+        let Some(cluster) = self.cluster.take() else {
+            return Ok(());
+        };
+
+        let mut buffer = Vec::with_capacity(cluster.blocks.len() + 16);
+        write_id(&mut buffer, CLUSTER);
+        let mut timestamp = Vec::new();
+        write_uint(&mut timestamp, TIMESTAMP, cluster.timestamp);
+        write_size(&mut buffer, (timestamp.len() + cluster.blocks.len()) as u64);
+        buffer.extend_from_slice(&timestamp);
+        buffer.extend_from_slice(&cluster.blocks);
+
+        self.writer.write_all(&buffer)
+    }
+}
+
+/// Video track configuration extracted from the first key frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(super) struct VideoConfig {
+    pub(super) width: u16,
+    pub(super) height: u16,
+    pub(super) codec_private: Option<Vec<u8>>,
+}
+
+impl VideoConfig {
+    pub(super) fn from_key_frame(codec: DepacketizerCodec, data: &[u8]) -> Option<Self> {
+        match codec {
+            DepacketizerCodec::Opus => None,
+            DepacketizerCodec::Vp8 => {
+                // 3 bytes of frame tag are followed by start code and dimensions
+                if data.get(3..6)? != [0x9D, 0x01, 0x2A] {
+                    return None;
+                }
+                let size = data.get(6..10)?;
+                Some(Self {
+                    width: u16::from_le_bytes([size[0], size[1]]) & 0x3FFF,
+                    height: u16::from_le_bytes([size[2], size[3]]) & 0x3FFF,
+                    codec_private: None,
+                })
+            }
+            DepacketizerCodec::Vp9 => {
+                let (width, height) = vp9_dimensions(data)?;
+                Some(Self {
+                    width,
+                    height,
+                    codec_private: None,
+                })
+            }
+            DepacketizerCodec::Av1 => {
+                // Frames coming from depacketizer always have OBU size fields
+                let mut rest = data;
+                while let Some(&header) = rest.first() {
+                    if header & 0x02 == 0 {
+                        return None;
+                    }
+                    let header_size = if header & 0x04 != 0 { 2 } else { 1 };
+                    let (size, size_field_size) = read_leb128(rest.get(header_size..)?)?;
+                    let obu_size = header_size + size_field_size + usize::try_from(size).ok()?;
+                    let obu = rest.get(..obu_size)?;
+
+                    if (header >> 3) & 0x0F == AV1_OBU_SEQUENCE_HEADER {
+                        let sequence_header =
+                            Av1SequenceHeader::parse(&obu[header_size + size_field_size..])?;
+
+                        // AV1CodecConfigurationRecord followed by sequence header OBU
+                        let mut av1c = vec![
+                            0x81,
+                            sequence_header.profile << 5 | sequence_header.level,
+                            sequence_header.tier << 7
+                                | sequence_header.high_bitdepth << 6
+                                | sequence_header.twelve_bit << 5
+                                | sequence_header.monochrome << 4
+                                | sequence_header.subsampling_x << 3
+                                | sequence_header.subsampling_y << 2
+                                | sequence_header.chroma_sample_position,
+                            0,
+                        ];
+                        av1c.extend_from_slice(obu);
+
+                        return Some(Self {
+                            width: sequence_header.width,
+                            height: sequence_header.height,
+                            codec_private: Some(av1c),
+                        });
+                    }
+
+                    rest = &rest[obu_size..];
+                }
+
+                None
+            }
+            DepacketizerCodec::H264 => {
+                let sps = annex_b_nal_units(data).find(|nal_unit| nal_unit[0] & 0x1F == 7)?;
+                let pps = annex_b_nal_units(data).find(|nal_unit| nal_unit[0] & 0x1F == 8)?;
+                let (width, height) = h264_dimensions(sps)?;
+                if sps.len() < 4 {
+                    return None;
+                }
+
+                // AVCDecoderConfigurationRecord
+                let mut avcc = vec![1, sps[1], sps[2], sps[3], 0xFF, 0xE1];
+                avcc.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+                avcc.extend_from_slice(sps);
+                avcc.push(1);
+                avcc.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+                avcc.extend_from_slice(pps);
+
+                Some(Self {
+                    width,
+                    height,
+                    codec_private: Some(avcc),
+                })
+            }
+        }
+    }
+}
+
+/// MSB-first bit reader with Exp-Golomb support.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bit(&mut self) -> Option<u32> {
+        let byte = self.data.get(self.position / 8)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Some(u32::from(bit))
+    }
+
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        (0..count).try_fold(0, |value, _| Some(value << 1 | self.bit()?))
+    }
+
+    fn ue(&mut self) -> Option<u32> {
+        let mut leading_zeros = 0;
+        while self.bit()? == 0 {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return None;
+            }
+        }
+        Some((1_u32 << leading_zeros) - 1 + self.bits(leading_zeros)?)
+    }
+
+    fn se(&mut self) -> Option<i32> {
+        let value = self.ue()?;
+        Some(if value % 2 == 1 {
+            ((value + 1) / 2) as i32
+        } else {
+            -((value / 2) as i32)
+        })
+    }
+}
+
+/// Frame size from uncompressed header of VP9 key frame.
+fn vp9_dimensions(data: &[u8]) -> Option<(u16, u16)> {
+    let mut reader = BitReader::new(data);
+    if reader.bits(2)? != 2 {
+        return None;
+    }
+    let profile = reader.bit()? | reader.bit()? << 1;
+    if profile == 3 {
+        reader.bit()?;
+    }
+    // show_existing_frame
+    if reader.bit()? == 1 {
+        return None;
+    }
+    // frame_type must be key frame
+    if reader.bit()? != 0 {
+        return None;
+    }
+    // show_frame, error_resilient_mode
+    reader.bits(2)?;
+    if reader.bits(24)? != 0x49_8342 {
+        return None;
+    }
+    // Color config
+    if profile >= 2 {
+        // ten_or_twelve_bit
+        reader.bit()?;
+    }
+    let color_space = reader.bits(3)?;
+    // Anything but sRGB
+    if color_space != 7 {
+        // color_range
+        reader.bit()?;
+        if profile == 1 || profile == 3 {
+            // subsampling_x, subsampling_y, reserved_zero
+            reader.bits(3)?;
+        }
+    } else if profile == 1 || profile == 3 {
+        // reserved_zero
+        reader.bit()?;
+    }
+
+    let width = reader.bits(16)? + 1;
+    let height = reader.bits(16)? + 1;
+
+    Some((u16::try_from(width).ok()?, u16::try_from(height).ok()?))
+}
+
+/// Fields of AV1 sequence header needed for `AV1CodecConfigurationRecord`.
+struct Av1SequenceHeader {
+    profile: u8,
+    level: u8,
+    tier: u8,
+    high_bitdepth: u8,
+    twelve_bit: u8,
+    monochrome: u8,
+    subsampling_x: u8,
+    subsampling_y: u8,
+    chroma_sample_position: u8,
+    width: u16,
+    height: u16,
+}
+
+impl Av1SequenceHeader {
+    /// Parses sequence header OBU payload as described in section 5.5 of AV1 specification.
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = BitReader::new(data);
+        let profile = reader.bits(3)?;
+        // still_picture
+        reader.bit()?;
+        let reduced_still_picture_header = reader.bit()? == 1;
+
+        let level;
+        let mut tier = 0;
+        if reduced_still_picture_header {
+            level = reader.bits(5)?;
+        } else {
+            let mut buffer_delay_length = 0;
+            let timing_info_present = reader.bit()? == 1;
+            let mut decoder_model_info_present = false;
+            if timing_info_present {
+                // num_units_in_display_tick, time_scale
+                reader.bits(32)?;
+                reader.bits(32)?;
+                // equal_picture_interval
+                if reader.bit()? == 1 {
+                    // num_ticks_per_picture_minus_1
+                    reader.ue()?;
+                }
+                decoder_model_info_present = reader.bit()? == 1;
+                if decoder_model_info_present {
+                    buffer_delay_length = reader.bits(5)? + 1;
+                    // num_units_in_decoding_tick
+                    reader.bits(32)?;
+                    // buffer_removal_time_length_minus_1, frame_presentation_time_length_minus_1
+                    reader.bits(10)?;
+                }
+            }
+            let initial_display_delay_present = reader.bit()? == 1;
+            let operating_points = reader.bits(5)? + 1;
+
+            // Configuration record describes the first operating point
+            let mut first_operating_point = None;
+            for _ in 0..operating_points {
+                // operating_point_idc
+                reader.bits(12)?;
+                let seq_level_idx = reader.bits(5)?;
+                let seq_tier = if seq_level_idx > 7 { reader.bit()? } else { 0 };
+                first_operating_point.get_or_insert((seq_level_idx, seq_tier));
+                if decoder_model_info_present && reader.bit()? == 1 {
+                    // decoder_buffer_delay, encoder_buffer_delay, low_delay_mode_flag
+                    reader.bits(buffer_delay_length)?;
+                    reader.bits(buffer_delay_length)?;
+                    reader.bit()?;
+                }
+                if initial_display_delay_present && reader.bit()? == 1 {
+                    // initial_display_delay_minus_1
+                    reader.bits(4)?;
+                }
+            }
+            (level, tier) = first_operating_point?;
+        }
+
+        let frame_width_bits = reader.bits(4)? + 1;
+        let frame_height_bits = reader.bits(4)? + 1;
+        let width = u16::try_from(reader.bits(frame_width_bits)? + 1).ok()?;
+        let height = u16::try_from(reader.bits(frame_height_bits)? + 1).ok()?;
+
+        if !reduced_still_picture_header {
+            // frame_id_numbers_present_flag
+            if reader.bit()? == 1 {
+                // delta_frame_id_length_minus_2, additional_frame_id_length_minus_1
+                reader.bits(7)?;
+            }
+        }
+        // use_128x128_superblock, enable_filter_intra, enable_intra_edge_filter
+        reader.bits(3)?;
+        if !reduced_still_picture_header {
+            // enable_interintra_compound, enable_masked_compound, enable_warped_motion,
+            // enable_dual_filter
+            reader.bits(4)?;
+            let enable_order_hint = reader.bit()? == 1;
+            if enable_order_hint {
+                // enable_jnt_comp, enable_ref_frame_mvs
+                reader.bits(2)?;
+            }
+            let seq_choose_screen_content_tools = reader.bit()? == 1;
+            let seq_force_screen_content_tools =
+                seq_choose_screen_content_tools || reader.bit()? == 1;
+            if seq_force_screen_content_tools {
+                // seq_choose_integer_mv
+                if reader.bit()? == 0 {
+                    // seq_force_integer_mv
+                    reader.bit()?;
+                }
+            }
+            if enable_order_hint {
+                // order_hint_bits_minus_1
+                reader.bits(3)?;
+            }
+        }
+        // enable_superres, enable_cdef, enable_restoration
+        reader.bits(3)?;
+
+        // Color config
+        let high_bitdepth = reader.bit()?;
+        let twelve_bit = if profile == 2 && high_bitdepth == 1 {
+            reader.bit()?
+        } else {
+            0
+        };
+        let monochrome = if profile == 1 { 0 } else { reader.bit()? };
+        let (color_primaries, transfer_characteristics, matrix_coefficients) = if reader.bit()? == 1
+        {
+            (reader.bits(8)?, reader.bits(8)?, reader.bits(8)?)
+        } else {
+            (2, 2, 2)
+        };
+        let mut chroma_sample_position = 0;
+        let (subsampling_x, subsampling_y) = if monochrome == 1 {
+            (1, 1)
+        } else if color_primaries == 1 && transfer_characteristics == 13 && matrix_coefficients == 0
+        {
+            // sRGB
+            (0, 0)
+        } else {
+            // color_range
+            reader.bit()?;
+            let subsampling = match profile {
+                0 => (1, 1),
+                1 => (0, 0),
+                _ if twelve_bit == 1 => {
+                    let subsampling_x = reader.bit()?;
+                    let subsampling_y = if subsampling_x == 1 { reader.bit()? } else { 0 };
+                    (subsampling_x, subsampling_y)
+                }
+                _ => (1, 0),
+            };
+            if subsampling == (1, 1) {
+                chroma_sample_position = reader.bits(2)?;
+            }
+            subsampling
+        };
+
+        Some(Self {
+            profile: profile as u8,
+            level: level as u8,
+            tier: tier as u8,
+            high_bitdepth: high_bitdepth as u8,
+            twelve_bit: twelve_bit as u8,
+            monochrome: monochrome as u8,
+            subsampling_x: subsampling_x as u8,
+            subsampling_y: subsampling_y as u8,
+            chroma_sample_position: chroma_sample_position as u8,
+            width,
+            height,
+        })
+    }
+}
+
+/// Frame size from H264 sequence parameter set.
+fn h264_dimensions(sps: &[u8]) -> Option<(u16, u16)> {
+    // Remove emulation prevention bytes
+    let mut rbsp = Vec::with_capacity(sps.len());
+    let mut zeros = 0;
+    for &byte in sps.get(1..)? {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+
+    let mut reader = BitReader::new(&rbsp);
+    let profile_idc = reader.bits(8)?;
+    // constraint_set flags and level_idc
+    reader.bits(16)?;
+    // seq_parameter_set_id
+    reader.ue()?;
+
+    let mut chroma_format_idc = 1;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = reader.ue()?;
+        if chroma_format_idc == 3 {
+            // separate_colour_plane_flag
+            reader.bit()?;
+        }
+        // bit_depth_luma_minus8, bit_depth_chroma_minus8
+        reader.ue()?;
+        reader.ue()?;
+        // qpprime_y_zero_transform_bypass_flag
+        reader.bit()?;
+        // seq_scaling_matrix_present_flag
+        if reader.bit()? == 1 {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for index in 0..lists {
+                if reader.bit()? == 1 {
+                    let size = if index < 6 { 16 } else { 64 };
+                    let mut last_scale = 8;
+                    let mut next_scale = 8;
+                    for _ in 0..size {
+                        if next_scale != 0 {
+                            next_scale = (last_scale + reader.se()? + 256) % 256;
+                        }
+                        if next_scale != 0 {
+                            last_scale = next_scale;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // log2_max_frame_num_minus4
+    reader.ue()?;
+    let pic_order_cnt_type = reader.ue()?;
+    if pic_order_cnt_type == 0 {
+        // log2_max_pic_order_cnt_lsb_minus4
+        reader.ue()?;
+    } else if pic_order_cnt_type == 1 {
+        // delta_pic_order_always_zero_flag
+        reader.bit()?;
+        // offset_for_non_ref_pic, offset_for_top_to_bottom_field
+        reader.se()?;
+        reader.se()?;
+        for _ in 0..reader.ue()? {
+            reader.se()?;
+        }
+    }
+    // max_num_ref_frames
+    reader.ue()?;
+    // gaps_in_frame_num_value_allowed_flag
+    reader.bit()?;
+    let width_in_mbs = reader.ue()? + 1;
+    let height_in_map_units = reader.ue()? + 1;
+    let frame_mbs_only = reader.bit()?;
+    if frame_mbs_only == 0 {
+        // mb_adaptive_frame_field_flag
+        reader.bit()?;
+    }
+    // direct_8x8_inference_flag
+    reader.bit()?;
+
+    let mut width = width_in_mbs * 16;
+    let mut height = (2 - frame_mbs_only) * height_in_map_units * 16;
+
+    // frame_cropping_flag
+    if reader.bit()? == 1 {
+        let (crop_unit_x, crop_unit_y) = match chroma_format_idc {
+            0 => (1, 2 - frame_mbs_only),
+            1 => (2, 2 * (2 - frame_mbs_only)),
+            2 => (2, 2 - frame_mbs_only),
+            _ => (1, 2 - frame_mbs_only),
+        };
+        let left = reader.ue()?;
+        let right = reader.ue()?;
+        let top = reader.ue()?;
+        let bottom = reader.ue()?;
+        width = width.checked_sub(crop_unit_x * (left + right))?;
+        height = height.checked_sub(crop_unit_y * (top + bottom))?;
+    }
+
+    Some((u16::try_from(width).ok()?, u16::try_from(height).ok()?))
+}
@@ -0,0 +1,1065 @@
+//! Optional room layer that takes care of participant bookkeeping on top of a [`Router`].
+//!
+//! Each [`Participant`] that joins a [`Room`] gets a pair of WebRTC transports: one for its own
+//! producers and another one for consumers of other participants' producers. Consumers are
+//! created and closed automatically according to participant's [`SubscriptionPolicy`] (only if
+//! [`Router::can_consume()`] allows it), and every change that signaling needs to tell the
+//! participant about is described by [`ParticipantEvent`].
+//!
+//! # Example
+//! ```no_run
+//! # use mediasoup::room::{ParticipantOptions, Room, RoomOptions};
+//! # use mediasoup::router::Router;
+//! # use mediasoup::webrtc_transport::WebRtcTransportOptions;
+//! # async fn f(
+//! #     router: Router,
+//! #     webrtc_transport_options: WebRtcTransportOptions,
+//! # ) -> Result<(), Box<dyn std::error::Error>> {
+//! let room = Room::new(router, RoomOptions::default());
+//! room.on_participant_event(|participant_id, event| {
+//!     // Send `event` to the participant with `participant_id` over signaling channel
+//! })
+//! .detach();
+//!
+//! let participant = room
+//!     .join(ParticipantOptions::new(webrtc_transport_options))
+//!     .await?;
+//! // Participant leaves the room once dropped
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Notes on usage
+//! * Connecting transports (DTLS parameters exchange) is still done by the application using
+//!   [`Participant::producer_transport()`] and [`Participant::consumer_transport()`].
+//! * Producers and consumers owned by the room are closed when they are removed from it, unless
+//!   the application keeps clones of them.
+
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::{Consumer, ConsumerId, ConsumerOptions};
+use crate::data_structures::AppData;
+use crate::producer::{Producer, ProducerId, ProducerOptions};
+use crate::router::Router;
+use crate::rtp_parameters::{MediaKind, RtpCapabilities};
+use crate::transport::{ConsumeError, ProduceError, Transport};
+use crate::uuid_based_wrapper_type;
+use crate::webrtc_transport::{WebRtcTransport, WebRtcTransportOptions};
+use crate::worker::RequestError;
+use event_listener_primitives::{Bag, HandlerId};
+use hash_hasher::{HashedMap, HashedSet};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+uuid_based_wrapper_type!(
+    /// [`Participant`] identifier.
+    ParticipantId
+);
+
+/// Which producers of other participants are consumed by a participant.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SubscriptionPolicy {
+    /// Consume all producers.
+    All,
+    /// Consume only producers that participant explicitly subscribed to with
+    /// [`Participant::subscribe()`].
+    Selective,
+    /// Consume all audio producers and video producers of the specified number of participants
+    /// that were active speakers most recently (see [`Room::set_active_speaker()`]).
+    LastN(usize),
+}
+
+impl Default for SubscriptionPolicy {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+impl SubscriptionPolicy {
+    /// Whether producer of participant with given rank (position among other participants ordered
+    /// from the most recent active speaker) is included.
+    fn includes(&self, kind: MediaKind, rank: usize, subscribed: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Selective => subscribed,
+            Self::LastN(n) => kind == MediaKind::Audio || rank < *n,
+        }
+    }
+}
+
+/// [`Room`] options.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RoomOptions {
+    /// Create consumers paused, so that they can be resumed once the participant is ready to
+    /// receive media (recommended for video, see [`ConsumerOptions::paused`]).
+    ///
+    /// Default false.
+    pub consumers_paused: bool,
+}
+
+/// Options of [`Participant`] joining the room.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ParticipantOptions {
+    /// Options used for both producer and consumer transports.
+    pub webrtc_transport_options: WebRtcTransportOptions,
+    /// RTP capabilities of participant's device, consumers are only created once they are known,
+    /// see also [`Participant::set_rtp_capabilities()`].
+    pub rtp_capabilities: Option<RtpCapabilities>,
+    /// Which producers of other participants are consumed.
+    pub subscription_policy: SubscriptionPolicy,
+    /// Custom application data.
+    pub app_data: AppData,
+}
+
+impl ParticipantOptions {
+    /// Create participant options with given transport options.
+    #[must_use]
+    pub fn new(webrtc_transport_options: WebRtcTransportOptions) -> Self {
+        Self {
+            webrtc_transport_options,
+            rtp_capabilities: None,
+            subscription_policy: SubscriptionPolicy::default(),
+            app_data: AppData::default(),
+        }
+    }
+}
+
+/// Change that signaling needs to tell participant about.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ParticipantEvent {
+    /// Another participant joined the room.
+    ParticipantJoined {
+        /// Participant that joined.
+        participant_id: ParticipantId,
+    },
+    /// Another participant left the room.
+    ParticipantLeft {
+        /// Participant that left.
+        participant_id: ParticipantId,
+    },
+    /// Another participant created a producer (whether it will be consumed depends on
+    /// subscription policy).
+    ProducerAdded {
+        /// Participant that owns the producer.
+        participant_id: ParticipantId,
+        /// Producer id.
+        producer_id: ProducerId,
+        /// Media kind of the producer.
+        kind: MediaKind,
+    },
+    /// Producer of another participant was closed.
+    ProducerRemoved {
+        /// Participant that owned the producer.
+        participant_id: ParticipantId,
+        /// Producer id.
+        producer_id: ProducerId,
+    },
+    /// Consumer was created on participant's consumer transport, its parameters need to be sent to
+    /// the participant.
+    ConsumerAdded {
+        /// Participant that owns consumed producer.
+        participant_id: ParticipantId,
+        /// New consumer.
+        consumer: Consumer,
+    },
+    /// Consumer was closed.
+    ConsumerRemoved {
+        /// Consumer id.
+        consumer_id: ConsumerId,
+    },
+    /// Producer of the consumer was paused.
+    ConsumerPaused {
+        /// Consumer id.
+        consumer_id: ConsumerId,
+    },
+    /// Producer of the consumer was resumed.
+    ConsumerResumed {
+        /// Consumer id.
+        consumer_id: ConsumerId,
+    },
+}
+
+/// Error that caused [`Room`] or [`Participant`] operation to fail.
+#[derive(Debug, Error)]
+pub enum RoomError {
+    /// Failed to create WebRTC transport.
+    #[error("Failed to create WebRTC transport: {0}")]
+    CreateTransport(RequestError),
+    /// Failed to produce.
+    #[error("Failed to produce: {0}")]
+    Produce(#[from] ProduceError),
+    /// Failed to consume.
+    #[error("Failed to consume: {0}")]
+    Consume(#[from] ConsumeError),
+    /// Producer not found among producers of other participants.
+    #[error("Producer with id \"{0}\" not found")]
+    ProducerNotFound(ProducerId),
+    /// Participant's RTP capabilities are not known yet.
+    #[error("RTP capabilities of participant are not set")]
+    NoRtpCapabilities,
+    /// Producer can't be consumed with participant's RTP capabilities.
+    #[error("Producer with id \"{0}\" can't be consumed with participant's RTP capabilities")]
+    CannotConsume(ProducerId),
+}
+
+#[derive(Default)]
+#[allow(clippy::type_complexity)]
+struct ParticipantHandlers {
+    event: Bag<Arc<dyn Fn(&ParticipantEvent) + Send + Sync>, ParticipantEvent>,
+}
+
+struct ParticipantInner {
+    id: ParticipantId,
+    producer_transport: WebRtcTransport,
+    consumer_transport: WebRtcTransport,
+    app_data: AppData,
+    handlers: ParticipantHandlers,
+}
+
+struct ParticipantState {
+    inner: Arc<ParticipantInner>,
+    rtp_capabilities: Option<RtpCapabilities>,
+    subscription_policy: SubscriptionPolicy,
+    /// Explicit subscriptions, see [`SubscriptionPolicy::Selective`].
+    subscriptions: HashedSet<ProducerId>,
+    /// Producers in the order of creation.
+    producers: Vec<Producer>,
+    /// Consumers by producer id.
+    consumers: HashedMap<ProducerId, Consumer>,
+    /// Producers that consumers are being created for.
+    pending: HashedSet<ProducerId>,
+}
+
+#[derive(Default)]
+struct State {
+    participants: HashedMap<ParticipantId, ParticipantState>,
+    /// Participant ids ordered from the most recent active speaker, recently joined participants
+    /// are last.
+    speakers: Vec<ParticipantId>,
+}
+
+impl State {
+    /// Producers that participant is subscribed to according to its subscription policy, along
+    /// with participants that own them.
+    fn subscribed_producers(
+        &self,
+        subscriber_id: &ParticipantId,
+    ) -> HashedMap<ProducerId, ParticipantId> {
+        let Some(subscriber) = self.participants.get(subscriber_id) else {
+            return HashedMap::default();
+        };
+
+        self.speakers
+            .iter()
+            .filter(|&participant_id| participant_id != subscriber_id)
+            .filter_map(|participant_id| self.participants.get(participant_id))
+            .enumerate()
+            .flat_map(|(rank, owner)| {
+                owner
+                    .producers
+                    .iter()
+                    .filter(move |producer| {
+                        subscriber.subscription_policy.includes(
+                            producer.kind(),
+                            rank,
+                            subscriber.subscriptions.contains(&producer.id()),
+                        )
+                    })
+                    .map(|producer| (producer.id(), owner.inner.id))
+            })
+            .collect()
+    }
+
+    fn owner_of(&self, producer_id: &ProducerId) -> Option<ParticipantId> {
+        self.participants
+            .iter()
+            .find_map(|(participant_id, participant)| {
+                participant
+                    .producers
+                    .iter()
+                    .any(|producer| producer.id() == *producer_id)
+                    .then_some(*participant_id)
+            })
+    }
+}
+
+/// Changes accumulated while the state is locked, applied after unlocking since dropping entities
+/// and calling event handlers may re-enter the room.
+#[derive(Default)]
+struct Changes {
+    events: Vec<(Arc<ParticipantInner>, ParticipantEvent)>,
+    closed_producers: Vec<Producer>,
+    closed_consumers: Vec<Consumer>,
+}
+
+impl Changes {
+    fn notify_others(
+        &mut self,
+        state: &State,
+        participant_id: &ParticipantId,
+        event: &ParticipantEvent,
+    ) {
+        for (other_id, other) in &state.participants {
+            if other_id != participant_id {
+                self.events.push((Arc::clone(&other.inner), event.clone()));
+            }
+        }
+    }
+
+    /// Remove consumers of producers participants are no longer subscribed to.
+    fn remove_unsubscribed_consumers(&mut self, state: &mut State) {
+        for subscriber_id in state.speakers.clone() {
+            let subscribed = state.subscribed_producers(&subscriber_id);
+            let Some(subscriber) = state.participants.get_mut(&subscriber_id) else {
+                continue;
+            };
+            let unsubscribed = subscriber
+                .consumers
+                .keys()
+                .filter(|producer_id| !subscribed.contains_key(producer_id))
+                .copied()
+                .collect::<Vec<_>>();
+            for producer_id in unsubscribed {
+                if let Some(consumer) = subscriber.consumers.remove(&producer_id) {
+                    self.events.push((
+                        Arc::clone(&subscriber.inner),
+                        ParticipantEvent::ConsumerRemoved {
+                            consumer_id: consumer.id(),
+                        },
+                    ));
+                    self.closed_consumers.push(consumer);
+                }
+            }
+        }
+    }
+}
+
+/// Consumer to be created.
+struct ConsumerJob {
+    subscriber_id: ParticipantId,
+    owner_id: ParticipantId,
+    producer_id: ProducerId,
+    transport: WebRtcTransport,
+    rtp_capabilities: RtpCapabilities,
+}
+
+#[derive(Default)]
+#[allow(clippy::type_complexity)]
+struct Handlers {
+    participant_event: Bag<Arc<dyn Fn(&ParticipantId, &ParticipantEvent) + Send + Sync>>,
+}
+
+struct Inner {
+    router: Router,
+    options: RoomOptions,
+    state: Mutex<State>,
+    handlers: Handlers,
+}
+
+impl Inner {
+    fn apply(&self, changes: Changes) {
+        let Changes {
+            events,
+            closed_producers,
+            closed_consumers,
+        } = changes;
+
+        drop(closed_consumers);
+        drop(closed_producers);
+
+        for (participant, event) in events {
+            self.handlers
+                .participant_event
+                .call(|callback| callback(&participant.id, &event));
+            participant.handlers.event.call_simple(&event);
+        }
+    }
+
+    /// Find producers participants are subscribed to and don't consume yet, marking them as
+    /// pending.
+    fn take_consumer_jobs(&self) -> Vec<ConsumerJob> {
+        let mut state = self.state.lock();
+        let mut jobs = Vec::new();
+        for subscriber_id in state.speakers.clone() {
+            let subscribed = state.subscribed_producers(&subscriber_id);
+            let Some(subscriber) = state.participants.get_mut(&subscriber_id) else {
+                continue;
+            };
+            let Some(rtp_capabilities) = &subscriber.rtp_capabilities else {
+                continue;
+            };
+            for (producer_id, owner_id) in subscribed {
+                if subscriber.consumers.contains_key(&producer_id)
+                    || subscriber.pending.contains(&producer_id)
+                {
+                    continue;
+                }
+                if !self.router.can_consume(&producer_id, rtp_capabilities) {
+                    debug!(
+                        "participant {} can't consume producer {}",
+                        subscriber_id, producer_id
+                    );
+                    continue;
+                }
+                subscriber.pending.insert(producer_id);
+                jobs.push(ConsumerJob {
+                    subscriber_id,
+                    owner_id,
+                    producer_id,
+                    transport: subscriber.inner.consumer_transport.clone(),
+                    rtp_capabilities: rtp_capabilities.clone(),
+                });
+            }
+        }
+
+        jobs
+    }
+
+    async fn create_consumer(self: &Arc<Self>, job: ConsumerJob) -> Result<(), ConsumeError> {
+        let mut consumer_options = ConsumerOptions::new(job.producer_id, job.rtp_capabilities);
+        consumer_options.paused = self.options.consumers_paused;
+        let result = job.transport.consume(consumer_options).await;
+
+        let mut changes = Changes::default();
+        {
+            let mut state = self.state.lock();
+            let subscribed = state
+                .subscribed_producers(&job.subscriber_id)
+                .contains_key(&job.producer_id);
+            let mut subscriber = state.participants.get_mut(&job.subscriber_id);
+            if let Some(subscriber) = &mut subscriber {
+                subscriber.pending.remove(&job.producer_id);
+            }
+            match (result?, subscriber) {
+                (consumer, Some(subscriber)) if subscribed => {
+                    self.watch_consumer(job.subscriber_id, job.producer_id, &consumer);
+                    subscriber
+                        .consumers
+                        .insert(job.producer_id, consumer.clone());
+                    changes.events.push((
+                        Arc::clone(&subscriber.inner),
+                        ParticipantEvent::ConsumerAdded {
+                            participant_id: job.owner_id,
+                            consumer,
+                        },
+                    ));
+                }
+                (consumer, _) => {
+                    // Participant left or unsubscribed in the meantime
+                    changes.closed_consumers.push(consumer);
+                }
+            }
+        }
+        self.apply(changes);
+
+        Ok(())
+    }
+
+    async fn run_consumer_jobs(self: &Arc<Self>, jobs: Vec<ConsumerJob>) {
+        for job in jobs {
+            let (subscriber_id, producer_id) = (job.subscriber_id, job.producer_id);
+            if let Err(error) = self.create_consumer(job).await {
+                warn!(
+                    "failed to consume producer {} for participant {}: {}",
+                    producer_id, subscriber_id, error
+                );
+            }
+        }
+    }
+
+    /// Create consumers of producers participants are subscribed to and don't consume yet.
+    async fn create_consumers(self: &Arc<Self>) {
+        let jobs = self.take_consumer_jobs();
+        self.run_consumer_jobs(jobs).await;
+    }
+
+    /// Create consumers in the background after synchronous changes (for instance when
+    /// participant leaves and last-N subscriptions of others change).
+    fn spawn_create_consumers(self: &Arc<Self>) {
+        let inner_weak = Arc::downgrade(self);
+        self.router.executor().spawn(Box::pin(async move {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.create_consumers().await;
+            }
+        }));
+    }
+
+    fn watch_consumer(
+        self: &Arc<Self>,
+        subscriber_id: ParticipantId,
+        producer_id: ProducerId,
+        consumer: &Consumer,
+    ) {
+        let consumer_id = consumer.id();
+        for (paused, event) in [
+            (true, ParticipantEvent::ConsumerPaused { consumer_id }),
+            (false, ParticipantEvent::ConsumerResumed { consumer_id }),
+        ] {
+            let inner_weak = Arc::downgrade(self);
+            let callback = move || {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.notify(&subscriber_id, event.clone());
+                }
+            };
+            if paused {
+                consumer.on_producer_pause(callback).detach();
+            } else {
+                consumer.on_producer_resume(callback).detach();
+            }
+        }
+
+        let inner_weak = Arc::downgrade(self);
+        consumer
+            .on_producer_close(move || {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.remove_consumer(&subscriber_id, &producer_id, consumer_id);
+                }
+            })
+            .detach();
+    }
+
+    fn watch_producer(self: &Arc<Self>, producer: &Producer) {
+        let producer_id = producer.id();
+        let inner_weak = Arc::downgrade(self);
+        producer
+            .on_close(move || {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.remove_producer(&producer_id);
+                }
+            })
+            .detach();
+    }
+
+    fn notify(&self, participant_id: &ParticipantId, event: ParticipantEvent) {
+        let participant = self
+            .state
+            .lock()
+            .participants
+            .get(participant_id)
+            .map(|participant| Arc::clone(&participant.inner));
+        if let Some(participant) = participant {
+            self.apply(Changes {
+                events: vec![(participant, event)],
+                ..Changes::default()
+            });
+        }
+    }
+
+    fn remove_consumer(
+        &self,
+        subscriber_id: &ParticipantId,
+        producer_id: &ProducerId,
+        consumer_id: ConsumerId,
+    ) {
+        let mut changes = Changes::default();
+        {
+            let mut state = self.state.lock();
+            let Some(subscriber) = state.participants.get_mut(subscriber_id) else {
+                return;
+            };
+            if subscriber
+                .consumers
+                .get(producer_id)
+                .map_or(true, |consumer| consumer.id() != consumer_id)
+            {
+                return;
+            }
+            if let Some(consumer) = subscriber.consumers.remove(producer_id) {
+                changes.events.push((
+                    Arc::clone(&subscriber.inner),
+                    ParticipantEvent::ConsumerRemoved { consumer_id },
+                ));
+                changes.closed_consumers.push(consumer);
+            }
+        }
+        self.apply(changes);
+    }
+
+    /// Returns `false` if there is no such producer.
+    fn remove_producer(self: &Arc<Self>, producer_id: &ProducerId) -> bool {
+        let mut changes = Changes::default();
+        {
+            let mut state = self.state.lock();
+            let Some(owner_id) = state.owner_of(producer_id) else {
+                return false;
+            };
+            if let Some(owner) = state.participants.get_mut(&owner_id) {
+                owner.producers.retain(|producer| {
+                    if producer.id() == *producer_id {
+                        changes.closed_producers.push(producer.clone());
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+            for participant in state.participants.values_mut() {
+                participant.subscriptions.remove(producer_id);
+            }
+            changes.notify_others(
+                &state,
+                &owner_id,
+                &ParticipantEvent::ProducerRemoved {
+                    participant_id: owner_id,
+                    producer_id: *producer_id,
+                },
+            );
+            changes.remove_unsubscribed_consumers(&mut state);
+        }
+        self.apply(changes);
+
+        true
+    }
+
+    fn leave(self: &Arc<Self>, participant_id: &ParticipantId) {
+        debug!("leave() [participant_id:{}]", participant_id);
+
+        let mut changes = Changes::default();
+        {
+            let mut state = self.state.lock();
+            let Some(participant) = state.participants.remove(participant_id) else {
+                return;
+            };
+            state.speakers.retain(|id| id != participant_id);
+
+            for producer in &participant.producers {
+                for other in state.participants.values_mut() {
+                    other.subscriptions.remove(&producer.id());
+                }
+                changes.notify_others(
+                    &state,
+                    participant_id,
+                    &ParticipantEvent::ProducerRemoved {
+                        participant_id: *participant_id,
+                        producer_id: producer.id(),
+                    },
+                );
+            }
+            changes.notify_others(
+                &state,
+                participant_id,
+                &ParticipantEvent::ParticipantLeft {
+                    participant_id: *participant_id,
+                },
+            );
+            changes.closed_producers.extend(participant.producers);
+            changes
+                .closed_consumers
+                .extend(participant.consumers.into_values());
+            changes.remove_unsubscribed_consumers(&mut state);
+        }
+        self.apply(changes);
+
+        // Others may be subscribed to more participants now (last-N)
+        self.spawn_create_consumers();
+    }
+}
+
+/// Room of participants that consume each other's producers, see
+/// [module-level documentation](self).
+#[derive(Clone)]
+#[must_use]
+pub struct Room {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for Room {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Room")
+            .field("router", &self.inner.router)
+            .field("options", &self.inner.options)
+            .field("participants", &self.participant_ids())
+            .finish()
+    }
+}
+
+impl Room {
+    /// Create a room on top of given router.
+    pub fn new(router: Router, options: RoomOptions) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                router,
+                options,
+                state: Mutex::default(),
+                handlers: Handlers::default(),
+            }),
+        }
+    }
+
+    /// Router the room is using.
+    pub fn router(&self) -> &Router {
+        &self.inner.router
+    }
+
+    /// Ids of participants in the room, ordered from the most recent active speaker.
+    #[must_use]
+    pub fn participant_ids(&self) -> Vec<ParticipantId> {
+        self.inner.state.lock().speakers.clone()
+    }
+
+    /// Producers of all participants along with participants that own them, useful to tell newly
+    /// joined participant what is available.
+    #[must_use]
+    pub fn producers(&self) -> Vec<(ParticipantId, Producer)> {
+        let state = self.inner.state.lock();
+        state
+            .speakers
+            .iter()
+            .filter_map(|participant_id| state.participants.get(participant_id))
+            .flat_map(|participant| {
+                participant
+                    .producers
+                    .iter()
+                    .map(|producer| (participant.inner.id, producer.clone()))
+            })
+            .collect()
+    }
+
+    /// Join the room, creating transports for the new participant.
+    ///
+    /// Existing producers are consumed right away if RTP capabilities are specified in options, in
+    /// which case resulting [`ParticipantEvent::ConsumerAdded`] events are only delivered to
+    /// [`Room::on_participant_event()`] callbacks.
+    pub async fn join(&self, options: ParticipantOptions) -> Result<Participant, RoomError> {
+        let participant_id = ParticipantId::new();
+
+        debug!("join() [participant_id:{}]", participant_id);
+
+        let ParticipantOptions {
+            webrtc_transport_options,
+            rtp_capabilities,
+            subscription_policy,
+            app_data,
+        } = options;
+
+        let producer_transport = self
+            .inner
+            .router
+            .create_webrtc_transport(webrtc_transport_options.clone())
+            .await
+            .map_err(RoomError::CreateTransport)?;
+        let consumer_transport = self
+            .inner
+            .router
+            .create_webrtc_transport(webrtc_transport_options)
+            .await
+            .map_err(RoomError::CreateTransport)?;
+
+        let participant_inner = Arc::new(ParticipantInner {
+            id: participant_id,
+            producer_transport,
+            consumer_transport,
+            app_data,
+            handlers: ParticipantHandlers::default(),
+        });
+
+        let mut changes = Changes::default();
+        {
+            let mut state = self.inner.state.lock();
+            state.participants.insert(
+                participant_id,
+                ParticipantState {
+                    inner: Arc::clone(&participant_inner),
+                    rtp_capabilities,
+                    subscription_policy,
+                    subscriptions: HashedSet::default(),
+                    producers: Vec::new(),
+                    consumers: HashedMap::default(),
+                    pending: HashedSet::default(),
+                },
+            );
+            state.speakers.push(participant_id);
+            changes.notify_others(
+                &state,
+                &participant_id,
+                &ParticipantEvent::ParticipantJoined { participant_id },
+            );
+        }
+        self.inner.apply(changes);
+
+        let participant = Participant {
+            inner: participant_inner,
+            room: self.clone(),
+        };
+
+        self.inner.create_consumers().await;
+
+        Ok(participant)
+    }
+
+    /// Move participant to the front of active speakers, which changes video subscriptions of
+    /// participants with [`SubscriptionPolicy::LastN`].
+    ///
+    /// Typically called from [`ActiveSpeakerObserver::on_dominant_speaker()`](crate::active_speaker_observer::ActiveSpeakerObserver::on_dominant_speaker)
+    /// callback.
+    pub async fn set_active_speaker(&self, participant_id: &ParticipantId) {
+        let mut changes = Changes::default();
+        {
+            let mut state = self.inner.state.lock();
+            if !promote(&mut state.speakers, participant_id) {
+                return;
+            }
+            changes.remove_unsubscribed_consumers(&mut state);
+        }
+        self.inner.apply(changes);
+
+        self.inner.create_consumers().await;
+    }
+
+    /// Callback is called for every event of every participant in the room.
+    pub fn on_participant_event<
+        F: Fn(&ParticipantId, &ParticipantEvent) + Send + Sync + 'static,
+    >(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner
+            .handlers
+            .participant_event
+            .add(Arc::new(callback))
+    }
+}
+
+/// Move participant to the front, returns `false` if it was already there or is not found.
+fn promote(speakers: &mut [ParticipantId], participant_id: &ParticipantId) -> bool {
+    match speakers.iter().position(|id| id == participant_id) {
+        Some(0) | None => false,
+        Some(position) => {
+            speakers[..=position].rotate_right(1);
+            true
+        }
+    }
+}
+
+/// Participant of a [`Room`], leaves the room once dropped.
+///
+/// Leaving closes participant's transports, producers and consumers, and consumers of its
+/// producers owned by other participants.
+#[must_use = "Participant leaves the room when dropped"]
+pub struct Participant {
+    inner: Arc<ParticipantInner>,
+    room: Room,
+}
+
+impl fmt::Debug for Participant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Participant")
+            .field("id", &self.inner.id)
+            .field("producer_transport", &self.inner.producer_transport)
+            .field("consumer_transport", &self.inner.consumer_transport)
+            .finish()
+    }
+}
+
+impl Drop for Participant {
+    fn drop(&mut self) {
+        self.room.inner.leave(&self.inner.id);
+    }
+}
+
+impl Participant {
+    /// Participant id.
+    #[must_use]
+    pub fn id(&self) -> ParticipantId {
+        self.inner.id
+    }
+
+    /// Room the participant is in.
+    pub fn room(&self) -> &Room {
+        &self.room
+    }
+
+    /// Transport that participant's producers are created on.
+    pub fn producer_transport(&self) -> &WebRtcTransport {
+        &self.inner.producer_transport
+    }
+
+    /// Transport that consumers of other participants' producers are created on.
+    pub fn consumer_transport(&self) -> &WebRtcTransport {
+        &self.inner.consumer_transport
+    }
+
+    /// Custom application data.
+    #[must_use]
+    pub fn app_data(&self) -> &AppData {
+        &self.inner.app_data
+    }
+
+    /// Producers of the participant.
+    #[must_use]
+    pub fn producers(&self) -> Vec<Producer> {
+        self.with_state(|participant| participant.producers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Consumers of other participants' producers.
+    #[must_use]
+    pub fn consumers(&self) -> Vec<Consumer> {
+        self.with_state(|participant| participant.consumers.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Create a producer on producer transport, other participants will consume it according to
+    /// their subscription policies.
+    pub async fn produce(&self, producer_options: ProducerOptions) -> Result<Producer, RoomError> {
+        debug!("produce() [participant_id:{}]", self.inner.id);
+
+        let producer = self
+            .inner
+            .producer_transport
+            .produce(producer_options)
+            .await?;
+        self.room.inner.watch_producer(&producer);
+
+        let mut changes = Changes::default();
+        {
+            let mut state = self.room.inner.state.lock();
+            if let Some(participant) = state.participants.get_mut(&self.inner.id) {
+                participant.producers.push(producer.clone());
+            }
+            changes.notify_others(
+                &state,
+                &self.inner.id,
+                &ParticipantEvent::ProducerAdded {
+                    participant_id: self.inner.id,
+                    producer_id: producer.id(),
+                    kind: producer.kind(),
+                },
+            );
+        }
+        self.room.inner.apply(changes);
+
+        self.room.inner.create_consumers().await;
+
+        Ok(producer)
+    }
+
+    /// Remove producer from the room, closing consumers of other participants, returns `false` if
+    /// participant has no such producer.
+    pub fn close_producer(&self, producer_id: &ProducerId) -> bool {
+        let owned = self
+            .with_state(|participant| {
+                participant
+                    .producers
+                    .iter()
+                    .any(|producer| producer.id() == *producer_id)
+            })
+            .unwrap_or_default();
+
+        owned && self.room.inner.remove_producer(producer_id)
+    }
+
+    /// Set RTP capabilities of participant's device and consume producers it is subscribed to.
+    pub async fn set_rtp_capabilities(&self, rtp_capabilities: RtpCapabilities) {
+        self.with_state(|participant| {
+            participant.rtp_capabilities.replace(rtp_capabilities);
+        });
+
+        self.room.inner.create_consumers().await;
+    }
+
+    /// Change subscription policy, creating and closing consumers accordingly.
+    pub async fn set_subscription_policy(&self, subscription_policy: SubscriptionPolicy) {
+        let mut changes = Changes::default();
+        {
+            let mut state = self.room.inner.state.lock();
+            if let Some(participant) = state.participants.get_mut(&self.inner.id) {
+                participant.subscription_policy = subscription_policy;
+            }
+            changes.remove_unsubscribed_consumers(&mut state);
+        }
+        self.room.inner.apply(changes);
+
+        self.room.inner.create_consumers().await;
+    }
+
+    /// Subscribe to producer of another participant, which is consumed if subscription policy is
+    /// [`SubscriptionPolicy::Selective`].
+    pub async fn subscribe(&self, producer_id: ProducerId) -> Result<(), RoomError> {
+        debug!("subscribe() [producer_id:{}]", producer_id);
+
+        {
+            let mut state = self.room.inner.state.lock();
+            if state
+                .owner_of(&producer_id)
+                .map_or(true, |owner_id| owner_id == self.inner.id)
+            {
+                return Err(RoomError::ProducerNotFound(producer_id));
+            }
+            let Some(participant) = state.participants.get_mut(&self.inner.id) else {
+                return Err(RoomError::ProducerNotFound(producer_id));
+            };
+            let rtp_capabilities = participant
+                .rtp_capabilities
+                .as_ref()
+                .ok_or(RoomError::NoRtpCapabilities)?;
+            if !self
+                .room
+                .inner
+                .router
+                .can_consume(&producer_id, rtp_capabilities)
+            {
+                return Err(RoomError::CannotConsume(producer_id));
+            }
+            participant.subscriptions.insert(producer_id);
+        }
+
+        // Own consumer is created last, so that its error can be returned
+        let (jobs, other_jobs) = self
+            .room
+            .inner
+            .take_consumer_jobs()
+            .into_iter()
+            .partition::<Vec<_>, _>(|job| {
+                job.subscriber_id == self.inner.id && job.producer_id == producer_id
+            });
+        self.room.inner.run_consumer_jobs(other_jobs).await;
+        for job in jobs {
+            self.room.inner.create_consumer(job).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Unsubscribe from producer of another participant, closing its consumer if subscription
+    /// policy is [`SubscriptionPolicy::Selective`].
+    pub fn unsubscribe(&self, producer_id: &ProducerId) {
+        debug!("unsubscribe() [producer_id:{}]", producer_id);
+
+        let mut changes = Changes::default();
+        {
+            let mut state = self.room.inner.state.lock();
+            if let Some(participant) = state.participants.get_mut(&self.inner.id) {
+                participant.subscriptions.remove(producer_id);
+            }
+            changes.remove_unsubscribed_consumers(&mut state);
+        }
+        self.room.inner.apply(changes);
+    }
+
+    /// Callback is called for every event of this participant.
+    pub fn on_event<F: Fn(&ParticipantEvent) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner.handlers.event.add(Arc::new(callback))
+    }
+
+    fn with_state<T, F: FnOnce(&mut ParticipantState) -> T>(&self, f: F) -> Option<T> {
+        self.room
+            .inner
+            .state
+            .lock()
+            .participants
+            .get_mut(&self.inner.id)
+            .map(f)
+    }
+}
@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn subscription_policy_includes() {
+    assert!(SubscriptionPolicy::All.includes(MediaKind::Video, 10, false));
+
+    assert!(SubscriptionPolicy::Selective.includes(MediaKind::Video, 0, true));
+    assert!(!SubscriptionPolicy::Selective.includes(MediaKind::Audio, 0, false));
+
+    let last_n = SubscriptionPolicy::LastN(2);
+    assert!(last_n.includes(MediaKind::Video, 1, false));
+    assert!(!last_n.includes(MediaKind::Video, 2, true));
+    // Audio of everyone is consumed
+    assert!(last_n.includes(MediaKind::Audio, 2, false));
+}
+
+#[test]
+fn promote_speaker() {
+    let ids = [
+        ParticipantId::new(),
+        ParticipantId::new(),
+        ParticipantId::new(),
+    ];
+    let mut speakers = ids.to_vec();
+
+    assert!(promote(&mut speakers, &ids[2]));
+    assert_eq!(speakers, vec![ids[2], ids[0], ids[1]]);
+
+    assert!(!promote(&mut speakers, &ids[2]));
+    assert!(!promote(&mut speakers, &ParticipantId::new()));
+    assert_eq!(speakers, vec![ids[2], ids[0], ids[1]]);
+
+    assert!(promote(&mut speakers, &ids[1]));
+    assert_eq!(speakers, vec![ids[1], ids[2], ids[0]]);
+}
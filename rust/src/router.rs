@@ -15,7 +15,9 @@ pub(super) mod direct_transport;
 pub(super) mod pipe_transport;
 pub(super) mod plain_transport;
 pub(super) mod producer;
+pub(super) mod remote_pipe;
 pub(super) mod rtp_observer;
+pub(super) mod snapshot;
 #[cfg(test)]
 mod tests;
 pub(super) mod transport;
@@ -30,6 +32,9 @@ use crate::data_producer::{
 };
 use crate::data_structures::{AppData, ListenInfo, Protocol};
 use crate::direct_transport::{DirectTransport, DirectTransportOptions};
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     RouterCloseRequest, RouterCreateActiveSpeakerObserverData,
     RouterCreateActiveSpeakerObserverRequest, RouterCreateAudioLevelObserverData,
@@ -40,22 +45,29 @@ use crate::messages::{
     RouterCreateWebRtcTransportWithServerRequest, RouterCreateWebrtcTransportData,
     RouterDumpRequest,
 };
+use crate::negotiation::{ConsumeIncompatibility, RtpCapabilitiesNegotiation};
+use crate::ortc::RtpCapabilitiesError;
 use crate::pipe_transport::{
     PipeTransport, PipeTransportOptions, PipeTransportRemoteParameters, WeakPipeTransport,
 };
 use crate::plain_transport::{PlainTransport, PlainTransportOptions};
 use crate::producer::{PipedProducer, Producer, ProducerId, ProducerOptions, WeakProducer};
+use crate::remote_pipe::{
+    PendingRemotePipe, RemotePipeAnswer, RemotePipeMessage, RemotePipeOffer, RemotePipeOptions,
+    RemotePipeProducer,
+};
+use crate::router::snapshot::SnapshotRegistry;
 use crate::rtp_observer::{RtpObserver, RtpObserverId};
 use crate::rtp_parameters::{RtpCapabilities, RtpCapabilitiesFinalized, RtpCodecCapability};
 use crate::sctp_parameters::NumSctpStreams;
+use crate::snapshot::{RouterSnapshot, RtpObserverSnapshotOptions, TransportSnapshotOptions};
 use crate::transport::{
     ConsumeDataError, ConsumeError, ProduceDataError, ProduceError, Transport, TransportGeneric,
     TransportId,
 };
 use crate::webrtc_transport::{WebRtcTransport, WebRtcTransportListen, WebRtcTransportOptions};
 use crate::worker::{Channel, RequestError, Worker};
-use crate::{ortc, uuid_based_wrapper_type};
-use async_executor::Executor;
+use crate::{negotiation, ortc, uuid_based_wrapper_type};
 use async_lock::Mutex as AsyncMutex;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use futures_lite::future;
@@ -82,7 +94,7 @@ uuid_based_wrapper_type!(
 /// * Feature codecs such as `RTX` MUST NOT be placed into the mediaCodecs list.
 /// * If `preferred_payload_type` is given in a [`RtpCodecCapability`] (although it's unnecessary)
 ///   it's extremely recommended to use a value in the 96-127 range.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct RouterOptions {
     /// Router media codecs.
@@ -311,6 +323,26 @@ impl<'a> Deref for NewTransport<'a> {
     }
 }
 
+/// Event of [`Router::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum RouterEvent {
+    /// New transport was created on this router.
+    NewTransport {
+        /// Transport id.
+        transport_id: TransportId,
+    },
+    /// New RTP observer was created on this router.
+    NewRtpObserver {
+        /// RTP observer id.
+        rtp_observer_id: RtpObserverId,
+    },
+    /// Worker this router belongs to was closed.
+    WorkerClose,
+    /// Router was closed, this is the last event in the stream.
+    Close,
+}
+
 /// New RTP observer that was just created.
 #[derive(Debug)]
 pub enum NewRtpObserver<'a> {
@@ -370,8 +402,9 @@ struct Handlers {
 
 struct Inner {
     id: RouterId,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     rtp_capabilities: RtpCapabilitiesFinalized,
+    media_codecs: Vec<RtpCodecCapability>,
     channel: Channel,
     handlers: Arc<Handlers>,
     app_data: AppData,
@@ -380,8 +413,11 @@ struct Inner {
     #[allow(clippy::type_complexity)]
     mapped_pipe_transports:
         Arc<Mutex<HashedMap<RouterId, Arc<AsyncMutex<Option<WeakPipeTransportPair>>>>>>,
+    snapshot_registry: SnapshotRegistry,
     // Make sure worker is not dropped until this router is not dropped
     worker: Worker,
+    #[cfg(feature = "tracing")]
+    span: crate::worker::EntitySpan,
     closed: AtomicBool,
     _on_worker_close_handler: Mutex<HandlerId>,
 }
@@ -402,13 +438,17 @@ impl Inner {
             {
                 let channel = self.channel.clone();
                 let request = RouterCloseRequest { router_id: self.id };
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request("", request).await {
-                            error!("router closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::router",
+                        "close_router",
+                        router_id = %request.router_id,
+                    );
+                    if let Err(error) = instrument!(channel.request("", request), span).await {
+                        error!("router closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -444,9 +484,10 @@ impl fmt::Debug for Router {
 impl Router {
     pub(super) fn new(
         id: RouterId,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         rtp_capabilities: RtpCapabilitiesFinalized,
+        media_codecs: Vec<RtpCodecCapability>,
         app_data: AppData,
         worker: Worker,
     ) -> Self {
@@ -472,17 +513,31 @@ impl Router {
                 }
             }
         });
+        #[cfg(feature = "tracing")]
+        let span = worker.register_entity_span(
+            id,
+            tracing::info_span!(
+                target: "mediasoup::router",
+                parent: worker.span(),
+                "router",
+                %id,
+            ),
+        );
         let inner = Arc::new(Inner {
             id,
             executor,
             rtp_capabilities,
+            media_codecs,
             channel,
             handlers,
             producers,
             data_producers,
             mapped_pipe_transports,
+            snapshot_registry: SnapshotRegistry::default(),
             app_data,
             worker,
+            #[cfg(feature = "tracing")]
+            span,
             closed: AtomicBool::new(false),
             _on_worker_close_handler: Mutex::new(on_worker_close_handler),
         });
@@ -528,6 +583,35 @@ impl Router {
         &self.inner.rtp_capabilities
     }
 
+    pub(crate) fn executor(&self) -> &Arc<dyn Executor> {
+        &self.inner.executor
+    }
+
+    fn snapshot_registry(&self) -> &SnapshotRegistry {
+        &self.inner.snapshot_registry
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> &tracing::Span {
+        self.inner.span.span()
+    }
+
+    /// Takes a snapshot of the router topology: media codecs, transports with options they were
+    /// created with, producers, consumers, data producers, data consumers and RTP observers that are
+    /// not closed yet.
+    ///
+    /// Snapshot is assembled from state tracked on this side without requests to the worker, so it
+    /// doesn't include custom application data and state negotiated with remote endpoints (ICE, DTLS,
+    /// remote addresses and SRTP keys given to `connect()`, etc.). It can be serialized, stored and
+    /// later used with [`Worker::restore_router()`] to re-create the same topology, for instance
+    /// after the worker died.
+    #[must_use]
+    pub fn snapshot(&self) -> RouterSnapshot {
+        self.inner
+            .snapshot_registry
+            .snapshot(self.inner.id, &self.inner.media_codecs)
+    }
+
     /// Dump Router.
     #[doc(hidden)]
     pub async fn dump(&self) -> Result<RouterDump, RequestError> {
@@ -562,9 +646,15 @@ impl Router {
 
         let _buffer_guard = self.inner.channel.buffer_messages_for(transport_id.into());
 
-        self.inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::transport",
+            "create_transport",
+            %transport_id,
+            kind = "direct",
+        );
+        instrument!(
+            self.inner.channel.request(
                 self.inner.id,
                 RouterCreateDirectTransportRequest {
                     data: RouterCreateDirectTransportData::from_options(
@@ -572,8 +662,12 @@ impl Router {
                         &direct_transport_options,
                     ),
                 },
-            )
-            .await?;
+            ),
+            span,
+        )
+        .await?;
+
+        let snapshot_options = TransportSnapshotOptions::direct(&direct_transport_options);
 
         let transport = DirectTransport::new(
             transport_id,
@@ -587,6 +681,10 @@ impl Router {
             callback(NewTransport::Direct(&transport));
         });
 
+        self.inner
+            .snapshot_registry
+            .add_transport(NewTransport::Direct(&transport), snapshot_options);
+
         self.after_transport_creation(&transport);
 
         Ok(transport)
@@ -629,9 +727,15 @@ impl Router {
 
         let data = match webrtc_transport_options.listen {
             WebRtcTransportListen::Individual { listen_infos: _ } => {
-                self.inner
-                    .channel
-                    .request(
+                #[cfg(feature = "tracing")]
+                let span = tracing::info_span!(
+                    target: "mediasoup::transport",
+                    "create_transport",
+                    %transport_id,
+                    kind = "webrtc",
+                );
+                instrument!(
+                    self.inner.channel.request(
                         self.inner.id,
                         RouterCreateWebRtcTransportRequest {
                             data: RouterCreateWebrtcTransportData::from_options(
@@ -639,13 +743,21 @@ impl Router {
                                 &webrtc_transport_options,
                             ),
                         },
-                    )
-                    .await?
+                    ),
+                    span,
+                )
+                .await?
             }
             WebRtcTransportListen::Server { webrtc_server: _ } => {
-                self.inner
-                    .channel
-                    .request(
+                #[cfg(feature = "tracing")]
+                let span = tracing::info_span!(
+                    target: "mediasoup::transport",
+                    "create_transport",
+                    %transport_id,
+                    kind = "webrtc",
+                );
+                instrument!(
+                    self.inner.channel.request(
                         self.inner.id,
                         RouterCreateWebRtcTransportWithServerRequest {
                             data: RouterCreateWebrtcTransportData::from_options(
@@ -653,11 +765,15 @@ impl Router {
                                 &webrtc_transport_options,
                             ),
                         },
-                    )
-                    .await?
+                    ),
+                    span,
+                )
+                .await?
             }
         };
 
+        let snapshot_options = TransportSnapshotOptions::webrtc(&webrtc_transport_options);
+
         let transport = WebRtcTransport::new(
             transport_id,
             Arc::clone(&self.inner.executor),
@@ -675,6 +791,10 @@ impl Router {
             callback(NewTransport::WebRtc(&transport));
         });
 
+        self.inner
+            .snapshot_registry
+            .add_transport(NewTransport::WebRtc(&transport), snapshot_options);
+
         self.after_transport_creation(&transport);
 
         Ok(transport)
@@ -713,10 +833,15 @@ impl Router {
 
         let _buffer_guard = self.inner.channel.buffer_messages_for(transport_id.into());
 
-        let data = self
-            .inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::transport",
+            "create_transport",
+            %transport_id,
+            kind = "pipe",
+        );
+        let data = instrument!(
+            self.inner.channel.request(
                 self.inner.id,
                 RouterCreatePipeTransportRequest {
                     data: RouterCreatePipeTransportData::from_options(
@@ -724,8 +849,12 @@ impl Router {
                         &pipe_transport_options,
                     ),
                 },
-            )
-            .await?;
+            ),
+            span,
+        )
+        .await?;
+
+        let snapshot_options = TransportSnapshotOptions::pipe(&pipe_transport_options);
 
         let transport = PipeTransport::new(
             transport_id,
@@ -740,6 +869,10 @@ impl Router {
             callback(NewTransport::Pipe(&transport));
         });
 
+        self.inner
+            .snapshot_registry
+            .add_transport(NewTransport::Pipe(&transport), snapshot_options);
+
         self.after_transport_creation(&transport);
 
         Ok(transport)
@@ -778,10 +911,15 @@ impl Router {
 
         let _buffer_guard = self.inner.channel.buffer_messages_for(transport_id.into());
 
-        let data = self
-            .inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::transport",
+            "create_transport",
+            %transport_id,
+            kind = "plain",
+        );
+        let data = instrument!(
+            self.inner.channel.request(
                 self.inner.id,
                 RouterCreatePlainTransportRequest {
                     data: RouterCreatePlainTransportData::from_options(
@@ -789,8 +927,12 @@ impl Router {
                         &plain_transport_options,
                     ),
                 },
-            )
-            .await?;
+            ),
+            span,
+        )
+        .await?;
+
+        let snapshot_options = TransportSnapshotOptions::plain(&plain_transport_options);
 
         let transport = PlainTransport::new(
             transport_id,
@@ -805,6 +947,10 @@ impl Router {
             callback(NewTransport::Plain(&transport));
         });
 
+        self.inner
+            .snapshot_registry
+            .add_transport(NewTransport::Plain(&transport), snapshot_options);
+
         self.after_transport_creation(&transport);
 
         Ok(transport)
@@ -845,9 +991,15 @@ impl Router {
             .channel
             .buffer_messages_for(rtp_observer_id.into());
 
-        self.inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::rtp_observer",
+            "create_rtp_observer",
+            %rtp_observer_id,
+            kind = "audio_level",
+        );
+        instrument!(
+            self.inner.channel.request(
                 self.inner.id,
                 RouterCreateAudioLevelObserverRequest {
                     data: RouterCreateAudioLevelObserverData::from_options(
@@ -855,8 +1007,13 @@ impl Router {
                         &audio_level_observer_options,
                     ),
                 },
-            )
-            .await?;
+            ),
+            span,
+        )
+        .await?;
+
+        let snapshot_options =
+            RtpObserverSnapshotOptions::audio_level(&audio_level_observer_options);
 
         let audio_level_observer = AudioLevelObserver::new(
             rtp_observer_id,
@@ -870,6 +1027,11 @@ impl Router {
             callback(NewRtpObserver::AudioLevel(&audio_level_observer));
         });
 
+        self.inner.snapshot_registry.add_rtp_observer(
+            NewRtpObserver::AudioLevel(&audio_level_observer),
+            snapshot_options,
+        );
+
         Ok(audio_level_observer)
     }
 
@@ -905,9 +1067,15 @@ impl Router {
             .channel
             .buffer_messages_for(rtp_observer_id.into());
 
-        self.inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::rtp_observer",
+            "create_rtp_observer",
+            %rtp_observer_id,
+            kind = "active_speaker",
+        );
+        instrument!(
+            self.inner.channel.request(
                 self.inner.id,
                 RouterCreateActiveSpeakerObserverRequest {
                     data: RouterCreateActiveSpeakerObserverData::from_options(
@@ -915,8 +1083,13 @@ impl Router {
                         &active_speaker_observer_options,
                     ),
                 },
-            )
-            .await?;
+            ),
+            span,
+        )
+        .await?;
+
+        let snapshot_options =
+            RtpObserverSnapshotOptions::active_speaker(&active_speaker_observer_options);
 
         let active_speaker_observer = ActiveSpeakerObserver::new(
             rtp_observer_id,
@@ -930,6 +1103,11 @@ impl Router {
             callback(NewRtpObserver::ActiveSpeaker(&active_speaker_observer));
         });
 
+        self.inner.snapshot_registry.add_rtp_observer(
+            NewRtpObserver::ActiveSpeaker(&active_speaker_observer),
+            snapshot_options,
+        );
+
         Ok(active_speaker_observer)
     }
 
@@ -1110,11 +1288,9 @@ impl Router {
 
                 move || {
                     if let Some(pipe_producer) = pipe_producer_weak.upgrade() {
-                        executor
-                            .spawn(async move {
-                                let _ = pipe_producer.pause().await;
-                            })
-                            .detach();
+                        executor.spawn(Box::pin(async move {
+                            let _ = pipe_producer.pause().await;
+                        }));
                     }
                 }
             })
@@ -1126,11 +1302,9 @@ impl Router {
 
                 move || {
                     if let Some(pipe_producer) = pipe_producer_weak.upgrade() {
-                        executor
-                            .spawn(async move {
-                                let _ = pipe_producer.resume().await;
-                            })
-                            .detach();
+                        executor.spawn(Box::pin(async move {
+                            let _ = pipe_producer.resume().await;
+                        }));
                     }
                 }
             })
@@ -1172,6 +1346,55 @@ impl Router {
         })
     }
 
+    /// Starts piping [`Producer`] with the given `producer_id` into [`Router`] on another host by
+    /// creating an offer, see [`remote_pipe`](crate::remote_pipe) module for details.
+    ///
+    /// Unlike [`Router::pipe_producer_to_router()`], a new [`PipeTransport`] is created for every
+    /// piped producer.
+    pub async fn create_remote_pipe_offer(
+        &self,
+        producer_id: ProducerId,
+        remote_pipe_options: RemotePipeOptions,
+    ) -> Result<PendingRemotePipe, PipeProducerToRouterError> {
+        debug!("create_remote_pipe_offer()");
+
+        let producer = match self
+            .inner
+            .producers
+            .read()
+            .get(&producer_id)
+            .and_then(WeakProducer::upgrade)
+        {
+            Some(producer) => producer,
+            None => {
+                return Err(PipeProducerToRouterError::ProducerNotFound(producer_id));
+            }
+        };
+
+        PendingRemotePipe::new(self, &producer, &remote_pipe_options).await
+    }
+
+    /// Accepts offer created with [`Router::create_remote_pipe_offer()`] on another host, creating
+    /// pipe producer with the same id as the piped producer.
+    ///
+    /// Returns answer that needs to be sent back to the offering host. `message_sender` is called
+    /// with messages that need to be delivered to
+    /// [`RemotePipeConsumer::handle_message()`](crate::remote_pipe::RemotePipeConsumer::handle_message)
+    /// on the offering host.
+    pub async fn accept_remote_pipe_offer<F>(
+        &self,
+        offer: RemotePipeOffer,
+        remote_pipe_options: RemotePipeOptions,
+        message_sender: F,
+    ) -> Result<(RemotePipeAnswer, RemotePipeProducer), PipeProducerToRouterError>
+    where
+        F: Fn(RemotePipeMessage) + Send + Sync + 'static,
+    {
+        debug!("accept_remote_pipe_offer()");
+
+        RemotePipeProducer::new(self, offer, &remote_pipe_options, message_sender).await
+    }
+
     /// Pipes [`DataProducer`] with the given `data_producer_id` into another [`Router`] on same
     /// host.
     ///
@@ -1343,6 +1566,17 @@ impl Router {
         })
     }
 
+    /// Compute intersection of router RTP capabilities and RTP capabilities of a remote endpoint,
+    /// explaining codecs and header extensions that were rejected.
+    ///
+    /// See [`negotiation`](crate::negotiation) module for details.
+    pub fn negotiate_rtp_capabilities(
+        &self,
+        rtp_capabilities: &RtpCapabilities,
+    ) -> Result<RtpCapabilitiesNegotiation, RtpCapabilitiesError> {
+        negotiation::negotiate(&self.inner.rtp_capabilities, rtp_capabilities)
+    }
+
     /// Check whether the given RTP capabilities are valid to consume the given producer.
     #[must_use]
     pub fn can_consume(
@@ -1367,6 +1601,27 @@ impl Router {
         }
     }
 
+    /// Check whether the given RTP capabilities are valid to consume the given producer, explaining
+    /// which codecs and header extensions don't match otherwise.
+    ///
+    /// This is a more verbose version of [`Router::can_consume()`] meant for diagnostics, for
+    /// instance:
+    /// ```text
+    /// No compatible media codecs: video/H264 (PT 125): [PT 102] Different profile (producer:
+    /// 640032 (High, Level5), capabilities: 42e01f (ConstrainedBaseline, Level31))
+    /// ```
+    pub fn check_consume(
+        &self,
+        producer_id: &ProducerId,
+        rtp_capabilities: &RtpCapabilities,
+    ) -> Result<(), ConsumeIncompatibility> {
+        let producer = self
+            .get_producer(producer_id)
+            .ok_or(ConsumeIncompatibility::ProducerNotFound(*producer_id))?;
+
+        ortc::check_consume(producer.consumable_rtp_parameters(), rtp_capabilities)
+    }
+
     /// Callback is called when a new transport is created.
     pub fn on_new_transport<F: Fn(NewTransport<'_>) + Send + Sync + 'static>(
         &self,
@@ -1401,6 +1656,36 @@ impl Router {
         handler_id
     }
 
+    /// Stream of router events, alternative to `on_*` callbacks.
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<RouterEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_new_transport({
+                    let sender = sender.clone();
+
+                    move |transport| {
+                        sender.send(RouterEvent::NewTransport {
+                            transport_id: transport.id(),
+                        });
+                    }
+                }),
+                self.on_new_rtp_observer({
+                    let sender = sender.clone();
+
+                    move |rtp_observer| {
+                        sender.send(RouterEvent::NewRtpObserver {
+                            rtp_observer_id: rtp_observer.id(),
+                        });
+                    }
+                }),
+                self.on_worker_close(sender.emitter(RouterEvent::WorkerClose)),
+                self.on_close(sender.last_emitter(RouterEvent::Close)),
+            ]
+        })
+    }
+
     async fn get_or_create_pipe_transport_pair(
         &self,
         pipe_to_router_options: PipeToRouterOptions,
@@ -1518,6 +1803,14 @@ impl Router {
         })
     }
 
+    /// Downgrade `Router` to [`WeakRouter`] instance.
+    #[must_use]
+    pub fn downgrade(&self) -> WeakRouter {
+        WeakRouter {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
     fn after_transport_creation(&self, transport: &impl TransportGeneric) {
         {
             let producers_weak = Arc::downgrade(&self.inner.producers);
@@ -1593,3 +1886,28 @@ impl Router {
         self.inner.close();
     }
 }
+
+/// [`WeakRouter`] doesn't own router instance on mediasoup-worker and will not prevent one from
+/// being destroyed once last instance of regular [`Router`] is dropped.
+///
+/// [`WeakRouter`] vs [`Router`] is similar to [`Weak`] vs [`Arc`].
+#[derive(Clone)]
+pub struct WeakRouter {
+    inner: Weak<Inner>,
+}
+
+impl fmt::Debug for WeakRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakRouter").finish()
+    }
+}
+
+impl WeakRouter {
+    /// Attempts to upgrade `WeakRouter` to [`Router`] if last instance of one wasn't dropped yet.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Router> {
+        let inner = self.inner.upgrade()?;
+
+        Some(Router { inner })
+    }
+}
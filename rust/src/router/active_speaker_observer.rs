@@ -2,6 +2,8 @@
 mod tests;
 
 use crate::data_structures::AppData;
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     RtpObserverAddProducerRequest, RtpObserverCloseRequest, RtpObserverPauseRequest,
     RtpObserverRemoveProducerRequest, RtpObserverResumeRequest,
@@ -10,7 +12,6 @@ use crate::producer::{Producer, ProducerId};
 use crate::router::Router;
 use crate::rtp_observer::{RtpObserver, RtpObserverAddProducerOptions, RtpObserverId};
 use crate::worker::{Channel, NotificationParseError, RequestError, SubscriptionHandler};
-use async_executor::Executor;
 use async_trait::async_trait;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
@@ -100,7 +101,7 @@ impl Notification {
 
 struct Inner {
     id: RtpObserverId,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     paused: AtomicBool,
@@ -136,13 +137,18 @@ impl Inner {
                     rtp_observer_id: self.id,
                 };
 
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request(router_id, request).await {
-                            error!("active speaker observer closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::rtp_observer",
+                        "close_rtp_observer",
+                        rtp_observer_id = %request.rtp_observer_id,
+                    );
+                    if let Err(error) = instrument!(channel.request(router_id, request), span).await
+                    {
+                        error!("active speaker observer closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -303,7 +309,7 @@ impl RtpObserver for ActiveSpeakerObserver {
 impl ActiveSpeakerObserver {
     pub(super) fn new(
         id: RtpObserverId,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         app_data: AppData,
         router: Router,
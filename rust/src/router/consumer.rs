@@ -1,7 +1,12 @@
 #[cfg(test)]
 mod tests;
 
-use crate::data_structures::{AppData, RtpPacketTraceInfo, SsrcTraceInfo, TraceEventDirection};
+use crate::data_structures::{
+    AppData, RtpPacketTraceInfo, SsrcTraceInfo, TraceEventDirection, TraceEventError,
+};
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     ConsumerCloseRequest, ConsumerDumpRequest, ConsumerEnableTraceEventRequest,
     ConsumerGetStatsRequest, ConsumerPauseRequest, ConsumerRequestKeyFrameRequest,
@@ -14,7 +19,6 @@ use crate::rtp_parameters::{
 use crate::transport::Transport;
 use crate::uuid_based_wrapper_type;
 use crate::worker::{Channel, NotificationParseError, RequestError, SubscriptionHandler};
-use async_executor::Executor;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
 use mediasoup_sys::fbs::{
@@ -119,6 +123,21 @@ pub struct ConsumerOptions {
     pub ignore_dtx: bool,
     /// Whether this Consumer should consume all RTP streams generated by the Producer.
     pub pipe: bool,
+    /// MIME types of media codecs the Consumer may use, from the most preferred one (e.g. AV1,
+    /// then VP9, then H264). Compatible codecs that are not listed are not used, consuming fails
+    /// if none of the listed codecs is compatible.
+    ///
+    /// Default is empty, meaning that all compatible codecs are used in the order of the Producer.
+    /// Not applicable to pipe transports.
+    pub codec_preferences: Vec<MimeType>,
+    /// Whether the Consumer must send media with payload types and header extension ids preferred
+    /// in `rtp_capabilities` instead of the Router ones. Default false.
+    ///
+    /// Needed when the consuming endpoint is the SDP offerer (e.g. WHEP), since it dictates
+    /// payload types and header extension ids. RTX codecs the endpoint didn't offer for a media
+    /// codec and header extensions with ids above 14 are not used. Not applicable to pipe
+    /// transports.
+    pub use_remote_ids: bool,
     /// Custom application data.
     pub app_data: AppData,
 }
@@ -136,6 +155,8 @@ impl ConsumerOptions {
             enable_rtx: None,
             pipe: false,
             mid: None,
+            codec_preferences: Vec::new(),
+            use_remote_ids: false,
             app_data: AppData::default(),
         }
     }
@@ -163,7 +184,9 @@ pub struct RtpStreamParams {
 }
 
 impl RtpStreamParams {
-    pub(crate) fn from_fbs_ref(params: rtp_stream::ParamsRef<'_>) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs_ref(
+        params: rtp_stream::ParamsRef<'_>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             clock_rate: params.clock_rate()?,
             cname: params.cname()?.to_string(),
@@ -197,7 +220,9 @@ pub struct RtxStreamParams {
 }
 
 impl RtxStreamParams {
-    pub(crate) fn from_fbs_ref(params: rtx_stream::ParamsRef<'_>) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs_ref(
+        params: rtx_stream::ParamsRef<'_>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             clock_rate: params.clock_rate()?,
             cname: params.cname()?.to_string(),
@@ -218,7 +243,9 @@ pub struct RtpStream {
 }
 
 impl RtpStream {
-    pub(crate) fn from_fbs_ref(dump: rtp_stream::DumpRef<'_>) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs_ref(
+        dump: rtp_stream::DumpRef<'_>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             params: RtpStreamParams::from_fbs_ref(dump.params()?)?,
             score: dump.score()?,
@@ -267,7 +294,7 @@ pub struct ConsumerDump {
 impl ConsumerDump {
     pub(crate) fn from_fbs_ref(
         dump: consumer::DumpResponseRef<'_>,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let dump = dump.data();
 
         Ok(Self {
@@ -286,7 +313,7 @@ impl ConsumerDump {
                 .trace_event_types()?
                 .iter()
                 .map(|trace_event_type| Ok(ConsumerTraceEventType::from_fbs(trace_event_type?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             r#type: ConsumerType::from_fbs(dump?.base()?.type_()?),
             consumable_rtp_encodings: dump?
                 .base()?
@@ -295,12 +322,12 @@ impl ConsumerDump {
                 .map(|encoding_parameters| {
                     RtpEncodingParameters::from_fbs_ref(encoding_parameters?)
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             rtp_streams: dump?
                 .rtp_streams()?
                 .iter()
                 .map(|stream| RtpStream::from_fbs_ref(stream?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             preferred_spatial_layer: dump?.preferred_spatial_layer()?,
             target_spatial_layer: dump?.target_spatial_layer()?,
             current_spatial_layer: dump?.current_spatial_layer()?,
@@ -440,6 +467,34 @@ impl ConsumerStats {
     }
 }
 
+/// Event of [`Consumer::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ConsumerEvent {
+    /// Consumer score changed, see [`Consumer::on_score()`].
+    Score(ConsumerScore),
+    /// Layers being sent changed, see [`Consumer::on_layers_change()`].
+    LayersChange(Option<ConsumerLayers>),
+    /// Consumer became paused, either by itself or because associated producer was paused.
+    Pause,
+    /// Consumer became resumed, either by itself or because associated producer was resumed.
+    Resume,
+    /// Associated producer was paused.
+    ProducerPause,
+    /// Associated producer was resumed.
+    ProducerResume,
+    /// Trace event, see [`Consumer::enable_trace_event()`].
+    Trace(ConsumerTraceEventData),
+    /// Trace event couldn't be decoded, see [`Consumer::on_trace_error()`].
+    TraceError(TraceEventError),
+    /// Associated producer was closed.
+    ProducerClose,
+    /// Transport this consumer belongs to was closed.
+    TransportClose,
+    /// Consumer was closed, this is the last event in the stream.
+    Close,
+}
+
 /// 'trace' event data.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -490,14 +545,14 @@ pub enum ConsumerTraceEventData {
 }
 
 impl ConsumerTraceEventData {
-    pub(crate) fn from_fbs(data: consumer::TraceNotification) -> Self {
-        match data.type_ {
+    pub(crate) fn from_fbs(data: consumer::TraceNotification) -> Result<Self, TraceEventError> {
+        Ok(match data.type_ {
             consumer::TraceEventType::Rtp => ConsumerTraceEventData::Rtp {
                 timestamp: data.timestamp,
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(consumer::TraceInfo::RtpTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     RtpPacketTraceInfo::from_fbs(*info.rtp_packet, info.is_rtx)
@@ -508,7 +563,7 @@ impl ConsumerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(consumer::TraceInfo::KeyFrameTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     RtpPacketTraceInfo::from_fbs(*info.rtp_packet, info.is_rtx)
@@ -523,7 +578,7 @@ impl ConsumerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(consumer::TraceInfo::PliTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     SsrcTraceInfo { ssrc: info.ssrc }
@@ -534,13 +589,13 @@ impl ConsumerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(consumer::TraceInfo::FirTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     SsrcTraceInfo { ssrc: info.ssrc }
                 },
             },
-        }
+        })
     }
 }
 
@@ -592,6 +647,8 @@ enum Notification {
     Score(ConsumerScore),
     LayersChange(Option<ConsumerLayers>),
     Trace(ConsumerTraceEventData),
+    #[serde(skip)]
+    TraceError(TraceEventError),
 }
 
 impl Notification {
@@ -628,16 +685,19 @@ impl Notification {
                 Ok(Notification::LayersChange(Some(layers)))
             }
             notification::Event::ConsumerTrace => {
-                let Ok(Some(notification::BodyRef::ConsumerTraceNotification(body))) =
-                    notification.body()
-                else {
-                    panic!("Wrong message from worker: {notification:?}");
+                let trace_notification = match notification.body() {
+                    Ok(Some(notification::BodyRef::ConsumerTraceNotification(body))) => {
+                        consumer::TraceNotification::try_from(body)
+                            .map_err(|error| TraceEventError::Decode(error.to_string()))
+                            .and_then(ConsumerTraceEventData::from_fbs)
+                    }
+                    _ => Err(TraceEventError::UnexpectedBody),
                 };
 
-                let trace_notification_fbs = consumer::TraceNotification::try_from(body).unwrap();
-                let trace_notification = ConsumerTraceEventData::from_fbs(trace_notification_fbs);
-
-                Ok(Notification::Trace(trace_notification))
+                Ok(match trace_notification {
+                    Ok(trace_notification) => Notification::Trace(trace_notification),
+                    Err(error) => Notification::TraceError(error),
+                })
             }
             _ => Err(NotificationParseError::InvalidEvent),
         }
@@ -656,6 +716,7 @@ struct Handlers {
     #[allow(clippy::type_complexity)]
     layers_change: Bag<Arc<dyn Fn(&Option<ConsumerLayers>) + Send + Sync>, Option<ConsumerLayers>>,
     trace: Bag<Arc<dyn Fn(&ConsumerTraceEventData) + Send + Sync>, ConsumerTraceEventData>,
+    trace_error: Bag<Arc<dyn Fn(&TraceEventError) + Send + Sync>, TraceEventError>,
     producer_close: BagOnce<Box<dyn FnOnce() + Send>>,
     transport_close: BagOnce<Box<dyn FnOnce() + Send>>,
     close: BagOnce<Box<dyn FnOnce() + Send>>,
@@ -668,7 +729,7 @@ struct Inner {
     r#type: ConsumerType,
     rtp_parameters: RtpParameters,
     paused: Arc<Mutex<bool>>,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     producer_paused: Arc<Mutex<bool>>,
     priority: Mutex<u8>,
@@ -708,15 +769,21 @@ impl Inner {
                 };
                 let weak_producer = self.weak_producer.clone();
 
-                self.executor
-                    .spawn(async move {
-                        if weak_producer.upgrade().is_some() {
-                            if let Err(error) = channel.request(transport_id, request).await {
-                                error!("consumer closing failed on drop: {}", error);
-                            }
+                self.executor.spawn(Box::pin(async move {
+                    if weak_producer.upgrade().is_some() {
+                        #[cfg(feature = "tracing")]
+                        let span = tracing::info_span!(
+                            target: "mediasoup::consumer",
+                            "close_consumer",
+                            consumer_id = %request.consumer_id,
+                        );
+                        if let Err(error) =
+                            instrument!(channel.request(transport_id, request), span).await
+                        {
+                            error!("consumer closing failed on drop: {}", error);
                         }
-                    })
-                    .detach();
+                    }
+                }));
             }
         }
     }
@@ -758,7 +825,7 @@ impl Consumer {
         r#type: ConsumerType,
         rtp_parameters: RtpParameters,
         paused: bool,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         producer_paused: bool,
         score: ConsumerScore,
@@ -797,15 +864,11 @@ impl Consumer {
                                 let maybe_inner =
                                     inner_weak.lock().as_ref().and_then(Weak::upgrade);
                                 if let Some(inner) = maybe_inner {
-                                    inner
-                                        .executor
-                                        .clone()
-                                        .spawn(async move {
-                                            // Potential drop needs to happen from a different
-                                            // thread to prevent potential deadlock
-                                            inner.close(false);
-                                        })
-                                        .detach();
+                                    inner.executor.clone().spawn(Box::pin(async move {
+                                        // Potential drop needs to happen from a different
+                                        // thread to prevent potential deadlock
+                                        inner.close(false);
+                                    }));
                                 }
                             }
                         }
@@ -851,6 +914,10 @@ impl Consumer {
                         Notification::Trace(trace_event_data) => {
                             handlers.trace.call_simple(&trace_event_data);
                         }
+                        Notification::TraceError(error) => {
+                            error!("Failed to decode trace event: {}", error);
+                            handlers.trace_error.call_simple(&error);
+                        }
                     },
                     Err(error) => {
                         error!("Failed to parse notification: {}", error);
@@ -1154,6 +1221,10 @@ impl Consumer {
             .await
     }
 
+    pub(crate) fn executor(&self) -> &Arc<dyn Executor> {
+        &self.inner.executor
+    }
+
     /// Callback is called when the consumer receives through its router a RTP packet from the
     /// associated producer.
     ///
@@ -1226,6 +1297,15 @@ impl Consumer {
         self.inner.handlers.trace.add(Arc::new(callback))
     }
 
+    /// Callback is called when trace event enabled with [`Consumer::enable_trace_event`] was received
+    /// from the worker, but couldn't be decoded.
+    pub fn on_trace_error<F: Fn(&TraceEventError) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner.handlers.trace_error.add(Arc::new(callback))
+    }
+
     /// Callback is called when the associated producer is closed for whatever reason. The consumer
     /// itself is also closed.
     pub fn on_producer_close<F: FnOnce() + Send + 'static>(&self, callback: F) -> HandlerId {
@@ -1249,6 +1329,43 @@ impl Consumer {
         handler_id
     }
 
+    /// Stream of consumer events, alternative to `on_*` callbacks (except [`Consumer::on_rtp()`]).
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<ConsumerEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_score({
+                    let sender = sender.clone();
+
+                    move |score| sender.send(ConsumerEvent::Score(score.clone()))
+                }),
+                self.on_layers_change({
+                    let sender = sender.clone();
+
+                    move |layers| sender.send(ConsumerEvent::LayersChange(*layers))
+                }),
+                self.on_pause(sender.emitter(ConsumerEvent::Pause)),
+                self.on_resume(sender.emitter(ConsumerEvent::Resume)),
+                self.on_producer_pause(sender.emitter(ConsumerEvent::ProducerPause)),
+                self.on_producer_resume(sender.emitter(ConsumerEvent::ProducerResume)),
+                self.on_trace({
+                    let sender = sender.clone();
+
+                    move |trace| sender.send(ConsumerEvent::Trace(trace.clone()))
+                }),
+                self.on_trace_error({
+                    let sender = sender.clone();
+
+                    move |error| sender.send(ConsumerEvent::TraceError(error.clone()))
+                }),
+                self.on_producer_close(sender.emitter(ConsumerEvent::ProducerClose)),
+                self.on_transport_close(sender.emitter(ConsumerEvent::TransportClose)),
+                self.on_close(sender.last_emitter(ConsumerEvent::Close)),
+            ]
+        })
+    }
+
     /// Downgrade `Consumer` to [`WeakConsumer`] instance.
     #[must_use]
     pub fn downgrade(&self) -> WeakConsumer {
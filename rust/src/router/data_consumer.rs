@@ -2,7 +2,10 @@
 mod tests;
 
 use crate::data_producer::{DataProducer, DataProducerId, WeakDataProducer};
-use crate::data_structures::{AppData, WebRtcMessage};
+use crate::data_structures::{AppData, OwnedWebRtcMessage, WebRtcMessage};
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     DataConsumerCloseRequest, DataConsumerDumpRequest, DataConsumerGetBufferedAmountRequest,
     DataConsumerGetStatsRequest, DataConsumerPauseRequest, DataConsumerResumeRequest,
@@ -13,7 +16,6 @@ use crate::sctp_parameters::SctpStreamParameters;
 use crate::transport::Transport;
 use crate::uuid_based_wrapper_type;
 use crate::worker::{Channel, NotificationParseError, RequestError, SubscriptionHandler};
-use async_executor::Executor;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
 use mediasoup_sys::fbs::{data_consumer, data_producer, notification, response};
@@ -163,7 +165,9 @@ pub struct DataConsumerDump {
 }
 
 impl DataConsumerDump {
-    pub(crate) fn from_fbs(dump: data_consumer::DumpResponse) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        dump: data_consumer::DumpResponse,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             id: dump.id.parse()?,
             data_producer_id: dump.data_producer_id.parse()?,
@@ -223,6 +227,37 @@ pub enum DataConsumerType {
     Direct,
 }
 
+/// Event of [`DataConsumer::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum DataConsumerEvent {
+    /// Message received from the corresponding data producer, see [`DataConsumer::on_message()`].
+    Message(OwnedWebRtcMessage),
+    /// Message could not be sent because the SCTP send buffer was full.
+    SctpSendBufferFull,
+    /// Buffered amount dropped below threshold, see [`DataConsumer::on_buffered_amount_low()`].
+    BufferedAmountLow {
+        /// Current buffered amount.
+        buffered_amount: u32,
+    },
+    /// Data consumer became paused, either by itself or because associated data producer was
+    /// paused.
+    Pause,
+    /// Data consumer became resumed, either by itself or because associated data producer was
+    /// resumed.
+    Resume,
+    /// Associated data producer was paused.
+    DataProducerPause,
+    /// Associated data producer was resumed.
+    DataProducerResume,
+    /// Associated data producer was closed.
+    DataProducerClose,
+    /// Transport this data consumer belongs to was closed.
+    TransportClose,
+    /// Data consumer was closed, this is the last event in the stream.
+    Close,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "event", rename_all = "lowercase", content = "data")]
 enum Notification {
@@ -312,7 +347,7 @@ struct Inner {
     paused: Arc<Mutex<bool>>,
     subchannels: Arc<Mutex<Vec<u16>>>,
     data_producer_paused: Arc<Mutex<bool>>,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     app_data: AppData,
@@ -348,15 +383,21 @@ impl Inner {
                 };
                 let weak_data_producer = self.weak_data_producer.clone();
 
-                self.executor
-                    .spawn(async move {
-                        if weak_data_producer.upgrade().is_some() {
-                            if let Err(error) = channel.request(transport_id, request).await {
-                                error!("consumer closing failed on drop: {}", error);
-                            }
+                self.executor.spawn(Box::pin(async move {
+                    if weak_data_producer.upgrade().is_some() {
+                        #[cfg(feature = "tracing")]
+                        let span = tracing::info_span!(
+                            target: "mediasoup::data_consumer",
+                            "close_data_consumer",
+                            data_consumer_id = %request.data_consumer_id,
+                        );
+                        if let Err(error) =
+                            instrument!(channel.request(transport_id, request), span).await
+                        {
+                            error!("consumer closing failed on drop: {}", error);
                         }
-                    })
-                    .detach();
+                    }
+                }));
             }
         }
     }
@@ -460,7 +501,7 @@ impl DataConsumer {
         protocol: String,
         paused: bool,
         data_producer: DataProducer,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         data_producer_paused: bool,
         subchannels: Vec<u16>,
@@ -495,15 +536,11 @@ impl DataConsumer {
                                 let maybe_inner =
                                     inner_weak.lock().as_ref().and_then(Weak::upgrade);
                                 if let Some(inner) = maybe_inner {
-                                    inner
-                                        .executor
-                                        .clone()
-                                        .spawn(async move {
-                                            // Potential drop needs to happen from a different
-                                            // thread to prevent potential deadlock
-                                            inner.close(false);
-                                        })
-                                        .detach();
+                                    inner.executor.clone().spawn(Box::pin(async move {
+                                        // Potential drop needs to happen from a different
+                                        // thread to prevent potential deadlock
+                                        inner.close(false);
+                                    }));
                                 }
                             }
                         }
@@ -887,6 +924,40 @@ impl DataConsumer {
         handler_id
     }
 
+    /// Stream of data consumer events, alternative to `on_*` callbacks.
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<DataConsumerEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_message({
+                    let sender = sender.clone();
+
+                    move |message| {
+                        sender.send(DataConsumerEvent::Message(message.clone().into_owned()));
+                    }
+                }),
+                self.on_sctp_send_buffer_full(
+                    sender.emitter(DataConsumerEvent::SctpSendBufferFull),
+                ),
+                self.on_buffered_amount_low({
+                    let sender = sender.clone();
+
+                    move |buffered_amount| {
+                        sender.send(DataConsumerEvent::BufferedAmountLow { buffered_amount });
+                    }
+                }),
+                self.on_pause(sender.emitter(DataConsumerEvent::Pause)),
+                self.on_resume(sender.emitter(DataConsumerEvent::Resume)),
+                self.on_data_producer_pause(sender.emitter(DataConsumerEvent::DataProducerPause)),
+                self.on_producer_resume(sender.emitter(DataConsumerEvent::DataProducerResume)),
+                self.on_data_producer_close(sender.emitter(DataConsumerEvent::DataProducerClose)),
+                self.on_transport_close(sender.emitter(DataConsumerEvent::TransportClose)),
+                self.on_close(sender.last_emitter(DataConsumerEvent::Close)),
+            ]
+        })
+    }
+
     /// Downgrade `DataConsumer` to [`WeakDataConsumer`] instance.
     #[must_use]
     pub fn downgrade(&self) -> WeakDataConsumer {
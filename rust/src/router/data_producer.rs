@@ -2,6 +2,8 @@
 mod tests;
 
 use crate::data_structures::{AppData, WebRtcMessage};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     DataProducerCloseRequest, DataProducerDumpRequest, DataProducerGetStatsRequest,
     DataProducerPauseRequest, DataProducerResumeRequest, DataProducerSendNotification,
@@ -10,7 +12,6 @@ use crate::sctp_parameters::SctpStreamParameters;
 use crate::transport::Transport;
 use crate::uuid_based_wrapper_type;
 use crate::worker::{Channel, NotificationError, RequestError};
-use async_executor::Executor;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
 use mediasoup_sys::fbs::{data_producer, response};
@@ -116,7 +117,9 @@ pub struct DataProducerDump {
 }
 
 impl DataProducerDump {
-    pub(crate) fn from_fbs(dump: data_producer::DumpResponse) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        dump: data_producer::DumpResponse,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             id: dump.id.parse()?,
             r#type: if dump.type_ == data_producer::Type::Sctp {
@@ -177,7 +180,7 @@ struct Inner {
     protocol: String,
     paused: AtomicBool,
     direct: bool,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     app_data: AppData,
@@ -207,13 +210,19 @@ impl Inner {
                 let request = DataProducerCloseRequest {
                     data_producer_id: self.id,
                 };
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request(transport_id, request).await {
-                            error!("data producer closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::data_producer",
+                        "close_data_producer",
+                        data_producer_id = %request.data_producer_id,
+                    );
+                    if let Err(error) =
+                        instrument!(channel.request(transport_id, request), span).await
+                    {
+                        error!("data producer closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -311,7 +320,7 @@ impl DataProducer {
         label: String,
         protocol: String,
         paused: bool,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         app_data: AppData,
         transport: Arc<dyn Transport>,
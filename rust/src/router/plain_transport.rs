@@ -4,7 +4,9 @@ mod tests;
 use crate::consumer::{Consumer, ConsumerId, ConsumerOptions};
 use crate::data_consumer::{DataConsumer, DataConsumerId, DataConsumerOptions, DataConsumerType};
 use crate::data_producer::{DataProducer, DataProducerId, DataProducerOptions, DataProducerType};
-use crate::data_structures::{AppData, ListenInfo, SctpState, TransportTuple};
+use crate::data_structures::{AppData, ListenInfo, SctpState, TraceEventError, TransportTuple};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{PlainTransportData, TransportCloseRequest, TransportConnectPlainRequest};
 use crate::producer::{Producer, ProducerId, ProducerOptions};
 use crate::router::transport::{TransportImpl, TransportType};
@@ -17,7 +19,6 @@ use crate::transport::{
     TransportTraceEventType,
 };
 use crate::worker::{Channel, NotificationParseError, RequestError, SubscriptionHandler};
-use async_executor::Executor;
 use async_trait::async_trait;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
@@ -127,7 +128,9 @@ pub struct PlainTransportDump {
 }
 
 impl PlainTransportDump {
-    pub(crate) fn from_fbs(dump: plain_transport::DumpResponse) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        dump: plain_transport::DumpResponse,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             // Common to all Transports.
             id: dump.base.id.parse()?,
@@ -137,37 +140,37 @@ impl PlainTransportDump {
                 .producer_ids
                 .iter()
                 .map(|producer_id| Ok(producer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             consumer_ids: dump
                 .base
                 .consumer_ids
                 .iter()
                 .map(|consumer_id| Ok(consumer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_ssrc_consumer_id: dump
                 .base
                 .map_ssrc_consumer_id
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_rtx_ssrc_consumer_id: dump
                 .base
                 .map_rtx_ssrc_consumer_id
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             data_producer_ids: dump
                 .base
                 .data_producer_ids
                 .iter()
                 .map(|data_producer_id| Ok(data_producer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             data_consumer_ids: dump
                 .base
                 .data_consumer_ids
                 .iter()
                 .map(|data_consumer_id| Ok(data_consumer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             recv_rtp_header_extensions: RecvRtpHeaderExtensions::from_fbs(
                 dump.base.recv_rtp_header_extensions.as_ref(),
             ),
@@ -251,7 +254,7 @@ pub struct PlainTransportStat {
 impl PlainTransportStat {
     pub(crate) fn from_fbs(
         stats: plain_transport::GetStatsResponse,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             transport_id: stats.base.transport_id.parse()?,
             timestamp: stats.base.timestamp,
@@ -319,6 +322,7 @@ struct Handlers {
     rtcp_tuple: Bag<Arc<dyn Fn(&TransportTuple) + Send + Sync>, TransportTuple>,
     sctp_state_change: Bag<Arc<dyn Fn(SctpState) + Send + Sync>>,
     trace: Bag<Arc<dyn Fn(&TransportTraceEventData) + Send + Sync>, TransportTraceEventData>,
+    trace_error: Bag<Arc<dyn Fn(&TraceEventError) + Send + Sync>, TraceEventError>,
     router_close: BagOnce<Box<dyn FnOnce() + Send>>,
     close: BagOnce<Box<dyn FnOnce() + Send>>,
 }
@@ -338,6 +342,8 @@ enum Notification {
         sctp_state: SctpState,
     },
     Trace(TransportTraceEventData),
+    #[serde(skip)]
+    TraceError(TraceEventError),
 }
 
 impl Notification {
@@ -381,16 +387,19 @@ impl Notification {
                 Ok(Notification::SctpStateChange { sctp_state })
             }
             notification::Event::TransportTrace => {
-                let Ok(Some(notification::BodyRef::TransportTraceNotification(body))) =
-                    notification.body()
-                else {
-                    panic!("Wrong message from worker: {notification:?}");
+                let trace_notification = match notification.body() {
+                    Ok(Some(notification::BodyRef::TransportTraceNotification(body))) => {
+                        transport::TraceNotification::try_from(body)
+                            .map_err(|error| TraceEventError::Decode(error.to_string()))
+                            .and_then(TransportTraceEventData::from_fbs)
+                    }
+                    _ => Err(TraceEventError::UnexpectedBody),
                 };
 
-                let trace_notification_fbs = transport::TraceNotification::try_from(body).unwrap();
-                let trace_notification = TransportTraceEventData::from_fbs(trace_notification_fbs);
-
-                Ok(Notification::Trace(trace_notification))
+                Ok(match trace_notification {
+                    Ok(trace_notification) => Notification::Trace(trace_notification),
+                    Err(error) => Notification::TraceError(error),
+                })
             }
             _ => Err(NotificationParseError::InvalidEvent),
         }
@@ -402,13 +411,15 @@ struct Inner {
     next_mid_for_consumers: AtomicUsize,
     used_sctp_stream_ids: Mutex<IntMap<u16, bool>>,
     cname_for_producers: Mutex<Option<String>>,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     data: Arc<PlainTransportData>,
     app_data: AppData,
     // Make sure router is not dropped until this transport is not dropped
     router: Router,
+    #[cfg(feature = "tracing")]
+    _span: crate::worker::EntitySpan,
     closed: AtomicBool,
     // Drop subscription to transport-specific notifications when transport itself is dropped
     _subscription_handler: Mutex<Option<SubscriptionHandler>>,
@@ -437,13 +448,18 @@ impl Inner {
                     transport_id: self.id,
                 };
 
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request(router_id, request).await {
-                            error!("transport closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::transport",
+                        "close_transport",
+                        transport_id = %request.transport_id,
+                    );
+                    if let Err(error) = instrument!(channel.request(router_id, request), span).await
+                    {
+                        error!("transport closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -647,7 +663,7 @@ impl TransportImpl for PlainTransport {
         &self.inner.channel
     }
 
-    fn executor(&self) -> &Arc<Executor<'static>> {
+    fn executor(&self) -> &Arc<dyn Executor> {
         &self.inner.executor
     }
 
@@ -667,7 +683,7 @@ impl TransportImpl for PlainTransport {
 impl PlainTransport {
     pub(super) fn new(
         id: TransportId,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         data: PlainTransportData,
         app_data: AppData,
@@ -705,6 +721,10 @@ impl PlainTransport {
                         Notification::Trace(trace_event_data) => {
                             handlers.trace.call_simple(&trace_event_data);
                         }
+                        Notification::TraceError(error) => {
+                            error!("Failed to decode trace event: {}", error);
+                            handlers.trace_error.call_simple(&error);
+                        }
                     },
                     Err(error) => {
                         error!("Failed to parse notification: {}", error);
@@ -736,6 +756,17 @@ impl PlainTransport {
                 }
             }
         });
+        #[cfg(feature = "tracing")]
+        let span = router.worker().register_entity_span(
+            id,
+            tracing::info_span!(
+                target: "mediasoup::transport",
+                parent: router.span(),
+                "transport",
+                %id,
+                kind = "plain",
+            ),
+        );
         let inner = Arc::new(Inner {
             id,
             next_mid_for_consumers,
@@ -747,6 +778,8 @@ impl PlainTransport {
             data,
             app_data,
             router,
+            #[cfg(feature = "tracing")]
+            _span: span,
             closed: AtomicBool::new(false),
             _subscription_handler: Mutex::new(subscription_handler),
             _on_router_close_handler: Mutex::new(on_router_close_handler),
@@ -976,6 +1009,15 @@ impl PlainTransport {
             .add(Arc::new(callback))
     }
 
+    /// Callback is called when trace event enabled with [`Transport::enable_trace_event()`] was
+    /// received from the worker, but couldn't be decoded.
+    pub fn on_trace_error<F: Fn(&TraceEventError) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner.handlers.trace_error.add(Arc::new(callback))
+    }
+
     /// Downgrade `PlainTransport` to [`WeakPlainTransport`] instance.
     #[must_use]
     pub fn downgrade(&self) -> WeakPlainTransport {
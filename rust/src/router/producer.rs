@@ -2,7 +2,12 @@
 mod tests;
 
 use crate::consumer::{RtpStreamParams, RtxStreamParams};
-use crate::data_structures::{AppData, RtpPacketTraceInfo, SsrcTraceInfo, TraceEventDirection};
+use crate::data_structures::{
+    AppData, RtpPacketTraceInfo, SsrcTraceInfo, TraceEventDirection, TraceEventError,
+};
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     ProducerCloseRequest, ProducerDumpRequest, ProducerEnableTraceEventRequest,
     ProducerGetStatsRequest, ProducerPauseRequest, ProducerResumeRequest, ProducerSendNotification,
@@ -14,7 +19,6 @@ use crate::uuid_based_wrapper_type;
 use crate::worker::{
     Channel, NotificationError, NotificationParseError, RequestError, SubscriptionHandler,
 };
-use async_executor::Executor;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
 use mediasoup_sys::fbs::{notification, producer, response, rtp_parameters, rtp_stream};
@@ -106,7 +110,9 @@ pub struct RtpStreamRecv {
 }
 
 impl RtpStreamRecv {
-    pub(crate) fn from_fbs_ref(dump: rtp_stream::DumpRef<'_>) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs_ref(
+        dump: rtp_stream::DumpRef<'_>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             params: RtpStreamParams::from_fbs_ref(dump.params()?)?,
             score: dump.score()?,
@@ -139,7 +145,7 @@ pub struct ProducerDump {
 impl ProducerDump {
     pub(crate) fn from_fbs_ref(
         dump: producer::DumpResponseRef<'_>,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             id: dump.id()?.parse()?,
             kind: MediaKind::from_fbs(dump.kind()?),
@@ -150,7 +156,7 @@ impl ProducerDump {
                 .rtp_streams()?
                 .iter()
                 .map(|rtp_stream| RtpStreamRecv::from_fbs_ref(rtp_stream?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             trace_event_types: dump
                 .trace_event_types()?
                 .iter()
@@ -347,6 +353,28 @@ impl ProducerStat {
     }
 }
 
+/// Event of [`Producer::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ProducerEvent {
+    /// Producer score changed, see [`Producer::on_score()`].
+    Score(Vec<ProducerScore>),
+    /// Video orientation changed, see [`Producer::on_video_orientation_change()`].
+    VideoOrientationChange(ProducerVideoOrientation),
+    /// Producer was paused.
+    Pause,
+    /// Producer was resumed.
+    Resume,
+    /// Trace event, see [`Producer::enable_trace_event()`].
+    Trace(ProducerTraceEventData),
+    /// Trace event couldn't be decoded, see [`Producer::on_trace_error()`].
+    TraceError(TraceEventError),
+    /// Transport this producer belongs to was closed.
+    TransportClose,
+    /// Producer was closed, this is the last event in the stream.
+    Close,
+}
+
 /// 'trace' event data.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -397,14 +425,14 @@ pub enum ProducerTraceEventData {
 }
 
 impl ProducerTraceEventData {
-    pub(crate) fn from_fbs(data: producer::TraceNotification) -> Self {
-        match data.type_ {
+    pub(crate) fn from_fbs(data: producer::TraceNotification) -> Result<Self, TraceEventError> {
+        Ok(match data.type_ {
             producer::TraceEventType::Rtp => ProducerTraceEventData::Rtp {
                 timestamp: data.timestamp,
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(producer::TraceInfo::RtpTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     RtpPacketTraceInfo::from_fbs(*info.rtp_packet, info.is_rtx)
@@ -415,7 +443,7 @@ impl ProducerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(producer::TraceInfo::KeyFrameTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     RtpPacketTraceInfo::from_fbs(*info.rtp_packet, info.is_rtx)
@@ -430,7 +458,7 @@ impl ProducerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(producer::TraceInfo::PliTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     SsrcTraceInfo { ssrc: info.ssrc }
@@ -441,13 +469,13 @@ impl ProducerTraceEventData {
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(producer::TraceInfo::FirTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     SsrcTraceInfo { ssrc: info.ssrc }
                 },
             },
-        }
+        })
     }
 }
 
@@ -495,6 +523,8 @@ enum Notification {
     Score(Vec<ProducerScore>),
     VideoOrientationChange(ProducerVideoOrientation),
     Trace(ProducerTraceEventData),
+    #[serde(skip)]
+    TraceError(TraceEventError),
 }
 
 impl Notification {
@@ -534,16 +564,19 @@ impl Notification {
                 Ok(Notification::VideoOrientationChange(video_orientation))
             }
             notification::Event::ProducerTrace => {
-                let Ok(Some(notification::BodyRef::ProducerTraceNotification(body))) =
-                    notification.body()
-                else {
-                    panic!("Wrong message from worker: {notification:?}");
+                let trace_notification = match notification.body() {
+                    Ok(Some(notification::BodyRef::ProducerTraceNotification(body))) => {
+                        producer::TraceNotification::try_from(body)
+                            .map_err(|error| TraceEventError::Decode(error.to_string()))
+                            .and_then(ProducerTraceEventData::from_fbs)
+                    }
+                    _ => Err(TraceEventError::UnexpectedBody),
                 };
 
-                let trace_notification_fbs = producer::TraceNotification::try_from(body).unwrap();
-                let trace_notification = ProducerTraceEventData::from_fbs(trace_notification_fbs);
-
-                Ok(Notification::Trace(trace_notification))
+                Ok(match trace_notification {
+                    Ok(trace_notification) => Notification::Trace(trace_notification),
+                    Err(error) => Notification::TraceError(error),
+                })
             }
             _ => Err(NotificationParseError::InvalidEvent),
         }
@@ -558,6 +591,7 @@ struct Handlers {
     pause: Bag<Arc<dyn Fn() + Send + Sync>>,
     resume: Bag<Arc<dyn Fn() + Send + Sync>>,
     trace: Bag<Arc<dyn Fn(&ProducerTraceEventData) + Send + Sync>, ProducerTraceEventData>,
+    trace_error: Bag<Arc<dyn Fn(&TraceEventError) + Send + Sync>, TraceEventError>,
     transport_close: BagOnce<Box<dyn FnOnce() + Send>>,
     close: BagOnce<Box<dyn FnOnce() + Send>>,
 }
@@ -571,7 +605,7 @@ struct Inner {
     direct: bool,
     paused: AtomicBool,
     score: Arc<Mutex<Vec<ProducerScore>>>,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     app_data: AppData,
@@ -604,13 +638,19 @@ impl Inner {
                     producer_id: self.id,
                 };
 
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request(transport_id, request).await {
-                            error!("producer closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::producer",
+                        "close_producer",
+                        producer_id = %request.producer_id,
+                    );
+                    if let Err(error) =
+                        instrument!(channel.request(transport_id, request), span).await
+                    {
+                        error!("producer closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -712,7 +752,7 @@ impl Producer {
         rtp_parameters: RtpParameters,
         consumable_rtp_parameters: RtpParameters,
         paused: bool,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         app_data: AppData,
         transport: Arc<dyn Transport>,
@@ -744,6 +784,10 @@ impl Producer {
                         Notification::Trace(trace_event_data) => {
                             handlers.trace.call_simple(&trace_event_data);
                         }
+                        Notification::TraceError(error) => {
+                            error!("Failed to decode trace event: {}", error);
+                            handlers.trace_error.call_simple(&error);
+                        }
                     },
                     Err(error) => {
                         error!("Failed to parse notification: {}", error);
@@ -972,6 +1016,15 @@ impl Producer {
         self.inner().handlers.trace.add(Arc::new(callback))
     }
 
+    /// Callback is called when trace event enabled with [`Producer::enable_trace_event`] was received
+    /// from the worker, but couldn't be decoded.
+    pub fn on_trace_error<F: Fn(&TraceEventError) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner().handlers.trace_error.add(Arc::new(callback))
+    }
+
     /// Callback is called when the transport this producer belongs to is closed for whatever
     /// reason. The producer itself is also closed. A `on_producer_close` callback is called on all
     /// its associated consumers.
@@ -993,6 +1046,42 @@ impl Producer {
         handler_id
     }
 
+    /// Stream of producer events, alternative to `on_*` callbacks.
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<ProducerEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_score({
+                    let sender = sender.clone();
+
+                    move |score| sender.send(ProducerEvent::Score(score.to_vec()))
+                }),
+                self.on_video_orientation_change({
+                    let sender = sender.clone();
+
+                    move |video_orientation| {
+                        sender.send(ProducerEvent::VideoOrientationChange(video_orientation));
+                    }
+                }),
+                self.on_pause(sender.emitter(ProducerEvent::Pause)),
+                self.on_resume(sender.emitter(ProducerEvent::Resume)),
+                self.on_trace({
+                    let sender = sender.clone();
+
+                    move |trace| sender.send(ProducerEvent::Trace(trace.clone()))
+                }),
+                self.on_trace_error({
+                    let sender = sender.clone();
+
+                    move |error| sender.send(ProducerEvent::TraceError(error.clone()))
+                }),
+                self.on_transport_close(sender.emitter(ProducerEvent::TransportClose)),
+                self.on_close(sender.last_emitter(ProducerEvent::Close)),
+            ]
+        })
+    }
+
     /// Consumable RTP parameters.
     // This is used in tests, otherwise would have been `pub(super)`
     #[doc(hidden)]
@@ -1,5 +1,5 @@
-use crate::data_structures::{ListenInfo, Protocol};
-use crate::producer::ProducerOptions;
+use crate::data_structures::{ListenInfo, Protocol, TraceEventError};
+use crate::producer::{ProducerOptions, ProducerTraceEventData};
 use crate::router::{Router, RouterOptions};
 use crate::rtp_parameters::{
     MediaKind, MimeTypeAudio, RtpCodecCapability, RtpCodecParameters, RtpCodecParametersParameters,
@@ -12,6 +12,7 @@ use crate::webrtc_transport::{
 use crate::worker::WorkerSettings;
 use crate::worker_manager::WorkerManager;
 use futures_lite::future;
+use mediasoup_sys::fbs::{common, producer};
 use std::env;
 use std::net::{IpAddr, Ipv4Addr};
 use std::num::{NonZeroU32, NonZeroU8};
@@ -114,3 +115,29 @@ fn transport_close_event() {
         assert!(audio_producer.closed());
     });
 }
+
+#[test]
+fn malformed_trace_event_is_error() {
+    let trace_notification = |info| producer::TraceNotification {
+        type_: producer::TraceEventType::Pli,
+        timestamp: 1234,
+        direction: common::TraceDirection::DirectionIn,
+        info,
+    };
+
+    assert!(matches!(
+        ProducerTraceEventData::from_fbs(trace_notification(Some(
+            producer::TraceInfo::PliTraceInfo(Box::new(producer::PliTraceInfo { ssrc: 1111 }))
+        ))),
+        Ok(ProducerTraceEventData::Pli { info, .. }) if info.ssrc == 1111,
+    ));
+    assert_eq!(
+        ProducerTraceEventData::from_fbs(trace_notification(Some(
+            producer::TraceInfo::FirTraceInfo(Box::new(producer::FirTraceInfo { ssrc: 1111 }))
+        )))
+        .unwrap_err(),
+        TraceEventError::UnexpectedInfo {
+            event_type: "Pli".to_string(),
+        },
+    );
+}
@@ -0,0 +1,452 @@
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::{Consumer, ConsumerOptions};
+use crate::data_structures::{ListenInfo, Protocol};
+use crate::pipe_transport::{PipeTransport, PipeTransportOptions, PipeTransportRemoteParameters};
+use crate::producer::{Producer, ProducerId, ProducerOptions};
+use crate::router::{PipeProducerToRouterError, Router};
+use crate::rtp_parameters::{MediaKind, RtpCapabilities, RtpParameters};
+use crate::sctp_parameters::{NumSctpStreams, SctpParameters};
+use crate::srtp_parameters::SrtpParameters;
+use crate::transport::Transport;
+use crate::worker::RequestError;
+use event_listener_primitives::HandlerId;
+use log::debug;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+/// Options used for either side of piping a producer into router on another host, see
+/// [`Router::create_remote_pipe_offer()`] and [`Router::accept_remote_pipe_offer()`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RemotePipeOptions {
+    /// Listening info of the pipe transport, `announced_ip` needs to be set to the IP reachable by
+    /// the other host in most cases.
+    ///
+    /// Default `{ protocol: 'udp', ip: '127.0.0.1' }`.
+    pub listen_info: ListenInfo,
+    /// Create a SCTP association.
+    ///
+    /// Default `false`.
+    pub enable_sctp: bool,
+    /// SCTP streams number.
+    pub num_sctp_streams: NumSctpStreams,
+    /// Enable RTX and NACK for RTP retransmission.
+    ///
+    /// Default `false`.
+    pub enable_rtx: bool,
+    /// Enable SRTP, must be the same on both sides.
+    ///
+    /// Default `false`.
+    pub enable_srtp: bool,
+}
+
+impl RemotePipeOptions {
+    /// Create remote pipe options with given listening info.
+    #[must_use]
+    pub fn new(listen_info: ListenInfo) -> Self {
+        Self {
+            listen_info,
+            enable_sctp: false,
+            num_sctp_streams: NumSctpStreams::default(),
+            enable_rtx: false,
+            enable_srtp: false,
+        }
+    }
+
+    fn pipe_transport_options(&self) -> PipeTransportOptions {
+        let mut pipe_transport_options = PipeTransportOptions::new(self.listen_info);
+        pipe_transport_options.enable_sctp = self.enable_sctp;
+        pipe_transport_options.num_sctp_streams = self.num_sctp_streams;
+        pipe_transport_options.enable_rtx = self.enable_rtx;
+        pipe_transport_options.enable_srtp = self.enable_srtp;
+        pipe_transport_options
+    }
+}
+
+impl Default for RemotePipeOptions {
+    fn default() -> Self {
+        Self::new(ListenInfo {
+            protocol: Protocol::Udp,
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            announced_ip: None,
+            port: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        })
+    }
+}
+
+/// Offer created by the router that has the producer, to be sent to the other host.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct RemotePipeOffer {
+    /// IP of the offering pipe transport.
+    pub ip: IpAddr,
+    /// Port of the offering pipe transport.
+    pub port: u16,
+    /// SRTP parameters of the offering pipe transport.
+    pub srtp_parameters: Option<SrtpParameters>,
+    /// SCTP parameters of the offering pipe transport.
+    pub sctp_parameters: Option<SctpParameters>,
+    /// Id of the piped producer, pipe producer on the other host will have the same id.
+    pub producer_id: ProducerId,
+    /// Media kind of the piped producer.
+    pub kind: MediaKind,
+    /// RTP parameters to be used by pipe producer on the other host.
+    pub rtp_parameters: RtpParameters,
+    /// Whether the piped producer is paused.
+    pub paused: bool,
+}
+
+/// Answer created by the router that receives the producer, to be sent back to the offering host.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct RemotePipeAnswer {
+    /// IP of the answering pipe transport.
+    pub ip: IpAddr,
+    /// Port of the answering pipe transport.
+    pub port: u16,
+    /// SRTP parameters of the answering pipe transport.
+    pub srtp_parameters: Option<SrtpParameters>,
+    /// SCTP parameters of the answering pipe transport.
+    pub sctp_parameters: Option<SctpParameters>,
+}
+
+/// Message sent between both sides of the remote pipe after handshake, needs to be delivered to
+/// [`RemotePipeConsumer::handle_message()`] or [`RemotePipeProducer::handle_message()`] on the
+/// other host.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum RemotePipeMessage {
+    /// Piped producer was paused.
+    #[serde(rename_all = "camelCase")]
+    Pause {
+        /// Id of the piped producer.
+        producer_id: ProducerId,
+    },
+    /// Piped producer was resumed.
+    #[serde(rename_all = "camelCase")]
+    Resume {
+        /// Id of the piped producer.
+        producer_id: ProducerId,
+    },
+    /// Either side of the pipe was closed.
+    #[serde(rename_all = "camelCase")]
+    Close {
+        /// Id of the piped producer.
+        producer_id: ProducerId,
+    },
+}
+
+impl RemotePipeMessage {
+    /// Id of the piped producer message relates to, can be used for routing messages to the right
+    /// pipe.
+    #[must_use]
+    pub fn producer_id(&self) -> ProducerId {
+        let (Self::Pause { producer_id }
+        | Self::Resume { producer_id }
+        | Self::Close { producer_id }) = self;
+        *producer_id
+    }
+}
+
+type MessageSender = Arc<dyn Fn(RemotePipeMessage) + Send + Sync>;
+
+fn remote_parameters(
+    ip: IpAddr,
+    port: u16,
+    srtp_parameters: Option<SrtpParameters>,
+) -> PipeTransportRemoteParameters {
+    PipeTransportRemoteParameters {
+        ip,
+        port,
+        srtp_parameters,
+    }
+}
+
+/// Offering side of the remote pipe that waits for the answer, created with
+/// [`Router::create_remote_pipe_offer()`].
+#[derive(Debug)]
+#[must_use = "Remote pipe will be closed on drop, call `connect()` once answer is received"]
+pub struct PendingRemotePipe {
+    offer: RemotePipeOffer,
+    pipe_transport: PipeTransport,
+    pipe_consumer: Consumer,
+}
+
+impl PendingRemotePipe {
+    pub(super) async fn new(
+        router: &Router,
+        producer: &Producer,
+        remote_pipe_options: &RemotePipeOptions,
+    ) -> Result<Self, PipeProducerToRouterError> {
+        let pipe_transport = router
+            .create_pipe_transport(remote_pipe_options.pipe_transport_options())
+            .await?;
+
+        let pipe_consumer = pipe_transport
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                RtpCapabilities::default(),
+            ))
+            .await?;
+
+        let tuple = pipe_transport.tuple();
+        let offer = RemotePipeOffer {
+            ip: tuple.local_ip(),
+            port: tuple.local_port(),
+            srtp_parameters: pipe_transport.srtp_parameters(),
+            sctp_parameters: pipe_transport.sctp_parameters(),
+            producer_id: producer.id(),
+            kind: pipe_consumer.kind(),
+            rtp_parameters: pipe_consumer.rtp_parameters().clone(),
+            paused: pipe_consumer.producer_paused(),
+        };
+
+        Ok(Self {
+            offer,
+            pipe_transport,
+            pipe_consumer,
+        })
+    }
+
+    /// Offer to be sent to the other host.
+    #[must_use]
+    pub fn offer(&self) -> &RemotePipeOffer {
+        &self.offer
+    }
+
+    /// Connect to the other host using its answer.
+    ///
+    /// `message_sender` is called with messages that need to be delivered to
+    /// [`RemotePipeProducer::handle_message()`] on the other host.
+    pub async fn connect<F>(
+        self,
+        answer: RemotePipeAnswer,
+        message_sender: F,
+    ) -> Result<RemotePipeConsumer, RequestError>
+    where
+        F: Fn(RemotePipeMessage) + Send + Sync + 'static,
+    {
+        debug!("connect()");
+
+        self.pipe_transport
+            .connect(remote_parameters(
+                answer.ip,
+                answer.port,
+                answer.srtp_parameters,
+            ))
+            .await?;
+
+        let message_sender: MessageSender = Arc::new(message_sender);
+        let producer_id = self.offer.producer_id;
+        let pipe_consumer = self.pipe_consumer;
+
+        let handlers = vec![
+            pipe_consumer.on_pause({
+                let message_sender = Arc::clone(&message_sender);
+
+                move || message_sender(RemotePipeMessage::Pause { producer_id })
+            }),
+            pipe_consumer.on_resume({
+                let message_sender = Arc::clone(&message_sender);
+
+                move || message_sender(RemotePipeMessage::Resume { producer_id })
+            }),
+            pipe_consumer.on_close({
+                let message_sender = Arc::clone(&message_sender);
+
+                move || message_sender(RemotePipeMessage::Close { producer_id })
+            }),
+        ];
+
+        // Piped producer might have been paused, resumed or closed since the offer was created.
+        // Close was already reported by `on_close()` that is called in place for closed consumer,
+        // pause state is reconciled here (the same message may also be sent by handlers above if
+        // it changes concurrently, which is harmless).
+        if !pipe_consumer.closed() {
+            let paused = pipe_consumer.producer_paused();
+            if paused != self.offer.paused {
+                message_sender(if paused {
+                    RemotePipeMessage::Pause { producer_id }
+                } else {
+                    RemotePipeMessage::Resume { producer_id }
+                });
+            }
+        }
+
+        Ok(RemotePipeConsumer {
+            producer_id,
+            pipe: Mutex::new(Some((self.pipe_transport, pipe_consumer))),
+            _handlers: handlers,
+        })
+    }
+}
+
+/// Offering side of the connected remote pipe.
+///
+/// Pause, resume and close of the piped producer are propagated to the other host through message
+/// sender given to [`PendingRemotePipe::connect()`]. Pipe is closed on drop or when
+/// [`RemotePipeMessage::Close`] is received.
+#[derive(Debug)]
+#[must_use = "Remote pipe will be closed on drop"]
+pub struct RemotePipeConsumer {
+    producer_id: ProducerId,
+    pipe: Mutex<Option<(PipeTransport, Consumer)>>,
+    _handlers: Vec<HandlerId>,
+}
+
+impl RemotePipeConsumer {
+    /// Id of the piped producer.
+    #[must_use]
+    pub fn producer_id(&self) -> ProducerId {
+        self.producer_id
+    }
+
+    /// Pipe consumer in local router, `None` if pipe was closed already.
+    #[must_use]
+    pub fn pipe_consumer(&self) -> Option<Consumer> {
+        self.pipe
+            .lock()
+            .as_ref()
+            .map(|(_pipe_transport, pipe_consumer)| pipe_consumer.clone())
+    }
+
+    /// Handle message received from the other host, messages for other producers are ignored.
+    pub fn handle_message(&self, message: RemotePipeMessage) {
+        if message.producer_id() != self.producer_id {
+            return;
+        }
+
+        if let RemotePipeMessage::Close { .. } = message {
+            debug!(
+                "handle_message() | remote side closed [producer_id:{}]",
+                self.producer_id
+            );
+            let _pipe = self.pipe.lock().take();
+        }
+    }
+}
+
+/// Answering side of the remote pipe, created with [`Router::accept_remote_pipe_offer()`].
+///
+/// Close of the pipe producer is propagated to the other host through message sender given to
+/// [`Router::accept_remote_pipe_offer()`]. Pipe is closed on drop or when
+/// [`RemotePipeMessage::Close`] is received.
+#[derive(Debug)]
+#[must_use = "Remote pipe will be closed on drop"]
+pub struct RemotePipeProducer {
+    producer_id: ProducerId,
+    pipe: Mutex<Option<(PipeTransport, Producer)>>,
+    _handlers: Vec<HandlerId>,
+}
+
+impl RemotePipeProducer {
+    pub(super) async fn new<F>(
+        router: &Router,
+        offer: RemotePipeOffer,
+        remote_pipe_options: &RemotePipeOptions,
+        message_sender: F,
+    ) -> Result<(RemotePipeAnswer, Self), PipeProducerToRouterError>
+    where
+        F: Fn(RemotePipeMessage) + Send + Sync + 'static,
+    {
+        let pipe_transport = router
+            .create_pipe_transport(remote_pipe_options.pipe_transport_options())
+            .await?;
+
+        pipe_transport
+            .connect(remote_parameters(
+                offer.ip,
+                offer.port,
+                offer.srtp_parameters,
+            ))
+            .await?;
+
+        let producer_id = offer.producer_id;
+        let pipe_producer = pipe_transport
+            .produce({
+                let mut producer_options = ProducerOptions::new_pipe_transport(
+                    producer_id,
+                    offer.kind,
+                    offer.rtp_parameters,
+                );
+                producer_options.paused = offer.paused;
+
+                producer_options
+            })
+            .await?;
+
+        let message_sender: MessageSender = Arc::new(message_sender);
+        let handlers = vec![pipe_producer.on_close(move || {
+            message_sender(RemotePipeMessage::Close { producer_id });
+        })];
+
+        let tuple = pipe_transport.tuple();
+        let answer = RemotePipeAnswer {
+            ip: tuple.local_ip(),
+            port: tuple.local_port(),
+            srtp_parameters: pipe_transport.srtp_parameters(),
+            sctp_parameters: pipe_transport.sctp_parameters(),
+        };
+
+        Ok((
+            answer,
+            Self {
+                producer_id,
+                pipe: Mutex::new(Some((pipe_transport, pipe_producer))),
+                _handlers: handlers,
+            },
+        ))
+    }
+
+    /// Id of the piped producer (the same as on the other host).
+    #[must_use]
+    pub fn producer_id(&self) -> ProducerId {
+        self.producer_id
+    }
+
+    /// Pipe producer in local router, `None` if pipe was closed already.
+    #[must_use]
+    pub fn pipe_producer(&self) -> Option<Producer> {
+        self.pipe
+            .lock()
+            .as_ref()
+            .map(|(_pipe_transport, pipe_producer)| pipe_producer.clone())
+    }
+
+    /// Handle message received from the other host, messages for other producers are ignored.
+    pub async fn handle_message(&self, message: RemotePipeMessage) -> Result<(), RequestError> {
+        if message.producer_id() != self.producer_id {
+            return Ok(());
+        }
+
+        match message {
+            RemotePipeMessage::Pause { .. } => {
+                if let Some(pipe_producer) = self.pipe_producer() {
+                    pipe_producer.pause().await?;
+                }
+            }
+            RemotePipeMessage::Resume { .. } => {
+                if let Some(pipe_producer) = self.pipe_producer() {
+                    pipe_producer.resume().await?;
+                }
+            }
+            RemotePipeMessage::Close { .. } => {
+                debug!(
+                    "handle_message() | remote side closed [producer_id:{}]",
+                    self.producer_id
+                );
+                let _pipe = self.pipe.lock().take();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use crate::data_structures::{ListenInfo, Protocol};
+use crate::producer::ProducerOptions;
+use crate::remote_pipe::{RemotePipeMessage, RemotePipeOffer, RemotePipeOptions};
+use crate::router::{Router, RouterOptions};
+use crate::rtp_parameters::{
+    MediaKind, MimeTypeVideo, RtpCodecCapability, RtpCodecParameters, RtpCodecParametersParameters,
+    RtpParameters,
+};
+use crate::transport::Transport;
+use crate::webrtc_transport::{
+    WebRtcTransport, WebRtcTransportListenInfos, WebRtcTransportOptions,
+};
+use crate::worker::WorkerSettings;
+use crate::worker_manager::WorkerManager;
+use futures_lite::future;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::NonZeroU32;
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![RtpCodecCapability::Video {
+        mime_type: MimeTypeVideo::Vp8,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }]
+}
+
+fn video_producer_options() -> ProducerOptions {
+    ProducerOptions::new(
+        MediaKind::Video,
+        RtpParameters {
+            mid: Some("VIDEO".to_string()),
+            codecs: vec![RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                payload_type: 112,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }],
+            ..RtpParameters::default()
+        },
+    )
+}
+
+async fn init() -> (Router, Router, WebRtcTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    // Separate worker managers stand for separate hosts
+    let worker1 = WorkerManager::new()
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let worker2 = WorkerManager::new()
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router1 = worker1
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    let router2 = worker2
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    let transport = router1
+        .create_webrtc_transport(WebRtcTransportOptions::new(
+            WebRtcTransportListenInfos::new(ListenInfo {
+                protocol: Protocol::Udp,
+                ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                announced_ip: None,
+                port: None,
+                send_buffer_size: None,
+                recv_buffer_size: None,
+            }),
+        ))
+        .await
+        .expect("Failed to create transport");
+
+    (router1, router2, transport)
+}
+
+#[test]
+fn remote_pipe_succeeds() {
+    future::block_on(async move {
+        let (router1, router2, transport) = init().await;
+
+        let video_producer = transport
+            .produce(video_producer_options())
+            .await
+            .expect("Failed to produce video");
+
+        let pending_pipe = router1
+            .create_remote_pipe_offer(video_producer.id(), RemotePipeOptions::default())
+            .await
+            .expect("Failed to create offer");
+
+        // Offer and answer go through serialization as they would over signaling
+        let offer = serde_json::from_str::<RemotePipeOffer>(
+            &serde_json::to_string(pending_pipe.offer()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&offer, pending_pipe.offer());
+        assert_eq!(offer.producer_id, video_producer.id());
+        assert_eq!(offer.kind, MediaKind::Video);
+        assert!(!offer.paused);
+
+        let (to_host1_sender, to_host1_receiver) = async_channel::unbounded();
+        let (to_host2_sender, to_host2_receiver) = async_channel::unbounded();
+
+        let (answer, pipe_producer) = router2
+            .accept_remote_pipe_offer(offer, RemotePipeOptions::default(), move |message| {
+                let _ = to_host1_sender.try_send(message);
+            })
+            .await
+            .expect("Failed to accept offer");
+
+        assert_eq!(pipe_producer.producer_id(), video_producer.id());
+        assert_eq!(
+            pipe_producer.pipe_producer().map(|producer| producer.id()),
+            Some(video_producer.id())
+        );
+
+        let pipe_consumer = pending_pipe
+            .connect(answer, move |message| {
+                let _ = to_host2_sender.try_send(message);
+            })
+            .await
+            .expect("Failed to connect");
+
+        assert_eq!(
+            pipe_consumer
+                .pipe_consumer()
+                .map(|consumer| consumer.producer_id()),
+            Some(video_producer.id())
+        );
+
+        video_producer.pause().await.expect("Failed to pause");
+
+        let message = to_host2_receiver.recv().await.unwrap();
+        assert_eq!(
+            message,
+            RemotePipeMessage::Pause {
+                producer_id: video_producer.id()
+            }
+        );
+        pipe_producer
+            .handle_message(message)
+            .await
+            .expect("Failed to handle message");
+        assert!(pipe_producer.pipe_producer().unwrap().paused());
+
+        video_producer.resume().await.expect("Failed to resume");
+
+        let message = to_host2_receiver.recv().await.unwrap();
+        pipe_producer
+            .handle_message(message)
+            .await
+            .expect("Failed to handle message");
+        assert!(!pipe_producer.pipe_producer().unwrap().paused());
+
+        drop(video_producer);
+
+        let message = to_host2_receiver.recv().await.unwrap();
+        assert!(matches!(message, RemotePipeMessage::Close { .. }));
+        assert!(pipe_consumer.pipe_consumer().is_some_and(|c| c.closed()));
+        pipe_producer
+            .handle_message(message)
+            .await
+            .expect("Failed to handle message");
+        assert!(pipe_producer.pipe_producer().is_none());
+
+        // Closing pipe producer is propagated back
+        let message = to_host1_receiver.recv().await.unwrap();
+        assert!(matches!(message, RemotePipeMessage::Close { .. }));
+        pipe_consumer.handle_message(message);
+        assert!(pipe_consumer.pipe_consumer().is_none());
+    });
+}
+
+#[test]
+fn remote_pipe_propagates_changes_before_connect() {
+    future::block_on(async move {
+        let (router1, router2, transport) = init().await;
+
+        let video_producer = transport
+            .produce(video_producer_options())
+            .await
+            .expect("Failed to produce video");
+
+        let pending_pipe = router1
+            .create_remote_pipe_offer(video_producer.id(), RemotePipeOptions::default())
+            .await
+            .expect("Failed to create offer");
+        assert!(!pending_pipe.offer().paused);
+
+        // Paused after offer was created, but before pipe was connected
+        video_producer.pause().await.expect("Failed to pause");
+
+        let (answer, pipe_producer) = router2
+            .accept_remote_pipe_offer(
+                pending_pipe.offer().clone(),
+                RemotePipeOptions::default(),
+                |_message| {},
+            )
+            .await
+            .expect("Failed to accept offer");
+
+        let (to_host2_sender, to_host2_receiver) = async_channel::unbounded();
+        let _pipe_consumer = pending_pipe
+            .connect(answer, move |message| {
+                let _ = to_host2_sender.try_send(message);
+            })
+            .await
+            .expect("Failed to connect");
+
+        let message = to_host2_receiver.recv().await.unwrap();
+        assert_eq!(
+            message,
+            RemotePipeMessage::Pause {
+                producer_id: video_producer.id()
+            }
+        );
+        pipe_producer
+            .handle_message(message)
+            .await
+            .expect("Failed to handle message");
+        assert!(pipe_producer.pipe_producer().unwrap().paused());
+        drop(pipe_producer);
+
+        // Closed after offer was created, but before pipe was connected
+        let pending_pipe = router1
+            .create_remote_pipe_offer(video_producer.id(), RemotePipeOptions::default())
+            .await
+            .expect("Failed to create offer");
+        let (answer, _pipe_producer) = router2
+            .accept_remote_pipe_offer(
+                pending_pipe.offer().clone(),
+                RemotePipeOptions::default(),
+                |_message| {},
+            )
+            .await
+            .expect("Failed to accept offer");
+
+        drop(video_producer);
+
+        let (to_host2_sender, to_host2_receiver) = async_channel::unbounded();
+        let _pipe_consumer = pending_pipe
+            .connect(answer, move |message| {
+                let _ = to_host2_sender.try_send(message);
+            })
+            .await
+            .expect("Failed to connect");
+
+        assert!(matches!(
+            to_host2_receiver.recv().await.unwrap(),
+            RemotePipeMessage::Close { .. }
+        ));
+        assert!(to_host2_receiver.try_recv().is_err());
+    });
+}
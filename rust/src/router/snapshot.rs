@@ -0,0 +1,1238 @@
+#[cfg(test)]
+mod tests;
+
+use crate::active_speaker_observer::{
+    ActiveSpeakerObserver, ActiveSpeakerObserverOptions, WeakActiveSpeakerObserver,
+};
+use crate::audio_level_observer::{
+    AudioLevelObserver, AudioLevelObserverOptions, WeakAudioLevelObserver,
+};
+use crate::consumer::{
+    Consumer, ConsumerId, ConsumerLayers, ConsumerOptions, ConsumerType, WeakConsumer,
+};
+use crate::data_consumer::{
+    DataConsumer, DataConsumerId, DataConsumerOptions, DataConsumerType, WeakDataConsumer,
+};
+use crate::data_producer::{
+    DataProducer, DataProducerId, DataProducerOptions, DataProducerType, WeakDataProducer,
+};
+use crate::data_structures::ListenInfo;
+use crate::direct_transport::{DirectTransport, DirectTransportOptions, WeakDirectTransport};
+use crate::pipe_transport::{PipeTransport, PipeTransportOptions, WeakPipeTransport};
+use crate::plain_transport::{PlainTransport, PlainTransportOptions, WeakPlainTransport};
+use crate::producer::{Producer, ProducerId, ProducerOptions, WeakProducer};
+use crate::router::{NewRtpObserver, NewTransport, Router, RouterId, RouterOptions};
+use crate::rtp_observer::{RtpObserver, RtpObserverAddProducerOptions, RtpObserverId};
+use crate::rtp_parameters::{
+    MediaKind, RtpCapabilities, RtpCodecCapability, RtpCodecParameters, RtpHeaderExtension,
+    RtpHeaderExtensionDirection, RtpParameters,
+};
+use crate::sctp_parameters::{NumSctpStreams, SctpStreamParameters};
+use crate::srtp_parameters::SrtpCryptoSuite;
+use crate::transport::{
+    ConsumeDataError, ConsumeError, ProduceDataError, ProduceError, Transport, TransportId,
+};
+use crate::webrtc_server::WebRtcServerId;
+use crate::webrtc_transport::{
+    WeakWebRtcTransport, WebRtcTransport, WebRtcTransportListen, WebRtcTransportListenInfos,
+    WebRtcTransportOptions,
+};
+use crate::worker::{CreateRouterError, RequestError, Worker};
+use event_listener_primitives::HandlerId;
+use hash_hasher::{HashedMap, HashedSet};
+use log::debug;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroU16;
+use std::ops::Deref;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Version of the [`RouterSnapshot`] format produced by [`Router::snapshot()`].
+///
+/// It is bumped on every incompatible change of the format, [`Worker::restore_router()`] rejects
+/// snapshots with any other version.
+pub const ROUTER_SNAPSHOT_VERSION: u32 = 1;
+
+/// Serializable snapshot of the [`Router`] topology, see [`Router::snapshot()`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct RouterSnapshot {
+    /// Format version, see [`ROUTER_SNAPSHOT_VERSION`].
+    pub version: u32,
+    /// Id of the router snapshot was taken from.
+    pub id: RouterId,
+    /// Media codecs the router was created with.
+    pub media_codecs: Vec<RtpCodecCapability>,
+    /// Transports in the order of creation.
+    pub transports: Vec<TransportSnapshot>,
+    /// RTP observers in the order of creation.
+    pub rtp_observers: Vec<RtpObserverSnapshot>,
+}
+
+/// Snapshot of a transport and entities created on it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct TransportSnapshot {
+    /// Transport id.
+    pub id: TransportId,
+    /// Options transport was created with.
+    pub options: TransportSnapshotOptions,
+    /// Producers in the order of creation.
+    pub producers: Vec<ProducerSnapshot>,
+    /// Consumers in the order of creation.
+    pub consumers: Vec<ConsumerSnapshot>,
+    /// Data producers in the order of creation.
+    pub data_producers: Vec<DataProducerSnapshot>,
+    /// Data consumers in the order of creation.
+    pub data_consumers: Vec<DataConsumerSnapshot>,
+}
+
+/// Options transport was created with, mirrors options of each transport type except custom
+/// application data.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum TransportSnapshotOptions {
+    /// See [`WebRtcTransportOptions`].
+    #[serde(rename_all = "camelCase")]
+    WebRtc {
+        /// How transport listens on interfaces.
+        listen: WebRtcTransportSnapshotListen,
+        /// Initial available outgoing bitrate (in bps).
+        initial_available_outgoing_bitrate: u32,
+        /// Enable UDP.
+        enable_udp: bool,
+        /// Enable TCP.
+        enable_tcp: bool,
+        /// Prefer UDP.
+        prefer_udp: bool,
+        /// Prefer TCP.
+        prefer_tcp: bool,
+        /// Create a SCTP association.
+        enable_sctp: bool,
+        /// SCTP streams number.
+        num_sctp_streams: NumSctpStreams,
+        /// Maximum allowed size for SCTP messages sent by data producers.
+        max_sctp_message_size: u32,
+        /// Maximum SCTP send buffer used by data consumers.
+        sctp_send_buffer_size: u32,
+    },
+    /// See [`PlainTransportOptions`].
+    #[serde(rename_all = "camelCase")]
+    Plain {
+        /// Listening info for RTP (and RTCP if `rtcp_mux` is enabled).
+        listen_info: ListenInfo,
+        /// Separate listening info for RTCP.
+        rtcp_listen_info: Option<ListenInfo>,
+        /// Use RTCP-mux (RTP and RTCP in the same port).
+        rtcp_mux: bool,
+        /// Whether remote IP:port should be auto-detected based on first RTP/RTCP packet received.
+        comedia: bool,
+        /// Create a SCTP association.
+        enable_sctp: bool,
+        /// SCTP streams number.
+        num_sctp_streams: NumSctpStreams,
+        /// Maximum allowed size for SCTP messages sent by data producers.
+        max_sctp_message_size: u32,
+        /// Maximum SCTP send buffer used by data consumers.
+        sctp_send_buffer_size: u32,
+        /// Enable SRTP.
+        enable_srtp: bool,
+        /// The SRTP crypto suite to be used if `enable_srtp` is set.
+        srtp_crypto_suite: SrtpCryptoSuite,
+    },
+    /// See [`PipeTransportOptions`].
+    #[serde(rename_all = "camelCase")]
+    Pipe {
+        /// Listening info.
+        listen_info: ListenInfo,
+        /// Create a SCTP association.
+        enable_sctp: bool,
+        /// SCTP streams number.
+        num_sctp_streams: NumSctpStreams,
+        /// Maximum allowed size for SCTP messages sent by data producers.
+        max_sctp_message_size: u32,
+        /// Maximum SCTP send buffer used by data consumers.
+        sctp_send_buffer_size: u32,
+        /// Enable RTX and NACK for RTP retransmission.
+        enable_rtx: bool,
+        /// Enable SRTP.
+        enable_srtp: bool,
+    },
+    /// See [`DirectTransportOptions`].
+    #[serde(rename_all = "camelCase")]
+    Direct {
+        /// Maximum allowed size for direct messages sent from data producers.
+        max_message_size: u32,
+    },
+}
+
+impl TransportSnapshotOptions {
+    pub(super) fn webrtc(options: &WebRtcTransportOptions) -> Self {
+        Self::WebRtc {
+            listen: match &options.listen {
+                WebRtcTransportListen::Individual { listen_infos } => {
+                    WebRtcTransportSnapshotListen::Individual {
+                        listen_infos: listen_infos.clone(),
+                    }
+                }
+                WebRtcTransportListen::Server { webrtc_server } => {
+                    WebRtcTransportSnapshotListen::Server {
+                        webrtc_server_id: webrtc_server.id(),
+                    }
+                }
+            },
+            initial_available_outgoing_bitrate: options.initial_available_outgoing_bitrate,
+            enable_udp: options.enable_udp,
+            enable_tcp: options.enable_tcp,
+            prefer_udp: options.prefer_udp,
+            prefer_tcp: options.prefer_tcp,
+            enable_sctp: options.enable_sctp,
+            num_sctp_streams: options.num_sctp_streams,
+            max_sctp_message_size: options.max_sctp_message_size,
+            sctp_send_buffer_size: options.sctp_send_buffer_size,
+        }
+    }
+
+    pub(super) fn plain(options: &PlainTransportOptions) -> Self {
+        Self::Plain {
+            listen_info: options.listen_info,
+            rtcp_listen_info: options.rtcp_listen_info,
+            rtcp_mux: options.rtcp_mux,
+            comedia: options.comedia,
+            enable_sctp: options.enable_sctp,
+            num_sctp_streams: options.num_sctp_streams,
+            max_sctp_message_size: options.max_sctp_message_size,
+            sctp_send_buffer_size: options.sctp_send_buffer_size,
+            enable_srtp: options.enable_srtp,
+            srtp_crypto_suite: options.srtp_crypto_suite,
+        }
+    }
+
+    pub(super) fn pipe(options: &PipeTransportOptions) -> Self {
+        Self::Pipe {
+            listen_info: options.listen_info,
+            enable_sctp: options.enable_sctp,
+            num_sctp_streams: options.num_sctp_streams,
+            max_sctp_message_size: options.max_sctp_message_size,
+            sctp_send_buffer_size: options.sctp_send_buffer_size,
+            enable_rtx: options.enable_rtx,
+            enable_srtp: options.enable_srtp,
+        }
+    }
+
+    pub(super) fn direct(options: &DirectTransportOptions) -> Self {
+        Self::Direct {
+            max_message_size: options.max_message_size,
+        }
+    }
+}
+
+/// How snapshotted [`WebRtcTransport`] was listening on interfaces.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WebRtcTransportSnapshotListen {
+    /// Individual protocol/IP/port combinations specific to this transport.
+    #[serde(rename_all = "camelCase")]
+    Individual {
+        /// Listening infos in order of preference (first one is the preferred one).
+        listen_infos: WebRtcTransportListenInfos,
+    },
+    /// Shared [`WebRtcServer`](crate::webrtc_server::WebRtcServer), can't be restored.
+    #[serde(rename_all = "camelCase")]
+    Server {
+        /// Id of the WebRTC server.
+        webrtc_server_id: WebRtcServerId,
+    },
+}
+
+/// Snapshot of a [`Producer`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ProducerSnapshot {
+    /// Producer id.
+    pub id: ProducerId,
+    /// Media kind.
+    pub kind: MediaKind,
+    /// RTP parameters defining what the endpoint is sending.
+    pub rtp_parameters: RtpParameters,
+    /// Whether the producer is paused.
+    pub paused: bool,
+    /// Just for video. Time (in ms) before asking the sender for a new key frame after having asked
+    /// a previous one.
+    pub key_frame_request_delay: u32,
+}
+
+/// Snapshot of a [`Consumer`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ConsumerSnapshot {
+    /// Consumer id.
+    pub id: ConsumerId,
+    /// Id of the consumed producer.
+    pub producer_id: ProducerId,
+    /// Media kind.
+    pub kind: MediaKind,
+    /// Consumer type.
+    pub r#type: ConsumerType,
+    /// RTP parameters negotiated with the consuming endpoint.
+    pub rtp_parameters: RtpParameters,
+    /// Whether the consumer is paused, regardless of whether the producer is paused.
+    pub paused: bool,
+    /// Preferred spatial and temporal layers for simulcast and SVC consumers.
+    pub preferred_layers: Option<ConsumerLayers>,
+    /// Consumer priority.
+    pub priority: u8,
+    /// Whether DTX packets are dropped.
+    pub ignore_dtx: bool,
+}
+
+/// Snapshot of a [`DataProducer`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct DataProducerSnapshot {
+    /// Data producer id.
+    pub id: DataProducerId,
+    /// Data producer type.
+    pub r#type: DataProducerType,
+    /// SCTP stream parameters, only for data producers of type `Sctp`.
+    pub sctp_stream_parameters: Option<SctpStreamParameters>,
+    /// A label which can be used to distinguish this DataChannel from others.
+    pub label: String,
+    /// Name of the sub-protocol used by this DataChannel.
+    pub protocol: String,
+    /// Whether the data producer is paused.
+    pub paused: bool,
+}
+
+/// Snapshot of a [`DataConsumer`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct DataConsumerSnapshot {
+    /// Data consumer id.
+    pub id: DataConsumerId,
+    /// Id of the consumed data producer.
+    pub data_producer_id: DataProducerId,
+    /// Data consumer type.
+    pub r#type: DataConsumerType,
+    /// SCTP stream parameters, only for data consumers of type `Sctp`.
+    pub sctp_stream_parameters: Option<SctpStreamParameters>,
+    /// Whether the data consumer is paused, regardless of whether the data producer is paused.
+    pub paused: bool,
+    /// Subchannels the data consumer is subscribed to.
+    pub subchannels: Vec<u16>,
+}
+
+/// Snapshot of an RTP observer.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct RtpObserverSnapshot {
+    /// RTP observer id.
+    pub id: RtpObserverId,
+    /// Options RTP observer was created with.
+    pub options: RtpObserverSnapshotOptions,
+    /// Whether the RTP observer is paused.
+    pub paused: bool,
+    /// Ids of observed producers in the order they were added.
+    pub producer_ids: Vec<ProducerId>,
+}
+
+/// Options RTP observer was created with, mirrors options of each observer type except custom
+/// application data.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum RtpObserverSnapshotOptions {
+    /// See [`AudioLevelObserverOptions`].
+    #[serde(rename_all = "camelCase")]
+    AudioLevel {
+        /// Maximum number of entries in the 'volumes' event.
+        max_entries: NonZeroU16,
+        /// Minimum average volume (in dBvo from -127 to 0) for entries in the 'volumes' event.
+        threshold: i8,
+        /// Interval in ms for checking audio volumes.
+        interval: u16,
+    },
+    /// See [`ActiveSpeakerObserverOptions`].
+    #[serde(rename_all = "camelCase")]
+    ActiveSpeaker {
+        /// Interval in ms for checking audio volumes.
+        interval: u16,
+    },
+}
+
+impl RtpObserverSnapshotOptions {
+    pub(super) fn audio_level(options: &AudioLevelObserverOptions) -> Self {
+        Self::AudioLevel {
+            max_entries: options.max_entries,
+            threshold: options.threshold,
+            interval: options.interval,
+        }
+    }
+
+    pub(super) fn active_speaker(options: &ActiveSpeakerObserverOptions) -> Self {
+        Self::ActiveSpeaker {
+            interval: options.interval,
+        }
+    }
+}
+
+/// Transport re-created by [`Worker::restore_router()`].
+#[derive(Debug, Clone)]
+pub enum RestoredTransport {
+    /// Direct transport
+    Direct(DirectTransport),
+    /// Pipe transport
+    Pipe(PipeTransport),
+    /// Plain transport
+    Plain(PlainTransport),
+    /// WebRtc transport
+    WebRtc(WebRtcTransport),
+}
+
+impl Deref for RestoredTransport {
+    type Target = dyn Transport;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Direct(transport) => transport as &Self::Target,
+            Self::Pipe(transport) => transport as &Self::Target,
+            Self::Plain(transport) => transport as &Self::Target,
+            Self::WebRtc(transport) => transport as &Self::Target,
+        }
+    }
+}
+
+/// RTP observer re-created by [`Worker::restore_router()`].
+#[derive(Debug, Clone)]
+pub enum RestoredRtpObserver {
+    /// Audio level observer
+    AudioLevel(AudioLevelObserver),
+    /// Active speaker observer
+    ActiveSpeaker(ActiveSpeakerObserver),
+}
+
+impl Deref for RestoredRtpObserver {
+    type Target = dyn RtpObserver + Send + Sync;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::AudioLevel(observer) => observer as &Self::Target,
+            Self::ActiveSpeaker(observer) => observer as &Self::Target,
+        }
+    }
+}
+
+/// Router re-created from [`RouterSnapshot`] by [`Worker::restore_router()`].
+///
+/// Entities are re-created with new ids, maps are keyed by ids from the snapshot. Just like entities
+/// created directly, restored entities are closed when their last instance is dropped, so the ones
+/// that are needed must be taken out of this struct and kept around.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RestoredRouter {
+    /// Restored router.
+    pub router: Router,
+    /// Restored transports.
+    pub transports: HashedMap<TransportId, RestoredTransport>,
+    /// Restored producers.
+    pub producers: HashedMap<ProducerId, Producer>,
+    /// Restored consumers.
+    pub consumers: HashedMap<ConsumerId, Consumer>,
+    /// Restored data producers.
+    pub data_producers: HashedMap<DataProducerId, DataProducer>,
+    /// Restored data consumers.
+    pub data_consumers: HashedMap<DataConsumerId, DataConsumer>,
+    /// Restored RTP observers.
+    pub rtp_observers: HashedMap<RtpObserverId, RestoredRtpObserver>,
+}
+
+/// Error that caused [`Worker::restore_router()`] to fail.
+#[derive(Debug, Error)]
+pub enum RestoreRouterError {
+    /// Snapshot version is not supported
+    #[error("Snapshot version {0} is not supported, expected {ROUTER_SNAPSHOT_VERSION}")]
+    UnsupportedVersion(u32),
+    /// Failed to create router
+    #[error("Failed to create router: \"{0}\"")]
+    CreateRouter(CreateRouterError),
+    /// Transport listening on WebRTC server can't be restored
+    #[error("Transport with id \"{0}\" listens on WebRTC server and can't be restored")]
+    WebRtcServerTransport(TransportId),
+    /// Producer referenced by snapshot is not present in it
+    #[error("Producer with id \"{0}\" not found in snapshot")]
+    ProducerNotFound(ProducerId),
+    /// Data producer referenced by snapshot is not present in it
+    #[error("Data producer with id \"{0}\" not found in snapshot")]
+    DataProducerNotFound(DataProducerId),
+    /// Request to worker failed
+    #[error("Request to worker failed: \"{0}\"")]
+    Request(RequestError),
+    /// Failed to produce
+    #[error("Failed to produce: \"{0}\"")]
+    Produce(ProduceError),
+    /// Failed to consume
+    #[error("Failed to consume: \"{0}\"")]
+    Consume(ConsumeError),
+    /// Failed to produce data
+    #[error("Failed to produce data: \"{0}\"")]
+    ProduceData(ProduceDataError),
+    /// Failed to consume data
+    #[error("Failed to consume data: \"{0}\"")]
+    ConsumeData(ConsumeDataError),
+}
+
+impl From<RequestError> for RestoreRouterError {
+    fn from(error: RequestError) -> Self {
+        RestoreRouterError::Request(error)
+    }
+}
+
+enum WeakTransport {
+    Direct(WeakDirectTransport),
+    Pipe(WeakPipeTransport),
+    Plain(WeakPlainTransport),
+    WebRtc(WeakWebRtcTransport),
+}
+
+impl WeakTransport {
+    fn upgrade(&self) -> Option<RestoredTransport> {
+        let transport = match self {
+            Self::Direct(transport) => RestoredTransport::Direct(transport.upgrade()?),
+            Self::Pipe(transport) => RestoredTransport::Pipe(transport.upgrade()?),
+            Self::Plain(transport) => RestoredTransport::Plain(transport.upgrade()?),
+            Self::WebRtc(transport) => RestoredTransport::WebRtc(transport.upgrade()?),
+        };
+
+        Some(transport)
+    }
+}
+
+enum WeakRtpObserver {
+    AudioLevel(WeakAudioLevelObserver),
+    ActiveSpeaker(WeakActiveSpeakerObserver),
+}
+
+impl WeakRtpObserver {
+    fn upgrade(&self) -> Option<RestoredRtpObserver> {
+        let rtp_observer = match self {
+            Self::AudioLevel(observer) => RestoredRtpObserver::AudioLevel(observer.upgrade()?),
+            Self::ActiveSpeaker(observer) => {
+                RestoredRtpObserver::ActiveSpeaker(observer.upgrade()?)
+            }
+        };
+
+        Some(rtp_observer)
+    }
+}
+
+struct RegisteredTransport {
+    id: TransportId,
+    transport: WeakTransport,
+    options: TransportSnapshotOptions,
+}
+
+struct RegisteredRtpObserver {
+    id: RtpObserverId,
+    rtp_observer: WeakRtpObserver,
+    options: RtpObserverSnapshotOptions,
+    producer_ids: Arc<Mutex<Vec<ProducerId>>>,
+    _handlers: [HandlerId; 2],
+}
+
+/// Entries are keyed by registration order, so that snapshot lists entities in the order they
+/// were created, and are removed once entities are closed.
+#[derive(Default)]
+struct Entries {
+    next_key: u64,
+    transports: BTreeMap<u64, RegisteredTransport>,
+    producers: BTreeMap<u64, (WeakProducer, u32)>,
+    consumers: BTreeMap<u64, (WeakConsumer, bool)>,
+    data_producers: BTreeMap<u64, WeakDataProducer>,
+    data_consumers: BTreeMap<u64, WeakDataConsumer>,
+    rtp_observers: BTreeMap<u64, RegisteredRtpObserver>,
+}
+
+/// Keeps track of entities created on the router along with options that can't be retrieved from
+/// entities themselves, so that [`RouterSnapshot`] can be produced without talking to the worker.
+#[derive(Default)]
+pub(super) struct SnapshotRegistry {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl SnapshotRegistry {
+    /// Inserts entry and returns callback that removes it, to be called when entity is closed.
+    ///
+    /// Callback must not be called with entries locked: it locks them, so does the entity close
+    /// handler callback is passed to if entity is already closed.
+    fn insert<T>(
+        &self,
+        select: fn(&mut Entries) -> &mut BTreeMap<u64, T>,
+        entry: T,
+    ) -> impl FnOnce() + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let key = {
+            let mut entries = self.entries.lock();
+            let key = entries.next_key;
+            entries.next_key += 1;
+            select(&mut entries).insert(key, entry);
+            key
+        };
+        let entries_weak = Arc::downgrade(&self.entries);
+
+        move || {
+            if let Some(entries) = entries_weak.upgrade() {
+                // Removed entry is dropped after entries are unlocked
+                let entry = select(&mut entries.lock()).remove(&key);
+                drop(entry);
+            }
+        }
+    }
+
+    pub(super) fn add_transport(
+        &self,
+        transport: NewTransport<'_>,
+        options: TransportSnapshotOptions,
+    ) {
+        let id = transport.id();
+        let weak_transport = match transport {
+            NewTransport::Direct(transport) => WeakTransport::Direct(transport.downgrade()),
+            NewTransport::Pipe(transport) => WeakTransport::Pipe(transport.downgrade()),
+            NewTransport::Plain(transport) => WeakTransport::Plain(transport.downgrade()),
+            NewTransport::WebRtc(transport) => WeakTransport::WebRtc(transport.downgrade()),
+        };
+
+        let remove = self.insert(
+            |entries| &mut entries.transports,
+            RegisteredTransport {
+                id,
+                transport: weak_transport,
+                options,
+            },
+        );
+        transport.on_close(Box::new(remove)).detach();
+    }
+
+    pub(super) fn add_producer(&self, producer: &Producer, key_frame_request_delay: u32) {
+        let remove = self.insert(
+            |entries| &mut entries.producers,
+            (producer.downgrade(), key_frame_request_delay),
+        );
+        producer.on_close(remove).detach();
+    }
+
+    pub(super) fn add_consumer(&self, consumer: &Consumer, ignore_dtx: bool) {
+        let remove = self.insert(
+            |entries| &mut entries.consumers,
+            (consumer.downgrade(), ignore_dtx),
+        );
+        consumer.on_close(remove).detach();
+    }
+
+    pub(super) fn add_data_producer(&self, data_producer: &DataProducer) {
+        let remove = self.insert(
+            |entries| &mut entries.data_producers,
+            data_producer.downgrade(),
+        );
+        data_producer.on_close(remove).detach();
+    }
+
+    pub(super) fn add_data_consumer(&self, data_consumer: &DataConsumer) {
+        let remove = self.insert(
+            |entries| &mut entries.data_consumers,
+            data_consumer.downgrade(),
+        );
+        data_consumer.on_close(remove).detach();
+    }
+
+    pub(super) fn add_rtp_observer(
+        &self,
+        observer: NewRtpObserver<'_>,
+        options: RtpObserverSnapshotOptions,
+    ) {
+        let producer_ids = Arc::<Mutex<Vec<ProducerId>>>::default();
+        let add_producer_handler = observer.on_add_producer(Box::new({
+            let producer_ids = Arc::clone(&producer_ids);
+
+            move |producer| {
+                producer_ids.lock().push(producer.id());
+            }
+        }));
+        let remove_producer_handler = observer.on_remove_producer(Box::new({
+            let producer_ids = Arc::clone(&producer_ids);
+
+            move |producer| {
+                producer_ids
+                    .lock()
+                    .retain(|producer_id| *producer_id != producer.id());
+            }
+        }));
+
+        let rtp_observer = match observer {
+            NewRtpObserver::AudioLevel(observer) => {
+                WeakRtpObserver::AudioLevel(observer.downgrade())
+            }
+            NewRtpObserver::ActiveSpeaker(observer) => {
+                WeakRtpObserver::ActiveSpeaker(observer.downgrade())
+            }
+        };
+
+        let remove = self.insert(
+            |entries| &mut entries.rtp_observers,
+            RegisteredRtpObserver {
+                id: observer.id(),
+                rtp_observer,
+                options,
+                producer_ids,
+                _handlers: [add_producer_handler, remove_producer_handler],
+            },
+        );
+        observer.on_close(Box::new(remove)).detach();
+    }
+
+    pub(super) fn snapshot(
+        &self,
+        id: RouterId,
+        media_codecs: &[RtpCodecCapability],
+    ) -> RouterSnapshot {
+        // Entities are upgraded with entries locked, but used (and dropped, which may close them
+        // and call handlers that lock entries) after entries are unlocked.
+        let (
+            registered_transports,
+            registered_producers,
+            registered_consumers,
+            registered_data_producers,
+            registered_data_consumers,
+            registered_rtp_observers,
+        ) = {
+            let entries = self.entries.lock();
+
+            (
+                entries
+                    .transports
+                    .values()
+                    .filter_map(|entry| {
+                        Some((entry.id, entry.transport.upgrade()?, entry.options.clone()))
+                    })
+                    .collect::<Vec<_>>(),
+                entries
+                    .producers
+                    .values()
+                    .filter_map(|(producer, key_frame_request_delay)| {
+                        Some((producer.upgrade()?, *key_frame_request_delay))
+                    })
+                    .collect::<Vec<_>>(),
+                entries
+                    .consumers
+                    .values()
+                    .filter_map(|(consumer, ignore_dtx)| Some((consumer.upgrade()?, *ignore_dtx)))
+                    .collect::<Vec<_>>(),
+                entries
+                    .data_producers
+                    .values()
+                    .filter_map(WeakDataProducer::upgrade)
+                    .collect::<Vec<_>>(),
+                entries
+                    .data_consumers
+                    .values()
+                    .filter_map(WeakDataConsumer::upgrade)
+                    .collect::<Vec<_>>(),
+                entries
+                    .rtp_observers
+                    .values()
+                    .filter_map(|entry| {
+                        Some((
+                            entry.id,
+                            entry.rtp_observer.upgrade()?,
+                            entry.options.clone(),
+                            entry.producer_ids.lock().clone(),
+                        ))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let mut transports = registered_transports
+            .iter()
+            .filter(|(_, transport, _)| !transport.closed())
+            .map(|(id, _, options)| TransportSnapshot {
+                id: *id,
+                options: options.clone(),
+                producers: Vec::new(),
+                consumers: Vec::new(),
+                data_producers: Vec::new(),
+                data_consumers: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let transport_indices = transports
+            .iter()
+            .enumerate()
+            .map(|(index, transport)| (transport.id, index))
+            .collect::<HashedMap<_, _>>();
+
+        let mut producer_ids = HashedSet::default();
+        for (producer, key_frame_request_delay) in &registered_producers {
+            if producer.closed() {
+                continue;
+            }
+            let Some(&index) = transport_indices.get(&producer.transport().id()) else {
+                continue;
+            };
+            producer_ids.insert(producer.id());
+            transports[index].producers.push(ProducerSnapshot {
+                id: producer.id(),
+                kind: producer.kind(),
+                rtp_parameters: producer.rtp_parameters().clone(),
+                paused: producer.paused(),
+                key_frame_request_delay: *key_frame_request_delay,
+            });
+        }
+
+        for (consumer, ignore_dtx) in &registered_consumers {
+            if consumer.closed() {
+                continue;
+            }
+            let Some(&index) = transport_indices.get(&consumer.transport().id()) else {
+                continue;
+            };
+            if !producer_ids.contains(&consumer.producer_id()) {
+                continue;
+            }
+            transports[index].consumers.push(ConsumerSnapshot {
+                id: consumer.id(),
+                producer_id: consumer.producer_id(),
+                kind: consumer.kind(),
+                r#type: consumer.r#type(),
+                rtp_parameters: consumer.rtp_parameters().clone(),
+                paused: consumer.paused(),
+                preferred_layers: consumer.preferred_layers(),
+                priority: consumer.priority(),
+                ignore_dtx: *ignore_dtx,
+            });
+        }
+
+        let mut data_producer_ids = HashedSet::default();
+        for data_producer in &registered_data_producers {
+            if data_producer.closed() {
+                continue;
+            }
+            let Some(&index) = transport_indices.get(&data_producer.transport().id()) else {
+                continue;
+            };
+            data_producer_ids.insert(data_producer.id());
+            transports[index].data_producers.push(DataProducerSnapshot {
+                id: data_producer.id(),
+                r#type: data_producer.r#type(),
+                sctp_stream_parameters: data_producer.sctp_stream_parameters(),
+                label: data_producer.label().clone(),
+                protocol: data_producer.protocol().clone(),
+                paused: data_producer.paused(),
+            });
+        }
+
+        for data_consumer in &registered_data_consumers {
+            if data_consumer.closed() {
+                continue;
+            }
+            let Some(&index) = transport_indices.get(&data_consumer.transport().id()) else {
+                continue;
+            };
+            if !data_producer_ids.contains(&data_consumer.data_producer_id()) {
+                continue;
+            }
+            transports[index].data_consumers.push(DataConsumerSnapshot {
+                id: data_consumer.id(),
+                data_producer_id: data_consumer.data_producer_id(),
+                r#type: data_consumer.r#type(),
+                sctp_stream_parameters: data_consumer.sctp_stream_parameters(),
+                paused: data_consumer.paused(),
+                subchannels: data_consumer.subchannels(),
+            });
+        }
+
+        let rtp_observers = registered_rtp_observers
+            .iter()
+            .filter(|(_, rtp_observer, _, _)| !rtp_observer.closed())
+            .map(
+                |(id, rtp_observer, options, observed_producer_ids)| RtpObserverSnapshot {
+                    id: *id,
+                    options: options.clone(),
+                    paused: rtp_observer.paused(),
+                    producer_ids: observed_producer_ids
+                        .iter()
+                        .filter(|producer_id| producer_ids.contains(producer_id))
+                        .copied()
+                        .collect(),
+                },
+            )
+            .collect();
+
+        RouterSnapshot {
+            version: ROUTER_SNAPSHOT_VERSION,
+            id,
+            media_codecs: media_codecs.to_vec(),
+            transports,
+            rtp_observers,
+        }
+    }
+}
+
+/// RTP capabilities that make consumer negotiate exactly the given RTP parameters again.
+fn consumer_rtp_capabilities(kind: MediaKind, rtp_parameters: &RtpParameters) -> RtpCapabilities {
+    RtpCapabilities {
+        codecs: rtp_parameters
+            .codecs
+            .iter()
+            .map(|codec| match codec.clone() {
+                RtpCodecParameters::Audio {
+                    mime_type,
+                    payload_type,
+                    clock_rate,
+                    channels,
+                    parameters,
+                    rtcp_feedback,
+                } => RtpCodecCapability::Audio {
+                    mime_type,
+                    preferred_payload_type: Some(payload_type),
+                    clock_rate,
+                    channels,
+                    parameters,
+                    rtcp_feedback,
+                },
+                RtpCodecParameters::Video {
+                    mime_type,
+                    payload_type,
+                    clock_rate,
+                    parameters,
+                    rtcp_feedback,
+                } => RtpCodecCapability::Video {
+                    mime_type,
+                    preferred_payload_type: Some(payload_type),
+                    clock_rate,
+                    parameters,
+                    rtcp_feedback,
+                },
+            })
+            .collect(),
+        header_extensions: rtp_parameters
+            .header_extensions
+            .iter()
+            .map(|header_extension| RtpHeaderExtension {
+                kind,
+                uri: header_extension.uri,
+                preferred_id: header_extension.id,
+                preferred_encrypt: header_extension.encrypt,
+                direction: RtpHeaderExtensionDirection::SendRecv,
+            })
+            .collect(),
+    }
+}
+
+pub(crate) async fn restore_router(
+    worker: &Worker,
+    snapshot: RouterSnapshot,
+) -> Result<RestoredRouter, RestoreRouterError> {
+    debug!("restore_router()");
+
+    if snapshot.version != ROUTER_SNAPSHOT_VERSION {
+        return Err(RestoreRouterError::UnsupportedVersion(snapshot.version));
+    }
+
+    // Check everything that can be checked upfront to avoid creating half of the router
+    for transport in &snapshot.transports {
+        if let TransportSnapshotOptions::WebRtc {
+            listen: WebRtcTransportSnapshotListen::Server { .. },
+            ..
+        } = &transport.options
+        {
+            return Err(RestoreRouterError::WebRtcServerTransport(transport.id));
+        }
+    }
+
+    let router = worker
+        .create_router(RouterOptions::new(snapshot.media_codecs))
+        .await
+        .map_err(RestoreRouterError::CreateRouter)?;
+
+    let mut restored = RestoredRouter {
+        router,
+        transports: HashedMap::default(),
+        producers: HashedMap::default(),
+        consumers: HashedMap::default(),
+        data_producers: HashedMap::default(),
+        data_consumers: HashedMap::default(),
+        rtp_observers: HashedMap::default(),
+    };
+
+    for transport in &snapshot.transports {
+        let restored_transport = restore_transport(&restored.router, transport).await?;
+        restored.transports.insert(transport.id, restored_transport);
+    }
+
+    // Entities are restored kind by kind rather than transport by transport since consumers may
+    // consume producers of transports that come later in the snapshot
+    for transport in &snapshot.transports {
+        let restored_transport = &restored.transports[&transport.id];
+        for producer in &transport.producers {
+            let mut options = ProducerOptions::new(producer.kind, producer.rtp_parameters.clone());
+            options.paused = producer.paused;
+            options.key_frame_request_delay = producer.key_frame_request_delay;
+
+            let restored_producer = restored_transport
+                .produce(options)
+                .await
+                .map_err(RestoreRouterError::Produce)?;
+            restored.producers.insert(producer.id, restored_producer);
+        }
+    }
+
+    for transport in &snapshot.transports {
+        let restored_transport = &restored.transports[&transport.id];
+        for consumer in &transport.consumers {
+            let producer_id = restored
+                .producers
+                .get(&consumer.producer_id)
+                .ok_or(RestoreRouterError::ProducerNotFound(consumer.producer_id))?
+                .id();
+            let mut options = ConsumerOptions::new(
+                producer_id,
+                consumer_rtp_capabilities(consumer.kind, &consumer.rtp_parameters),
+            );
+            options.paused = consumer.paused;
+            options.mid = consumer.rtp_parameters.mid.clone();
+            options.preferred_layers = consumer.preferred_layers;
+            options.enable_rtx = Some(
+                consumer
+                    .rtp_parameters
+                    .codecs
+                    .iter()
+                    .any(RtpCodecParameters::is_rtx),
+            );
+            options.ignore_dtx = consumer.ignore_dtx;
+            options.pipe = consumer.r#type == ConsumerType::Pipe;
+            // Keep payload types and header extension ids the remote endpoint already knows.
+            options.use_remote_ids = true;
+
+            let restored_consumer = restored_transport
+                .consume(options)
+                .await
+                .map_err(RestoreRouterError::Consume)?;
+            if restored_consumer.priority() != consumer.priority {
+                restored_consumer.set_priority(consumer.priority).await?;
+            }
+            restored.consumers.insert(consumer.id, restored_consumer);
+        }
+    }
+
+    for transport in &snapshot.transports {
+        let restored_transport = &restored.transports[&transport.id];
+        for data_producer in &transport.data_producers {
+            let mut options = match data_producer.sctp_stream_parameters {
+                Some(sctp_stream_parameters) => {
+                    DataProducerOptions::new_sctp(sctp_stream_parameters)
+                }
+                None => DataProducerOptions::new_direct(),
+            };
+            options.label = data_producer.label.clone();
+            options.protocol = data_producer.protocol.clone();
+            options.paused = data_producer.paused;
+
+            let restored_data_producer = restored_transport
+                .produce_data(options)
+                .await
+                .map_err(RestoreRouterError::ProduceData)?;
+            restored
+                .data_producers
+                .insert(data_producer.id, restored_data_producer);
+        }
+    }
+
+    for transport in &snapshot.transports {
+        let restored_transport = &restored.transports[&transport.id];
+        for data_consumer in &transport.data_consumers {
+            let data_producer_id = restored
+                .data_producers
+                .get(&data_consumer.data_producer_id)
+                .ok_or(RestoreRouterError::DataProducerNotFound(
+                    data_consumer.data_producer_id,
+                ))?
+                .id();
+            let mut options = match data_consumer.sctp_stream_parameters {
+                Some(sctp_stream_parameters) => {
+                    let mut options = DataConsumerOptions::new_sctp(data_producer_id);
+                    options.ordered = Some(sctp_stream_parameters.ordered());
+                    options.max_packet_life_time = sctp_stream_parameters.max_packet_life_time();
+                    options.max_retransmits = sctp_stream_parameters.max_retransmits();
+                    options
+                }
+                None => DataConsumerOptions::new_direct(data_producer_id, None),
+            };
+            options.paused = data_consumer.paused;
+            options.subchannels = Some(data_consumer.subchannels.clone());
+
+            let restored_data_consumer = restored_transport
+                .consume_data(options)
+                .await
+                .map_err(RestoreRouterError::ConsumeData)?;
+            restored
+                .data_consumers
+                .insert(data_consumer.id, restored_data_consumer);
+        }
+    }
+
+    for rtp_observer in &snapshot.rtp_observers {
+        let restored_rtp_observer = match &rtp_observer.options {
+            RtpObserverSnapshotOptions::AudioLevel {
+                max_entries,
+                threshold,
+                interval,
+            } => {
+                let options = AudioLevelObserverOptions {
+                    max_entries: *max_entries,
+                    threshold: *threshold,
+                    interval: *interval,
+                    ..AudioLevelObserverOptions::default()
+                };
+
+                RestoredRtpObserver::AudioLevel(
+                    restored.router.create_audio_level_observer(options).await?,
+                )
+            }
+            RtpObserverSnapshotOptions::ActiveSpeaker { interval } => {
+                let options = ActiveSpeakerObserverOptions {
+                    interval: *interval,
+                    ..ActiveSpeakerObserverOptions::default()
+                };
+
+                RestoredRtpObserver::ActiveSpeaker(
+                    restored
+                        .router
+                        .create_active_speaker_observer(options)
+                        .await?,
+                )
+            }
+        };
+
+        for producer_id in &rtp_observer.producer_ids {
+            let producer_id = restored
+                .producers
+                .get(producer_id)
+                .ok_or(RestoreRouterError::ProducerNotFound(*producer_id))?
+                .id();
+            restored_rtp_observer
+                .add_producer(RtpObserverAddProducerOptions::new(producer_id))
+                .await?;
+        }
+        if rtp_observer.paused {
+            restored_rtp_observer.pause().await?;
+        }
+
+        restored
+            .rtp_observers
+            .insert(rtp_observer.id, restored_rtp_observer);
+    }
+
+    Ok(restored)
+}
+
+async fn restore_transport(
+    router: &Router,
+    transport: &TransportSnapshot,
+) -> Result<RestoredTransport, RestoreRouterError> {
+    Ok(match transport.options.clone() {
+        TransportSnapshotOptions::WebRtc {
+            listen,
+            initial_available_outgoing_bitrate,
+            enable_udp,
+            enable_tcp,
+            prefer_udp,
+            prefer_tcp,
+            enable_sctp,
+            num_sctp_streams,
+            max_sctp_message_size,
+            sctp_send_buffer_size,
+        } => {
+            let listen_infos = match listen {
+                WebRtcTransportSnapshotListen::Individual { listen_infos } => listen_infos,
+                WebRtcTransportSnapshotListen::Server { .. } => {
+                    return Err(RestoreRouterError::WebRtcServerTransport(transport.id));
+                }
+            };
+            let mut options = WebRtcTransportOptions::new(listen_infos);
+            options.initial_available_outgoing_bitrate = initial_available_outgoing_bitrate;
+            options.enable_udp = enable_udp;
+            options.enable_tcp = enable_tcp;
+            options.prefer_udp = prefer_udp;
+            options.prefer_tcp = prefer_tcp;
+            options.enable_sctp = enable_sctp;
+            options.num_sctp_streams = num_sctp_streams;
+            options.max_sctp_message_size = max_sctp_message_size;
+            options.sctp_send_buffer_size = sctp_send_buffer_size;
+
+            RestoredTransport::WebRtc(router.create_webrtc_transport(options).await?)
+        }
+        TransportSnapshotOptions::Plain {
+            listen_info,
+            rtcp_listen_info,
+            rtcp_mux,
+            comedia,
+            enable_sctp,
+            num_sctp_streams,
+            max_sctp_message_size,
+            sctp_send_buffer_size,
+            enable_srtp,
+            srtp_crypto_suite,
+        } => {
+            let mut options = PlainTransportOptions::new(listen_info);
+            options.rtcp_listen_info = rtcp_listen_info;
+            options.rtcp_mux = rtcp_mux;
+            options.comedia = comedia;
+            options.enable_sctp = enable_sctp;
+            options.num_sctp_streams = num_sctp_streams;
+            options.max_sctp_message_size = max_sctp_message_size;
+            options.sctp_send_buffer_size = sctp_send_buffer_size;
+            options.enable_srtp = enable_srtp;
+            options.srtp_crypto_suite = srtp_crypto_suite;
+
+            RestoredTransport::Plain(router.create_plain_transport(options).await?)
+        }
+        TransportSnapshotOptions::Pipe {
+            listen_info,
+            enable_sctp,
+            num_sctp_streams,
+            max_sctp_message_size,
+            sctp_send_buffer_size,
+            enable_rtx,
+            enable_srtp,
+        } => {
+            let mut options = PipeTransportOptions::new(listen_info);
+            options.enable_sctp = enable_sctp;
+            options.num_sctp_streams = num_sctp_streams;
+            options.max_sctp_message_size = max_sctp_message_size;
+            options.sctp_send_buffer_size = sctp_send_buffer_size;
+            options.enable_rtx = enable_rtx;
+            options.enable_srtp = enable_srtp;
+
+            RestoredTransport::Pipe(router.create_pipe_transport(options).await?)
+        }
+        TransportSnapshotOptions::Direct { max_message_size } => {
+            let options = DirectTransportOptions {
+                max_message_size,
+                ..DirectTransportOptions::default()
+            };
+
+            RestoredTransport::Direct(router.create_direct_transport(options).await?)
+        }
+    })
+}
@@ -0,0 +1,492 @@
+use super::*;
+use crate::data_structures::Protocol;
+use crate::rtp_parameters::{
+    MimeTypeAudio, MimeTypeVideo, RtcpFeedback, RtpCodecParametersParameters,
+    RtpHeaderExtensionParameters, RtpHeaderExtensionUri,
+};
+use crate::worker::WorkerSettings;
+use crate::worker_manager::WorkerManager;
+use futures_lite::future;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    ]
+}
+
+fn listen_info() -> ListenInfo {
+    ListenInfo {
+        protocol: Protocol::Udp,
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        announced_ip: None,
+        port: None,
+        send_buffer_size: None,
+        recv_buffer_size: None,
+    }
+}
+
+fn audio_producer_options() -> ProducerOptions {
+    ProducerOptions::new(
+        MediaKind::Audio,
+        RtpParameters {
+            mid: Some("AUDIO".to_string()),
+            codecs: vec![RtpCodecParameters::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                payload_type: 111,
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }],
+            ..RtpParameters::default()
+        },
+    )
+}
+
+fn video_producer_options() -> ProducerOptions {
+    ProducerOptions::new(
+        MediaKind::Video,
+        RtpParameters {
+            mid: Some("VIDEO".to_string()),
+            codecs: vec![RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                payload_type: 112,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+            }],
+            ..RtpParameters::default()
+        },
+    )
+}
+
+fn consumer_device_capabilities() -> RtpCapabilities {
+    RtpCapabilities {
+        codecs: vec![RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: Some(101),
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+        }],
+        header_extensions: vec![],
+    }
+}
+
+async fn init() -> (Worker, Router) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker = WorkerManager::new()
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    (worker, router)
+}
+
+#[test]
+fn consumer_rtp_capabilities_reproduce_parameters() {
+    let rtp_parameters = RtpParameters {
+        codecs: vec![
+            RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                payload_type: 101,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![RtcpFeedback::Nack],
+            },
+            RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Rtx,
+                payload_type: 102,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::from([("apt", 101_u32.into())]),
+                rtcp_feedback: vec![],
+            },
+        ],
+        header_extensions: vec![RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::AbsSendTime,
+            id: 4,
+            encrypt: false,
+        }],
+        ..RtpParameters::default()
+    };
+
+    let rtp_capabilities = consumer_rtp_capabilities(MediaKind::Video, &rtp_parameters);
+
+    assert_eq!(
+        rtp_capabilities.codecs,
+        vec![
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                preferred_payload_type: Some(101),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![RtcpFeedback::Nack],
+            },
+            RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Rtx,
+                preferred_payload_type: Some(102),
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::from([("apt", 101_u32.into())]),
+                rtcp_feedback: vec![],
+            },
+        ]
+    );
+    assert_eq!(
+        rtp_capabilities.header_extensions,
+        vec![RtpHeaderExtension {
+            kind: MediaKind::Video,
+            uri: RtpHeaderExtensionUri::AbsSendTime,
+            preferred_id: 4,
+            preferred_encrypt: false,
+            direction: RtpHeaderExtensionDirection::SendRecv,
+        }]
+    );
+}
+
+#[test]
+fn snapshot_serialization_round_trip() {
+    let snapshot = RouterSnapshot {
+        version: ROUTER_SNAPSHOT_VERSION,
+        id: RouterId::new(),
+        media_codecs: media_codecs(),
+        transports: vec![
+            TransportSnapshot {
+                id: TransportId::new(),
+                options: TransportSnapshotOptions::webrtc(&WebRtcTransportOptions::new(
+                    WebRtcTransportListenInfos::new(listen_info()),
+                )),
+                producers: vec![ProducerSnapshot {
+                    id: ProducerId::new(),
+                    kind: MediaKind::Audio,
+                    rtp_parameters: audio_producer_options().rtp_parameters,
+                    paused: true,
+                    key_frame_request_delay: 0,
+                }],
+                consumers: vec![],
+                data_producers: vec![],
+                data_consumers: vec![],
+            },
+            TransportSnapshot {
+                id: TransportId::new(),
+                options: TransportSnapshotOptions::direct(&DirectTransportOptions::default()),
+                producers: vec![],
+                consumers: vec![],
+                data_producers: vec![DataProducerSnapshot {
+                    id: DataProducerId::new(),
+                    r#type: DataProducerType::Direct,
+                    sctp_stream_parameters: None,
+                    label: "chat".to_string(),
+                    protocol: String::new(),
+                    paused: false,
+                }],
+                data_consumers: vec![],
+            },
+        ],
+        rtp_observers: vec![RtpObserverSnapshot {
+            id: RtpObserverId::new(),
+            options: RtpObserverSnapshotOptions::audio_level(&AudioLevelObserverOptions::default()),
+            paused: false,
+            producer_ids: vec![],
+        }],
+    };
+
+    let value = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(value["version"], ROUTER_SNAPSHOT_VERSION);
+    assert_eq!(value["transports"][0]["options"]["type"], "webRtc");
+    assert_eq!(
+        value["transports"][0]["options"]["listen"]["type"],
+        "individual"
+    );
+    assert_eq!(value["transports"][1]["options"]["type"], "direct");
+    assert_eq!(value["rtpObservers"][0]["options"]["type"], "audioLevel");
+
+    assert_eq!(
+        serde_json::from_value::<RouterSnapshot>(value).unwrap(),
+        snapshot
+    );
+}
+
+#[test]
+fn snapshot_rejects_empty_listen_infos() {
+    let value = serde_json::json!({
+        "type": "individual",
+        "listenInfos": [],
+    });
+
+    assert!(serde_json::from_value::<WebRtcTransportSnapshotListen>(value).is_err());
+}
+
+#[test]
+fn snapshot_and_restore_succeeds() {
+    future::block_on(async move {
+        let (worker, router) = init().await;
+
+        let transport1 = router
+            .create_webrtc_transport(WebRtcTransportOptions::new(
+                WebRtcTransportListenInfos::new(listen_info()),
+            ))
+            .await
+            .expect("Failed to create transport1");
+        let transport2 = router
+            .create_webrtc_transport(WebRtcTransportOptions::new(
+                WebRtcTransportListenInfos::new(listen_info()),
+            ))
+            .await
+            .expect("Failed to create transport2");
+        let direct_transport = router
+            .create_direct_transport(DirectTransportOptions::default())
+            .await
+            .expect("Failed to create direct transport");
+
+        let audio_producer = transport1
+            .produce(audio_producer_options())
+            .await
+            .expect("Failed to produce audio");
+        let video_producer = transport1
+            .produce({
+                let mut options = video_producer_options();
+                options.paused = true;
+                options
+            })
+            .await
+            .expect("Failed to produce video");
+
+        let video_consumer = transport2
+            .consume(ConsumerOptions::new(
+                video_producer.id(),
+                consumer_device_capabilities(),
+            ))
+            .await
+            .expect("Failed to consume video");
+        video_consumer
+            .set_priority(2)
+            .await
+            .expect("Failed to set priority");
+
+        let data_producer = direct_transport
+            .produce_data({
+                let mut options = DataProducerOptions::new_direct();
+                options.label = "chat".to_string();
+                options
+            })
+            .await
+            .expect("Failed to produce data");
+        let data_consumer = direct_transport
+            .consume_data(DataConsumerOptions::new_direct(
+                data_producer.id(),
+                Some(vec![1, 2]),
+            ))
+            .await
+            .expect("Failed to consume data");
+
+        let audio_level_observer = router
+            .create_audio_level_observer(AudioLevelObserverOptions::default())
+            .await
+            .expect("Failed to create audio level observer");
+        audio_level_observer
+            .add_producer(RtpObserverAddProducerOptions::new(audio_producer.id()))
+            .await
+            .expect("Failed to add producer");
+        audio_level_observer
+            .pause()
+            .await
+            .expect("Failed to pause observer");
+
+        // Closed entities are not part of the snapshot
+        let closed_transport = router
+            .create_direct_transport(DirectTransportOptions::default())
+            .await
+            .expect("Failed to create direct transport");
+        drop(closed_transport);
+
+        let snapshot = router.snapshot();
+
+        assert_eq!(snapshot.version, ROUTER_SNAPSHOT_VERSION);
+        assert_eq!(snapshot.id, router.id());
+        assert_eq!(snapshot.media_codecs, media_codecs());
+        assert_eq!(
+            snapshot
+                .transports
+                .iter()
+                .map(|transport| transport.id)
+                .collect::<Vec<_>>(),
+            vec![transport1.id(), transport2.id(), direct_transport.id()]
+        );
+        assert_eq!(
+            snapshot.transports[0]
+                .producers
+                .iter()
+                .map(|producer| (producer.id, producer.paused))
+                .collect::<Vec<_>>(),
+            vec![(audio_producer.id(), false), (video_producer.id(), true)]
+        );
+        assert_eq!(snapshot.transports[1].consumers.len(), 1);
+        assert_eq!(snapshot.transports[1].consumers[0].id, video_consumer.id());
+        assert_eq!(snapshot.transports[1].consumers[0].priority, 2);
+        assert_eq!(
+            snapshot.transports[2].data_consumers[0].subchannels,
+            vec![1, 2]
+        );
+        assert_eq!(snapshot.rtp_observers.len(), 1);
+        assert!(snapshot.rtp_observers[0].paused);
+        assert_eq!(
+            snapshot.rtp_observers[0].producer_ids,
+            vec![audio_producer.id()]
+        );
+
+        let snapshot = serde_json::from_str::<RouterSnapshot>(
+            &serde_json::to_string(&snapshot).expect("Failed to serialize snapshot"),
+        )
+        .expect("Failed to deserialize snapshot");
+
+        let restored = worker
+            .restore_router(snapshot.clone())
+            .await
+            .expect("Failed to restore router");
+
+        assert_ne!(restored.router.id(), router.id());
+        assert_eq!(restored.transports.len(), 3);
+
+        let restored_video_producer = &restored.producers[&video_producer.id()];
+        assert_ne!(restored_video_producer.id(), video_producer.id());
+        assert!(restored_video_producer.paused());
+        assert_eq!(
+            restored_video_producer.rtp_parameters(),
+            video_producer.rtp_parameters()
+        );
+
+        let restored_video_consumer = &restored.consumers[&video_consumer.id()];
+        assert_eq!(
+            restored_video_consumer.producer_id(),
+            restored_video_producer.id()
+        );
+        assert_eq!(restored_video_consumer.priority(), 2);
+        assert_eq!(
+            restored_video_consumer.rtp_parameters().codecs,
+            video_consumer.rtp_parameters().codecs
+        );
+        assert_eq!(
+            restored_video_consumer.rtp_parameters().mid,
+            video_consumer.rtp_parameters().mid
+        );
+
+        let restored_data_consumer = &restored.data_consumers[&data_consumer.id()];
+        assert_eq!(
+            restored_data_consumer.data_producer_id(),
+            restored.data_producers[&data_producer.id()].id()
+        );
+        assert_eq!(restored_data_consumer.subchannels(), vec![1, 2]);
+
+        let restored_observer = &restored.rtp_observers[&audio_level_observer.id()];
+        assert!(restored_observer.paused());
+
+        // Snapshot of the restored router describes the same topology
+        let restored_snapshot = restored.router.snapshot();
+        assert_eq!(
+            restored_snapshot.transports.len(),
+            snapshot.transports.len()
+        );
+        assert_eq!(
+            restored_snapshot.rtp_observers[0].producer_ids,
+            vec![restored.producers[&audio_producer.id()].id()]
+        );
+    });
+}
+
+#[test]
+fn restore_rejects_unsupported_version() {
+    future::block_on(async move {
+        let (worker, router) = init().await;
+
+        let mut snapshot = router.snapshot();
+        snapshot.version = ROUTER_SNAPSHOT_VERSION + 1;
+
+        assert!(matches!(
+            worker.restore_router(snapshot).await,
+            Err(RestoreRouterError::UnsupportedVersion(version))
+                if version == ROUTER_SNAPSHOT_VERSION + 1,
+        ));
+    });
+}
+
+#[test]
+fn registry_forgets_closed_entities() {
+    future::block_on(async move {
+        let (_worker, router) = init().await;
+
+        let transport = router
+            .create_webrtc_transport(WebRtcTransportOptions::new(
+                WebRtcTransportListenInfos::new(listen_info()),
+            ))
+            .await
+            .expect("Failed to create transport");
+        let producer = transport
+            .produce(video_producer_options())
+            .await
+            .expect("Failed to produce video");
+        let consumer = transport
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                consumer_device_capabilities(),
+            ))
+            .await
+            .expect("Failed to consume video");
+        let audio_level_observer = router
+            .create_audio_level_observer(AudioLevelObserverOptions::default())
+            .await
+            .expect("Failed to create audio level observer");
+
+        let entry_counts = || {
+            let entries = router.snapshot_registry().entries.lock();
+            [
+                entries.transports.len(),
+                entries.producers.len(),
+                entries.consumers.len(),
+                entries.rtp_observers.len(),
+            ]
+        };
+
+        assert_eq!(entry_counts(), [1, 1, 1, 1]);
+
+        drop(consumer);
+        assert_eq!(entry_counts(), [1, 1, 0, 1]);
+
+        drop(audio_level_observer);
+        assert_eq!(entry_counts(), [1, 1, 0, 0]);
+
+        drop(producer);
+        assert_eq!(entry_counts(), [1, 0, 0, 0]);
+
+        drop(transport);
+        assert_eq!(entry_counts(), [0, 0, 0, 0]);
+    });
+}
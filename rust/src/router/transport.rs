@@ -1,7 +1,12 @@
+#[cfg(test)]
+mod tests;
+
 use crate::consumer::{Consumer, ConsumerId, ConsumerOptions, ConsumerType};
 use crate::data_consumer::{DataConsumer, DataConsumerId, DataConsumerOptions, DataConsumerType};
 use crate::data_producer::{DataProducer, DataProducerId, DataProducerOptions, DataProducerType};
-use crate::data_structures::{AppData, BweTraceInfo, RtpPacketTraceInfo, TraceEventDirection};
+use crate::data_structures::{AppData, BweTraceInfo, TraceEventDirection, TraceEventError};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     TransportConsumeDataRequest, TransportConsumeRequest, TransportDumpRequest,
     TransportEnableTraceEventRequest, TransportGetStatsRequest, TransportProduceDataRequest,
@@ -17,7 +22,6 @@ use crate::rtp_parameters::{MediaKind, RtpEncodingParameters};
 use crate::sctp_parameters::SctpStreamParameters;
 use crate::worker::{Channel, RequestError};
 use crate::{ortc, uuid_based_wrapper_type};
-use async_executor::Executor;
 use async_trait::async_trait;
 use event_listener_primitives::HandlerId;
 use log::{error, warn};
@@ -50,8 +54,6 @@ pub enum TransportTraceEventData {
         timestamp: u64,
         /// Event direction.
         direction: TraceEventDirection,
-        /// RTP packet info.
-        info: RtpPacketTraceInfo,
     },
     /// Transport bandwidth estimation changed.
     Bwe {
@@ -65,21 +67,31 @@ pub enum TransportTraceEventData {
 }
 
 impl TransportTraceEventData {
-    pub(crate) fn from_fbs(data: transport::TraceNotification) -> Self {
-        match data.type_ {
-            transport::TraceEventType::Probation => unimplemented!(),
+    pub(crate) fn from_fbs(data: transport::TraceNotification) -> Result<Self, TraceEventError> {
+        Ok(match data.type_ {
+            transport::TraceEventType::Probation => {
+                // Worker doesn't attach any info to probation events
+                if data.info.is_some() {
+                    return Err(TraceEventError::unexpected_info(data.type_));
+                }
+
+                TransportTraceEventData::Probation {
+                    timestamp: data.timestamp,
+                    direction: TraceEventDirection::from_fbs(data.direction),
+                }
+            }
             transport::TraceEventType::Bwe => TransportTraceEventData::Bwe {
                 timestamp: data.timestamp,
                 direction: TraceEventDirection::from_fbs(data.direction),
                 info: {
                     let Some(transport::TraceInfo::BweTraceInfo(info)) = data.info else {
-                        panic!("Wrong message from worker: {data:?}");
+                        return Err(TraceEventError::unexpected_info(data.type_));
                     };
 
                     BweTraceInfo::from_fbs(*info)
                 },
             },
-        }
+        })
     }
 }
 
@@ -122,23 +134,25 @@ pub struct RtpListener {
 }
 
 impl RtpListener {
-    pub(crate) fn from_fbs(rtp_listener: &transport::RtpListener) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        rtp_listener: &transport::RtpListener,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             mid_table: rtp_listener
                 .mid_table
                 .iter()
                 .map(|key_value| Ok((key_value.key.to_string(), key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             rid_table: rtp_listener
                 .rid_table
                 .iter()
                 .map(|key_value| Ok((key_value.key.to_string(), key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             ssrc_table: rtp_listener
                 .ssrc_table
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
         })
     }
 }
@@ -175,13 +189,15 @@ pub struct SctpListener {
 }
 
 impl SctpListener {
-    pub(crate) fn from_fbs(listener: &transport::SctpListener) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        listener: &transport::SctpListener,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             stream_id_table: listener
                 .stream_id_table
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
         })
     }
 }
@@ -413,7 +429,7 @@ pub enum ConsumeDataError {
 pub(super) trait TransportImpl: TransportGeneric {
     fn channel(&self) -> &Channel;
 
-    fn executor(&self) -> &Arc<Executor<'static>>;
+    fn executor(&self) -> &Arc<dyn Executor>;
 
     fn next_mid_for_consumers(&self) -> &AtomicUsize;
 
@@ -546,9 +562,15 @@ pub(super) trait TransportImpl: TransportGeneric {
 
         let _buffer_guard = self.channel().buffer_messages_for(producer_id.into());
 
-        let response = self
-            .channel()
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::producer",
+            "create_producer",
+            %producer_id,
+            transport_id = %self.id(),
+        );
+        let response = instrument!(
+            self.channel().request(
                 self.id(),
                 TransportProduceRequest {
                     producer_id,
@@ -558,9 +580,11 @@ pub(super) trait TransportImpl: TransportGeneric {
                     key_frame_request_delay,
                     paused,
                 },
-            )
-            .await
-            .map_err(ProduceError::Request)?;
+            ),
+            span,
+        )
+        .await
+        .map_err(ProduceError::Request)?;
 
         let producer_fut = Producer::new(
             producer_id,
@@ -575,8 +599,13 @@ pub(super) trait TransportImpl: TransportGeneric {
             Arc::new(self.clone()),
             transport_type == TransportType::Direct,
         );
+        let producer = producer_fut.await;
 
-        Ok(producer_fut.await)
+        self.router()
+            .snapshot_registry()
+            .add_producer(&producer, key_frame_request_delay);
+
+        Ok(producer)
     }
 
     async fn consume_impl(
@@ -594,6 +623,8 @@ pub(super) trait TransportImpl: TransportGeneric {
             enable_rtx,
             ignore_dtx,
             pipe,
+            codec_preferences,
+            use_remote_ids,
             app_data,
         } = consumer_options;
         ortc::validate_rtp_capabilities(&rtp_capabilities)
@@ -616,6 +647,8 @@ pub(super) trait TransportImpl: TransportGeneric {
                 &rtp_capabilities,
                 pipe,
                 enable_rtx,
+                &codec_preferences,
+                use_remote_ids && !pipe,
             )
             .map_err(ConsumeError::BadConsumerRtpParameters)?;
 
@@ -634,6 +667,11 @@ pub(super) trait TransportImpl: TransportGeneric {
             rtp_parameters
         };
 
+        let payload_type_mapping = ortc::get_consumer_payload_type_mapping(
+            producer.consumable_rtp_parameters(),
+            &rtp_parameters.codecs,
+        );
+
         let consumer_id = ConsumerId::new();
 
         let r#type = if transport_type == TransportType::Pipe || pipe {
@@ -644,9 +682,15 @@ pub(super) trait TransportImpl: TransportGeneric {
 
         let _buffer_guard = self.channel().buffer_messages_for(consumer_id.into());
 
-        let response = self
-            .channel()
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::consumer",
+            "create_consumer",
+            %consumer_id,
+            transport_id = %self.id(),
+        );
+        let response = instrument!(
+            self.channel().request(
                 self.id(),
                 TransportConsumeRequest {
                     consumer_id,
@@ -661,12 +705,15 @@ pub(super) trait TransportImpl: TransportGeneric {
                     paused,
                     preferred_layers,
                     ignore_dtx,
+                    payload_type_mapping,
                 },
-            )
-            .await
-            .map_err(ConsumeError::Request)?;
+            ),
+            span,
+        )
+        .await
+        .map_err(ConsumeError::Request)?;
 
-        Ok(Consumer::new(
+        let consumer = Consumer::new(
             consumer_id,
             producer,
             r#type,
@@ -679,7 +726,13 @@ pub(super) trait TransportImpl: TransportGeneric {
             response.preferred_layers,
             app_data,
             Arc::new(self.clone()),
-        ))
+        );
+
+        self.router()
+            .snapshot_registry()
+            .add_consumer(&consumer, ignore_dtx);
+
+        Ok(consumer)
     }
 
     async fn produce_data_impl(
@@ -722,9 +775,15 @@ pub(super) trait TransportImpl: TransportGeneric {
 
         let _buffer_guard = self.channel().buffer_messages_for(data_producer_id.into());
 
-        let response = self
-            .channel()
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::data_producer",
+            "create_data_producer",
+            %data_producer_id,
+            transport_id = %self.id(),
+        );
+        let response = instrument!(
+            self.channel().request(
                 self.id(),
                 TransportProduceDataRequest {
                     data_producer_id,
@@ -734,11 +793,13 @@ pub(super) trait TransportImpl: TransportGeneric {
                     protocol,
                     paused,
                 },
-            )
-            .await
-            .map_err(ProduceDataError::Request)?;
+            ),
+            span,
+        )
+        .await
+        .map_err(ProduceDataError::Request)?;
 
-        Ok(DataProducer::new(
+        let data_producer = DataProducer::new(
             data_producer_id,
             response.r#type,
             response.sctp_stream_parameters,
@@ -750,7 +811,13 @@ pub(super) trait TransportImpl: TransportGeneric {
             app_data,
             Arc::new(self.clone()),
             transport_type == TransportType::Direct,
-        ))
+        );
+
+        self.router()
+            .snapshot_registry()
+            .add_data_producer(&data_producer);
+
+        Ok(data_producer)
     }
 
     async fn consume_data_impl(
@@ -819,9 +886,15 @@ pub(super) trait TransportImpl: TransportGeneric {
 
         let _buffer_guard = self.channel().buffer_messages_for(data_consumer_id.into());
 
-        let response = self
-            .channel()
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::data_consumer",
+            "create_data_consumer",
+            %data_consumer_id,
+            transport_id = %self.id(),
+        );
+        let response = instrument!(
+            self.channel().request(
                 self.id(),
                 TransportConsumeDataRequest {
                     data_consumer_id,
@@ -833,9 +906,11 @@ pub(super) trait TransportImpl: TransportGeneric {
                     subchannels,
                     paused,
                 },
-            )
-            .await
-            .map_err(ConsumeDataError::Request)?;
+            ),
+            span,
+        )
+        .await
+        .map_err(ConsumeDataError::Request)?;
 
         let data_consumer = DataConsumer::new(
             data_consumer_id,
@@ -864,6 +939,10 @@ pub(super) trait TransportImpl: TransportGeneric {
                 .detach();
         }
 
+        self.router()
+            .snapshot_registry()
+            .add_data_consumer(&data_consumer);
+
         Ok(data_consumer)
     }
 }
@@ -0,0 +1,61 @@
+use super::*;
+use crate::data_structures::BweType;
+use mediasoup_sys::fbs::common;
+
+#[test]
+fn probation_trace_event_decoded() {
+    let trace_event_data = TransportTraceEventData::from_fbs(transport::TraceNotification {
+        type_: transport::TraceEventType::Probation,
+        timestamp: 1234,
+        direction: common::TraceDirection::DirectionOut,
+        info: None,
+    })
+    .expect("Failed to decode probation trace event");
+
+    assert!(matches!(
+        trace_event_data,
+        TransportTraceEventData::Probation {
+            timestamp: 1234,
+            direction: TraceEventDirection::Out,
+        }
+    ));
+}
+
+#[test]
+fn bwe_trace_event_decoded() {
+    let trace_event_data = TransportTraceEventData::from_fbs(transport::TraceNotification {
+        type_: transport::TraceEventType::Bwe,
+        timestamp: 1234,
+        direction: common::TraceDirection::DirectionOut,
+        info: Some(transport::TraceInfo::BweTraceInfo(Box::new(
+            transport::BweTraceInfo {
+                bwe_type: transport::BweType::Remb,
+                available_bitrate: 300_000,
+                ..transport::BweTraceInfo::default()
+            },
+        ))),
+    })
+    .expect("Failed to decode BWE trace event");
+
+    let TransportTraceEventData::Bwe { info, .. } = trace_event_data else {
+        panic!("Unexpected trace event: {trace_event_data:?}");
+    };
+    assert!(matches!(info.r#type, BweType::Remb));
+    assert_eq!(info.available_bitrate, 300_000);
+}
+
+#[test]
+fn malformed_trace_event_is_error() {
+    assert_eq!(
+        TransportTraceEventData::from_fbs(transport::TraceNotification {
+            type_: transport::TraceEventType::Bwe,
+            timestamp: 1234,
+            direction: common::TraceDirection::DirectionIn,
+            info: None,
+        })
+        .unwrap_err(),
+        TraceEventError::UnexpectedInfo {
+            event_type: "Bwe".to_string(),
+        },
+    );
+}
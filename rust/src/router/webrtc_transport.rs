@@ -6,8 +6,11 @@ use crate::data_consumer::{DataConsumer, DataConsumerId, DataConsumerOptions, Da
 use crate::data_producer::{DataProducer, DataProducerId, DataProducerOptions, DataProducerType};
 use crate::data_structures::{
     AppData, DtlsParameters, DtlsState, IceCandidate, IceParameters, IceRole, IceState, ListenInfo,
-    SctpState, TransportTuple,
+    SctpState, TraceEventDirection, TraceEventError, TransportTuple,
 };
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     TransportCloseRequest, TransportRestartIceRequest, WebRtcTransportConnectRequest,
     WebRtcTransportData,
@@ -23,7 +26,6 @@ use crate::transport::{
 };
 use crate::webrtc_server::WebRtcServer;
 use crate::worker::{Channel, NotificationParseError, RequestError, SubscriptionHandler};
-use async_executor::Executor;
 use async_trait::async_trait;
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use log::{debug, error};
@@ -40,7 +42,8 @@ use std::sync::{Arc, Weak};
 use thiserror::Error;
 
 /// Struct that protects an invariant of having non-empty list of listen IPs
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "Vec<ListenInfo>")]
 pub struct WebRtcTransportListenInfos(Vec<ListenInfo>);
 
 impl WebRtcTransportListenInfos {
@@ -213,7 +216,9 @@ pub struct WebRtcTransportDump {
 }
 
 impl WebRtcTransportDump {
-    pub(crate) fn from_fbs(dump: web_rtc_transport::DumpResponse) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn from_fbs(
+        dump: web_rtc_transport::DumpResponse,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             // Common to all Transports.
             id: dump.base.id.parse()?,
@@ -223,37 +228,37 @@ impl WebRtcTransportDump {
                 .producer_ids
                 .iter()
                 .map(|producer_id| Ok(producer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             consumer_ids: dump
                 .base
                 .consumer_ids
                 .iter()
                 .map(|consumer_id| Ok(consumer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_ssrc_consumer_id: dump
                 .base
                 .map_ssrc_consumer_id
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             map_rtx_ssrc_consumer_id: dump
                 .base
                 .map_rtx_ssrc_consumer_id
                 .iter()
                 .map(|key_value| Ok((key_value.key, key_value.value.parse()?)))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             data_producer_ids: dump
                 .base
                 .data_producer_ids
                 .iter()
                 .map(|data_producer_id| Ok(data_producer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             data_consumer_ids: dump
                 .base
                 .data_consumer_ids
                 .iter()
                 .map(|data_consumer_id| Ok(data_consumer_id.parse()?))
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             recv_rtp_header_extensions: RecvRtpHeaderExtensions::from_fbs(
                 dump.base.recv_rtp_header_extensions.as_ref(),
             ),
@@ -342,7 +347,7 @@ pub struct WebRtcTransportStat {
 impl WebRtcTransportStat {
     pub(crate) fn from_fbs(
         stats: web_rtc_transport::GetStatsResponse,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             transport_id: stats.base.transport_id.parse()?,
             timestamp: stats.base.timestamp,
@@ -386,6 +391,52 @@ pub struct WebRtcTransportRemoteParameters {
     pub dtls_parameters: DtlsParameters,
 }
 
+/// Event of [`WebRtcTransport::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum WebRtcTransportEvent {
+    /// New producer was created on this transport.
+    NewProducer {
+        /// Producer id.
+        producer_id: ProducerId,
+    },
+    /// New consumer was created on this transport.
+    NewConsumer {
+        /// Consumer id.
+        consumer_id: ConsumerId,
+    },
+    /// New data producer was created on this transport.
+    NewDataProducer {
+        /// Data producer id.
+        data_producer_id: DataProducerId,
+    },
+    /// New data consumer was created on this transport.
+    NewDataConsumer {
+        /// Data consumer id.
+        data_consumer_id: DataConsumerId,
+    },
+    /// ICE state changed.
+    IceStateChange(IceState),
+    /// ICE selected tuple changed.
+    IceSelectedTupleChange(TransportTuple),
+    /// DTLS state changed.
+    DtlsStateChange(DtlsState),
+    /// SCTP state changed.
+    SctpStateChange(SctpState),
+    /// Trace event, see [`Transport::enable_trace_event()`].
+    Trace(TransportTraceEventData),
+    /// Trace event couldn't be decoded, see [`WebRtcTransport::on_trace_error()`].
+    TraceError(TraceEventError),
+    /// Available outgoing bitrate changed, see [`WebRtcTransport::on_available_bitrate()`].
+    AvailableBitrate(u32),
+    /// Router this transport belongs to was closed.
+    RouterClose,
+    /// WebRTC server this transport uses was closed.
+    WebRtcServerClose,
+    /// Transport was closed, this is the last event in the stream.
+    Close,
+}
+
 #[derive(Default)]
 #[allow(clippy::type_complexity)]
 struct Handlers {
@@ -397,7 +448,9 @@ struct Handlers {
     ice_selected_tuple_change: Bag<Arc<dyn Fn(&TransportTuple) + Send + Sync>, TransportTuple>,
     dtls_state_change: Bag<Arc<dyn Fn(DtlsState) + Send + Sync>>,
     sctp_state_change: Bag<Arc<dyn Fn(SctpState) + Send + Sync>>,
+    available_bitrate: Bag<Arc<dyn Fn(u32) + Send + Sync>>,
     trace: Bag<Arc<dyn Fn(&TransportTraceEventData) + Send + Sync>, TransportTraceEventData>,
+    trace_error: Bag<Arc<dyn Fn(&TraceEventError) + Send + Sync>, TraceEventError>,
     router_close: BagOnce<Box<dyn FnOnce() + Send>>,
     webrtc_server_close: BagOnce<Box<dyn FnOnce() + Send>>,
     close: BagOnce<Box<dyn FnOnce() + Send>>,
@@ -424,6 +477,8 @@ enum Notification {
         sctp_state: SctpState,
     },
     Trace(TransportTraceEventData),
+    #[serde(skip)]
+    TraceError(TraceEventError),
 }
 
 impl Notification {
@@ -484,16 +539,19 @@ impl Notification {
                 Ok(Notification::SctpStateChange { sctp_state })
             }
             notification::Event::TransportTrace => {
-                let Ok(Some(notification::BodyRef::TransportTraceNotification(body))) =
-                    notification.body()
-                else {
-                    panic!("Wrong message from worker: {notification:?}");
+                let trace_notification = match notification.body() {
+                    Ok(Some(notification::BodyRef::TransportTraceNotification(body))) => {
+                        transport::TraceNotification::try_from(body)
+                            .map_err(|error| TraceEventError::Decode(error.to_string()))
+                            .and_then(TransportTraceEventData::from_fbs)
+                    }
+                    _ => Err(TraceEventError::UnexpectedBody),
                 };
 
-                let trace_notification_fbs = transport::TraceNotification::try_from(body).unwrap();
-                let trace_notification = TransportTraceEventData::from_fbs(trace_notification_fbs);
-
-                Ok(Notification::Trace(trace_notification))
+                Ok(match trace_notification {
+                    Ok(trace_notification) => Notification::Trace(trace_notification),
+                    Err(error) => Notification::TraceError(error),
+                })
             }
             _ => Err(NotificationParseError::InvalidEvent),
         }
@@ -505,7 +563,7 @@ struct Inner {
     next_mid_for_consumers: AtomicUsize,
     used_sctp_stream_ids: Mutex<IntMap<u16, bool>>,
     cname_for_producers: Mutex<Option<String>>,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     data: Arc<WebRtcTransportData>,
@@ -514,6 +572,8 @@ struct Inner {
     webrtc_server: Option<WebRtcServer>,
     // Make sure router is not dropped until this transport is not dropped
     router: Router,
+    #[cfg(feature = "tracing")]
+    _span: crate::worker::EntitySpan,
     closed: AtomicBool,
     // Drop subscription to transport-specific notifications when transport itself is dropped
     _subscription_handler: Mutex<Option<SubscriptionHandler>>,
@@ -543,13 +603,18 @@ impl Inner {
                     transport_id: self.id,
                 };
 
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request(router_id, request).await {
-                            error!("transport closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::transport",
+                        "close_transport",
+                        transport_id = %request.transport_id,
+                    );
+                    if let Err(error) = instrument!(channel.request(router_id, request), span).await
+                    {
+                        error!("transport closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -762,7 +827,7 @@ impl TransportImpl for WebRtcTransport {
         &self.inner.channel
     }
 
-    fn executor(&self) -> &Arc<Executor<'static>> {
+    fn executor(&self) -> &Arc<dyn Executor> {
         &self.inner.executor
     }
 
@@ -783,7 +848,7 @@ impl WebRtcTransport {
     #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         id: TransportId,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         data: WebRtcTransportData,
         app_data: AppData,
@@ -836,8 +901,26 @@ impl WebRtcTransport {
                             });
                         }
                         Notification::Trace(trace_event_data) => {
+                            if let TransportTraceEventData::Bwe {
+                                direction: TraceEventDirection::Out,
+                                info,
+                                ..
+                            } = &trace_event_data
+                            {
+                                let available_bitrate = info.available_bitrate;
+                                data.available_bitrate.lock().replace(available_bitrate);
+
+                                handlers.available_bitrate.call(|callback| {
+                                    callback(available_bitrate);
+                                });
+                            }
+
                             handlers.trace.call_simple(&trace_event_data);
                         }
+                        Notification::TraceError(error) => {
+                            error!("Failed to decode trace event: {}", error);
+                            handlers.trace_error.call_simple(&error);
+                        }
                     },
                     Err(error) => {
                         error!("Failed to parse notification: {}", error);
@@ -882,6 +965,17 @@ impl WebRtcTransport {
                 }
             }
         });
+        #[cfg(feature = "tracing")]
+        let span = router.worker().register_entity_span(
+            id,
+            tracing::info_span!(
+                target: "mediasoup::transport",
+                parent: router.span(),
+                "transport",
+                %id,
+                kind = "webrtc",
+            ),
+        );
         let inner = Arc::new(Inner {
             id,
             next_mid_for_consumers,
@@ -894,6 +988,8 @@ impl WebRtcTransport {
             app_data,
             webrtc_server,
             router,
+            #[cfg(feature = "tracing")]
+            _span: span,
             closed: AtomicBool::new(false),
             _subscription_handler: Mutex::new(subscription_handler),
             _on_webrtc_server_close_handler: Mutex::new(on_webrtc_server_close_handler),
@@ -1058,6 +1154,16 @@ impl WebRtcTransport {
         *self.inner.data.sctp_state.lock()
     }
 
+    /// Last available outgoing bitrate (in bps) reported by bandwidth estimation. Or `None` if no
+    /// estimation was received yet.
+    ///
+    /// Estimations are only delivered while [`TransportTraceEventType::Bwe`] trace event is
+    /// enabled, see [`Transport::enable_trace_event()`].
+    #[must_use]
+    pub fn available_bitrate(&self) -> Option<u32> {
+        *self.inner.data.available_bitrate.lock()
+    }
+
     /// Restarts the ICE layer by generating new local ICE parameters that must be signaled to the
     /// remote endpoint.
     pub async fn restart_ice(&self) -> Result<IceParameters, RequestError> {
@@ -1122,6 +1228,122 @@ impl WebRtcTransport {
             .add(Arc::new(callback))
     }
 
+    /// Callback is called when bandwidth estimation reports available outgoing bitrate (in bps).
+    ///
+    /// NOTE: [`TransportTraceEventType::Bwe`] trace event needs to be enabled with
+    /// [`Transport::enable_trace_event()`], otherwise worker will not send estimations and callback
+    /// will never be called.
+    pub fn on_available_bitrate<F: Fn(u32) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner
+            .handlers
+            .available_bitrate
+            .add(Arc::new(callback))
+    }
+
+    /// Callback is called when trace event enabled with [`Transport::enable_trace_event()`] was
+    /// received from the worker, but couldn't be decoded.
+    pub fn on_trace_error<F: Fn(&TraceEventError) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner.handlers.trace_error.add(Arc::new(callback))
+    }
+
+    /// Stream of transport events, alternative to `on_*` callbacks.
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<WebRtcTransportEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_new_producer(Arc::new({
+                    let sender = sender.clone();
+
+                    move |producer| {
+                        sender.send(WebRtcTransportEvent::NewProducer {
+                            producer_id: producer.id(),
+                        });
+                    }
+                })),
+                self.on_new_consumer(Arc::new({
+                    let sender = sender.clone();
+
+                    move |consumer| {
+                        sender.send(WebRtcTransportEvent::NewConsumer {
+                            consumer_id: consumer.id(),
+                        });
+                    }
+                })),
+                self.on_new_data_producer(Arc::new({
+                    let sender = sender.clone();
+
+                    move |data_producer| {
+                        sender.send(WebRtcTransportEvent::NewDataProducer {
+                            data_producer_id: data_producer.id(),
+                        });
+                    }
+                })),
+                self.on_new_data_consumer(Arc::new({
+                    let sender = sender.clone();
+
+                    move |data_consumer| {
+                        sender.send(WebRtcTransportEvent::NewDataConsumer {
+                            data_consumer_id: data_consumer.id(),
+                        });
+                    }
+                })),
+                self.on_ice_state_change({
+                    let sender = sender.clone();
+
+                    move |ice_state| sender.send(WebRtcTransportEvent::IceStateChange(ice_state))
+                }),
+                self.on_ice_selected_tuple_change({
+                    let sender = sender.clone();
+
+                    move |tuple| sender.send(WebRtcTransportEvent::IceSelectedTupleChange(*tuple))
+                }),
+                self.on_dtls_state_change({
+                    let sender = sender.clone();
+
+                    move |dtls_state| {
+                        sender.send(WebRtcTransportEvent::DtlsStateChange(dtls_state));
+                    }
+                }),
+                self.on_sctp_state_change({
+                    let sender = sender.clone();
+
+                    move |sctp_state| {
+                        sender.send(WebRtcTransportEvent::SctpStateChange(sctp_state));
+                    }
+                }),
+                self.on_trace(Arc::new({
+                    let sender = sender.clone();
+
+                    move |trace| sender.send(WebRtcTransportEvent::Trace(trace.clone()))
+                })),
+                self.on_trace_error({
+                    let sender = sender.clone();
+
+                    move |error| sender.send(WebRtcTransportEvent::TraceError(error.clone()))
+                }),
+                self.on_available_bitrate({
+                    let sender = sender.clone();
+
+                    move |available_bitrate| {
+                        sender.send(WebRtcTransportEvent::AvailableBitrate(available_bitrate));
+                    }
+                }),
+                self.on_router_close(Box::new(sender.emitter(WebRtcTransportEvent::RouterClose))),
+                self.on_webrtc_server_close(Box::new(
+                    sender.emitter(WebRtcTransportEvent::WebRtcServerClose),
+                )),
+                self.on_close(Box::new(sender.last_emitter(WebRtcTransportEvent::Close))),
+            ]
+        })
+    }
+
     /// Downgrade `WebRtcTransport` to [`WeakWebRtcTransport`] instance.
     #[must_use]
     pub fn downgrade(&self) -> WeakWebRtcTransport {
@@ -0,0 +1,454 @@
+//! Parsing and building of RTP and RTCP packets.
+//!
+//! This is useful for working with raw packets of direct transports:
+//! [`DirectProducer::send()`](crate::producer::DirectProducer::send),
+//! [`Consumer::on_rtp()`](crate::consumer::Consumer::on_rtp),
+//! [`DirectTransport::send_rtcp()`](crate::direct_transport::DirectTransport::send_rtcp) and
+//! [`DirectTransport::on_rtcp()`](crate::direct_transport::DirectTransport::on_rtcp).
+//!
+//! [`RtpPacket`] is a zero-copy view over RTP packet bytes, [`RtpPacketBuilder`] serializes a new
+//! one. Header extension ids negotiated in [`RtpParameters`](crate::rtp_parameters::RtpParameters)
+//! can be resolved with [`HeaderExtensionIds`], after which typed values of well-known extensions
+//! can be read from packets. RTCP packets are handled by [`rtcp`] module.
+//!
+//! Encoded frames can be reassembled out of RTP packets with [`jitter_buffer`] and
+//! [`depacketizer`] modules, or with [`FrameReceiver`](frame_receiver::FrameReceiver) that
+//! attaches both to a consumer. In the opposite direction [`packetizer`] splits encoded frames
+//! into RTP packets for [`DirectProducer::send()`](crate::producer::DirectProducer::send).
+//!
+//! # Examples
+//! ```rust
+//! use mediasoup::rtp::{RtpPacket, RtpPacketBuilder};
+//!
+//! let mut builder = RtpPacketBuilder::new(111, 1000, 48000, 1234);
+//! builder.marker = true;
+//! builder.extensions.push((1, b"0".to_vec()));
+//! builder.payload = vec![1, 2, 3];
+//! let data = builder.build().unwrap();
+//!
+//! let packet = RtpPacket::parse(&data).unwrap();
+//! assert!(packet.marker());
+//! assert_eq!(packet.sequence_number(), 1000);
+//! assert_eq!(packet.extension(1), Some(b"0".as_ref()));
+//! assert_eq!(packet.payload(), &[1, 2, 3]);
+//! ```
+
+pub mod depacketizer;
+pub mod frame_receiver;
+mod header_extensions;
+pub mod jitter_buffer;
+pub mod packetizer;
+pub mod rtcp;
+#[cfg(test)]
+mod tests;
+
+pub use header_extensions::{AudioLevel, HeaderExtensionIds};
+use thiserror::Error;
+
+const FIXED_HEADER_SIZE: usize = 12;
+const ONE_BYTE_PROFILE: u16 = 0xBEDE;
+const TWO_BYTE_PROFILE_MASK: u16 = 0xFFF0;
+const TWO_BYTE_PROFILE: u16 = 0x1000;
+
+/// Error that caused [`RtpPacket::parse()`] to fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RtpParseError {
+    /// Packet is shorter than its header claims.
+    #[error("Packet is too short")]
+    TooShort,
+    /// RTP version is not 2.
+    #[error("Unsupported RTP version {0}")]
+    UnsupportedVersion(u8),
+    /// Padding size is zero or larger than the packet.
+    #[error("Invalid padding")]
+    InvalidPadding,
+    /// One-byte or two-byte header extension elements don't fit into extension data.
+    #[error("Invalid header extension")]
+    InvalidHeaderExtension,
+}
+
+/// Error that caused [`RtpPacketBuilder::build()`] to fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RtpBuildError {
+    /// Payload type doesn't fit into 7 bits.
+    #[error("Invalid payload type {0}")]
+    InvalidPayloadType(u8),
+    /// More than 15 CSRCs.
+    #[error("Too many CSRCs: {0}")]
+    TooManyCsrcs(usize),
+    /// Extension id is 0 or extension value is longer than 255 bytes.
+    #[error("Invalid header extension with id {0}")]
+    InvalidHeaderExtension(u8),
+}
+
+/// Header extension format as defined in [RFC 8285](https://tools.ietf.org/html/rfc8285).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HeaderExtensionProfile {
+    /// One-byte header (profile `0xBEDE`).
+    OneByte,
+    /// Two-byte header (profile `0x100X`).
+    TwoByte {
+        /// Application-dependent 4 bits of the profile.
+        app_bits: u8,
+    },
+    /// Some other profile, elements are not parsed.
+    Other(u16),
+}
+
+impl HeaderExtensionProfile {
+    fn from_u16(profile: u16) -> Self {
+        if profile == ONE_BYTE_PROFILE {
+            Self::OneByte
+        } else if profile & TWO_BYTE_PROFILE_MASK == TWO_BYTE_PROFILE {
+            Self::TwoByte {
+                app_bits: (profile & !TWO_BYTE_PROFILE_MASK) as u8,
+            }
+        } else {
+            Self::Other(profile)
+        }
+    }
+}
+
+/// Iterator over one-byte or two-byte header extension elements, yields extension id and value.
+#[derive(Debug, Clone)]
+pub struct HeaderExtensions<'a> {
+    two_byte: bool,
+    data: &'a [u8],
+}
+
+impl<'a> HeaderExtensions<'a> {
+    fn new(profile: HeaderExtensionProfile, data: &'a [u8]) -> Self {
+        match profile {
+            HeaderExtensionProfile::OneByte => Self {
+                two_byte: false,
+                data,
+            },
+            HeaderExtensionProfile::TwoByte { .. } => Self {
+                two_byte: true,
+                data,
+            },
+            HeaderExtensionProfile::Other(_) => Self {
+                two_byte: false,
+                data: &[],
+            },
+        }
+    }
+
+    fn try_next(&mut self) -> Result<Option<(u8, &'a [u8])>, RtpParseError> {
+        loop {
+            let Some((&first, rest)) = self.data.split_first() else {
+                return Ok(None);
+            };
+
+            // Padding
+            if first == 0 {
+                self.data = rest;
+                continue;
+            }
+
+            let (id, len, rest) = if self.two_byte {
+                let Some((&len, rest)) = rest.split_first() else {
+                    return Err(RtpParseError::InvalidHeaderExtension);
+                };
+                (first, usize::from(len), rest)
+            } else {
+                let id = first >> 4;
+                // Id 15 is reserved and means the rest of extension data must be ignored
+                if id == 15 {
+                    self.data = &[];
+                    return Ok(None);
+                }
+                (id, usize::from(first & 0x0F) + 1, rest)
+            };
+
+            if rest.len() < len {
+                self.data = &[];
+                return Err(RtpParseError::InvalidHeaderExtension);
+            }
+
+            let (value, rest) = rest.split_at(len);
+            self.data = rest;
+
+            return Ok(Some((id, value)));
+        }
+    }
+}
+
+impl<'a> Iterator for HeaderExtensions<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().ok().flatten()
+    }
+}
+
+/// Zero-copy view over RTP packet.
+#[derive(Debug, Copy, Clone)]
+pub struct RtpPacket<'a> {
+    data: &'a [u8],
+    extension: Option<(HeaderExtensionProfile, &'a [u8])>,
+    payload: &'a [u8],
+    padding_size: u8,
+}
+
+impl<'a> RtpPacket<'a> {
+    /// Parse and validate RTP packet.
+    pub fn parse(data: &'a [u8]) -> Result<Self, RtpParseError> {
+        if data.len() < FIXED_HEADER_SIZE {
+            return Err(RtpParseError::TooShort);
+        }
+
+        let version = data[0] >> 6;
+        if version != 2 {
+            return Err(RtpParseError::UnsupportedVersion(version));
+        }
+
+        let mut offset = FIXED_HEADER_SIZE + usize::from(data[0] & 0x0F) * 4;
+        if data.len() < offset {
+            return Err(RtpParseError::TooShort);
+        }
+
+        let extension = if data[0] & 0x10 != 0 {
+            if data.len() < offset + 4 {
+                return Err(RtpParseError::TooShort);
+            }
+            let profile = u16::from_be_bytes([data[offset], data[offset + 1]]);
+            let len = usize::from(u16::from_be_bytes([data[offset + 2], data[offset + 3]])) * 4;
+            offset += 4;
+            if data.len() < offset + len {
+                return Err(RtpParseError::TooShort);
+            }
+
+            let profile = HeaderExtensionProfile::from_u16(profile);
+            let extension_data = &data[offset..offset + len];
+            offset += len;
+
+            let mut extensions = HeaderExtensions::new(profile, extension_data);
+            while extensions.try_next()?.is_some() {}
+
+            Some((profile, extension_data))
+        } else {
+            None
+        };
+
+        let padding_size = if data[0] & 0x20 != 0 {
+            let padding_size = data[data.len() - 1];
+            if padding_size == 0 || data.len() < offset + usize::from(padding_size) {
+                return Err(RtpParseError::InvalidPadding);
+            }
+            padding_size
+        } else {
+            0
+        };
+
+        Ok(Self {
+            data,
+            extension,
+            payload: &data[offset..data.len() - usize::from(padding_size)],
+            padding_size,
+        })
+    }
+
+    /// Raw packet bytes.
+    #[must_use]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Marker bit.
+    #[must_use]
+    pub fn marker(&self) -> bool {
+        self.data[1] & 0x80 != 0
+    }
+
+    /// Payload type.
+    #[must_use]
+    pub fn payload_type(&self) -> u8 {
+        self.data[1] & 0x7F
+    }
+
+    /// Sequence number.
+    #[must_use]
+    pub fn sequence_number(&self) -> u16 {
+        u16::from_be_bytes([self.data[2], self.data[3]])
+    }
+
+    /// RTP timestamp.
+    #[must_use]
+    pub fn timestamp(&self) -> u32 {
+        u32::from_be_bytes([self.data[4], self.data[5], self.data[6], self.data[7]])
+    }
+
+    /// Synchronization source.
+    #[must_use]
+    pub fn ssrc(&self) -> u32 {
+        u32::from_be_bytes([self.data[8], self.data[9], self.data[10], self.data[11]])
+    }
+
+    /// Contributing sources.
+    pub fn csrcs(&self) -> impl ExactSizeIterator<Item = u32> + 'a {
+        let csrc_count = usize::from(self.data[0] & 0x0F);
+
+        self.data[FIXED_HEADER_SIZE..FIXED_HEADER_SIZE + csrc_count * 4]
+            .chunks_exact(4)
+            .map(|csrc| u32::from_be_bytes([csrc[0], csrc[1], csrc[2], csrc[3]]))
+    }
+
+    /// Header extension profile, if header extension is present.
+    #[must_use]
+    pub fn header_extension_profile(&self) -> Option<HeaderExtensionProfile> {
+        self.extension.map(|(profile, _data)| profile)
+    }
+
+    /// Raw header extension data (without profile and length), if header extension is present.
+    #[must_use]
+    pub fn header_extension_data(&self) -> Option<&'a [u8]> {
+        self.extension.map(|(_profile, data)| data)
+    }
+
+    /// Iterator over one-byte or two-byte header extension elements.
+    pub fn extensions(&self) -> HeaderExtensions<'a> {
+        match self.extension {
+            Some((profile, data)) => HeaderExtensions::new(profile, data),
+            None => HeaderExtensions::new(HeaderExtensionProfile::Other(0), &[]),
+        }
+    }
+
+    /// Value of header extension with specified id.
+    #[must_use]
+    pub fn extension(&self, id: u8) -> Option<&'a [u8]> {
+        self.extensions()
+            .find(|(extension_id, _value)| *extension_id == id)
+            .map(|(_id, value)| value)
+    }
+
+    /// Payload, excluding padding.
+    #[must_use]
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Number of padding bytes at the end of the packet (including padding size byte itself).
+    #[must_use]
+    pub fn padding_size(&self) -> u8 {
+        self.padding_size
+    }
+}
+
+/// Builder of new RTP packets.
+///
+/// One-byte header extensions are used when all extension ids are within `1..=14` and values are
+/// 1 to 16 bytes long, two-byte header extensions otherwise.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RtpPacketBuilder {
+    /// Marker bit.
+    pub marker: bool,
+    /// Payload type (7 bits).
+    pub payload_type: u8,
+    /// Sequence number.
+    pub sequence_number: u16,
+    /// RTP timestamp.
+    pub timestamp: u32,
+    /// Synchronization source.
+    pub ssrc: u32,
+    /// Contributing sources (up to 15).
+    pub csrcs: Vec<u32>,
+    /// Header extensions as pairs of id and value.
+    pub extensions: Vec<(u8, Vec<u8>)>,
+    /// Payload.
+    pub payload: Vec<u8>,
+    /// Number of padding bytes to append, `0` means no padding.
+    pub padding_size: u8,
+}
+
+impl RtpPacketBuilder {
+    /// Create builder for a packet with empty payload and no extensions.
+    #[must_use]
+    pub fn new(payload_type: u8, sequence_number: u16, timestamp: u32, ssrc: u32) -> Self {
+        Self {
+            payload_type,
+            sequence_number,
+            timestamp,
+            ssrc,
+            ..Self::default()
+        }
+    }
+
+    /// Serialize RTP packet.
+    pub fn build(&self) -> Result<Vec<u8>, RtpBuildError> {
+        if self.payload_type > 0x7F {
+            return Err(RtpBuildError::InvalidPayloadType(self.payload_type));
+        }
+        if self.csrcs.len() > 15 {
+            return Err(RtpBuildError::TooManyCsrcs(self.csrcs.len()));
+        }
+        for (id, value) in &self.extensions {
+            if *id == 0 || value.len() > 255 {
+                return Err(RtpBuildError::InvalidHeaderExtension(*id));
+            }
+        }
+
+        let mut data = Vec::with_capacity(
+            FIXED_HEADER_SIZE
+                + self.csrcs.len() * 4
+                + self.payload.len()
+                + usize::from(self.padding_size),
+        );
+
+        data.push(
+            0x80 | u8::from(self.padding_size > 0) << 5
+                | u8::from(!self.extensions.is_empty()) << 4
+                | self.csrcs.len() as u8,
+        );
+        data.push(u8::from(self.marker) << 7 | self.payload_type);
+        data.extend_from_slice(&self.sequence_number.to_be_bytes());
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        data.extend_from_slice(&self.ssrc.to_be_bytes());
+        for csrc in &self.csrcs {
+            data.extend_from_slice(&csrc.to_be_bytes());
+        }
+
+        if !self.extensions.is_empty() {
+            let one_byte = self
+                .extensions
+                .iter()
+                .all(|(id, value)| *id <= 14 && (1..=16).contains(&value.len()));
+
+            let profile = if one_byte {
+                ONE_BYTE_PROFILE
+            } else {
+                TWO_BYTE_PROFILE
+            };
+            data.extend_from_slice(&profile.to_be_bytes());
+            let length_offset = data.len();
+            data.extend_from_slice(&[0, 0]);
+
+            let start = data.len();
+            for (id, value) in &self.extensions {
+                if one_byte {
+                    data.push(id << 4 | (value.len() - 1) as u8);
+                } else {
+                    data.push(*id);
+                    data.push(value.len() as u8);
+                }
+                data.extend_from_slice(value);
+            }
+            while (data.len() - start) % 4 != 0 {
+                data.push(0);
+            }
+
+            let length = ((data.len() - start) / 4) as u16;
+            data[length_offset..length_offset + 2].copy_from_slice(&length.to_be_bytes());
+        }
+
+        data.extend_from_slice(&self.payload);
+
+        if self.padding_size > 0 {
+            data.resize(data.len() + usize::from(self.padding_size) - 1, 0);
+            data.push(self.padding_size);
+        }
+
+        Ok(data)
+    }
+}
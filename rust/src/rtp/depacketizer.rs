@@ -0,0 +1,512 @@
+//! Reassembly of encoded frames out of RTP packets.
+//!
+//! [`Depacketizer`] expects packets of a single stream in sequence number order, for instance
+//! those received through [`Consumer::on_rtp()`](crate::consumer::Consumer::on_rtp) after
+//! reordering with [`JitterBuffer`](super::jitter_buffer::JitterBuffer). When packets are lost,
+//! [`Depacketizer::reset()`] must be called so that incomplete frame is discarded instead of being
+//! glued together with the next one.
+//!
+//! [`FrameReceiver`](super::frame_receiver::FrameReceiver) combines both for a consumer and
+//! requests key frames when necessary.
+
+#[cfg(test)]
+mod tests;
+
+use super::RtpPacket;
+use crate::rtp_parameters::{MimeType, MimeTypeAudio, MimeTypeVideo};
+
+const ANNEX_B_START_CODE: &[u8] = &[0, 0, 0, 1];
+
+const H264_NAL_IDR: u8 = 5;
+const H264_NAL_STAP_A: u8 = 24;
+const H264_NAL_FU_A: u8 = 28;
+
+pub(crate) const AV1_OBU_SEQUENCE_HEADER: u8 = 1;
+const AV1_OBU_TEMPORAL_DELIMITER: u8 = 2;
+const AV1_OBU_TILE_LIST: u8 = 8;
+const AV1_OBU_PADDING: u8 = 15;
+
+/// Codec supported by [`Depacketizer`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DepacketizerCodec {
+    /// Opus, every packet is a frame.
+    Opus,
+    /// VP8 as defined in [RFC 7741](https://tools.ietf.org/html/rfc7741).
+    Vp8,
+    /// VP9 as defined in [RFC 9628](https://tools.ietf.org/html/rfc9628).
+    Vp9,
+    /// H264 as defined in [RFC 6184](https://tools.ietf.org/html/rfc6184), frames are produced in
+    /// Annex B format.
+    H264,
+    /// AV1 as defined in [RTP Payload Format For AV1](https://aomediacodec.github.io/av1-rtp-spec/),
+    /// frames are produced as a sequence of OBUs with size fields and without temporal delimiters.
+    Av1,
+}
+
+impl DepacketizerCodec {
+    /// Codec corresponding to MIME type, if supported.
+    #[must_use]
+    pub fn from_mime_type(mime_type: MimeType) -> Option<Self> {
+        match mime_type {
+            MimeType::Audio(MimeTypeAudio::Opus) => Some(Self::Opus),
+            MimeType::Video(MimeTypeVideo::Vp8) => Some(Self::Vp8),
+            MimeType::Video(MimeTypeVideo::Vp9) => Some(Self::Vp9),
+            MimeType::Video(MimeTypeVideo::H264) => Some(Self::H264),
+            MimeType::Video(MimeTypeVideo::Av1) => Some(Self::Av1),
+            _ => None,
+        }
+    }
+}
+
+/// Complete encoded frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EncodedFrame {
+    /// RTP timestamp of the frame.
+    pub timestamp: u32,
+    /// Whether frame can be decoded without previous frames.
+    pub key_frame: bool,
+    /// The highest spatial layer contained in the frame, if signaled in payload (VP9, AV1).
+    pub spatial_layer: Option<u8>,
+    /// Temporal layer of the frame, if signaled in payload (VP8, VP9, AV1).
+    pub temporal_layer: Option<u8>,
+    /// Encoded frame data.
+    pub data: Vec<u8>,
+}
+
+/// Frame that is being reassembled.
+#[derive(Debug, Default)]
+struct PartialFrame {
+    timestamp: u32,
+    key_frame: bool,
+    spatial_layer: Option<u8>,
+    temporal_layer: Option<u8>,
+    data: Vec<u8>,
+    /// Fragment of AV1 OBU that continues in the next packet.
+    obu_fragment: Vec<u8>,
+}
+
+impl PartialFrame {
+    fn set_layers(&mut self, spatial_layer: Option<u8>, temporal_layer: Option<u8>) {
+        if let Some(spatial_layer) = spatial_layer {
+            self.spatial_layer = Some(
+                self.spatial_layer
+                    .map_or(spatial_layer, |current| current.max(spatial_layer)),
+            );
+        }
+        if temporal_layer.is_some() {
+            self.temporal_layer = temporal_layer;
+        }
+    }
+}
+
+/// Reassembles [`EncodedFrame`]s out of RTP packets, see [module-level documentation](self).
+#[derive(Debug)]
+pub struct Depacketizer {
+    codec: DepacketizerCodec,
+    current: Option<PartialFrame>,
+}
+
+impl Depacketizer {
+    /// Create depacketizer for specified codec.
+    #[must_use]
+    pub fn new(codec: DepacketizerCodec) -> Self {
+        Self {
+            codec,
+            current: None,
+        }
+    }
+
+    /// Codec of this depacketizer.
+    #[must_use]
+    pub fn codec(&self) -> DepacketizerCodec {
+        self.codec
+    }
+
+    /// Discard partially reassembled frame, must be called when packets were lost.
+    pub fn reset(&mut self) {
+        self.current.take();
+    }
+
+    /// Process next packet, returns a frame once its last packet was received.
+    ///
+    /// Packets that are malformed or don't belong to any frame that can be completed (because
+    /// the beginning of the frame was lost) are ignored.
+    pub fn push(&mut self, packet: &RtpPacket<'_>) -> Option<EncodedFrame> {
+        let payload = packet.payload();
+        if payload.is_empty() {
+            return None;
+        }
+
+        if self.codec == DepacketizerCodec::Opus {
+            return Some(EncodedFrame {
+                timestamp: packet.timestamp(),
+                key_frame: true,
+                spatial_layer: None,
+                temporal_layer: None,
+                data: payload.to_vec(),
+            });
+        }
+
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| current.timestamp != packet.timestamp())
+        {
+            // The end of previous frame was lost
+            self.current.take();
+        }
+
+        let started = self.current.is_some();
+        let current = self.current.get_or_insert_with(|| PartialFrame {
+            timestamp: packet.timestamp(),
+            ..PartialFrame::default()
+        });
+
+        let accepted = match self.codec {
+            DepacketizerCodec::Opus => unreachable!(),
+            DepacketizerCodec::Vp8 => push_vp8(current, started, payload),
+            DepacketizerCodec::Vp9 => push_vp9(current, started, payload),
+            DepacketizerCodec::H264 => push_h264(current, started, payload),
+            DepacketizerCodec::Av1 => push_av1(current, started, payload),
+        };
+        if !accepted {
+            if !started {
+                self.current.take();
+            }
+            return None;
+        }
+
+        if !packet.marker() {
+            return None;
+        }
+
+        self.current.take().and_then(|current| {
+            (!current.data.is_empty()).then_some(EncodedFrame {
+                timestamp: current.timestamp,
+                key_frame: current.key_frame,
+                spatial_layer: current.spatial_layer,
+                temporal_layer: current.temporal_layer,
+                data: current.data,
+            })
+        })
+    }
+}
+
+/// Returns `false` if packet is malformed or doesn't continue current frame.
+fn push_vp8(current: &mut PartialFrame, started: bool, payload: &[u8]) -> bool {
+    let descriptor = payload[0];
+    let mut offset = 1;
+    let mut temporal_layer = None;
+    if descriptor & 0x80 != 0 {
+        let Some(&extension) = payload.get(offset) else {
+            return false;
+        };
+        offset += 1;
+        // Picture ID
+        if extension & 0x80 != 0 {
+            let Some(&picture_id) = payload.get(offset) else {
+                return false;
+            };
+            offset += if picture_id & 0x80 != 0 { 2 } else { 1 };
+        }
+        // TL0PICIDX
+        if extension & 0x40 != 0 {
+            offset += 1;
+        }
+        // TID/Y/KEYIDX
+        if extension & 0x30 != 0 {
+            let Some(&tid_y_keyidx) = payload.get(offset) else {
+                return false;
+            };
+            if extension & 0x20 != 0 {
+                temporal_layer.replace(tid_y_keyidx >> 6);
+            }
+            offset += 1;
+        }
+    }
+    let Some(data) = payload.get(offset..).filter(|data| !data.is_empty()) else {
+        return false;
+    };
+
+    let start_of_frame = descriptor & 0x10 != 0 && descriptor & 0x07 == 0;
+    if !started {
+        if !start_of_frame {
+            return false;
+        }
+        // Inverse key frame flag in the frame tag
+        current.key_frame = data[0] & 0x01 == 0;
+    }
+    current.set_layers(None, temporal_layer);
+    current.data.extend_from_slice(data);
+
+    true
+}
+
+/// Returns `false` if packet is malformed or doesn't continue current frame.
+fn push_vp9(current: &mut PartialFrame, started: bool, payload: &[u8]) -> bool {
+    let descriptor = payload[0];
+    let inter_picture_predicted = descriptor & 0x40 != 0;
+    let layer_indices = descriptor & 0x20 != 0;
+    let flexible = descriptor & 0x10 != 0;
+    let beginning_of_frame = descriptor & 0x08 != 0;
+    let scalability_structure = descriptor & 0x02 != 0;
+
+    let mut offset = 1;
+    // Picture ID
+    if descriptor & 0x80 != 0 {
+        let Some(&picture_id) = payload.get(offset) else {
+            return false;
+        };
+        offset += if picture_id & 0x80 != 0 { 2 } else { 1 };
+    }
+    let mut spatial_layer = None;
+    let mut temporal_layer = None;
+    if layer_indices {
+        let Some(&indices) = payload.get(offset) else {
+            return false;
+        };
+        temporal_layer.replace(indices >> 5);
+        spatial_layer.replace((indices >> 1) & 0x07);
+        // TL0PICIDX is only present in non-flexible mode
+        offset += if flexible { 1 } else { 2 };
+    }
+    // Reference indices
+    if flexible && inter_picture_predicted {
+        loop {
+            let Some(&p_diff) = payload.get(offset) else {
+                return false;
+            };
+            offset += 1;
+            if p_diff & 0x01 == 0 {
+                break;
+            }
+        }
+    }
+    if scalability_structure {
+        let Some(&ss) = payload.get(offset) else {
+            return false;
+        };
+        offset += 1;
+        let spatial_layers = usize::from(ss >> 5) + 1;
+        // Resolutions
+        if ss & 0x10 != 0 {
+            offset += spatial_layers * 4;
+        }
+        // Picture group description
+        if ss & 0x08 != 0 {
+            let Some(&pictures) = payload.get(offset) else {
+                return false;
+            };
+            offset += 1;
+            for _ in 0..pictures {
+                let Some(&picture) = payload.get(offset) else {
+                    return false;
+                };
+                offset += 1 + usize::from((picture >> 2) & 0x03);
+            }
+        }
+    }
+    let Some(data) = payload.get(offset..).filter(|data| !data.is_empty()) else {
+        return false;
+    };
+
+    if !started {
+        if !beginning_of_frame {
+            return false;
+        }
+        current.key_frame = !inter_picture_predicted && spatial_layer.unwrap_or(0) == 0;
+    }
+    current.set_layers(spatial_layer, temporal_layer);
+    current.data.extend_from_slice(data);
+
+    true
+}
+
+/// Returns `false` if packet is malformed or doesn't continue current frame.
+fn push_h264(current: &mut PartialFrame, started: bool, payload: &[u8]) -> bool {
+    let nal_header = payload[0];
+
+    match nal_header & 0x1F {
+        1..=23 => {
+            push_h264_nal_unit(current, payload);
+        }
+        H264_NAL_STAP_A => {
+            let mut rest = &payload[1..];
+            while !rest.is_empty() {
+                let Some(size) = rest.get(..2) else {
+                    return false;
+                };
+                let size = usize::from(u16::from_be_bytes([size[0], size[1]]));
+                let Some(nal_unit) = rest.get(2..2 + size).filter(|_| size > 0) else {
+                    return false;
+                };
+                push_h264_nal_unit(current, nal_unit);
+                rest = &rest[2 + size..];
+            }
+        }
+        H264_NAL_FU_A => {
+            let Some(&fu_header) = payload.get(1) else {
+                return false;
+            };
+            let start = fu_header & 0x80 != 0;
+            if start {
+                push_h264_nal_unit(current, &[nal_header & 0xE0 | fu_header & 0x1F]);
+            } else if !started {
+                // Beginning of NAL unit was lost
+                return false;
+            }
+            current.data.extend_from_slice(&payload[2..]);
+        }
+        _ => {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn push_h264_nal_unit(current: &mut PartialFrame, nal_unit: &[u8]) {
+    if nal_unit[0] & 0x1F == H264_NAL_IDR {
+        current.key_frame = true;
+    }
+    current.data.extend_from_slice(ANNEX_B_START_CODE);
+    current.data.extend_from_slice(nal_unit);
+}
+
+/// Returns `false` if packet is malformed or doesn't continue current frame.
+fn push_av1(current: &mut PartialFrame, started: bool, payload: &[u8]) -> bool {
+    let aggregation_header = payload[0];
+    let continuation = aggregation_header & 0x80 != 0;
+    let continues = aggregation_header & 0x40 != 0;
+    let elements = (aggregation_header >> 4) & 0x03;
+    let new_coded_video_sequence = aggregation_header & 0x08 != 0;
+
+    if continuation && current.obu_fragment.is_empty() {
+        // The beginning of OBU was lost
+        return false;
+    }
+    if !started {
+        current.key_frame = new_coded_video_sequence;
+    }
+
+    let mut rest = &payload[1..];
+    let mut index = 0;
+    while !rest.is_empty() {
+        index += 1;
+        // The last element doesn't have length field when number of elements is known
+        let element = if elements != 0 && index == elements {
+            std::mem::take(&mut rest)
+        } else {
+            let Some((length, length_size)) = read_leb128(rest) else {
+                return false;
+            };
+            let Some(element) = usize::try_from(length)
+                .ok()
+                .and_then(|length| rest.get(length_size..length_size + length))
+            else {
+                return false;
+            };
+            rest = &rest[length_size + element.len()..];
+            element
+        };
+
+        if !(index == 1 && continuation) {
+            current.obu_fragment.clear();
+        }
+        current.obu_fragment.extend_from_slice(element);
+
+        if rest.is_empty() && continues {
+            break;
+        }
+        let obu = std::mem::take(&mut current.obu_fragment);
+        push_av1_obu(current, &obu);
+    }
+
+    true
+}
+
+fn push_av1_obu(current: &mut PartialFrame, obu: &[u8]) {
+    let Some(&header) = obu.first() else {
+        return;
+    };
+    let obu_type = (header >> 3) & 0x0F;
+    if matches!(
+        obu_type,
+        AV1_OBU_TEMPORAL_DELIMITER | AV1_OBU_TILE_LIST | AV1_OBU_PADDING
+    ) {
+        return;
+    }
+
+    let has_extension = header & 0x04 != 0;
+    let has_size = header & 0x02 != 0;
+    let header_size = if has_extension { 2 } else { 1 };
+    if obu.len() < header_size {
+        return;
+    }
+    if has_extension {
+        let extension = obu[1];
+        current.set_layers(Some((extension >> 3) & 0x03), Some(extension >> 5));
+    }
+
+    if has_size {
+        current.data.extend_from_slice(obu);
+    } else {
+        current.data.push(header | 0x02);
+        current.data.extend_from_slice(&obu[1..header_size]);
+        write_leb128(&mut current.data, (obu.len() - header_size) as u64);
+        current.data.extend_from_slice(&obu[header_size..]);
+    }
+}
+
+/// Read unsigned LEB128 value, returns value and number of bytes it occupied.
+pub(crate) fn read_leb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0_u64;
+    for (index, &byte) in data.iter().take(8).enumerate() {
+        value |= u64::from(byte & 0x7F) << (index * 7);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+
+    None
+}
+
+pub(crate) fn write_leb128(buffer: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+/// Iterator over NAL units of H264 frame in Annex B format (without start codes).
+pub(crate) fn annex_b_nal_units(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = data;
+
+    std::iter::from_fn(move || loop {
+        // Skip start code
+        let start = rest.windows(3).position(|window| window == [0, 0, 1])? + 3;
+        rest = &rest[start..];
+        let end = rest
+            .windows(3)
+            .position(|window| window == [0, 0, 1])
+            .map_or(rest.len(), |end| {
+                // 4-byte start code or trailing zero byte
+                if end > 0 && rest[end - 1] == 0 {
+                    end - 1
+                } else {
+                    end
+                }
+            });
+        let nal_unit = &rest[..end];
+        rest = &rest[end..];
+        if !nal_unit.is_empty() {
+            return Some(nal_unit);
+        }
+    })
+}
@@ -0,0 +1,315 @@
+use super::*;
+use crate::rtp::RtpPacketBuilder;
+
+fn packet(sequence_number: u16, timestamp: u32, marker: bool, payload: &[u8]) -> Vec<u8> {
+    let mut builder = RtpPacketBuilder::new(96, sequence_number, timestamp, 1234);
+    builder.marker = marker;
+    builder.payload = payload.to_vec();
+    builder.build().unwrap()
+}
+
+fn push(depacketizer: &mut Depacketizer, data: &[u8]) -> Option<EncodedFrame> {
+    depacketizer.push(&RtpPacket::parse(data).unwrap())
+}
+
+#[test]
+fn opus() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Opus);
+
+    assert_eq!(
+        push(&mut depacketizer, &packet(1, 960, false, &[0xF8, 1, 2])),
+        Some(EncodedFrame {
+            timestamp: 960,
+            key_frame: true,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0xF8, 1, 2],
+        }),
+    );
+    assert_eq!(push(&mut depacketizer, &packet(2, 1920, false, &[])), None);
+}
+
+#[test]
+fn vp8() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Vp8);
+
+    // Extended descriptor with 15-bit picture id, start of partition 0, key frame
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(1, 3000, false, &[0x90, 0x80, 0x81, 0x23, 0x10, 0x02]),
+        ),
+        None,
+    );
+    assert_eq!(
+        push(&mut depacketizer, &packet(2, 3000, true, &[0x00, 0x03])),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: true,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0x10, 0x02, 0x03],
+        }),
+    );
+
+    // Frame without beginning is ignored
+    assert_eq!(
+        push(&mut depacketizer, &packet(4, 6000, true, &[0x00, 0x05])),
+        None,
+    );
+
+    // Delta frame, incomplete one is discarded on reset
+    assert_eq!(
+        push(&mut depacketizer, &packet(5, 9000, false, &[0x10, 0x01])),
+        None,
+    );
+    depacketizer.reset();
+    assert_eq!(
+        push(&mut depacketizer, &packet(7, 9000, true, &[0x00, 0x02])),
+        None,
+    );
+    assert_eq!(
+        push(&mut depacketizer, &packet(8, 12000, true, &[0x10, 0x01])),
+        Some(EncodedFrame {
+            timestamp: 12000,
+            key_frame: false,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0x01],
+        }),
+    );
+}
+
+#[test]
+fn vp9() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Vp9);
+
+    // Picture id, non-flexible layer indices (spatial layer 0), beginning of frame
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(1, 3000, false, &[0xA8, 0x01, 0x00, 0x05, 0x82]),
+        ),
+        None,
+    );
+    // End of frame
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(2, 3000, true, &[0xA4, 0x01, 0x00, 0x05, 0x49]),
+        ),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: true,
+            spatial_layer: Some(0),
+            temporal_layer: Some(0),
+            data: vec![0x82, 0x49],
+        }),
+    );
+
+    // Inter-picture predicted frame
+    assert_eq!(
+        push(&mut depacketizer, &packet(3, 6000, true, &[0x4C, 0x86])),
+        Some(EncodedFrame {
+            timestamp: 6000,
+            key_frame: false,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0x86],
+        }),
+    );
+}
+
+#[test]
+fn h264() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::H264);
+
+    // STAP-A with SPS and PPS
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(
+                1,
+                3000,
+                false,
+                &[0x18, 0x00, 0x02, 0x67, 0x42, 0x00, 0x02, 0x68, 0xCE],
+            ),
+        ),
+        None,
+    );
+    // FU-A with IDR slice
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(2, 3000, false, &[0x7C, 0x85, 0x01])
+        ),
+        None,
+    );
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(3, 3000, true, &[0x7C, 0x45, 0x02])
+        ),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: true,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![
+                0, 0, 0, 1, 0x67, 0x42, //
+                0, 0, 0, 1, 0x68, 0xCE, //
+                0, 0, 0, 1, 0x65, 0x01, 0x02,
+            ],
+        }),
+    );
+
+    // FU-A without start is ignored
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(5, 6000, true, &[0x7C, 0x41, 0x02])
+        ),
+        None,
+    );
+
+    // Single NAL unit
+    assert_eq!(
+        push(&mut depacketizer, &packet(6, 9000, true, &[0x41, 0x9A])),
+        Some(EncodedFrame {
+            timestamp: 9000,
+            key_frame: false,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0, 0, 0, 1, 0x41, 0x9A],
+        }),
+    );
+}
+
+#[test]
+fn vp8_temporal_layer() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Vp8);
+
+    // TL0PICIDX and TID present, temporal layer 2
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(1, 3000, true, &[0x90, 0x60, 0x07, 0x80, 0x11]),
+        ),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: false,
+            spatial_layer: None,
+            temporal_layer: Some(2),
+            data: vec![0x11],
+        }),
+    );
+}
+
+#[test]
+fn vp9_spatial_layers() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Vp9);
+
+    // Flexible mode, spatial layer 0 and 1 of the same picture, temporal layer 1
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(1, 3000, false, &[0x3C, 0x20, 0x01])
+        ),
+        None,
+    );
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(2, 3000, true, &[0x3C, 0x23, 0x02])
+        ),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: true,
+            spatial_layer: Some(1),
+            temporal_layer: Some(1),
+            data: vec![0x01, 0x02],
+        }),
+    );
+}
+
+#[test]
+fn av1() {
+    let mut depacketizer = Depacketizer::new(DepacketizerCodec::Av1);
+
+    // New coded video sequence, two elements: temporal delimiter (dropped) and sequence header,
+    // which is complete
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(1, 3000, false, &[0x28, 0x01, 0x12, 0x0A, 0x01, 0x02]),
+        ),
+        None,
+    );
+    // Single element: frame OBU with extension (temporal layer 1, spatial layer 0) that
+    // continues in the next packet
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(2, 3000, false, &[0x50, 0x34, 0x20, 0x03]),
+        ),
+        None,
+    );
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(3, 3000, true, &[0x90, 0x04, 0x05])
+        ),
+        Some(EncodedFrame {
+            timestamp: 3000,
+            key_frame: true,
+            spatial_layer: Some(0),
+            temporal_layer: Some(1),
+            data: vec![
+                0x0A, 0x01, 0x02, // sequence header
+                0x36, 0x20, 0x03, 0x03, 0x04, 0x05, // frame with size field
+            ],
+        }),
+    );
+
+    // Continuation of OBU which beginning was lost
+    assert_eq!(
+        push(&mut depacketizer, &packet(5, 6000, true, &[0x90, 0x04])),
+        None,
+    );
+
+    // Elements with length fields (W = 0)
+    assert_eq!(
+        push(
+            &mut depacketizer,
+            &packet(6, 9000, true, &[0x00, 0x02, 0x30, 0x07, 0x01, 0x30]),
+        ),
+        Some(EncodedFrame {
+            timestamp: 9000,
+            key_frame: false,
+            spatial_layer: None,
+            temporal_layer: None,
+            data: vec![0x32, 0x01, 0x07, 0x32, 0x00],
+        }),
+    );
+}
+
+#[test]
+fn leb128() {
+    for value in [0, 1, 127, 128, 300, 1 << 40] {
+        let mut buffer = Vec::new();
+        write_leb128(&mut buffer, value);
+        assert_eq!(read_leb128(&buffer), Some((value, buffer.len())));
+    }
+    assert_eq!(read_leb128(&[0x80]), None);
+}
+
+#[test]
+fn annex_b() {
+    let data = [0, 0, 0, 1, 0x67, 0x42, 0, 0, 1, 0x68, 0, 0, 0, 1, 0x65, 1];
+
+    assert_eq!(
+        annex_b_nal_units(&data).collect::<Vec<_>>(),
+        vec![[0x67, 0x42].as_ref(), [0x68].as_ref(), [0x65, 1].as_ref()],
+    );
+    assert_eq!(annex_b_nal_units(&[1, 2, 3]).count(), 0);
+}
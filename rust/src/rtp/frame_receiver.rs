@@ -0,0 +1,285 @@
+//! Encoded frames of a consumer on a direct transport.
+//!
+//! [`FrameReceiver`] attaches to [`Consumer::on_rtp()`], restores packet order with
+//! [`JitterBuffer`], reassembles frames with [`Depacketizer`] and calls
+//! [`FrameReceiver::on_frame()`] callbacks with complete frames. This is what applications that
+//! run transcription, thumbnailing or similar processing on media usually need instead of raw RTP.
+//!
+//! When video packets are lost and the gap can't be recovered by reordering, frames are dropped
+//! until the next key frame and a key frame is requested with
+//! [`Consumer::request_key_frame()`] (at most once per
+//! [`FrameReceiverOptions::key_frame_request_interval`]).
+//!
+//! Jitter buffer is polled periodically in the background, so that packets waiting for lost ones
+//! are released after [`JitterBufferOptions::max_delay`] even if consumer stops receiving packets.
+
+use super::depacketizer::{Depacketizer, DepacketizerCodec, EncodedFrame};
+use super::jitter_buffer::{JitterBuffer, JitterBufferOptions, JitterBufferOutput};
+use super::RtpPacket;
+use crate::consumer::{Consumer, WeakConsumer};
+use crate::executor::Executor;
+use crate::rtp_parameters::MimeType;
+use async_io::Timer;
+use event_listener_primitives::{Bag, HandlerId};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How often jitter buffer is checked for packets that waited for missing ones for too long.
+const JITTER_BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// [`FrameReceiver`] options.
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct FrameReceiverOptions {
+    /// Options of jitter buffer that restores packet order.
+    pub jitter_buffer: JitterBufferOptions,
+    /// Minimum interval between key frame requests.
+    ///
+    /// Default 1 second.
+    pub key_frame_request_interval: Duration,
+}
+
+impl Default for FrameReceiverOptions {
+    fn default() -> Self {
+        Self {
+            jitter_buffer: JitterBufferOptions::default(),
+            key_frame_request_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Error that caused [`FrameReceiver`] creation to fail.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum FrameReceiverError {
+    /// Consumer has no media codec.
+    #[error("Consumer has no codecs")]
+    NoCodecs,
+    /// Codec of the consumer can't be depacketized.
+    #[error("Unsupported codec {}", .0.as_str())]
+    UnsupportedCodec(MimeType),
+}
+
+struct State {
+    jitter_buffer: JitterBuffer,
+    depacketizer: Depacketizer,
+    waiting_for_key_frame: bool,
+    last_key_frame_request: Option<Instant>,
+}
+
+#[allow(clippy::type_complexity)]
+struct Inner {
+    payload_type: u8,
+    video: bool,
+    key_frame_request_interval: Duration,
+    state: Mutex<State>,
+    frame_handlers: Bag<Arc<dyn Fn(&EncodedFrame) + Send + Sync>, EncodedFrame>,
+    weak_consumer: WeakConsumer,
+    executor: Arc<dyn Executor>,
+    rtp_handler: Mutex<Option<HandlerId>>,
+}
+
+impl Inner {
+    fn handle_packet(&self, packet: &[u8]) {
+        match RtpPacket::parse(packet) {
+            Ok(rtp_packet) if rtp_packet.payload_type() == self.payload_type => {}
+            Ok(_) => {
+                return;
+            }
+            Err(error) => {
+                debug!("ignoring invalid RTP packet: {}", error);
+                return;
+            }
+        }
+
+        self.process(|jitter_buffer| {
+            jitter_buffer
+                .push(packet.to_vec(), Instant::now())
+                .expect("Packet was parsed before; qed")
+        });
+    }
+
+    fn poll(&self) {
+        self.process(|jitter_buffer| jitter_buffer.poll(Instant::now()));
+    }
+
+    /// Depacketize packets released by jitter buffer and call handlers with complete frames.
+    fn process<F>(&self, release: F)
+    where
+        F: FnOnce(&mut JitterBuffer) -> Vec<JitterBufferOutput>,
+    {
+        let mut frames = Vec::new();
+        let mut request_key_frame = false;
+        {
+            let mut state = self.state.lock();
+            let output = release(&mut state.jitter_buffer);
+
+            for output in output {
+                let packet = match output {
+                    JitterBufferOutput::Packet(packet) => packet,
+                    JitterBufferOutput::Gap { lost } => {
+                        debug!("{} packets lost", lost);
+
+                        state.depacketizer.reset();
+                        if self.video {
+                            state.waiting_for_key_frame = true;
+                            request_key_frame = true;
+                        }
+                        continue;
+                    }
+                };
+                let rtp_packet = RtpPacket::parse(&packet).expect("Packet was parsed before; qed");
+                let Some(frame) = state.depacketizer.push(&rtp_packet) else {
+                    continue;
+                };
+                if state.waiting_for_key_frame {
+                    if !frame.key_frame {
+                        request_key_frame = true;
+                        continue;
+                    }
+                    state.waiting_for_key_frame = false;
+                }
+                frames.push(frame);
+            }
+
+            if request_key_frame {
+                if state
+                    .last_key_frame_request
+                    .is_some_and(|last| last.elapsed() < self.key_frame_request_interval)
+                {
+                    request_key_frame = false;
+                } else {
+                    state.last_key_frame_request.replace(Instant::now());
+                }
+            }
+        }
+
+        if request_key_frame {
+            self.request_key_frame();
+        }
+
+        for frame in frames {
+            self.frame_handlers.call_simple(&frame);
+        }
+    }
+
+    fn request_key_frame(&self) {
+        let weak_consumer = self.weak_consumer.clone();
+
+        self.executor.spawn(Box::pin(async move {
+            let Some(consumer) = weak_consumer.upgrade() else {
+                return;
+            };
+
+            debug!("requesting key frame [consumer_id:{}]", consumer.id());
+
+            if let Err(error) = consumer.request_key_frame().await {
+                warn!(
+                    "failed to request key frame [consumer_id:{}]: {}",
+                    consumer.id(),
+                    error,
+                );
+            }
+        }));
+    }
+}
+
+/// Produces complete encoded frames of a consumer, see [module-level documentation](self).
+///
+/// Consumer must belong to a [`DirectTransport`](crate::direct_transport::DirectTransport).
+/// Frames are no longer produced once receiver is dropped.
+#[derive(Clone)]
+#[must_use = "Frames are no longer produced once receiver is dropped"]
+pub struct FrameReceiver {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for FrameReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("FrameReceiver")
+            .field("codec", &state.depacketizer.codec())
+            .field("buffered", &state.jitter_buffer.buffered())
+            .field("waiting_for_key_frame", &state.waiting_for_key_frame)
+            .finish()
+    }
+}
+
+impl FrameReceiver {
+    /// Start receiving frames of given consumer.
+    ///
+    /// For video consumers a key frame is requested immediately, since frames before the first
+    /// key frame are dropped.
+    pub fn new(
+        consumer: &Consumer,
+        options: FrameReceiverOptions,
+    ) -> Result<Self, FrameReceiverError> {
+        debug!("new()");
+
+        let codec = consumer
+            .rtp_parameters()
+            .codecs
+            .first()
+            .ok_or(FrameReceiverError::NoCodecs)?;
+        let mime_type = codec.mime_type();
+        let depacketizer_codec = DepacketizerCodec::from_mime_type(mime_type)
+            .ok_or(FrameReceiverError::UnsupportedCodec(mime_type))?;
+        let video = depacketizer_codec != DepacketizerCodec::Opus;
+
+        let inner = Arc::new(Inner {
+            payload_type: codec.payload_type(),
+            video,
+            key_frame_request_interval: options.key_frame_request_interval,
+            state: Mutex::new(State {
+                jitter_buffer: JitterBuffer::new(options.jitter_buffer),
+                depacketizer: Depacketizer::new(depacketizer_codec),
+                waiting_for_key_frame: video,
+                last_key_frame_request: video.then(Instant::now),
+            }),
+            frame_handlers: Bag::default(),
+            weak_consumer: consumer.downgrade(),
+            executor: Arc::clone(consumer.executor()),
+            rtp_handler: Mutex::default(),
+        });
+
+        let rtp_handler = consumer.on_rtp({
+            let inner_weak = Arc::downgrade(&inner);
+
+            move |packet| {
+                if let Some(inner) = Weak::upgrade(&inner_weak) {
+                    inner.handle_packet(packet);
+                }
+            }
+        });
+        inner.rtp_handler.lock().replace(rtp_handler);
+
+        inner.executor.spawn(Box::pin({
+            let inner_weak = Arc::downgrade(&inner);
+
+            async move {
+                loop {
+                    Timer::after(JITTER_BUFFER_POLL_INTERVAL).await;
+
+                    let Some(inner) = Weak::upgrade(&inner_weak) else {
+                        break;
+                    };
+                    inner.poll();
+                }
+            }
+        }));
+
+        if video {
+            inner.request_key_frame();
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Callback is called with every complete frame, in order.
+    pub fn on_frame<F: Fn(&EncodedFrame) + Send + Sync + 'static>(&self, callback: F) -> HandlerId {
+        self.inner.frame_handlers.add(Arc::new(callback))
+    }
+}
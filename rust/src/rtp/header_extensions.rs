@@ -0,0 +1,185 @@
+use super::RtpPacket;
+use crate::producer::{ProducerVideoOrientation, Rotation};
+use crate::rtp_parameters::{RtpHeaderExtensionParameters, RtpHeaderExtensionUri, RtpParameters};
+
+/// Ids of well-known header extensions negotiated for a stream.
+///
+/// Extensions that were not negotiated (or have ids that can't be present in RTP packets) are
+/// `None`, typed getters return `None` for them.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct HeaderExtensionIds {
+    /// urn:ietf:params:rtp-hdrext:sdes:mid
+    pub mid: Option<u8>,
+    /// urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id
+    pub rid: Option<u8>,
+    /// urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id
+    pub rrid: Option<u8>,
+    /// urn:ietf:params:rtp-hdrext:ssrc-audio-level
+    pub audio_level: Option<u8>,
+    /// urn:3gpp:video-orientation
+    pub video_orientation: Option<u8>,
+    /// urn:ietf:params:rtp-hdrext:toffset
+    pub time_offset: Option<u8>,
+    /// <http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01>
+    pub transport_wide_cc: Option<u8>,
+    /// <http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time>
+    pub abs_send_time: Option<u8>,
+    /// <http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time>
+    pub abs_capture_time: Option<u8>,
+}
+
+/// Value of audio level header extension as defined in
+/// [RFC 6464](https://tools.ietf.org/html/rfc6464).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AudioLevel {
+    /// Whether audio in the packet contains voice activity.
+    pub voice: bool,
+    /// Audio level in -dBov, from `0` (loudest) to `127` (silence).
+    pub level: u8,
+}
+
+impl HeaderExtensionIds {
+    /// Resolve ids from header extension parameters.
+    #[must_use]
+    pub fn new(header_extensions: &[RtpHeaderExtensionParameters]) -> Self {
+        let mut ids = Self::default();
+
+        for header_extension in header_extensions {
+            let Ok(id) = u8::try_from(header_extension.id) else {
+                continue;
+            };
+            if id == 0 {
+                continue;
+            }
+
+            let slot = match header_extension.uri {
+                RtpHeaderExtensionUri::Mid => &mut ids.mid,
+                RtpHeaderExtensionUri::RtpStreamId => &mut ids.rid,
+                RtpHeaderExtensionUri::RepairRtpStreamId => &mut ids.rrid,
+                RtpHeaderExtensionUri::AudioLevel => &mut ids.audio_level,
+                RtpHeaderExtensionUri::VideoOrientation => &mut ids.video_orientation,
+                RtpHeaderExtensionUri::TimeOffset => &mut ids.time_offset,
+                RtpHeaderExtensionUri::TransportWideCcDraft01 => &mut ids.transport_wide_cc,
+                RtpHeaderExtensionUri::AbsSendTime => &mut ids.abs_send_time,
+                RtpHeaderExtensionUri::AbsCaptureTime => &mut ids.abs_capture_time,
+                RtpHeaderExtensionUri::FrameMarkingDraft07
+                | RtpHeaderExtensionUri::FrameMarking
+                | RtpHeaderExtensionUri::DependencyDescriptor
+                | RtpHeaderExtensionUri::Unsupported => {
+                    continue;
+                }
+            };
+            slot.replace(id);
+        }
+
+        ids
+    }
+
+    /// Resolve ids from header extensions of RTP parameters (of producer or consumer).
+    #[must_use]
+    pub fn from_rtp_parameters(rtp_parameters: &RtpParameters) -> Self {
+        Self::new(&rtp_parameters.header_extensions)
+    }
+
+    fn value<'a>(id: Option<u8>, packet: &RtpPacket<'a>) -> Option<&'a [u8]> {
+        packet.extension(id?)
+    }
+
+    fn string<'a>(id: Option<u8>, packet: &RtpPacket<'a>) -> Option<&'a str> {
+        let value = Self::value(id, packet)?;
+        // Some senders pad string values with zeroes
+        let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+
+        std::str::from_utf8(&value[..end]).ok()
+    }
+
+    /// MID of the packet.
+    #[must_use]
+    pub fn mid<'a>(&self, packet: &RtpPacket<'a>) -> Option<&'a str> {
+        Self::string(self.mid, packet)
+    }
+
+    /// RID of the packet.
+    #[must_use]
+    pub fn rid<'a>(&self, packet: &RtpPacket<'a>) -> Option<&'a str> {
+        Self::string(self.rid, packet)
+    }
+
+    /// Repaired RID of the (RTX) packet.
+    #[must_use]
+    pub fn rrid<'a>(&self, packet: &RtpPacket<'a>) -> Option<&'a str> {
+        Self::string(self.rrid, packet)
+    }
+
+    /// Audio level of the packet.
+    #[must_use]
+    pub fn audio_level(&self, packet: &RtpPacket<'_>) -> Option<AudioLevel> {
+        let &[byte, ..] = Self::value(self.audio_level, packet)? else {
+            return None;
+        };
+
+        Some(AudioLevel {
+            voice: byte & 0x80 != 0,
+            level: byte & 0x7F,
+        })
+    }
+
+    /// Video orientation of the packet.
+    #[must_use]
+    pub fn video_orientation(&self, packet: &RtpPacket<'_>) -> Option<ProducerVideoOrientation> {
+        let &[byte, ..] = Self::value(self.video_orientation, packet)? else {
+            return None;
+        };
+
+        Some(ProducerVideoOrientation {
+            camera: byte & 0x08 != 0,
+            flip: byte & 0x04 != 0,
+            rotation: match byte & 0x03 {
+                0 => Rotation::None,
+                1 => Rotation::Clockwise,
+                2 => Rotation::Rotate180,
+                _ => Rotation::CounterClockwise,
+            },
+        })
+    }
+
+    /// Transmission time offset of the packet (signed 24-bit value in RTP timestamp units).
+    #[must_use]
+    pub fn time_offset(&self, packet: &RtpPacket<'_>) -> Option<i32> {
+        let &[b0, b1, b2, ..] = Self::value(self.time_offset, packet)? else {
+            return None;
+        };
+
+        // Sign-extend 24-bit value
+        Some(i32::from_be_bytes([b0, b1, b2, 0]) >> 8)
+    }
+
+    /// Transport-wide sequence number of the packet.
+    #[must_use]
+    pub fn transport_wide_sequence_number(&self, packet: &RtpPacket<'_>) -> Option<u16> {
+        let &[b0, b1, ..] = Self::value(self.transport_wide_cc, packet)? else {
+            return None;
+        };
+
+        Some(u16::from_be_bytes([b0, b1]))
+    }
+
+    /// Absolute send time of the packet (24-bit 6.18 fixed point seconds).
+    #[must_use]
+    pub fn abs_send_time(&self, packet: &RtpPacket<'_>) -> Option<u32> {
+        let &[b0, b1, b2, ..] = Self::value(self.abs_send_time, packet)? else {
+            return None;
+        };
+
+        Some(u32::from_be_bytes([0, b0, b1, b2]))
+    }
+
+    /// Absolute capture time of the packet (64-bit NTP timestamp).
+    #[must_use]
+    pub fn abs_capture_time(&self, packet: &RtpPacket<'_>) -> Option<u64> {
+        let value = Self::value(self.abs_capture_time, packet)?;
+
+        Some(u64::from_be_bytes(value.get(..8)?.try_into().ok()?))
+    }
+}
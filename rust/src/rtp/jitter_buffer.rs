@@ -0,0 +1,150 @@
+//! Restoring sequence number order of RTP packets.
+//!
+//! [`JitterBuffer`] holds out of order packets until missing ones arrive. Missing packets are
+//! considered lost once too many packets are buffered after them or once the oldest buffered
+//! packet waited for too long, in which case [`JitterBufferOutput::Gap`] is emitted so that
+//! depacketizer can be reset and key frame requested.
+//!
+//! Time is only checked when packets are pushed or when [`JitterBuffer::poll()`] is called, so
+//! applications that need strict latency bounds should call it periodically.
+
+#[cfg(test)]
+mod tests;
+
+use super::{RtpPacket, RtpParseError};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// [`JitterBuffer`] options.
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct JitterBufferOptions {
+    /// Maximum number of packets buffered while waiting for a missing one.
+    ///
+    /// Default 64.
+    pub max_packets: usize,
+    /// Maximum time a packet can wait for missing packets before it.
+    ///
+    /// Default 200ms.
+    pub max_delay: Duration,
+}
+
+impl Default for JitterBufferOptions {
+    fn default() -> Self {
+        Self {
+            max_packets: 64,
+            max_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Output of [`JitterBuffer`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JitterBufferOutput {
+    /// Next packet in sequence number order.
+    Packet(Vec<u8>),
+    /// Packets were lost and will never be delivered.
+    Gap {
+        /// Number of lost packets.
+        lost: u64,
+    },
+}
+
+/// Restores order of RTP packets, see [module-level documentation](self).
+#[derive(Debug)]
+pub struct JitterBuffer {
+    options: JitterBufferOptions,
+    /// Extended sequence number of the highest packet received so far.
+    highest: Option<u64>,
+    /// Extended sequence number of the next packet to deliver.
+    next: u64,
+    packets: BTreeMap<u64, (Instant, Vec<u8>)>,
+}
+
+impl JitterBuffer {
+    /// Create jitter buffer with given options.
+    #[must_use]
+    pub fn new(options: JitterBufferOptions) -> Self {
+        Self {
+            options,
+            highest: None,
+            next: 0,
+            packets: BTreeMap::new(),
+        }
+    }
+
+    /// Number of packets waiting for missing ones.
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Forget all buffered packets and start over with the next pushed packet (for instance when
+    /// stream is restarted with unrelated sequence numbers).
+    pub fn reset(&mut self) {
+        self.highest.take();
+        self.packets.clear();
+    }
+
+    /// Add packet received at `now`, returns packets (and gaps) that are ready in order.
+    ///
+    /// Duplicates and packets that arrive after they were considered lost are dropped.
+    pub fn push(
+        &mut self,
+        packet: Vec<u8>,
+        now: Instant,
+    ) -> Result<Vec<JitterBufferOutput>, RtpParseError> {
+        let sequence_number = RtpPacket::parse(&packet)?.sequence_number();
+
+        let extended = match self.highest {
+            Some(highest) => {
+                let diff = i64::from(sequence_number.wrapping_sub(highest as u16) as i16);
+                let extended = highest as i64 + diff;
+                if extended < self.next as i64 {
+                    return Ok(Vec::new());
+                }
+                extended as u64
+            }
+            None => {
+                // Start far enough from zero to never go negative
+                let extended = (1 << 16) + u64::from(sequence_number);
+                self.next = extended;
+                extended
+            }
+        };
+        self.highest = Some(
+            self.highest
+                .map_or(extended, |highest| highest.max(extended)),
+        );
+        self.packets.entry(extended).or_insert((now, packet));
+
+        Ok(self.poll(now))
+    }
+
+    /// Release packets that waited for missing ones for too long.
+    pub fn poll(&mut self, now: Instant) -> Vec<JitterBufferOutput> {
+        let mut output = Vec::new();
+        loop {
+            while let Some((_received_at, packet)) = self.packets.remove(&self.next) {
+                output.push(JitterBufferOutput::Packet(packet));
+                self.next += 1;
+            }
+
+            let Some((&first, (received_at, _packet))) = self.packets.iter().next() else {
+                break;
+            };
+            if self.packets.len() <= self.options.max_packets
+                && now.saturating_duration_since(*received_at) < self.options.max_delay
+            {
+                break;
+            }
+            // Give up on missing packets
+            output.push(JitterBufferOutput::Gap {
+                lost: first - self.next,
+            });
+            self.next = first;
+        }
+
+        output
+    }
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::rtp::RtpPacketBuilder;
+
+fn packet(sequence_number: u16) -> Vec<u8> {
+    RtpPacketBuilder::new(96, sequence_number, 0, 1234)
+        .build()
+        .unwrap()
+}
+
+fn sequence_numbers(output: Vec<JitterBufferOutput>) -> Vec<Option<u16>> {
+    output
+        .into_iter()
+        .map(|output| match output {
+            JitterBufferOutput::Packet(packet) => {
+                Some(RtpPacket::parse(&packet).unwrap().sequence_number())
+            }
+            JitterBufferOutput::Gap { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn reorder_and_gap_by_count() {
+    let mut jitter_buffer = JitterBuffer::new(JitterBufferOptions {
+        max_packets: 2,
+        max_delay: Duration::from_secs(10),
+    });
+    let now = Instant::now();
+
+    let mut push = |sequence_number| {
+        sequence_numbers(jitter_buffer.push(packet(sequence_number), now).unwrap())
+    };
+
+    assert_eq!(push(65535), vec![Some(65535)]);
+    assert_eq!(push(1), vec![]);
+    assert_eq!(push(0), vec![Some(0), Some(1)]);
+    // Duplicate
+    assert_eq!(push(0), vec![]);
+    // Packet 2 is lost
+    assert_eq!(push(3), vec![]);
+    assert_eq!(push(4), vec![]);
+    assert_eq!(push(5), vec![None, Some(3), Some(4), Some(5)]);
+    // Too late
+    assert_eq!(push(2), vec![]);
+
+    assert!(jitter_buffer.push(vec![0; 3], now).is_err());
+}
+
+#[test]
+fn gap_by_delay() {
+    let mut jitter_buffer = JitterBuffer::new(JitterBufferOptions {
+        max_packets: 100,
+        max_delay: Duration::from_millis(100),
+    });
+    let now = Instant::now();
+
+    assert_eq!(
+        sequence_numbers(jitter_buffer.push(packet(10), now).unwrap()),
+        vec![Some(10)],
+    );
+    assert_eq!(
+        sequence_numbers(jitter_buffer.push(packet(13), now).unwrap()),
+        vec![],
+    );
+    assert_eq!(jitter_buffer.buffered(), 1);
+    assert_eq!(jitter_buffer.poll(now + Duration::from_millis(50)), vec![]);
+    assert_eq!(
+        jitter_buffer.poll(now + Duration::from_millis(100)),
+        vec![
+            JitterBufferOutput::Gap { lost: 2 },
+            JitterBufferOutput::Packet(packet(13)),
+        ],
+    );
+
+    // Unrelated sequence numbers after reset
+    jitter_buffer.reset();
+    assert_eq!(
+        sequence_numbers(jitter_buffer.push(packet(30000), now).unwrap()),
+        vec![Some(30000)],
+    );
+}
@@ -0,0 +1,650 @@
+//! Splitting of encoded frames into RTP packets.
+//!
+//! [`Packetizer`] is the counterpart of [`Depacketizer`](super::depacketizer::Depacketizer): it
+//! takes encoded frames and produces RTP packets ready to be sent with
+//! [`DirectProducer::send()`], which allows bots and media file playback to inject media without
+//! an external RTP pipeline.
+//!
+//! Packetizer is created from producer's [`RtpParameters`] and takes care of payload formats,
+//! sequence numbers, RTP timestamps (derived from frame presentation time and codec clock rate),
+//! marker bits and values of negotiated mid, abs-send-time, transport-wide-cc and audio level
+//! header extensions.
+//!
+//! # Examples
+//! ```rust
+//! use mediasoup::rtp::packetizer::{Packetizer, PacketizerOptions};
+//! use mediasoup::rtp::RtpPacket;
+//! use mediasoup::rtp_parameters::{
+//!     MimeTypeAudio, RtpCodecParameters, RtpCodecParametersParameters, RtpEncodingParameters,
+//!     RtpParameters,
+//! };
+//! use std::num::{NonZeroU32, NonZeroU8};
+//! use std::time::Duration;
+//!
+//! let rtp_parameters = RtpParameters {
+//!     codecs: vec![RtpCodecParameters::Audio {
+//!         mime_type: MimeTypeAudio::Opus,
+//!         payload_type: 111,
+//!         clock_rate: NonZeroU32::new(48000).unwrap(),
+//!         channels: NonZeroU8::new(2).unwrap(),
+//!         parameters: RtpCodecParametersParameters::default(),
+//!         rtcp_feedback: vec![],
+//!     }],
+//!     encodings: vec![RtpEncodingParameters {
+//!         ssrc: Some(1111),
+//!         ..RtpEncodingParameters::default()
+//!     }],
+//!     ..RtpParameters::default()
+//! };
+//!
+//! let mut packetizer = Packetizer::new(&rtp_parameters, PacketizerOptions::default()).unwrap();
+//! let packets = packetizer
+//!     .packetize(&[0xFC, 0x01, 0x02], Duration::from_millis(20), None)
+//!     .unwrap();
+//!
+//! let packet = RtpPacket::parse(&packets[0]).unwrap();
+//! assert_eq!(packet.payload_type(), 111);
+//! assert_eq!(packet.ssrc(), 1111);
+//! assert_eq!(packet.payload(), &[0xFC, 0x01, 0x02]);
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use super::depacketizer::{annex_b_nal_units, read_leb128, write_leb128};
+use super::{AudioLevel, HeaderExtensionIds, RtpBuildError, RtpPacketBuilder};
+use crate::producer::DirectProducer;
+use crate::rtp_parameters::{
+    MimeType, MimeTypeAudio, MimeTypeVideo, RtpCodecParametersParametersValue, RtpParameters,
+};
+use std::num::NonZeroU32;
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+const RTP_HEADER_SIZE: usize = 12;
+const VP8_PAYLOAD_DESCRIPTOR_SIZE: usize = 4;
+const VP9_PAYLOAD_DESCRIPTOR_SIZE: usize = 3;
+
+const H264_NAL_IDR: u8 = 5;
+const H264_NAL_STAP_A: u8 = 24;
+const H264_NAL_FU_A: u8 = 28;
+
+const AV1_OBU_SEQUENCE_HEADER: u8 = 1;
+const AV1_OBU_TEMPORAL_DELIMITER: u8 = 2;
+const AV1_OBU_TILE_LIST: u8 = 8;
+const AV1_OBU_PADDING: u8 = 15;
+
+/// Codec supported by [`Packetizer`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PacketizerCodec {
+    /// Opus, every frame is sent in a single packet.
+    Opus,
+    /// VP8 as defined in [RFC 7741](https://tools.ietf.org/html/rfc7741), payload descriptor
+    /// carries 15-bit picture ID.
+    Vp8,
+    /// VP9 as defined in [RFC 9628](https://tools.ietf.org/html/rfc9628), non-flexible mode
+    /// without layer indices, payload descriptor carries 15-bit picture ID.
+    Vp9,
+    /// H264 as defined in [RFC 6184](https://tools.ietf.org/html/rfc6184), frames are expected in
+    /// Annex B format.
+    ///
+    /// NAL units are aggregated (STAP-A) and fragmented (FU-A) only when `packetization-mode` is
+    /// `1`, otherwise every NAL unit must fit into a single packet.
+    H264,
+    /// AV1 as defined in [RTP Payload Format For AV1](https://aomediacodec.github.io/av1-rtp-spec/),
+    /// frames are expected as a sequence of OBUs with size fields (low overhead bitstream format).
+    Av1,
+}
+
+impl PacketizerCodec {
+    /// Codec corresponding to MIME type, if supported.
+    #[must_use]
+    pub fn from_mime_type(mime_type: MimeType) -> Option<Self> {
+        match mime_type {
+            MimeType::Audio(MimeTypeAudio::Opus) => Some(Self::Opus),
+            MimeType::Video(MimeTypeVideo::Vp8) => Some(Self::Vp8),
+            MimeType::Video(MimeTypeVideo::Vp9) => Some(Self::Vp9),
+            MimeType::Video(MimeTypeVideo::H264) => Some(Self::H264),
+            MimeType::Video(MimeTypeVideo::Av1) => Some(Self::Av1),
+            _ => None,
+        }
+    }
+}
+
+/// [`Packetizer`] options.
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct PacketizerOptions {
+    /// Maximum size of produced RTP packets, including RTP header and header extensions.
+    ///
+    /// Default 1200.
+    pub max_packet_size: usize,
+}
+
+impl Default for PacketizerOptions {
+    fn default() -> Self {
+        Self {
+            max_packet_size: 1200,
+        }
+    }
+}
+
+/// Error that caused [`Packetizer`] creation to fail.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum PacketizerError {
+    /// RTP parameters have no media codec.
+    #[error("RTP parameters have no codecs")]
+    NoCodecs,
+    /// RTP parameters have no encoding with SSRC.
+    #[error("RTP parameters have no encoding with SSRC")]
+    NoSsrc,
+    /// Codec can't be packetized.
+    #[error("Unsupported codec {}", .0.as_str())]
+    UnsupportedCodec(MimeType),
+    /// Maximum packet size doesn't leave space for payload.
+    #[error("Maximum packet size is too small")]
+    MaxPacketSizeTooSmall,
+}
+
+/// Error that caused frame packetization to fail.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum PacketizeError {
+    /// Frame is empty or malformed.
+    #[error("Invalid frame")]
+    InvalidFrame,
+    /// Frame (or H264 NAL unit) doesn't fit into a packet and codec doesn't allow fragmentation.
+    #[error("Frame is too large")]
+    TooLarge,
+    /// Failed to build RTP packet.
+    #[error("Failed to build RTP packet: {0}")]
+    Build(#[from] RtpBuildError),
+    /// Producer channel is already closed.
+    #[error("Channel already closed")]
+    ChannelClosed,
+}
+
+/// Splits encoded frames into RTP packets, see [module-level documentation](self).
+#[derive(Debug)]
+pub struct Packetizer {
+    codec: PacketizerCodec,
+    payload_type: u8,
+    clock_rate: NonZeroU32,
+    ssrc: u32,
+    h264_non_interleaved: bool,
+    mid: Option<String>,
+    header_extension_ids: HeaderExtensionIds,
+    max_payload_size: usize,
+    sequence_number: u16,
+    transport_sequence_number: u16,
+    timestamp_offset: u32,
+    picture_id: u16,
+}
+
+impl Packetizer {
+    /// Create packetizer for the first media codec and the first encoding of given RTP parameters
+    /// (usually those of [`DirectProducer`]).
+    ///
+    /// Sequence numbers and RTP timestamp offset start from random values.
+    pub fn new(
+        rtp_parameters: &RtpParameters,
+        options: PacketizerOptions,
+    ) -> Result<Self, PacketizerError> {
+        let codec = rtp_parameters
+            .codecs
+            .iter()
+            .find(|codec| !codec.is_rtx())
+            .ok_or(PacketizerError::NoCodecs)?;
+        let mime_type = codec.mime_type();
+        let packetizer_codec = PacketizerCodec::from_mime_type(mime_type)
+            .ok_or(PacketizerError::UnsupportedCodec(mime_type))?;
+        let ssrc = rtp_parameters
+            .encodings
+            .first()
+            .and_then(|encoding| encoding.ssrc)
+            .ok_or(PacketizerError::NoSsrc)?;
+        let h264_non_interleaved = matches!(
+            codec.parameters().get("packetization-mode"),
+            Some(RtpCodecParametersParametersValue::Number(1)),
+        );
+
+        let header_extension_ids = HeaderExtensionIds::from_rtp_parameters(rtp_parameters);
+        let mid = rtp_parameters
+            .mid
+            .clone()
+            .filter(|_| header_extension_ids.mid.is_some());
+
+        // Worst case of two-byte header extensions with padding
+        let mut header_extensions_size = 4 + 3;
+        if let Some(mid) = &mid {
+            header_extensions_size += 2 + mid.len();
+        }
+        if header_extension_ids.abs_send_time.is_some() {
+            header_extensions_size += 2 + 3;
+        }
+        if header_extension_ids.transport_wide_cc.is_some() {
+            header_extensions_size += 2 + 2;
+        }
+        if header_extension_ids.audio_level.is_some() {
+            header_extensions_size += 2 + 1;
+        }
+        let max_payload_size = options
+            .max_packet_size
+            .checked_sub(RTP_HEADER_SIZE + header_extensions_size)
+            .filter(|&size| {
+                // Enough for payload headers and at least one byte of data
+                size > VP8_PAYLOAD_DESCRIPTOR_SIZE
+            })
+            .ok_or(PacketizerError::MaxPacketSizeTooSmall)?;
+
+        Ok(Self {
+            codec: packetizer_codec,
+            payload_type: codec.payload_type(),
+            clock_rate: codec.clock_rate(),
+            ssrc,
+            h264_non_interleaved,
+            mid,
+            header_extension_ids,
+            max_payload_size,
+            sequence_number: fastrand::u16(..),
+            transport_sequence_number: fastrand::u16(..),
+            timestamp_offset: fastrand::u32(..),
+            picture_id: fastrand::u16(..0x8000),
+        })
+    }
+
+    /// Codec of this packetizer.
+    #[must_use]
+    pub fn codec(&self) -> PacketizerCodec {
+        self.codec
+    }
+
+    /// SSRC of produced packets.
+    #[must_use]
+    pub fn ssrc(&self) -> u32 {
+        self.ssrc
+    }
+
+    /// Split frame into RTP packets.
+    ///
+    /// `presentation_time` is the time of the frame relative to the beginning of the stream, it is
+    /// converted to RTP timestamp using codec clock rate. `audio_level` is only written into
+    /// packets when audio level header extension was negotiated.
+    pub fn packetize(
+        &mut self,
+        frame: &[u8],
+        presentation_time: Duration,
+        audio_level: Option<AudioLevel>,
+    ) -> Result<Vec<Vec<u8>>, PacketizeError> {
+        if frame.is_empty() {
+            return Err(PacketizeError::InvalidFrame);
+        }
+
+        let payloads = match self.codec {
+            PacketizerCodec::Opus => {
+                if frame.len() > self.max_payload_size {
+                    return Err(PacketizeError::TooLarge);
+                }
+                vec![frame.to_vec()]
+            }
+            PacketizerCodec::Vp8 => {
+                let payloads = vp8_payloads(frame, self.picture_id, self.max_payload_size);
+                self.picture_id = (self.picture_id + 1) & 0x7FFF;
+                payloads
+            }
+            PacketizerCodec::Vp9 => {
+                let payloads = vp9_payloads(frame, self.picture_id, self.max_payload_size);
+                self.picture_id = (self.picture_id + 1) & 0x7FFF;
+                payloads
+            }
+            PacketizerCodec::H264 => {
+                h264_payloads(frame, self.h264_non_interleaved, self.max_payload_size)?
+            }
+            PacketizerCodec::Av1 => av1_payloads(frame, self.max_payload_size)?,
+        };
+
+        let timestamp = self.timestamp_offset.wrapping_add(
+            (presentation_time.as_nanos() * u128::from(self.clock_rate.get()) / 1_000_000_000)
+                as u32,
+        );
+        let last = payloads.len() - 1;
+
+        payloads
+            .into_iter()
+            .enumerate()
+            .map(|(index, payload)| {
+                let mut builder = RtpPacketBuilder::new(
+                    self.payload_type,
+                    self.sequence_number,
+                    timestamp,
+                    self.ssrc,
+                );
+                self.sequence_number = self.sequence_number.wrapping_add(1);
+                // Audio frames are independent, marker only signals the end of video frame
+                builder.marker = self.codec != PacketizerCodec::Opus && index == last;
+                builder.extensions = self.header_extensions(audio_level);
+                builder.payload = payload;
+
+                builder.build().map_err(PacketizeError::from)
+            })
+            .collect()
+    }
+
+    /// Split frame into RTP packets with [`Packetizer::packetize()`] and send them through given
+    /// producer.
+    pub fn send(
+        &mut self,
+        producer: &DirectProducer,
+        frame: &[u8],
+        presentation_time: Duration,
+        audio_level: Option<AudioLevel>,
+    ) -> Result<(), PacketizeError> {
+        for packet in self.packetize(frame, presentation_time, audio_level)? {
+            producer
+                .send(packet)
+                .map_err(|_| PacketizeError::ChannelClosed)?;
+        }
+
+        Ok(())
+    }
+
+    fn header_extensions(&mut self, audio_level: Option<AudioLevel>) -> Vec<(u8, Vec<u8>)> {
+        let ids = self.header_extension_ids;
+        let mut extensions = Vec::new();
+
+        if let (Some(id), Some(mid)) = (ids.mid, &self.mid) {
+            extensions.push((id, mid.as_bytes().to_vec()));
+        }
+        if let Some(id) = ids.abs_send_time {
+            extensions.push((id, abs_send_time(SystemTime::now()).to_vec()));
+        }
+        if let Some(id) = ids.transport_wide_cc {
+            extensions.push((id, self.transport_sequence_number.to_be_bytes().to_vec()));
+            self.transport_sequence_number = self.transport_sequence_number.wrapping_add(1);
+        }
+        if let (Some(id), Some(audio_level)) = (ids.audio_level, audio_level) {
+            extensions.push((
+                id,
+                vec![u8::from(audio_level.voice) << 7 | audio_level.level.min(127)],
+            ));
+        }
+
+        extensions
+    }
+}
+
+/// 6.18 fixed point seconds, wrapping every 64 seconds.
+fn abs_send_time(now: SystemTime) -> [u8; 3] {
+    let since_epoch = now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let value = (since_epoch.as_nanos() << 18) / 1_000_000_000;
+    let [.., a, b, c] = (value as u32).to_be_bytes();
+
+    [a, b, c]
+}
+
+/// Splits `size` bytes into the smallest number of chunks that fit into `capacity`, with sizes as
+/// equal as possible.
+fn split_evenly(size: usize, capacity: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let chunks = ((size + capacity - 1) / capacity).max(1);
+    let chunk_size = size / chunks;
+    let remainder = size % chunks;
+
+    (0..chunks).scan(0, move |start, index| {
+        let end = *start + chunk_size + usize::from(index < remainder);
+        let range = *start..end;
+        *start = end;
+        Some(range)
+    })
+}
+
+fn vp8_payloads(frame: &[u8], picture_id: u16, max_payload_size: usize) -> Vec<Vec<u8>> {
+    split_evenly(frame.len(), max_payload_size - VP8_PAYLOAD_DESCRIPTOR_SIZE)
+        .map(|range| {
+            let start_of_partition = range.start == 0;
+            let mut payload = Vec::with_capacity(VP8_PAYLOAD_DESCRIPTOR_SIZE + range.len());
+            // X and S bits, partition index 0
+            payload.push(0x80 | u8::from(start_of_partition) << 4);
+            // I bit
+            payload.push(0x80);
+            // M bit and 15-bit picture ID
+            payload.extend_from_slice(&(0x8000 | picture_id).to_be_bytes());
+            payload.extend_from_slice(&frame[range]);
+            payload
+        })
+        .collect()
+}
+
+fn vp9_payloads(frame: &[u8], picture_id: u16, max_payload_size: usize) -> Vec<Vec<u8>> {
+    let inter_picture_predicted = !is_key_frame(PacketizerCodec::Vp9, frame);
+    let mut fragments =
+        split_evenly(frame.len(), max_payload_size - VP9_PAYLOAD_DESCRIPTOR_SIZE).peekable();
+    let mut payloads = Vec::new();
+    while let Some(range) = fragments.next() {
+        let beginning_of_frame = range.start == 0;
+        let end_of_frame = fragments.peek().is_none();
+        let mut payload = Vec::with_capacity(VP9_PAYLOAD_DESCRIPTOR_SIZE + range.len());
+        // I, P, B and E bits
+        payload.push(
+            0x80 | u8::from(inter_picture_predicted) << 6
+                | u8::from(beginning_of_frame) << 3
+                | u8::from(end_of_frame) << 2,
+        );
+        // M bit and 15-bit picture ID
+        payload.extend_from_slice(&(0x8000 | picture_id).to_be_bytes());
+        payload.extend_from_slice(&frame[range]);
+        payloads.push(payload);
+    }
+
+    payloads
+}
+
+fn h264_payloads(
+    frame: &[u8],
+    non_interleaved: bool,
+    max_payload_size: usize,
+) -> Result<Vec<Vec<u8>>, PacketizeError> {
+    let nal_units = annex_b_nal_units(frame).collect::<Vec<_>>();
+    if nal_units.is_empty() {
+        return Err(PacketizeError::InvalidFrame);
+    }
+
+    let mut payloads = Vec::new();
+    let mut index = 0;
+    while index < nal_units.len() {
+        let nal_unit = nal_units[index];
+
+        if nal_unit.len() > max_payload_size {
+            if !non_interleaved || nal_unit.len() < 2 {
+                return Err(PacketizeError::TooLarge);
+            }
+            let fu_indicator = nal_unit[0] & 0xE0 | H264_NAL_FU_A;
+            let nal_unit_type = nal_unit[0] & 0x1F;
+            let data = &nal_unit[1..];
+            let mut fragments = split_evenly(data.len(), max_payload_size - 2).peekable();
+            while let Some(range) = fragments.next() {
+                let start = range.start == 0;
+                let end = fragments.peek().is_none();
+                let mut payload = Vec::with_capacity(2 + range.len());
+                payload.push(fu_indicator);
+                payload.push(u8::from(start) << 7 | u8::from(end) << 6 | nal_unit_type);
+                payload.extend_from_slice(&data[range]);
+                payloads.push(payload);
+            }
+            index += 1;
+            continue;
+        }
+
+        // Aggregate as many following NAL units as fit into a single STAP-A packet
+        let mut end = index + 1;
+        if non_interleaved {
+            let mut size = 1 + 2 + nal_unit.len();
+            while let Some(next) = nal_units.get(end) {
+                if size + 2 + next.len() > max_payload_size {
+                    break;
+                }
+                size += 2 + next.len();
+                end += 1;
+            }
+        }
+
+        if end - index == 1 {
+            payloads.push(nal_unit.to_vec());
+        } else {
+            let aggregated = &nal_units[index..end];
+            let forbidden_bit = aggregated.iter().fold(0, |bit, n| bit | n[0] & 0x80);
+            let nri = aggregated.iter().map(|n| n[0] & 0x60).max().unwrap_or(0);
+            let mut payload = vec![forbidden_bit | nri | H264_NAL_STAP_A];
+            for nal_unit in aggregated {
+                payload.extend_from_slice(&(nal_unit.len() as u16).to_be_bytes());
+                payload.extend_from_slice(nal_unit);
+            }
+            payloads.push(payload);
+        }
+        index = end;
+    }
+
+    Ok(payloads)
+}
+
+fn av1_payloads(frame: &[u8], max_payload_size: usize) -> Result<Vec<Vec<u8>>, PacketizeError> {
+    // OBU elements without size fields
+    let mut elements = Vec::new();
+    let mut new_coded_video_sequence = false;
+    let mut rest = frame;
+    while let Some(&header) = rest.first() {
+        let obu_type = (header >> 3) & 0x0F;
+        let header_size = if header & 0x04 != 0 { 2 } else { 1 };
+        let Some(header_bytes) = rest.get(..header_size) else {
+            return Err(PacketizeError::InvalidFrame);
+        };
+        let (payload_size, size_field_size) = if header & 0x02 != 0 {
+            let (size, size_field_size) =
+                read_leb128(&rest[header_size..]).ok_or(PacketizeError::InvalidFrame)?;
+            (
+                usize::try_from(size).map_err(|_| PacketizeError::InvalidFrame)?,
+                size_field_size,
+            )
+        } else {
+            // The last OBU may omit size field
+            (rest.len() - header_size, 0)
+        };
+        let payload_start = header_size + size_field_size;
+        let Some(payload) = rest.get(payload_start..payload_start + payload_size) else {
+            return Err(PacketizeError::InvalidFrame);
+        };
+        rest = &rest[payload_start + payload_size..];
+
+        if matches!(
+            obu_type,
+            AV1_OBU_TEMPORAL_DELIMITER | AV1_OBU_TILE_LIST | AV1_OBU_PADDING
+        ) {
+            continue;
+        }
+        if obu_type == AV1_OBU_SEQUENCE_HEADER {
+            new_coded_video_sequence = true;
+        }
+
+        let mut element = Vec::with_capacity(header_size + payload.len());
+        element.push(header & !0x02);
+        element.extend_from_slice(&header_bytes[1..]);
+        element.extend_from_slice(payload);
+        elements.push(element);
+    }
+    if elements.is_empty() {
+        return Err(PacketizeError::InvalidFrame);
+    }
+
+    // Every element is prefixed with its length (W = 0), elements that don't fit are continued in
+    // the next packet
+    let mut payloads = vec![vec![u8::from(new_coded_video_sequence) << 3]];
+    for element in &elements {
+        let mut rest = element.as_slice();
+        let mut first_fragment = true;
+        while !rest.is_empty() {
+            // At least 1 byte of length and 1 byte of data
+            if max_payload_size - payloads.last().map_or(0, Vec::len) < 2 {
+                payloads.push(vec![0]);
+            }
+            let payload = payloads.last_mut().unwrap();
+            let space = max_payload_size - payload.len();
+            if payload.len() == 1 && !first_fragment {
+                // Z bit, the first element continues OBU from previous packet
+                payload[0] |= 0x80;
+            }
+
+            let mut take = rest.len().min(space - 1);
+            while leb128_size(take as u64) + take > space {
+                take -= 1;
+            }
+            write_leb128(payload, take as u64);
+            payload.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            first_fragment = false;
+
+            if !rest.is_empty() {
+                // Y bit, the last element continues in the next packet
+                payload[0] |= 0x40;
+                payloads.push(vec![0]);
+            }
+        }
+    }
+
+    Ok(payloads)
+}
+
+/// Whether frame can be decoded without previous frames, judging by its bitstream.
+pub(crate) fn is_key_frame(codec: PacketizerCodec, frame: &[u8]) -> bool {
+    match codec {
+        PacketizerCodec::Opus => true,
+        // Inverse key frame flag in the frame tag
+        PacketizerCodec::Vp8 => frame.first().is_some_and(|tag| tag & 0x01 == 0),
+        PacketizerCodec::Vp9 => {
+            let Some(&header) = frame.first() else {
+                return false;
+            };
+            // Frame marker (2 bits), profile (2 bits, plus reserved bit for profile 3)
+            let profile = (header >> 5) & 0x01 | (header >> 3) & 0x02;
+            let mut bit = if profile == 3 { 5 } else { 4 };
+            let show_existing_frame = header & (0x80 >> bit) != 0;
+            bit += 1;
+            // Frame type 0 is key frame
+            !show_existing_frame && header & (0x80 >> bit) == 0
+        }
+        PacketizerCodec::H264 => {
+            annex_b_nal_units(frame).any(|nal_unit| nal_unit[0] & 0x1F == H264_NAL_IDR)
+        }
+        PacketizerCodec::Av1 => {
+            // Key frames of AV1 streams start new coded video sequence
+            let mut rest = frame;
+            while let Some(&header) = rest.first() {
+                if (header >> 3) & 0x0F == AV1_OBU_SEQUENCE_HEADER {
+                    return true;
+                }
+                let header_size = if header & 0x04 != 0 { 2 } else { 1 };
+                if header & 0x02 == 0 {
+                    break;
+                }
+                let Some((size, size_field_size)) = rest.get(header_size..).and_then(read_leb128)
+                else {
+                    break;
+                };
+                let Some(next) = usize::try_from(size)
+                    .ok()
+                    .and_then(|size| rest.get(header_size + size_field_size + size..))
+                else {
+                    break;
+                };
+                rest = next;
+            }
+            false
+        }
+    }
+}
+
+fn leb128_size(value: u64) -> usize {
+    let bits = 64 - value.leading_zeros() as usize;
+
+    ((bits + 6) / 7).max(1)
+}
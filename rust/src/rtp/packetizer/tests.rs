@@ -0,0 +1,397 @@
+use super::*;
+use crate::rtp::depacketizer::{Depacketizer, DepacketizerCodec};
+use crate::rtp::RtpPacket;
+use crate::rtp_parameters::{
+    RtpCodecParameters, RtpCodecParametersParameters, RtpEncodingParameters,
+    RtpHeaderExtensionParameters, RtpHeaderExtensionUri,
+};
+use std::num::NonZeroU8;
+
+fn opus_rtp_parameters() -> RtpParameters {
+    RtpParameters {
+        mid: Some("a".to_string()),
+        codecs: vec![RtpCodecParameters::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            payload_type: 111,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        }],
+        encodings: vec![RtpEncodingParameters {
+            ssrc: Some(1111),
+            ..RtpEncodingParameters::default()
+        }],
+        ..RtpParameters::default()
+    }
+}
+
+fn video_rtp_parameters(
+    mime_type: MimeTypeVideo,
+    parameters: RtpCodecParametersParameters,
+) -> RtpParameters {
+    RtpParameters {
+        codecs: vec![RtpCodecParameters::Video {
+            mime_type,
+            payload_type: 96,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters,
+            rtcp_feedback: vec![],
+        }],
+        encodings: vec![RtpEncodingParameters {
+            ssrc: Some(2222),
+            ..RtpEncodingParameters::default()
+        }],
+        ..RtpParameters::default()
+    }
+}
+
+fn options(max_packet_size: usize) -> PacketizerOptions {
+    PacketizerOptions { max_packet_size }
+}
+
+/// Depacketize packets of a single frame, checking sequence numbers and marker along the way.
+fn depacketize(codec: DepacketizerCodec, packets: &[Vec<u8>]) -> Vec<u8> {
+    let mut depacketizer = Depacketizer::new(codec);
+    let mut frame = None;
+    for (index, data) in packets.iter().enumerate() {
+        let packet = RtpPacket::parse(data).unwrap();
+        assert_eq!(packet.marker(), index == packets.len() - 1);
+        if index > 0 {
+            let previous = RtpPacket::parse(&packets[index - 1]).unwrap();
+            assert_eq!(
+                packet.sequence_number(),
+                previous.sequence_number().wrapping_add(1),
+            );
+            assert_eq!(packet.timestamp(), previous.timestamp());
+        }
+        frame = depacketizer.push(&packet);
+    }
+
+    frame.expect("Frame must be complete").data
+}
+
+#[test]
+fn opus() {
+    let mut packetizer = Packetizer::new(&opus_rtp_parameters(), options(100)).unwrap();
+    assert_eq!(packetizer.codec(), PacketizerCodec::Opus);
+    assert_eq!(packetizer.ssrc(), 1111);
+
+    let first = packetizer
+        .packetize(&[0xFC, 0x01], Duration::ZERO, None)
+        .unwrap();
+    let second = packetizer
+        .packetize(&[0xFC, 0x02], Duration::from_millis(20), None)
+        .unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+
+    let first = RtpPacket::parse(&first[0]).unwrap();
+    let second = RtpPacket::parse(&second[0]).unwrap();
+    assert_eq!(first.payload_type(), 111);
+    assert!(!first.marker());
+    assert_eq!(first.payload(), &[0xFC, 0x01]);
+    assert_eq!(
+        second.sequence_number(),
+        first.sequence_number().wrapping_add(1),
+    );
+    assert_eq!(second.timestamp(), first.timestamp().wrapping_add(960));
+    // Mid header extension was not negotiated
+    assert_eq!(first.header_extension_profile(), None);
+
+    assert_eq!(
+        packetizer.packetize(&[], Duration::ZERO, None),
+        Err(PacketizeError::InvalidFrame),
+    );
+    assert_eq!(
+        packetizer.packetize(&[0; 100], Duration::ZERO, None),
+        Err(PacketizeError::TooLarge),
+    );
+}
+
+#[test]
+fn header_extensions() {
+    let mut rtp_parameters = opus_rtp_parameters();
+    rtp_parameters.header_extensions = [
+        (RtpHeaderExtensionUri::Mid, 1),
+        (RtpHeaderExtensionUri::AbsSendTime, 2),
+        (RtpHeaderExtensionUri::TransportWideCcDraft01, 3),
+        (RtpHeaderExtensionUri::AudioLevel, 4),
+    ]
+    .into_iter()
+    .map(|(uri, id)| RtpHeaderExtensionParameters {
+        uri,
+        id,
+        encrypt: false,
+    })
+    .collect();
+    let ids = HeaderExtensionIds::from_rtp_parameters(&rtp_parameters);
+
+    let mut packetizer = Packetizer::new(&rtp_parameters, PacketizerOptions::default()).unwrap();
+    let audio_level = AudioLevel {
+        voice: true,
+        level: 30,
+    };
+    let first = packetizer
+        .packetize(&[0xFC], Duration::ZERO, Some(audio_level))
+        .unwrap();
+    let second = packetizer
+        .packetize(&[0xFC], Duration::from_millis(20), None)
+        .unwrap();
+
+    let first = RtpPacket::parse(&first[0]).unwrap();
+    let second = RtpPacket::parse(&second[0]).unwrap();
+    assert_eq!(ids.mid(&first), Some("a"));
+    assert!(ids.abs_send_time(&first).is_some());
+    assert_eq!(ids.audio_level(&first), Some(audio_level));
+    assert_eq!(ids.audio_level(&second), None);
+    assert_eq!(
+        ids.transport_wide_sequence_number(&second),
+        ids.transport_wide_sequence_number(&first)
+            .map(|sequence_number| sequence_number.wrapping_add(1)),
+    );
+}
+
+#[test]
+fn vp8() {
+    let mut packetizer = Packetizer::new(
+        &video_rtp_parameters(MimeTypeVideo::Vp8, RtpCodecParametersParameters::default()),
+        options(500),
+    )
+    .unwrap();
+
+    // Key frame
+    let frame = (0..3000).map(|i| (i * 2) as u8).collect::<Vec<_>>();
+    let packets = packetizer.packetize(&frame, Duration::ZERO, None).unwrap();
+    assert_eq!(packets.len(), 7);
+    assert!(packets.iter().all(|packet| packet.len() <= 500));
+    assert_eq!(depacketize(DepacketizerCodec::Vp8, &packets), frame);
+
+    // Picture ID is incremented for every frame
+    let picture_id = |packet: &[u8]| {
+        let payload = RtpPacket::parse(packet).unwrap().payload();
+        u16::from_be_bytes([payload[2], payload[3]]) & 0x7FFF
+    };
+    let next_packets = packetizer
+        .packetize(&frame[..10], Duration::from_millis(33), None)
+        .unwrap();
+    assert_eq!(next_packets.len(), 1);
+    assert_eq!(
+        picture_id(&next_packets[0]),
+        (picture_id(&packets[0]) + 1) & 0x7FFF,
+    );
+}
+
+#[test]
+fn vp9() {
+    let mut packetizer = Packetizer::new(
+        &video_rtp_parameters(MimeTypeVideo::Vp9, RtpCodecParametersParameters::default()),
+        options(500),
+    )
+    .unwrap();
+
+    // Profile 0 key frame
+    let key_frame = [0x82, 0x49, 0x83, 0x42, 0x00]
+        .into_iter()
+        .chain((0..1000).map(|i| i as u8))
+        .collect::<Vec<_>>();
+    let packets = packetizer
+        .packetize(&key_frame, Duration::ZERO, None)
+        .unwrap();
+    assert_eq!(packets.len(), 3);
+    assert_eq!(depacketize(DepacketizerCodec::Vp9, &packets), key_frame);
+
+    // Inter frame has P bit set
+    let packets = packetizer
+        .packetize(&[0x86, 0x00, 0x01], Duration::from_millis(33), None)
+        .unwrap();
+    assert_eq!(
+        RtpPacket::parse(&packets[0]).unwrap().payload()[0],
+        0x80 | 0x40 | 0x08 | 0x04,
+    );
+}
+
+#[test]
+fn key_frames() {
+    assert!(is_key_frame(PacketizerCodec::Opus, &[0xFC]));
+    assert!(is_key_frame(PacketizerCodec::Vp8, &[0x10, 0x02, 0x00]));
+    assert!(!is_key_frame(PacketizerCodec::Vp8, &[0x11, 0x02, 0x00]));
+    assert!(is_key_frame(PacketizerCodec::Vp9, &[0x82, 0x49]));
+    assert!(!is_key_frame(PacketizerCodec::Vp9, &[0x86, 0x00]));
+    // Profile 3 has reserved bit before show existing frame flag
+    assert!(is_key_frame(PacketizerCodec::Vp9, &[0xB0, 0x00]));
+    // Show existing frame
+    assert!(!is_key_frame(PacketizerCodec::Vp9, &[0x88]));
+    assert!(is_key_frame(
+        PacketizerCodec::H264,
+        &[0, 0, 0, 1, 0x65, 0x88]
+    ));
+    assert!(!is_key_frame(
+        PacketizerCodec::H264,
+        &[0, 0, 0, 1, 0x41, 0x9A]
+    ));
+    // Frame OBU, then sequence header
+    assert!(is_key_frame(
+        PacketizerCodec::Av1,
+        &[0x32, 0x01, 0x00, 0x0A, 0x01, 0x00],
+    ));
+    assert!(!is_key_frame(PacketizerCodec::Av1, &[0x32, 0x01, 0x00]));
+}
+
+#[test]
+fn h264() {
+    let sps = [0x67, 0x42, 0xC0, 0x1F];
+    let pps = [0x68, 0xCE, 0x3C, 0x80];
+    let idr = std::iter::once(0x65)
+        .chain((0..2000).map(|i| i as u8))
+        .collect::<Vec<_>>();
+    let frame = [
+        &[0, 0, 0, 1][..],
+        &sps,
+        &[0, 0, 0, 1],
+        &pps,
+        &[0, 0, 0, 1],
+        &idr,
+    ]
+    .concat();
+
+    let mut parameters = RtpCodecParametersParameters::default();
+    parameters.insert("packetization-mode", 1_u32);
+    let mut packetizer = Packetizer::new(
+        &video_rtp_parameters(MimeTypeVideo::H264, parameters),
+        options(500),
+    )
+    .unwrap();
+    let packets = packetizer.packetize(&frame, Duration::ZERO, None).unwrap();
+    // STAP-A with SPS and PPS, then IDR in FU-A fragments
+    assert_eq!(packets.len(), 6);
+    assert_eq!(
+        RtpPacket::parse(&packets[0]).unwrap().payload()[0] & 0x1F,
+        H264_NAL_STAP_A,
+    );
+    assert!(packets[1..]
+        .iter()
+        .all(|packet| RtpPacket::parse(packet).unwrap().payload()[0] & 0x1F == H264_NAL_FU_A));
+    assert_eq!(depacketize(DepacketizerCodec::H264, &packets), frame);
+
+    // Single NAL unit mode doesn't allow aggregation and fragmentation
+    let mut packetizer = Packetizer::new(
+        &video_rtp_parameters(MimeTypeVideo::H264, RtpCodecParametersParameters::default()),
+        options(500),
+    )
+    .unwrap();
+    let small_frame = [&[0, 0, 0, 1][..], &sps, &[0, 0, 0, 1], &pps].concat();
+    let packets = packetizer
+        .packetize(&small_frame, Duration::ZERO, None)
+        .unwrap();
+    assert_eq!(packets.len(), 2);
+    assert_eq!(depacketize(DepacketizerCodec::H264, &packets), small_frame);
+    assert_eq!(
+        packetizer.packetize(&frame, Duration::ZERO, None),
+        Err(PacketizeError::TooLarge),
+    );
+    assert_eq!(
+        packetizer.packetize(&[0, 0, 0, 1], Duration::ZERO, None),
+        Err(PacketizeError::InvalidFrame),
+    );
+}
+
+#[test]
+fn av1() {
+    let frame_obu = std::iter::once(0x36)
+        // Extension: temporal layer 1, spatial layer 0
+        .chain([0x20])
+        .chain({
+            let mut size = Vec::new();
+            write_leb128(&mut size, 1000);
+            size
+        })
+        .chain((0..1000).map(|i| i as u8))
+        .collect::<Vec<_>>();
+    let sequence_header = [0x0A, 0x02, 0x01, 0x02];
+    let temporal_delimiter = [0x12, 0x00];
+    let frame = [&temporal_delimiter[..], &sequence_header, &frame_obu].concat();
+
+    let payloads = av1_payloads(&frame, 300).unwrap();
+    assert_eq!(payloads.len(), 4);
+    assert!(payloads.iter().all(|payload| payload.len() <= 300));
+    // New coded video sequence, Z and Y bits of fragments
+    assert_eq!(payloads[0][0], 0x48);
+    assert_eq!(payloads[1][0], 0xC0);
+    assert_eq!(payloads[3][0], 0x80);
+
+    let packets = payloads
+        .into_iter()
+        .enumerate()
+        .map(|(index, payload)| {
+            let mut builder = RtpPacketBuilder::new(96, index as u16, 3000, 2222);
+            builder.marker = index == 3;
+            builder.payload = payload;
+            builder.build().unwrap()
+        })
+        .collect::<Vec<_>>();
+    // Temporal delimiter is dropped
+    assert_eq!(
+        depacketize(DepacketizerCodec::Av1, &packets),
+        [&sequence_header[..], &frame_obu].concat(),
+    );
+
+    // Truncated OBU
+    assert_eq!(
+        av1_payloads(&sequence_header[..3], 300),
+        Err(PacketizeError::InvalidFrame),
+    );
+
+    // Round trip through codecs selected from AV1 MIME type
+    let mut packetizer = Packetizer::new(
+        &video_rtp_parameters(MimeTypeVideo::Av1, RtpCodecParametersParameters::default()),
+        options(300),
+    )
+    .unwrap();
+    assert_eq!(packetizer.codec(), PacketizerCodec::Av1);
+    let packets = packetizer.packetize(&frame, Duration::ZERO, None).unwrap();
+    assert_eq!(
+        depacketize(
+            DepacketizerCodec::from_mime_type(MimeType::Video(MimeTypeVideo::Av1)).unwrap(),
+            &packets,
+        ),
+        [&sequence_header[..], &frame_obu].concat(),
+    );
+}
+
+#[test]
+fn create_fails() {
+    let mut rtp_parameters = opus_rtp_parameters();
+    assert_eq!(
+        Packetizer::new(&rtp_parameters, options(20)).err(),
+        Some(PacketizerError::MaxPacketSizeTooSmall),
+    );
+
+    rtp_parameters.encodings.clear();
+    assert_eq!(
+        Packetizer::new(&rtp_parameters, PacketizerOptions::default()).err(),
+        Some(PacketizerError::NoSsrc),
+    );
+
+    assert_eq!(
+        Packetizer::new(
+            &video_rtp_parameters(MimeTypeVideo::H265, RtpCodecParametersParameters::default()),
+            PacketizerOptions::default(),
+        )
+        .err(),
+        Some(PacketizerError::UnsupportedCodec(MimeType::Video(
+            MimeTypeVideo::H265
+        ))),
+    );
+}
+
+#[test]
+fn split() {
+    assert_eq!(
+        split_evenly(10, 4).collect::<Vec<_>>(),
+        vec![0..4, 4..7, 7..10]
+    );
+    assert_eq!(split_evenly(8, 4).collect::<Vec<_>>(), vec![0..4, 4..8]);
+    assert_eq!(leb128_size(127), 1);
+    assert_eq!(leb128_size(128), 2);
+}
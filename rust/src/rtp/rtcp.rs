@@ -0,0 +1,934 @@
+//! Parsing and building of RTCP packets.
+//!
+//! Supported packets are Sender Report, Receiver Report, SDES and BYE from
+//! [RFC 3550](https://tools.ietf.org/html/rfc3550), Generic NACK, PLI and FIR from
+//! [RFC 4585](https://tools.ietf.org/html/rfc4585) and
+//! [RFC 5104](https://tools.ietf.org/html/rfc5104), REMB from
+//! [draft-alvestrand-rmcat-remb](https://tools.ietf.org/html/draft-alvestrand-rmcat-remb-03) and
+//! transport-wide congestion control feedback from
+//! [draft-holmer-rmcat-transport-wide-cc-extensions](https://tools.ietf.org/html/draft-holmer-rmcat-transport-wide-cc-extensions-01).
+//! Everything else is returned as [`RtcpPacket::Unknown`].
+//!
+//! # Examples
+//! ```rust
+//! use mediasoup::rtp::rtcp::{self, Pli, RtcpPacket};
+//!
+//! let data = rtcp::build_compound(&[RtcpPacket::Pli(Pli {
+//!     sender_ssrc: 1,
+//!     media_ssrc: 2,
+//! })])
+//! .unwrap();
+//!
+//! for packet in rtcp::parse_compound(&data) {
+//!     if let RtcpPacket::Pli(pli) = packet.unwrap() {
+//!         assert_eq!(pli.media_ssrc, 2);
+//!     }
+//! }
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use thiserror::Error;
+
+const HEADER_SIZE: usize = 4;
+const REPORT_BLOCK_SIZE: usize = 24;
+const MAX_COUNT: usize = 31;
+
+const PT_SR: u8 = 200;
+const PT_RR: u8 = 201;
+const PT_SDES: u8 = 202;
+const PT_BYE: u8 = 203;
+const PT_RTPFB: u8 = 205;
+const PT_PSFB: u8 = 206;
+
+const RTPFB_NACK: u8 = 1;
+const RTPFB_TWCC: u8 = 15;
+const PSFB_PLI: u8 = 1;
+const PSFB_FIR: u8 = 4;
+const PSFB_AFB: u8 = 15;
+
+const REMB_IDENTIFIER: &[u8; 4] = b"REMB";
+
+/// SDES item type of canonical name.
+pub const SDES_CNAME: u8 = 1;
+
+/// Error that caused RTCP packet parsing to fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RtcpParseError {
+    /// Packet is shorter than its header or contents claim.
+    #[error("Packet is too short")]
+    TooShort,
+    /// RTCP version is not 2.
+    #[error("Unsupported RTCP version {0}")]
+    UnsupportedVersion(u8),
+    /// Padding size is zero or larger than the packet.
+    #[error("Invalid padding")]
+    InvalidPadding,
+}
+
+/// Error that caused RTCP packet building to fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RtcpBuildError {
+    /// More items than packet header can describe (31 report blocks, SDES chunks or BYE sources,
+    /// 255 REMB sources).
+    #[error("Too many items in packet")]
+    TooManyItems,
+    /// SDES item or BYE reason is longer than 255 bytes.
+    #[error("Value is too long")]
+    ValueTooLong,
+    /// Value doesn't fit into the field of the packet (for instance transport-wide feedback
+    /// receive delta).
+    #[error("Value is out of range")]
+    ValueOutOfRange,
+    /// Packet is longer than RTCP length field allows.
+    #[error("Packet is too large")]
+    TooLarge,
+}
+
+/// Reception report block of Sender and Receiver Reports.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ReportBlock {
+    /// SSRC of the reported source.
+    pub ssrc: u32,
+    /// Fraction of packets lost since the previous report, in 1/256 units.
+    pub fraction_lost: u8,
+    /// Cumulative number of packets lost (signed 24-bit value).
+    pub total_lost: i32,
+    /// Extended highest sequence number received.
+    pub highest_sequence_number: u32,
+    /// Interarrival jitter in RTP timestamp units.
+    pub jitter: u32,
+    /// Middle 32 bits of NTP timestamp of the last Sender Report received.
+    pub last_sr: u32,
+    /// Delay since the last Sender Report received, in 1/65536 seconds.
+    pub delay_since_last_sr: u32,
+}
+
+/// Sender Report.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SenderReport {
+    /// SSRC of the sender.
+    pub ssrc: u32,
+    /// 64-bit NTP timestamp.
+    pub ntp_timestamp: u64,
+    /// RTP timestamp corresponding to NTP timestamp.
+    pub rtp_timestamp: u32,
+    /// Number of RTP packets sent.
+    pub packet_count: u32,
+    /// Number of payload bytes sent.
+    pub octet_count: u32,
+    /// Reception report blocks.
+    pub report_blocks: Vec<ReportBlock>,
+}
+
+/// Receiver Report.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ReceiverReport {
+    /// SSRC of the receiver.
+    pub ssrc: u32,
+    /// Reception report blocks.
+    pub report_blocks: Vec<ReportBlock>,
+}
+
+/// SDES item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SdesItem {
+    /// Item type, for instance [`SDES_CNAME`].
+    pub item_type: u8,
+    /// Item value, up to 255 bytes.
+    pub value: Vec<u8>,
+}
+
+/// SDES chunk with items of a single source.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SdesChunk {
+    /// SSRC or CSRC the items describe.
+    pub ssrc: u32,
+    /// Items.
+    pub items: Vec<SdesItem>,
+}
+
+impl SdesChunk {
+    /// Canonical name, if present and valid UTF-8.
+    #[must_use]
+    pub fn cname(&self) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|item| item.item_type == SDES_CNAME)
+            .and_then(|item| std::str::from_utf8(&item.value).ok())
+    }
+}
+
+/// Source Description.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Sdes {
+    /// Chunks.
+    pub chunks: Vec<SdesChunk>,
+}
+
+/// Goodbye.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Bye {
+    /// Sources that are leaving.
+    pub ssrcs: Vec<u32>,
+    /// Reason for leaving.
+    pub reason: Option<String>,
+}
+
+/// Packet identifier and bitmask of following lost packets in Generic NACK.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NackItem {
+    /// Sequence number of lost packet.
+    pub pid: u16,
+    /// Bitmask of lost packets among 16 following sequence numbers.
+    pub blp: u16,
+}
+
+/// Generic NACK.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Nack {
+    /// SSRC of the sender of this packet.
+    pub sender_ssrc: u32,
+    /// SSRC of the media source packets of which were lost.
+    pub media_ssrc: u32,
+    /// Lost packets.
+    pub items: Vec<NackItem>,
+}
+
+impl Nack {
+    /// Create NACK for specified lost sequence numbers, which are expected to be sorted in
+    /// ascending order (with respect to wrap-around).
+    #[must_use]
+    pub fn from_sequence_numbers<I>(sender_ssrc: u32, media_ssrc: u32, lost: I) -> Self
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        let mut items = Vec::<NackItem>::new();
+
+        for sequence_number in lost {
+            if let Some(item) = items.last_mut() {
+                let diff = sequence_number.wrapping_sub(item.pid);
+                if diff == 0 {
+                    continue;
+                }
+                if diff <= 16 {
+                    item.blp |= 1 << (diff - 1);
+                    continue;
+                }
+            }
+            items.push(NackItem {
+                pid: sequence_number,
+                blp: 0,
+            });
+        }
+
+        Self {
+            sender_ssrc,
+            media_ssrc,
+            items,
+        }
+    }
+
+    /// Iterator over all lost sequence numbers described by this NACK.
+    pub fn lost_sequence_numbers(&self) -> impl Iterator<Item = u16> + '_ {
+        self.items.iter().flat_map(|item| {
+            std::iter::once(item.pid).chain(
+                (0..16)
+                    .filter(|bit| item.blp & (1 << bit) != 0)
+                    .map(|bit| item.pid.wrapping_add(bit + 1)),
+            )
+        })
+    }
+}
+
+/// Picture Loss Indication.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Pli {
+    /// SSRC of the sender of this packet.
+    pub sender_ssrc: u32,
+    /// SSRC of the media source a key frame is requested from.
+    pub media_ssrc: u32,
+}
+
+/// Entry of Full Intra Request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FirEntry {
+    /// SSRC of the media source a key frame is requested from.
+    pub ssrc: u32,
+    /// Command sequence number, incremented for every new request.
+    pub sequence_number: u8,
+}
+
+/// Full Intra Request.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Fir {
+    /// SSRC of the sender of this packet.
+    pub sender_ssrc: u32,
+    /// Requests.
+    pub entries: Vec<FirEntry>,
+}
+
+/// Receiver Estimated Maximum Bitrate.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Remb {
+    /// SSRC of the sender of this packet.
+    pub sender_ssrc: u32,
+    /// Estimated bitrate in bits per second.
+    ///
+    /// It is encoded with 18-bit mantissa, so large values lose precision.
+    pub bitrate: u64,
+    /// Sources the estimate applies to.
+    pub ssrcs: Vec<u32>,
+}
+
+/// Reception status of a packet in transport-wide congestion control feedback.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TransportCcPacket {
+    /// Transport-wide sequence number.
+    pub sequence_number: u16,
+    /// Receive time relative to the previous received packet (or reference time for the first
+    /// one) in 250µs units, `None` if packet was not received.
+    pub receive_delta: Option<i16>,
+}
+
+/// Transport-wide congestion control feedback.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TransportCc {
+    /// SSRC of the sender of this packet.
+    pub sender_ssrc: u32,
+    /// SSRC of the media source (typically unused and `0`).
+    pub media_ssrc: u32,
+    /// Transport-wide sequence number of the first packet in feedback.
+    pub base_sequence_number: u16,
+    /// Reference time in 64ms units (signed 24-bit value).
+    pub reference_time: i32,
+    /// Feedback packet counter, incremented for every new feedback.
+    pub feedback_packet_count: u8,
+    /// Packets with consecutive sequence numbers starting at `base_sequence_number`.
+    pub packets: Vec<TransportCcPacket>,
+}
+
+/// RTCP packet of unsupported or unknown type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnknownRtcpPacket<'a> {
+    /// Packet type.
+    pub packet_type: u8,
+    /// Value of the 5-bit count (or feedback message type) field.
+    pub count: u8,
+    /// Packet contents after the header, excluding padding.
+    pub payload: &'a [u8],
+}
+
+/// RTCP packet.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RtcpPacket<'a> {
+    /// Sender Report.
+    SenderReport(SenderReport),
+    /// Receiver Report.
+    ReceiverReport(ReceiverReport),
+    /// Source Description.
+    Sdes(Sdes),
+    /// Goodbye.
+    Bye(Bye),
+    /// Generic NACK.
+    Nack(Nack),
+    /// Picture Loss Indication.
+    Pli(Pli),
+    /// Full Intra Request.
+    Fir(Fir),
+    /// Receiver Estimated Maximum Bitrate.
+    Remb(Remb),
+    /// Transport-wide congestion control feedback.
+    TransportCc(TransportCc),
+    /// Packet of unsupported or unknown type.
+    Unknown(UnknownRtcpPacket<'a>),
+}
+
+/// Iterator over packets of compound RTCP packet, see [`parse_compound()`].
+#[derive(Debug, Clone)]
+pub struct RtcpPackets<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for RtcpPackets<'a> {
+    type Item = Result<RtcpPacket<'a>, RtcpParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let result = split_packet(self.data).and_then(|(packet, rest)| {
+            self.data = rest;
+            parse_packet(packet)
+        });
+        if result.is_err() {
+            // Nothing can be parsed after an error
+            self.data = &[];
+        }
+
+        Some(result)
+    }
+}
+
+/// Parse compound RTCP packet (or a single RTCP packet).
+///
+/// Iteration stops after the first error.
+pub fn parse_compound(data: &[u8]) -> RtcpPackets<'_> {
+    RtcpPackets { data }
+}
+
+/// Build compound RTCP packet out of individual packets.
+pub fn build_compound(packets: &[RtcpPacket<'_>]) -> Result<Vec<u8>, RtcpBuildError> {
+    let mut buffer = Vec::new();
+    for packet in packets {
+        packet.write(&mut buffer)?;
+    }
+
+    Ok(buffer)
+}
+
+impl<'a> RtcpPacket<'a> {
+    /// Parse a single RTCP packet, trailing data (if any) is ignored.
+    pub fn parse(data: &'a [u8]) -> Result<Self, RtcpParseError> {
+        let (packet, _rest) = split_packet(data)?;
+
+        parse_packet(packet)
+    }
+
+    /// Serialize RTCP packet.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RtcpBuildError> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<(), RtcpBuildError> {
+        match self {
+            Self::SenderReport(sr) => {
+                let count = count(sr.report_blocks.len())?;
+                write_packet(buffer, count, PT_SR, |buffer| {
+                    buffer.extend_from_slice(&sr.ssrc.to_be_bytes());
+                    buffer.extend_from_slice(&sr.ntp_timestamp.to_be_bytes());
+                    buffer.extend_from_slice(&sr.rtp_timestamp.to_be_bytes());
+                    buffer.extend_from_slice(&sr.packet_count.to_be_bytes());
+                    buffer.extend_from_slice(&sr.octet_count.to_be_bytes());
+                    write_report_blocks(buffer, &sr.report_blocks);
+                    Ok(())
+                })
+            }
+            Self::ReceiverReport(rr) => {
+                let count = count(rr.report_blocks.len())?;
+                write_packet(buffer, count, PT_RR, |buffer| {
+                    buffer.extend_from_slice(&rr.ssrc.to_be_bytes());
+                    write_report_blocks(buffer, &rr.report_blocks);
+                    Ok(())
+                })
+            }
+            Self::Sdes(sdes) => {
+                let count = count(sdes.chunks.len())?;
+                write_packet(buffer, count, PT_SDES, |buffer| {
+                    for chunk in &sdes.chunks {
+                        buffer.extend_from_slice(&chunk.ssrc.to_be_bytes());
+                        let start = buffer.len();
+                        for item in &chunk.items {
+                            let len = u8::try_from(item.value.len())
+                                .map_err(|_| RtcpBuildError::ValueTooLong)?;
+                            buffer.push(item.item_type);
+                            buffer.push(len);
+                            buffer.extend_from_slice(&item.value);
+                        }
+                        // Null item terminates the list, followed by padding to 32-bit boundary
+                        buffer.push(0);
+                        pad(buffer, start);
+                    }
+                    Ok(())
+                })
+            }
+            Self::Bye(bye) => {
+                let count = count(bye.ssrcs.len())?;
+                write_packet(buffer, count, PT_BYE, |buffer| {
+                    for ssrc in &bye.ssrcs {
+                        buffer.extend_from_slice(&ssrc.to_be_bytes());
+                    }
+                    if let Some(reason) = &bye.reason {
+                        let len =
+                            u8::try_from(reason.len()).map_err(|_| RtcpBuildError::ValueTooLong)?;
+                        let start = buffer.len();
+                        buffer.push(len);
+                        buffer.extend_from_slice(reason.as_bytes());
+                        pad(buffer, start);
+                    }
+                    Ok(())
+                })
+            }
+            Self::Nack(nack) => write_packet(buffer, RTPFB_NACK, PT_RTPFB, |buffer| {
+                buffer.extend_from_slice(&nack.sender_ssrc.to_be_bytes());
+                buffer.extend_from_slice(&nack.media_ssrc.to_be_bytes());
+                for item in &nack.items {
+                    buffer.extend_from_slice(&item.pid.to_be_bytes());
+                    buffer.extend_from_slice(&item.blp.to_be_bytes());
+                }
+                Ok(())
+            }),
+            Self::Pli(pli) => write_packet(buffer, PSFB_PLI, PT_PSFB, |buffer| {
+                buffer.extend_from_slice(&pli.sender_ssrc.to_be_bytes());
+                buffer.extend_from_slice(&pli.media_ssrc.to_be_bytes());
+                Ok(())
+            }),
+            Self::Fir(fir) => write_packet(buffer, PSFB_FIR, PT_PSFB, |buffer| {
+                buffer.extend_from_slice(&fir.sender_ssrc.to_be_bytes());
+                // Media source SSRC is not used in FIR
+                buffer.extend_from_slice(&0_u32.to_be_bytes());
+                for entry in &fir.entries {
+                    buffer.extend_from_slice(&entry.ssrc.to_be_bytes());
+                    buffer.extend_from_slice(&[entry.sequence_number, 0, 0, 0]);
+                }
+                Ok(())
+            }),
+            Self::Remb(remb) => {
+                let num_ssrcs =
+                    u8::try_from(remb.ssrcs.len()).map_err(|_| RtcpBuildError::TooManyItems)?;
+                write_packet(buffer, PSFB_AFB, PT_PSFB, |buffer| {
+                    buffer.extend_from_slice(&remb.sender_ssrc.to_be_bytes());
+                    buffer.extend_from_slice(&0_u32.to_be_bytes());
+                    buffer.extend_from_slice(REMB_IDENTIFIER);
+                    let mut exp = 0_u32;
+                    while remb.bitrate >> exp > 0x3_FFFF {
+                        exp += 1;
+                    }
+                    let mantissa = (remb.bitrate >> exp) as u32;
+                    buffer.push(num_ssrcs);
+                    buffer.extend_from_slice(&(exp << 18 | mantissa).to_be_bytes()[1..]);
+                    for ssrc in &remb.ssrcs {
+                        buffer.extend_from_slice(&ssrc.to_be_bytes());
+                    }
+                    Ok(())
+                })
+            }
+            Self::TransportCc(transport_cc) => {
+                write_packet(buffer, RTPFB_TWCC, PT_RTPFB, |buffer| {
+                    write_transport_cc(buffer, transport_cc)
+                })
+            }
+            Self::Unknown(unknown) => {
+                if unknown.payload.len() % 4 != 0 {
+                    return Err(RtcpBuildError::ValueOutOfRange);
+                }
+                write_packet(
+                    buffer,
+                    unknown.count & 0x1F,
+                    unknown.packet_type,
+                    |buffer| {
+                        buffer.extend_from_slice(unknown.payload);
+                        Ok(())
+                    },
+                )
+            }
+        }
+    }
+}
+
+fn count(len: usize) -> Result<u8, RtcpBuildError> {
+    if len > MAX_COUNT {
+        return Err(RtcpBuildError::TooManyItems);
+    }
+
+    Ok(len as u8)
+}
+
+fn pad(buffer: &mut Vec<u8>, start: usize) {
+    while (buffer.len() - start) % 4 != 0 {
+        buffer.push(0);
+    }
+}
+
+fn write_packet<F>(
+    buffer: &mut Vec<u8>,
+    count: u8,
+    packet_type: u8,
+    write_body: F,
+) -> Result<(), RtcpBuildError>
+where
+    F: FnOnce(&mut Vec<u8>) -> Result<(), RtcpBuildError>,
+{
+    let start = buffer.len();
+    buffer.extend_from_slice(&[0x80 | count, packet_type, 0, 0]);
+
+    if let Err(error) = write_body(buffer) {
+        buffer.truncate(start);
+        return Err(error);
+    }
+
+    let Ok(length) = u16::try_from((buffer.len() - start) / 4 - 1) else {
+        buffer.truncate(start);
+        return Err(RtcpBuildError::TooLarge);
+    };
+    buffer[start + 2..start + 4].copy_from_slice(&length.to_be_bytes());
+
+    Ok(())
+}
+
+fn write_report_blocks(buffer: &mut Vec<u8>, report_blocks: &[ReportBlock]) {
+    for block in report_blocks {
+        buffer.extend_from_slice(&block.ssrc.to_be_bytes());
+        buffer.push(block.fraction_lost);
+        buffer.extend_from_slice(&block.total_lost.to_be_bytes()[1..]);
+        buffer.extend_from_slice(&block.highest_sequence_number.to_be_bytes());
+        buffer.extend_from_slice(&block.jitter.to_be_bytes());
+        buffer.extend_from_slice(&block.last_sr.to_be_bytes());
+        buffer.extend_from_slice(&block.delay_since_last_sr.to_be_bytes());
+    }
+}
+
+fn write_transport_cc(
+    buffer: &mut Vec<u8>,
+    transport_cc: &TransportCc,
+) -> Result<(), RtcpBuildError> {
+    let packet_status_count =
+        u16::try_from(transport_cc.packets.len()).map_err(|_| RtcpBuildError::TooManyItems)?;
+    if !(-0x80_0000..0x80_0000).contains(&transport_cc.reference_time) {
+        return Err(RtcpBuildError::ValueOutOfRange);
+    }
+
+    let start = buffer.len();
+    buffer.extend_from_slice(&transport_cc.sender_ssrc.to_be_bytes());
+    buffer.extend_from_slice(&transport_cc.media_ssrc.to_be_bytes());
+    buffer.extend_from_slice(&transport_cc.base_sequence_number.to_be_bytes());
+    buffer.extend_from_slice(&packet_status_count.to_be_bytes());
+    buffer.extend_from_slice(&transport_cc.reference_time.to_be_bytes()[1..]);
+    buffer.push(transport_cc.feedback_packet_count);
+
+    // Status vector chunks with 7 two-bit symbols each
+    for packets in transport_cc.packets.chunks(7) {
+        let mut chunk = 0xC000_u16;
+        for (index, packet) in packets.iter().enumerate() {
+            chunk |= u16::from(TransportCcSymbol::of(packet).0) << (12 - index * 2);
+        }
+        buffer.extend_from_slice(&chunk.to_be_bytes());
+    }
+
+    for packet in &transport_cc.packets {
+        match (TransportCcSymbol::of(packet), packet.receive_delta) {
+            (TransportCcSymbol::SMALL_DELTA, Some(delta)) => {
+                buffer.push(delta as u8);
+            }
+            (TransportCcSymbol::LARGE_DELTA, Some(delta)) => {
+                buffer.extend_from_slice(&delta.to_be_bytes());
+            }
+            _ => {}
+        }
+    }
+
+    pad(buffer, start);
+
+    Ok(())
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct TransportCcSymbol(u8);
+
+impl TransportCcSymbol {
+    const NOT_RECEIVED: Self = Self(0);
+    const SMALL_DELTA: Self = Self(1);
+    const LARGE_DELTA: Self = Self(2);
+
+    fn of(packet: &TransportCcPacket) -> Self {
+        match packet.receive_delta {
+            None => Self::NOT_RECEIVED,
+            Some(0..=255) => Self::SMALL_DELTA,
+            Some(_) => Self::LARGE_DELTA,
+        }
+    }
+}
+
+/// Split the first packet (excluding padding) off compound packet.
+fn split_packet(data: &[u8]) -> Result<(&[u8], &[u8]), RtcpParseError> {
+    if data.len() < HEADER_SIZE {
+        return Err(RtcpParseError::TooShort);
+    }
+
+    let version = data[0] >> 6;
+    if version != 2 {
+        return Err(RtcpParseError::UnsupportedVersion(version));
+    }
+
+    let length = (usize::from(u16::from_be_bytes([data[2], data[3]])) + 1) * 4;
+    if data.len() < length {
+        return Err(RtcpParseError::TooShort);
+    }
+
+    let (packet, rest) = data.split_at(length);
+
+    if packet[0] & 0x20 != 0 {
+        let padding_size = usize::from(packet[length - 1]);
+        if padding_size == 0 || padding_size > length - HEADER_SIZE {
+            return Err(RtcpParseError::InvalidPadding);
+        }
+
+        return Ok((&packet[..length - padding_size], rest));
+    }
+
+    Ok((packet, rest))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], RtcpParseError> {
+        if self.data.len() < len {
+            return Err(RtcpParseError::TooShort);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RtcpParseError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RtcpParseError> {
+        let bytes = self.bytes(2)?;
+
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i24(&mut self) -> Result<i32, RtcpParseError> {
+        let bytes = self.bytes(3)?;
+
+        // Sign-extend 24-bit value
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8)
+    }
+
+    fn u32(&mut self) -> Result<u32, RtcpParseError> {
+        let bytes = self.bytes(4)?;
+
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, RtcpParseError> {
+        Ok(u64::from(self.u32()?) << 32 | u64::from(self.u32()?))
+    }
+
+    fn report_blocks(&mut self, count: u8) -> Result<Vec<ReportBlock>, RtcpParseError> {
+        if self.data.len() < usize::from(count) * REPORT_BLOCK_SIZE {
+            return Err(RtcpParseError::TooShort);
+        }
+
+        (0..count)
+            .map(|_| {
+                Ok(ReportBlock {
+                    ssrc: self.u32()?,
+                    fraction_lost: self.u8()?,
+                    total_lost: self.i24()?,
+                    highest_sequence_number: self.u32()?,
+                    jitter: self.u32()?,
+                    last_sr: self.u32()?,
+                    delay_since_last_sr: self.u32()?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_packet(packet: &[u8]) -> Result<RtcpPacket<'_>, RtcpParseError> {
+    let count = packet[0] & 0x1F;
+    let packet_type = packet[1];
+    let payload = &packet[HEADER_SIZE..];
+    let mut reader = Reader { data: payload };
+
+    let packet = match (packet_type, count) {
+        (PT_SR, _) => RtcpPacket::SenderReport(SenderReport {
+            ssrc: reader.u32()?,
+            ntp_timestamp: reader.u64()?,
+            rtp_timestamp: reader.u32()?,
+            packet_count: reader.u32()?,
+            octet_count: reader.u32()?,
+            report_blocks: reader.report_blocks(count)?,
+        }),
+        (PT_RR, _) => RtcpPacket::ReceiverReport(ReceiverReport {
+            ssrc: reader.u32()?,
+            report_blocks: reader.report_blocks(count)?,
+        }),
+        (PT_SDES, _) => RtcpPacket::Sdes(parse_sdes(&mut reader, count)?),
+        (PT_BYE, _) => {
+            let ssrcs = (0..count)
+                .map(|_| reader.u32())
+                .collect::<Result<Vec<_>, _>>()?;
+            let reason = match reader.data.first() {
+                Some(&len) if len > 0 => {
+                    reader.u8()?;
+                    let reason = reader.bytes(usize::from(len))?;
+                    Some(String::from_utf8_lossy(reason).into_owned())
+                }
+                _ => None,
+            };
+
+            RtcpPacket::Bye(Bye { ssrcs, reason })
+        }
+        (PT_RTPFB, RTPFB_NACK) => {
+            let sender_ssrc = reader.u32()?;
+            let media_ssrc = reader.u32()?;
+            let mut items = Vec::with_capacity(reader.data.len() / 4);
+            while reader.data.len() >= 4 {
+                items.push(NackItem {
+                    pid: reader.u16()?,
+                    blp: reader.u16()?,
+                });
+            }
+
+            RtcpPacket::Nack(Nack {
+                sender_ssrc,
+                media_ssrc,
+                items,
+            })
+        }
+        (PT_RTPFB, RTPFB_TWCC) => RtcpPacket::TransportCc(parse_transport_cc(&mut reader)?),
+        (PT_PSFB, PSFB_PLI) => RtcpPacket::Pli(Pli {
+            sender_ssrc: reader.u32()?,
+            media_ssrc: reader.u32()?,
+        }),
+        (PT_PSFB, PSFB_FIR) => {
+            let sender_ssrc = reader.u32()?;
+            let _media_ssrc = reader.u32()?;
+            let mut entries = Vec::with_capacity(reader.data.len() / 8);
+            while reader.data.len() >= 8 {
+                let ssrc = reader.u32()?;
+                let sequence_number = reader.u8()?;
+                reader.bytes(3)?;
+                entries.push(FirEntry {
+                    ssrc,
+                    sequence_number,
+                });
+            }
+
+            RtcpPacket::Fir(Fir {
+                sender_ssrc,
+                entries,
+            })
+        }
+        (PT_PSFB, PSFB_AFB) if payload.get(8..12) == Some(REMB_IDENTIFIER) => {
+            let sender_ssrc = reader.u32()?;
+            reader.bytes(8)?;
+            let num_ssrcs = reader.u8()?;
+            let bitrate = reader.bytes(3)?;
+            let exp = bitrate[0] >> 2;
+            let mantissa = u64::from(bitrate[0] & 0x03) << 16
+                | u64::from(bitrate[1]) << 8
+                | u64::from(bitrate[2]);
+            let ssrcs = (0..num_ssrcs)
+                .map(|_| reader.u32())
+                .collect::<Result<Vec<_>, _>>()?;
+
+            RtcpPacket::Remb(Remb {
+                sender_ssrc,
+                bitrate: mantissa.checked_shl(exp.into()).unwrap_or(u64::MAX),
+                ssrcs,
+            })
+        }
+        _ => RtcpPacket::Unknown(UnknownRtcpPacket {
+            packet_type,
+            count,
+            payload,
+        }),
+    };
+
+    Ok(packet)
+}
+
+fn parse_sdes(reader: &mut Reader<'_>, count: u8) -> Result<Sdes, RtcpParseError> {
+    let mut chunks = Vec::with_capacity(usize::from(count));
+
+    for _ in 0..count {
+        let ssrc = reader.u32()?;
+        let start = reader.data.len();
+        let mut items = Vec::new();
+        loop {
+            let item_type = reader.u8()?;
+            if item_type == 0 {
+                break;
+            }
+            let len = reader.u8()?;
+            items.push(SdesItem {
+                item_type,
+                value: reader.bytes(usize::from(len))?.to_vec(),
+            });
+        }
+        // Skip padding up to 32-bit boundary
+        let consumed = start - reader.data.len();
+        reader.bytes((4 - consumed % 4) % 4)?;
+
+        chunks.push(SdesChunk { ssrc, items });
+    }
+
+    Ok(Sdes { chunks })
+}
+
+fn parse_transport_cc(reader: &mut Reader<'_>) -> Result<TransportCc, RtcpParseError> {
+    let sender_ssrc = reader.u32()?;
+    let media_ssrc = reader.u32()?;
+    let base_sequence_number = reader.u16()?;
+    let packet_status_count = usize::from(reader.u16()?);
+    let reference_time = reader.i24()?;
+    let feedback_packet_count = reader.u8()?;
+
+    let mut symbols = Vec::with_capacity(packet_status_count);
+    while symbols.len() < packet_status_count {
+        let chunk = reader.u16()?;
+        let remaining = packet_status_count - symbols.len();
+
+        if chunk & 0x8000 == 0 {
+            // Run length chunk
+            let symbol = TransportCcSymbol((chunk >> 13) as u8 & 0x03);
+            let run_length = usize::from(chunk & 0x1FFF);
+            symbols.extend(std::iter::repeat(symbol).take(run_length.min(remaining)));
+        } else if chunk & 0x4000 == 0 {
+            // Status vector chunk with 14 one-bit symbols
+            symbols.extend(
+                (0..14)
+                    .take(remaining)
+                    .map(|index| TransportCcSymbol((chunk >> (13 - index)) as u8 & 0x01)),
+            );
+        } else {
+            // Status vector chunk with 7 two-bit symbols
+            symbols.extend(
+                (0..7)
+                    .take(remaining)
+                    .map(|index| TransportCcSymbol((chunk >> (12 - index * 2)) as u8 & 0x03)),
+            );
+        }
+    }
+
+    let packets = symbols
+        .into_iter()
+        .zip(0_u16..)
+        .map(|(symbol, offset)| {
+            let receive_delta = match symbol {
+                TransportCcSymbol::SMALL_DELTA => Some(i16::from(reader.u8()?)),
+                TransportCcSymbol::LARGE_DELTA => Some(reader.u16()? as i16),
+                _ => None,
+            };
+
+            Ok(TransportCcPacket {
+                sequence_number: base_sequence_number.wrapping_add(offset),
+                receive_delta,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(TransportCc {
+        sender_ssrc,
+        media_ssrc,
+        base_sequence_number,
+        reference_time,
+        feedback_packet_count,
+        packets,
+    })
+}
@@ -0,0 +1,186 @@
+use super::*;
+
+fn round_trip(packet: RtcpPacket<'_>) {
+    let data = packet.to_bytes().unwrap();
+    assert_eq!(data.len() % 4, 0);
+    assert_eq!(RtcpPacket::parse(&data).unwrap(), packet);
+}
+
+#[test]
+fn reports_round_trip() {
+    let report_block = ReportBlock {
+        ssrc: 1,
+        fraction_lost: 25,
+        total_lost: -3,
+        highest_sequence_number: 70000,
+        jitter: 12,
+        last_sr: 0x1234_5678,
+        delay_since_last_sr: 65536,
+    };
+
+    round_trip(RtcpPacket::SenderReport(SenderReport {
+        ssrc: 2,
+        ntp_timestamp: 0x0102_0304_0506_0708,
+        rtp_timestamp: 90000,
+        packet_count: 10,
+        octet_count: 1000,
+        report_blocks: vec![report_block],
+    }));
+    round_trip(RtcpPacket::ReceiverReport(ReceiverReport {
+        ssrc: 3,
+        report_blocks: vec![report_block, report_block],
+    }));
+}
+
+#[test]
+fn sdes_and_bye_round_trip() {
+    let sdes = Sdes {
+        chunks: vec![
+            SdesChunk {
+                ssrc: 1,
+                items: vec![SdesItem {
+                    item_type: SDES_CNAME,
+                    value: b"cname".to_vec(),
+                }],
+            },
+            SdesChunk {
+                ssrc: 2,
+                items: vec![],
+            },
+        ],
+    };
+    assert_eq!(sdes.chunks[0].cname(), Some("cname"));
+    round_trip(RtcpPacket::Sdes(sdes));
+
+    round_trip(RtcpPacket::Bye(Bye {
+        ssrcs: vec![1, 2],
+        reason: Some("bye".to_string()),
+    }));
+    round_trip(RtcpPacket::Bye(Bye {
+        ssrcs: vec![1],
+        reason: None,
+    }));
+}
+
+#[test]
+fn feedback_round_trip() {
+    let nack = Nack::from_sequence_numbers(1, 2, [65534, 65535, 0, 14, 16, 100]);
+    assert_eq!(
+        nack.items,
+        vec![
+            NackItem {
+                pid: 65534,
+                blp: 0b1000_0000_0000_0011,
+            },
+            NackItem { pid: 16, blp: 0 },
+            NackItem { pid: 100, blp: 0 },
+        ],
+    );
+    assert_eq!(
+        nack.lost_sequence_numbers().collect::<Vec<_>>(),
+        vec![65534, 65535, 0, 14, 16, 100],
+    );
+    round_trip(RtcpPacket::Nack(nack));
+
+    round_trip(RtcpPacket::Pli(Pli {
+        sender_ssrc: 1,
+        media_ssrc: 2,
+    }));
+    round_trip(RtcpPacket::Fir(Fir {
+        sender_ssrc: 1,
+        entries: vec![FirEntry {
+            ssrc: 2,
+            sequence_number: 3,
+        }],
+    }));
+    round_trip(RtcpPacket::Remb(Remb {
+        sender_ssrc: 1,
+        bitrate: 0x3_FFFF << 4,
+        ssrcs: vec![2, 3],
+    }));
+    round_trip(RtcpPacket::TransportCc(TransportCc {
+        sender_ssrc: 1,
+        media_ssrc: 0,
+        base_sequence_number: 65530,
+        reference_time: -5,
+        feedback_packet_count: 7,
+        packets: (0..10)
+            .map(|index| TransportCcPacket {
+                sequence_number: 65530_u16.wrapping_add(index),
+                receive_delta: match index % 3 {
+                    0 => None,
+                    1 => Some(index as i16),
+                    _ => Some(-(index as i16) * 100),
+                },
+            })
+            .collect(),
+    }));
+}
+
+#[test]
+fn parse_transport_cc_chunks() {
+    let data = [
+        0x8F, 0xCD, 0x00, 0x06, // RTPFB TWCC, length=6
+        0x00, 0x00, 0x00, 0x01, // sender ssrc
+        0x00, 0x00, 0x00, 0x00, // media ssrc
+        0x00, 0x0A, 0x00, 0x05, // base seq=10, status count=5
+        0x00, 0x00, 0x01, 0x02, // reference time=1, fb count=2
+        0x20, 0x02, 0x90, 0x00, // run length: 2 small; one-bit vector: lost, small, lost
+        0x04, 0x08, 0x0C, 0x00, // deltas 4, 8, 12, padding
+    ];
+
+    let Ok(RtcpPacket::TransportCc(transport_cc)) = RtcpPacket::parse(&data) else {
+        panic!("Not a transport-wide feedback");
+    };
+    assert_eq!(transport_cc.reference_time, 1);
+    assert_eq!(transport_cc.feedback_packet_count, 2);
+    assert_eq!(
+        transport_cc
+            .packets
+            .iter()
+            .map(|packet| (packet.sequence_number, packet.receive_delta))
+            .collect::<Vec<_>>(),
+        vec![
+            (10, Some(4)),
+            (11, Some(8)),
+            (12, None),
+            (13, Some(12)),
+            (14, None),
+        ],
+    );
+}
+
+#[test]
+fn compound() {
+    let packets = vec![
+        RtcpPacket::ReceiverReport(ReceiverReport {
+            ssrc: 1,
+            report_blocks: vec![],
+        }),
+        RtcpPacket::Unknown(UnknownRtcpPacket {
+            packet_type: 204,
+            count: 0,
+            payload: &[0, 0, 0, 1, b'n', b'a', b'm', b'e'],
+        }),
+        RtcpPacket::Pli(Pli {
+            sender_ssrc: 1,
+            media_ssrc: 2,
+        }),
+    ];
+    let mut data = build_compound(&packets).unwrap();
+
+    assert_eq!(
+        parse_compound(&data)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        packets,
+    );
+
+    // Errors stop iteration
+    data.truncate(data.len() - 1);
+    let mut iter = parse_compound(&data);
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_ok());
+    assert_eq!(iter.next(), Some(Err(RtcpParseError::TooShort)));
+    assert_eq!(iter.next(), None);
+}
@@ -0,0 +1,183 @@
+use super::*;
+use crate::producer::Rotation;
+use crate::rtp_parameters::{RtpHeaderExtensionParameters, RtpHeaderExtensionUri};
+
+#[test]
+fn parse_packet() {
+    let data = [
+        0xB1, 0xE0, 0x03, 0xE8, // V=2, P, X, CC=1, M, PT=96, seq=1000
+        0x00, 0x00, 0xBB, 0x80, // timestamp=48000
+        0x00, 0x00, 0x04, 0xD2, // ssrc=1234
+        0x00, 0x00, 0x16, 0x2E, // csrc=5678
+        0xBE, 0xDE, 0x00, 0x02, // one-byte extensions, 2 words
+        0x10, 0x61, 0x00, 0x21, // id=1 "a", padding, id=2 len=2
+        0x01, 0x02, 0x00, 0x00, // ..., padding
+        0xAA, 0xBB, 0x00, 0x02, // payload, padding
+    ];
+
+    let packet = RtpPacket::parse(&data).unwrap();
+    assert!(packet.marker());
+    assert_eq!(packet.payload_type(), 96);
+    assert_eq!(packet.sequence_number(), 1000);
+    assert_eq!(packet.timestamp(), 48000);
+    assert_eq!(packet.ssrc(), 1234);
+    assert_eq!(packet.csrcs().collect::<Vec<_>>(), vec![5678]);
+    assert_eq!(
+        packet.header_extension_profile(),
+        Some(HeaderExtensionProfile::OneByte),
+    );
+    assert_eq!(
+        packet.extensions().collect::<Vec<_>>(),
+        vec![(1, b"a".as_ref()), (2, [1, 2].as_ref())],
+    );
+    assert_eq!(packet.extension(2), Some([1, 2].as_ref()));
+    assert_eq!(packet.extension(3), None);
+    assert_eq!(packet.payload(), &[0xAA, 0xBB]);
+    assert_eq!(packet.padding_size(), 2);
+}
+
+#[test]
+fn parse_invalid_packet() {
+    assert_eq!(
+        RtpPacket::parse(&[0x80, 0x60, 0x00]).unwrap_err(),
+        RtpParseError::TooShort,
+    );
+    assert_eq!(
+        RtpPacket::parse(&[0x40; 12]).unwrap_err(),
+        RtpParseError::UnsupportedVersion(1),
+    );
+    // Padding larger than the packet
+    let mut data = [0; 13];
+    data[0] = 0xA0;
+    data[12] = 5;
+    assert_eq!(
+        RtpPacket::parse(&data).unwrap_err(),
+        RtpParseError::InvalidPadding,
+    );
+    // Extension element longer than extension data
+    let mut data = [0; 20];
+    data[0] = 0x90;
+    data[12..18].copy_from_slice(&[0xBE, 0xDE, 0x00, 0x01, 0x1F, 0x00]);
+    assert_eq!(
+        RtpPacket::parse(&data).unwrap_err(),
+        RtpParseError::InvalidHeaderExtension,
+    );
+}
+
+#[test]
+fn build_round_trip() {
+    let mut builder = RtpPacketBuilder::new(100, 65535, 1, 2);
+    builder.csrcs = vec![3, 4];
+    builder.extensions = vec![(3, b"mid".to_vec()), (14, vec![7; 16])];
+    builder.payload = vec![9; 5];
+    builder.padding_size = 3;
+
+    let data = builder.build().unwrap();
+    assert_eq!(data.len() % 4, 0);
+
+    let packet = RtpPacket::parse(&data).unwrap();
+    assert!(!packet.marker());
+    assert_eq!(packet.payload_type(), 100);
+    assert_eq!(packet.sequence_number(), 65535);
+    assert_eq!(packet.csrcs().collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(
+        packet.header_extension_profile(),
+        Some(HeaderExtensionProfile::OneByte),
+    );
+    assert_eq!(packet.extension(3), Some(b"mid".as_ref()));
+    assert_eq!(packet.extension(14), Some([7; 16].as_ref()));
+    assert_eq!(packet.payload(), &[9; 5]);
+    assert_eq!(packet.padding_size(), 3);
+
+    // Id 15 and empty values require two-byte header
+    builder.extensions = vec![(15, vec![1]), (1, vec![]), (2, vec![5; 17])];
+    let data = builder.build().unwrap();
+    let packet = RtpPacket::parse(&data).unwrap();
+    assert_eq!(
+        packet.header_extension_profile(),
+        Some(HeaderExtensionProfile::TwoByte { app_bits: 0 }),
+    );
+    assert_eq!(
+        packet.extensions().collect::<Vec<_>>(),
+        vec![(15, [1].as_ref()), (1, [].as_ref()), (2, [5; 17].as_ref())],
+    );
+
+    builder.payload_type = 128;
+    assert_eq!(
+        builder.build().unwrap_err(),
+        RtpBuildError::InvalidPayloadType(128),
+    );
+}
+
+#[test]
+fn header_extension_ids() {
+    let ids = HeaderExtensionIds::new(&[
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::Mid,
+            id: 1,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::AudioLevel,
+            id: 2,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::VideoOrientation,
+            id: 3,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::TimeOffset,
+            id: 4,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::TransportWideCcDraft01,
+            id: 5,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::AbsSendTime,
+            id: 6,
+            encrypt: false,
+        },
+        RtpHeaderExtensionParameters {
+            uri: RtpHeaderExtensionUri::RtpStreamId,
+            id: 300,
+            encrypt: false,
+        },
+    ]);
+    assert_eq!(ids.mid, Some(1));
+    assert_eq!(ids.rid, None);
+
+    let mut builder = RtpPacketBuilder::new(100, 0, 0, 0);
+    builder.extensions = vec![
+        (1, b"v0\0".to_vec()),
+        (2, vec![0x80 | 42]),
+        (3, vec![0x0D]),
+        (4, vec![0xFF, 0xFF, 0xFE]),
+        (5, vec![0x12, 0x34]),
+        (6, vec![0x01, 0x02, 0x03]),
+    ];
+    let data = builder.build().unwrap();
+    let packet = RtpPacket::parse(&data).unwrap();
+
+    assert_eq!(ids.mid(&packet), Some("v0"));
+    assert_eq!(ids.rid(&packet), None);
+    assert_eq!(
+        ids.audio_level(&packet),
+        Some(AudioLevel {
+            voice: true,
+            level: 42,
+        }),
+    );
+    let video_orientation = ids.video_orientation(&packet).unwrap();
+    assert!(video_orientation.camera);
+    assert!(video_orientation.flip);
+    assert_eq!(video_orientation.rotation, Rotation::Clockwise);
+    assert_eq!(ids.time_offset(&packet), Some(-2));
+    assert_eq!(ids.transport_wide_sequence_number(&packet), Some(0x1234));
+    assert_eq!(ids.abs_send_time(&packet), Some(0x010203));
+    assert_eq!(ids.abs_capture_time(&packet), None);
+}
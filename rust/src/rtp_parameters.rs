@@ -154,6 +154,13 @@ impl RtpCodecCapabilityFinalized {
         }
     }
 
+    pub(crate) fn mime_type(&self) -> MimeType {
+        match self {
+            Self::Audio { mime_type, .. } => MimeType::Audio(*mime_type),
+            Self::Video { mime_type, .. } => MimeType::Video(*mime_type),
+        }
+    }
+
     pub(crate) fn clock_rate(&self) -> NonZeroU32 {
         let (Self::Audio { clock_rate, .. } | Self::Video { clock_rate, .. }) = self;
         *clock_rate
@@ -164,6 +171,11 @@ impl RtpCodecCapabilityFinalized {
         parameters
     }
 
+    pub(crate) fn rtcp_feedback(&self) -> &Vec<RtcpFeedback> {
+        let (Self::Audio { rtcp_feedback, .. } | Self::Video { rtcp_feedback, .. }) = self;
+        rtcp_feedback
+    }
+
     pub(crate) fn parameters_mut(&mut self) -> &mut RtpCodecParametersParameters {
         let (Self::Audio { parameters, .. } | Self::Video { parameters, .. }) = self;
         parameters
@@ -355,6 +367,7 @@ impl MimeTypeAudio {
 
 /// Known Video MIME types.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize, Serialize)]
+#[non_exhaustive]
 pub enum MimeTypeVideo {
     /// VP8
     #[serde(rename = "video/VP8")]
@@ -371,6 +384,9 @@ pub enum MimeTypeVideo {
     /// H265
     #[serde(rename = "video/H265")]
     H265,
+    /// AV1
+    #[serde(rename = "video/AV1")]
+    Av1,
     /// RTX
     #[serde(rename = "video/rtx")]
     Rtx,
@@ -392,6 +408,7 @@ impl FromStr for MimeTypeVideo {
             "video/H264" => Ok(Self::H264),
             "video/H264-SVC" => Ok(Self::H264Svc),
             "video/H265" => Ok(Self::H265),
+            "video/AV1" => Ok(Self::Av1),
             "video/rtx" => Ok(Self::Rtx),
             "video/red" => Ok(Self::Red),
             "video/ulpfec" => Ok(Self::Ulpfec),
@@ -413,6 +430,7 @@ impl MimeTypeVideo {
             Self::H264 => "video/H264",
             Self::H264Svc => "video/H264-SVC",
             Self::H265 => "video/H265",
+            Self::Av1 => "video/AV1",
             Self::Rtx => "video/rtx",
             Self::Red => "video/red",
             Self::Ulpfec => "video/ulpfec",
@@ -551,6 +569,7 @@ pub enum RtpHeaderExtensionUriParseError {
 
 /// URI for supported RTP header extension
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize, Serialize)]
+#[non_exhaustive]
 pub enum RtpHeaderExtensionUri {
     /// urn:ietf:params:rtp-hdrext:sdes:mid
     #[serde(rename = "urn:ietf:params:rtp-hdrext:sdes:mid")]
@@ -585,14 +604,23 @@ pub enum RtpHeaderExtensionUri {
     /// <http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time>
     #[serde(rename = "http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time")]
     AbsCaptureTime,
+    /// <https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension>
+    ///
+    /// Not understood by the worker yet, so it is not part of supported RTP capabilities and is
+    /// stripped from RTP parameters before they are sent to the worker.
+    #[serde(
+        rename = "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension"
+    )]
+    DependencyDescriptor,
     #[doc(hidden)]
     #[serde(other, rename = "unsupported")]
     Unsupported,
 }
 
 impl RtpHeaderExtensionUri {
-    pub(crate) fn to_fbs(self) -> rtp_parameters::RtpHeaderExtensionUri {
-        match self {
+    /// Returns `None` for header extensions the worker doesn't know about.
+    pub(crate) fn to_fbs(self) -> Option<rtp_parameters::RtpHeaderExtensionUri> {
+        Some(match self {
             RtpHeaderExtensionUri::Mid => rtp_parameters::RtpHeaderExtensionUri::Mid,
             RtpHeaderExtensionUri::RtpStreamId => {
                 rtp_parameters::RtpHeaderExtensionUri::RtpStreamId
@@ -620,8 +648,11 @@ impl RtpHeaderExtensionUri {
             RtpHeaderExtensionUri::AbsCaptureTime => {
                 rtp_parameters::RtpHeaderExtensionUri::AbsCaptureTime
             }
+            RtpHeaderExtensionUri::DependencyDescriptor => {
+                return None;
+            }
             RtpHeaderExtensionUri::Unsupported => panic!("Invalid RTP extension header URI"),
-        }
+        })
     }
 
     pub(crate) fn from_fbs(uri: rtp_parameters::RtpHeaderExtensionUri) -> Self {
@@ -679,6 +710,9 @@ impl FromStr for RtpHeaderExtensionUri {
             "http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time" => {
                 Ok(Self::AbsCaptureTime)
             }
+            "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension" => {
+                Ok(Self::DependencyDescriptor)
+            }
             _ => Err(RtpHeaderExtensionUriParseError::Unsupported),
         }
     }
@@ -710,6 +744,9 @@ impl RtpHeaderExtensionUri {
             RtpHeaderExtensionUri::AbsCaptureTime => {
                 "http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time"
             }
+            RtpHeaderExtensionUri::DependencyDescriptor => {
+                "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension"
+            }
             RtpHeaderExtensionUri::Unsupported => "unsupported",
         }
     }
@@ -789,7 +826,7 @@ pub struct RtpParameters {
 impl RtpParameters {
     pub(crate) fn from_fbs_ref(
         rtp_parameters: rtp_parameters::RtpParametersRef<'_>,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             mid: rtp_parameters.mid()?.map(|mid| mid.to_string()),
             codecs: rtp_parameters
@@ -824,7 +861,7 @@ impl RtpParameters {
                                 },
                             ))
                         })
-                        .collect::<Result<_, Box<dyn Error>>>()?;
+                        .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?;
                     let rtcp_feedback = codec?
                         .rtcp_feedback()?
                         .unwrap_or(planus::Vector::new_empty())
@@ -835,7 +872,7 @@ impl RtpParameters {
                                 rtcp_feedback?.parameter()?.unwrap_or_default(),
                             )?)
                         })
-                        .collect::<Result<_, Box<dyn Error>>>()?;
+                        .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?;
 
                     Ok(match MimeType::from_str(codec?.mime_type()?)? {
                         MimeType::Audio(mime_type) => RtpCodecParameters::Audio {
@@ -858,7 +895,7 @@ impl RtpParameters {
                         },
                     })
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             header_extensions: rtp_parameters
                 .header_extensions()?
                 .into_iter()
@@ -869,7 +906,7 @@ impl RtpParameters {
                         encrypt: header_extension_parameters?.encrypt()?,
                     })
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             encodings: rtp_parameters
                 .encodings()?
                 .into_iter()
@@ -894,7 +931,7 @@ impl RtpParameters {
                         max_bitrate: encoding?.max_bitrate()?,
                     })
                 })
-                .collect::<Result<_, Box<dyn Error>>>()?,
+                .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?,
             rtcp: RtcpParameters {
                 cname: rtp_parameters
                     .rtcp()?
@@ -963,14 +1000,14 @@ impl RtpParameters {
             header_extensions: self
                 .header_extensions
                 .into_iter()
-                .map(
-                    |header_extension_parameters| rtp_parameters::RtpHeaderExtensionParameters {
-                        uri: header_extension_parameters.uri.to_fbs(),
+                .filter_map(|header_extension_parameters| {
+                    Some(rtp_parameters::RtpHeaderExtensionParameters {
+                        uri: header_extension_parameters.uri.to_fbs()?,
                         id: header_extension_parameters.id as u8,
                         encrypt: header_extension_parameters.encrypt,
                         parameters: None,
-                    },
-                )
+                    })
+                })
                 .collect(),
             encodings: self
                 .encodings
@@ -1108,6 +1145,11 @@ impl RtpCodecParameters {
         *payload_type
     }
 
+    pub(crate) fn payload_type_mut(&mut self) -> &mut u8 {
+        let (Self::Audio { payload_type, .. } | Self::Video { payload_type, .. }) = self;
+        payload_type
+    }
+
     pub(crate) fn clock_rate(&self) -> NonZeroU32 {
         let (Self::Audio { clock_rate, .. } | Self::Video { clock_rate, .. }) = self;
         *clock_rate
@@ -1118,6 +1160,11 @@ impl RtpCodecParameters {
         parameters
     }
 
+    pub(crate) fn parameters_mut(&mut self) -> &mut RtpCodecParametersParameters {
+        let (Self::Audio { parameters, .. } | Self::Video { parameters, .. }) = self;
+        parameters
+    }
+
     pub(crate) fn rtcp_feedback_mut(&mut self) -> &mut Vec<RtcpFeedback> {
         let (Self::Audio { rtcp_feedback, .. } | Self::Video { rtcp_feedback, .. }) = self;
         rtcp_feedback
@@ -1310,7 +1357,7 @@ impl RtpEncodingParameters {
 
     pub(crate) fn from_fbs_ref(
         encoding_parameters: rtp_parameters::RtpEncodingParametersRef<'_>,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Self {
             ssrc: encoding_parameters.ssrc()?,
             rid: encoding_parameters.rid()?.map(|rid| rid.to_string()),
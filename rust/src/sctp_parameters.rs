@@ -24,7 +24,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// mediasoup-client provides specific per browser/version OS and MIS values via the
 /// device.sctpCapabilities getter.
-#[derive(Debug, Serialize, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub struct NumSctpStreams {
     /// Initially requested number of outgoing SCTP streams.
     #[serde(rename = "OS")]
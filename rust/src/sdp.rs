@@ -0,0 +1,1359 @@
+//! Conversion between mediasoup entities and SDP.
+//!
+//! mediasoup itself doesn't use SDP, but many endpoints (browsers without a handler library,
+//! WHIP/WHEP clients, etc.) do. [`RemoteSdp`] builds a complete remote session description for
+//! such an endpoint out of [`WebRtcTransport`], [`Producer`] and [`Consumer`] parameters, while
+//! [`SessionDescription`] parses an SDP created by the endpoint, so that it can be turned into
+//! [`DtlsParameters`], [`RtpCapabilities`] and [`ProducerOptions`].
+//!
+//! # Notes on usage
+//! Only BUNDLE with RTCP multiplexing is supported, which is what all modern WebRTC endpoints do
+//! anyway. Payload types and header extension ids are always taken from the given RTP
+//! parameters, it is up to the application to provide parameters negotiated with the endpoint.
+
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::Consumer;
+use crate::data_structures::{
+    DtlsFingerprint, DtlsParameters, DtlsRole, IceCandidate, IceCandidateTcpType, IceParameters,
+    Protocol,
+};
+use crate::ortc;
+use crate::producer::{Producer, ProducerOptions};
+use crate::rtp_parameters::{
+    MediaKind, MimeType, RtcpFeedback, RtcpParameters, RtpCapabilities, RtpCapabilitiesFinalized,
+    RtpCodecCapability, RtpCodecParameters, RtpCodecParametersParameters,
+    RtpCodecParametersParametersValue, RtpEncodingParameters, RtpEncodingParametersRtx,
+    RtpHeaderExtension, RtpHeaderExtensionDirection, RtpHeaderExtensionParameters,
+    RtpHeaderExtensionUri, RtpParameters,
+};
+use crate::sctp_parameters::SctpParameters;
+use crate::webrtc_transport::WebRtcTransport;
+use std::fmt;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Error that caused SDP parsing or conversion failure.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum SdpParseError {
+    /// Line is not of `<type>=<value>` form.
+    #[error("Invalid SDP line \"{line}\"")]
+    InvalidLine {
+        /// Line that failed to parse.
+        line: String,
+    },
+    /// Media description line is malformed.
+    #[error("Invalid media description \"{line}\"")]
+    InvalidMediaDescription {
+        /// Line that failed to parse.
+        line: String,
+    },
+    /// Required attribute is missing.
+    #[error("Missing attribute a={0}")]
+    MissingAttribute(&'static str),
+    /// Attribute has invalid value.
+    #[error("Invalid attribute value a={name}:{value}")]
+    InvalidAttribute {
+        /// Attribute name.
+        name: &'static str,
+        /// Attribute value.
+        value: String,
+    },
+    /// DTLS fingerprint attribute is malformed or uses unsupported hash function.
+    #[error("Invalid DTLS fingerprint \"{fingerprint}\": {reason}")]
+    InvalidFingerprint {
+        /// Attribute value.
+        fingerprint: String,
+        /// Why fingerprint is invalid.
+        reason: &'static str,
+    },
+    /// None of the offered media codecs is supported by the Router.
+    #[error("No compatible media codecs [mid:{mid}]")]
+    NoCompatibleMediaCodecs {
+        /// MID of the media description.
+        mid: String,
+    },
+}
+
+/// Direction of the media in a media description.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MediaDirection {
+    /// sendrecv
+    SendRecv,
+    /// sendonly
+    SendOnly,
+    /// recvonly
+    RecvOnly,
+    /// inactive
+    Inactive,
+}
+
+impl MediaDirection {
+    /// String representation of media direction.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SendRecv => "sendrecv",
+            Self::SendOnly => "sendonly",
+            Self::RecvOnly => "recvonly",
+            Self::Inactive => "inactive",
+        }
+    }
+
+    /// Direction the other side of the session sees for media flowing in this direction.
+    #[must_use]
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::SendRecv => Self::SendRecv,
+            Self::SendOnly => Self::RecvOnly,
+            Self::RecvOnly => Self::SendOnly,
+            Self::Inactive => Self::Inactive,
+        }
+    }
+}
+
+/// Single attribute (`a=` line) of SDP.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SdpAttribute {
+    /// Attribute name (part before the first colon).
+    pub name: String,
+    /// Attribute value (part after the first colon), `None` for property attributes.
+    pub value: Option<String>,
+}
+
+/// Media description (`m=` section) of parsed SDP.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MediaDescription {
+    /// Media type (`audio`, `video`, `application`, etc.).
+    pub media: String,
+    /// Port, `0` means that media description was rejected.
+    pub port: u16,
+    /// Transport protocol (`UDP/TLS/RTP/SAVPF`, `UDP/DTLS/SCTP`, etc.).
+    pub protocol: String,
+    /// Media formats (payload types for RTP media).
+    pub formats: Vec<String>,
+    /// Media-level attributes in order of appearance.
+    pub attributes: Vec<SdpAttribute>,
+}
+
+/// Parsed session description.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SessionDescription {
+    /// Session-level attributes in order of appearance.
+    pub attributes: Vec<SdpAttribute>,
+    /// Media descriptions in order of appearance.
+    pub media: Vec<MediaDescription>,
+}
+
+impl FromStr for SessionDescription {
+    type Err = SdpParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut session_description = SessionDescription::default();
+
+        for line in s.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+
+            let (r#type, value) = match line.split_once('=') {
+                Some((r#type, value)) if r#type.len() == 1 => (r#type, value),
+                _ => {
+                    return Err(SdpParseError::InvalidLine {
+                        line: line.to_string(),
+                    });
+                }
+            };
+
+            match r#type {
+                "m" => {
+                    session_description
+                        .media
+                        .push(MediaDescription::parse_media_line(value)?);
+                }
+                "a" => {
+                    let attribute = match value.split_once(':') {
+                        Some((name, value)) => SdpAttribute {
+                            name: name.to_string(),
+                            value: Some(value.to_string()),
+                        },
+                        None => SdpAttribute {
+                            name: value.to_string(),
+                            value: None,
+                        },
+                    };
+                    match session_description.media.last_mut() {
+                        Some(media) => media.attributes.push(attribute),
+                        None => session_description.attributes.push(attribute),
+                    }
+                }
+                // Other lines are not relevant for mediasoup.
+                _ => {}
+            }
+        }
+
+        Ok(session_description)
+    }
+}
+
+impl SessionDescription {
+    /// Value of the first session-level attribute with given name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// ICE parameters of the endpoint.
+    ///
+    /// Session-level attributes take precedence, otherwise the first non-rejected media
+    /// description is used (as all of them share the same transport due to BUNDLE).
+    pub fn ice_parameters(&self) -> Result<IceParameters, SdpParseError> {
+        let username_fragment = self
+            .transport_attribute("ice-ufrag")
+            .ok_or(SdpParseError::MissingAttribute("ice-ufrag"))?;
+        let password = self
+            .transport_attribute("ice-pwd")
+            .ok_or(SdpParseError::MissingAttribute("ice-pwd"))?;
+
+        Ok(IceParameters {
+            username_fragment: username_fragment.to_string(),
+            password: password.to_string(),
+            ice_lite: Some(
+                self.attributes
+                    .iter()
+                    .any(|attribute| attribute.name == "ice-lite"),
+            ),
+        })
+    }
+
+    /// DTLS parameters of the endpoint, suitable for
+    /// [`WebRtcTransportRemoteParameters`](crate::webrtc_transport::WebRtcTransportRemoteParameters).
+    ///
+    /// `a=setup:actpass` is resolved to [`DtlsRole::Client`], so the answer must be generated with
+    /// [`RemoteSdp::set_remote_dtls_parameters`] using these parameters.
+    pub fn dtls_parameters(&self) -> Result<DtlsParameters, SdpParseError> {
+        let mut fingerprints = self
+            .attributes
+            .iter()
+            .filter(|attribute| attribute.name == "fingerprint")
+            .filter_map(|attribute| attribute.value.as_deref())
+            .map(parse_fingerprint)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if fingerprints.is_empty() {
+            if let Some(media) = self.media.iter().find(|media| !media.is_rejected()) {
+                fingerprints = media
+                    .attributes_iter("fingerprint")
+                    .map(parse_fingerprint)
+                    .collect::<Result<Vec<_>, _>>()?;
+            }
+        }
+
+        if fingerprints.is_empty() {
+            return Err(SdpParseError::MissingAttribute("fingerprint"));
+        }
+
+        let role = match self.transport_attribute("setup") {
+            Some("active" | "actpass") => DtlsRole::Client,
+            Some("passive") => DtlsRole::Server,
+            Some(value) => {
+                return Err(SdpParseError::InvalidAttribute {
+                    name: "setup",
+                    value: value.to_string(),
+                });
+            }
+            None => DtlsRole::Auto,
+        };
+
+        Ok(DtlsParameters { role, fingerprints })
+    }
+
+    /// RTP capabilities of the endpoint, collected from all non-rejected audio and video media
+    /// descriptions. Codecs and header extensions not supported by mediasoup are skipped.
+    ///
+    /// Resulting capabilities are suitable for
+    /// [`ConsumerOptions`](crate::consumer::ConsumerOptions).
+    pub fn rtp_capabilities(&self) -> RtpCapabilities {
+        let mut rtp_capabilities = RtpCapabilities::default();
+
+        for media in &self.media {
+            let media_rtp_capabilities = media.rtp_capabilities();
+
+            for codec in media_rtp_capabilities.codecs {
+                if !rtp_capabilities.codecs.contains(&codec) {
+                    rtp_capabilities.codecs.push(codec);
+                }
+            }
+
+            for header_extension in media_rtp_capabilities.header_extensions {
+                if !rtp_capabilities
+                    .header_extensions
+                    .iter()
+                    .any(|ext| ext.kind == header_extension.kind && ext.uri == header_extension.uri)
+                {
+                    rtp_capabilities.header_extensions.push(header_extension);
+                }
+            }
+        }
+
+        rtp_capabilities
+    }
+
+    /// Producer options for every non-rejected audio and video media description in which the
+    /// endpoint sends media, in order of appearance.
+    ///
+    /// See [`MediaDescription::producer_options`] for details.
+    pub fn producer_options(
+        &self,
+        router_rtp_capabilities: &RtpCapabilitiesFinalized,
+    ) -> Result<Vec<ProducerOptions>, SdpParseError> {
+        self.media
+            .iter()
+            .filter(|media| {
+                !media.is_rejected()
+                    && media.kind().is_some()
+                    && matches!(
+                        media.direction(),
+                        MediaDirection::SendOnly | MediaDirection::SendRecv
+                    )
+            })
+            .map(|media| media.producer_options(router_rtp_capabilities))
+            .collect()
+    }
+
+    fn transport_attribute(&self, name: &str) -> Option<&str> {
+        self.attribute(name).or_else(|| {
+            self.media
+                .iter()
+                .find(|media| !media.is_rejected())
+                .and_then(|media| media.attribute(name))
+        })
+    }
+}
+
+impl MediaDescription {
+    fn parse_media_line(line: &str) -> Result<Self, SdpParseError> {
+        let invalid = || SdpParseError::InvalidMediaDescription {
+            line: line.to_string(),
+        };
+        let mut parts = line.split(' ');
+        let media = parts
+            .next()
+            .filter(|media| !media.is_empty())
+            .ok_or_else(invalid)?;
+        // Port may be followed by number of ports, which is irrelevant here.
+        let port = parts
+            .next()
+            .and_then(|port| port.split('/').next())
+            .and_then(|port| port.parse().ok())
+            .ok_or_else(invalid)?;
+        let protocol = parts.next().ok_or_else(invalid)?;
+
+        Ok(Self {
+            media: media.to_string(),
+            port,
+            protocol: protocol.to_string(),
+            formats: parts.map(str::to_string).collect(),
+            attributes: Vec::new(),
+        })
+    }
+
+    /// Media kind, `None` for non-RTP media descriptions.
+    pub fn kind(&self) -> Option<MediaKind> {
+        match self.media.as_str() {
+            "audio" => Some(MediaKind::Audio),
+            "video" => Some(MediaKind::Video),
+            _ => None,
+        }
+    }
+
+    /// Whether media description was rejected (port is zero).
+    pub fn is_rejected(&self) -> bool {
+        self.port == 0
+    }
+
+    /// MID of the media description.
+    pub fn mid(&self) -> Option<&str> {
+        self.attribute("mid")
+    }
+
+    /// Direction of the media from the point of view of the SDP creator.
+    pub fn direction(&self) -> MediaDirection {
+        self.attributes
+            .iter()
+            .rev()
+            .find_map(|attribute| match attribute.name.as_str() {
+                "sendrecv" => Some(MediaDirection::SendRecv),
+                "sendonly" => Some(MediaDirection::SendOnly),
+                "recvonly" => Some(MediaDirection::RecvOnly),
+                "inactive" => Some(MediaDirection::Inactive),
+                _ => None,
+            })
+            .unwrap_or(MediaDirection::SendRecv)
+    }
+
+    /// Value of the first attribute with given name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// RTP capabilities of the endpoint in this media description, empty if it is rejected or
+    /// not an audio or video one. Codecs and header extensions not supported by mediasoup are
+    /// skipped, payload types and header extension ids are those of the offer.
+    pub fn rtp_capabilities(&self) -> RtpCapabilities {
+        let mut rtp_capabilities = RtpCapabilities::default();
+
+        let kind = match self.kind() {
+            Some(kind) if !self.is_rejected() => kind,
+            _ => return rtp_capabilities,
+        };
+
+        for codec in self.codecs() {
+            let codec = codec.into_capability();
+            if !rtp_capabilities.codecs.contains(&codec) {
+                rtp_capabilities.codecs.push(codec);
+            }
+        }
+
+        for header_extension in self.header_extensions() {
+            if !rtp_capabilities
+                .header_extensions
+                .iter()
+                .any(|ext| ext.uri == header_extension.uri)
+            {
+                rtp_capabilities.header_extensions.push(RtpHeaderExtension {
+                    kind,
+                    uri: header_extension.uri,
+                    preferred_id: header_extension.id,
+                    preferred_encrypt: header_extension.encrypt,
+                    direction: RtpHeaderExtensionDirection::SendRecv,
+                });
+            }
+        }
+
+        rtp_capabilities
+    }
+
+    /// Producer options for the media the endpoint sends in this media description.
+    ///
+    /// The first offered media codec (in endpoint's preference order) supported by the Router is
+    /// selected along with its RTX codec, header extensions are reduced to those the Router can
+    /// receive. Encodings are created from RIDs (`a=rid` and `a=simulcast`) if present, from SSRCs
+    /// (`a=ssrc-group:SIM` and `a=ssrc-group:FID`) otherwise.
+    pub fn producer_options(
+        &self,
+        router_rtp_capabilities: &RtpCapabilitiesFinalized,
+    ) -> Result<ProducerOptions, SdpParseError> {
+        let kind = self.kind().ok_or(SdpParseError::InvalidMediaDescription {
+            line: format!("{} {} {}", self.media, self.port, self.protocol),
+        })?;
+        let mid = self.mid().ok_or(SdpParseError::MissingAttribute("mid"))?;
+        let codecs = self.codecs();
+
+        let (media_codec, cap_codec) = codecs
+            .iter()
+            .filter(|codec| !codec.is_rtx())
+            .find_map(|codec| {
+                let codec = codec.clone().into_parameters();
+                ortc::find_matching_codec_capability(&codec, router_rtp_capabilities)
+                    .map(|cap_codec| (codec, cap_codec))
+            })
+            .ok_or_else(|| SdpParseError::NoCompatibleMediaCodecs {
+                mid: mid.to_string(),
+            })?;
+
+        let cap_rtx_supported = router_rtp_capabilities.codecs.iter().any(|cap_rtx_codec| {
+            cap_rtx_codec.is_rtx()
+                && cap_rtx_codec.parameters().get("apt")
+                    == Some(&RtpCodecParametersParametersValue::Number(u32::from(
+                        cap_codec.preferred_payload_type(),
+                    )))
+        });
+        let rtx_codec = codecs
+            .iter()
+            .find(|codec| {
+                cap_rtx_supported
+                    && codec.is_rtx()
+                    && codec.parameters.get("apt")
+                        == Some(&RtpCodecParametersParametersValue::Number(u32::from(
+                            media_codec.payload_type(),
+                        )))
+            })
+            .map(|codec| codec.clone().into_parameters());
+
+        let header_extensions = self
+            .header_extensions()
+            .into_iter()
+            .filter(|ext| {
+                router_rtp_capabilities
+                    .header_extensions
+                    .iter()
+                    .any(|cap_ext| {
+                        cap_ext.kind == kind
+                            && cap_ext.uri == ext.uri
+                            && matches!(
+                                cap_ext.direction,
+                                RtpHeaderExtensionDirection::SendRecv
+                                    | RtpHeaderExtensionDirection::RecvOnly
+                            )
+                    })
+            })
+            .collect();
+
+        let encodings = self.encodings(rtx_codec.is_some());
+
+        let mut codecs = vec![media_codec];
+        codecs.extend(rtx_codec);
+
+        Ok(ProducerOptions::new(
+            kind,
+            RtpParameters {
+                mid: Some(mid.to_string()),
+                codecs,
+                header_extensions,
+                encodings,
+                rtcp: RtcpParameters {
+                    cname: self.cname().map(str::to_string),
+                    reduced_size: self.attribute_present("rtcp-rsize"),
+                },
+            },
+        ))
+    }
+
+    fn attributes_iter<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.attributes
+            .iter()
+            .filter(move |attribute| attribute.name == name)
+            .filter_map(|attribute| attribute.value.as_deref())
+    }
+
+    fn attribute_present(&self, name: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute.name == name)
+    }
+
+    /// Values of attributes like `a=rtpmap:<pt> <value>` for given payload type.
+    fn payload_type_attributes<'a>(
+        &'a self,
+        name: &'a str,
+        payload_type: &'a str,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.attributes_iter(name).filter_map(move |value| {
+            let (pt, value) = value.split_once(' ')?;
+            (pt == payload_type || pt == "*").then_some(value)
+        })
+    }
+
+    fn codecs(&self) -> Vec<ParsedCodec> {
+        let kind = match self.kind() {
+            Some(kind) => kind,
+            None => return Vec::new(),
+        };
+
+        self.formats
+            .iter()
+            .filter_map(|format| {
+                let payload_type = format.parse::<u8>().ok()?;
+                let rtpmap = self.payload_type_attributes("rtpmap", format).next()?;
+                let mut rtpmap = rtpmap.split('/');
+                let name = rtpmap.next()?;
+                let clock_rate = rtpmap.next()?.parse::<NonZeroU32>().ok()?;
+                let channels = match rtpmap.next() {
+                    Some(channels) => channels.parse::<NonZeroU8>().ok()?,
+                    None => NonZeroU8::new(1).unwrap(),
+                };
+                let mime_type = match kind {
+                    MediaKind::Audio => MimeType::from_str(&format!("audio/{name}")),
+                    MediaKind::Video => MimeType::from_str(&format!("video/{name}")),
+                }
+                .ok()?;
+
+                let parameters = self
+                    .payload_type_attributes("fmtp", format)
+                    .flat_map(|fmtp| fmtp.split(';'))
+                    .filter_map(|parameter| {
+                        let (key, value) = parameter.trim().split_once('=')?;
+                        Some((key.to_string(), parse_parameter_value(key, value)))
+                    })
+                    .collect();
+
+                let rtcp_feedback = self
+                    .payload_type_attributes("rtcp-fb", format)
+                    .filter_map(|rtcp_fb| {
+                        let (r#type, parameter) = rtcp_fb.split_once(' ').unwrap_or((rtcp_fb, ""));
+                        RtcpFeedback::from_type_parameter(r#type, parameter).ok()
+                    })
+                    .fold(Vec::new(), |mut rtcp_feedback, fb| {
+                        if !rtcp_feedback.contains(&fb) {
+                            rtcp_feedback.push(fb);
+                        }
+                        rtcp_feedback
+                    });
+
+                Some(ParsedCodec {
+                    mime_type,
+                    payload_type,
+                    clock_rate,
+                    channels,
+                    parameters,
+                    rtcp_feedback,
+                })
+            })
+            .collect()
+    }
+
+    fn header_extensions(&self) -> Vec<RtpHeaderExtensionParameters> {
+        self.attributes_iter("extmap")
+            .filter_map(|extmap| {
+                let mut parts = extmap.split(' ');
+                // Id may be followed by direction, like `4/recvonly`.
+                let id = parts.next()?.split('/').next()?.parse().ok()?;
+                let uri = parts.next()?.parse().ok()?;
+                Some(RtpHeaderExtensionParameters {
+                    uri,
+                    id,
+                    encrypt: false,
+                })
+            })
+            .collect()
+    }
+
+    fn cname(&self) -> Option<&str> {
+        self.attributes_iter("ssrc").find_map(|ssrc| {
+            let (_, attribute) = ssrc.split_once(' ')?;
+            attribute.strip_prefix("cname:")
+        })
+    }
+
+    fn ssrc_group(&self, semantics: &str) -> impl Iterator<Item = Vec<u32>> + '_ {
+        let semantics = format!("{semantics} ");
+        self.attributes_iter("ssrc-group").filter_map(move |group| {
+            group.strip_prefix(semantics.as_str()).map(|ssrcs| {
+                ssrcs
+                    .split(' ')
+                    .filter_map(|ssrc| ssrc.parse().ok())
+                    .collect()
+            })
+        })
+    }
+
+    fn encodings(&self, rtx: bool) -> Vec<RtpEncodingParameters> {
+        let rids = self
+            .attributes_iter("rid")
+            .filter_map(|rid| {
+                let mut parts = rid.split(' ');
+                let rid = parts.next()?;
+                (parts.next()? == "send").then_some(rid)
+            })
+            .collect::<Vec<_>>();
+
+        if !rids.is_empty() {
+            // Simulcast attribute defines the order of streams, use it if available.
+            let simulcast_rids = self
+                .attribute("simulcast")
+                .and_then(|simulcast| simulcast.strip_prefix("send "))
+                .map(|streams| {
+                    streams
+                        .split(';')
+                        // Only the first of alternative formats is used.
+                        .filter_map(|alternatives| alternatives.split(',').next())
+                        .map(|rid| rid.trim_start_matches('~'))
+                        .filter(|rid| rids.contains(rid))
+                        .collect::<Vec<_>>()
+                })
+                .filter(|simulcast_rids| !simulcast_rids.is_empty())
+                .unwrap_or(rids);
+
+            return simulcast_rids
+                .into_iter()
+                .map(|rid| RtpEncodingParameters {
+                    rid: Some(rid.to_string()),
+                    ..RtpEncodingParameters::default()
+                })
+                .collect();
+        }
+
+        let fid_groups = self.ssrc_group("FID").collect::<Vec<_>>();
+        let rtx_ssrc = |ssrc: u32| {
+            fid_groups
+                .iter()
+                .find(|group| group.first() == Some(&ssrc))
+                .and_then(|group| group.get(1))
+                .filter(|_| rtx)
+                .map(|&ssrc| RtpEncodingParametersRtx { ssrc })
+        };
+
+        let ssrcs = match self.ssrc_group("SIM").next() {
+            Some(ssrcs) => ssrcs,
+            None => fid_groups
+                .first()
+                .and_then(|group| group.first().copied())
+                .or_else(|| {
+                    self.attributes_iter("ssrc")
+                        .find_map(|ssrc| ssrc.split(' ').next()?.parse().ok())
+                })
+                .into_iter()
+                .collect(),
+        };
+
+        if ssrcs.is_empty() {
+            // No explicit SSRC, stream will be matched by MID.
+            return vec![RtpEncodingParameters::default()];
+        }
+
+        ssrcs
+            .into_iter()
+            .map(|ssrc| RtpEncodingParameters {
+                ssrc: Some(ssrc),
+                rtx: rtx_ssrc(ssrc),
+                ..RtpEncodingParameters::default()
+            })
+            .collect()
+    }
+}
+
+/// Codec as described in a media description.
+#[derive(Debug, Clone)]
+struct ParsedCodec {
+    mime_type: MimeType,
+    payload_type: u8,
+    clock_rate: NonZeroU32,
+    channels: NonZeroU8,
+    parameters: RtpCodecParametersParameters,
+    rtcp_feedback: Vec<RtcpFeedback>,
+}
+
+impl ParsedCodec {
+    fn is_rtx(&self) -> bool {
+        self.mime_type.as_str().ends_with("/rtx")
+    }
+
+    fn into_capability(self) -> RtpCodecCapability {
+        match self.mime_type {
+            MimeType::Audio(mime_type) => RtpCodecCapability::Audio {
+                mime_type,
+                preferred_payload_type: Some(self.payload_type),
+                clock_rate: self.clock_rate,
+                channels: self.channels,
+                parameters: self.parameters,
+                rtcp_feedback: self.rtcp_feedback,
+            },
+            MimeType::Video(mime_type) => RtpCodecCapability::Video {
+                mime_type,
+                preferred_payload_type: Some(self.payload_type),
+                clock_rate: self.clock_rate,
+                parameters: self.parameters,
+                rtcp_feedback: self.rtcp_feedback,
+            },
+        }
+    }
+
+    fn into_parameters(self) -> RtpCodecParameters {
+        match self.mime_type {
+            MimeType::Audio(mime_type) => RtpCodecParameters::Audio {
+                mime_type,
+                payload_type: self.payload_type,
+                clock_rate: self.clock_rate,
+                channels: self.channels,
+                parameters: self.parameters,
+                rtcp_feedback: self.rtcp_feedback,
+            },
+            MimeType::Video(mime_type) => RtpCodecParameters::Video {
+                mime_type,
+                payload_type: self.payload_type,
+                clock_rate: self.clock_rate,
+                parameters: self.parameters,
+                rtcp_feedback: self.rtcp_feedback,
+            },
+        }
+    }
+}
+
+fn media_kind_str(kind: MediaKind) -> &'static str {
+    match kind {
+        MediaKind::Audio => "audio",
+        MediaKind::Video => "video",
+    }
+}
+
+fn find_attribute<'a>(attributes: &'a [SdpAttribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .map(|attribute| attribute.value.as_deref().unwrap_or_default())
+}
+
+fn parse_parameter_value(key: &str, value: &str) -> RtpCodecParametersParametersValue {
+    // `profile-level-id` is hexadecimal and must stay a string even if it only has digits.
+    if key != "profile-level-id" {
+        if let Ok(number) = value.parse::<u32>() {
+            return RtpCodecParametersParametersValue::Number(number);
+        }
+    }
+
+    RtpCodecParametersParametersValue::String(value.to_string().into())
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<DtlsFingerprint, SdpParseError> {
+    /// Parses colon-separated hex bytes like `1B:EA:BF`.
+    fn parse_bytes<const N: usize>(value: &str) -> Option<[u8; N]> {
+        let mut bytes = [0_u8; N];
+        let mut parts = value.split(':');
+        for byte in &mut bytes {
+            let part = parts.next()?;
+            if part.len() != 2 || !part.bytes().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            *byte = u8::from_str_radix(part, 16).ok()?;
+        }
+
+        parts.next().is_none().then_some(bytes)
+    }
+
+    let invalid = |reason| SdpParseError::InvalidFingerprint {
+        fingerprint: fingerprint.to_string(),
+        reason,
+    };
+    let (algorithm, value) = fingerprint
+        .split_once(' ')
+        .ok_or_else(|| invalid("missing value"))?;
+    let invalid_value = || invalid("value is not a series of hex bytes of expected length");
+
+    Ok(match algorithm.to_ascii_lowercase().as_str() {
+        "sha-1" => DtlsFingerprint::Sha1 {
+            value: parse_bytes(value).ok_or_else(invalid_value)?,
+        },
+        "sha-224" => DtlsFingerprint::Sha224 {
+            value: parse_bytes(value).ok_or_else(invalid_value)?,
+        },
+        "sha-256" => DtlsFingerprint::Sha256 {
+            value: parse_bytes(value).ok_or_else(invalid_value)?,
+        },
+        "sha-384" => DtlsFingerprint::Sha384 {
+            value: parse_bytes(value).ok_or_else(invalid_value)?,
+        },
+        "sha-512" => DtlsFingerprint::Sha512 {
+            value: parse_bytes(value).ok_or_else(invalid_value)?,
+        },
+        _ => {
+            return Err(invalid("unsupported hash function"));
+        }
+    })
+}
+
+#[derive(Debug, Clone)]
+enum MediaSection {
+    Rtp {
+        mid: String,
+        kind: MediaKind,
+        direction: MediaDirection,
+        rtp_parameters: RtpParameters,
+        /// Stream and track ids.
+        msid: Option<(String, String)>,
+    },
+    Application {
+        mid: String,
+    },
+    Rejected {
+        mid: Option<String>,
+        media: String,
+        protocol: String,
+        formats: Vec<String>,
+    },
+}
+
+impl MediaSection {
+    fn mid(&self) -> Option<&str> {
+        match self {
+            Self::Rtp { mid, .. } | Self::Application { mid } => Some(mid),
+            Self::Rejected { mid, .. } => mid.as_deref(),
+        }
+    }
+}
+
+/// Builder of the SDP describing mediasoup side of a [`WebRtcTransport`] for the remote endpoint.
+///
+/// Media sections are written in the order they were added. When answering an offer, they must be
+/// added in the same order as media descriptions in the offer (use [`RemoteSdp::reject`] for those
+/// that are not accepted).
+///
+/// # Example
+/// ```no_run
+/// # async fn f(
+/// #     transport: mediasoup::webrtc_transport::WebRtcTransport,
+/// #     consumer: mediasoup::consumer::Consumer,
+/// # ) {
+/// use mediasoup::sdp::RemoteSdp;
+///
+/// let mut remote_sdp = RemoteSdp::from_webrtc_transport(&transport);
+/// remote_sdp.add_consumer(&consumer);
+///
+/// let offer = remote_sdp.to_string();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RemoteSdp {
+    session_id: u64,
+    session_version: u64,
+    ice_parameters: IceParameters,
+    ice_candidates: Vec<IceCandidate>,
+    dtls_parameters: DtlsParameters,
+    sctp_parameters: Option<SctpParameters>,
+    media_sections: Vec<MediaSection>,
+}
+
+impl RemoteSdp {
+    /// Create remote SDP with given transport parameters and no media sections.
+    #[must_use]
+    pub fn new(
+        ice_parameters: IceParameters,
+        ice_candidates: Vec<IceCandidate>,
+        dtls_parameters: DtlsParameters,
+        sctp_parameters: Option<SctpParameters>,
+    ) -> Self {
+        Self {
+            session_id: fastrand::u64(1_000_000_000..u64::from(u32::MAX)),
+            session_version: 0,
+            ice_parameters,
+            ice_candidates,
+            dtls_parameters,
+            sctp_parameters,
+            media_sections: Vec::new(),
+        }
+    }
+
+    /// Create remote SDP with transport parameters of given [`WebRtcTransport`].
+    #[must_use]
+    pub fn from_webrtc_transport(transport: &WebRtcTransport) -> Self {
+        Self::new(
+            transport.ice_parameters().clone(),
+            transport.ice_candidates().clone(),
+            transport.dtls_parameters(),
+            transport.sctp_parameters(),
+        )
+    }
+
+    /// Update ICE parameters (after
+    /// [`WebRtcTransport::restart_ice`](crate::webrtc_transport::WebRtcTransport::restart_ice)),
+    /// this also increments session version.
+    pub fn set_ice_parameters(&mut self, ice_parameters: IceParameters) {
+        self.ice_parameters = ice_parameters;
+        self.session_version += 1;
+    }
+
+    /// Set local DTLS role announced in `a=setup` attribute.
+    ///
+    /// [`DtlsRole::Auto`] results in `actpass`, which is only valid in an offer.
+    pub fn set_dtls_role(&mut self, role: DtlsRole) {
+        self.dtls_parameters.role = role;
+    }
+
+    /// Set local DTLS role complementary to the role in remote DTLS parameters (as returned by
+    /// [`SessionDescription::dtls_parameters`]), which is needed when answering an offer.
+    pub fn set_remote_dtls_parameters(&mut self, remote_dtls_parameters: &DtlsParameters) {
+        self.dtls_parameters.role = match remote_dtls_parameters.role {
+            DtlsRole::Auto => DtlsRole::Auto,
+            DtlsRole::Client => DtlsRole::Server,
+            DtlsRole::Server => DtlsRole::Client,
+        };
+    }
+
+    /// Add media section through which mediasoup sends media of given [`Consumer`].
+    pub fn add_consumer(&mut self, consumer: &Consumer) {
+        let rtp_parameters = consumer.rtp_parameters();
+        self.media_sections.push(MediaSection::Rtp {
+            mid: rtp_parameters
+                .mid
+                .clone()
+                .unwrap_or_else(|| consumer.id().to_string()),
+            kind: consumer.kind(),
+            direction: MediaDirection::SendOnly,
+            rtp_parameters: rtp_parameters.clone(),
+            msid: Some((
+                consumer.producer_id().to_string(),
+                consumer.id().to_string(),
+            )),
+        });
+    }
+
+    /// Add media section through which mediasoup receives media of given [`Producer`].
+    pub fn add_producer(&mut self, producer: &Producer) {
+        let rtp_parameters = producer.rtp_parameters();
+        self.media_sections.push(MediaSection::Rtp {
+            mid: rtp_parameters
+                .mid
+                .clone()
+                .unwrap_or_else(|| producer.id().to_string()),
+            kind: producer.kind(),
+            direction: MediaDirection::RecvOnly,
+            rtp_parameters: rtp_parameters.clone(),
+            msid: None,
+        });
+    }
+
+    /// Add media section through which mediasoup sends media with given RTP parameters.
+    pub fn add_send_media(
+        &mut self,
+        mid: impl Into<String>,
+        kind: MediaKind,
+        rtp_parameters: RtpParameters,
+    ) {
+        self.media_sections.push(MediaSection::Rtp {
+            mid: mid.into(),
+            kind,
+            direction: MediaDirection::SendOnly,
+            rtp_parameters,
+            msid: None,
+        });
+    }
+
+    /// Add media section through which mediasoup receives media with given RTP parameters.
+    pub fn add_recv_media(
+        &mut self,
+        mid: impl Into<String>,
+        kind: MediaKind,
+        rtp_parameters: RtpParameters,
+    ) {
+        self.media_sections.push(MediaSection::Rtp {
+            mid: mid.into(),
+            kind,
+            direction: MediaDirection::RecvOnly,
+            rtp_parameters,
+            msid: None,
+        });
+    }
+
+    /// Add SCTP media section for DataChannels. It is rejected if transport has no SCTP
+    /// parameters.
+    pub fn add_data_channel(&mut self, mid: impl Into<String>) {
+        self.media_sections
+            .push(MediaSection::Application { mid: mid.into() });
+    }
+
+    /// Add rejected media section in place of given media description of the offer.
+    pub fn reject(&mut self, media: &MediaDescription) {
+        self.media_sections.push(MediaSection::Rejected {
+            mid: media.mid().map(str::to_string),
+            media: media.media.clone(),
+            protocol: media.protocol.clone(),
+            formats: media.formats.clone(),
+        });
+    }
+
+    /// MIDs of media sections in order they were added.
+    pub fn mids(&self) -> impl Iterator<Item = &str> {
+        self.media_sections.iter().filter_map(MediaSection::mid)
+    }
+
+    /// Trickle ICE fragment (`application/trickle-ice-sdpfrag`, RFC 8840) with current local ICE
+    /// parameters and candidates, as used in response to ICE restart requests.
+    pub fn ice_fragment(&self) -> String {
+        let mut fragment = String::new();
+        // Writing into `String` never fails.
+        let _ = self.write_ice_fragment(&mut fragment);
+        fragment
+    }
+
+    fn write_ice_fragment(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if self.ice_parameters.ice_lite != Some(false) {
+            write!(f, "a=ice-lite\r\n")?;
+        }
+        let bundle_mids = self.bundle_mids();
+        if !bundle_mids.is_empty() {
+            write!(f, "a=group:BUNDLE {}\r\n", bundle_mids.join(" "))?;
+        }
+        // Candidates are shared by all bundled media sections, so the first one is sufficient.
+        for media_section in &self.media_sections {
+            match media_section {
+                MediaSection::Rtp {
+                    mid,
+                    kind,
+                    rtp_parameters,
+                    ..
+                } => {
+                    write!(f, "m={} 9 UDP/TLS/RTP/SAVPF", media_kind_str(*kind))?;
+                    for codec in &rtp_parameters.codecs {
+                        write!(f, " {}", codec.payload_type())?;
+                    }
+                    write!(f, "\r\na=mid:{mid}\r\n")?;
+                }
+                MediaSection::Application { mid } if self.sctp_parameters.is_some() => {
+                    write!(f, "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n")?;
+                    write!(f, "a=mid:{mid}\r\n")?;
+                }
+                MediaSection::Application { .. } | MediaSection::Rejected { .. } => continue,
+            }
+            self.write_ice_credentials(f)?;
+            self.write_ice_candidates(f)?;
+            break;
+        }
+
+        Ok(())
+    }
+
+    fn bundle_mids(&self) -> Vec<&str> {
+        self.media_sections
+            .iter()
+            .filter(|media_section| match media_section {
+                MediaSection::Rtp { .. } => true,
+                MediaSection::Application { .. } => self.sctp_parameters.is_some(),
+                MediaSection::Rejected { .. } => false,
+            })
+            .filter_map(MediaSection::mid)
+            .collect()
+    }
+
+    fn write_ice_credentials(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(
+            f,
+            "a=ice-ufrag:{}\r\n",
+            self.ice_parameters.username_fragment
+        )?;
+        write!(f, "a=ice-pwd:{}\r\n", self.ice_parameters.password)
+    }
+
+    fn write_ice_candidates(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for candidate in &self.ice_candidates {
+            write!(
+                f,
+                "a=candidate:{} 1 {} {} {} {} typ host",
+                candidate.foundation,
+                match candidate.protocol {
+                    Protocol::Udp => "udp",
+                    Protocol::Tcp => "tcp",
+                },
+                candidate.priority,
+                candidate.ip,
+                candidate.port,
+            )?;
+            if let Some(IceCandidateTcpType::Passive) = candidate.tcp_type {
+                write!(f, " tcptype passive")?;
+            }
+            write!(f, "\r\n")?;
+        }
+        write!(f, "a=end-of-candidates\r\n")
+    }
+
+    fn write_transport(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_ice_credentials(f)?;
+        for fingerprint in &self.dtls_parameters.fingerprints {
+            write!(
+                f,
+                "a=fingerprint:{} {}\r\n",
+                fingerprint.algorithm_str(),
+                fingerprint.value_string()
+            )?;
+        }
+        write!(
+            f,
+            "a=setup:{}\r\n",
+            match self.dtls_parameters.role {
+                DtlsRole::Auto => "actpass",
+                DtlsRole::Client => "active",
+                DtlsRole::Server => "passive",
+            }
+        )?;
+        self.write_ice_candidates(f)
+    }
+
+    fn write_rtp_section(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        mid: &str,
+        kind: MediaKind,
+        direction: MediaDirection,
+        rtp_parameters: &RtpParameters,
+        msid: &Option<(String, String)>,
+    ) -> fmt::Result {
+        write!(f, "m={} 7 UDP/TLS/RTP/SAVPF", media_kind_str(kind))?;
+        for codec in &rtp_parameters.codecs {
+            write!(f, " {}", codec.payload_type())?;
+        }
+        write!(f, "\r\nc=IN IP4 127.0.0.1\r\n")?;
+        self.write_transport(f)?;
+        write!(f, "a=mid:{mid}\r\n")?;
+        write!(f, "a={}\r\n", direction.as_str())?;
+        write!(f, "a=rtcp-mux\r\n")?;
+        if rtp_parameters.rtcp.reduced_size {
+            write!(f, "a=rtcp-rsize\r\n")?;
+        }
+
+        for codec in &rtp_parameters.codecs {
+            let payload_type = codec.payload_type();
+            let mime_type = codec.mime_type();
+            let (_, name) = mime_type.as_str().split_once('/').unwrap_or_default();
+            write!(f, "a=rtpmap:{payload_type} {name}/{}", codec.clock_rate())?;
+            if let RtpCodecParameters::Audio { channels, .. } = codec {
+                if channels.get() > 1 {
+                    write!(f, "/{channels}")?;
+                }
+            }
+            write!(f, "\r\n")?;
+
+            let (RtpCodecParameters::Audio {
+                parameters,
+                rtcp_feedback,
+                ..
+            }
+            | RtpCodecParameters::Video {
+                parameters,
+                rtcp_feedback,
+                ..
+            }) = codec;
+
+            if parameters.iter().next().is_some() {
+                write!(f, "a=fmtp:{payload_type} ")?;
+                for (i, (key, value)) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ";")?;
+                    }
+                    match value {
+                        RtpCodecParametersParametersValue::String(value) => {
+                            write!(f, "{key}={value}")?;
+                        }
+                        RtpCodecParametersParametersValue::Number(value) => {
+                            write!(f, "{key}={value}")?;
+                        }
+                    }
+                }
+                write!(f, "\r\n")?;
+            }
+
+            for fb in rtcp_feedback {
+                if fb == &RtcpFeedback::Unsupported {
+                    continue;
+                }
+                match fb.as_type_parameter() {
+                    (r#type, "") => write!(f, "a=rtcp-fb:{payload_type} {type}\r\n")?,
+                    (r#type, parameter) => {
+                        write!(f, "a=rtcp-fb:{payload_type} {type} {parameter}\r\n")?;
+                    }
+                }
+            }
+        }
+
+        for ext in &rtp_parameters.header_extensions {
+            if ext.uri != RtpHeaderExtensionUri::Unsupported {
+                write!(f, "a=extmap:{} {}\r\n", ext.id, ext.uri.as_str())?;
+            }
+        }
+
+        match direction {
+            MediaDirection::RecvOnly | MediaDirection::SendRecv => {
+                let rids = rtp_parameters
+                    .encodings
+                    .iter()
+                    .filter_map(|encoding| encoding.rid.as_deref())
+                    .collect::<Vec<_>>();
+                if !rids.is_empty() {
+                    for rid in &rids {
+                        write!(f, "a=rid:{rid} recv\r\n")?;
+                    }
+                    write!(f, "a=simulcast:recv {}\r\n", rids.join(";"))?;
+                }
+            }
+            MediaDirection::SendOnly | MediaDirection::Inactive => {}
+        }
+
+        if direction == MediaDirection::SendOnly {
+            if let Some((stream_id, track_id)) = msid {
+                write!(f, "a=msid:{stream_id} {track_id}\r\n")?;
+            }
+            let cname = rtp_parameters.rtcp.cname.as_deref().unwrap_or(mid);
+            for encoding in &rtp_parameters.encodings {
+                let ssrc = match encoding.ssrc {
+                    Some(ssrc) => ssrc,
+                    None => continue,
+                };
+                write!(f, "a=ssrc:{ssrc} cname:{cname}\r\n")?;
+                if let Some((stream_id, track_id)) = msid {
+                    write!(f, "a=ssrc:{ssrc} msid:{stream_id} {track_id}\r\n")?;
+                }
+                if let Some(rtx) = encoding.rtx {
+                    write!(f, "a=ssrc:{} cname:{cname}\r\n", rtx.ssrc)?;
+                    if let Some((stream_id, track_id)) = msid {
+                        write!(f, "a=ssrc:{} msid:{stream_id} {track_id}\r\n", rtx.ssrc)?;
+                    }
+                    write!(f, "a=ssrc-group:FID {ssrc} {}\r\n", rtx.ssrc)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for RemoteSdp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v=0\r\n")?;
+        write!(
+            f,
+            "o=mediasoup-server {} {} IN IP4 0.0.0.0\r\n",
+            self.session_id, self.session_version
+        )?;
+        write!(f, "s=-\r\n")?;
+        write!(f, "t=0 0\r\n")?;
+        if self.ice_parameters.ice_lite != Some(false) {
+            write!(f, "a=ice-lite\r\n")?;
+        }
+
+        let bundle_mids = self.bundle_mids();
+        if !bundle_mids.is_empty() {
+            write!(f, "a=group:BUNDLE {}\r\n", bundle_mids.join(" "))?;
+        }
+        write!(f, "a=msid-semantic: WMS *\r\n")?;
+
+        for media_section in &self.media_sections {
+            match media_section {
+                MediaSection::Rtp {
+                    mid,
+                    kind,
+                    direction,
+                    rtp_parameters,
+                    msid,
+                } => {
+                    self.write_rtp_section(f, mid, *kind, *direction, rtp_parameters, msid)?;
+                }
+                MediaSection::Application { mid } => match &self.sctp_parameters {
+                    Some(sctp_parameters) => {
+                        write!(f, "m=application 7 UDP/DTLS/SCTP webrtc-datachannel\r\n")?;
+                        write!(f, "c=IN IP4 127.0.0.1\r\n")?;
+                        self.write_transport(f)?;
+                        write!(f, "a=mid:{mid}\r\n")?;
+                        write!(f, "a=sctp-port:{}\r\n", sctp_parameters.port)?;
+                        write!(
+                            f,
+                            "a=max-message-size:{}\r\n",
+                            sctp_parameters.max_message_size
+                        )?;
+                    }
+                    None => {
+                        write!(f, "m=application 0 UDP/DTLS/SCTP webrtc-datachannel\r\n")?;
+                        write!(f, "c=IN IP4 127.0.0.1\r\n")?;
+                        write!(f, "a=mid:{mid}\r\n")?;
+                    }
+                },
+                MediaSection::Rejected {
+                    mid,
+                    media,
+                    protocol,
+                    formats,
+                } => {
+                    write!(f, "m={media} 0 {protocol}")?;
+                    for format in formats {
+                        write!(f, " {format}")?;
+                    }
+                    write!(f, "\r\nc=IN IP4 127.0.0.1\r\n")?;
+                    if let Some(mid) = mid {
+                        write!(f, "a=mid:{mid}\r\n")?;
+                    }
+                    write!(f, "a=inactive\r\n")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+use super::*;
+use crate::data_structures::{IceCandidateType, Protocol};
+use crate::rtp_parameters::{MimeTypeAudio, MimeTypeVideo, RtpCodecCapability};
+use std::net::{IpAddr, Ipv4Addr};
+
+const BROWSER_OFFER: &str = "v=0\r
+o=- 4611731400430051336 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0 1 2\r
+a=extmap-allow-mixed\r
+a=msid-semantic: WMS stream\r
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r
+c=IN IP4 0.0.0.0\r
+a=rtcp:9 IN IP4 0.0.0.0\r
+a=ice-ufrag:ZpmH\r
+a=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r
+a=ice-options:trickle\r
+a=fingerprint:sha-256 1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:0\r
+a=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r
+a=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid\r
+a=extmap:14 urn:ietf:params:rtp-hdrext:unknown\r
+a=sendonly\r
+a=msid:stream audio-track\r
+a=rtcp-mux\r
+a=rtpmap:111 opus/48000/2\r
+a=rtcp-fb:111 transport-cc\r
+a=fmtp:111 minptime=10;useinbandfec=1\r
+a=rtpmap:0 PCMU/8000\r
+a=ssrc:1111 cname:browser-cname\r
+a=ssrc:1111 msid:stream audio-track\r
+m=video 9 UDP/TLS/RTP/SAVPF 98 96 97 102\r
+c=IN IP4 0.0.0.0\r
+a=rtcp:9 IN IP4 0.0.0.0\r
+a=ice-ufrag:ZpmH\r
+a=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r
+a=ice-options:trickle\r
+a=fingerprint:sha-256 1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:1\r
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid\r
+a=extmap:10 urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id\r
+a=extmap:11 urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id\r
+a=sendonly\r
+a=msid:stream video-track\r
+a=rtcp-mux\r
+a=rtcp-rsize\r
+a=rtpmap:98 VP9/90000\r
+a=rtcp-fb:98 nack\r
+a=fmtp:98 profile-id=2\r
+a=rtpmap:96 VP8/90000\r
+a=rtcp-fb:96 goog-remb\r
+a=rtcp-fb:96 transport-cc\r
+a=rtcp-fb:96 ccm fir\r
+a=rtcp-fb:96 nack\r
+a=rtcp-fb:96 nack pli\r
+a=rtpmap:97 rtx/90000\r
+a=fmtp:97 apt=96\r
+a=rtpmap:102 H264/90000\r
+a=rtcp-fb:102 nack\r
+a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r
+a=rid:h send\r
+a=rid:m send\r
+a=rid:l send\r
+a=simulcast:send l;m;h\r
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:ZpmH\r
+a=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r
+a=fingerprint:sha-256 1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:2\r
+a=sctp-port:5000\r
+a=max-message-size:262144\r
+";
+
+fn router_rtp_capabilities() -> RtpCapabilitiesFinalized {
+    ortc::generate_router_rtp_capabilities(vec![
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    ])
+    .expect("Failed to generate router RTP capabilities")
+}
+
+fn remote_sdp() -> RemoteSdp {
+    RemoteSdp::new(
+        IceParameters {
+            username_fragment: "ufrag".to_string(),
+            password: "password".to_string(),
+            ice_lite: Some(true),
+        },
+        vec![IceCandidate {
+            foundation: "udpcandidate".to_string(),
+            priority: 1076302079,
+            ip: IpAddr::V4(Ipv4Addr::new(9, 9, 9, 9)),
+            protocol: Protocol::Udp,
+            port: 40533,
+            r#type: IceCandidateType::Host,
+            tcp_type: None,
+        }],
+        BROWSER_OFFER
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters()
+            .unwrap(),
+        Some(SctpParameters {
+            port: 5000,
+            os: 1024,
+            mis: 1024,
+            max_message_size: 262144,
+        }),
+    )
+}
+
+#[test]
+fn parse_transport_parameters() {
+    let offer = BROWSER_OFFER.parse::<SessionDescription>().unwrap();
+
+    assert_eq!(offer.media.len(), 3);
+    assert_eq!(offer.media[1].mid(), Some("1"));
+    assert_eq!(offer.media[1].direction(), MediaDirection::SendOnly);
+    assert_eq!(offer.media[2].kind(), None);
+
+    let ice_parameters = offer.ice_parameters().unwrap();
+    assert_eq!(ice_parameters.username_fragment, "ZpmH");
+    assert_eq!(ice_parameters.password, "IqdnXKvXpHG3VSU6ZcMhsbJA");
+    assert_eq!(ice_parameters.ice_lite, Some(false));
+
+    let dtls_parameters = offer.dtls_parameters().unwrap();
+    assert_eq!(dtls_parameters.role, DtlsRole::Client);
+    assert_eq!(dtls_parameters.fingerprints.len(), 1);
+    assert_eq!(dtls_parameters.fingerprints[0].algorithm_str(), "sha-256");
+    assert_eq!(
+        dtls_parameters.fingerprints[0].value_string(),
+        "1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43",
+    );
+}
+
+#[test]
+fn parse_invalid() {
+    assert_eq!(
+        "v=0\r\nfoo\r\n".parse::<SessionDescription>(),
+        Err(SdpParseError::InvalidLine {
+            line: "foo".to_string()
+        }),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio\r\n".parse::<SessionDescription>(),
+        Err(SdpParseError::InvalidMediaDescription {
+            line: "audio".to_string()
+        }),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n"
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters(),
+        Err(SdpParseError::MissingAttribute("fingerprint")),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=fingerprint:sha-256 1B:EA:BF\r\n"
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters(),
+        Err(SdpParseError::InvalidFingerprint {
+            fingerprint: "sha-256 1B:EA:BF".to_string(),
+            reason: "value is not a series of hex bytes of expected length",
+        }),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=fingerprint:md5 1B:EA:BF\r\n"
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters(),
+        Err(SdpParseError::InvalidFingerprint {
+            fingerprint: "md5 1B:EA:BF".to_string(),
+            reason: "unsupported hash function",
+        }),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=fingerprint:SHA-1 \
+        +B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46\r\n"
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters(),
+        Err(SdpParseError::InvalidFingerprint {
+            fingerprint: "SHA-1 +B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46"
+                .to_string(),
+            reason: "value is not a series of hex bytes of expected length",
+        }),
+    );
+    assert_eq!(
+        "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=fingerprint:SHA-1 \
+        1b:ea:bf:33:b8:11:26:6d:91:ad:1b:a0:16:fd:5d:60:59:33:f7:46\r\n"
+            .parse::<SessionDescription>()
+            .unwrap()
+            .dtls_parameters()
+            .map(|dtls_parameters| dtls_parameters.fingerprints),
+        Ok(vec![DtlsFingerprint::Sha1 {
+            value: [
+                0x1B, 0xEA, 0xBF, 0x33, 0xB8, 0x11, 0x26, 0x6D, 0x91, 0xAD, 0x1B, 0xA0, 0x16, 0xFD,
+                0x5D, 0x60, 0x59, 0x33, 0xF7, 0x46,
+            ],
+        }]),
+    );
+}
+
+#[test]
+fn rtp_capabilities() {
+    let rtp_capabilities = BROWSER_OFFER
+        .parse::<SessionDescription>()
+        .unwrap()
+        .rtp_capabilities();
+
+    // Opus, PCMU, VP9, VP8, RTX and H264.
+    assert_eq!(rtp_capabilities.codecs.len(), 6);
+    assert_eq!(
+        rtp_capabilities.codecs[0],
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: Some(111),
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::from([
+                ("minptime", 10_u32.into()),
+                ("useinbandfec", 1_u32.into()),
+            ]),
+            rtcp_feedback: vec![RtcpFeedback::TransportCc],
+        },
+    );
+    assert_eq!(
+        rtp_capabilities.codecs[5]
+            .parameters()
+            .get("profile-level-id"),
+        Some(&RtpCodecParametersParametersValue::String("42e01f".into())),
+    );
+
+    // Unknown extension is skipped, MID is present for both kinds.
+    assert_eq!(rtp_capabilities.header_extensions.len(), 6);
+    assert!(rtp_capabilities
+        .header_extensions
+        .iter()
+        .all(|ext| ext.uri != RtpHeaderExtensionUri::Unsupported));
+}
+
+#[test]
+fn producer_options() {
+    let offer = BROWSER_OFFER.parse::<SessionDescription>().unwrap();
+    let router_rtp_capabilities = router_rtp_capabilities();
+
+    let producer_options = offer.producer_options(&router_rtp_capabilities).unwrap();
+    assert_eq!(producer_options.len(), 2);
+
+    let audio = &producer_options[0];
+    assert_eq!(audio.kind, MediaKind::Audio);
+    assert_eq!(audio.rtp_parameters.mid.as_deref(), Some("0"));
+    assert_eq!(audio.rtp_parameters.codecs.len(), 1);
+    assert_eq!(audio.rtp_parameters.codecs[0].payload_type(), 111);
+    assert_eq!(audio.rtp_parameters.encodings.len(), 1);
+    assert_eq!(audio.rtp_parameters.encodings[0].ssrc, Some(1111));
+    assert_eq!(
+        audio.rtp_parameters.rtcp.cname.as_deref(),
+        Some("browser-cname")
+    );
+    assert!(!audio.rtp_parameters.rtcp.reduced_size);
+
+    let video = &producer_options[1];
+    assert_eq!(video.kind, MediaKind::Video);
+    // VP9 is not supported by the router, so VP8 and its RTX are selected.
+    assert_eq!(
+        video
+            .rtp_parameters
+            .codecs
+            .iter()
+            .map(|codec| (codec.mime_type(), codec.payload_type()))
+            .collect::<Vec<_>>(),
+        vec![
+            (MimeType::Video(MimeTypeVideo::Vp8), 96),
+            (MimeType::Video(MimeTypeVideo::Rtx), 97),
+        ],
+    );
+    assert_eq!(
+        video
+            .rtp_parameters
+            .encodings
+            .iter()
+            .map(|encoding| encoding.rid.as_deref().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["l", "m", "h"],
+    );
+    assert!(video.rtp_parameters.rtcp.reduced_size);
+    assert_eq!(video.rtp_parameters.header_extensions.len(), 3);
+
+    let mut only_h264 = offer.media[1].clone();
+    only_h264.formats = vec!["102".to_string()];
+    assert_eq!(
+        only_h264.producer_options(&router_rtp_capabilities).err(),
+        Some(SdpParseError::NoCompatibleMediaCodecs {
+            mid: "1".to_string()
+        }),
+    );
+}
+
+#[test]
+fn producer_options_ssrc_simulcast() {
+    let offer = "v=0\r
+m=video 9 UDP/TLS/RTP/SAVPF 96 97\r
+a=mid:video\r
+a=sendrecv\r
+a=rtpmap:96 VP8/90000\r
+a=rtpmap:97 rtx/90000\r
+a=fmtp:97 apt=96\r
+a=ssrc-group:FID 1 11\r
+a=ssrc-group:FID 2 22\r
+a=ssrc-group:SIM 1 2\r
+a=ssrc:1 cname:cname\r
+a=ssrc:11 cname:cname\r
+a=ssrc:2 cname:cname\r
+a=ssrc:22 cname:cname\r
+"
+    .parse::<SessionDescription>()
+    .unwrap();
+
+    let producer_options = offer.producer_options(&router_rtp_capabilities()).unwrap();
+    assert_eq!(producer_options.len(), 1);
+    assert_eq!(
+        producer_options[0]
+            .rtp_parameters
+            .encodings
+            .iter()
+            .map(|encoding| (encoding.ssrc, encoding.rtx.map(|rtx| rtx.ssrc)))
+            .collect::<Vec<_>>(),
+        vec![(Some(1), Some(11)), (Some(2), Some(22))],
+    );
+}
+
+#[test]
+fn remote_sdp_answer() {
+    let offer = BROWSER_OFFER.parse::<SessionDescription>().unwrap();
+    let producer_options = offer.producer_options(&router_rtp_capabilities()).unwrap();
+
+    let mut remote_sdp = remote_sdp();
+    remote_sdp.set_remote_dtls_parameters(&offer.dtls_parameters().unwrap());
+    for options in &producer_options {
+        remote_sdp.add_recv_media(
+            options.rtp_parameters.mid.clone().unwrap(),
+            options.kind,
+            options.rtp_parameters.clone(),
+        );
+    }
+    remote_sdp.add_data_channel("2");
+
+    let answer = remote_sdp.to_string();
+    let lines = answer.split("\r\n").collect::<Vec<_>>();
+
+    assert!(lines.contains(&"a=ice-lite"));
+    assert!(lines.contains(&"a=group:BUNDLE 0 1 2"));
+    assert!(lines.contains(&"m=audio 7 UDP/TLS/RTP/SAVPF 111"));
+    assert!(lines.contains(&"m=video 7 UDP/TLS/RTP/SAVPF 96 97"));
+    assert!(lines.contains(&"m=application 7 UDP/DTLS/SCTP webrtc-datachannel"));
+    assert!(lines.contains(&"a=setup:passive"));
+    assert!(lines.contains(&"a=candidate:udpcandidate 1 udp 1076302079 9.9.9.9 40533 typ host"));
+    assert!(lines.contains(&"a=recvonly"));
+    assert!(lines.contains(&"a=rtpmap:111 opus/48000/2"));
+    assert!(lines.contains(&"a=fmtp:111 minptime=10;useinbandfec=1"));
+    assert!(lines.contains(&"a=rtcp-fb:96 nack pli"));
+    assert!(lines.contains(&"a=fmtp:97 apt=96"));
+    assert!(lines.contains(&"a=rid:l recv"));
+    assert!(lines.contains(&"a=simulcast:recv l;m;h"));
+    assert!(lines.contains(&"a=sctp-port:5000"));
+    assert!(lines.contains(&"a=max-message-size:262144"));
+
+    // Generated SDP can be parsed back.
+    let parsed = answer.parse::<SessionDescription>().unwrap();
+    assert_eq!(parsed.media.len(), 3);
+    assert_eq!(parsed.media[1].direction(), MediaDirection::RecvOnly);
+    assert_eq!(parsed.dtls_parameters().unwrap().role, DtlsRole::Server);
+}
+
+#[test]
+fn remote_sdp_offer() {
+    let mut remote_sdp = remote_sdp();
+    remote_sdp.set_dtls_role(DtlsRole::Auto);
+    remote_sdp.add_send_media(
+        "0",
+        MediaKind::Video,
+        RtpParameters {
+            mid: Some("0".to_string()),
+            codecs: vec![
+                RtpCodecParameters::Video {
+                    mime_type: MimeTypeVideo::Vp8,
+                    payload_type: 101,
+                    clock_rate: NonZeroU32::new(90000).unwrap(),
+                    parameters: RtpCodecParametersParameters::default(),
+                    rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+                },
+                RtpCodecParameters::Video {
+                    mime_type: MimeTypeVideo::Rtx,
+                    payload_type: 102,
+                    clock_rate: NonZeroU32::new(90000).unwrap(),
+                    parameters: RtpCodecParametersParameters::from([("apt", 101_u32.into())]),
+                    rtcp_feedback: vec![],
+                },
+            ],
+            header_extensions: vec![RtpHeaderExtensionParameters {
+                uri: RtpHeaderExtensionUri::AbsSendTime,
+                id: 4,
+                encrypt: false,
+            }],
+            encodings: vec![RtpEncodingParameters {
+                ssrc: Some(1000),
+                rtx: Some(RtpEncodingParametersRtx { ssrc: 1001 }),
+                ..RtpEncodingParameters::default()
+            }],
+            rtcp: RtcpParameters {
+                cname: Some("mediasoup".to_string()),
+                reduced_size: true,
+            },
+        },
+    );
+    let offer = BROWSER_OFFER.parse::<SessionDescription>().unwrap();
+    remote_sdp.reject(&offer.media[0]);
+
+    let sdp = remote_sdp.to_string();
+    let lines = sdp.split("\r\n").collect::<Vec<_>>();
+
+    assert!(lines.contains(&"a=group:BUNDLE 0"));
+    assert!(lines.contains(&"a=setup:actpass"));
+    assert!(lines.contains(&"a=sendonly"));
+    assert!(
+        lines.contains(&"a=extmap:4 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time")
+    );
+    assert!(lines.contains(&"a=ssrc:1000 cname:mediasoup"));
+    assert!(lines.contains(&"a=ssrc:1001 cname:mediasoup"));
+    assert!(lines.contains(&"a=ssrc-group:FID 1000 1001"));
+    assert!(lines.contains(&"m=audio 0 UDP/TLS/RTP/SAVPF 111 0"));
+    assert!(!lines.iter().any(|line| line.starts_with("a=rid")));
+}
+
+#[test]
+fn remote_sdp_ice_fragment() {
+    let mut remote_sdp = remote_sdp();
+    remote_sdp.add_data_channel("0");
+    remote_sdp.set_ice_parameters(IceParameters {
+        username_fragment: "restarted".to_string(),
+        password: "restarted-password".to_string(),
+        ice_lite: Some(true),
+    });
+
+    let fragment = remote_sdp.ice_fragment();
+    let lines = fragment.split("\r\n").collect::<Vec<_>>();
+
+    assert!(lines.contains(&"a=ice-lite"));
+    assert!(lines.contains(&"m=application 9 UDP/DTLS/SCTP webrtc-datachannel"));
+    assert!(lines.contains(&"a=mid:0"));
+    assert!(lines.contains(&"a=ice-ufrag:restarted"));
+    assert!(lines.contains(&"a=ice-pwd:restarted-password"));
+    assert!(lines.contains(&"a=end-of-candidates"));
+    assert!(!lines.iter().any(|line| line.starts_with("a=fingerprint")));
+
+    let parsed = fragment.parse::<SessionDescription>().unwrap();
+    assert_eq!(
+        parsed.ice_parameters().unwrap().username_fragment,
+        "restarted"
+    );
+}
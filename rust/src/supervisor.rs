@@ -0,0 +1,558 @@
+//! Opt-in supervisor that recovers routers after unexpected worker death.
+//!
+//! Routers created through [`Supervisor`] are tracked (until they are closed). Once the supervised
+//! worker dies, a replacement worker is created with the same settings and every tracked router is
+//! re-created on it with [`Worker::restore_router()`] from the [`RouterSnapshot`] taken right before
+//! entities of the dead worker were closed. Everything snapshot covers is recovered: transports of
+//! all kinds, producers, consumers, data producers, data consumers and RTP observers, no matter
+//! whether they were created before or after previous recovery.
+//!
+//! Recovered entities are owned by the supervisor, they can be looked up by ids entities had
+//! before the first recovery with [`Supervisor::router()`], [`Supervisor::transport()`],
+//! [`Supervisor::producer()`], etc. and are kept alive until closed with
+//! [`Supervisor::close_router()`] and similar methods. [`Supervisor::on_recovered`] callbacks are
+//! called after each recovery, so that signaling can renegotiate with new ICE and DTLS parameters.
+//!
+//! # Notes on usage
+//! * Routers with WebRTC transports that use [`WebRtcServer`](crate::webrtc_server::WebRtcServer)
+//!   can't be restored and are reported in [`Recovery::failed_routers`] instead.
+//! * New ids of recovered entities and new ICE and DTLS parameters of WebRTC transports are listed
+//!   in [`Recovery`], so that signaling doesn't need to look each of them up.
+//! * See [`Router::snapshot()`] for the state that is not part of the snapshot and needs to be
+//!   re-applied by the application.
+
+#[cfg(test)]
+mod tests;
+
+use crate::consumer::{Consumer, ConsumerId};
+use crate::data_consumer::{DataConsumer, DataConsumerId};
+use crate::data_producer::{DataProducer, DataProducerId};
+use crate::data_structures::{DtlsParameters, IceCandidate, IceParameters};
+use crate::producer::{Producer, ProducerId};
+use crate::router::{Router, RouterId, RouterOptions, WeakRouter};
+use crate::rtp_observer::RtpObserverId;
+use crate::snapshot::{
+    RestoreRouterError, RestoredRouter, RestoredRtpObserver, RestoredTransport, RouterSnapshot,
+};
+use crate::transport::{Transport, TransportId};
+use crate::worker::{CreateRouterError, ExitError, Worker, WorkerId, WorkerSettings};
+use crate::worker_manager::WorkerManager;
+use event_listener_primitives::{Bag, HandlerId};
+use hash_hasher::HashedMap;
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::{fmt, io, mem};
+
+/// Result of recovery after worker death.
+///
+/// Maps are keyed by ids entities had before the first recovery (or when they were created, for
+/// those created after it), the same ids recovered entities can be looked up by with
+/// [`Supervisor`] methods.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Recovery {
+    /// Id of the dead worker.
+    pub dead_worker_id: WorkerId,
+    /// Reason of the worker death.
+    pub exit_status: Result<(), ExitError>,
+    /// Replacement worker.
+    pub worker: Worker,
+    /// New ids of recovered routers.
+    pub routers: HashedMap<RouterId, RouterId>,
+    /// New ids of recovered transports.
+    pub transports: HashedMap<TransportId, TransportId>,
+    /// New ICE and DTLS parameters of recovered WebRTC transports.
+    pub webrtc_transports: HashedMap<TransportId, RecoveredWebRtcTransport>,
+    /// New ids of recovered producers.
+    pub producers: HashedMap<ProducerId, ProducerId>,
+    /// New ids of recovered consumers.
+    pub consumers: HashedMap<ConsumerId, ConsumerId>,
+    /// New ids of recovered data producers.
+    pub data_producers: HashedMap<DataProducerId, DataProducerId>,
+    /// New ids of recovered data consumers.
+    pub data_consumers: HashedMap<DataConsumerId, DataConsumerId>,
+    /// Routers that failed to be recovered, they are no longer supervised.
+    pub failed_routers: HashedMap<RouterId, RestoreRouterError>,
+}
+
+/// Parameters of recovered WebRTC transport the remote endpoint needs to reconnect.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RecoveredWebRtcTransport {
+    /// New id of the transport.
+    pub id: TransportId,
+    /// Local ICE parameters.
+    pub ice_parameters: IceParameters,
+    /// Local ICE candidates.
+    pub ice_candidates: Vec<IceCandidate>,
+    /// Local DTLS parameters.
+    pub dtls_parameters: DtlsParameters,
+}
+
+impl Recovery {
+    fn add_router(&mut self, router_id: RouterId, recovered: &RestoredRouter) {
+        self.routers.insert(router_id, recovered.router.id());
+
+        for (&transport_id, transport) in &recovered.transports {
+            self.transports.insert(transport_id, transport.id());
+            if let RestoredTransport::WebRtc(transport) = transport {
+                self.webrtc_transports.insert(
+                    transport_id,
+                    RecoveredWebRtcTransport {
+                        id: transport.id(),
+                        ice_parameters: transport.ice_parameters().clone(),
+                        ice_candidates: transport.ice_candidates().clone(),
+                        dtls_parameters: transport.dtls_parameters(),
+                    },
+                );
+            }
+        }
+        self.producers.extend(
+            recovered
+                .producers
+                .iter()
+                .map(|(&producer_id, producer)| (producer_id, producer.id())),
+        );
+        self.consumers.extend(
+            recovered
+                .consumers
+                .iter()
+                .map(|(&consumer_id, consumer)| (consumer_id, consumer.id())),
+        );
+        self.data_producers.extend(
+            recovered
+                .data_producers
+                .iter()
+                .map(|(&data_producer_id, data_producer)| (data_producer_id, data_producer.id())),
+        );
+        self.data_consumers.extend(
+            recovered
+                .data_consumers
+                .iter()
+                .map(|(&data_consumer_id, data_consumer)| (data_consumer_id, data_consumer.id())),
+        );
+    }
+}
+
+struct SupervisedRouter {
+    /// Current instance of the router.
+    router: WeakRouter,
+    /// Entities recovered on current instance of the router (keyed by ids entities had before the
+    /// first recovery), `None` if router was not recovered yet.
+    recovered: Option<RestoredRouter>,
+}
+
+#[derive(Default)]
+#[allow(clippy::type_complexity)]
+struct Handlers {
+    recovered: Bag<Arc<dyn Fn(&Recovery) + Send + Sync>, Recovery>,
+}
+
+struct Inner {
+    worker_manager: WorkerManager,
+    worker_settings: WorkerSettings,
+    worker: Mutex<(Worker, Option<HandlerId>)>,
+    routers: Mutex<HashedMap<RouterId, SupervisedRouter>>,
+    handlers: Handlers,
+}
+
+impl Inner {
+    fn supervise(self: &Arc<Self>, worker: Worker) {
+        let dead_handler = worker.on_dead({
+            let worker_id = worker.id();
+            let inner_weak = Arc::downgrade(self);
+
+            move |exit_status| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.recover(worker_id, exit_status);
+                }
+            }
+        });
+
+        *self.worker.lock() = (worker, Some(dead_handler));
+    }
+
+    fn recover(self: &Arc<Self>, dead_worker_id: WorkerId, exit_status: Result<(), ExitError>) {
+        warn!(
+            "supervised worker died, recovering [id:{}]: {:?}",
+            dead_worker_id, exit_status
+        );
+
+        // Snapshots are taken synchronously, before entities of the dead worker are closed and
+        // would disappear from them.
+        let snapshots = mem::take(&mut *self.routers.lock())
+            .into_iter()
+            .filter_map(|(router_id, supervised_router)| {
+                let router = supervised_router
+                    .router
+                    .upgrade()
+                    .filter(|router| !router.closed())?;
+
+                Some((router_id, router.snapshot(), supervised_router.recovered))
+            })
+            .collect::<Vec<_>>();
+
+        let inner_weak = Arc::downgrade(self);
+        self.worker_manager.executor().spawn(Box::pin(async move {
+            let Some(inner) = inner_weak.upgrade() else {
+                return;
+            };
+
+            let worker = match inner
+                .worker_manager
+                .create_worker(inner.worker_settings.clone())
+                .await
+            {
+                Ok(worker) => worker,
+                Err(error) => {
+                    error!("failed to create replacement worker: {}", error);
+                    return;
+                }
+            };
+            inner.supervise(worker.clone());
+
+            let recovery = inner
+                .restore(snapshots, dead_worker_id, exit_status, worker)
+                .await;
+
+            debug!(
+                "recovered [dead worker id:{}, new worker id:{}]",
+                dead_worker_id,
+                recovery.worker.id()
+            );
+
+            inner.handlers.recovered.call_simple(&recovery);
+        }));
+    }
+
+    async fn restore(
+        &self,
+        snapshots: Vec<(RouterId, RouterSnapshot, Option<RestoredRouter>)>,
+        dead_worker_id: WorkerId,
+        exit_status: Result<(), ExitError>,
+        worker: Worker,
+    ) -> Recovery {
+        let mut recovery = Recovery {
+            dead_worker_id,
+            exit_status,
+            worker,
+            routers: HashedMap::default(),
+            transports: HashedMap::default(),
+            webrtc_transports: HashedMap::default(),
+            producers: HashedMap::default(),
+            consumers: HashedMap::default(),
+            data_producers: HashedMap::default(),
+            data_consumers: HashedMap::default(),
+            failed_routers: HashedMap::default(),
+        };
+
+        for (router_id, snapshot, previously_recovered) in snapshots {
+            match recovery.worker.restore_router(snapshot).await {
+                Ok(restored) => {
+                    let recovered = match &previously_recovered {
+                        Some(previously_recovered) => rekey(restored, previously_recovered),
+                        None => restored,
+                    };
+                    recovery.add_router(router_id, &recovered);
+                    self.routers.lock().insert(
+                        router_id,
+                        SupervisedRouter {
+                            router: recovered.router.downgrade(),
+                            recovered: Some(recovered),
+                        },
+                    );
+                }
+                Err(error) => {
+                    error!("failed to recover router [id:{}]: {}", router_id, error);
+                    recovery.failed_routers.insert(router_id, error);
+                }
+            }
+        }
+
+        recovery
+    }
+}
+
+/// Supervisor that owns a worker and re-creates routers on a new worker in case of the worker
+/// death, see [module documentation](self) for details.
+#[derive(Clone)]
+#[must_use]
+pub struct Supervisor {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("worker", &self.inner.worker.lock().0)
+            .finish()
+    }
+}
+
+impl Supervisor {
+    /// Create supervisor with a new worker created with given settings.
+    pub async fn new(
+        worker_manager: &WorkerManager,
+        worker_settings: WorkerSettings,
+    ) -> io::Result<Self> {
+        debug!("new()");
+
+        let worker = worker_manager
+            .create_worker(worker_settings.clone())
+            .await?;
+
+        let inner = Arc::new(Inner {
+            worker_manager: worker_manager.clone(),
+            worker_settings,
+            worker: Mutex::new((worker.clone(), None)),
+            routers: Mutex::default(),
+            handlers: Handlers::default(),
+        });
+        inner.supervise(worker);
+
+        Ok(Self { inner })
+    }
+
+    /// Currently supervised worker, changes after recovery.
+    pub fn worker(&self) -> Worker {
+        self.inner.worker.lock().0.clone()
+    }
+
+    /// Create a router on supervised worker, see [`Worker::create_router()`].
+    ///
+    /// Router is supervised until it is closed, entities created on it don't need any special
+    /// handling.
+    pub async fn create_router(
+        &self,
+        router_options: RouterOptions,
+    ) -> Result<Router, CreateRouterError> {
+        debug!("create_router()");
+
+        let router = self.worker().create_router(router_options).await?;
+
+        let router_id = router.id();
+        self.inner.routers.lock().insert(
+            router_id,
+            SupervisedRouter {
+                router: router.downgrade(),
+                recovered: None,
+            },
+        );
+        router
+            .on_close({
+                let inner_weak = Arc::downgrade(&self.inner);
+
+                // No-op when recovering, since routers of the dead worker are already taken by
+                // then
+                move || {
+                    if let Some(inner) = inner_weak.upgrade() {
+                        inner.routers.lock().remove(&router_id);
+                    }
+                }
+            })
+            .detach();
+
+        Ok(router)
+    }
+
+    /// Router created with [`Supervisor::create_router()`] or the one recovered in its place.
+    #[must_use]
+    pub fn router(&self, router_id: RouterId) -> Option<Router> {
+        self.inner
+            .routers
+            .lock()
+            .get(&router_id)?
+            .router
+            .upgrade()
+            .filter(|router| !router.closed())
+    }
+
+    /// Transport recovered in place of the transport with given id.
+    #[must_use]
+    pub fn transport(&self, transport_id: TransportId) -> Option<RestoredTransport> {
+        self.find_recovered(|recovered| recovered.transports.get(&transport_id).cloned())
+            .filter(|transport| !transport.closed())
+    }
+
+    /// Producer recovered in place of the producer with given id.
+    #[must_use]
+    pub fn producer(&self, producer_id: ProducerId) -> Option<Producer> {
+        self.find_recovered(|recovered| recovered.producers.get(&producer_id).cloned())
+            .filter(|producer| !producer.closed())
+    }
+
+    /// Consumer recovered in place of the consumer with given id.
+    #[must_use]
+    pub fn consumer(&self, consumer_id: ConsumerId) -> Option<Consumer> {
+        self.find_recovered(|recovered| recovered.consumers.get(&consumer_id).cloned())
+            .filter(|consumer| !consumer.closed())
+    }
+
+    /// Data producer recovered in place of the data producer with given id.
+    #[must_use]
+    pub fn data_producer(&self, data_producer_id: DataProducerId) -> Option<DataProducer> {
+        self.find_recovered(|recovered| recovered.data_producers.get(&data_producer_id).cloned())
+            .filter(|data_producer| !data_producer.closed())
+    }
+
+    /// Data consumer recovered in place of the data consumer with given id.
+    #[must_use]
+    pub fn data_consumer(&self, data_consumer_id: DataConsumerId) -> Option<DataConsumer> {
+        self.find_recovered(|recovered| recovered.data_consumers.get(&data_consumer_id).cloned())
+            .filter(|data_consumer| !data_consumer.closed())
+    }
+
+    /// RTP observer recovered in place of the RTP observer with given id.
+    #[must_use]
+    pub fn rtp_observer(&self, rtp_observer_id: RtpObserverId) -> Option<RestoredRtpObserver> {
+        self.find_recovered(|recovered| recovered.rtp_observers.get(&rtp_observer_id).cloned())
+            .filter(|rtp_observer| !rtp_observer.closed())
+    }
+
+    /// Stops supervising router and drops supervisor's instances of the recovered router and
+    /// entities on it, returns `false` if there is no such router.
+    ///
+    /// Just like with entities created directly, router is closed once all of its instances are
+    /// dropped.
+    pub fn close_router(&self, router_id: RouterId) -> bool {
+        self.inner.routers.lock().remove(&router_id).is_some()
+    }
+
+    /// Drops supervisor's instance of the recovered transport, returns `false` if there is no such
+    /// transport, see [`Supervisor::close_router()`].
+    pub fn close_transport(&self, transport_id: TransportId) -> bool {
+        self.remove_recovered(|recovered| recovered.transports.remove(&transport_id).is_some())
+    }
+
+    /// Drops supervisor's instance of the recovered producer, returns `false` if there is no such
+    /// producer, see [`Supervisor::close_router()`].
+    pub fn close_producer(&self, producer_id: ProducerId) -> bool {
+        self.remove_recovered(|recovered| recovered.producers.remove(&producer_id).is_some())
+    }
+
+    /// Drops supervisor's instance of the recovered consumer, returns `false` if there is no such
+    /// consumer, see [`Supervisor::close_router()`].
+    pub fn close_consumer(&self, consumer_id: ConsumerId) -> bool {
+        self.remove_recovered(|recovered| recovered.consumers.remove(&consumer_id).is_some())
+    }
+
+    /// Drops supervisor's instance of the recovered data producer, returns `false` if there is no
+    /// such data producer, see [`Supervisor::close_router()`].
+    pub fn close_data_producer(&self, data_producer_id: DataProducerId) -> bool {
+        self.remove_recovered(|recovered| {
+            recovered.data_producers.remove(&data_producer_id).is_some()
+        })
+    }
+
+    /// Drops supervisor's instance of the recovered data consumer, returns `false` if there is no
+    /// such data consumer, see [`Supervisor::close_router()`].
+    pub fn close_data_consumer(&self, data_consumer_id: DataConsumerId) -> bool {
+        self.remove_recovered(|recovered| {
+            recovered.data_consumers.remove(&data_consumer_id).is_some()
+        })
+    }
+
+    /// Drops supervisor's instance of the recovered RTP observer, returns `false` if there is no
+    /// such RTP observer, see [`Supervisor::close_router()`].
+    pub fn close_rtp_observer(&self, rtp_observer_id: RtpObserverId) -> bool {
+        self.remove_recovered(|recovered| {
+            recovered.rtp_observers.remove(&rtp_observer_id).is_some()
+        })
+    }
+
+    /// Callback is called when supervised worker died and routers were re-created on a new
+    /// worker.
+    pub fn on_recovered<F: Fn(&Recovery) + Send + Sync + 'static>(&self, callback: F) -> HandlerId {
+        self.inner.handlers.recovered.add(Arc::new(callback))
+    }
+
+    fn find_recovered<T, F>(&self, f: F) -> Option<T>
+    where
+        F: Fn(&RestoredRouter) -> Option<T>,
+    {
+        self.inner
+            .routers
+            .lock()
+            .values()
+            .filter_map(|supervised_router| supervised_router.recovered.as_ref())
+            .find_map(f)
+    }
+
+    fn remove_recovered<F>(&self, f: F) -> bool
+    where
+        F: FnMut(&mut RestoredRouter) -> bool,
+    {
+        self.inner
+            .routers
+            .lock()
+            .values_mut()
+            .filter_map(|supervised_router| supervised_router.recovered.as_mut())
+            .any(f)
+    }
+}
+
+/// Re-keys entities restored from the snapshot of previously recovered router by ids entities had
+/// before the first recovery. Entities that were created after previous recovery keep their ids.
+fn rekey(restored: RestoredRouter, previously_recovered: &RestoredRouter) -> RestoredRouter {
+    RestoredRouter {
+        router: restored.router,
+        transports: rekey_entities(
+            restored.transports,
+            &previously_recovered.transports,
+            |transport| transport.id(),
+        ),
+        producers: rekey_entities(
+            restored.producers,
+            &previously_recovered.producers,
+            Producer::id,
+        ),
+        consumers: rekey_entities(
+            restored.consumers,
+            &previously_recovered.consumers,
+            Consumer::id,
+        ),
+        data_producers: rekey_entities(
+            restored.data_producers,
+            &previously_recovered.data_producers,
+            DataProducer::id,
+        ),
+        data_consumers: rekey_entities(
+            restored.data_consumers,
+            &previously_recovered.data_consumers,
+            DataConsumer::id,
+        ),
+        rtp_observers: rekey_entities(
+            restored.rtp_observers,
+            &previously_recovered.rtp_observers,
+            |rtp_observer| rtp_observer.id(),
+        ),
+    }
+}
+
+fn rekey_entities<Id, T, F>(
+    restored: HashedMap<Id, T>,
+    previously_recovered: &HashedMap<Id, T>,
+    id: F,
+) -> HashedMap<Id, T>
+where
+    Id: Copy + Eq + Hash,
+    F: Fn(&T) -> Id,
+{
+    let original_ids = previously_recovered
+        .iter()
+        .map(|(original_id, entity)| (id(entity), *original_id))
+        .collect::<HashedMap<_, _>>();
+
+    restored
+        .into_iter()
+        .map(|(snapshot_id, entity)| {
+            let id = original_ids
+                .get(&snapshot_id)
+                .copied()
+                .unwrap_or(snapshot_id);
+            (id, entity)
+        })
+        .collect()
+}
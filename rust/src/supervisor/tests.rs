@@ -0,0 +1,377 @@
+use super::*;
+use crate::data_structures::{ListenInfo, Protocol};
+use crate::prelude::*;
+use crate::rtp_parameters::{
+    MimeTypeAudio, RtcpParameters, RtpCapabilities, RtpCodecCapability, RtpCodecParameters,
+    RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use crate::webrtc_transport::WebRtcTransportListenInfos;
+use futures_lite::future;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![RtpCodecCapability::Audio {
+        mime_type: MimeTypeAudio::Opus,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(48000).unwrap(),
+        channels: NonZeroU8::new(2).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }]
+}
+
+fn producer_options() -> ProducerOptions {
+    ProducerOptions::new(
+        MediaKind::Audio,
+        RtpParameters {
+            mid: Some("AUDIO".to_string()),
+            codecs: vec![RtpCodecParameters::Audio {
+                mime_type: MimeTypeAudio::Opus,
+                payload_type: 111,
+                clock_rate: NonZeroU32::new(48000).unwrap(),
+                channels: NonZeroU8::new(2).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }],
+            encodings: vec![RtpEncodingParameters {
+                ssrc: Some(11111111),
+                ..RtpEncodingParameters::default()
+            }],
+            rtcp: RtcpParameters {
+                cname: Some("FOOBAR".to_string()),
+                ..RtcpParameters::default()
+            },
+            ..RtpParameters::default()
+        },
+    )
+}
+
+fn listen_info() -> ListenInfo {
+    ListenInfo {
+        protocol: Protocol::Udp,
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        announced_ip: None,
+        port: None,
+        send_buffer_size: None,
+        recv_buffer_size: None,
+    }
+}
+
+fn webrtc_transport_options() -> WebRtcTransportOptions {
+    WebRtcTransportOptions::new(WebRtcTransportListenInfos::new(listen_info()))
+}
+
+async fn init() -> Supervisor {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    Supervisor::new(&worker_manager, WorkerSettings::default())
+        .await
+        .expect("Failed to create supervisor")
+}
+
+/// Ids and parameters reported in [`Recovery`].
+struct RecoveredIds {
+    dead_worker_id: WorkerId,
+    worker_id: WorkerId,
+    routers: HashedMap<RouterId, RouterId>,
+    transports: HashedMap<TransportId, TransportId>,
+    webrtc_transports: HashedMap<TransportId, RecoveredWebRtcTransport>,
+    producers: HashedMap<ProducerId, ProducerId>,
+    consumers: HashedMap<ConsumerId, ConsumerId>,
+    data_producers: HashedMap<DataProducerId, DataProducerId>,
+    data_consumers: HashedMap<DataConsumerId, DataConsumerId>,
+}
+
+/// Simulates death of currently supervised worker and waits for recovery to finish.
+async fn recover(supervisor: &Supervisor) -> RecoveredIds {
+    let dead_worker_id = supervisor.worker().id();
+
+    let (recovered_sender, recovered_receiver) = async_oneshot::oneshot();
+    let recovered_sender = Mutex::new(Some(recovered_sender));
+    let _handler = supervisor.on_recovered(move |recovery| {
+        if let Some(mut recovered_sender) = recovered_sender.lock().take() {
+            assert!(recovery.failed_routers.is_empty());
+            let _ = recovered_sender.send(RecoveredIds {
+                dead_worker_id: recovery.dead_worker_id,
+                worker_id: recovery.worker.id(),
+                routers: recovery.routers.clone(),
+                transports: recovery.transports.clone(),
+                webrtc_transports: recovery.webrtc_transports.clone(),
+                producers: recovery.producers.clone(),
+                consumers: recovery.consumers.clone(),
+                data_producers: recovery.data_producers.clone(),
+                data_consumers: recovery.data_consumers.clone(),
+            });
+        }
+    });
+
+    // There is no way to crash worker on purpose
+    supervisor
+        .inner
+        .recover(dead_worker_id, Err(ExitError::Unexpected));
+
+    recovered_receiver
+        .await
+        .expect("Failed to receive recovered event")
+}
+
+#[test]
+fn recover_succeeds() {
+    future::block_on(async move {
+        let supervisor = init().await;
+        let dead_worker_id = supervisor.worker().id();
+
+        let router = supervisor
+            .create_router(RouterOptions::new(media_codecs()))
+            .await
+            .expect("Failed to create router");
+        let transport_1 = router
+            .create_webrtc_transport(webrtc_transport_options())
+            .await
+            .expect("Failed to create transport");
+        let transport_2 = router
+            .create_plain_transport(PlainTransportOptions::new(listen_info()))
+            .await
+            .expect("Failed to create transport");
+        let direct_transport = router
+            .create_direct_transport(DirectTransportOptions::default())
+            .await
+            .expect("Failed to create transport");
+        let producer = transport_1
+            .produce(producer_options())
+            .await
+            .expect("Failed to produce");
+        let consumer = transport_2
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                RtpCapabilities {
+                    codecs: media_codecs(),
+                    header_extensions: vec![],
+                },
+            ))
+            .await
+            .expect("Failed to consume");
+        let data_producer = direct_transport
+            .produce_data(DataProducerOptions::new_direct())
+            .await
+            .expect("Failed to produce data");
+        let data_consumer = direct_transport
+            .consume_data(DataConsumerOptions::new_direct(data_producer.id(), None))
+            .await
+            .expect("Failed to consume data");
+        let audio_level_observer = router
+            .create_audio_level_observer(AudioLevelObserverOptions::default())
+            .await
+            .expect("Failed to create audio level observer");
+        audio_level_observer
+            .add_producer(RtpObserverAddProducerOptions::new(producer.id()))
+            .await
+            .expect("Failed to add producer to audio level observer");
+
+        // Closed entities are not recovered
+        let closed_transport_id = {
+            let closed_transport = router
+                .create_webrtc_transport(webrtc_transport_options())
+                .await
+                .expect("Failed to create transport");
+            closed_transport.id()
+        };
+
+        let recovered_ids = recover(&supervisor).await;
+
+        assert_eq!(recovered_ids.dead_worker_id, dead_worker_id);
+        assert_eq!(recovered_ids.worker_id, supervisor.worker().id());
+        assert_ne!(recovered_ids.worker_id, dead_worker_id);
+        assert_eq!(
+            recovered_ids.routers.keys().copied().collect::<Vec<_>>(),
+            vec![router.id()]
+        );
+
+        let recovered_router = supervisor
+            .router(router.id())
+            .expect("Router not recovered");
+        assert_ne!(recovered_router.id(), router.id());
+        assert_eq!(
+            recovered_ids.routers.get(&router.id()),
+            Some(&recovered_router.id())
+        );
+        assert_eq!(recovered_ids.transports.len(), 3);
+        assert!(!recovered_ids.transports.contains_key(&closed_transport_id));
+
+        let Some(RestoredTransport::WebRtc(recovered_transport_1)) =
+            supervisor.transport(transport_1.id())
+        else {
+            panic!("WebRTC transport not recovered");
+        };
+        assert_ne!(recovered_transport_1.id(), transport_1.id());
+        assert_ne!(
+            recovered_transport_1.ice_parameters().username_fragment,
+            transport_1.ice_parameters().username_fragment
+        );
+        assert_eq!(
+            recovered_ids.transports.get(&transport_1.id()),
+            Some(&recovered_transport_1.id())
+        );
+        assert_eq!(recovered_ids.webrtc_transports.len(), 1);
+        let recovered_webrtc_transport = &recovered_ids.webrtc_transports[&transport_1.id()];
+        assert_eq!(recovered_webrtc_transport.id, recovered_transport_1.id());
+        assert_eq!(
+            &recovered_webrtc_transport.ice_parameters,
+            recovered_transport_1.ice_parameters()
+        );
+        assert_eq!(
+            &recovered_webrtc_transport.ice_candidates,
+            recovered_transport_1.ice_candidates()
+        );
+        assert_eq!(
+            recovered_webrtc_transport.dtls_parameters,
+            recovered_transport_1.dtls_parameters()
+        );
+        let Some(RestoredTransport::Plain(recovered_transport_2)) =
+            supervisor.transport(transport_2.id())
+        else {
+            panic!("Plain transport not recovered");
+        };
+        assert!(matches!(
+            supervisor.transport(direct_transport.id()),
+            Some(RestoredTransport::Direct(_)),
+        ));
+        assert!(supervisor.transport(closed_transport_id).is_none());
+
+        let recovered_producer = supervisor
+            .producer(producer.id())
+            .expect("Producer not recovered");
+        assert_ne!(recovered_producer.id(), producer.id());
+        assert_eq!(
+            recovered_ids.producers.get(&producer.id()),
+            Some(&recovered_producer.id())
+        );
+        let recovered_consumer = supervisor
+            .consumer(consumer.id())
+            .expect("Consumer not recovered");
+        assert_eq!(
+            recovered_ids.consumers.get(&consumer.id()),
+            Some(&recovered_consumer.id())
+        );
+        assert_eq!(recovered_consumer.producer_id(), recovered_producer.id());
+        assert_eq!(
+            recovered_consumer.transport().id(),
+            recovered_transport_2.id()
+        );
+        let recovered_data_producer = supervisor
+            .data_producer(data_producer.id())
+            .expect("Data producer not recovered");
+        let recovered_data_consumer = supervisor
+            .data_consumer(data_consumer.id())
+            .expect("Data consumer not recovered");
+        assert_eq!(
+            recovered_data_consumer.data_producer_id(),
+            recovered_data_producer.id()
+        );
+        assert_eq!(
+            recovered_ids.data_producers.get(&data_producer.id()),
+            Some(&recovered_data_producer.id())
+        );
+        assert_eq!(
+            recovered_ids.data_consumers.get(&data_consumer.id()),
+            Some(&recovered_data_consumer.id())
+        );
+        assert!(supervisor.rtp_observer(audio_level_observer.id()).is_some());
+
+        // Recovered entities are owned by supervisor and stay alive when other instances are
+        // dropped
+        drop((
+            recovered_router,
+            recovered_transport_1,
+            recovered_transport_2,
+            recovered_producer,
+            recovered_consumer,
+            recovered_data_producer,
+            recovered_data_consumer,
+        ));
+        assert!(supervisor.router(router.id()).is_some());
+        assert!(supervisor.producer(producer.id()).is_some());
+        assert!(supervisor.consumer(consumer.id()).is_some());
+
+        // Ids from before the first recovery keep working after subsequent recoveries
+        let previous_producer_id = supervisor.producer(producer.id()).unwrap().id();
+        let recovered_ids = recover(&supervisor).await;
+        assert_eq!(
+            recovered_ids.routers.keys().copied().collect::<Vec<_>>(),
+            vec![router.id()]
+        );
+        let recovered_producer = supervisor
+            .producer(producer.id())
+            .expect("Producer not recovered");
+        assert_ne!(recovered_producer.id(), previous_producer_id);
+        assert_eq!(
+            recovered_ids.producers.get(&producer.id()),
+            Some(&recovered_producer.id())
+        );
+        assert_ne!(recovered_producer.id(), producer.id());
+        assert_eq!(
+            supervisor
+                .consumer(consumer.id())
+                .expect("Consumer not recovered")
+                .producer_id(),
+            recovered_producer.id()
+        );
+    });
+}
+
+#[test]
+fn close_router_succeeds() {
+    future::block_on(async move {
+        let supervisor = init().await;
+
+        let router_id = supervisor
+            .create_router(RouterOptions::new(media_codecs()))
+            .await
+            .expect("Failed to create router")
+            .id();
+
+        // Routers closed by the application are no longer supervised
+        assert!(supervisor.router(router_id).is_none());
+        assert!(!supervisor.close_router(router_id));
+
+        let router = supervisor
+            .create_router(RouterOptions::new(media_codecs()))
+            .await
+            .expect("Failed to create router");
+        let transport = router
+            .create_webrtc_transport(webrtc_transport_options())
+            .await
+            .expect("Failed to create transport");
+
+        let router_id = router.id();
+        let transport_id = transport.id();
+
+        recover(&supervisor).await;
+        drop((router, transport));
+
+        let recovered_router = supervisor.router(router_id).expect("Router not recovered");
+
+        assert!(supervisor.close_router(router_id));
+        assert!(!supervisor.close_router(router_id));
+        assert!(supervisor.router(router_id).is_none());
+        assert!(supervisor.transport(transport_id).is_none());
+
+        let (mut close_sender, close_receiver) = async_oneshot::oneshot::<()>();
+        let _handler = recovered_router.on_close(move || {
+            let _ = close_sender.send(());
+        });
+        drop(recovered_router);
+
+        close_receiver.await.expect("Failed to receive close event");
+    });
+}
@@ -11,12 +11,13 @@
 mod tests;
 
 use crate::data_structures::{AppData, ListenInfo};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{WebRtcServerCloseRequest, WebRtcServerDumpRequest};
 use crate::transport::TransportId;
 use crate::uuid_based_wrapper_type;
 use crate::webrtc_transport::WebRtcTransport;
 use crate::worker::{Channel, RequestError, Worker};
-use async_executor::Executor;
 use event_listener_primitives::{BagOnce, HandlerId};
 use hash_hasher::HashedSet;
 use log::{debug, error};
@@ -155,7 +156,7 @@ struct Handlers {
 
 struct Inner {
     id: WebRtcServerId,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     channel: Channel,
     handlers: Arc<Handlers>,
     app_data: AppData,
@@ -182,13 +183,17 @@ impl Inner {
                 let request = WebRtcServerCloseRequest {
                     webrtc_server_id: self.id,
                 };
-                self.executor
-                    .spawn(async move {
-                        if let Err(error) = channel.request("", request).await {
-                            error!("WebRTC server closing failed on drop: {}", error);
-                        }
-                    })
-                    .detach();
+                self.executor.spawn(Box::pin(async move {
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::info_span!(
+                        target: "mediasoup::webrtc_server",
+                        "close_webrtc_server",
+                        webrtc_server_id = %request.webrtc_server_id,
+                    );
+                    if let Err(error) = instrument!(channel.request("", request), span).await {
+                        error!("WebRTC server closing failed on drop: {}", error);
+                    }
+                }));
             }
         }
     }
@@ -220,7 +225,7 @@ impl fmt::Debug for WebRtcServer {
 impl WebRtcServer {
     pub(crate) fn new(
         id: WebRtcServerId,
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         channel: Channel,
         app_data: AppData,
         worker: Worker,
@@ -0,0 +1,351 @@
+//! WHIP (WebRTC-HTTP ingestion protocol) and WHEP (WebRTC-HTTP egress protocol) sessions.
+//!
+//! Both protocols boil down to a single SDP offer/answer exchange over HTTP, followed by optional
+//! trickle ICE/ICE restart requests (`PATCH`) and session teardown (`DELETE`). This module
+//! implements everything but HTTP itself, so it can be used with any HTTP server:
+//!
+//! * `POST` with SDP offer: create [`WhipSession`] (ingest) or [`WhepSession`] (egress) and respond
+//!   with [`WhipSession::answer`]/[`WhepSession::answer`], use [`WhipSession::id`] in resource URL.
+//! * `PATCH` with `application/trickle-ice-sdpfrag` body: call [`WhipSession::patch`], respond with
+//!   returned fragment if there is one (ICE restart) or with no content otherwise.
+//! * `DELETE`: drop the session.
+//!
+//! # Notes on usage
+//! Codecs of the answer are those of the [`Router`], so make sure Router media codecs are
+//! compatible with what clients offer. Payload types and header extension ids of the answer are
+//! those of the offer (WHIP producers map them to the Router ones, WHEP consumers map Router ones
+//! back to them).
+
+use crate::consumer::{Consumer, ConsumerOptions};
+use crate::data_structures::IceParameters;
+use crate::producer::Producer;
+use crate::router::Router;
+use crate::sdp::{MediaDescription, MediaDirection, RemoteSdp, SdpParseError, SessionDescription};
+use crate::transport::{ConsumeError, ProduceError, Transport, TransportId};
+use crate::webrtc_transport::{
+    WebRtcTransport, WebRtcTransportOptions, WebRtcTransportRemoteParameters,
+};
+use crate::worker::RequestError;
+use log::debug;
+use parking_lot::Mutex;
+use thiserror::Error;
+
+/// Error that caused WHIP or WHEP session operation to fail.
+#[derive(Debug, Error)]
+pub enum WhipError {
+    /// SDP offer or fragment is invalid.
+    #[error("Invalid SDP: {0}")]
+    InvalidSdp(#[from] SdpParseError),
+    /// Failed to create WebRTC transport.
+    #[error("Failed to create WebRTC transport: {0}")]
+    CreateTransport(RequestError),
+    /// Failed to connect WebRTC transport.
+    #[error("Failed to connect WebRTC transport: {0}")]
+    Connect(RequestError),
+    /// Failed to produce media described in the offer.
+    #[error("Failed to produce: {0}")]
+    Produce(#[from] ProduceError),
+    /// Failed to consume requested producer.
+    #[error("Failed to consume: {0}")]
+    Consume(#[from] ConsumeError),
+    /// Failed to restart ICE.
+    #[error("Failed to restart ICE: {0}")]
+    RestartIce(RequestError),
+    /// None of the media descriptions in the offer was accepted.
+    #[error("No acceptable media in the offer")]
+    NoAcceptableMedia,
+}
+
+/// State shared by ingest and egress sessions.
+#[derive(Debug)]
+struct Session {
+    transport: WebRtcTransport,
+    remote_sdp: Mutex<RemoteSdp>,
+    remote_ice_parameters: Mutex<IceParameters>,
+}
+
+impl Session {
+    async fn new(
+        router: &Router,
+        webrtc_transport_options: WebRtcTransportOptions,
+        offer: &SessionDescription,
+    ) -> Result<Self, WhipError> {
+        // Validate offer before creating anything on the worker.
+        let remote_ice_parameters = offer.ice_parameters()?;
+        let dtls_parameters = offer.dtls_parameters()?;
+
+        let transport = router
+            .create_webrtc_transport(webrtc_transport_options)
+            .await
+            .map_err(WhipError::CreateTransport)?;
+
+        let mut remote_sdp = RemoteSdp::from_webrtc_transport(&transport);
+        remote_sdp.set_remote_dtls_parameters(&dtls_parameters);
+
+        transport
+            .connect(WebRtcTransportRemoteParameters { dtls_parameters })
+            .await
+            .map_err(WhipError::Connect)?;
+
+        Ok(Self {
+            transport,
+            remote_sdp: Mutex::new(remote_sdp),
+            remote_ice_parameters: Mutex::new(remote_ice_parameters),
+        })
+    }
+
+    fn accept_data_channel(&self, media: &MediaDescription) -> bool {
+        match media.mid() {
+            Some(mid)
+                if media.media == "application" && self.transport.sctp_parameters().is_some() =>
+            {
+                self.remote_sdp.lock().add_data_channel(mid);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn patch(&self, fragment: &str) -> Result<Option<String>, WhipError> {
+        let fragment = fragment.parse::<SessionDescription>()?;
+        let ice_parameters = fragment.ice_parameters()?;
+
+        {
+            let mut remote_ice_parameters = self.remote_ice_parameters.lock();
+            if remote_ice_parameters.username_fragment == ice_parameters.username_fragment
+                && remote_ice_parameters.password == ice_parameters.password
+            {
+                // mediasoup is ICE Lite and doesn't need remote candidates, nothing to do.
+                return Ok(None);
+            }
+            *remote_ice_parameters = ice_parameters;
+        }
+
+        debug!("patch() | restarting ICE");
+
+        let local_ice_parameters = self
+            .transport
+            .restart_ice()
+            .await
+            .map_err(WhipError::RestartIce)?;
+
+        let mut remote_sdp = self.remote_sdp.lock();
+        remote_sdp.set_ice_parameters(local_ice_parameters);
+
+        Ok(Some(remote_sdp.ice_fragment()))
+    }
+}
+
+/// WHIP session, created from a publisher's SDP offer.
+///
+/// Every audio and video media description the publisher sends results in a [`Producer`],
+/// media descriptions with codecs not supported by the [`Router`] are rejected. Dropping the
+/// session closes the transport and all of its producers.
+#[derive(Debug)]
+#[must_use = "Session will be closed on drop, make sure to keep it around for as long as needed"]
+pub struct WhipSession {
+    producers: Vec<Producer>,
+    session: Session,
+}
+
+impl WhipSession {
+    /// Create WebRTC transport on given router, produce media described in the SDP offer and
+    /// prepare the answer.
+    ///
+    /// Use [`WebRtcTransportOptions::new_with_server`] to create the transport on a
+    /// [`WebRtcServer`](crate::webrtc_server::WebRtcServer).
+    pub async fn new(
+        router: &Router,
+        webrtc_transport_options: WebRtcTransportOptions,
+        offer: &str,
+    ) -> Result<Self, WhipError> {
+        debug!("new()");
+
+        let offer = offer.parse::<SessionDescription>()?;
+        let session = Session::new(router, webrtc_transport_options, &offer).await?;
+        let mut producers = Vec::new();
+
+        for media in &offer.media {
+            let accepted_kind = media.kind().filter(|_| {
+                !media.is_rejected()
+                    && matches!(
+                        media.direction(),
+                        MediaDirection::SendOnly | MediaDirection::SendRecv
+                    )
+            });
+
+            if accepted_kind.is_none() {
+                if media.is_rejected() || !session.accept_data_channel(media) {
+                    session.remote_sdp.lock().reject(media);
+                }
+                continue;
+            }
+
+            match media.producer_options(router.rtp_capabilities()) {
+                Ok(producer_options) => {
+                    let producer = session.transport.produce(producer_options).await?;
+                    session.remote_sdp.lock().add_producer(&producer);
+                    producers.push(producer);
+                }
+                Err(SdpParseError::NoCompatibleMediaCodecs { .. }) => {
+                    session.remote_sdp.lock().reject(media);
+                }
+                Err(error) => {
+                    return Err(error.into());
+                }
+            }
+        }
+
+        if producers.is_empty() {
+            return Err(WhipError::NoAcceptableMedia);
+        }
+
+        Ok(Self { producers, session })
+    }
+
+    /// Session id (the same as id of the underlying transport), suitable for resource URL.
+    #[must_use]
+    pub fn id(&self) -> TransportId {
+        self.session.transport.id()
+    }
+
+    /// SDP answer for the publisher.
+    #[must_use]
+    pub fn answer(&self) -> String {
+        self.session.remote_sdp.lock().to_string()
+    }
+
+    /// Underlying WebRTC transport.
+    pub fn transport(&self) -> &WebRtcTransport {
+        &self.session.transport
+    }
+
+    /// Producers created for media described in the offer.
+    pub fn producers(&self) -> &[Producer] {
+        &self.producers
+    }
+
+    /// Handle trickle ICE fragment (`PATCH` request).
+    ///
+    /// Returns `Some(fragment)` with new local ICE parameters if remote ICE credentials changed
+    /// and ICE was restarted, `None` otherwise (remote candidates are not needed since mediasoup is
+    /// ICE Lite).
+    pub async fn patch(&self, fragment: &str) -> Result<Option<String>, WhipError> {
+        self.session.patch(fragment).await
+    }
+}
+
+/// WHEP session, created from a viewer's SDP offer.
+///
+/// Every audio and video media description the viewer receives on is matched with the next given
+/// [`Producer`] of the same kind, for which a [`Consumer`] is created, media descriptions without
+/// matching producer are rejected. Dropping the session closes the transport and all of its
+/// consumers.
+#[derive(Debug)]
+#[must_use = "Session will be closed on drop, make sure to keep it around for as long as needed"]
+pub struct WhepSession {
+    consumers: Vec<Consumer>,
+    session: Session,
+}
+
+impl WhepSession {
+    /// Create WebRTC transport on given router, consume given producers according to the SDP
+    /// offer and prepare the answer.
+    ///
+    /// Use [`WebRtcTransportOptions::new_with_server`] to create the transport on a
+    /// [`WebRtcServer`](crate::webrtc_server::WebRtcServer).
+    pub async fn new(
+        router: &Router,
+        webrtc_transport_options: WebRtcTransportOptions,
+        producers: &[Producer],
+        offer: &str,
+    ) -> Result<Self, WhipError> {
+        debug!("new()");
+
+        let offer = offer.parse::<SessionDescription>()?;
+        let session = Session::new(router, webrtc_transport_options, &offer).await?;
+        let mut remaining_producers = producers.iter().collect::<Vec<_>>();
+        let mut consumers = Vec::new();
+
+        for media in &offer.media {
+            let producer = media
+                .kind()
+                .filter(|_| {
+                    !media.is_rejected()
+                        && matches!(
+                            media.direction(),
+                            MediaDirection::RecvOnly | MediaDirection::SendRecv
+                        )
+                })
+                .and_then(|kind| {
+                    remaining_producers
+                        .iter()
+                        .position(|producer| producer.kind() == kind)
+                })
+                .map(|index| remaining_producers.remove(index));
+
+            // Payload types and header extension ids may differ between media descriptions.
+            let rtp_capabilities = media.rtp_capabilities();
+
+            let producer = match producer {
+                Some(producer)
+                    if router.can_consume(&producer.id(), &rtp_capabilities)
+                        && media.mid().is_some() =>
+                {
+                    producer
+                }
+                _ => {
+                    if media.is_rejected() || !session.accept_data_channel(media) {
+                        session.remote_sdp.lock().reject(media);
+                    }
+                    continue;
+                }
+            };
+
+            let mut consumer_options = ConsumerOptions::new(producer.id(), rtp_capabilities);
+            consumer_options.mid = media.mid().map(str::to_string);
+            consumer_options.use_remote_ids = true;
+
+            let consumer = session.transport.consume(consumer_options).await?;
+            session.remote_sdp.lock().add_consumer(&consumer);
+            consumers.push(consumer);
+        }
+
+        if consumers.is_empty() {
+            return Err(WhipError::NoAcceptableMedia);
+        }
+
+        Ok(Self { consumers, session })
+    }
+
+    /// Session id (the same as id of the underlying transport), suitable for resource URL.
+    #[must_use]
+    pub fn id(&self) -> TransportId {
+        self.session.transport.id()
+    }
+
+    /// SDP answer for the viewer.
+    #[must_use]
+    pub fn answer(&self) -> String {
+        self.session.remote_sdp.lock().to_string()
+    }
+
+    /// Underlying WebRTC transport.
+    pub fn transport(&self) -> &WebRtcTransport {
+        &self.session.transport
+    }
+
+    /// Consumers created for media requested in the offer.
+    pub fn consumers(&self) -> &[Consumer] {
+        &self.consumers
+    }
+
+    /// Handle trickle ICE fragment (`PATCH` request).
+    ///
+    /// Returns `Some(fragment)` with new local ICE parameters if remote ICE credentials changed
+    /// and ICE was restarted, `None` otherwise (remote candidates are not needed since mediasoup is
+    /// ICE Lite).
+    pub async fn patch(&self, fragment: &str) -> Result<Option<String>, WhipError> {
+        self.session.patch(fragment).await
+    }
+}
@@ -1,36 +1,62 @@
 //! A worker represents a mediasoup C++ thread that runs on a single CPU core and handles
 //! [`Router`] instances.
+//!
+//! # Logging
+//! Log lines produced by worker are emitted through `log` with `mediasoup::worker::<tag>` targets
+//! (like `mediasoup::worker::ice`, see [`WorkerLogTag`]) or `mediasoup::worker` when tag of the
+//! line is not known, so they can be filtered per tag with regular logger configuration. Note that
+//! worker only produces lines for tags enabled in [`WorkerSettings::log_tags`].
+//!
+//! With `tracing` feature enabled, log lines are emitted as `tracing` events with the same targets
+//! instead. Events are emitted within the span of the worker, router or transport they are related
+//! to, which allows correlating them with the rest of application traces.
+//!
+//! # Tracing
+//! With `tracing` feature enabled, every request to the worker is wrapped into `request` span
+//! (target `mediasoup::channel`) that records request method, handler ID, duration and error if
+//! request failed. Creation and closing of entities is wrapped into spans like `create_transport`
+//! and `close_transport` with ID of the entity, while dispatching of notifications received from
+//! the worker happens within `notification` span on debug level.
 
 mod channel;
 mod common;
+mod logging;
 mod utils;
 
 use crate::data_structures::AppData;
+use crate::events::{EventStream, EVENT_STREAM_CAPACITY};
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::messages::{
     WorkerCloseRequest, WorkerCreateRouterRequest, WorkerCreateWebRtcServerRequest,
     WorkerDumpRequest, WorkerUpdateSettingsRequest,
 };
 pub use crate::ortc::RtpCapabilitiesError;
 use crate::router::{Router, RouterId, RouterOptions};
+use crate::snapshot::{self, RestoreRouterError, RestoredRouter, RouterSnapshot};
 use crate::webrtc_server::{WebRtcServer, WebRtcServerId, WebRtcServerOptions};
 use crate::worker::channel::BufferMessagesGuard;
 pub use crate::worker::utils::ExitError;
 use crate::worker_manager::WorkerManager;
 use crate::{ortc, uuid_based_wrapper_type};
-use async_executor::Executor;
+pub use channel::WithRequestTimeout;
 pub(crate) use channel::{Channel, NotificationError, NotificationParseError};
 pub(crate) use common::{SubscriptionHandler, SubscriptionTarget};
 use event_listener_primitives::{Bag, BagOnce, HandlerId};
 use futures_lite::FutureExt;
 use log::{debug, error, warn};
+#[cfg(feature = "tracing")]
+pub(crate) use logging::EntitySpan;
 use mediasoup_sys::fbs;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::future::Future;
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 use std::{fmt, io};
 use thiserror::Error;
 use utils::WorkerRunResult;
@@ -67,7 +93,33 @@ pub enum RequestError {
     NoData,
     /// Response conversion error.
     #[error("Response conversion error: {0}")]
-    ResponseConversion(Box<dyn Error>),
+    ResponseConversion(Box<dyn Error + Send + Sync>),
+}
+
+/// Run given future with requests to the worker it makes using custom timeout instead of
+/// [`WorkerSettings::request_timeout`] (`None` disables timeout).
+///
+/// Timed out requests fail with [`RequestError::TimedOut`] and are forgotten, late responses are
+/// ignored.
+///
+/// # Example
+/// ```no_run
+/// # use mediasoup::prelude::*;
+/// # use mediasoup::worker::with_request_timeout;
+/// # use std::time::Duration;
+/// # async fn f(router: Router, webrtc_transport_options: WebRtcTransportOptions) {
+/// let result = with_request_timeout(
+///     Some(Duration::from_secs(1)),
+///     router.create_webrtc_transport(webrtc_transport_options),
+/// )
+/// .await;
+/// # }
+/// ```
+pub fn with_request_timeout<F: Future>(
+    timeout: Option<Duration>,
+    future: F,
+) -> WithRequestTimeout<F> {
+    WithRequestTimeout::new(future, timeout)
 }
 
 /// Logging level for logs generated by the media worker thread (check the
@@ -168,6 +220,39 @@ pub struct WorkerDtlsFiles {
     pub private_key: PathBuf,
 }
 
+/// Settings for running worker in a child process instead of a thread of the current process, see
+/// [`WorkerSettings::process`].
+///
+/// Worker process talks to this process over a pair of pipes with the same protocol as in-process
+/// worker, so crash of the worker doesn't affect this process and is reported with
+/// [`Worker::on_dead()`] instead. Only supported on Unix.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WorkerProcessSettings {
+    /// Path to `mediasoup-worker` executable.
+    pub executable: PathBuf,
+    /// Niceness of the worker process, from -20 (highest priority) to 19 (lowest priority).
+    ///
+    /// If `None`, niceness of the current process is inherited.
+    pub niceness: Option<i32>,
+    /// CPU cores worker process is allowed to run on (Linux only).
+    ///
+    /// If `None`, CPU affinity of the current process is inherited.
+    pub cpu_affinity: Option<Vec<usize>>,
+}
+
+impl WorkerProcessSettings {
+    /// Create worker process settings with given path to `mediasoup-worker` executable.
+    #[must_use]
+    pub fn new(executable: impl Into<PathBuf>) -> Self {
+        Self {
+            executable: executable.into(),
+            niceness: None,
+            cpu_affinity: None,
+        }
+    }
+}
+
 /// Settings for worker to be created with.
 #[derive(Clone)]
 #[non_exhaustive]
@@ -194,7 +279,19 @@ pub struct WorkerSettings {
     pub libwebrtc_field_trials: Option<String>,
     /// Function that will be called under worker thread before worker starts, can be used for
     /// pinning worker threads to CPU cores.
+    ///
+    /// Not called when worker runs in a separate process, see [`WorkerProcessSettings`] instead.
     pub thread_initializer: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Run worker in a child process instead of a thread of the current process.
+    ///
+    /// Default `None` (worker runs in a thread).
+    pub process: Option<WorkerProcessSettings>,
+    /// Default timeout for requests sent to the worker, after which they fail with
+    /// [`RequestError::TimedOut`]. Can be overridden for specific calls with
+    /// [`with_request_timeout()`].
+    ///
+    /// Default `None` (requests never time out).
+    pub request_timeout: Option<Duration>,
     /// Custom application data.
     pub app_data: AppData,
 }
@@ -222,6 +319,8 @@ impl Default for WorkerSettings {
             dtls_files: None,
             libwebrtc_field_trials: None,
             thread_initializer: None,
+            process: None,
+            request_timeout: None,
             app_data: AppData::default(),
         }
     }
@@ -236,6 +335,8 @@ impl fmt::Debug for WorkerSettings {
             dtls_files,
             libwebrtc_field_trials,
             thread_initializer,
+            process,
+            request_timeout,
             app_data,
         } = self;
 
@@ -249,6 +350,8 @@ impl fmt::Debug for WorkerSettings {
                 "thread_initializer",
                 &thread_initializer.as_ref().map(|_| "ThreadInitializer"),
             )
+            .field("process", &process)
+            .field("request_timeout", &request_timeout)
             .field("app_data", &app_data)
             .finish()
     }
@@ -309,6 +412,26 @@ pub enum CreateRouterError {
     Request(RequestError),
 }
 
+/// Event of [`Worker::events()`] stream.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum WorkerEvent {
+    /// New WebRTC server was created on this worker.
+    NewWebRtcServer {
+        /// WebRTC server id.
+        webrtc_server_id: WebRtcServerId,
+    },
+    /// New router was created on this worker.
+    NewRouter {
+        /// Router id.
+        router_id: RouterId,
+    },
+    /// Worker thread unexpectedly died, see [`Worker::on_dead()`].
+    Dead(Result<(), ExitError>),
+    /// Worker was closed, this is the last event in the stream.
+    Close,
+}
+
 #[derive(Default)]
 #[allow(clippy::type_complexity)]
 struct Handlers {
@@ -321,11 +444,16 @@ struct Handlers {
 
 struct Inner {
     id: WorkerId,
+    pid: u32,
     channel: Channel,
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     handlers: Handlers,
     app_data: AppData,
     closed: Arc<AtomicBool>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    entity_spans: logging::EntitySpans,
     // Make sure worker is not dropped until this worker manager is not dropped
     _worker_manager: WorkerManager,
 }
@@ -340,7 +468,7 @@ impl Drop for Inner {
 
 impl Inner {
     async fn new<OE: FnOnce() + Send + 'static>(
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         WorkerSettings {
             log_level,
             log_tags,
@@ -348,6 +476,8 @@ impl Inner {
             dtls_files,
             libwebrtc_field_trials,
             thread_initializer,
+            process,
+            request_timeout,
             app_data,
         }: WorkerSettings,
         worker_manager: WorkerManager,
@@ -405,29 +535,49 @@ impl Inner {
         let closed = Arc::new(AtomicBool::new(false));
 
         let (mut status_sender, status_receiver) = async_oneshot::oneshot();
+        let on_worker_exit = move |result| {
+            let _ = status_sender.send(result);
+            on_exit();
+        };
         let WorkerRunResult {
             channel,
             buffer_worker_messages_guard,
-        } = utils::run_worker_with_channels(
-            id,
-            thread_initializer,
-            spawn_args,
-            Arc::clone(&closed),
-            move |result| {
-                let _ = status_sender.send(result);
-                on_exit();
-            },
-        );
+            pid,
+        } = match process {
+            Some(process) => utils::run_worker_process(
+                &executor,
+                id,
+                process,
+                spawn_args,
+                Arc::clone(&closed),
+                request_timeout,
+                on_worker_exit,
+            )?,
+            None => utils::run_worker_with_channels(
+                &executor,
+                id,
+                thread_initializer,
+                spawn_args,
+                Arc::clone(&closed),
+                request_timeout,
+                on_worker_exit,
+            )?,
+        };
 
         let handlers = Handlers::default();
 
         let mut inner = Self {
             id,
+            pid,
             channel,
             executor,
             handlers,
             app_data,
             closed,
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(target: "mediasoup::worker", "worker", id = %id, pid),
+            #[cfg(feature = "tracing")]
+            entity_spans: logging::EntitySpans::default(),
             _worker_manager: worker_manager,
         };
 
@@ -438,24 +588,21 @@ impl Inner {
         let inner = Arc::new(inner);
         {
             let inner_weak = Arc::downgrade(&inner);
-            inner
-                .executor
-                .spawn(async move {
-                    let status = status_receiver.await.unwrap_or(Err(ExitError::Unexpected));
-                    let _ = early_status_sender.send(status);
-
-                    if let Some(inner) = inner_weak.upgrade() {
-                        warn!("worker exited [id:{}]: {:?}", id, status);
-
-                        if !inner.closed.swap(true, Ordering::SeqCst) {
-                            inner.handlers.dead.call(|callback| {
-                                callback(status);
-                            });
-                            inner.handlers.close.call_simple();
-                        }
+            inner.executor.spawn(Box::pin(async move {
+                let status = status_receiver.await.unwrap_or(Err(ExitError::Unexpected));
+                let _ = early_status_sender.send(status);
+
+                if let Some(inner) = inner_weak.upgrade() {
+                    warn!("worker exited [id:{}]: {:?}", id, status);
+
+                    if !inner.closed.swap(true, Ordering::SeqCst) {
+                        inner.handlers.dead.call(|callback| {
+                            callback(status);
+                        });
+                        inner.handlers.close.call_simple();
                     }
-                })
-                .detach();
+                }
+            }));
         }
 
         inner
@@ -489,7 +636,7 @@ impl Inner {
         let id = self.id;
         let sender = Mutex::new(Some(sender));
         let _handler = self.channel.subscribe_to_notifications(
-            SubscriptionTarget::String(std::process::id().to_string()),
+            SubscriptionTarget::String(self.pid.to_string()),
             move |notification| {
                 let result = match notification.event().unwrap() {
                     fbs::notification::Event::WorkerRunning => {
@@ -522,27 +669,40 @@ impl Inner {
         let channel_receiver = self.channel.get_internal_message_receiver();
         let id = self.id;
         let closed = Arc::clone(&self.closed);
-        self.executor
-            .spawn(async move {
-                while let Ok(message) = channel_receiver.recv().await {
-                    match message {
-                        channel::InternalMessage::Debug(text) => debug!("[id:{}] {}", id, text),
-                        channel::InternalMessage::Warn(text) => warn!("[id:{}] {}", id, text),
-                        channel::InternalMessage::Error(text) => {
-                            if !closed.load(Ordering::SeqCst) {
-                                error!("[id:{}] {}", id, text)
-                            }
+        #[cfg(feature = "tracing")]
+        let (span, entity_spans) = (self.span.clone(), self.entity_spans.clone());
+        self.executor.spawn(Box::pin(async move {
+            while let Ok(message) = channel_receiver.recv().await {
+                let (level, text) = match message {
+                    channel::InternalMessage::Debug(text) => (log::Level::Debug, text),
+                    channel::InternalMessage::Warn(text) => (log::Level::Warn, text),
+                    channel::InternalMessage::Error(text) => {
+                        if closed.load(Ordering::SeqCst) {
+                            continue;
                         }
-                        channel::InternalMessage::Dump(text) => eprintln!("{text}"),
-                        channel::InternalMessage::Unexpected(data) => error!(
+                        (log::Level::Error, text)
+                    }
+                    channel::InternalMessage::Dump(text) => {
+                        eprintln!("{text}");
+                        continue;
+                    }
+                    channel::InternalMessage::Unexpected(data) => {
+                        error!(
                             "worker[id:{}] unexpected channel data: {}",
                             id,
                             String::from_utf8_lossy(&data)
-                        ),
+                        );
+                        continue;
                     }
-                }
-            })
-            .detach();
+                };
+
+                let record = logging::WorkerLogRecord::parse(id, level, &text);
+                #[cfg(not(feature = "tracing"))]
+                record.emit();
+                #[cfg(feature = "tracing")]
+                record.emit(&span, &entity_spans);
+            }
+        }));
     }
 
     fn close(&self) {
@@ -550,15 +710,20 @@ impl Inner {
 
         if !already_closed {
             let channel = self.channel.clone();
+            #[cfg(feature = "tracing")]
+            let span = tracing::info_span!(
+                target: "mediasoup::worker",
+                parent: &self.span,
+                "close_worker",
+                id = %self.id,
+            );
 
-            self.executor
-                .spawn(async move {
-                    let _ = channel.request("", WorkerCloseRequest {}).await;
+            self.executor.spawn(Box::pin(async move {
+                let _ = instrument!(channel.request("", WorkerCloseRequest {}), span).await;
 
-                    // Drop channels in here after response from worker
-                    drop(channel);
-                })
-                .detach();
+                // Drop channels in here after response from worker
+                drop(channel);
+            }));
 
             self.handlers.close.call_simple();
         }
@@ -584,7 +749,7 @@ impl fmt::Debug for Worker {
 
 impl Worker {
     pub(super) async fn new<OE: FnOnce() + Send + 'static>(
-        executor: Arc<Executor<'static>>,
+        executor: Arc<dyn Executor>,
         worker_settings: WorkerSettings,
         worker_manager: WorkerManager,
         on_exit: OE,
@@ -600,6 +765,22 @@ impl Worker {
         self.inner.id
     }
 
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> &tracing::Span {
+        &self.inner.span
+    }
+
+    /// Registers span of the entity within this worker, so that worker log lines mentioning the
+    /// entity are emitted within its span.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn register_entity_span(
+        &self,
+        id: impl ToString,
+        span: tracing::Span,
+    ) -> logging::EntitySpan {
+        self.inner.entity_spans.register(id.to_string(), span)
+    }
+
     /// Worker manager to which worker belongs.
     pub fn worker_manager(&self) -> &WorkerManager {
         &self.inner._worker_manager
@@ -617,6 +798,12 @@ impl Worker {
         self.inner.closed.load(Ordering::SeqCst)
     }
 
+    /// Number of requests sent to the worker that didn't get a response yet.
+    #[must_use]
+    pub fn outstanding_requests(&self) -> usize {
+        self.inner.channel.outstanding_requests()
+    }
+
     /// Dump Worker.
     #[doc(hidden)]
     pub async fn dump(&self) -> Result<WorkerDump, RequestError> {
@@ -661,17 +848,24 @@ impl Worker {
             .channel
             .buffer_messages_for(webrtc_server_id.into());
 
-        self.inner
-            .channel
-            .request(
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            target: "mediasoup::webrtc_server",
+            "create_webrtc_server",
+            %webrtc_server_id,
+        );
+        instrument!(
+            self.inner.channel.request(
                 "",
                 WorkerCreateWebRtcServerRequest {
                     webrtc_server_id,
                     listen_infos,
                 },
-            )
-            .await
-            .map_err(CreateWebRtcServerError::Request)?;
+            ),
+            span,
+        )
+        .await
+        .map_err(CreateWebRtcServerError::Request)?;
 
         let webrtc_server = WebRtcServer::new(
             webrtc_server_id,
@@ -703,24 +897,30 @@ impl Worker {
             media_codecs,
         } = router_options;
 
-        let rtp_capabilities = ortc::generate_router_rtp_capabilities(media_codecs)
+        let rtp_capabilities = ortc::generate_router_rtp_capabilities(media_codecs.clone())
             .map_err(CreateRouterError::FailedRtpCapabilitiesGeneration)?;
 
         let router_id = RouterId::new();
 
         let _buffer_guard = self.inner.channel.buffer_messages_for(router_id.into());
 
-        self.inner
-            .channel
-            .request("", WorkerCreateRouterRequest { router_id })
-            .await
-            .map_err(CreateRouterError::Request)?;
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(target: "mediasoup::router", "create_router", %router_id);
+        instrument!(
+            self.inner
+                .channel
+                .request("", WorkerCreateRouterRequest { router_id }),
+            span,
+        )
+        .await
+        .map_err(CreateRouterError::Request)?;
 
         let router = Router::new(
             router_id,
             Arc::clone(&self.inner.executor),
             self.inner.channel.clone(),
             rtp_capabilities,
+            media_codecs,
             app_data,
             self.clone(),
         );
@@ -730,6 +930,18 @@ impl Worker {
         Ok(router)
     }
 
+    /// Re-creates router from the snapshot taken with [`Router::snapshot()`] on this worker.
+    ///
+    /// Everything in the snapshot is re-created with new ids, see [`RestoredRouter`] for mapping
+    /// from ids in the snapshot to restored entities. Transports listening on a
+    /// [`WebRtcServer`] can't be restored and cause an error before anything is created.
+    pub async fn restore_router(
+        &self,
+        snapshot: RouterSnapshot,
+    ) -> Result<RestoredRouter, RestoreRouterError> {
+        snapshot::restore_router(self, snapshot).await
+    }
+
     /// Callback is called when a new WebRTC server is created.
     pub fn on_new_webrtc_server<F: Fn(&WebRtcServer) + Send + Sync + 'static>(
         &self,
@@ -765,8 +977,75 @@ impl Worker {
         handler_id
     }
 
+    /// Stream of worker events, alternative to `on_*` callbacks.
+    ///
+    /// See [`events`](crate::events) module for details.
+    pub fn events(&self) -> EventStream<WorkerEvent> {
+        EventStream::new(EVENT_STREAM_CAPACITY, |sender| {
+            vec![
+                self.on_new_webrtc_server({
+                    let sender = sender.clone();
+
+                    move |webrtc_server| {
+                        sender.send(WorkerEvent::NewWebRtcServer {
+                            webrtc_server_id: webrtc_server.id(),
+                        });
+                    }
+                }),
+                self.on_new_router({
+                    let sender = sender.clone();
+
+                    move |router| {
+                        sender.send(WorkerEvent::NewRouter {
+                            router_id: router.id(),
+                        });
+                    }
+                }),
+                self.on_dead({
+                    let sender = sender.clone();
+
+                    move |status| sender.send(WorkerEvent::Dead(status))
+                }),
+                self.on_close(sender.last_emitter(WorkerEvent::Close)),
+            ]
+        })
+    }
+
+    /// Downgrade `Worker` to [`WeakWorker`] instance.
+    #[must_use]
+    pub fn downgrade(&self) -> WeakWorker {
+        WeakWorker {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn close(&self) {
         self.inner.close();
     }
 }
+
+/// [`WeakWorker`] doesn't own worker instance and will not prevent one from being destroyed once
+/// last instance of regular [`Worker`] is dropped.
+///
+/// [`WeakWorker`] vs [`Worker`] is similar to [`Weak`] vs [`Arc`].
+#[derive(Clone)]
+pub struct WeakWorker {
+    inner: Weak<Inner>,
+}
+
+impl fmt::Debug for WeakWorker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakWorker").finish()
+    }
+}
+
+impl WeakWorker {
+    /// Attempts to upgrade `WeakWorker` to [`Worker`] if last instance of one wasn't dropped yet.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Worker> {
+        let inner = self.inner.upgrade()?;
+
+        Some(Worker { inner })
+    }
+}
@@ -1,25 +1,83 @@
+#[cfg(all(test, feature = "tracing"))]
+mod tests;
+
 use crate::messages::{Notification, Request};
 use crate::worker::common::{EventHandlers, SubscriptionTarget, WeakEventHandlers};
 use crate::worker::utils;
 use crate::worker::utils::{PreparedChannelRead, PreparedChannelWrite};
 use crate::worker::{RequestError, SubscriptionHandler};
 use atomic_take::AtomicTake;
+use futures_lite::FutureExt;
 use hash_hasher::HashedMap;
 use log::{debug, error, trace, warn};
 use lru::LruCache;
 use mediasoup_sys::fbs::{message, notification, request, response};
 use mediasoup_sys::UvAsyncT;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 use planus::ReadAsRoot;
 use serde::Deserialize;
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::fmt::{Debug, Display};
+use std::future::Future;
 use std::num::NonZeroUsize;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
+thread_local! {
+    /// Request timeout override set by [`WithRequestTimeout`] for the duration of its inner
+    /// future's `poll()`.
+    static REQUEST_TIMEOUT_OVERRIDE: Cell<Option<Option<Duration>>> = Cell::new(None);
+}
+
+/// Future returned by [`with_request_timeout()`](super::with_request_timeout).
+#[must_use = "Futures do nothing unless polled"]
+pub struct WithRequestTimeout<F> {
+    future: Pin<Box<F>>,
+    timeout: Option<Duration>,
+}
+
+impl<F> Debug for WithRequestTimeout<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithRequestTimeout")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<F> WithRequestTimeout<F> {
+    pub(super) fn new(future: F, timeout: Option<Duration>) -> Self {
+        Self {
+            future: Box::pin(future),
+            timeout,
+        }
+    }
+}
+
+impl<F: Future> Future for WithRequestTimeout<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let timeout = self.timeout;
+        let previous = REQUEST_TIMEOUT_OVERRIDE.with(|cell| cell.replace(Some(timeout)));
+        // Restore previous value even if inner future panics
+        struct RestoreGuard(Option<Option<Duration>>);
+        impl Drop for RestoreGuard {
+            fn drop(&mut self) {
+                REQUEST_TIMEOUT_OVERRIDE.with(|cell| cell.set(self.0));
+            }
+        }
+        let _guard = RestoreGuard(previous);
+
+        self.future.as_mut().poll(cx)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub(super) enum InternalMessage {
@@ -168,11 +226,90 @@ struct FBSRequestsContainer {
     handlers: HashedMap<u32, async_oneshot::Sender<FBSResponseResult>>,
 }
 
+/// Wakes the worker up when there are new messages for it in [`OutgoingMessageBuffer`].
+#[derive(Clone)]
+pub(super) enum WorkerWaker {
+    /// Worker runs in a thread of this process and reads messages when async handle is signaled.
+    UvAsync(UvAsyncT),
+    /// Worker runs in a child process, messages are written into its pipe by a blocking task that
+    /// waits on this condition variable in [`OutgoingMessageQueue::pop_blocking()`].
+    Condvar(Arc<Condvar>),
+}
+
+impl WorkerWaker {
+    /// Returns `false` if worker can't be woken up anymore.
+    fn wake(&self) -> bool {
+        match self {
+            Self::UvAsync(handle) => {
+                // Notify worker that there is something to read
+                let ret = unsafe { mediasoup_sys::uv_async_send(*handle) };
+                if ret != 0 {
+                    error!("uv_async_send call failed with code {}", ret);
+                    return false;
+                }
+                true
+            }
+            Self::Condvar(condvar) => {
+                condvar.notify_one();
+                true
+            }
+        }
+    }
+}
+
 struct OutgoingMessageBuffer {
-    handle: Option<UvAsyncT>,
+    waker: Option<WorkerWaker>,
     messages: VecDeque<Arc<AtomicTake<Vec<u8>>>>,
 }
 
+impl OutgoingMessageBuffer {
+    fn pop(&mut self) -> Option<Vec<u8>> {
+        while let Some(maybe_message) = self.messages.pop_front() {
+            // Request might have already been cancelled
+            if let Some(message) = maybe_message.take() {
+                return Some(message);
+            }
+        }
+
+        None
+    }
+}
+
+/// Queue of messages to be sent to the worker, for workers that don't read them through channel
+/// read function.
+#[derive(Clone)]
+pub(super) struct OutgoingMessageQueue(Arc<Mutex<OutgoingMessageBuffer>>);
+
+impl OutgoingMessageQueue {
+    pub(super) fn set_waker(&self, waker: WorkerWaker) {
+        self.0.lock().waker.replace(waker);
+    }
+
+    /// Blocks until there is a message to send, returns `None` once there are no messages left and
+    /// `stopped` is set. `condvar` must be the one of [`WorkerWaker::Condvar`] set with
+    /// [`Self::set_waker()`].
+    pub(super) fn pop_blocking(&self, condvar: &Condvar, stopped: &AtomicBool) -> Option<Vec<u8>> {
+        let mut buffer = self.0.lock();
+        loop {
+            if let Some(message) = buffer.pop() {
+                return Some(message);
+            }
+            if stopped.load(Ordering::Acquire) {
+                return None;
+            }
+            condvar.wait(&mut buffer);
+        }
+    }
+
+    /// Wakes up the worker (or the task writing messages to it), for instance to notice that it
+    /// should stop.
+    pub(super) fn wake(&self) {
+        if let Some(waker) = &self.0.lock().waker {
+            waker.wake();
+        }
+    }
+}
+
 // TODO: use 'close' in 'request' method.
 #[allow(clippy::type_complexity, dead_code)]
 struct Inner {
@@ -183,6 +320,7 @@ struct Inner {
     event_handlers_weak:
         WeakEventHandlers<Arc<dyn Fn(notification::NotificationRef<'_>) + Send + Sync + 'static>>,
     worker_closed: Arc<AtomicBool>,
+    request_timeout: Option<Duration>,
     closed: AtomicBool,
 }
 
@@ -200,9 +338,10 @@ pub(crate) struct Channel {
 impl Channel {
     pub(super) fn new(
         worker_closed: Arc<AtomicBool>,
+        request_timeout: Option<Duration>,
     ) -> (Self, PreparedChannelRead, PreparedChannelWrite) {
         let outgoing_message_buffer = Arc::new(Mutex::new(OutgoingMessageBuffer {
-            handle: None,
+            waker: None,
             messages: VecDeque::with_capacity(10),
         }));
         let requests_container = Arc::<Mutex<FBSRequestsContainer>>::default();
@@ -217,18 +356,13 @@ impl Channel {
 
             move |handle| {
                 let mut outgoing_message_buffer = outgoing_message_buffer.lock();
-                if outgoing_message_buffer.handle.is_none() {
-                    outgoing_message_buffer.handle.replace(handle);
+                if outgoing_message_buffer.waker.is_none() {
+                    outgoing_message_buffer
+                        .waker
+                        .replace(WorkerWaker::UvAsync(handle));
                 }
 
-                while let Some(maybe_message) = outgoing_message_buffer.messages.pop_front() {
-                    // Request might have already been cancelled
-                    if let Some(message) = maybe_message.take() {
-                        return Some(message);
-                    }
-                }
-
-                None
+                outgoing_message_buffer.pop()
             }
         });
 
@@ -317,6 +451,7 @@ impl Channel {
             buffered_notifications_for,
             event_handlers_weak,
             worker_closed,
+            request_timeout,
             closed: AtomicBool::new(false),
         });
 
@@ -327,6 +462,21 @@ impl Channel {
         )
     }
 
+    /// Messages that are waiting to be sent to the worker.
+    pub(super) fn outgoing_message_queue(&self) -> OutgoingMessageQueue {
+        OutgoingMessageQueue(Arc::clone(&self.inner.outgoing_message_buffer))
+    }
+
+    /// Number of requests sent to the worker that are still waiting for response.
+    pub(super) fn outstanding_requests(&self) -> usize {
+        self.inner
+            .requests_container_weak
+            .upgrade()
+            .map_or(0, |requests_container| {
+                requests_container.lock().handlers.len()
+            })
+    }
+
     pub(super) fn get_internal_message_receiver(&self) -> async_channel::Receiver<InternalMessage> {
         self.inner.internal_message_receiver.clone()
     }
@@ -356,6 +506,52 @@ impl Channel {
         R: Request<HandlerId = HandlerId> + 'static,
         HandlerId: Display,
     {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::field;
+
+            let span = tracing::info_span!(
+                target: "mediasoup::channel",
+                "request",
+                method = ?R::METHOD,
+                handler_id = %handler_id,
+                id = field::Empty,
+                duration_ms = field::Empty,
+                error = field::Empty,
+            );
+            let started = std::time::Instant::now();
+
+            let result = tracing::Instrument::instrument(
+                self.request_internal(handler_id, request),
+                span.clone(),
+            )
+            .await;
+
+            span.record("duration_ms", started.elapsed().as_secs_f64() * 1000.0);
+            if let Err(error) = &result {
+                span.record("error", field::display(error));
+            }
+
+            result
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.request_internal(handler_id, request).await
+    }
+
+    async fn request_internal<R, HandlerId>(
+        &self,
+        handler_id: HandlerId,
+        request: R,
+    ) -> Result<R::Response, RequestError>
+    where
+        R: Request<HandlerId = HandlerId> + 'static,
+        HandlerId: Display,
+    {
+        // Override (if any) must be read before the first `.await`, while still being polled by
+        // `WithRequestTimeout`
+        let timeout = REQUEST_TIMEOUT_OVERRIDE
+            .with(Cell::get)
+            .unwrap_or(self.inner.request_timeout);
         let id;
         let (result_sender, result_receiver) = async_oneshot::oneshot();
 
@@ -375,17 +571,26 @@ impl Channel {
         }
 
         debug!("request() [method:{:?}, id:{}]", R::METHOD, id);
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("id", id);
 
         let data = request.into_bytes(id, handler_id);
 
         let buffer = Arc::new(AtomicTake::new(data));
 
+        // Drop guard to make sure to drop pending request when future is cancelled, times out or
+        // request can't be sent
+        let request_drop_guard = RequestDropGuard {
+            id,
+            message: Arc::clone(&buffer),
+            channel: self,
+            removed: false,
+        };
+
         {
             let mut outgoing_message_buffer = self.inner.outgoing_message_buffer.lock();
-            outgoing_message_buffer
-                .messages
-                .push_back(Arc::clone(&buffer));
-            if let Some(handle) = outgoing_message_buffer.handle {
+            outgoing_message_buffer.messages.push_back(buffer);
+            if let Some(waker) = &outgoing_message_buffer.waker {
                 if self.inner.worker_closed.load(Ordering::Acquire) {
                     // Forbid all requests after worker closing except one worker closing request
                     // TODO: We were checking before that inner.closed.
@@ -393,27 +598,36 @@ impl Channel {
                         return Err(RequestError::ChannelClosed);
                     }
                 }
-                unsafe {
-                    // Notify worker that there is something to read
-                    let ret = mediasoup_sys::uv_async_send(handle);
-                    if ret != 0 {
-                        error!("uv_async_send call failed with code {}", ret);
-
-                        return Err(RequestError::ChannelClosed);
-                    }
+                if !waker.wake() {
+                    return Err(RequestError::ChannelClosed);
                 }
             }
         }
 
-        // Drop guard to make sure to drop pending request when future is cancelled
-        let request_drop_guard = RequestDropGuard {
-            id,
-            message: buffer,
-            channel: self,
-            removed: false,
-        };
+        let response_result_fut = match timeout {
+            Some(timeout) => {
+                let response_fut = async { Some(result_receiver.await) };
+                let timeout_fut = async {
+                    async_io::Timer::after(timeout).await;
+                    None
+                };
 
-        let response_result_fut = result_receiver.await;
+                match response_fut.or(timeout_fut).await {
+                    Some(response_result_fut) => response_result_fut,
+                    None => {
+                        warn!(
+                            "request timed out [method:{:?}, id:{}, timeout:{:?}]",
+                            R::METHOD,
+                            id,
+                            timeout
+                        );
+
+                        return Err(RequestError::TimedOut);
+                    }
+                }
+            }
+            None => result_receiver.await,
+        };
 
         request_drop_guard.remove();
 
@@ -485,17 +699,12 @@ impl Channel {
             outgoing_message_buffer
                 .messages
                 .push_back(Arc::clone(&message));
-            if let Some(handle) = outgoing_message_buffer.handle {
+            if let Some(waker) = &outgoing_message_buffer.waker {
                 if self.inner.worker_closed.load(Ordering::Acquire) {
                     return Err(NotificationError::ChannelClosed);
                 }
-                unsafe {
-                    // Notify worker that there is something to read
-                    let ret = mediasoup_sys::uv_async_send(handle);
-                    if ret != 0 {
-                        error!("uv_async_send call failed with code {}", ret);
-                        return Err(NotificationError::ChannelClosed);
-                    }
+                if !waker.wake() {
+                    return Err(NotificationError::ChannelClosed);
                 }
             }
         }
@@ -0,0 +1,134 @@
+use super::Channel;
+use crate::messages::WorkerDumpRequest;
+use crate::router::RouterOptions;
+use crate::worker::{with_request_timeout, RequestError, WorkerSettings};
+use crate::worker_manager::WorkerManager;
+use futures_lite::future;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{subscriber, Event, Metadata, Subscriber};
+
+#[derive(Debug)]
+struct CapturedSpan {
+    target: &'static str,
+    name: &'static str,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for CapturedSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.insert(field.name(), format!("{value:?}"));
+    }
+}
+
+/// Subscriber that remembers all spans with their recorded fields.
+#[derive(Clone, Default)]
+struct CapturingSubscriber {
+    spans: Arc<Mutex<Vec<CapturedSpan>>>,
+}
+
+impl CapturingSubscriber {
+    /// Fields of the only request span captured for given handler.
+    fn request_span_fields(&self, handler_id: &str) -> HashMap<&'static str, String> {
+        let spans = self.spans.lock();
+        let mut request_spans = spans.iter().filter(|span| {
+            span.target == "mediasoup::channel"
+                && span.name == "request"
+                && span.fields["handler_id"] == handler_id
+        });
+        let span = request_spans.next().expect("Request span was not created");
+        assert!(request_spans.next().is_none(), "Unexpected request span");
+
+        span.fields.clone()
+    }
+}
+
+impl Subscriber for CapturingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let mut span = CapturedSpan {
+            target: attributes.metadata().target(),
+            name: attributes.metadata().name(),
+            fields: HashMap::new(),
+        };
+        attributes.record(&mut span);
+
+        let mut spans = self.spans.lock();
+        spans.push(span);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        let index = id.into_u64() as usize - 1;
+        values.record(&mut self.spans.lock()[index]);
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn request_span_has_method_handler_and_duration() {
+    let capturing_subscriber = CapturingSubscriber::default();
+
+    subscriber::with_default(capturing_subscriber.clone(), || {
+        future::block_on(async move {
+            let worker = WorkerManager::new()
+                .create_worker(WorkerSettings::default())
+                .await
+                .expect("Failed to create worker");
+            let router = worker
+                .create_router(RouterOptions::default())
+                .await
+                .expect("Failed to create router");
+
+            router.dump().await.expect("Failed to dump router");
+
+            let fields = capturing_subscriber.request_span_fields(&router.id().to_string());
+            assert_eq!(fields["method"], "RouterDump");
+            assert!(fields.contains_key("id"));
+            assert!(fields["duration_ms"].parse::<f64>().unwrap() >= 0.0);
+            assert!(!fields.contains_key("error"));
+        });
+    });
+}
+
+#[test]
+fn request_span_records_error() {
+    let capturing_subscriber = CapturingSubscriber::default();
+
+    subscriber::with_default(capturing_subscriber.clone(), || {
+        // Nothing reads messages from this channel, so request never gets a response
+        let (channel, _prepared_channel_read, _prepared_channel_write) =
+            Channel::new(Arc::new(AtomicBool::new(false)), None);
+
+        let result = future::block_on(with_request_timeout(
+            Some(Duration::from_millis(10)),
+            channel.request("", WorkerDumpRequest {}),
+        ));
+        assert!(matches!(result, Err(RequestError::TimedOut)));
+
+        let fields = capturing_subscriber.request_span_fields("");
+        assert_eq!(fields["method"], "WorkerDump");
+        assert!(fields["duration_ms"].parse::<f64>().unwrap() >= 10.0);
+        assert_eq!(
+            fields["error"],
+            result.as_ref().unwrap_err().to_string(),
+            "Error must be recorded"
+        );
+    });
+}
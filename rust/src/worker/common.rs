@@ -86,8 +86,20 @@ impl EventHandlers<Arc<dyn Fn(notification::NotificationRef<'_>) + Send + Sync +
         target_id: &SubscriptionTarget,
         value: notification::NotificationRef<'_>,
     ) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "mediasoup::channel",
+            "notification",
+            target_id = ?target_id,
+            event = ?value.event(),
+            handlers = tracing::field::Empty,
+        )
+        .entered();
+
         let handlers = self.handlers.lock();
         if let Some(list) = handlers.get(target_id) {
+            #[cfg(feature = "tracing")]
+            _span.record("handlers", list.callbacks.len());
             for callback in list.callbacks.values() {
                 callback(value);
             }
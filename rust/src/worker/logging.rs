@@ -0,0 +1,358 @@
+//! Routing of log lines produced by mediasoup-worker into `log` (or `tracing` when corresponding
+//! feature is enabled).
+//!
+//! Worker sends its logs as plain text lines in the following format:
+//! ```text
+//! [<file>:<line> | ]<class>::<function>()[ | <message>]
+//! ```
+//! Log tag that enabled the line is not part of it, so tag is inferred from the worker class that
+//! produced the line for classes dedicated to a single area (ICE, DTLS, SCTP, etc.). Records are
+//! emitted with `mediasoup::worker::<tag>` target (or just `mediasoup::worker` when tag is not
+//! known), which allows filtering them per tag with usual logger configuration like
+//! `RUST_LOG=mediasoup::worker::ice=debug`.
+
+#[cfg(test)]
+mod tests;
+
+use crate::worker::{WorkerId, WorkerLogTag};
+use log::Level;
+#[cfg(feature = "tracing")]
+pub(crate) use spans::{EntitySpan, EntitySpans};
+
+/// Target used for worker log lines with unknown tag.
+const TARGET: &str = "mediasoup::worker";
+
+impl WorkerLogTag {
+    /// Log target that worker log lines with this tag are emitted with.
+    pub(crate) fn target(self) -> &'static str {
+        match self {
+            Self::Info => "mediasoup::worker::info",
+            Self::Ice => "mediasoup::worker::ice",
+            Self::Dtls => "mediasoup::worker::dtls",
+            Self::Rtp => "mediasoup::worker::rtp",
+            Self::Srtp => "mediasoup::worker::srtp",
+            Self::Rtcp => "mediasoup::worker::rtcp",
+            Self::Rtx => "mediasoup::worker::rtx",
+            Self::Bwe => "mediasoup::worker::bwe",
+            Self::Score => "mediasoup::worker::score",
+            Self::Simulcast => "mediasoup::worker::simulcast",
+            Self::Svc => "mediasoup::worker::svc",
+            Self::Sctp => "mediasoup::worker::sctp",
+            Self::Message => "mediasoup::worker::message",
+        }
+    }
+
+    /// Tag of log lines produced by given worker class, `None` for classes that log about
+    /// multiple areas (transports, producers, consumers, etc.).
+    fn from_class(class: &str) -> Option<Self> {
+        if class.starts_with("RTC::RTCP::") {
+            return Some(Self::Rtcp);
+        }
+        if class.starts_with("RTC::Codecs::") {
+            return Some(Self::Rtp);
+        }
+
+        Some(match class {
+            "mediasoup-worker" | "Worker" | "Settings" | "DepLibUV" | "DepLibWebRTC"
+            | "DepOpenSSL" | "DepLibSRTP" => Self::Info,
+            "RTC::IceServer" | "RTC::IceCandidate" | "RTC::StunPacket" => Self::Ice,
+            "RTC::DtlsTransport" => Self::Dtls,
+            "RTC::RtpPacket" => Self::Rtp,
+            "RTC::SrtpSession" => Self::Srtp,
+            "RTC::RtxStream" | "RTC::NackGenerator" | "RTC::RtpRetransmissionBuffer" => Self::Rtx,
+            "RTC::TransportCongestionControlClient"
+            | "RTC::TransportCongestionControlServer"
+            | "RTC::SenderBandwidthEstimator" => Self::Bwe,
+            "RTC::SimulcastConsumer" => Self::Simulcast,
+            "RTC::SvcConsumer" => Self::Svc,
+            "DepUsrSCTP" | "RTC::SctpAssociation" | "RTC::SctpListener" => Self::Sctp,
+            _ => {
+                return None;
+            }
+        })
+    }
+}
+
+/// Entity mentioned in worker log line, like `[transportId:xyz]`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct WorkerLogEntity<'a> {
+    /// Kind of the entity, like `transportId`.
+    pub(crate) kind: &'a str,
+    /// Entity ID.
+    pub(crate) id: &'a str,
+}
+
+/// Structured representation of the log line produced by worker.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct WorkerLogRecord<'a> {
+    pub(crate) worker_id: WorkerId,
+    pub(crate) level: Level,
+    /// Tag inferred from the worker class that produced the line.
+    pub(crate) tag: Option<WorkerLogTag>,
+    /// Source file in worker, only present when worker is built with `MS_LOG_FILE_LINE`.
+    pub(crate) file: Option<&'a str>,
+    /// Line in source file, only present when worker is built with `MS_LOG_FILE_LINE`.
+    pub(crate) line: Option<u32>,
+    pub(crate) class: Option<&'a str>,
+    pub(crate) function: Option<&'a str>,
+    pub(crate) entity: Option<WorkerLogEntity<'a>>,
+    pub(crate) message: &'a str,
+    /// Original text of the line (without level prefix).
+    pub(crate) text: &'a str,
+}
+
+impl<'a> WorkerLogRecord<'a> {
+    /// Parses log line text (without level prefix), lines that do not follow expected format are
+    /// still represented as a record, just without class, function and tag.
+    pub(crate) fn parse(worker_id: WorkerId, level: Level, text: &'a str) -> Self {
+        let mut record = Self {
+            worker_id,
+            level,
+            tag: None,
+            file: None,
+            line: None,
+            class: None,
+            function: None,
+            entity: None,
+            message: text,
+            text,
+        };
+
+        // Trace lines look like `(trace) RTC::Router::HandleRequest()`
+        let mut rest = text.strip_prefix("(trace) ").unwrap_or(text);
+
+        if let Some((location, tail)) = rest.split_once(" | ") {
+            if let Some((file, line)) = parse_location(location) {
+                record.file = Some(file);
+                record.line = Some(line);
+                rest = tail;
+            }
+        }
+
+        let (callsite, message) = rest.split_once(" | ").unwrap_or((rest, ""));
+        let Some((class, function)) = callsite
+            .strip_suffix("()")
+            .and_then(|callsite| callsite.rsplit_once("::"))
+        else {
+            return record;
+        };
+
+        record.tag = WorkerLogTag::from_class(class);
+        record.class = Some(class);
+        record.function = Some(function);
+        record.entity = find_entity(message);
+        record.message = message;
+
+        record
+    }
+
+    /// Log target of this record.
+    pub(crate) fn target(&self) -> &'static str {
+        self.tag.map_or(TARGET, WorkerLogTag::target)
+    }
+
+    /// Emit record through `log`.
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn emit(&self) {
+        let metadata = log::Metadata::builder()
+            .level(self.level)
+            .target(self.target())
+            .build();
+        let logger = log::logger();
+
+        if self.level <= log::max_level() && logger.enabled(&metadata) {
+            logger.log(
+                &log::Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("[id:{}] {}", self.worker_id, self.text))
+                    .file(self.file)
+                    .line(self.line)
+                    .build(),
+            );
+        }
+    }
+
+    /// Emit record as `tracing` event within the span of the entity mentioned in the record or
+    /// worker span otherwise.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn emit(&self, worker_span: &tracing::Span, entity_spans: &EntitySpans) {
+        let span = self
+            .entity
+            .and_then(|entity| entity_spans.get(entity.id))
+            .unwrap_or_else(|| worker_span.clone());
+
+        spans::emit(self, &span);
+    }
+}
+
+/// Parses `file:line`, file name may not contain spaces.
+fn parse_location(location: &str) -> Option<(&str, u32)> {
+    let (file, line) = location.rsplit_once(':')?;
+    if file.is_empty() || file.contains(' ') || file.ends_with(':') {
+        return None;
+    }
+
+    Some((file, line.parse().ok()?))
+}
+
+/// Finds first `<kind>Id:<id>` pair within square brackets in the message.
+fn find_entity(message: &str) -> Option<WorkerLogEntity<'_>> {
+    let (_, details) = message.rsplit_once('[')?;
+    let (details, _) = details.split_once(']')?;
+
+    details.split(", ").find_map(|pair| {
+        let (kind, id) = pair.split_once(':')?;
+        let is_entity_id = kind.ends_with("Id")
+            && kind.bytes().all(|byte| byte.is_ascii_alphanumeric())
+            && !id.is_empty()
+            && id
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-');
+
+        is_entity_id.then_some(WorkerLogEntity { kind, id })
+    })
+}
+
+#[cfg(feature = "tracing")]
+mod spans {
+    use super::WorkerLogRecord;
+    use hash_hasher::HashedMap;
+    use log::Level;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Spans of entities (routers, transports) within the worker by their IDs, used to attach
+    /// worker log lines that mention particular entity to the span of that entity.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct EntitySpans(Arc<Mutex<HashedMap<String, tracing::Span>>>);
+
+    impl EntitySpans {
+        /// Registers span of the entity, span is unregistered when returned [`EntitySpan`] is
+        /// dropped.
+        pub(crate) fn register(&self, id: String, span: tracing::Span) -> EntitySpan {
+            self.0.lock().insert(id.clone(), span.clone());
+
+            EntitySpan {
+                id,
+                span,
+                entity_spans: self.clone(),
+            }
+        }
+
+        pub(super) fn get(&self, id: &str) -> Option<tracing::Span> {
+            self.0.lock().get(id).cloned()
+        }
+    }
+
+    /// Span of the entity registered in [`EntitySpans`].
+    #[derive(Debug)]
+    pub(crate) struct EntitySpan {
+        id: String,
+        span: tracing::Span,
+        entity_spans: EntitySpans,
+    }
+
+    impl Drop for EntitySpan {
+        fn drop(&mut self) {
+            self.entity_spans.0.lock().remove(&self.id);
+        }
+    }
+
+    impl EntitySpan {
+        pub(crate) fn span(&self) -> &tracing::Span {
+            &self.span
+        }
+    }
+
+    macro_rules! event {
+        ($target:expr, $record:expr, $span:expr) => {{
+            let record = $record;
+            let entity_id = record.entity.map(|entity| entity.id);
+            match record.level {
+                Level::Error => tracing::error!(
+                    target: $target,
+                    parent: $span,
+                    worker_id = %record.worker_id,
+                    class = record.class,
+                    function = record.function,
+                    file = record.file,
+                    line = record.line,
+                    entity_id,
+                    "{}",
+                    record.message,
+                ),
+                Level::Warn => tracing::warn!(
+                    target: $target,
+                    parent: $span,
+                    worker_id = %record.worker_id,
+                    class = record.class,
+                    function = record.function,
+                    file = record.file,
+                    line = record.line,
+                    entity_id,
+                    "{}",
+                    record.message,
+                ),
+                Level::Info => tracing::info!(
+                    target: $target,
+                    parent: $span,
+                    worker_id = %record.worker_id,
+                    class = record.class,
+                    function = record.function,
+                    file = record.file,
+                    line = record.line,
+                    entity_id,
+                    "{}",
+                    record.message,
+                ),
+                Level::Debug => tracing::debug!(
+                    target: $target,
+                    parent: $span,
+                    worker_id = %record.worker_id,
+                    class = record.class,
+                    function = record.function,
+                    file = record.file,
+                    line = record.line,
+                    entity_id,
+                    "{}",
+                    record.message,
+                ),
+                Level::Trace => tracing::trace!(
+                    target: $target,
+                    parent: $span,
+                    worker_id = %record.worker_id,
+                    class = record.class,
+                    function = record.function,
+                    file = record.file,
+                    line = record.line,
+                    entity_id,
+                    "{}",
+                    record.message,
+                ),
+            }
+        }};
+    }
+
+    /// `tracing` requires target and level to be known statically, hence a separate callsite for
+    /// each combination.
+    pub(super) fn emit(record: &WorkerLogRecord<'_>, span: &tracing::Span) {
+        match record.target() {
+            "mediasoup::worker::info" => event!("mediasoup::worker::info", record, span),
+            "mediasoup::worker::ice" => event!("mediasoup::worker::ice", record, span),
+            "mediasoup::worker::dtls" => event!("mediasoup::worker::dtls", record, span),
+            "mediasoup::worker::rtp" => event!("mediasoup::worker::rtp", record, span),
+            "mediasoup::worker::srtp" => event!("mediasoup::worker::srtp", record, span),
+            "mediasoup::worker::rtcp" => event!("mediasoup::worker::rtcp", record, span),
+            "mediasoup::worker::rtx" => event!("mediasoup::worker::rtx", record, span),
+            "mediasoup::worker::bwe" => event!("mediasoup::worker::bwe", record, span),
+            "mediasoup::worker::score" => event!("mediasoup::worker::score", record, span),
+            "mediasoup::worker::simulcast" => {
+                event!("mediasoup::worker::simulcast", record, span)
+            }
+            "mediasoup::worker::svc" => event!("mediasoup::worker::svc", record, span),
+            "mediasoup::worker::sctp" => event!("mediasoup::worker::sctp", record, span),
+            "mediasoup::worker::message" => event!("mediasoup::worker::message", record, span),
+            _ => event!("mediasoup::worker", record, span),
+        }
+    }
+}
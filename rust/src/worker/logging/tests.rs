@@ -0,0 +1,93 @@
+use super::*;
+
+#[test]
+fn parse_line_with_tag() {
+    let worker_id = WorkerId::new();
+    let record = WorkerLogRecord::parse(
+        worker_id,
+        Level::Debug,
+        "RTC::IceServer::ProcessStunPacket() | transition from state 'new' to 'connected'",
+    );
+
+    assert_eq!(record.worker_id, worker_id);
+    assert_eq!(record.level, Level::Debug);
+    assert_eq!(record.tag, Some(WorkerLogTag::Ice));
+    assert_eq!(record.target(), "mediasoup::worker::ice");
+    assert_eq!(record.file, None);
+    assert_eq!(record.line, None);
+    assert_eq!(record.class, Some("RTC::IceServer"));
+    assert_eq!(record.function, Some("ProcessStunPacket"));
+    assert_eq!(record.entity, None);
+    assert_eq!(record.message, "transition from state 'new' to 'connected'",);
+
+    let record = WorkerLogRecord::parse(
+        worker_id,
+        Level::Warn,
+        "RTC::RTCP::FeedbackRtpNack::Parse() | not enough space for Nack item, discarded",
+    );
+    assert_eq!(record.tag, Some(WorkerLogTag::Rtcp));
+    assert_eq!(record.target(), "mediasoup::worker::rtcp");
+}
+
+#[test]
+fn parse_line_with_location_and_entity() {
+    let record = WorkerLogRecord::parse(
+        WorkerId::new(),
+        Level::Debug,
+        "RTC/Transport.cpp:123 | RTC::Transport::HandleRequest() | Producer created \
+        [producerId:5bd8ab4b-3d4c-4f17-8d35-5ce3b0c0b7a9]",
+    );
+
+    assert_eq!(record.tag, None);
+    assert_eq!(record.target(), "mediasoup::worker");
+    assert_eq!(record.file, Some("RTC/Transport.cpp"));
+    assert_eq!(record.line, Some(123));
+    assert_eq!(record.class, Some("RTC::Transport"));
+    assert_eq!(record.function, Some("HandleRequest"));
+    assert_eq!(
+        record.entity,
+        Some(WorkerLogEntity {
+            kind: "producerId",
+            id: "5bd8ab4b-3d4c-4f17-8d35-5ce3b0c0b7a9",
+        }),
+    );
+    assert_eq!(
+        record.message,
+        "Producer created [producerId:5bd8ab4b-3d4c-4f17-8d35-5ce3b0c0b7a9]",
+    );
+
+    let record = WorkerLogRecord::parse(
+        WorkerId::new(),
+        Level::Debug,
+        "RTC::SimulcastConsumer::UpdateTargetLayers() | target layers changed \
+        [spatial:-1, temporal:-1, consumerId:abc]",
+    );
+    assert_eq!(record.tag, Some(WorkerLogTag::Simulcast));
+    assert_eq!(
+        record.entity,
+        Some(WorkerLogEntity {
+            kind: "consumerId",
+            id: "abc",
+        }),
+    );
+}
+
+#[test]
+fn parse_unexpected_line() {
+    let record = WorkerLogRecord::parse(WorkerId::new(), Level::Error, "something went wrong");
+
+    assert_eq!(record.tag, None);
+    assert_eq!(record.class, None);
+    assert_eq!(record.function, None);
+    assert_eq!(record.message, "something went wrong");
+    assert_eq!(record.text, "something went wrong");
+
+    let record = WorkerLogRecord::parse(
+        WorkerId::new(),
+        Level::Debug,
+        "(trace) RTC::Router::HandleRequest()",
+    );
+    assert_eq!(record.class, Some("RTC::Router"));
+    assert_eq!(record.function, Some("HandleRequest"));
+    assert_eq!(record.message, "");
+}
@@ -1,18 +1,25 @@
 mod channel_read_fn;
 mod channel_write_fn;
+#[cfg(unix)]
+mod process;
 
+use crate::executor::Executor;
 use crate::worker::channel::BufferMessagesGuard;
 use crate::worker::{Channel, SubscriptionTarget, WorkerId};
 pub(super) use channel_read_fn::{prepare_channel_read_fn, PreparedChannelRead};
 pub(super) use channel_write_fn::{prepare_channel_write_fn, PreparedChannelWrite};
+#[cfg(unix)]
+pub(super) use process::run_worker_process;
 use std::ffi::CString;
 use std::os::raw::{c_char, c_int};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Worker exit error
 #[derive(Debug, Copy, Clone, Error)]
+#[non_exhaustive]
 pub enum ExitError {
     /// Generic error.
     #[error("Worker exited with generic error")]
@@ -26,6 +33,12 @@ pub enum ExitError {
         /// Status code returned by worker
         status_code: i32,
     },
+    /// Worker process was terminated by a signal.
+    #[error("Worker process was terminated by signal {signal}")]
+    Signal {
+        /// Signal number
+        signal: i32,
+    },
     /// Unexpected error.
     #[error("Worker exited unexpectedly")]
     Unexpected,
@@ -34,26 +47,50 @@ pub enum ExitError {
 pub(super) struct WorkerRunResult {
     pub(super) channel: Channel,
     pub(super) buffer_worker_messages_guard: BufferMessagesGuard,
+    /// PID of the process worker runs in, used by worker as a target of its own notifications.
+    pub(super) pid: u32,
+}
+
+#[cfg(not(unix))]
+pub(super) fn run_worker_process<OE>(
+    _executor: &Arc<dyn Executor>,
+    _id: WorkerId,
+    _process: crate::worker::WorkerProcessSettings,
+    _args: Vec<String>,
+    _worker_closed: Arc<AtomicBool>,
+    _request_timeout: Option<Duration>,
+    _on_exit: OE,
+) -> std::io::Result<WorkerRunResult>
+where
+    OE: FnOnce(Result<(), ExitError>) + Send + 'static,
+{
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Running worker in a separate process is only supported on Unix",
+    ))
 }
 
 pub(super) fn run_worker_with_channels<OE>(
+    executor: &Arc<dyn Executor>,
     id: WorkerId,
     thread_initializer: Option<Arc<dyn Fn() + Send + Sync>>,
     args: Vec<String>,
     worker_closed: Arc<AtomicBool>,
+    request_timeout: Option<Duration>,
     on_exit: OE,
-) -> WorkerRunResult
+) -> std::io::Result<WorkerRunResult>
 where
     OE: FnOnce(Result<(), ExitError>) + Send + 'static,
 {
     let (channel, prepared_channel_read, prepared_channel_write) =
-        Channel::new(Arc::clone(&worker_closed));
+        Channel::new(Arc::clone(&worker_closed), request_timeout);
+    let pid = std::process::id();
     let buffer_worker_messages_guard =
-        channel.buffer_messages_for(SubscriptionTarget::String(std::process::id().to_string()));
+        channel.buffer_messages_for(SubscriptionTarget::String(pid.to_string()));
 
-    std::thread::Builder::new()
-        .name(format!("mediasoup-worker-{id}"))
-        .spawn(move || {
+    executor.spawn_blocking(
+        format!("mediasoup-worker-{id}"),
+        Box::new(move || {
             if let Some(thread_initializer) = thread_initializer {
                 thread_initializer();
             }
@@ -95,11 +132,12 @@ where
                 42 => Err(ExitError::Settings),
                 status_code => Err(ExitError::Unknown { status_code }),
             });
-        })
-        .expect("Failed to spawn mediasoup-worker thread");
+        }),
+    )?;
 
-    WorkerRunResult {
+    Ok(WorkerRunResult {
         channel,
         buffer_worker_messages_guard,
-    }
+        pid,
+    })
 }
@@ -5,6 +5,12 @@ use std::slice;
 #[allow(clippy::type_complexity)]
 pub(super) struct ChannelReadCallback(Box<dyn FnMut(&[u8]) + Send + 'static>);
 
+impl ChannelReadCallback {
+    pub(super) fn call(&mut self, message: &[u8]) {
+        (self.0)(message);
+    }
+}
+
 pub(crate) struct PreparedChannelWrite {
     channel_write_fn: ChannelWriteFn,
     channel_write_ctx: ChannelWriteCtx,
@@ -28,6 +34,12 @@ impl PreparedChannelWrite {
         } = self;
         (channel_write_fn, channel_write_ctx, read_callback)
     }
+
+    /// Callback to call with messages received from the worker directly, for workers that don't
+    /// use channel write function.
+    pub(super) fn into_read_callback(self) -> ChannelReadCallback {
+        self.read_callback
+    }
 }
 
 /// Given callback function, prepares a pair of channel write function and context, which can be
@@ -0,0 +1,315 @@
+#[cfg(test)]
+mod tests;
+
+use super::{ExitError, WorkerRunResult};
+use crate::executor::Executor;
+use crate::worker::channel::WorkerWaker;
+use crate::worker::{Channel, SubscriptionTarget, WorkerId, WorkerProcessSettings};
+use log::{debug, error};
+use parking_lot::Condvar;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+/// File descriptor worker executable reads messages from.
+const CONSUMER_CHANNEL_FD: RawFd = 3;
+/// File descriptor worker executable writes messages to.
+const PRODUCER_CHANNEL_FD: RawFd = 4;
+/// Child ends of both pipes are moved to this or higher file descriptors before being duplicated
+/// into their final places, so that neither of them is overwritten before it was duplicated.
+const MIN_TEMPORARY_FD: RawFd = 10;
+
+/// Creates a pipe, returns read and write ends, both with `FD_CLOEXEC` flag set.
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as RawFd; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    for fd in [&read, &write] {
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok((read, write))
+}
+
+#[cfg(target_os = "linux")]
+fn cpu_set(cpu_affinity: &[usize]) -> io::Result<libc::cpu_set_t> {
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpu_affinity {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("CPU {cpu} is out of range of CPU affinity mask"),
+            ));
+        }
+        unsafe {
+            libc::CPU_SET(cpu, &mut cpu_set);
+        }
+    }
+
+    Ok(cpu_set)
+}
+
+/// Kills and reaps worker process when dropped, unless it was taken out to be waited for, so
+/// that the process doesn't outlive failed [`run_worker_process()`].
+struct ChildGuard(Option<Child>);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.0.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Writes message prefixed with its length the way worker executable expects it.
+pub(super) fn write_message<W: Write>(writer: &mut W, message: &[u8]) -> io::Result<()> {
+    let length = u32::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Message is too large"))?;
+    let mut buffer = Vec::with_capacity(4 + message.len());
+    buffer.extend_from_slice(&length.to_ne_bytes());
+    buffer.extend_from_slice(message);
+
+    writer.write_all(&buffer)?;
+    writer.flush()
+}
+
+/// Reads next message written by worker executable, returned message keeps its size prefix.
+///
+/// Returns `None` once the worker closed its end of the pipe.
+pub(super) fn read_message<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    if let Err(error) = reader.read_exact(&mut length) {
+        return match error.kind() {
+            io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(error),
+        };
+    }
+
+    let mut message = vec![0; 4 + u32::from_ne_bytes(length) as usize];
+    message[..4].copy_from_slice(&length);
+    reader.read_exact(&mut message[4..])?;
+
+    Ok(Some(message))
+}
+
+pub(super) fn exit_status_to_result(status: ExitStatus) -> Result<(), ExitError> {
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(1) => Err(ExitError::Generic),
+        Some(42) => Err(ExitError::Settings),
+        Some(status_code) => Err(ExitError::Unknown { status_code }),
+        None => match status.signal() {
+            Some(signal) => Err(ExitError::Signal { signal }),
+            None => Err(ExitError::Unexpected),
+        },
+    }
+}
+
+pub(in crate::worker) fn run_worker_process<OE>(
+    executor: &Arc<dyn Executor>,
+    id: WorkerId,
+    process: WorkerProcessSettings,
+    args: Vec<String>,
+    worker_closed: Arc<AtomicBool>,
+    request_timeout: Option<Duration>,
+    on_exit: OE,
+) -> io::Result<WorkerRunResult>
+where
+    OE: FnOnce(Result<(), ExitError>) + Send + 'static,
+{
+    let WorkerProcessSettings {
+        executable,
+        niceness,
+        cpu_affinity,
+    } = process;
+
+    #[cfg(target_os = "linux")]
+    let cpu_set = cpu_affinity.as_deref().map(cpu_set).transpose()?;
+    #[cfg(not(target_os = "linux"))]
+    if cpu_affinity.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "CPU affinity of worker process is only supported on Linux",
+        ));
+    }
+
+    let (consumer_read, consumer_write) = pipe()?;
+    let (producer_read, producer_write) = pipe()?;
+
+    let mut command = Command::new(&executable);
+    // First argument is the program name, which is set by `Command` already
+    command
+        .args(args.iter().skip(1))
+        .env("MEDIASOUP_VERSION", env!("CARGO_PKG_VERSION"))
+        .stdin(Stdio::null());
+
+    {
+        let consumer_fd = consumer_read.as_raw_fd();
+        let producer_fd = producer_write.as_raw_fd();
+
+        // SAFETY: Only async-signal-safe functions are called in between `fork()` and `exec()`
+        unsafe {
+            command.pre_exec(move || {
+                // Both ends are moved out of the way first, either of them might occupy the other's
+                // final file descriptor
+                let mut temporary_fds = [0 as RawFd; 2];
+                for (fd, temporary_fd) in [consumer_fd, producer_fd]
+                    .into_iter()
+                    .zip(&mut temporary_fds)
+                {
+                    *temporary_fd = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, MIN_TEMPORARY_FD);
+                    if *temporary_fd == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                for (temporary_fd, target_fd) in temporary_fds
+                    .into_iter()
+                    .zip([CONSUMER_CHANNEL_FD, PRODUCER_CHANNEL_FD])
+                {
+                    if libc::dup2(temporary_fd, target_fd) == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+
+                if let Some(niceness) = niceness {
+                    if libc::setpriority(libc::PRIO_PROCESS as _, 0, niceness) == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+
+                #[cfg(target_os = "linux")]
+                if let Some(cpu_set) = &cpu_set {
+                    if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), cpu_set)
+                        == -1
+                    {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+
+                Ok(())
+            });
+        }
+    }
+
+    let child = command.spawn()?;
+    // Worker ends of the pipes are not needed here anymore, this also makes sure reads return EOF
+    // once the worker exits
+    drop(consumer_read);
+    drop(producer_write);
+
+    let pid = child.id();
+    let child_guard = ChildGuard(Some(child));
+    debug!(
+        "spawned worker process [id:{}, pid:{}]: {}",
+        id,
+        pid,
+        executable.display(),
+    );
+
+    let (channel, _prepared_channel_read, prepared_channel_write) =
+        Channel::new(worker_closed, request_timeout);
+    // Notifications from the worker use its PID as target
+    let buffer_worker_messages_guard =
+        channel.buffer_messages_for(SubscriptionTarget::String(pid.to_string()));
+
+    let exited = Arc::new(AtomicBool::new(false));
+    let outgoing_message_queue = channel.outgoing_message_queue();
+    let condvar = Arc::new(Condvar::new());
+    outgoing_message_queue.set_waker(WorkerWaker::Condvar(Arc::clone(&condvar)));
+
+    // Sender is dropped once reader is done, there is nothing to send
+    let (reader_done_sender, reader_done_receiver) = mpsc::channel::<()>();
+
+    // Threads are spawned in such order that the ones already spawned finish once the worker
+    // process is killed by `child_guard` in case spawning of the next one fails
+    executor.spawn_blocking(format!("mediasoup-worker-{id}-reader"), {
+        let mut read_callback = prepared_channel_write.into_read_callback();
+        let mut reader = BufReader::new(File::from(producer_read));
+
+        Box::new(move || {
+            let _reader_done_sender = reader_done_sender;
+
+            loop {
+                match read_message(&mut reader) {
+                    Ok(Some(message)) => {
+                        read_callback.call(&message);
+                    }
+                    Ok(None) => {
+                        break;
+                    }
+                    Err(error) => {
+                        error!("failed to read from worker process [id:{}]: {}", id, error);
+                        break;
+                    }
+                }
+            }
+        })
+    })?;
+
+    executor.spawn_blocking(format!("mediasoup-worker-{id}-writer"), {
+        let outgoing_message_queue = outgoing_message_queue.clone();
+        let exited = Arc::clone(&exited);
+        let mut writer = File::from(consumer_write);
+
+        Box::new(move || {
+            while let Some(message) = outgoing_message_queue.pop_blocking(&condvar, &exited) {
+                if let Err(error) = write_message(&mut writer, &message) {
+                    debug!("failed to write to worker process [id:{}]: {}", id, error);
+                    break;
+                }
+            }
+        })
+    })?;
+
+    let result = executor.spawn_blocking(format!("mediasoup-worker-{id}"), {
+        let exited = Arc::clone(&exited);
+        let outgoing_message_queue = outgoing_message_queue.clone();
+
+        Box::new(move || {
+            let mut child_guard = child_guard;
+            let mut child = child_guard.0.take().expect("Child is only taken here");
+
+            let result = match child.wait() {
+                Ok(status) => exit_status_to_result(status),
+                Err(error) => {
+                    error!("failed to wait for worker process [id:{}]: {}", id, error);
+                    Err(ExitError::Unexpected)
+                }
+            };
+
+            // Process all messages worker managed to send before exiting
+            let _ = reader_done_receiver.recv();
+
+            exited.store(true, Ordering::Release);
+            outgoing_message_queue.wake();
+
+            on_exit(result);
+        })
+    });
+    if let Err(error) = result {
+        // Worker process was killed when closure with `child_guard` was dropped, writer is the only
+        // thread left to stop
+        exited.store(true, Ordering::Release);
+        outgoing_message_queue.wake();
+
+        return Err(error);
+    }
+
+    Ok(WorkerRunResult {
+        channel,
+        buffer_worker_messages_guard,
+        pid,
+    })
+}
@@ -0,0 +1,133 @@
+use super::*;
+use parking_lot::Mutex;
+use std::future::Future;
+use std::io::Cursor;
+use std::pin::Pin;
+use std::thread;
+
+#[test]
+fn write_message_prefixes_length() {
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, b"hello").expect("Failed to write message");
+
+    assert_eq!(&buffer[..4], &5_u32.to_ne_bytes());
+    assert_eq!(&buffer[4..], b"hello");
+}
+
+#[test]
+fn read_message_keeps_size_prefix() {
+    let mut stream = Vec::new();
+    for message in [&b"first"[..], &b"second message"[..]] {
+        stream.extend_from_slice(&(message.len() as u32).to_ne_bytes());
+        stream.extend_from_slice(message);
+    }
+    // Incomplete message, worker exited in the middle of writing it
+    stream.extend_from_slice(&[1, 0]);
+    let mut reader = Cursor::new(stream);
+
+    let first = read_message(&mut reader)
+        .expect("Failed to read message")
+        .expect("Message expected");
+    assert_eq!(&first[..4], &5_u32.to_ne_bytes());
+    assert_eq!(&first[4..], b"first");
+
+    let second = read_message(&mut reader)
+        .expect("Failed to read message")
+        .expect("Message expected");
+    assert_eq!(&second[4..], b"second message");
+
+    assert_eq!(
+        read_message(&mut reader).expect("Failed to read message"),
+        None
+    );
+}
+
+#[test]
+fn exit_status_is_reported() {
+    // Raw wait status, exit code is in the second byte, terminating signal in the first one
+    assert!(matches!(
+        exit_status_to_result(ExitStatus::from_raw(0)),
+        Ok(()),
+    ));
+    assert!(matches!(
+        exit_status_to_result(ExitStatus::from_raw(42 << 8)),
+        Err(ExitError::Settings),
+    ));
+    assert!(matches!(
+        exit_status_to_result(ExitStatus::from_raw(41 << 8)),
+        Err(ExitError::Unknown { status_code: 41 }),
+    ));
+    assert!(matches!(
+        exit_status_to_result(ExitStatus::from_raw(libc::SIGSEGV)),
+        Err(ExitError::Signal { signal }) if signal == libc::SIGSEGV,
+    ));
+}
+
+#[test]
+fn missing_executable_is_error() {
+    let executor: Arc<dyn Executor> = Arc::new(async_executor::Executor::new());
+    let result = run_worker_process(
+        &executor,
+        WorkerId::new(),
+        WorkerProcessSettings::new("/nonexistent/mediasoup-worker"),
+        vec![String::new()],
+        Arc::default(),
+        None,
+        |_| {},
+    );
+
+    assert_eq!(
+        result.err().map(|error| error.kind()),
+        Some(io::ErrorKind::NotFound),
+    );
+}
+
+/// Runs blocking closures on threads it keeps handles of, but fails to spawn the given one.
+struct FailingExecutor {
+    fail_on: usize,
+    threads: Mutex<Vec<thread::JoinHandle<()>>>,
+}
+
+impl Executor for FailingExecutor {
+    fn spawn(&self, _future: Pin<Box<dyn Future<Output = ()> + Send + 'static>>) {}
+
+    fn spawn_blocking(
+        &self,
+        name: String,
+        f: Box<dyn FnOnce() + Send + 'static>,
+    ) -> io::Result<()> {
+        let mut threads = self.threads.lock();
+        if threads.len() == self.fail_on {
+            return Err(io::Error::new(io::ErrorKind::Other, "No threads left"));
+        }
+        threads.push(thread::Builder::new().name(name).spawn(f)?);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn failed_thread_spawn_kills_process() {
+    for fail_on in 0..3 {
+        let executor = Arc::new(FailingExecutor {
+            fail_on,
+            threads: Mutex::default(),
+        });
+
+        let result = run_worker_process(
+            &(Arc::clone(&executor) as Arc<dyn Executor>),
+            WorkerId::new(),
+            WorkerProcessSettings::new("sleep"),
+            vec!["sleep".to_string(), "60".to_string()],
+            Arc::default(),
+            None,
+            |_| panic!("Process exit must not be reported"),
+        );
+        assert!(result.is_err());
+
+        // Reader only finishes once the process is gone, writer once it knows about it
+        for thread in std::mem::take(&mut *executor.threads.lock()) {
+            thread.join().expect("Thread panicked");
+        }
+    }
+}
@@ -3,15 +3,18 @@
 #[cfg(test)]
 mod tests;
 
+use crate::executor::Executor;
+use crate::macros::instrument;
 use crate::worker::{Worker, WorkerId, WorkerSettings};
-use async_executor::Executor;
 use async_oneshot::Sender;
 use event_listener_primitives::{Bag, HandlerId};
 use futures_lite::future;
-use log::debug;
+use log::{debug, error, warn};
 use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::ops::DerefMut;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::{fmt, io, mem};
@@ -23,7 +26,7 @@ struct Handlers {
 }
 
 struct Inner {
-    executor: Arc<Executor<'static>>,
+    executor: Arc<dyn Executor>,
     handlers: Handlers,
     /// Mapping from worker ID to the close event receiver
     workers: Arc<Mutex<HashMap<WorkerId, mpsc::Receiver<()>>>>,
@@ -61,9 +64,10 @@ impl Drop for Inner {
 /// })
 /// ```
 ///
-/// If you already happen to have [`async_executor::Executor`] instance available or need a
-/// multi-threaded executor, [`WorkerManager::with_executor()`] can be used to create an instance
-/// instead.
+/// If you already happen to have an async runtime (like [`async_executor::Executor`] or, with
+/// `tokio` feature enabled, [`tokio::runtime::Handle`]) or need a multi-threaded executor,
+/// [`WorkerManager::with_executor()`] can be used to create an instance instead, see
+/// [`Executor`] for details.
 #[derive(Clone)]
 #[must_use]
 pub struct WorkerManager {
@@ -85,7 +89,7 @@ impl Default for WorkerManager {
 impl WorkerManager {
     /// Create new worker manager, internally a new single-threaded executor will be created.
     pub fn new() -> Self {
-        let executor = Arc::new(Executor::new());
+        let executor = Arc::new(async_executor::Executor::new());
         let (stop_sender, stop_receiver) = async_oneshot::oneshot::<()>();
         {
             let executor = Arc::clone(&executor);
@@ -108,7 +112,31 @@ impl WorkerManager {
     }
 
     /// Create new worker manager, uses externally provided executor.
-    pub fn with_executor(executor: Arc<Executor<'static>>) -> Self {
+    ///
+    /// # Examples
+    /// ```
+    /// use mediasoup::worker_manager::WorkerManager;
+    /// use std::sync::Arc;
+    ///
+    /// let executor = Arc::new(async_executor::Executor::new());
+    /// let worker_manager = WorkerManager::with_executor(executor.clone());
+    /// ```
+    ///
+    /// With `tokio` feature enabled, handle of Tokio runtime can be used instead:
+    /// ```
+    /// # #[cfg(feature = "tokio")]
+    /// # {
+    /// use mediasoup::worker_manager::WorkerManager;
+    /// use std::sync::Arc;
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// runtime.block_on(async {
+    ///     let worker_manager =
+    ///         WorkerManager::with_executor(Arc::new(tokio::runtime::Handle::current()));
+    /// });
+    /// # }
+    /// ```
+    pub fn with_executor(executor: Arc<dyn Executor>) -> Self {
         let handlers = Handlers::default();
 
         let inner = Arc::new(Inner {
@@ -129,21 +157,26 @@ impl WorkerManager {
 
         let (exit_sender, exit_receiver) = mpsc::channel();
         let id = Arc::new(Mutex::new(None));
-        let worker = Worker::new(
-            Arc::clone(&self.inner.executor),
-            worker_settings,
-            self.clone(),
-            {
-                let id = Arc::clone(&id);
-                let workers = Arc::clone(&self.inner.workers);
-
-                move || {
-                    let _ = exit_sender.send(());
-                    if let Some(id) = id.lock().take() {
-                        workers.lock().remove(&id);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(target: "mediasoup::worker", "create_worker");
+        let worker = instrument!(
+            Worker::new(
+                Arc::clone(&self.inner.executor),
+                worker_settings,
+                self.clone(),
+                {
+                    let id = Arc::clone(&id);
+                    let workers = Arc::clone(&self.inner.workers);
+
+                    move || {
+                        let _ = exit_sender.send(());
+                        if let Some(id) = id.lock().take() {
+                            workers.lock().remove(&id);
+                        }
                     }
-                }
-            },
+                },
+            ),
+            span,
         )
         .await?;
 
@@ -156,8 +189,386 @@ impl WorkerManager {
         Ok(worker)
     }
 
+    pub(crate) fn executor(&self) -> &Arc<dyn Executor> {
+        &self.inner.executor
+    }
+
+    /// Creates a pool of `size` workers with the given settings, see [`WorkerPool`] for details.
+    pub async fn create_worker_pool(
+        &self,
+        size: NonZeroUsize,
+        worker_settings: WorkerSettings,
+    ) -> io::Result<WorkerPool> {
+        debug!("create_worker_pool() [size:{}]", size);
+
+        let worker_pool = WorkerPool {
+            inner: Arc::new(WorkerPoolInner {
+                worker_manager: self.clone(),
+                worker_settings,
+                workers: Mutex::default(),
+                next_index: AtomicUsize::new(0),
+                handlers: WorkerPoolHandlers::default(),
+            }),
+        };
+
+        for _ in 0..size.get() {
+            let worker = self
+                .create_worker(worker_pool.inner.worker_settings.clone())
+                .await?;
+            worker_pool.inner.add_worker(worker);
+        }
+
+        Ok(worker_pool)
+    }
+
     /// Callback is called when a new worker is created.
     pub fn on_new_worker<F: Fn(&Worker) + Send + Sync + 'static>(&self, callback: F) -> HandlerId {
         self.inner.handlers.new_worker.add(Arc::new(callback))
     }
 }
+
+/// Number of entities that currently exist on a worker.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct WorkerLoad {
+    /// Number of routers.
+    pub routers: usize,
+    /// Number of transports (of any kind).
+    pub transports: usize,
+    /// Number of producers.
+    pub producers: usize,
+    /// Number of consumers.
+    pub consumers: usize,
+}
+
+/// Strategy for picking a worker from [`WorkerPool`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub enum PickWorkerStrategy {
+    /// Pick workers one after another.
+    RoundRobin,
+    /// Pick the worker with the least consumers (the most expensive entity in terms of CPU usage),
+    /// ties are broken by the number of producers.
+    LeastConsumers,
+    /// Pick the worker with the lowest score returned by given function.
+    Custom(Arc<dyn Fn(&Worker, &WorkerLoad) -> u64 + Send + Sync>),
+}
+
+impl fmt::Debug for PickWorkerStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RoundRobin => f.write_str("RoundRobin"),
+            Self::LeastConsumers => f.write_str("LeastConsumers"),
+            Self::Custom(_) => f.debug_tuple("Custom").finish(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct WorkerLoadCounters {
+    routers: AtomicUsize,
+    transports: AtomicUsize,
+    producers: AtomicUsize,
+    consumers: AtomicUsize,
+}
+
+impl WorkerLoadCounters {
+    fn load(&self) -> WorkerLoad {
+        WorkerLoad {
+            routers: self.routers.load(Ordering::Relaxed),
+            transports: self.transports.load(Ordering::Relaxed),
+            producers: self.producers.load(Ordering::Relaxed),
+            consumers: self.consumers.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Increment counter returned by `counter` and decrement it when entity is closed, `on_close`
+    /// is expected to register decrementing callback.
+    fn track(
+        self: &Arc<Self>,
+        counter: fn(&Self) -> &AtomicUsize,
+        on_close: impl FnOnce(Box<dyn FnOnce() + Send>) -> HandlerId,
+    ) {
+        counter(self).fetch_add(1, Ordering::Relaxed);
+        let counters = Arc::clone(self);
+        on_close(Box::new(move || {
+            counter(&counters).fetch_sub(1, Ordering::Relaxed);
+        }))
+        .detach();
+    }
+}
+
+struct PooledWorker {
+    worker: Worker,
+    counters: Arc<WorkerLoadCounters>,
+    _handlers: Vec<HandlerId>,
+}
+
+#[derive(Default)]
+#[allow(clippy::type_complexity)]
+struct WorkerPoolHandlers {
+    worker_replaced: Bag<Arc<dyn Fn(WorkerId, &Worker) + Send + Sync>, WorkerId, Worker>,
+}
+
+struct WorkerPoolInner {
+    worker_manager: WorkerManager,
+    worker_settings: WorkerSettings,
+    workers: Mutex<Vec<PooledWorker>>,
+    next_index: AtomicUsize,
+    handlers: WorkerPoolHandlers,
+}
+
+impl WorkerPoolInner {
+    fn add_worker(self: &Arc<Self>, worker: Worker) {
+        let counters = Arc::<WorkerLoadCounters>::default();
+
+        let new_router_handler = worker.on_new_router({
+            let counters = Arc::clone(&counters);
+
+            move |router| {
+                counters.track(|c| &c.routers, |callback| router.on_close(callback));
+
+                let counters = Arc::clone(&counters);
+                router
+                    .on_new_transport(move |transport| {
+                        counters.track(|c| &c.transports, |callback| transport.on_close(callback));
+
+                        transport
+                            .on_new_producer(Arc::new({
+                                let counters = Arc::clone(&counters);
+
+                                move |producer| {
+                                    counters.track(
+                                        |c| &c.producers,
+                                        |callback| producer.on_close(callback),
+                                    );
+                                }
+                            }))
+                            .detach();
+
+                        transport
+                            .on_new_consumer(Arc::new({
+                                let counters = Arc::clone(&counters);
+
+                                move |consumer| {
+                                    counters.track(
+                                        |c| &c.consumers,
+                                        |callback| consumer.on_close(callback),
+                                    );
+                                }
+                            }))
+                            .detach();
+                    })
+                    .detach();
+            }
+        });
+
+        let dead_handler = worker.on_dead({
+            let worker_id = worker.id();
+            let inner_weak = Arc::downgrade(self);
+
+            move |status| {
+                warn!("pooled worker died [id:{}]: {:?}", worker_id, status);
+
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.replace_worker(worker_id);
+                }
+            }
+        });
+
+        self.workers.lock().push(PooledWorker {
+            worker,
+            counters,
+            _handlers: vec![new_router_handler, dead_handler],
+        });
+    }
+
+    fn replace_worker(self: &Arc<Self>, worker_id: WorkerId) {
+        // Drop dead worker outside of the lock
+        let _dead_worker = {
+            let mut workers = self.workers.lock();
+            workers
+                .iter()
+                .position(|pooled_worker| pooled_worker.worker.id() == worker_id)
+                .map(|index| workers.remove(index))
+        };
+
+        let inner_weak = Arc::downgrade(self);
+        self.worker_manager
+            .inner
+            .executor
+            .spawn(Box::pin(async move {
+                let Some(inner) = inner_weak.upgrade() else {
+                    return;
+                };
+
+                match inner
+                    .worker_manager
+                    .create_worker(inner.worker_settings.clone())
+                    .await
+                {
+                    Ok(worker) => {
+                        debug!(
+                            "replaced dead pooled worker [old id:{}, new id:{}]",
+                            worker_id,
+                            worker.id()
+                        );
+
+                        inner.add_worker(worker.clone());
+                        inner.handlers.worker_replaced.call(|callback| {
+                            callback(worker_id, &worker);
+                        });
+                    }
+                    Err(error) => {
+                        error!(
+                            "failed to replace dead pooled worker [id:{}]: {}",
+                            worker_id, error
+                        );
+                    }
+                }
+            }));
+    }
+}
+
+/// Pool of workers created with [`WorkerManager::create_worker_pool()`].
+///
+/// Pool keeps track of the number of routers, transports, producers and consumers on each worker
+/// and allows to pick a worker for new router according to [`PickWorkerStrategy`]. Workers that
+/// die unexpectedly are removed from the pool and replaced with new workers created with the same
+/// settings.
+///
+/// Workers are closed once both the pool and all other instances of the worker are dropped.
+///
+/// # Examples
+/// ```no_run
+/// use futures_lite::future;
+/// use mediasoup::router::RouterOptions;
+/// use mediasoup::worker::WorkerSettings;
+/// use mediasoup::worker_manager::{PickWorkerStrategy, WorkerManager};
+/// use std::num::NonZeroUsize;
+///
+/// let worker_manager = WorkerManager::new();
+///
+/// future::block_on(async move {
+///     let worker_pool = worker_manager
+///         .create_worker_pool(NonZeroUsize::new(4).unwrap(), WorkerSettings::default())
+///         .await
+///         .unwrap();
+///
+///     let worker = worker_pool
+///         .pick_worker(&PickWorkerStrategy::LeastConsumers)
+///         .unwrap();
+///     let router = worker
+///         .create_router(RouterOptions::new(vec![]))
+///         .await
+///         .unwrap();
+/// })
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct WorkerPool {
+    inner: Arc<WorkerPoolInner>,
+}
+
+impl fmt::Debug for WorkerPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkerPool")
+            .field("workers", &self.loads())
+            .finish()
+    }
+}
+
+impl WorkerPool {
+    /// Worker manager that created this pool.
+    pub fn worker_manager(&self) -> &WorkerManager {
+        &self.inner.worker_manager
+    }
+
+    /// Workers currently in the pool.
+    #[must_use]
+    pub fn workers(&self) -> Vec<Worker> {
+        self.inner
+            .workers
+            .lock()
+            .iter()
+            .map(|pooled_worker| pooled_worker.worker.clone())
+            .collect()
+    }
+
+    /// Current load of every worker in the pool.
+    #[must_use]
+    pub fn loads(&self) -> Vec<(WorkerId, WorkerLoad)> {
+        self.inner
+            .workers
+            .lock()
+            .iter()
+            .map(|pooled_worker| (pooled_worker.worker.id(), pooled_worker.counters.load()))
+            .collect()
+    }
+
+    /// Current load of the worker with given id, `None` if worker is not in the pool.
+    #[must_use]
+    pub fn load(&self, worker_id: WorkerId) -> Option<WorkerLoad> {
+        self.inner
+            .workers
+            .lock()
+            .iter()
+            .find(|pooled_worker| pooled_worker.worker.id() == worker_id)
+            .map(|pooled_worker| pooled_worker.counters.load())
+    }
+
+    /// Pick a worker according to given strategy.
+    ///
+    /// Returns `None` if the pool is empty, which only happens while dead workers are being
+    /// replaced.
+    #[must_use]
+    pub fn pick_worker(&self, strategy: &PickWorkerStrategy) -> Option<Worker> {
+        match strategy {
+            PickWorkerStrategy::RoundRobin => {
+                let workers = self.inner.workers.lock();
+                if workers.is_empty() {
+                    return None;
+                }
+                let index = self.inner.next_index.fetch_add(1, Ordering::Relaxed);
+                Some(workers[index % workers.len()].worker.clone())
+            }
+            PickWorkerStrategy::LeastConsumers => self
+                .inner
+                .workers
+                .lock()
+                .iter()
+                .min_by_key(|pooled_worker| {
+                    let load = pooled_worker.counters.load();
+                    (load.consumers, load.producers)
+                })
+                .map(|pooled_worker| pooled_worker.worker.clone()),
+            PickWorkerStrategy::Custom(scorer) => {
+                // Scorer is user code that may call back into the pool, so it is called only after
+                // workers are unlocked
+                let workers = self
+                    .inner
+                    .workers
+                    .lock()
+                    .iter()
+                    .map(|pooled_worker| {
+                        (pooled_worker.worker.clone(), pooled_worker.counters.load())
+                    })
+                    .collect::<Vec<_>>();
+
+                workers
+                    .into_iter()
+                    .min_by_key(|(worker, load)| scorer(worker, load))
+                    .map(|(worker, _load)| worker)
+            }
+        }
+    }
+
+    /// Callback is called when a dead worker was replaced with a new one. First argument is the id
+    /// of the dead worker.
+    pub fn on_worker_replaced<F: Fn(WorkerId, &Worker) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> HandlerId {
+        self.inner.handlers.worker_replaced.add(Arc::new(callback))
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::router::RouterOptions;
 use std::env;
 
 fn init() {
@@ -24,3 +25,121 @@ fn worker_manager_test() {
             .unwrap();
     });
 }
+
+#[cfg(feature = "tokio")]
+#[test]
+fn worker_manager_tokio_test() {
+    init();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async move {
+        let worker_manager =
+            WorkerManager::with_executor(Arc::new(tokio::runtime::Handle::current()));
+
+        let worker = worker_manager
+            .create_worker(WorkerSettings::default())
+            .await
+            .unwrap();
+
+        // Closing of router on drop is driven by Tokio runtime
+        let router = worker
+            .create_router(RouterOptions::default())
+            .await
+            .unwrap();
+        let (closed_sender, closed_receiver) = async_oneshot::oneshot::<()>();
+        let mut closed_sender = Some(closed_sender);
+        let _handler = router.on_close(move || {
+            let _ = closed_sender.take().unwrap().send(());
+        });
+        drop(router);
+
+        closed_receiver.await.unwrap();
+        worker.dump().await.unwrap();
+    });
+}
+
+#[test]
+fn worker_pool_test() {
+    init();
+
+    let worker_manager = WorkerManager::new();
+
+    future::block_on(async move {
+        let worker_pool = worker_manager
+            .create_worker_pool(NonZeroUsize::new(2).unwrap(), WorkerSettings::default())
+            .await
+            .unwrap();
+
+        let workers = worker_pool.workers();
+        assert_eq!(workers.len(), 2);
+
+        let worker = worker_pool
+            .pick_worker(&PickWorkerStrategy::RoundRobin)
+            .unwrap();
+        assert_eq!(worker.id(), workers[0].id());
+        let worker = worker_pool
+            .pick_worker(&PickWorkerStrategy::RoundRobin)
+            .unwrap();
+        assert_eq!(worker.id(), workers[1].id());
+        let worker = worker_pool
+            .pick_worker(&PickWorkerStrategy::RoundRobin)
+            .unwrap();
+        assert_eq!(worker.id(), workers[0].id());
+
+        let router = workers[0]
+            .create_router(RouterOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            worker_pool.load(workers[0].id()),
+            Some(WorkerLoad {
+                routers: 1,
+                ..WorkerLoad::default()
+            })
+        );
+
+        let worker = worker_pool
+            .pick_worker(&PickWorkerStrategy::Custom(Arc::new(|_worker, load| {
+                load.routers as u64
+            })))
+            .unwrap();
+        assert_eq!(worker.id(), workers[1].id());
+
+        // Scorer is allowed to call back into the pool
+        let worker = worker_pool
+            .pick_worker(&PickWorkerStrategy::Custom(Arc::new({
+                let worker_pool = worker_pool.clone();
+
+                move |worker, _load| worker_pool.load(worker.id()).unwrap().routers as u64
+            })))
+            .unwrap();
+        assert_eq!(worker.id(), workers[1].id());
+
+        drop(router);
+
+        assert_eq!(
+            worker_pool.load(workers[0].id()),
+            Some(WorkerLoad::default())
+        );
+
+        let (replaced_sender, replaced_receiver) = async_oneshot::oneshot::<(WorkerId, WorkerId)>();
+        let replaced_sender = Mutex::new(Some(replaced_sender));
+        let _handler = worker_pool.on_worker_replaced(move |old_worker_id, new_worker| {
+            if let Some(mut replaced_sender) = replaced_sender.lock().take() {
+                let _ = replaced_sender.send((old_worker_id, new_worker.id()));
+            }
+        });
+
+        // Simulate worker death, there is no way to crash worker on purpose
+        worker_pool.inner.replace_worker(workers[1].id());
+
+        let (old_worker_id, new_worker_id) = replaced_receiver.await.unwrap();
+        assert_eq!(old_worker_id, workers[1].id());
+        assert!(worker_pool.load(new_worker_id).is_some());
+        assert!(worker_pool.load(old_worker_id).is_none());
+    });
+}
@@ -4,8 +4,9 @@ use futures_lite::future;
 use hash_hasher::{HashedMap, HashedSet};
 use mediasoup::consumer::{ConsumerLayers, ConsumerOptions, ConsumerScore, ConsumerType};
 use mediasoup::data_structures::{AppData, ListenInfo, Protocol};
+use mediasoup::negotiation::ConsumeIncompatibility;
 use mediasoup::prelude::*;
-use mediasoup::producer::ProducerOptions;
+use mediasoup::producer::{ProducerOptions, ProducerType};
 use mediasoup::router::{Router, RouterOptions};
 use mediasoup::rtp_parameters::{
     MediaKind, MimeType, MimeTypeAudio, MimeTypeVideo, RtcpFeedback, RtcpParameters,
@@ -854,6 +855,16 @@ fn consume_incompatible_rtp_capabilities() {
 
             assert!(!router.can_consume(&audio_producer.id(), &incompatible_device_capabilities));
 
+            match router.check_consume(&audio_producer.id(), &incompatible_device_capabilities) {
+                Err(ConsumeIncompatibility::NoCompatibleMediaCodecs { codecs, .. }) => {
+                    assert_eq!(codecs.len(), 1);
+                    assert_eq!(codecs[0].mime_type, MimeType::Audio(MimeTypeAudio::Opus));
+                    // Capabilities have no Opus at all
+                    assert!(codecs[0].mismatches.is_empty());
+                }
+                result => panic!("Unexpected result: {result:?}"),
+            }
+
             assert!(matches!(
                 transport_2
                     .consume(ConsumerOptions::new(
@@ -1478,3 +1489,114 @@ fn close_event() {
         }
     });
 }
+
+#[test]
+fn consume_av1_succeeds() {
+    future::block_on(async move {
+        let (_executor_guard, worker, _router, _transport_1, _transport_2) = init().await;
+
+        let router = worker
+            .create_router(RouterOptions::new(vec![RtpCodecCapability::Video {
+                mime_type: MimeTypeVideo::Av1,
+                preferred_payload_type: None,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }]))
+            .await
+            .expect("Failed to create router");
+
+        let transport_options =
+            WebRtcTransportOptions::new(WebRtcTransportListenInfos::new(ListenInfo {
+                protocol: Protocol::Udp,
+                ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                announced_ip: None,
+                port: None,
+                send_buffer_size: None,
+                recv_buffer_size: None,
+            }));
+
+        let transport_1 = router
+            .create_webrtc_transport(transport_options.clone())
+            .await
+            .expect("Failed to create transport1");
+
+        let transport_2 = router
+            .create_webrtc_transport(transport_options)
+            .await
+            .expect("Failed to create transport2");
+
+        let producer = transport_1
+            .produce(ProducerOptions::new(
+                MediaKind::Video,
+                RtpParameters {
+                    mid: Some("AV1".to_string()),
+                    codecs: vec![RtpCodecParameters::Video {
+                        mime_type: MimeTypeVideo::Av1,
+                        payload_type: 45,
+                        clock_rate: NonZeroU32::new(90000).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+                    }],
+                    header_extensions: vec![RtpHeaderExtensionParameters {
+                        uri: RtpHeaderExtensionUri::Mid,
+                        id: 10,
+                        encrypt: false,
+                    }],
+                    encodings: vec![RtpEncodingParameters {
+                        ssrc: Some(33333333),
+                        scalability_mode: ScalabilityMode::L3T3,
+                        ..RtpEncodingParameters::default()
+                    }],
+                    rtcp: RtcpParameters {
+                        cname: Some("AV1".to_string()),
+                        ..RtcpParameters::default()
+                    },
+                },
+            ))
+            .await
+            .expect("Failed to produce AV1");
+
+        // Worker doesn't parse AV1 payloads, so all layers are forwarded as a single stream
+        assert_eq!(producer.r#type(), ProducerType::Simple);
+
+        let consumer = transport_2
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                RtpCapabilities {
+                    codecs: vec![RtpCodecCapability::Video {
+                        mime_type: MimeTypeVideo::Av1,
+                        preferred_payload_type: Some(101),
+                        clock_rate: NonZeroU32::new(90000).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![RtcpFeedback::Nack, RtcpFeedback::NackPli],
+                    }],
+                    header_extensions: vec![],
+                },
+            ))
+            .await
+            .expect("Failed to consume AV1");
+
+        assert_eq!(consumer.r#type(), ConsumerType::Simple);
+        assert!(matches!(
+            consumer.rtp_parameters().codecs[0],
+            RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Av1,
+                ..
+            },
+        ));
+        assert_eq!(
+            consumer.rtp_parameters().encodings[0].scalability_mode,
+            ScalabilityMode::L3T3,
+        );
+
+        let dump = consumer.dump().await.expect("Failed to dump consumer");
+        assert!(matches!(
+            dump.rtp_parameters.codecs[0],
+            RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Av1,
+                ..
+            },
+        ));
+    });
+}
@@ -0,0 +1,202 @@
+use async_io::Timer;
+use futures_lite::future;
+use mediasoup::direct_transport::{DirectTransport, DirectTransportOptions};
+use mediasoup::prelude::*;
+use mediasoup::router::{Router, RouterOptions};
+use mediasoup::rtp::frame_receiver::{FrameReceiver, FrameReceiverOptions};
+use mediasoup::rtp::RtpPacketBuilder;
+use mediasoup::rtp_parameters::{
+    MimeTypeAudio, RtpCapabilities, RtpCodecCapability, RtpCodecParameters,
+    RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use parking_lot::Mutex;
+use std::env;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn opus_codec_capability() -> RtpCodecCapability {
+    RtpCodecCapability::Audio {
+        mime_type: MimeTypeAudio::Opus,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(48000).unwrap(),
+        channels: NonZeroU8::new(2).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }
+}
+
+async fn init() -> (Worker, Router, DirectTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(vec![opus_codec_capability()]))
+        .await
+        .expect("Failed to create router");
+
+    let transport = router
+        .create_direct_transport(DirectTransportOptions::default())
+        .await
+        .expect("Failed to create transport");
+
+    (worker, router, transport)
+}
+
+async fn opus_consumer(transport: &DirectTransport) -> (Producer, Consumer) {
+    let producer = transport
+        .produce(ProducerOptions::new(
+            MediaKind::Audio,
+            RtpParameters {
+                codecs: vec![RtpCodecParameters::Audio {
+                    mime_type: MimeTypeAudio::Opus,
+                    payload_type: 111,
+                    clock_rate: NonZeroU32::new(48000).unwrap(),
+                    channels: NonZeroU8::new(2).unwrap(),
+                    parameters: RtpCodecParametersParameters::default(),
+                    rtcp_feedback: vec![],
+                }],
+                encodings: vec![RtpEncodingParameters {
+                    ssrc: Some(1111),
+                    ..RtpEncodingParameters::default()
+                }],
+                ..RtpParameters::default()
+            },
+        ))
+        .await
+        .expect("Failed to produce");
+
+    let consumer = transport
+        .consume(ConsumerOptions::new(
+            producer.id(),
+            RtpCapabilities {
+                codecs: vec![opus_codec_capability()],
+                header_extensions: vec![],
+            },
+        ))
+        .await
+        .expect("Failed to consume");
+
+    (producer, consumer)
+}
+
+#[test]
+fn receive_frames_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        let (producer, consumer) = opus_consumer(&transport).await;
+
+        let frame_receiver = FrameReceiver::new(&consumer, FrameReceiverOptions::default())
+            .expect("Failed to create frame receiver");
+
+        let num_packets = 8_u16;
+        let (received_tx, received_rx) = async_oneshot::oneshot::<()>();
+        let timestamps = Arc::new(Mutex::new(Vec::new()));
+        let _handler = frame_receiver.on_frame({
+            let received_tx = Mutex::new(Some(received_tx));
+            let timestamps = Arc::clone(&timestamps);
+
+            move |frame| {
+                assert!(frame.key_frame);
+                assert_eq!(frame.data, vec![0xFC, (frame.timestamp / 960) as u8]);
+
+                let mut timestamps = timestamps.lock();
+                timestamps.push(frame.timestamp);
+                if timestamps.len() == usize::from(num_packets) {
+                    let _ = received_tx.lock().take().unwrap().send(());
+                }
+            }
+        });
+
+        let Producer::Direct(direct_producer) = &producer else {
+            panic!("Expected direct producer");
+        };
+
+        // Swap two packets, frames must still come out in order
+        for sequence_number in [0, 1, 3, 2].into_iter().chain(4..num_packets) {
+            let mut builder =
+                RtpPacketBuilder::new(111, sequence_number, u32::from(sequence_number) * 960, 1111);
+            builder.payload = vec![0xFC, sequence_number as u8];
+            direct_producer
+                .send(builder.build().unwrap())
+                .expect("Failed to send RTP packet");
+        }
+
+        received_rx.await.expect("Failed to receive frames");
+
+        assert_eq!(
+            *timestamps.lock(),
+            (0..u32::from(num_packets))
+                .map(|index| index * 960)
+                .collect::<Vec<_>>(),
+        );
+    });
+}
+
+#[test]
+fn release_frames_after_lost_packet_without_new_packets() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        let (producer, consumer) = opus_consumer(&transport).await;
+
+        let frame_receiver = FrameReceiver::new(&consumer, FrameReceiverOptions::default())
+            .expect("Failed to create frame receiver");
+
+        let (received_tx, received_rx) = async_oneshot::oneshot::<()>();
+        let timestamps = Arc::new(Mutex::new(Vec::new()));
+        let _handler = frame_receiver.on_frame({
+            let received_tx = Mutex::new(Some(received_tx));
+            let timestamps = Arc::clone(&timestamps);
+
+            move |frame| {
+                let mut timestamps = timestamps.lock();
+                timestamps.push(frame.timestamp);
+                if timestamps.len() == 3 {
+                    let _ = received_tx.lock().take().unwrap().send(());
+                }
+            }
+        });
+
+        let Producer::Direct(direct_producer) = &producer else {
+            panic!("Expected direct producer");
+        };
+
+        // Packet 2 is lost and nothing is sent after packet 3, it must still be released once
+        // jitter buffer gives up on waiting
+        for sequence_number in [0_u16, 1, 3] {
+            let mut builder =
+                RtpPacketBuilder::new(111, sequence_number, u32::from(sequence_number) * 960, 1111);
+            builder.payload = vec![0xFC, sequence_number as u8];
+            direct_producer
+                .send(builder.build().unwrap())
+                .expect("Failed to send RTP packet");
+        }
+
+        future::or(
+            async { received_rx.await.expect("Failed to receive frames") },
+            async {
+                Timer::after(Duration::from_secs(2)).await;
+                panic!("Frame after lost packet was not released");
+            },
+        )
+        .await;
+
+        assert_eq!(*timestamps.lock(), vec![0, 960, 3 * 960]);
+    });
+}
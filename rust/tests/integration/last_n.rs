@@ -0,0 +1,213 @@
+use async_io::Timer;
+use futures_lite::future;
+use mediasoup::consumer::{Consumer, ConsumerOptions};
+use mediasoup::data_structures::{ListenInfo, Protocol};
+use mediasoup::last_n::{LastN, LastNOptions, VideoTier};
+use mediasoup::prelude::*;
+use mediasoup::producer::ProducerOptions;
+use mediasoup::router::RouterOptions;
+use mediasoup::rtp_parameters::{
+    MediaKind, MimeTypeAudio, MimeTypeVideo, RtpCapabilities, RtpCodecCapability,
+    RtpCodecParameters, RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::webrtc_transport::{
+    WebRtcTransport, WebRtcTransportListenInfos, WebRtcTransportOptions,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+use std::time::Duration;
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    ]
+}
+
+fn producer_options(kind: MediaKind, ssrc: u32) -> ProducerOptions {
+    let codec = match kind {
+        MediaKind::Audio => RtpCodecParameters::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            payload_type: 111,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        MediaKind::Video => RtpCodecParameters::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            payload_type: 96,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    };
+
+    ProducerOptions::new(
+        kind,
+        RtpParameters {
+            mid: Some(ssrc.to_string()),
+            codecs: vec![codec],
+            encodings: vec![RtpEncodingParameters {
+                ssrc: Some(ssrc),
+                ..RtpEncodingParameters::default()
+            }],
+            ..RtpParameters::default()
+        },
+    )
+}
+
+async fn init() -> (Worker, Router, WebRtcTransport, WebRtcTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    let transport_options =
+        WebRtcTransportOptions::new(WebRtcTransportListenInfos::new(ListenInfo {
+            protocol: Protocol::Udp,
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            announced_ip: None,
+            port: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        }));
+
+    let transport_1 = router
+        .create_webrtc_transport(transport_options.clone())
+        .await
+        .expect("Failed to create transport1");
+
+    let transport_2 = router
+        .create_webrtc_transport(transport_options)
+        .await
+        .expect("Failed to create transport2");
+
+    (worker, router, transport_1, transport_2)
+}
+
+/// Tier and actual state of the consumer once policy was applied.
+async fn state(last_n: &LastN, consumer: &Consumer) -> (Option<VideoTier>, bool, u8) {
+    Timer::after(Duration::from_millis(100)).await;
+
+    (
+        last_n.tier(&consumer.id()),
+        consumer.paused(),
+        consumer.priority(),
+    )
+}
+
+#[test]
+fn last_n_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport_1, transport_2) = init().await;
+
+        let mut options = LastNOptions::new(1);
+        options.thumbnails = 1;
+        options.hold = Duration::ZERO;
+        let last_n = LastN::new(options);
+
+        let mut speakers = Vec::new();
+        let mut consumers = Vec::new();
+        for ssrc in [1000, 2000, 3000] {
+            let audio_producer = transport_1
+                .produce(producer_options(MediaKind::Audio, ssrc))
+                .await
+                .expect("Failed to produce audio");
+            let video_producer = transport_1
+                .produce(producer_options(MediaKind::Video, ssrc + 1))
+                .await
+                .expect("Failed to produce video");
+            let consumer = transport_2
+                .consume(ConsumerOptions::new(
+                    video_producer.id(),
+                    RtpCapabilities {
+                        codecs: media_codecs(),
+                        header_extensions: vec![],
+                    },
+                ))
+                .await
+                .expect("Failed to consume");
+
+            last_n.add_video(&audio_producer, &video_producer);
+            last_n.add_consumer(&consumer, None);
+
+            speakers.push((audio_producer, video_producer));
+            consumers.push(consumer);
+        }
+
+        // Nobody spoke yet, speakers are ordered as they were added
+        assert_eq!(
+            state(&last_n, &consumers[0]).await,
+            (Some(VideoTier::High), false, 10),
+        );
+        assert_eq!(
+            state(&last_n, &consumers[1]).await,
+            (Some(VideoTier::Thumbnail), false, 1),
+        );
+        assert_eq!(
+            state(&last_n, &consumers[2]).await,
+            (Some(VideoTier::Off), true, 1),
+        );
+
+        last_n.set_dominant_speaker(speakers[2].0.id());
+
+        assert_eq!(
+            state(&last_n, &consumers[2]).await,
+            (Some(VideoTier::High), false, 10),
+        );
+        assert_eq!(
+            state(&last_n, &consumers[0]).await,
+            (Some(VideoTier::Thumbnail), false, 1),
+        );
+        assert_eq!(
+            state(&last_n, &consumers[1]).await,
+            (Some(VideoTier::Off), true, 1),
+        );
+
+        // Speaker that is gone doesn't take place of others
+        let (_audio_producer, video_producer) = speakers.remove(2);
+        drop(video_producer);
+        let consumer = consumers.remove(2);
+        assert_eq!(last_n.tier(&consumer.id()), None);
+        assert_eq!(
+            state(&last_n, &consumers[0]).await,
+            (Some(VideoTier::High), false, 10),
+        );
+        assert_eq!(
+            state(&last_n, &consumers[1]).await,
+            (Some(VideoTier::Thumbnail), false, 1),
+        );
+    });
+}
@@ -0,0 +1,176 @@
+use async_io::Timer;
+use futures_lite::future;
+use mediasoup::consumer::{ConsumerLayers, ConsumerOptions};
+use mediasoup::data_structures::{ListenInfo, Protocol};
+use mediasoup::layer_allocator::{LayerAllocator, LayerAllocatorOptions, TileSize};
+use mediasoup::prelude::*;
+use mediasoup::producer::ProducerOptions;
+use mediasoup::router::RouterOptions;
+use mediasoup::rtp_parameters::{
+    MediaKind, MimeTypeVideo, RtpCapabilities, RtpCodecCapability, RtpCodecParameters,
+    RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::webrtc_transport::{
+    WebRtcTransport, WebRtcTransportListenInfos, WebRtcTransportOptions,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![RtpCodecCapability::Video {
+        mime_type: MimeTypeVideo::Vp8,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }]
+}
+
+fn simulcast_producer_options(ssrc: u32) -> ProducerOptions {
+    ProducerOptions::new(
+        MediaKind::Video,
+        RtpParameters {
+            mid: Some(ssrc.to_string()),
+            codecs: vec![RtpCodecParameters::Video {
+                mime_type: MimeTypeVideo::Vp8,
+                payload_type: 96,
+                clock_rate: NonZeroU32::new(90000).unwrap(),
+                parameters: RtpCodecParametersParameters::default(),
+                rtcp_feedback: vec![],
+            }],
+            encodings: (0..3)
+                .map(|index| RtpEncodingParameters {
+                    ssrc: Some(ssrc + index),
+                    ..RtpEncodingParameters::default()
+                })
+                .collect(),
+            ..RtpParameters::default()
+        },
+    )
+}
+
+async fn init() -> (Worker, Router, WebRtcTransport, WebRtcTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    let transport_options =
+        WebRtcTransportOptions::new(WebRtcTransportListenInfos::new(ListenInfo {
+            protocol: Protocol::Udp,
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            announced_ip: None,
+            port: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        }));
+
+    let transport_1 = router
+        .create_webrtc_transport(transport_options.clone())
+        .await
+        .expect("Failed to create transport1");
+
+    let transport_2 = router
+        .create_webrtc_transport(transport_options)
+        .await
+        .expect("Failed to create transport2");
+
+    (worker, router, transport_1, transport_2)
+}
+
+#[test]
+fn allocate_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport_1, transport_2) = init().await;
+
+        assert_eq!(transport_2.available_bitrate(), None);
+
+        let allocator = LayerAllocator::new(&transport_2, LayerAllocatorOptions::default());
+
+        let mut consumers = Vec::new();
+        for ssrc in [1000, 2000] {
+            let producer = transport_1
+                .produce(simulcast_producer_options(ssrc))
+                .await
+                .expect("Failed to produce");
+
+            // Consumers are picked up by the allocator automatically
+            let consumer = transport_2
+                .consume(ConsumerOptions::new(
+                    producer.id(),
+                    RtpCapabilities {
+                        codecs: media_codecs(),
+                        header_extensions: vec![],
+                    },
+                ))
+                .await
+                .expect("Failed to consume");
+
+            consumers.push((producer, consumer));
+        }
+
+        let (_, main_consumer) = &consumers[0];
+        let (_, thumbnail_consumer) = &consumers[1];
+
+        allocator.set_importance(&main_consumer.id(), 4.0);
+        allocator.set_tile_size(
+            &thumbnail_consumer.id(),
+            Some(TileSize {
+                width: 320,
+                height: 180,
+            }),
+        );
+
+        let allocation = allocator.allocation(1_000_000);
+        assert_eq!(allocation.len(), 2);
+        assert_eq!(
+            allocation.get(&main_consumer.id()),
+            Some(&ConsumerLayers {
+                spatial_layer: 1,
+                temporal_layer: None,
+            }),
+        );
+
+        allocator.allocate(2_000_000).await;
+
+        assert_eq!(allocator.available_bitrate(), Some(2_000_000));
+        assert_eq!(
+            main_consumer
+                .preferred_layers()
+                .map(|layers| layers.spatial_layer),
+            Some(2),
+        );
+        assert_eq!(
+            thumbnail_consumer
+                .preferred_layers()
+                .map(|layers| layers.spatial_layer),
+            Some(0),
+        );
+
+        // Closed consumers are forgotten
+        let (_, consumer) = consumers.pop().unwrap();
+        drop(consumer);
+        Timer::after(Duration::from_millis(100)).await;
+
+        assert_eq!(allocator.allocation(2_000_000).len(), 1);
+    });
+}
@@ -4,12 +4,20 @@ mod consumer;
 mod data_consumer;
 mod data_producer;
 mod direct_transport;
+mod frame_receiver;
+mod last_n;
+mod layer_allocator;
 mod multiopus;
+mod packetizer;
 mod pipe_transport;
 mod plain_transport;
+mod playback;
 mod producer;
+mod recorder;
+mod room;
 mod router;
 mod smoke;
 mod webrtc_server;
 mod webrtc_transport;
+mod whip;
 mod worker;
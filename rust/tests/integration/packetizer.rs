@@ -0,0 +1,145 @@
+use futures_lite::future;
+use mediasoup::direct_transport::{DirectTransport, DirectTransportOptions};
+use mediasoup::prelude::*;
+use mediasoup::router::{Router, RouterOptions};
+use mediasoup::rtp::frame_receiver::{FrameReceiver, FrameReceiverOptions};
+use mediasoup::rtp::packetizer::{Packetizer, PacketizerOptions};
+use mediasoup::rtp_parameters::{
+    MimeTypeAudio, RtpCapabilities, RtpCodecCapability, RtpCodecParameters,
+    RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use parking_lot::Mutex;
+use std::env;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn opus_codec_capability() -> RtpCodecCapability {
+    RtpCodecCapability::Audio {
+        mime_type: MimeTypeAudio::Opus,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(48000).unwrap(),
+        channels: NonZeroU8::new(2).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }
+}
+
+async fn init() -> (Worker, Router, DirectTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(vec![opus_codec_capability()]))
+        .await
+        .expect("Failed to create router");
+
+    let transport = router
+        .create_direct_transport(DirectTransportOptions::default())
+        .await
+        .expect("Failed to create transport");
+
+    (worker, router, transport)
+}
+
+#[test]
+fn send_frames_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        let producer = transport
+            .produce(ProducerOptions::new(
+                MediaKind::Audio,
+                RtpParameters {
+                    codecs: vec![RtpCodecParameters::Audio {
+                        mime_type: MimeTypeAudio::Opus,
+                        payload_type: 111,
+                        clock_rate: NonZeroU32::new(48000).unwrap(),
+                        channels: NonZeroU8::new(2).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![],
+                    }],
+                    encodings: vec![RtpEncodingParameters {
+                        ssrc: Some(1111),
+                        ..RtpEncodingParameters::default()
+                    }],
+                    ..RtpParameters::default()
+                },
+            ))
+            .await
+            .expect("Failed to produce");
+
+        let consumer = transport
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                RtpCapabilities {
+                    codecs: vec![opus_codec_capability()],
+                    header_extensions: vec![],
+                },
+            ))
+            .await
+            .expect("Failed to consume");
+
+        let frame_receiver = FrameReceiver::new(&consumer, FrameReceiverOptions::default())
+            .expect("Failed to create frame receiver");
+
+        let num_frames = 5_u8;
+        let (received_tx, received_rx) = async_oneshot::oneshot::<()>();
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let _handler = frame_receiver.on_frame({
+            let received_tx = Mutex::new(Some(received_tx));
+            let frames = Arc::clone(&frames);
+
+            move |frame| {
+                let mut frames = frames.lock();
+                frames.push(frame.clone());
+                if frames.len() == usize::from(num_frames) {
+                    let _ = received_tx.lock().take().unwrap().send(());
+                }
+            }
+        });
+
+        let Producer::Direct(direct_producer) = &producer else {
+            panic!("Expected direct producer");
+        };
+
+        let mut packetizer =
+            Packetizer::new(producer.rtp_parameters(), PacketizerOptions::default())
+                .expect("Failed to create packetizer");
+        for index in 0..num_frames {
+            packetizer
+                .send(
+                    direct_producer,
+                    &[0xFC, index],
+                    Duration::from_millis(20) * u32::from(index),
+                    None,
+                )
+                .expect("Failed to send frame");
+        }
+
+        received_rx.await.expect("Failed to receive frames");
+
+        let frames = frames.lock();
+        for (index, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.data, vec![0xFC, index as u8]);
+            assert_eq!(
+                frame.timestamp.wrapping_sub(frames[0].timestamp),
+                index as u32 * 960,
+            );
+        }
+    });
+}
@@ -0,0 +1,134 @@
+use futures_lite::future;
+use mediasoup::direct_transport::{DirectTransport, DirectTransportOptions};
+use mediasoup::playback::{Playback, PlaybackError, PlaybackOptions};
+use mediasoup::prelude::*;
+use mediasoup::router::{Router, RouterOptions};
+use mediasoup::rtp::frame_receiver::{FrameReceiver, FrameReceiverOptions};
+use mediasoup::rtp_parameters::{
+    MimeTypeVideo, RtpCapabilities, RtpCodecCapability, RtpCodecParametersParameters,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use parking_lot::Mutex;
+use std::env;
+use std::io::Cursor;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn vp8_codec_capability() -> RtpCodecCapability {
+    RtpCodecCapability::Video {
+        mime_type: MimeTypeVideo::Vp8,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(90000).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }
+}
+
+async fn init() -> (Worker, Router, DirectTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(vec![vp8_codec_capability()]))
+        .await
+        .expect("Failed to create router");
+
+    let transport = router
+        .create_direct_transport(DirectTransportOptions::default())
+        .await
+        .expect("Failed to create transport");
+
+    (worker, router, transport)
+}
+
+#[test]
+fn play_ivf_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        let mut options = PlaybackOptions::default();
+        // Start paused so that consumer is created before the first frame is sent
+        options.paused = true;
+        let playback = Playback::create(&transport, "tests/integration/data/playback.ivf", options)
+            .await
+            .expect("Failed to start playback");
+        assert!(playback.paused());
+        assert!(playback.producer().paused());
+        assert_eq!(playback.producer().kind(), MediaKind::Video);
+
+        let consumer = transport
+            .consume(ConsumerOptions::new(
+                playback.producer().id(),
+                RtpCapabilities {
+                    codecs: vec![vp8_codec_capability()],
+                    header_extensions: vec![],
+                },
+            ))
+            .await
+            .expect("Failed to consume");
+
+        let frame_receiver = FrameReceiver::new(&consumer, FrameReceiverOptions::default())
+            .expect("Failed to create frame receiver");
+        let key_frames = Arc::new(Mutex::new(Vec::new()));
+        let _frame_handler = frame_receiver.on_frame({
+            let key_frames = Arc::clone(&key_frames);
+
+            move |frame| {
+                key_frames.lock().push(frame.key_frame);
+            }
+        });
+
+        let (mut ended_tx, ended_rx) = async_oneshot::oneshot::<()>();
+        let _ended_handler = playback.on_ended(move || {
+            let _ = ended_tx.send(());
+        });
+
+        playback.resume().await.expect("Failed to resume playback");
+        assert!(!playback.paused());
+
+        ended_rx
+            .await
+            .expect("Failed to wait for the end of playback");
+        // Let the last frame to be delivered
+        async_io::Timer::after(Duration::from_millis(100)).await;
+
+        // 10 frames with key frame every 5 frames
+        assert_eq!(
+            *key_frames.lock(),
+            (0..10).map(|index| index % 5 == 0).collect::<Vec<_>>(),
+        );
+
+        playback.stop().expect("Failed to stop playback");
+    });
+}
+
+#[test]
+fn play_unsupported_format_fails() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        assert!(matches!(
+            Playback::new(
+                &transport,
+                Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec()),
+                PlaybackOptions::default(),
+            )
+            .await,
+            Err(PlaybackError::UnsupportedFormat),
+        ));
+    });
+}
@@ -1,9 +1,9 @@
 use async_io::Timer;
-use futures_lite::future;
+use futures_lite::{future, StreamExt};
 use hash_hasher::{HashedMap, HashedSet};
 use mediasoup::data_structures::{AppData, ListenInfo, Protocol};
 use mediasoup::prelude::*;
-use mediasoup::producer::{ProducerOptions, ProducerTraceEventType, ProducerType};
+use mediasoup::producer::{ProducerEvent, ProducerOptions, ProducerTraceEventType, ProducerType};
 use mediasoup::router::{Router, RouterOptions};
 use mediasoup::rtp_parameters::{
     MediaKind, MimeTypeAudio, MimeTypeVideo, RtcpFeedback, RtcpParameters, RtpCodecCapability,
@@ -942,3 +942,41 @@ fn close_event() {
         }
     });
 }
+
+#[test]
+fn events_stream() {
+    future::block_on(async move {
+        let (_worker, _router, transport_1, _transport_2) = init().await;
+
+        let audio_producer = transport_1
+            .produce(audio_producer_options())
+            .await
+            .expect("Failed to produce audio");
+
+        let mut events = audio_producer.events();
+
+        audio_producer
+            .pause()
+            .await
+            .expect("Failed to pause audio producer");
+        audio_producer
+            .resume()
+            .await
+            .expect("Failed to resume audio producer");
+        drop(audio_producer);
+
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(ProducerEvent::Pause))
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(ProducerEvent::Resume))
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(ProducerEvent::Close))
+        ));
+        assert!(events.next().await.is_none());
+    });
+}
@@ -0,0 +1,189 @@
+use futures_lite::future;
+use mediasoup::direct_transport::{DirectTransport, DirectTransportOptions};
+use mediasoup::prelude::*;
+use mediasoup::recorder::{Recorder, RecorderError, RecorderOptions, RecordingFormat};
+use mediasoup::router::{Router, RouterOptions};
+use mediasoup::rtp::RtpPacketBuilder;
+use mediasoup::rtp_parameters::{
+    MimeTypeAudio, RtpCapabilities, RtpCodecCapability, RtpCodecParameters,
+    RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use parking_lot::Mutex;
+use std::env;
+use std::io::{self, Write};
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn opus_codec_capability() -> RtpCodecCapability {
+    RtpCodecCapability::Audio {
+        mime_type: MimeTypeAudio::Opus,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(48000).unwrap(),
+        channels: NonZeroU8::new(2).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }
+}
+
+async fn init() -> (Worker, Router, DirectTransport) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(vec![opus_codec_capability()]))
+        .await
+        .expect("Failed to create router");
+
+    let transport = router
+        .create_direct_transport(DirectTransportOptions::default())
+        .await
+        .expect("Failed to create transport");
+
+    (worker, router, transport)
+}
+
+#[test]
+fn record_ogg_succeeds() {
+    future::block_on(async move {
+        let (_worker, _router, transport) = init().await;
+
+        let producer = transport
+            .produce(ProducerOptions::new(
+                MediaKind::Audio,
+                RtpParameters {
+                    codecs: vec![RtpCodecParameters::Audio {
+                        mime_type: MimeTypeAudio::Opus,
+                        payload_type: 111,
+                        clock_rate: NonZeroU32::new(48000).unwrap(),
+                        channels: NonZeroU8::new(2).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![],
+                    }],
+                    encodings: vec![RtpEncodingParameters {
+                        ssrc: Some(1111),
+                        ..RtpEncodingParameters::default()
+                    }],
+                    ..RtpParameters::default()
+                },
+            ))
+            .await
+            .expect("Failed to produce");
+
+        let consumer = transport
+            .consume(ConsumerOptions::new(
+                producer.id(),
+                RtpCapabilities {
+                    codecs: vec![opus_codec_capability()],
+                    header_extensions: vec![],
+                },
+            ))
+            .await
+            .expect("Failed to consume");
+
+        assert!(matches!(
+            Recorder::new(
+                SharedBuffer::default(),
+                vec![consumer.clone(), consumer.clone()],
+                RecorderOptions::new(RecordingFormat::Ogg),
+            ),
+            Err(RecorderError::UnsupportedTracks),
+        ));
+
+        // Multiple of 4, so that all swapped packets are sent
+        let num_packets = 52_u16;
+        let (received_tx, received_rx) = async_oneshot::oneshot::<()>();
+        let _handler = consumer.on_rtp({
+            let received_tx = Mutex::new(Some(received_tx));
+            let received = Mutex::new(0_u16);
+
+            move |_packet| {
+                let mut received = received.lock();
+                *received += 1;
+                if *received == num_packets {
+                    let _ = received_tx.lock().take().unwrap().send(());
+                }
+            }
+        });
+
+        let buffer = SharedBuffer::default();
+        let recorder = Recorder::new(
+            buffer.clone(),
+            vec![consumer],
+            RecorderOptions::new(RecordingFormat::Ogg),
+        )
+        .expect("Failed to start recorder");
+
+        let Producer::Direct(direct_producer) = &producer else {
+            panic!("Expected direct producer");
+        };
+
+        // Swap every other pair of packets to exercise reordering
+        for index in 0..num_packets {
+            let sequence_number = if index % 4 == 1 {
+                index + 1
+            } else if index % 4 == 2 {
+                index - 1
+            } else {
+                index
+            };
+            let mut builder =
+                RtpPacketBuilder::new(111, sequence_number, u32::from(sequence_number) * 960, 1111);
+            // 20ms CELT frame
+            builder.payload = vec![0xFC, sequence_number as u8];
+            direct_producer
+                .send(builder.build().unwrap())
+                .expect("Failed to send RTP packet");
+        }
+
+        received_rx.await.expect("Failed to receive RTP packets");
+        // Let other `on_rtp` callbacks of the last packet complete
+        async_io::Timer::after(Duration::from_millis(100)).await;
+
+        recorder.stop().expect("Failed to stop recorder");
+
+        let data = buffer.0.lock();
+        assert_eq!(&data[..4], b"OggS");
+        assert!(data.windows(8).any(|window| window == b"OpusHead"));
+        assert!(data.windows(8).any(|window| window == b"OpusTags"));
+
+        // The last page has end of stream flag and covers all packets
+        let last_page = data
+            .windows(4)
+            .rposition(|window| window == b"OggS")
+            .unwrap();
+        assert_eq!(data[last_page + 5], 0x04);
+        assert_eq!(
+            u64::from_le_bytes(data[last_page + 6..last_page + 14].try_into().unwrap()),
+            u64::from(num_packets) * 960,
+        );
+    });
+}
@@ -0,0 +1,338 @@
+use futures_lite::future;
+use mediasoup::data_structures::{ListenInfo, Protocol};
+use mediasoup::prelude::*;
+use mediasoup::producer::ProducerOptions;
+use mediasoup::room::{
+    Participant, ParticipantEvent, ParticipantId, ParticipantOptions, Room, RoomOptions,
+    SubscriptionPolicy,
+};
+use mediasoup::router::RouterOptions;
+use mediasoup::rtp_parameters::{
+    MediaKind, MimeTypeAudio, MimeTypeVideo, RtpCapabilities, RtpCodecCapability,
+    RtpCodecParameters, RtpCodecParametersParameters, RtpEncodingParameters, RtpParameters,
+};
+use mediasoup::webrtc_transport::{WebRtcTransportListenInfos, WebRtcTransportOptions};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use parking_lot::Mutex;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![
+        RtpCodecCapability::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        RtpCodecCapability::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            preferred_payload_type: None,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    ]
+}
+
+fn device_capabilities() -> RtpCapabilities {
+    RtpCapabilities {
+        codecs: media_codecs(),
+        header_extensions: vec![],
+    }
+}
+
+fn producer_options(kind: MediaKind, ssrc: u32) -> ProducerOptions {
+    let codec = match kind {
+        MediaKind::Audio => RtpCodecParameters::Audio {
+            mime_type: MimeTypeAudio::Opus,
+            payload_type: 111,
+            clock_rate: NonZeroU32::new(48000).unwrap(),
+            channels: NonZeroU8::new(2).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+        MediaKind::Video => RtpCodecParameters::Video {
+            mime_type: MimeTypeVideo::Vp8,
+            payload_type: 96,
+            clock_rate: NonZeroU32::new(90000).unwrap(),
+            parameters: RtpCodecParametersParameters::default(),
+            rtcp_feedback: vec![],
+        },
+    };
+
+    ProducerOptions::new(
+        kind,
+        RtpParameters {
+            mid: Some(ssrc.to_string()),
+            codecs: vec![codec],
+            encodings: vec![RtpEncodingParameters {
+                ssrc: Some(ssrc),
+                ..RtpEncodingParameters::default()
+            }],
+            ..RtpParameters::default()
+        },
+    )
+}
+
+fn participant_options(subscription_policy: SubscriptionPolicy) -> ParticipantOptions {
+    let mut options = ParticipantOptions::new(WebRtcTransportOptions::new(
+        WebRtcTransportListenInfos::new(ListenInfo {
+            protocol: Protocol::Udp,
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            announced_ip: None,
+            port: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        }),
+    ));
+    options.rtp_capabilities = Some(device_capabilities());
+    options.subscription_policy = subscription_policy;
+    options
+}
+
+type Events = Arc<Mutex<Vec<(ParticipantId, ParticipantEvent)>>>;
+
+async fn init() -> (Worker, Room, Events) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    let room = Room::new(router, RoomOptions::default());
+    let events = Events::default();
+    room.on_participant_event({
+        let events = Arc::clone(&events);
+
+        move |participant_id, event| {
+            events.lock().push((*participant_id, event.clone()));
+        }
+    })
+    .detach();
+
+    (worker, room, events)
+}
+
+/// Producer ids consumed by participant.
+fn consumed(participant: &Participant) -> Vec<ProducerId> {
+    let mut producer_ids = participant
+        .consumers()
+        .iter()
+        .map(|consumer| consumer.producer_id())
+        .collect::<Vec<_>>();
+    producer_ids.sort();
+    producer_ids
+}
+
+fn sorted(mut producer_ids: Vec<ProducerId>) -> Vec<ProducerId> {
+    producer_ids.sort();
+    producer_ids
+}
+
+#[test]
+fn join_and_leave_succeeds() {
+    future::block_on(async move {
+        let (_worker, room, events) = init().await;
+
+        let alice = room
+            .join(participant_options(SubscriptionPolicy::All))
+            .await
+            .expect("Failed to join");
+        let audio_producer = alice
+            .produce(producer_options(MediaKind::Audio, 1111))
+            .await
+            .expect("Failed to produce audio");
+
+        let bob = room
+            .join(participant_options(SubscriptionPolicy::All))
+            .await
+            .expect("Failed to join");
+        // Existing producers are consumed on join
+        assert_eq!(consumed(&bob), vec![audio_producer.id()]);
+
+        let video_producer = alice
+            .produce(producer_options(MediaKind::Video, 2222))
+            .await
+            .expect("Failed to produce video");
+        assert_eq!(
+            consumed(&bob),
+            sorted(vec![audio_producer.id(), video_producer.id()]),
+        );
+        assert!(alice.consumers().is_empty());
+        assert_eq!(room.participant_ids(), vec![alice.id(), bob.id()]);
+        assert_eq!(room.producers().len(), 2);
+
+        {
+            let events = events.lock();
+            assert!(events.iter().any(|(participant_id, event)| {
+                *participant_id == alice.id()
+                    && matches!(
+                        event,
+                        ParticipantEvent::ParticipantJoined { participant_id }
+                            if *participant_id == bob.id()
+                    )
+            }));
+            assert!(events.iter().any(|(participant_id, event)| {
+                *participant_id == bob.id()
+                    && matches!(
+                        event,
+                        ParticipantEvent::ConsumerAdded { participant_id, consumer }
+                            if *participant_id == alice.id()
+                                && consumer.producer_id() == video_producer.id()
+                    )
+            }));
+        }
+
+        assert!(alice.close_producer(&video_producer.id()));
+        assert!(!bob.close_producer(&audio_producer.id()));
+        assert_eq!(consumed(&bob), vec![audio_producer.id()]);
+
+        events.lock().clear();
+        let alice_id = alice.id();
+        drop(alice);
+
+        assert!(bob.consumers().is_empty());
+        assert_eq!(room.participant_ids(), vec![bob.id()]);
+        let events = events.lock();
+        assert!(events
+            .iter()
+            .all(|(participant_id, _)| *participant_id == bob.id()));
+        assert!(events
+            .iter()
+            .any(|(_, event)| matches!(event, ParticipantEvent::ConsumerRemoved { .. })));
+        assert!(events.iter().any(|(_, event)| matches!(
+            event,
+            ParticipantEvent::ParticipantLeft { participant_id } if *participant_id == alice_id
+        )));
+    });
+}
+
+#[test]
+fn selective_subscription_succeeds() {
+    future::block_on(async move {
+        let (_worker, room, _events) = init().await;
+
+        let alice = room
+            .join(participant_options(SubscriptionPolicy::All))
+            .await
+            .expect("Failed to join");
+        let audio_producer = alice
+            .produce(producer_options(MediaKind::Audio, 1111))
+            .await
+            .expect("Failed to produce audio");
+        let video_producer = alice
+            .produce(producer_options(MediaKind::Video, 2222))
+            .await
+            .expect("Failed to produce video");
+
+        let bob = room
+            .join(participant_options(SubscriptionPolicy::Selective))
+            .await
+            .expect("Failed to join");
+        let bob_events = Arc::new(Mutex::new(Vec::new()));
+        bob.on_event({
+            let bob_events = Arc::clone(&bob_events);
+
+            move |event| {
+                bob_events.lock().push(event.clone());
+            }
+        })
+        .detach();
+        assert!(bob.consumers().is_empty());
+
+        bob.subscribe(video_producer.id())
+            .await
+            .expect("Failed to subscribe");
+        assert_eq!(consumed(&bob), vec![video_producer.id()]);
+        assert!(matches!(
+            bob_events.lock().as_slice(),
+            [ParticipantEvent::ConsumerAdded { consumer, .. }]
+                if consumer.producer_id() == video_producer.id()
+        ));
+
+        bob.unsubscribe(&video_producer.id());
+        assert!(bob.consumers().is_empty());
+
+        // Own producers can't be subscribed to
+        assert!(alice.subscribe(audio_producer.id()).await.is_err());
+
+        bob.set_subscription_policy(SubscriptionPolicy::All).await;
+        assert_eq!(
+            consumed(&bob),
+            sorted(vec![audio_producer.id(), video_producer.id()]),
+        );
+    });
+}
+
+#[test]
+fn last_n_subscription_succeeds() {
+    future::block_on(async move {
+        let (_worker, room, _events) = init().await;
+
+        let mut producers = Vec::new();
+        let mut participants = Vec::new();
+        for ssrc in [1000, 2000] {
+            let participant = room
+                .join(participant_options(SubscriptionPolicy::All))
+                .await
+                .expect("Failed to join");
+            for (kind, ssrc) in [(MediaKind::Audio, ssrc), (MediaKind::Video, ssrc + 1)] {
+                producers.push(
+                    participant
+                        .produce(producer_options(kind, ssrc))
+                        .await
+                        .expect("Failed to produce"),
+                );
+            }
+            participants.push(participant);
+        }
+
+        let carol = room
+            .join(participant_options(SubscriptionPolicy::LastN(1)))
+            .await
+            .expect("Failed to join");
+        // Audio of everyone and video of the first participant
+        assert_eq!(
+            consumed(&carol),
+            sorted(vec![
+                producers[0].id(),
+                producers[1].id(),
+                producers[2].id()
+            ]),
+        );
+
+        room.set_active_speaker(&participants[1].id()).await;
+        assert_eq!(
+            room.participant_ids(),
+            vec![participants[1].id(), participants[0].id(), carol.id()],
+        );
+        assert_eq!(
+            consumed(&carol),
+            sorted(vec![
+                producers[0].id(),
+                producers[2].id(),
+                producers[3].id()
+            ]),
+        );
+    });
+}
@@ -1,9 +1,11 @@
 use futures_lite::future;
 use hash_hasher::{HashedMap, HashedSet};
 use mediasoup::data_structures::AppData;
+use mediasoup::negotiation::{CodecMismatch, CodecRejection};
 use mediasoup::router::RouterOptions;
 use mediasoup::rtp_parameters::{
-    MimeTypeAudio, MimeTypeVideo, RtpCodecCapability, RtpCodecParametersParameters,
+    MimeType, MimeTypeAudio, MimeTypeVideo, RtpCapabilities, RtpCodecCapability,
+    RtpCodecParametersParameters,
 };
 use mediasoup::worker::{ChannelMessageHandlers, Worker, WorkerSettings};
 use mediasoup::worker_manager::WorkerManager;
@@ -153,3 +155,58 @@ fn close_event() {
         rx.await.expect("Failed to receive close event");
     });
 }
+
+#[test]
+fn negotiate_rtp_capabilities_succeeds() {
+    future::block_on(async move {
+        let worker = init().await;
+
+        let router = worker
+            .create_router(RouterOptions::new(media_codecs()))
+            .await
+            .expect("Failed to create router");
+
+        let negotiation = router
+            .negotiate_rtp_capabilities(&RtpCapabilities {
+                codecs: vec![
+                    RtpCodecCapability::Audio {
+                        mime_type: MimeTypeAudio::Opus,
+                        preferred_payload_type: Some(111),
+                        clock_rate: NonZeroU32::new(48000).unwrap(),
+                        channels: NonZeroU8::new(2).unwrap(),
+                        parameters: RtpCodecParametersParameters::default(),
+                        rtcp_feedback: vec![],
+                    },
+                    RtpCodecCapability::Video {
+                        mime_type: MimeTypeVideo::H264,
+                        preferred_payload_type: Some(102),
+                        clock_rate: NonZeroU32::new(90000).unwrap(),
+                        parameters: RtpCodecParametersParameters::from([
+                            ("packetization-mode", 0_u32.into()),
+                            ("profile-level-id", "4d0032".into()),
+                        ]),
+                        rtcp_feedback: vec![],
+                    },
+                ],
+                header_extensions: vec![],
+            })
+            .expect("Failed to negotiate RTP capabilities");
+
+        assert_eq!(negotiation.rtp_capabilities.codecs.len(), 1);
+        assert_eq!(
+            negotiation
+                .rejected_codecs
+                .iter()
+                .filter(|rejected_codec| {
+                    rejected_codec.mime_type == MimeType::Video(MimeTypeVideo::H264)
+                })
+                .map(|rejected_codec| rejected_codec.reason)
+                .collect::<Vec<_>>(),
+            vec![
+                CodecRejection::Mismatch(CodecMismatch::PacketizationMode),
+                CodecRejection::NotSupportedByRouter,
+            ],
+        );
+        assert!(negotiation.rtp_capabilities.header_extensions.is_empty());
+    });
+}
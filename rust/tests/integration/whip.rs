@@ -0,0 +1,281 @@
+use futures_lite::future;
+use mediasoup::data_structures::{ListenInfo, Protocol};
+use mediasoup::prelude::*;
+use mediasoup::router::{Router, RouterOptions};
+use mediasoup::rtp_parameters::{
+    MimeTypeAudio, RtpCodecCapability, RtpCodecParameters, RtpHeaderExtensionUri,
+};
+use mediasoup::webrtc_transport::{WebRtcTransportListenInfos, WebRtcTransportOptions};
+use mediasoup::whip::{WhepSession, WhipError, WhipSession};
+use mediasoup::worker::{Worker, WorkerSettings};
+use mediasoup::worker_manager::WorkerManager;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::{NonZeroU32, NonZeroU8};
+
+const PUBLISHER_OFFER: &str = "v=0\r
+o=- 4611731400430051336 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0 1\r
+a=msid-semantic: WMS stream\r
+m=audio 9 UDP/TLS/RTP/SAVPF 111\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:ZpmH\r
+a=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r
+a=fingerprint:sha-256 1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:0\r
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid\r
+a=sendonly\r
+a=rtcp-mux\r
+a=rtpmap:111 opus/48000/2\r
+a=fmtp:111 minptime=10;useinbandfec=1\r
+a=ssrc:1111 cname:publisher\r
+m=video 9 UDP/TLS/RTP/SAVPF 102\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:ZpmH\r
+a=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r
+a=fingerprint:sha-256 1B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:1\r
+a=sendonly\r
+a=rtcp-mux\r
+a=rtpmap:102 H264/90000\r
+a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r
+a=ssrc:2222 cname:publisher\r
+";
+
+const VIEWER_OFFER: &str = "v=0\r
+o=- 1234 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0 1\r
+m=audio 9 UDP/TLS/RTP/SAVPF 111\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:vIeW\r
+a=ice-pwd:xP1iA2ZbVSgJEpcA2nBh0Mdo\r
+a=fingerprint:sha-256 2B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:0\r
+a=recvonly\r
+a=rtcp-mux\r
+a=rtpmap:111 opus/48000/2\r
+m=video 9 UDP/TLS/RTP/SAVPF 96\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:vIeW\r
+a=ice-pwd:xP1iA2ZbVSgJEpcA2nBh0Mdo\r
+a=fingerprint:sha-256 2B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:1\r
+a=recvonly\r
+a=rtcp-mux\r
+a=rtpmap:96 VP8/90000\r
+";
+
+fn media_codecs() -> Vec<RtpCodecCapability> {
+    vec![RtpCodecCapability::Audio {
+        mime_type: MimeTypeAudio::Opus,
+        preferred_payload_type: None,
+        clock_rate: NonZeroU32::new(48000).unwrap(),
+        channels: NonZeroU8::new(2).unwrap(),
+        parameters: RtpCodecParametersParameters::default(),
+        rtcp_feedback: vec![],
+    }]
+}
+
+fn webrtc_transport_options() -> WebRtcTransportOptions {
+    WebRtcTransportOptions::new(WebRtcTransportListenInfos::new(ListenInfo {
+        protocol: Protocol::Udp,
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        announced_ip: None,
+        port: None,
+        send_buffer_size: None,
+        recv_buffer_size: None,
+    }))
+}
+
+async fn init() -> (Worker, Router) {
+    {
+        let mut builder = env_logger::builder();
+        if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+            builder.filter_level(log::LevelFilter::Off);
+        }
+        let _ = builder.is_test(true).try_init();
+    }
+
+    let worker_manager = WorkerManager::new();
+
+    let worker = worker_manager
+        .create_worker(WorkerSettings::default())
+        .await
+        .expect("Failed to create worker");
+
+    let router = worker
+        .create_router(RouterOptions::new(media_codecs()))
+        .await
+        .expect("Failed to create router");
+
+    (worker, router)
+}
+
+#[test]
+fn whip_session_succeeds() {
+    future::block_on(async move {
+        let (_worker, router) = init().await;
+
+        let session = WhipSession::new(&router, webrtc_transport_options(), PUBLISHER_OFFER)
+            .await
+            .expect("Failed to create WHIP session");
+
+        assert_eq!(session.id(), session.transport().id());
+        // Router has no video codecs, so only audio is produced.
+        assert_eq!(session.producers().len(), 1);
+        assert_eq!(session.producers()[0].kind(), MediaKind::Audio);
+
+        let answer = session.answer();
+        assert!(answer.contains("a=mid:0\r\n"));
+        assert!(answer.contains("a=recvonly\r\n"));
+        assert!(answer.contains("m=video 0 "));
+        assert!(answer.contains("a=setup:active\r\n"));
+
+        // Same credentials means nothing but new candidates, which are ignored.
+        let fragment = "a=ice-ufrag:ZpmH\r\na=ice-pwd:IqdnXKvXpHG3VSU6ZcMhsbJA\r\n";
+        assert_eq!(
+            session.patch(fragment).await.expect("Failed to patch"),
+            None
+        );
+
+        let fragment = "a=ice-ufrag:NeWw\r\na=ice-pwd:NfqV0xKG8pEt0Nt1BHrHk1Zt\r\n";
+        let local_fragment = session
+            .patch(fragment)
+            .await
+            .expect("Failed to patch")
+            .expect("ICE was not restarted");
+        let ice_parameters = session.transport().ice_parameters();
+        assert!(local_fragment.contains(&format!(
+            "a=ice-ufrag:{}\r\n",
+            ice_parameters.username_fragment
+        )));
+    });
+}
+
+#[test]
+fn whep_session_succeeds() {
+    future::block_on(async move {
+        let (_worker, router) = init().await;
+
+        let whip_session = WhipSession::new(&router, webrtc_transport_options(), PUBLISHER_OFFER)
+            .await
+            .expect("Failed to create WHIP session");
+
+        let session = WhepSession::new(
+            &router,
+            webrtc_transport_options(),
+            whip_session.producers(),
+            VIEWER_OFFER,
+        )
+        .await
+        .expect("Failed to create WHEP session");
+
+        assert_eq!(session.consumers().len(), 1);
+        assert_eq!(
+            session.consumers()[0].producer_id(),
+            whip_session.producers()[0].id()
+        );
+
+        let answer = session.answer();
+        assert!(answer.contains("a=sendonly\r\n"));
+        assert!(answer.contains("m=video 0 "));
+    });
+}
+
+#[test]
+fn whip_session_without_acceptable_media_fails() {
+    future::block_on(async move {
+        let (_worker, router) = init().await;
+
+        let offer = PUBLISHER_OFFER.replace("opus/48000/2", "PCMA/8000");
+
+        assert!(matches!(
+            WhipSession::new(&router, webrtc_transport_options(), &offer).await,
+            Err(WhipError::NoAcceptableMedia),
+        ));
+
+        assert!(matches!(
+            WhipSession::new(&router, webrtc_transport_options(), "garbage").await,
+            Err(WhipError::InvalidSdp(_)),
+        ));
+    });
+}
+
+#[test]
+fn whep_session_uses_offer_payload_types_and_header_extension_ids() {
+    future::block_on(async move {
+        let (_worker, router) = init().await;
+
+        let whip_session = WhipSession::new(&router, webrtc_transport_options(), PUBLISHER_OFFER)
+            .await
+            .expect("Failed to create WHIP session");
+
+        // Router uses payload type 100 for Opus, id 1 for MID and id 10 for audio level.
+        let offer = "v=0\r
+o=- 5678 2 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0\r
+m=audio 9 UDP/TLS/RTP/SAVPF 109\r
+c=IN IP4 0.0.0.0\r
+a=ice-ufrag:vIeW\r
+a=ice-pwd:xP1iA2ZbVSgJEpcA2nBh0Mdo\r
+a=fingerprint:sha-256 2B:EA:BF:33:B8:11:26:6D:91:AD:1B:A0:16:FD:5D:60:59:33:F7:46:A3:BA:99:2A:1D:04:99:A6:F2:C6:2D:43\r
+a=setup:actpass\r
+a=mid:0\r
+a=extmap:9 urn:ietf:params:rtp-hdrext:sdes:mid\r
+a=extmap:7 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r
+a=recvonly\r
+a=rtcp-mux\r
+a=rtpmap:109 opus/48000/2\r
+";
+
+        let session = WhepSession::new(
+            &router,
+            webrtc_transport_options(),
+            whip_session.producers(),
+            offer,
+        )
+        .await
+        .expect("Failed to create WHEP session");
+
+        let rtp_parameters = session.consumers()[0].rtp_parameters();
+        assert_eq!(
+            rtp_parameters
+                .codecs
+                .iter()
+                .map(|codec| match codec {
+                    RtpCodecParameters::Audio { payload_type, .. }
+                    | RtpCodecParameters::Video { payload_type, .. } => *payload_type,
+                })
+                .collect::<Vec<_>>(),
+            vec![109]
+        );
+        assert_eq!(
+            rtp_parameters
+                .header_extensions
+                .iter()
+                .map(|header_extension| (header_extension.uri, header_extension.id))
+                .collect::<Vec<_>>(),
+            vec![
+                (RtpHeaderExtensionUri::Mid, 9),
+                (RtpHeaderExtensionUri::AudioLevel, 7),
+            ]
+        );
+
+        let answer = session.answer();
+        assert!(answer.contains("m=audio 7 UDP/TLS/RTP/SAVPF 109\r\n"));
+        assert!(answer.contains("a=rtpmap:109 opus/48000/2\r\n"));
+        assert!(answer.contains("a=extmap:9 urn:ietf:params:rtp-hdrext:sdes:mid\r\n"));
+        assert!(answer.contains("a=extmap:7 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\n"));
+        assert!(!answer.contains("a=rtpmap:100 "));
+    });
+}
@@ -1,10 +1,12 @@
 use futures_lite::future;
 use mediasoup::data_structures::AppData;
+use mediasoup::router::RouterOptions;
 use mediasoup::worker::{
-    ChannelMessageHandlers, WorkerDtlsFiles, WorkerLogLevel, WorkerLogTag, WorkerSettings,
-    WorkerUpdateSettings,
+    with_request_timeout, ChannelMessageHandlers, RequestError, WorkerDtlsFiles, WorkerLogLevel,
+    WorkerLogTag, WorkerProcessSettings, WorkerSettings, WorkerUpdateSettings,
 };
 use mediasoup::worker_manager::WorkerManager;
+use std::time::Duration;
 use std::{env, io};
 
 async fn init() -> WorkerManager {
@@ -156,6 +158,97 @@ fn dump_succeeds() {
     });
 }
 
+#[test]
+fn request_timeout() {
+    future::block_on(async move {
+        let worker_manager = init().await;
+
+        let worker = worker_manager
+            .create_worker({
+                let mut settings = WorkerSettings::default();
+
+                settings.request_timeout = Some(Duration::from_secs(10));
+
+                settings
+            })
+            .await
+            .expect("Failed to create worker");
+
+        worker.dump().await.expect("Failed to dump worker");
+        assert_eq!(worker.outstanding_requests(), 0);
+
+        // Response can't possibly arrive before zero timeout fires
+        assert!(matches!(
+            with_request_timeout(Some(Duration::ZERO), worker.dump()).await,
+            Err(RequestError::TimedOut),
+        ));
+        // Timed out request must not linger around
+        assert_eq!(worker.outstanding_requests(), 0);
+
+        {
+            let dump_fut = worker.dump();
+            futures_lite::pin!(dump_fut);
+            assert!(future::poll_once(&mut dump_fut).await.is_none());
+            assert_eq!(worker.outstanding_requests(), 1);
+        }
+        // Cancelled request must not linger around either
+        assert_eq!(worker.outstanding_requests(), 0);
+
+        // Override with no timeout still works
+        with_request_timeout(None, worker.dump())
+            .await
+            .expect("Failed to dump worker");
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn worker_process_succeeds() {
+    future::block_on(async move {
+        let worker_manager = init().await;
+
+        // Same lookup as in Node.js tests
+        let executable = env::var("MEDIASOUP_WORKER_BIN").unwrap_or_else(|_| {
+            let build_type = match env::var("MEDIASOUP_BUILDTYPE").as_deref() {
+                Ok("Debug") => "Debug",
+                _ => "Release",
+            };
+            format!(
+                "{}/../worker/out/{build_type}/mediasoup-worker",
+                env!("CARGO_MANIFEST_DIR"),
+            )
+        });
+
+        let worker = worker_manager
+            .create_worker({
+                let mut settings = WorkerSettings::default();
+
+                settings.process = Some(WorkerProcessSettings::new(executable));
+
+                settings
+            })
+            .await
+            .expect("Failed to create worker process");
+
+        let router = worker
+            .create_router(RouterOptions::default())
+            .await
+            .expect("Failed to create router in worker process");
+
+        let dump = worker.dump().await.expect("Failed to dump worker process");
+        assert_eq!(dump.router_ids, vec![router.id()]);
+
+        let (mut tx, rx) = async_oneshot::oneshot::<()>();
+        let _handler = worker.on_close(move || {
+            let _ = tx.send(());
+        });
+        drop(router);
+        drop(worker);
+
+        rx.await.expect("Failed to receive close event");
+    });
+}
+
 #[test]
 fn close_event() {
     future::block_on(async move {
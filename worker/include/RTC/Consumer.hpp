@@ -18,7 +18,9 @@
 #include "RTC/RtpStreamRecv.hpp"
 #include "RTC/RtpStreamSend.hpp"
 #include "RTC/Shared.hpp"
+#include <absl/container/flat_hash_map.h>
 #include <absl/container/flat_hash_set.h>
+#include <array>
 #include <string>
 #include <vector>
 
@@ -109,6 +111,7 @@ namespace RTC
 		{
 			return this->rtxSsrcs;
 		}
+		RTC::RtpPacket* CloneRewrittenRtpPacket(const RTC::RtpPacket* packet) const;
 		virtual bool IsActive() const
 		{
 			// The parent Consumer just checks whether Consumer and Producer are
@@ -208,6 +211,12 @@ namespace RTC
 		// Others.
 		std::vector<uint32_t> mediaSsrcs;
 		std::vector<uint32_t> rtxSsrcs;
+		// Mapping of Router payload types to the ones used by this Consumer.
+		absl::flat_hash_map<uint8_t, uint8_t> payloadTypeMapping;
+		// Mapping of mediasoup RTP header extension ids to the ones used by this
+		// Consumer (0 for those not negotiated).
+		std::array<uint8_t, 15> headerExtensionIdMapping{};
+		bool rewriteRtpPackets{ false };
 		bool transportConnected{ false };
 		bool paused{ false };
 		bool producerPaused{ false };
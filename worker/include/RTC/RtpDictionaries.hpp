@@ -49,6 +49,7 @@ namespace RTC
 			H264_SVC,
 			X_H264UC,
 			H265,
+			AV1,
 			// Complementary codecs:
 			CN = 300,
 			TELEPHONE_EVENT,
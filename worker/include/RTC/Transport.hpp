@@ -185,6 +185,8 @@ namespace RTC
 		virtual bool IsConnected() const = 0;
 		virtual void SendRtpPacket(
 		  RTC::Consumer* consumer, RTC::RtpPacket* packet, onSendCallback* cb = nullptr) = 0;
+		void SendConsumerRtpPacket(
+		  RTC::Consumer* consumer, RTC::RtpPacket* packet, onSendCallback* cb = nullptr);
 		void HandleRtcpPacket(RTC::RTCP::Packet* packet);
 		void SendRtcp(uint64_t nowMs);
 		virtual void SendRtcpPacket(RTC::RTCP::Packet* packet)                 = 0;
@@ -5,7 +5,9 @@
 #include "DepLibUV.hpp"
 #include "Logger.hpp"
 #include "MediaSoupErrors.hpp"
-#include <iterator> // std::ostream_iterator
+#include <algorithm> // std::find
+#include <cstring>   // std::memset
+#include <iterator>  // std::ostream_iterator
 #include <sstream>  // std::ostringstream
 
 namespace RTC
@@ -112,17 +114,58 @@ namespace RTC
 			{
 				this->rtpHeaderExtensionIds.rrid = exten.id;
 			}
+
+			// Packets carry mediasoup RTP header extension ids, so those of the
+			// remote endpoint must be written into every sent packet.
+			const auto mediasoupId = static_cast<uint8_t>(exten.type);
+
+			if (exten.id != mediasoupId)
+			{
+				if (exten.id > 14u)
+				{
+					MS_THROW_TYPE_ERROR("RTP extension id must be lower than 15 when rewritten");
+				}
+
+				this->rewriteRtpPackets = true;
+			}
+
+			this->headerExtensionIdMapping[mediasoupId] = exten.id;
+		}
+
+		if (flatbuffers::IsFieldPresent(data, FBS::Transport::ConsumeRequest::VT_PAYLOADTYPEMAPPING))
+		{
+			for (const auto* codecMapping : *data->payloadTypeMapping())
+			{
+				this->payloadTypeMapping[codecMapping->payloadType()] = codecMapping->mappedPayloadType();
+
+				if (codecMapping->payloadType() != codecMapping->mappedPayloadType())
+				{
+					this->rewriteRtpPackets = true;
+				}
+			}
 		}
 
 		// paused is set to false by default.
 		this->paused = data->paused();
 
-		// Fill supported codec payload types.
+		// Fill supported codec payload types. Packets carry Router payload types.
 		for (auto& codec : this->rtpParameters.codecs)
 		{
 			if (codec.mimeType.IsMediaCodec())
 			{
-				this->supportedCodecPayloadTypes[codec.payloadType] = true;
+				auto payloadType = codec.payloadType;
+
+				for (const auto& [routerPayloadType, mappedPayloadType] : this->payloadTypeMapping)
+				{
+					if (mappedPayloadType == codec.payloadType)
+					{
+						payloadType = routerPayloadType;
+
+						break;
+					}
+				}
+
+				this->supportedCodecPayloadTypes[payloadType] = true;
 			}
 		}
 
@@ -464,6 +507,81 @@ namespace RTC
 		this->listener->OnConsumerProducerClosed(this);
 	}
 
+	// Returns nullptr if the packet can be sent as is, otherwise the caller must
+	// send and delete the returned clone.
+	RTC::RtpPacket* Consumer::CloneRewrittenRtpPacket(const RTC::RtpPacket* packet) const
+	{
+		MS_TRACE();
+
+		if (!this->rewriteRtpPackets)
+		{
+			return nullptr;
+		}
+
+		auto* clone = packet->Clone();
+
+		// RTX packets already have the payload type of this Consumer.
+		auto it = this->payloadTypeMapping.find(clone->GetPayloadType());
+
+		if (
+		  it != this->payloadTypeMapping.end() &&
+		  std::find(this->rtxSsrcs.begin(), this->rtxSsrcs.end(), clone->GetSsrc()) ==
+		    this->rtxSsrcs.end())
+		{
+			clone->SetPayloadType(it->second);
+		}
+
+		// Producers always write One-Byte extensions with mediasoup ids.
+		if (clone->HasOneByteExtensions())
+		{
+			uint8_t* ptr       = clone->GetHeaderExtensionValue();
+			const uint8_t* end = ptr + clone->GetHeaderExtensionLength();
+
+			while (ptr < end)
+			{
+				const uint8_t id = *ptr >> 4;
+
+				// Padding byte.
+				if (id == 0u)
+				{
+					++ptr;
+
+					continue;
+				}
+
+				// Reserved id, stop parsing.
+				if (id == 15u)
+				{
+					break;
+				}
+
+				const size_t len = static_cast<size_t>(*ptr & 0x0F) + 1;
+
+				if (ptr + 1 + len > end)
+				{
+					break;
+				}
+
+				const uint8_t mappedId = this->headerExtensionIdMapping[id];
+
+				// Not negotiated with the remote endpoint, turn it into padding so it
+				// cannot be confused with a negotiated one.
+				if (mappedId == 0u)
+				{
+					std::memset(ptr, 0, 1 + len);
+				}
+				else
+				{
+					*ptr = static_cast<uint8_t>((mappedId << 4) | (*ptr & 0x0F));
+				}
+
+				ptr += 1 + len;
+			}
+		}
+
+		return clone;
+	}
+
 	void Consumer::EmitTraceEventRtpAndKeyFrameTypes(RTC::RtpPacket* packet, bool isRtx) const
 	{
 		MS_TRACE();
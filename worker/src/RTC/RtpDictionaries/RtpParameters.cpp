@@ -44,6 +44,12 @@ namespace RTC
 				{
 					type.emplace(RtpParameters::Type::SIMULCAST);
 				}
+				// Codecs whose payload is not parsed (such as AV1) are forwarded with
+				// all their layers.
+				else if (!RTC::Codecs::Tools::CanBeKeyFrame(mediaCodec->mimeType))
+				{
+					type.emplace(RtpParameters::Type::SIMPLE);
+				}
 			}
 			else
 			{
@@ -1746,6 +1746,27 @@ namespace RTC
 		return it->second;
 	}
 
+	void Transport::SendConsumerRtpPacket(
+	  RTC::Consumer* consumer, RTC::RtpPacket* packet, onSendCallback* cb)
+	{
+		MS_TRACE();
+
+		// Consumer may use payload types and RTP header extension ids other than
+		// the Router ones.
+		auto* rewrittenPacket = consumer->CloneRewrittenRtpPacket(packet);
+
+		if (rewrittenPacket)
+		{
+			SendRtpPacket(consumer, rewrittenPacket, cb);
+
+			delete rewrittenPacket;
+		}
+		else
+		{
+			SendRtpPacket(consumer, packet, cb);
+		}
+	}
+
 	void Transport::HandleRtcpPacket(RTC::RTCP::Packet* packet)
 	{
 		MS_TRACE();
@@ -2495,7 +2516,7 @@ namespace RTC
 				  }
 			  });
 
-			SendRtpPacket(consumer, packet, cb);
+			SendConsumerRtpPacket(consumer, packet, cb);
 #else
 			const auto* cb = new onSendCallback(
 			  [tccClientWeakPtr, &packetInfo](bool sent)
@@ -2511,12 +2532,12 @@ namespace RTC
 				  }
 			  });
 
-			SendRtpPacket(consumer, packet, cb);
+			SendConsumerRtpPacket(consumer, packet, cb);
 #endif
 		}
 		else
 		{
-			SendRtpPacket(consumer, packet);
+			SendConsumerRtpPacket(consumer, packet);
 		}
 
 		this->sendRtpTransmission.Update(packet);
@@ -2584,7 +2605,7 @@ namespace RTC
 				  }
 			  });
 
-			SendRtpPacket(consumer, packet, cb);
+			SendConsumerRtpPacket(consumer, packet, cb);
 #else
 			const auto* cb = new onSendCallback(
 			  [tccClientWeakPtr, &packetInfo](bool sent)
@@ -2600,12 +2621,12 @@ namespace RTC
 				  }
 			  });
 
-			SendRtpPacket(consumer, packet, cb);
+			SendConsumerRtpPacket(consumer, packet, cb);
 #endif
 		}
 		else
 		{
-			SendRtpPacket(consumer, packet);
+			SendConsumerRtpPacket(consumer, packet);
 		}
 
 		this->sendRtxTransmission.Update(packet);